@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use http_dragonfly::{
+    config::{
+        response::StatusRegex,
+        target::{ConditionFilter, ConditionInput},
+    },
+    context::{Context, RootOsEnvironment},
+};
+use hyper::Request;
+use regex::Regex;
+use serde_json::json;
+use std::hint::black_box;
+
+const FILTER: &str = r#".body.a == 1 and .request.uri.path == "/some/path""#;
+const REGEX: &str = r"4\d{2}|5\d{2}";
+const STATUS: &str = "503";
+
+/// Evaluation of the target condition and status regex compiled per request vs compiled once during config loading
+fn conditions(c: &mut Criterion) {
+    let ctx = Context::root(RootOsEnvironment::new("^PATH$"));
+    let (req, _) = Request::post("http://localhost:8080/some/path")
+        .body(())
+        .unwrap()
+        .into_parts();
+    let input = ConditionInput::new(&ctx, &req, json!({"a": 1}));
+
+    let mut group = c.benchmark_group("conditions");
+    group.bench_function("compiled_per_request", |b| {
+        b.iter(|| {
+            assert!(ConditionFilter::from(black_box(FILTER)).run(&input));
+            assert!(Regex::new(black_box(REGEX)).unwrap().is_match(STATUS));
+        })
+    });
+
+    let filter = ConditionFilter::from(FILTER);
+    let regex = StatusRegex::from(REGEX);
+    group.bench_function("compiled_once", |b| {
+        b.iter(|| {
+            assert!(filter.run(&input));
+            assert!(regex.is_match(black_box(STATUS)));
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, conditions);
+criterion_main!(benches);
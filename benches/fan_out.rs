@@ -0,0 +1,131 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures_util::future::join_all;
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+/// Numbers of the targets to fan each request out to
+const TARGET_COUNTS: [usize; 4] = [1, 2, 4, 8];
+/// Requests in flight in the load profile
+const CONCURRENCY: usize = 64;
+const FIRST_LISTENER_PORT: u16 = 9940;
+const BODY: &str = r#"{"id": 42, "name": "dragonfly", "tags": ["a", "b", "c"]}"#;
+
+/// Listener which fans requests out to the mock targets, targets are kept alive with it
+struct Setup {
+    url: String,
+    targets: Vec<MockTarget>,
+    _splitter: TestSplitter,
+}
+
+impl Setup {
+    async fn new(target_count: usize) -> Self {
+        let mut targets = vec![];
+        for _ in 0..target_count {
+            let target = MockTarget::start().await.unwrap();
+            target.respond_with(
+                MockResponse::new(200)
+                    .header("content-type", "application/json")
+                    .body(BODY),
+            );
+            targets.push(target);
+        }
+
+        let port = FIRST_LISTENER_PORT + target_count as u16;
+        let target_list = targets
+            .iter()
+            .enumerate()
+            .map(|(index, target)| format!("{{id: target-{index}, url: \"{}\"}}", target.url()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let config = format!(
+            r#"
+            listeners:
+              - id: fan-out
+                listen_on: "127.0.0.1:{port}"
+                strategy: always_target_id
+                headers:
+                  - add: x-bench
+                    value: "yes"
+                targets: [{target_list}]
+                response:
+                  target_selector: target-0
+            "#
+        );
+        let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+            .await
+            .unwrap();
+
+        Self {
+            url: splitter.url("fan-out"),
+            targets,
+            _splitter: splitter,
+        }
+    }
+
+    /// Mock targets record all requests, so they're forgotten between samples
+    fn reset(&self) {
+        self.targets.iter().for_each(MockTarget::reset);
+    }
+}
+
+async fn send(client: &Client, url: &str) {
+    let resp = client.post(url).body(BODY).send().await.unwrap();
+    assert!(resp.status().is_success());
+    resp.bytes().await.unwrap();
+}
+
+fn fan_out(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let client = &Client::new();
+
+    let mut group = c.benchmark_group("fan_out");
+    group.measurement_time(Duration::from_secs(10));
+    for target_count in TARGET_COUNTS {
+        let setup = runtime.block_on(Setup::new(target_count));
+
+        // Latency of the single request
+        group.throughput(Throughput::Elements(1));
+        group.bench_with_input(
+            BenchmarkId::new("sequential", target_count),
+            &setup,
+            |b, setup| {
+                b.to_async(&runtime).iter_custom(|iters| async move {
+                    let started = Instant::now();
+                    for _ in 0..iters {
+                        send(client, &setup.url).await;
+                    }
+                    let elapsed = started.elapsed();
+                    setup.reset();
+                    elapsed
+                })
+            },
+        );
+
+        // Load profile: requests per second with many requests in flight
+        group.throughput(Throughput::Elements(CONCURRENCY as u64));
+        group.bench_with_input(
+            BenchmarkId::new("concurrent", target_count),
+            &setup,
+            |b, setup| {
+                b.to_async(&runtime).iter_custom(|iters| async move {
+                    let started = Instant::now();
+                    for _ in 0..iters {
+                        join_all((0..CONCURRENCY).map(|_| send(client, &setup.url))).await;
+                    }
+                    let elapsed = started.elapsed();
+                    setup.reset();
+                    elapsed
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fan_out);
+criterion_main!(benches);
@@ -0,0 +1,286 @@
+use crate::config::{acme::AcmeConfig, server_tls};
+use instant_acme::{
+    Account, AuthorizationStatus, ChallengeType, Identifier, NewAccount, NewOrder, OrderStatus,
+    RetryPolicy,
+};
+use rcgen::{CertificateParams, CustomExtension, KeyPair};
+use rustls::{
+    crypto::ring::sign::any_supported_type,
+    pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, LazyLock, Mutex, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+use x509_parser::{extensions::GeneralName, parse_x509_certificate};
+
+/// ALPN protocol of TLS-ALPN-01 challenge handshakes, RFC 8737
+pub const ACME_TLS_ALPN_NAME: &[u8] = b"acme-tls/1";
+/// How long to wait before the next attempt if certificate can't be obtained
+const RETRY_INTERVAL: Duration = Duration::from_secs(3600);
+const ACCOUNT_FILE: &str = "account.json";
+const CERT_FILE: &str = "cert.pem";
+const KEY_FILE: &str = "key.pem";
+
+/// Resolvers by their cache directory, so TCP and QUIC configs of the listener share the same certificate
+/// and renewal task
+static RESOLVERS: LazyLock<Mutex<HashMap<PathBuf, Arc<AcmeCertResolver>>>> =
+    LazyLock::new(Default::default);
+
+/// Returns resolver of the listener's ACME certificate, cached certificate is loaded and renewal task is started
+/// when it's requested first time
+pub fn resolver(cfg: &AcmeConfig) -> Result<Arc<AcmeCertResolver>, anyhow::Error> {
+    let mut resolvers = RESOLVERS
+        .lock()
+        .expect("unable to lock ACME resolvers, looks like a BUG");
+    if let Some(resolver) = resolvers.get(cfg.cache_dir()) {
+        return Ok(resolver.clone());
+    }
+
+    fs::create_dir_all(cfg.cache_dir())?;
+    let certificate = match (
+        fs::read(cfg.cache_dir().join(CERT_FILE)),
+        fs::read(cfg.cache_dir().join(KEY_FILE)),
+    ) {
+        (Ok(cert), Ok(key)) => match Issued::new(cfg, &cert, &key) {
+            Ok(issued) => Some(issued),
+            Err(e) => {
+                warn!(cache_dir = %cfg.cache_dir().display(), error = %e, "unable to use cached ACME certificate, new one will be obtained");
+                None
+            }
+        },
+        (Err(e), _) | (_, Err(e)) if e.kind() == io::ErrorKind::NotFound => None,
+        (Err(e), _) | (_, Err(e)) => return Err(e.into()),
+    };
+
+    let resolver = Arc::new(AcmeCertResolver {
+        cfg: cfg.clone(),
+        certificate: RwLock::new(certificate),
+        challenges: RwLock::default(),
+    });
+    tokio::spawn(resolver.clone().renew_loop());
+    resolvers.insert(cfg.cache_dir().into(), resolver.clone());
+
+    Ok(resolver)
+}
+
+/// Resolves listener's certificate which is obtained from ACME server and renewed `renew_before` its expiration,
+/// handshakes of TLS-ALPN-01 challenges are answered with the challenge certificate of the requested domain.
+/// Handshakes fail until the first certificate is issued.
+#[derive(Debug)]
+pub struct AcmeCertResolver {
+    cfg: AcmeConfig,
+    certificate: RwLock<Option<Issued>>,
+    /// Challenge certificates of the order in progress by their domain
+    challenges: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+#[derive(Debug)]
+struct Issued {
+    certified_key: Arc<CertifiedKey>,
+    not_after: SystemTime,
+}
+
+impl Issued {
+    /// Parses issued certificate, it's valid only if it covers exactly the configured domains
+    fn new(cfg: &AcmeConfig, cert: &[u8], key: &[u8]) -> Result<Self, anyhow::Error> {
+        let cache_dir = cfg.cache_dir();
+        let certified_key = server_tls::certified_key(
+            cert,
+            &cache_dir.join(CERT_FILE),
+            key,
+            &cache_dir.join(KEY_FILE),
+        )?;
+        let (_, x509) = parse_x509_certificate(certified_key.end_entity_cert()?)?;
+
+        let names = x509
+            .subject_alternative_name()?
+            .map(|san| {
+                san.value
+                    .general_names
+                    .iter()
+                    .filter_map(|name| match name {
+                        GeneralName::DNSName(name) => Some(name.to_lowercase()),
+                        _ => None,
+                    })
+                    .collect::<BTreeSet<_>>()
+            })
+            .unwrap_or_default();
+        let domains = cfg
+            .domains()
+            .iter()
+            .map(|domain| domain.to_lowercase())
+            .collect::<BTreeSet<_>>();
+        if names != domains {
+            return Err(anyhow::anyhow!(
+                "certificate domains {names:?} don't match ACME `domains`"
+            ));
+        }
+
+        let not_after = u64::try_from(x509.validity().not_after.timestamp()).unwrap_or_default();
+        Ok(Self {
+            certified_key: Arc::new(certified_key),
+            not_after: UNIX_EPOCH + Duration::from_secs(not_after),
+        })
+    }
+}
+
+impl AcmeCertResolver {
+    async fn renew_loop(self: Arc<Self>) {
+        loop {
+            let renew_at = self
+                .certificate
+                .read()
+                .expect("unable to lock ACME certificate, looks like a BUG")
+                .as_ref()
+                .map(|issued| {
+                    issued
+                        .not_after
+                        .checked_sub(self.cfg.renew_before())
+                        .unwrap_or(UNIX_EPOCH)
+                });
+            if let Some(wait) = renew_at.and_then(|at| at.duration_since(SystemTime::now()).ok()) {
+                tokio::time::sleep(wait).await;
+            }
+
+            let issued = self.order().await;
+            self.challenges
+                .write()
+                .expect("unable to lock ACME challenges, looks like a BUG")
+                .clear();
+            match issued {
+                Ok(issued) => {
+                    info!(domains = ?self.cfg.domains(), "ACME certificate issued");
+                    *self
+                        .certificate
+                        .write()
+                        .expect("unable to lock ACME certificate, looks like a BUG") = Some(issued);
+                }
+                Err(e) => {
+                    warn!(domains = ?self.cfg.domains(), error = %e, "unable to obtain ACME certificate, retry in {}", humantime::format_duration(RETRY_INTERVAL));
+                    tokio::time::sleep(RETRY_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// Orders new certificate and stores it in the cache directory
+    async fn order(&self) -> Result<Issued, anyhow::Error> {
+        let account = self.account().await?;
+        let identifiers = self
+            .cfg
+            .domains()
+            .iter()
+            .map(|domain| Identifier::Dns(domain.clone()))
+            .collect::<Vec<_>>();
+        let mut order = account.new_order(&NewOrder::new(&identifiers)).await?;
+
+        let mut authorizations = order.authorizations();
+        while let Some(authorization) = authorizations.next().await {
+            let mut authorization = authorization?;
+            match authorization.status {
+                AuthorizationStatus::Pending => {}
+                AuthorizationStatus::Valid => continue,
+                status => return Err(anyhow::anyhow!("ACME authorization is {status:?}")),
+            }
+            let mut challenge = authorization
+                .challenge(ChallengeType::TlsAlpn01)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("ACME server doesn't offer TLS-ALPN-01 challenge")
+                })?;
+            let domain = challenge.identifier().to_string().to_lowercase();
+            let certified_key =
+                challenge_certificate(&domain, challenge.key_authorization().digest().as_ref())?;
+            self.challenges
+                .write()
+                .expect("unable to lock ACME challenges, looks like a BUG")
+                .insert(domain, Arc::new(certified_key));
+            challenge.set_ready().await?;
+        }
+
+        let status = order.poll_ready(&RetryPolicy::new()).await?;
+        if status != OrderStatus::Ready {
+            return Err(anyhow::anyhow!("ACME order is {status:?}"));
+        }
+        let key = order.finalize().await?;
+        let cert = order.poll_certificate(&RetryPolicy::new()).await?;
+        let issued = Issued::new(&self.cfg, cert.as_bytes(), key.as_bytes())?;
+
+        tokio::fs::write(self.cfg.cache_dir().join(CERT_FILE), cert).await?;
+        tokio::fs::write(self.cfg.cache_dir().join(KEY_FILE), key).await?;
+
+        Ok(issued)
+    }
+
+    /// Restores account from the cache directory or creates new one
+    async fn account(&self) -> Result<Account, anyhow::Error> {
+        let builder = match self.cfg.directory_ca() {
+            Some(ca) => Account::builder_with_root(ca)?,
+            None => Account::builder()?,
+        };
+        let path = self.cfg.cache_dir().join(ACCOUNT_FILE);
+        match tokio::fs::read(&path).await {
+            Ok(credentials) => Ok(builder
+                .from_credentials(serde_json::from_slice(&credentials)?)
+                .await?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let contact = self.cfg.email().map(|email| format!("mailto:{email}"));
+                let contact = contact.as_deref().into_iter().collect::<Vec<_>>();
+                let account = NewAccount {
+                    contact: &contact,
+                    terms_of_service_agreed: true,
+                    only_return_existing: false,
+                };
+                let (account, credentials) = builder
+                    .create(&account, self.cfg.directory().into(), None)
+                    .await?;
+                tokio::fs::write(&path, serde_json::to_vec(&credentials)?).await?;
+
+                Ok(account)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl ResolvesServerCert for AcmeCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let is_challenge = client_hello
+            .alpn()
+            .is_some_and(|mut protocols| protocols.any(|protocol| protocol == ACME_TLS_ALPN_NAME));
+        if is_challenge {
+            let domain = client_hello.server_name()?.to_lowercase();
+            return self
+                .challenges
+                .read()
+                .expect("unable to lock ACME challenges, looks like a BUG")
+                .get(&domain)
+                .cloned();
+        }
+
+        self.certificate
+            .read()
+            .expect("unable to lock ACME certificate, looks like a BUG")
+            .as_ref()
+            .map(|issued| issued.certified_key.clone())
+    }
+}
+
+/// Self-signed certificate with `acmeIdentifier` extension of the challenge's key authorization, RFC 8737
+fn challenge_certificate(domain: &str, digest: &[u8]) -> Result<CertifiedKey, anyhow::Error> {
+    let mut params = CertificateParams::new(vec![domain.to_string()])?;
+    params.custom_extensions = vec![CustomExtension::new_acme_identifier(digest)];
+    let key_pair = KeyPair::generate()?;
+    let cert = params.self_signed(&key_pair)?;
+    let key = any_supported_type(&PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+        key_pair.serialize_der(),
+    )))?;
+
+    Ok(CertifiedKey::new(vec![cert.der().clone()], key))
+}
@@ -0,0 +1,251 @@
+use crate::{
+    canary::{self, CanaryAction},
+    comparison,
+    config::{faults::FaultsConfig, ConfigFormat},
+    maintenance,
+    metrics::{self, METRICS_CONTENT_TYPE},
+    reload::{self, ReloadError},
+    signal::SignalHandler,
+    HyperTaskJoinHandle,
+};
+use core::time::Duration;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderValue, CONTENT_TYPE},
+    http,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+};
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::{net::TcpListener, select, task::JoinSet};
+use tracing::{debug, error, info, warn};
+
+/// Max size of the candidate config in the request body
+const MAX_CONFIG_SIZE: usize = 16 * 1024 * 1024;
+
+/// Admin API handler
+///
+/// Supported endpoints:
+/// - `GET /faults`: returns current state of the fault injection
+/// - `POST /faults/enable`, `POST /faults/disable`: switches fault injection on/off
+/// - `GET /maintenance`: returns current state of the maintenance mode
+/// - `POST /maintenance/enable`, `POST /maintenance/disable`: switches maintenance mode on/off
+/// - `GET /metrics`: returns metrics in Prometheus text format
+/// - `GET /canary`: returns state of all canary rollouts
+/// - `POST /canary/<listener>/<target>/{pause,resume,rollback}`: controls canary rollout of the target
+/// - `GET /comparisons`: returns mismatches of the targets' responses with the baseline ones of all listeners
+/// - `POST /comparisons/clear`: drops all kept mismatches and resets comparison counters
+/// - `GET /config`: returns running config with source of each value (file, include, env, default, etc.)
+/// - `POST /config/diff`: validates candidate config from the body and returns its diff with the running one
+/// - `POST /config/apply`: validates and applies candidate config from the body, returns applied diff
+async fn handle(
+    addr: SocketAddr,
+    req: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, http::Error> {
+    debug!(
+        "admin request, from={}, {} {}",
+        addr,
+        req.method(),
+        req.uri()
+    );
+    let enabled = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            return Response::builder()
+                .header(CONTENT_TYPE, METRICS_CONTENT_TYPE)
+                .body(Full::from(metrics::render()))
+        }
+        (&Method::GET, "/canary") => return Ok(Response::new(Full::from(canary::status()))),
+        (&Method::POST, path) if path.starts_with("/canary/") => {
+            return Ok(canary_response(addr, path));
+        }
+        (&Method::GET, "/comparisons") => {
+            return Ok(json_response(StatusCode::OK, comparison::status()))
+        }
+        (&Method::POST, "/comparisons/clear") => {
+            info!("comparison reports cleared by admin request from {addr}");
+            comparison::clear();
+            return Ok(Response::new(Full::default()));
+        }
+        (&Method::GET, "/config") => {
+            return Ok(match reload::dump() {
+                Ok(dump) => json_response(StatusCode::OK, dump),
+                Err(e) => text_response(StatusCode::SERVICE_UNAVAILABLE, format!("{e}\n")),
+            })
+        }
+        (&Method::POST, "/config/diff") => return Ok(config_response(addr, req, false).await),
+        (&Method::POST, "/config/apply") => return Ok(config_response(addr, req, true).await),
+        (&Method::GET, "/faults") => FaultsConfig::is_enabled(),
+        (&Method::POST, "/faults/enable") => {
+            info!("fault injection enabled by admin request from {addr}");
+            FaultsConfig::set_enabled(true);
+            true
+        }
+        (&Method::POST, "/faults/disable") => {
+            info!("fault injection disabled by admin request from {addr}");
+            FaultsConfig::set_enabled(false);
+            false
+        }
+        (&Method::GET, "/maintenance") => maintenance::is_enabled(),
+        (&Method::POST, "/maintenance/enable") => {
+            info!("maintenance mode enabled by admin request from {addr}");
+            maintenance::set_enabled(true);
+            true
+        }
+        (&Method::POST, "/maintenance/disable") => {
+            info!("maintenance mode disabled by admin request from {addr}");
+            maintenance::set_enabled(false);
+            false
+        }
+        _ => {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Full::default())
+        }
+    };
+
+    let state = if enabled { "enabled\n" } else { "disabled\n" };
+    Ok(Response::new(Full::from(Bytes::from(state))))
+}
+
+/// Applies action from the `/canary/<listener>/<target>/<action>` path
+fn canary_response(addr: SocketAddr, path: &str) -> Response<Full<Bytes>> {
+    let parts: Vec<&str> = path.trim_start_matches("/canary/").split('/').collect();
+    let (listener_id, target_id, action) = match parts[..] {
+        [listener_id, target_id, "pause"] => (listener_id, target_id, CanaryAction::Pause),
+        [listener_id, target_id, "resume"] => (listener_id, target_id, CanaryAction::Resume),
+        [listener_id, target_id, "rollback"] => (listener_id, target_id, CanaryAction::Rollback),
+        _ => return not_found(),
+    };
+    info!("canary rollout {action:?} of target `{target_id}`, listener `{listener_id}`, by admin request from {addr}");
+    match canary::apply(listener_id, target_id, action) {
+        Some(state) => Response::new(Full::from(state)),
+        None => not_found(),
+    }
+}
+
+/// Diffs or applies candidate config from the request body, format is defined by `Content-Type`, YAML by default
+async fn config_response(
+    addr: SocketAddr,
+    req: Request<Incoming>,
+    apply: bool,
+) -> Response<Full<Bytes>> {
+    let format = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ConfigFormat::from_content_type)
+        .unwrap_or(ConfigFormat::Yaml);
+    let content = match Limited::new(req.into_body(), MAX_CONFIG_SIZE)
+        .collect()
+        .await
+    {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            return text_response(
+                StatusCode::BAD_REQUEST,
+                format!("unable to read config: {e}\n"),
+            )
+        }
+    };
+    let Ok(content) = std::str::from_utf8(&content) else {
+        return text_response(StatusCode::BAD_REQUEST, "config isn't valid UTF-8\n".into());
+    };
+
+    let result = if apply {
+        info!("config apply is requested by admin request from {addr}");
+        reload::apply(content, format)
+    } else {
+        reload::diff(content, format)
+    };
+    match result {
+        Ok(diff) => json_response(StatusCode::OK, diff.to_json()),
+        Err(ReloadError::RestartRequired(diff)) => {
+            warn!("config isn't applied, listeners can't be changed without restart");
+            json_response(StatusCode::CONFLICT, diff.to_json())
+        }
+        Err(e @ ReloadError::InvalidConfig(_)) => {
+            text_response(StatusCode::UNPROCESSABLE_ENTITY, format!("{e}\n"))
+        }
+        Err(e) => {
+            error!("unable to apply config: {e}");
+            text_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{e}\n"))
+        }
+    }
+}
+
+fn json_response(status: StatusCode, json: String) -> Response<Full<Bytes>> {
+    let mut resp = text_response(status, json + "\n");
+    resp.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    resp
+}
+
+fn text_response(status: StatusCode, text: String) -> Response<Full<Bytes>> {
+    let mut resp = Response::new(Full::from(text));
+    *resp.status_mut() = status;
+    resp
+}
+
+fn not_found() -> Response<Full<Bytes>> {
+    let mut resp = Response::new(Full::default());
+    *resp.status_mut() = StatusCode::NOT_FOUND;
+    resp
+}
+
+/// Admin API handler builder
+pub async fn new(port: u16, timeout_sec: u64) -> HyperTaskJoinHandle {
+    info!("Creating admin API handler on *:{}", port);
+
+    let ip = Ipv4Addr::new(0, 0, 0, 0);
+    let socket = SocketAddr::new(ip.into(), port);
+    let listener = TcpListener::bind(&socket)
+        .await
+        .expect("unable to create admin API listener");
+    let mut signal_handler = SignalHandler::new("admin");
+    let mut join_set = JoinSet::new();
+
+    let server = async move {
+        loop {
+            select! {
+                biased;
+                _ = signal_handler.wait() => {
+                    while (join_set.join_next().await).is_some() {}
+                    break
+                },
+                accepted = listener.accept() => {
+                    let (stream, addr) = match accepted {
+                        Ok(x) => x,
+                        Err(e) => {
+                            warn!(error = %e, "failed to accept connection");
+                            continue;
+                        }
+                    };
+
+                    let serve_connection = async move {
+                        let result = Builder::new(TokioExecutor::new())
+                            .http1()
+                            .timer(TokioTimer::default())
+                            .header_read_timeout(Duration::from_secs(timeout_sec))
+                            .serve_connection(TokioIo::new(stream), service_fn(move |req| handle(addr, req)))
+                            .await;
+
+                        if let Err(e) = result {
+                            error!(error = %e, "error serving request from {addr}");
+                        }
+                    };
+
+                    join_set.spawn(serve_connection);
+                }
+            }
+        }
+
+        Ok(())
+    };
+
+    tokio::spawn(server)
+}
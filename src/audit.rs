@@ -0,0 +1,329 @@
+use crate::{
+    config::{
+        audit::{AuditConfig, AuditSink},
+        listener::TlsConfig,
+        target::{HttpsClient, TargetConfig},
+    },
+    redaction,
+    streaming::RequestBody,
+};
+use hyper::{
+    body::{Body, Bytes},
+    header::CONTENT_TYPE,
+    Method, Request, Response,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    net::SocketAddr,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs::File, io::AsyncWriteExt, sync::mpsc};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Hash which the first record of the chain refers to
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Max size of the tail of the audit file which is read to continue the chain
+const MAX_RECORD_SIZE: u64 = 1024 * 1024;
+
+/// Audit trail of the listener, records are written in order by the dedicated task
+#[derive(Debug)]
+pub struct AuditLog {
+    identity_header: Option<String>,
+    records: mpsc::UnboundedSender<AuditRecord>,
+}
+
+/// What was requested by whom, where it was forwarded and what was responded
+#[derive(Serialize, Debug)]
+pub struct AuditRecord {
+    timestamp_ms: u64,
+    request_id: String,
+    listener: String,
+    client: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity: Option<String>,
+    method: String,
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body_sha256: Option<String>,
+    targets: Vec<AuditTarget>,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_size: Option<u64>,
+}
+
+#[derive(Serialize, Debug)]
+struct AuditTarget {
+    id: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+/// Record with its position in the chain, hash is appended to its serialized form
+#[derive(Serialize)]
+struct ChainedRecord<'a> {
+    seq: u64,
+    #[serde(flatten)]
+    record: &'a AuditRecord,
+    prev_hash: &'a str,
+}
+
+/// Position of the last written record, to continue the chain after restart
+#[derive(Deserialize)]
+struct ChainHead {
+    seq: u64,
+    hash: String,
+}
+
+enum Writer {
+    File(File),
+    Http {
+        client: Box<HttpsClient>,
+        url: String,
+        timeout: Duration,
+    },
+}
+
+impl AuditLog {
+    /// Opens the sink and starts the writer task, existing audit file is continued from its last record
+    pub fn new(cfg: &AuditConfig, tls: &TlsConfig) -> Result<Self, io::Error> {
+        let (head, writer) = match cfg.sink() {
+            AuditSink::File(path) => {
+                let head = read_chain_head(path)?;
+                let file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                (head, Writer::File(File::from_std(file)))
+            }
+            AuditSink::Http(url) => (
+                None,
+                Writer::Http {
+                    client: Box::new(TargetConfig::tls_client(tls, cfg.timeout())),
+                    url: url.into(),
+                    timeout: cfg.timeout(),
+                },
+            ),
+        };
+        let head = head.unwrap_or(ChainHead {
+            seq: 0,
+            hash: GENESIS_HASH.into(),
+        });
+        let (records, rx) = mpsc::unbounded_channel();
+        tokio::spawn(writer.run(head, rx));
+
+        Ok(Self {
+            identity_header: cfg.identity_header().map(String::from),
+            records,
+        })
+    }
+
+    /// Starts record of the request, it's completed and written when response is ready
+    pub fn start<B>(
+        &self,
+        request_id: Uuid,
+        listener_id: String,
+        addr: &SocketAddr,
+        req: &Request<B>,
+    ) -> AuditRecord {
+        let identity = self
+            .identity_header
+            .as_ref()
+            .and_then(|name| Some((name, req.headers().get(name)?)))
+            .map(|(name, value)| {
+                redaction::value(name, &String::from_utf8_lossy(value.as_bytes())).to_string()
+            });
+
+        AuditRecord {
+            timestamp_ms: unix_millis(SystemTime::now()),
+            request_id: request_id.to_string(),
+            listener: listener_id,
+            client: addr.to_string(),
+            identity,
+            method: req.method().to_string(),
+            uri: req.uri().to_string(),
+            body_sha256: None,
+            targets: vec![],
+            status: 0,
+            response_size: None,
+        }
+    }
+
+    /// Completes record with the response summary and queues it to write
+    pub fn write<B: Body>(&self, mut record: AuditRecord, resp: &Response<B>) {
+        record.status = resp.status().as_u16();
+        record.response_size = resp.body().size_hint().exact();
+        if self.records.send(record).is_err() {
+            warn!("audit writer has been stopped, record is lost");
+        }
+    }
+}
+
+impl AuditRecord {
+    pub fn set_body(&mut self, body: &Bytes) {
+        self.body_sha256 = Some(format!("{:x}", Sha256::digest(body)));
+    }
+
+    /// Adds outcome of the target's request, status is response status or kind of failure
+    pub fn add_target(&mut self, id: &str, status: &str, duration: Option<Duration>) {
+        self.targets.push(AuditTarget {
+            id: id.into(),
+            status: status.into(),
+            duration_ms: duration.map(|d| d.as_millis() as u64),
+        });
+    }
+}
+
+impl Writer {
+    async fn run(mut self, mut head: ChainHead, mut records: mpsc::UnboundedReceiver<AuditRecord>) {
+        while let Some(record) = records.recv().await {
+            let line = chain(&record, &mut head);
+            if let Err(e) = self.write(line).await {
+                warn!("unable to write audit record {}: {e}", head.seq);
+            } else {
+                debug!("audit record {} is written", head.seq);
+            }
+        }
+    }
+
+    async fn write(&mut self, line: String) -> Result<(), String> {
+        match self {
+            Writer::File(file) => {
+                let mut line = line.into_bytes();
+                line.push(b'\n');
+                file.write_all(&line).await.map_err(|e| e.to_string())?;
+                file.flush().await.map_err(|e| e.to_string())
+            }
+            Writer::Http {
+                client,
+                url,
+                timeout,
+            } => {
+                let req = Request::builder()
+                    .method(Method::POST)
+                    .uri(url.as_str())
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(RequestBody::from(line))
+                    .map_err(|e| e.to_string())?;
+                match tokio::time::timeout(*timeout, client.request(req)).await {
+                    Ok(Ok(resp)) if resp.status().is_success() => Ok(()),
+                    Ok(Ok(resp)) => Err(format!("response status {}", resp.status())),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(_) => Err("timeout".into()),
+                }
+            }
+        }
+    }
+}
+
+/// Serializes record as the next link of the chain and moves head to it.
+///
+/// Hash is SHA-256 of the serialized record with `seq` and `prev_hash` fields,
+/// it's appended as the last `hash` field, so the record can be verified without re-serialization.
+fn chain(record: &AuditRecord, head: &mut ChainHead) -> String {
+    let seq = head.seq + 1;
+    let mut line = serde_json::to_string(&ChainedRecord {
+        seq,
+        record,
+        prev_hash: &head.hash,
+    })
+    .expect("unable to serialize audit record, looks like a BUG");
+    let hash = format!("{:x}", Sha256::digest(line.as_bytes()));
+
+    line.pop();
+    line.push_str(&format!(",\"hash\":\"{hash}\"}}"));
+    *head = ChainHead { seq, hash };
+    line
+}
+
+/// Returns position of the last record in the existing audit file
+fn read_chain_head(path: &Path) -> Result<Option<ChainHead>, io::Error> {
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(MAX_RECORD_SIZE)))?;
+    let mut tail = String::new();
+    file.read_to_string(&mut tail)?;
+
+    match tail.lines().rev().find(|line| !line.trim().is_empty()) {
+        Some(line) => serde_json::from_str(line).map(Some).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "unable to continue audit chain, invalid last record of {}: {e}",
+                    path.display()
+                ),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn hash_chain() {
+        let req = Request::builder()
+            .method("POST")
+            .uri("/path?q=1")
+            .header("x-user", "alice")
+            .body(())
+            .unwrap();
+        let (records, _rx) = mpsc::unbounded_channel();
+        let log = AuditLog {
+            identity_header: Some("x-user".into()),
+            records,
+        };
+        let mut record = log.start(
+            Uuid::nil(),
+            "listener".into(),
+            &([127, 0, 0, 1], 1234).into(),
+            &req,
+        );
+        record.set_body(&Bytes::from("body"));
+        record.add_target("target", "200", Some(Duration::from_millis(15)));
+        record.status = 200;
+
+        let mut head = ChainHead {
+            seq: 0,
+            hash: GENESIS_HASH.into(),
+        };
+        let first = chain(&record, &mut head);
+        let second = chain(&record, &mut head);
+
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (seq, line) in [first, second].iter().enumerate() {
+            let json: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(json["seq"], seq as u64 + 1);
+            assert_eq!(json["identity"], "alice");
+            assert_eq!(json["targets"][0]["duration_ms"], 15);
+            assert_eq!(json["prev_hash"], prev_hash.as_str());
+
+            // Hash covers the record without its own `hash` field
+            let (content, _) = line.rsplit_once(",\"hash\":").unwrap();
+            let hash = format!("{:x}", Sha256::digest(format!("{content}}}").as_bytes()));
+            assert_eq!(json["hash"], hash.as_str());
+            prev_hash = hash;
+        }
+        assert_eq!(head.hash, prev_hash);
+
+        let head: ChainHead = serde_json::from_str(&chain(&record, &mut head)).unwrap();
+        assert_eq!(head.seq, 3);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::config::body_logging::{BodyLoggingConfig, RedactionRule};
+use hyper::{body::Bytes, header::CONTENT_ENCODING, HeaderMap};
+use regex::Regex;
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Prepares bodies for logging: masks configured parts and truncates them to the max size
+#[derive(Debug)]
+pub struct BodyLogger {
+    request: bool,
+    response: bool,
+    max_size: usize,
+    mask: String,
+    json_paths: Vec<Vec<String>>,
+    regexes: Vec<Regex>,
+}
+
+impl BodyLogger {
+    pub fn new(cfg: &BodyLoggingConfig) -> Self {
+        let mut json_paths = vec![];
+        let mut regexes = vec![];
+        for rule in cfg.redact() {
+            match rule {
+                RedactionRule::JsonPath(path) => json_paths.push(
+                    path.trim_start_matches("$.")
+                        .split('.')
+                        .map(String::from)
+                        .collect(),
+                ),
+                RedactionRule::Regex(regex) => regexes
+                    .push(Regex::new(regex).expect("invalid redaction regex, looks like a BUG")),
+            }
+        }
+
+        Self {
+            request: cfg.request(),
+            response: cfg.response(),
+            max_size: cfg.max_size(),
+            mask: cfg.mask().into(),
+            json_paths,
+            regexes,
+        }
+    }
+
+    /// Returns printable body of the request, if it should be logged
+    pub fn request_body(&self, headers: &HeaderMap, body: &Bytes) -> Option<String> {
+        self.request.then(|| self.printable(headers, body))
+    }
+
+    /// Returns printable body of the target's response, if it should be logged
+    pub fn response_body(&self, headers: &HeaderMap, body: &Bytes) -> Option<String> {
+        self.response.then(|| self.printable(headers, body))
+    }
+
+    fn printable(&self, headers: &HeaderMap, body: &Bytes) -> String {
+        // Encoded body is logged as its size only since it can't be redacted
+        if let Some(encoding) = headers.get(CONTENT_ENCODING) {
+            if encoding != "identity" {
+                return format!("<{} bytes, encoded: {encoding:?}>", body.len());
+            }
+        }
+
+        let mut redacted = self.redact(body);
+        if redacted.len() > self.max_size {
+            let mut end = self.max_size;
+            while !redacted.is_char_boundary(end) {
+                end -= 1;
+            }
+            let size = redacted.len();
+            redacted.truncate(end);
+            redacted.push_str(&format!("... <{size} bytes total>"));
+        }
+
+        redacted
+    }
+
+    fn redact(&self, body: &Bytes) -> String {
+        let mut text = match self.redact_json(body) {
+            Some(json) => Cow::Owned(json),
+            None => String::from_utf8_lossy(body),
+        };
+        for regex in &self.regexes {
+            if let Cow::Owned(replaced) = regex.replace_all(&text, self.mask.as_str()) {
+                text = Cow::Owned(replaced);
+            }
+        }
+
+        text.into_owned()
+    }
+
+    /// Masks values of the configured paths if body is JSON
+    fn redact_json(&self, body: &Bytes) -> Option<String> {
+        if self.json_paths.is_empty() {
+            return None;
+        }
+        let mut json: Value = serde_json::from_slice(body).ok()?;
+        for path in &self.json_paths {
+            Self::mask_path(&mut json, path, &self.mask);
+        }
+
+        Some(json.to_string())
+    }
+
+    fn mask_path(value: &mut Value, path: &[String], mask: &str) {
+        let Some((key, rest)) = path.split_first() else {
+            *value = Value::String(mask.into());
+            return;
+        };
+        let children: Vec<&mut Value> = match value {
+            Value::Object(fields) if key == "*" => fields.values_mut().collect(),
+            Value::Object(fields) => fields.get_mut(key).into_iter().collect(),
+            Value::Array(items) if key == "*" => items.iter_mut().collect(),
+            Value::Array(items) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get_mut(index))
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        };
+        for child in children {
+            Self::mask_path(child, rest, mask);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    fn logger(cfg: &str) -> BodyLogger {
+        BodyLogger::new(&serde_json::from_str(cfg).unwrap())
+    }
+
+    #[test]
+    fn redact_json_paths() {
+        let logger = logger(
+            r#"{"redact": [{"json_path": "$.user.email"}, {"json_path": "cards.*.number"}]}"#,
+        );
+        let body = Bytes::from(
+            r#"{"user":{"email":"a@b.c","name":"A"},"cards":[{"number":"4111"},{"number":"5500"}]}"#,
+        );
+
+        assert_eq!(
+            logger.request_body(&HeaderMap::new(), &body).unwrap(),
+            r#"{"cards":[{"number":"***"},{"number":"***"}],"user":{"email":"***","name":"A"}}"#
+        );
+    }
+
+    #[test]
+    fn redact_regex_and_truncate() {
+        let logger = logger(
+            r#"{"max_size": 16, "mask": "<hidden>", "redact": [{"regex": "token=\\w+"}], "response": false}"#,
+        );
+        let body = Bytes::from("token=secret&user=somebody");
+
+        assert_eq!(
+            logger.request_body(&HeaderMap::new(), &body).unwrap(),
+            "<hidden>&user=so... <22 bytes total>"
+        );
+        assert_eq!(logger.response_body(&HeaderMap::new(), &body), None);
+    }
+
+    #[test]
+    fn encoded_body() {
+        let logger = logger("{}");
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+
+        assert_eq!(
+            logger
+                .response_body(&headers, &Bytes::from_static(b"\x1f\x8b"))
+                .unwrap(),
+            r#"<2 bytes, encoded: "gzip">"#
+        );
+    }
+}
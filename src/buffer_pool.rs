@@ -0,0 +1,242 @@
+use crate::{
+    config::buffer_pool::BufferPoolConfig,
+    metrics::{self, MetricsSource},
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use http_body_util::BodyExt;
+use hyper::{body::Body, HeaderMap};
+use std::{
+    pin::pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Pool of the listener's body buffers, it's shared by all workers of the listener.
+///
+/// Body is read into the pooled buffer and handed out as `Bytes` which shares buffer's allocation,
+/// so allocation is reused by the next body only after all `Bytes` of the previous one are dropped.
+/// Buffer is taken from the pool successfully (hit) if its allocation is reclaimed this way.
+#[derive(Debug)]
+pub struct BufferPool {
+    listener_id: String,
+    chunk_size: usize,
+    max_chunks: usize,
+    idle: Mutex<Vec<BytesMut>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl BufferPool {
+    pub fn new(cfg: &BufferPoolConfig, listener_id: String) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            listener_id,
+            chunk_size: cfg.chunk_size(),
+            max_chunks: cfg.max_chunks(),
+            idle: Mutex::new(vec![]),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        });
+        metrics::register(pool.clone());
+
+        pool
+    }
+
+    /// Returns empty buffer with at least `chunk_size` capacity, allocates new one if pool has no reusable buffers
+    fn acquire(&self) -> BytesMut {
+        let idle = self.lock().pop();
+        if let Some(mut buf) = idle {
+            if buf.try_reclaim(self.chunk_size) {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return buf;
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        BytesMut::with_capacity(self.chunk_size)
+    }
+
+    /// Splits content off the buffer and returns the rest of it to the pool, buffers which have grown above
+    /// `chunk_size` aren't kept to avoid holding large allocations
+    fn release(&self, mut buf: BytesMut) -> Bytes {
+        let reusable = buf.len() <= self.chunk_size;
+        let content = buf.split().freeze();
+        if reusable {
+            let mut idle = self.lock();
+            if idle.len() < self.max_chunks {
+                idle.push(buf);
+            }
+        }
+
+        content
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<BytesMut>> {
+        self.idle
+            .lock()
+            .expect("unable to lock buffer pool, looks like a BUG")
+    }
+}
+
+impl MetricsSource for BufferPool {
+    fn write_metrics(&self, out: &mut String) {
+        let labels = [("listener", self.listener_id.as_str())];
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        metrics::gauge(out, "buffer_pool_hits_total", labels, hits as f64);
+        metrics::gauge(out, "buffer_pool_misses_total", labels, misses as f64);
+        if hits + misses > 0 {
+            let ratio = hits as f64 / (hits + misses) as f64;
+            metrics::gauge(out, "buffer_pool_hit_ratio", labels, ratio);
+        }
+        metrics::gauge(
+            out,
+            "buffer_pool_idle_chunks",
+            labels,
+            self.lock().len() as f64,
+        );
+    }
+}
+
+/// Reads the whole body with its trailers, pooled buffer is used if pool is defined
+pub async fn collect<B: Body>(
+    pool: Option<&BufferPool>,
+    body: B,
+) -> Result<(Bytes, Option<HeaderMap>), B::Error> {
+    let Some(pool) = pool else {
+        let collected = body.collect().await?;
+        let trailers = collected.trailers().cloned();
+        return Ok((collected.to_bytes(), trailers));
+    };
+
+    let mut body = pin!(body);
+    let mut buf = pool.acquire();
+    let mut trailers: Option<HeaderMap> = None;
+    while let Some(frame) = body.frame().await {
+        match frame?.into_data() {
+            Ok(data) => buf.put(data),
+            Err(frame) => {
+                if let Ok(frame_trailers) = frame.into_trailers() {
+                    trailers.get_or_insert_default().extend(frame_trailers);
+                }
+            }
+        }
+    }
+
+    Ok((pool.release(buf), trailers))
+}
+
+/// Returns content of the buffer, pooled buffer is returned to the pool
+fn freeze(pool: Option<&BufferPool>, buf: BytesMut) -> Bytes {
+    match pool {
+        Some(pool) => pool.release(buf),
+        None => buf.freeze(),
+    }
+}
+
+/// Body which is read with the size limit
+pub enum Collected<B> {
+    /// Whole body with its trailers
+    Complete(Bytes, Option<HeaderMap>),
+    /// Body is larger than the limit: data which has been read already, it's above the limit,
+    /// and the rest of the body which hasn't been read
+    Oversized(Bytes, B),
+}
+
+/// Reads the body until its end or until it exceeds the limit, pooled buffer is used if pool is defined
+pub async fn collect_limited<B: Body + Unpin>(
+    pool: Option<&BufferPool>,
+    mut body: B,
+    limit: usize,
+) -> Result<Collected<B>, B::Error> {
+    let mut buf = pool.map(BufferPool::acquire).unwrap_or_default();
+    let mut trailers: Option<HeaderMap> = None;
+    while let Some(frame) = body.frame().await {
+        match frame?.into_data() {
+            Ok(data) => {
+                buf.put(data);
+                if buf.len() > limit {
+                    let data = freeze(pool, buf);
+                    return Ok(Collected::Oversized(data, body));
+                }
+            }
+            Err(frame) => {
+                if let Ok(frame_trailers) = frame.into_trailers() {
+                    trailers.get_or_insert_default().extend(frame_trailers);
+                }
+            }
+        }
+    }
+
+    let data = freeze(pool, buf);
+    Ok(Collected::Complete(data, trailers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::Full;
+
+    fn body(content: &'static str) -> Full<Bytes> {
+        Full::new(Bytes::from(content))
+    }
+
+    fn pool(max_chunks: usize) -> Arc<BufferPool> {
+        let cfg: BufferPoolConfig = serde_json::from_str(&format!(
+            r#"{{"chunk_size": 8, "max_chunks": {max_chunks}}}"#
+        ))
+        .unwrap();
+        BufferPool::new(&cfg, "pool".into())
+    }
+
+    #[tokio::test]
+    async fn buffers_are_reused() {
+        let pool = pool(1);
+        let (first, trailers) = collect(Some(&pool), body("first")).await.unwrap();
+        assert_eq!(first, "first");
+        assert_eq!(trailers, None);
+        assert_eq!(pool.misses.load(Ordering::Relaxed), 1);
+
+        // Allocation is still shared with the previous body
+        let (second, _) = collect(Some(&pool), body("second")).await.unwrap();
+        assert_eq!(second, "second");
+        assert_eq!(pool.misses.load(Ordering::Relaxed), 2);
+
+        drop((first, second));
+        let (third, _) = collect(Some(&pool), body("third")).await.unwrap();
+        assert_eq!(third, "third");
+        assert_eq!(pool.hits.load(Ordering::Relaxed), 1);
+
+        // Large bodies don't return buffers to the pool
+        drop(third);
+        let (large, _) = collect(Some(&pool), body("large body")).await.unwrap();
+        assert_eq!(large, "large body");
+        assert_eq!(pool.hits.load(Ordering::Relaxed), 2);
+        assert!(pool.lock().is_empty());
+
+        let mut out = String::new();
+        pool.write_metrics(&mut out);
+        assert!(out.contains("http_dragonfly_buffer_pool_hit_ratio{listener=\"pool\"} 0.5\n"));
+    }
+
+    #[tokio::test]
+    async fn collect_with_limit() {
+        let pool = pool(1);
+        for pool in [None, Some(pool.as_ref())] {
+            let Collected::Complete(data, None) =
+                collect_limited(pool, body("small"), 5).await.unwrap()
+            else {
+                panic!("body should be complete");
+            };
+            assert_eq!(data, "small");
+
+            let Collected::Oversized(data, rest) =
+                collect_limited(pool, body("large body"), 5).await.unwrap()
+            else {
+                panic!("body should be oversized");
+            };
+            assert_eq!(data, "large body");
+            assert!(rest.is_end_stream());
+        }
+    }
+}
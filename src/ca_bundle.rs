@@ -0,0 +1,216 @@
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        WebPkiServerVerifier,
+    },
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+/// Verifies server certificates against root CAs from all PEM files of the directory (and optional CA file),
+/// the directory is re-read when new connection is established but not more often than once per reload interval,
+/// so rotated CAs are picked up without restart. Previous CAs are kept if the directory can't be loaded.
+#[derive(Debug)]
+pub struct ReloadableCaVerifier {
+    ca_file: Option<PathBuf>,
+    ca_dir: PathBuf,
+    reload_interval: Duration,
+    state: RwLock<CaBundleState>,
+}
+
+#[derive(Debug)]
+struct CaBundleState {
+    certs: Vec<CertificateDer<'static>>,
+    verifier: Arc<WebPkiServerVerifier>,
+    loaded_at: Instant,
+}
+
+impl ReloadableCaVerifier {
+    pub fn new(
+        ca_file: Option<&str>,
+        ca_dir: &str,
+        reload_interval: Duration,
+    ) -> Result<Self, anyhow::Error> {
+        let ca_file = ca_file.map(PathBuf::from);
+        let ca_dir = PathBuf::from(ca_dir);
+        let certs = load_bundle(ca_file.as_deref(), &ca_dir)?;
+        let state = CaBundleState {
+            verifier: build_verifier(&certs)?,
+            certs,
+            loaded_at: Instant::now(),
+        };
+
+        Ok(Self {
+            ca_file,
+            ca_dir,
+            reload_interval,
+            state: RwLock::new(state),
+        })
+    }
+
+    /// Returns verifier with the current CAs, reloads them if they are old enough
+    fn verifier(&self) -> Arc<WebPkiServerVerifier> {
+        {
+            let state = self
+                .state
+                .read()
+                .expect("unable to lock CA bundle, looks like a BUG");
+            if state.loaded_at.elapsed() < self.reload_interval {
+                return state.verifier.clone();
+            }
+        }
+
+        let mut state = self
+            .state
+            .write()
+            .expect("unable to lock CA bundle, looks like a BUG");
+        // Other connection could reload it while we were waiting for the lock
+        if state.loaded_at.elapsed() >= self.reload_interval {
+            self.reload(&mut state);
+        }
+        state.verifier.clone()
+    }
+
+    fn reload(&self, state: &mut CaBundleState) {
+        state.loaded_at = Instant::now();
+        let certs = match load_bundle(self.ca_file.as_deref(), &self.ca_dir) {
+            Ok(certs) if certs == state.certs => return,
+            Ok(certs) => certs,
+            Err(e) => {
+                warn!(ca_dir = %self.ca_dir.display(), error = %e, "unable to reload CA bundle, keep previous one");
+                return;
+            }
+        };
+        match build_verifier(&certs) {
+            Ok(verifier) => {
+                info!(ca_dir = %self.ca_dir.display(), certificates = certs.len(), "CA bundle reloaded");
+                state.certs = certs;
+                state.verifier = verifier;
+            }
+            Err(e) => {
+                warn!(ca_dir = %self.ca_dir.display(), error = %e, "unable to reload CA bundle, keep previous one")
+            }
+        }
+    }
+}
+
+impl ServerCertVerifier for ReloadableCaVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verifier().verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        )
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.verifier().verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.verifier().verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.verifier().supported_verify_schemes()
+    }
+}
+
+/// Loads certificates from all PEM files of the directory, files are sorted by name,
+/// files without certificates (like keys) are skipped, but at least one certificate is required
+pub fn load_ca_dir(ca_dir: &Path) -> Result<Vec<CertificateDer<'static>>, anyhow::Error> {
+    let mut files = fs::read_dir(ca_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.retain(|path| path.is_file());
+    files.sort();
+
+    let mut certs = vec![];
+    for path in files {
+        let reader = &mut BufReader::new(File::open(&path)?);
+        for cert in rustls_pemfile::certs(reader) {
+            certs.push(cert.map_err(|e| {
+                anyhow::anyhow!("unable to parse certificate from `{}`: {e}", path.display())
+            })?);
+        }
+    }
+    if certs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no certificates were found in `{}`",
+            ca_dir.display()
+        ));
+    }
+
+    Ok(certs)
+}
+
+fn load_bundle(
+    ca_file: Option<&Path>,
+    ca_dir: &Path,
+) -> Result<Vec<CertificateDer<'static>>, anyhow::Error> {
+    let mut certs = vec![];
+    if let Some(ca_file) = ca_file {
+        let reader = &mut BufReader::new(File::open(ca_file)?);
+        certs = rustls_pemfile::certs(reader).collect::<Result<Vec<_>, _>>()?;
+    }
+    certs.extend(load_ca_dir(ca_dir)?);
+
+    Ok(certs)
+}
+
+fn build_verifier(
+    certs: &[CertificateDer<'static>],
+) -> Result<Arc<WebPkiServerVerifier>, anyhow::Error> {
+    let mut store = RootCertStore::empty();
+    for cert in certs {
+        store.add(cert.clone())?;
+    }
+
+    Ok(WebPkiServerVerifier::builder(Arc::new(store)).build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_ca_directory() {
+        let certs = load_ca_dir(Path::new("tests/tls")).unwrap();
+        // `ca.crt` has root CA, `ca.pem` has intermediate and root ones
+        assert_eq!(certs.len(), 3);
+
+        let verifier = ReloadableCaVerifier::new(None, "tests/tls", Duration::ZERO).unwrap();
+        verifier.verifier();
+        assert_eq!(verifier.state.read().unwrap().certs, certs);
+
+        assert!(load_ca_dir(Path::new("tests/configs/wrong")).is_err());
+        assert!(load_ca_dir(Path::new("tests/absent")).is_err());
+    }
+}
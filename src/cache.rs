@@ -0,0 +1,428 @@
+use crate::config::cache::CacheConfig;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue, CACHE_CONTROL, SET_COOKIE},
+    HeaderMap, Method, Request, Response, StatusCode,
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, warn};
+
+/// Header which is added to the responses returned from the cache
+pub const CACHE_HEADER: &str = "x-splitter-cache";
+
+/// Cache of the final listener's responses, stored in memory or in files
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    max_body_size: usize,
+    vary: Vec<HeaderName>,
+    path: Option<PathBuf>,
+    state: Mutex<CacheState>,
+}
+
+/// Index of cached responses, keys are ordered by insertion time,
+/// so the first one is the oldest one since all entries have the same TTL
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    order: VecDeque<String>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    expires_at: SystemTime,
+    /// Response is stored in the file if it's absent
+    response: Option<CachedResponse>,
+}
+
+/// Buffered copy of the final response
+#[derive(Clone, Debug)]
+pub struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl ResponseCache {
+    /// Creates cache, responses stored in files during previous runs are added to the index
+    pub fn new(cfg: &CacheConfig) -> Self {
+        let cache = Self {
+            ttl: cfg.ttl(),
+            max_entries: cfg.max_entries(),
+            max_body_size: cfg.max_body_size(),
+            vary: cfg.vary(),
+            path: cfg.path().cloned(),
+            state: Mutex::new(CacheState::default()),
+        };
+
+        if let Some(path) = &cache.path {
+            if let Err(e) = fs::create_dir_all(path) {
+                warn!("unable to create cache folder `{}`: {e}", path.display());
+            }
+            cache.load_index();
+        }
+
+        cache
+    }
+
+    /// Returns cache key of the request, or `None` if request shouldn't be cached
+    pub fn key<B>(&self, req: &Request<B>) -> Option<String> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return None;
+        }
+        if has_directive(req.headers(), &["no-cache", "no-store"]) {
+            return None;
+        }
+
+        let mut key = format!("{} {}", req.method(), req.uri());
+        for name in &self.vary {
+            let values: Vec<&str> = req
+                .headers()
+                .get_all(name)
+                .iter()
+                .map(|v| v.to_str().unwrap_or_default())
+                .collect();
+            key.push_str(&format!("|{name}={}", values.join(",")));
+        }
+
+        Some(key)
+    }
+
+    /// Returns cached response if it's present and isn't expired
+    pub async fn get(&self, key: &str) -> Option<Response<Full<Bytes>>> {
+        let expired = {
+            let mut state = self
+                .state
+                .lock()
+                .expect("unable to lock cache, looks like a BUG");
+            let entry = state.entries.get(key)?;
+            if let Some(response) = &entry.response {
+                if entry.expires_at > SystemTime::now() {
+                    return Some(response.clone().into_response(CACHE_HEADER));
+                }
+            }
+            let expired = entry.expires_at <= SystemTime::now();
+            if expired {
+                debug!("cache entry expired: {key}");
+                state.remove(key);
+            }
+            expired
+        };
+        if expired {
+            self.remove_files(&[key.to_string()]).await;
+            return None;
+        }
+
+        let file = self.file_path(key)?;
+        let response = match tokio::fs::read(&file).await {
+            Ok(data) => CachedResponse::decode(&data, key).map(|(response, _)| response),
+            Err(e) => {
+                warn!("unable to read cache file `{}`: {e}", file.display());
+                None
+            }
+        };
+        if response.is_none() {
+            let mut state = self
+                .state
+                .lock()
+                .expect("unable to lock cache, looks like a BUG");
+            state.remove(key);
+        }
+
+        response.map(|response| response.into_response(CACHE_HEADER))
+    }
+
+    /// Puts response into the cache if it's cacheable:
+    /// successful, without cookies and `no-store`/`private` directives, and isn't too large
+    pub async fn put(&self, key: String, resp: &Response<Full<Bytes>>) {
+        if !resp.status().is_success()
+            || resp.headers().contains_key(SET_COOKIE)
+            || has_directive(resp.headers(), &["no-store", "private"])
+        {
+            return;
+        }
+        let response = CachedResponse::from_response(resp).await;
+        if response.body.len() > self.max_body_size {
+            debug!(
+                "response is too large to cache: {} bytes",
+                response.body.len()
+            );
+            return;
+        }
+
+        let expires_at = SystemTime::now() + self.ttl;
+        let response = if let Some(file) = self.file_path(&key) {
+            if let Err(e) = tokio::fs::write(&file, response.encode(&key, expires_at)).await {
+                warn!("unable to write cache file `{}`: {e}", file.display());
+                return;
+            }
+            None
+        } else {
+            Some(response)
+        };
+
+        let evicted = {
+            let mut state = self
+                .state
+                .lock()
+                .expect("unable to lock cache, looks like a BUG");
+            state.insert(
+                key,
+                CacheEntry {
+                    expires_at,
+                    response,
+                },
+            );
+            state.evict(self.max_entries)
+        };
+        self.remove_files(&evicted).await;
+    }
+
+    fn file_path(&self, key: &str) -> Option<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.path
+            .as_ref()
+            .map(|path| path.join(format!("{:016x}.cache", hasher.finish())))
+    }
+
+    async fn remove_files(&self, keys: &[String]) {
+        for file in keys.iter().filter_map(|key| self.file_path(key)) {
+            let _ = tokio::fs::remove_file(file).await;
+        }
+    }
+
+    /// Adds valid not expired files from the cache folder to the index, removes all others
+    fn load_index(&self) {
+        let Some(Ok(dir)) = self.path.as_ref().map(fs::read_dir) else {
+            return;
+        };
+
+        let mut loaded = vec![];
+        for file in dir.flatten().map(|entry| entry.path()) {
+            if file.extension().is_none_or(|ext| ext != "cache") {
+                continue;
+            }
+            let entry = fs::read(&file).ok().and_then(|data| {
+                let key = data.split(|b| *b == b'\n').next()?;
+                let key = String::from_utf8(key.to_vec()).ok()?;
+                let (_, expires_at) = CachedResponse::decode(&data, &key)?;
+                (expires_at > SystemTime::now() && self.file_path(&key)? == file)
+                    .then_some((key, expires_at))
+            });
+            match entry {
+                Some(entry) => loaded.push(entry),
+                None => {
+                    let _ = fs::remove_file(&file);
+                }
+            }
+        }
+
+        loaded.sort_by_key(|(_, expires_at)| *expires_at);
+        debug!("{} response(s) loaded from cache folder", loaded.len());
+        let mut state = self
+            .state
+            .lock()
+            .expect("unable to lock cache, looks like a BUG");
+        for (key, expires_at) in loaded {
+            state.insert(
+                key,
+                CacheEntry {
+                    expires_at,
+                    response: None,
+                },
+            );
+        }
+        let evicted = state.evict(self.max_entries);
+        drop(state);
+        for file in evicted.iter().filter_map(|key| self.file_path(key)) {
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+impl CacheState {
+    fn insert(&mut self, key: String, entry: CacheEntry) {
+        if self.entries.insert(key.clone(), entry).is_some() {
+            self.order.retain(|k| k != &key);
+        }
+        self.order.push_back(key);
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.order.retain(|k| k != key);
+        }
+    }
+
+    /// Removes expired entries and the oldest ones above the limit, returns keys of removed entries
+    fn evict(&mut self, max_entries: usize) -> Vec<String> {
+        let now = SystemTime::now();
+        let mut evicted = vec![];
+        while let Some(key) = self.order.front() {
+            let expired = self.entries.get(key).is_none_or(|e| e.expires_at <= now);
+            if !expired && self.order.len() <= max_entries {
+                break;
+            }
+            let key = self
+                .order
+                .pop_front()
+                .expect("cache index is empty, looks like a BUG");
+            self.entries.remove(&key);
+            evicted.push(key);
+        }
+
+        evicted
+    }
+}
+
+impl CachedResponse {
+    pub async fn from_response(resp: &Response<Full<Bytes>>) -> Self {
+        let Ok(body) = resp.body().clone().collect().await;
+        Self {
+            status: resp.status(),
+            headers: resp.headers().clone(),
+            body: body.to_bytes(),
+        }
+    }
+
+    /// Creates response with the `marker` header to distinguish it from the origin one
+    pub fn into_response(self, marker: &'static str) -> Response<Full<Bytes>> {
+        let mut resp = Response::new(Full::from(self.body));
+        *resp.status_mut() = self.status;
+        *resp.headers_mut() = self.headers;
+        resp.headers_mut()
+            .insert(marker, HeaderValue::from_static("hit"));
+        resp
+    }
+
+    /// File format: key, expiration time (unix seconds), status and headers - one per line,
+    /// then empty line and body
+    fn encode(&self, key: &str, expires_at: SystemTime) -> Vec<u8> {
+        let expires_at = expires_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut data = format!("{key}\n{expires_at}\n{}\n", self.status.as_u16()).into_bytes();
+        for (name, value) in &self.headers {
+            data.extend_from_slice(name.as_str().as_bytes());
+            data.extend_from_slice(b": ");
+            data.extend_from_slice(value.as_bytes());
+            data.push(b'\n');
+        }
+        data.push(b'\n');
+        data.extend_from_slice(&self.body);
+
+        data
+    }
+
+    fn decode(data: &[u8], key: &str) -> Option<(Self, SystemTime)> {
+        let head_len = data.windows(2).position(|w| w == b"\n\n")?;
+        let head = std::str::from_utf8(&data[..head_len]).ok()?;
+        let mut lines = head.lines();
+        if lines.next()? != key {
+            return None;
+        }
+        let expires_at = UNIX_EPOCH + Duration::from_secs(lines.next()?.parse().ok()?);
+        let status = StatusCode::from_u16(lines.next()?.parse().ok()?).ok()?;
+        let mut headers = HeaderMap::new();
+        for line in lines {
+            let (name, value) = line.split_once(": ")?;
+            headers.append(
+                HeaderName::from_bytes(name.as_bytes()).ok()?,
+                HeaderValue::from_str(value).ok()?,
+            );
+        }
+        let body = Bytes::copy_from_slice(&data[head_len + 2..]);
+
+        Some((
+            Self {
+                status,
+                headers,
+                body,
+            },
+            expires_at,
+        ))
+    }
+}
+
+/// Verifies if `Cache-Control` header contains any of the directives
+fn has_directive(headers: &HeaderMap, directives: &[&str]) -> bool {
+    headers.get_all(CACHE_CONTROL).iter().any(|value| {
+        value
+            .to_str()
+            .unwrap_or_default()
+            .split(',')
+            .any(|d| directives.iter().any(|x| d.trim().eq_ignore_ascii_case(x)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_response(body: &'static str) -> Response<Full<Bytes>> {
+        Response::builder()
+            .header("x-test", "value")
+            .body(Full::from(body))
+            .unwrap()
+    }
+
+    #[test]
+    fn encode_decode() {
+        let response = CachedResponse {
+            status: StatusCode::CREATED,
+            headers: test_response("").headers().clone(),
+            body: Bytes::from("line 1\n\nline 2"),
+        };
+        let expires_at = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let data = response.encode("GET /path", expires_at);
+
+        let (decoded, decoded_expires_at) = CachedResponse::decode(&data, "GET /path").unwrap();
+        assert_eq!(decoded_expires_at, expires_at);
+        assert_eq!(decoded.status, StatusCode::CREATED);
+        assert_eq!(decoded.headers, response.headers);
+        assert_eq!(decoded.body, response.body);
+
+        assert!(CachedResponse::decode(&data, "GET /other").is_none());
+    }
+
+    #[test]
+    fn eviction() {
+        let mut state = CacheState::default();
+        let now = SystemTime::now();
+        for (key, ttl) in [("a", 0), ("b", 100), ("c", 100), ("d", 100)] {
+            state.insert(
+                key.into(),
+                CacheEntry {
+                    expires_at: now + Duration::from_secs(ttl),
+                    response: None,
+                },
+            );
+        }
+
+        assert_eq!(state.evict(2), vec!["a", "b"]);
+        assert_eq!(state.order, ["c", "d"]);
+        assert!(state.entries.contains_key("c") && state.entries.contains_key("d"));
+    }
+
+    #[test]
+    fn directives() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CACHE_CONTROL,
+            HeaderValue::from_static("max-age=0, No-Store"),
+        );
+        assert!(has_directive(&headers, &["no-cache", "no-store"]));
+        assert!(!has_directive(&headers, &["private"]));
+    }
+}
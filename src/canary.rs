@@ -0,0 +1,294 @@
+use crate::{
+    config::canary::CanaryConfig,
+    metrics::{self, MetricsSource},
+};
+use std::{
+    fmt::Write,
+    sync::{Arc, LazyLock, Mutex, Weak},
+    time::{Duration, Instant},
+};
+use strum_macros::Display;
+use tracing::{info, warn};
+
+/// Rollback isn't decided until the canary has at least this number of requests on the current step
+const CANARY_MIN_REQUESTS: u64 = 10;
+
+static REGISTRY: LazyLock<Mutex<Vec<Weak<CanaryController>>>> =
+    LazyLock::new(|| Mutex::new(vec![]));
+
+/// Moves canary target through the rollout steps, state is shared by all workers of the listener
+/// and controlled by the admin API
+#[derive(Debug)]
+pub struct CanaryController {
+    listener_id: String,
+    target_id: String,
+    steps: Vec<f64>,
+    bake_time: Duration,
+    max_error_rate_increase: f64,
+    state: Mutex<CanaryState>,
+}
+
+#[derive(Debug)]
+struct CanaryState {
+    status: CanaryStatus,
+    step: usize,
+    step_started: Instant,
+    canary: Outcomes,
+    baseline: Outcomes,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Outcomes {
+    total: u64,
+    failed: u64,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum CanaryStatus {
+    Running,
+    Paused,
+    RolledBack,
+}
+
+/// Action requested by the admin API
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CanaryAction {
+    Pause,
+    Resume,
+    Rollback,
+}
+
+impl CanaryController {
+    pub fn new(cfg: &CanaryConfig, listener_id: String, target_id: String) -> Arc<Self> {
+        info!(
+            listener = listener_id,
+            target = target_id,
+            "canary rollout started at {}%",
+            cfg.steps()[0]
+        );
+        let controller = Arc::new(Self {
+            listener_id,
+            target_id,
+            steps: cfg.steps().to_vec(),
+            bake_time: cfg.bake_time(),
+            max_error_rate_increase: cfg.max_error_rate_increase(),
+            state: Mutex::new(CanaryState {
+                status: CanaryStatus::Running,
+                step: 0,
+                step_started: Instant::now(),
+                canary: Outcomes::default(),
+                baseline: Outcomes::default(),
+            }),
+        });
+        REGISTRY
+            .lock()
+            .expect("unable to lock canary registry, looks like a BUG")
+            .push(Arc::downgrade(&controller));
+        metrics::register(controller.clone());
+
+        controller
+    }
+
+    pub fn target_id(&self) -> &str {
+        &self.target_id
+    }
+
+    /// Returns current percent of the responses to select from the canary target,
+    /// rollout goes to the next step if the current one has been baked
+    pub fn percent(&self) -> f64 {
+        let mut state = self.lock();
+        if state.status == CanaryStatus::Running
+            && state.step + 1 < self.steps.len()
+            && state.step_started.elapsed() >= self.bake_time
+        {
+            let step = state.step + 1;
+            state.next_step(step);
+            info!(
+                listener = self.listener_id,
+                target = self.target_id,
+                "canary rollout goes to {}%",
+                self.steps[state.step]
+            );
+        }
+        self.state_percent(&state)
+    }
+
+    /// Records outcome of the target's request, canary is rolled back if it fails more often than other targets
+    pub fn record(&self, target_id: &str, failed: bool) {
+        let mut state = self.lock();
+        if state.status != CanaryStatus::Running {
+            return;
+        }
+        let outcomes = if target_id == self.target_id {
+            &mut state.canary
+        } else {
+            &mut state.baseline
+        };
+        outcomes.total += 1;
+        outcomes.failed += u64::from(failed);
+
+        if state.canary.total < CANARY_MIN_REQUESTS {
+            return;
+        }
+        let canary_rate = state.canary.failures_rate();
+        let baseline_rate = state.baseline.failures_rate();
+        if canary_rate - baseline_rate > self.max_error_rate_increase {
+            warn!(
+                listener = self.listener_id,
+                target = self.target_id,
+                canary_rate,
+                baseline_rate,
+                "canary rollout is rolled back because of failures"
+            );
+            state.status = CanaryStatus::RolledBack;
+        }
+    }
+
+    /// Applies action of the admin API, returns state after it
+    pub fn apply(&self, action: CanaryAction) -> String {
+        let mut state = self.lock();
+        match (action, state.status) {
+            (CanaryAction::Pause, CanaryStatus::Running) => state.status = CanaryStatus::Paused,
+            (CanaryAction::Resume, CanaryStatus::Paused) => {
+                let step = state.step;
+                state.next_step(step);
+            }
+            // Rolled back canary starts from the first step
+            (CanaryAction::Resume, CanaryStatus::RolledBack) => state.next_step(0),
+            (CanaryAction::Rollback, _) => state.status = CanaryStatus::RolledBack,
+            _ => {}
+        }
+        self.describe(&state)
+    }
+
+    fn state_percent(&self, state: &CanaryState) -> f64 {
+        match state.status {
+            CanaryStatus::RolledBack => 0.0,
+            _ => self.steps[state.step],
+        }
+    }
+
+    fn describe(&self, state: &CanaryState) -> String {
+        format!(
+            "{}/{}: {}%, {}\n",
+            self.listener_id,
+            self.target_id,
+            self.state_percent(state),
+            state.status
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CanaryState> {
+        self.state
+            .lock()
+            .expect("unable to lock canary state, looks like a BUG")
+    }
+}
+
+impl CanaryState {
+    /// Starts rollout step from scratch
+    fn next_step(&mut self, step: usize) {
+        self.status = CanaryStatus::Running;
+        self.step = step;
+        self.step_started = Instant::now();
+        self.canary = Outcomes::default();
+        self.baseline = Outcomes::default();
+    }
+}
+
+impl Outcomes {
+    fn failures_rate(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.failed as f64 / self.total as f64
+        }
+    }
+}
+
+impl MetricsSource for CanaryController {
+    fn write_metrics(&self, out: &mut String) {
+        metrics::gauge(
+            out,
+            "canary_percent",
+            [
+                ("listener", self.listener_id.as_str()),
+                ("target", self.target_id.as_str()),
+            ],
+            self.state_percent(&self.lock()),
+        );
+    }
+}
+
+/// Returns state of all canary rollouts, one per line
+pub fn status() -> String {
+    controllers().iter().fold(String::new(), |mut out, c| {
+        let _ = write!(out, "{}", c.describe(&c.lock()));
+        out
+    })
+}
+
+/// Applies action to the canary rollout of the target, returns `None` if there is no such rollout
+pub fn apply(listener_id: &str, target_id: &str, action: CanaryAction) -> Option<String> {
+    controllers()
+        .iter()
+        .find(|c| c.listener_id == listener_id && c.target_id == target_id)
+        .map(|c| c.apply(action))
+}
+
+fn controllers() -> Vec<Arc<CanaryController>> {
+    let mut registry = REGISTRY
+        .lock()
+        .expect("unable to lock canary registry, looks like a BUG");
+    registry.retain(|c| c.strong_count() > 0);
+    registry.iter().filter_map(Weak::upgrade).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn controller(cfg: &str, listener_id: &str) -> Arc<CanaryController> {
+        let cfg: CanaryConfig = serde_json::from_str(cfg).unwrap();
+        CanaryController::new(&cfg, listener_id.into(), "canary".into())
+    }
+
+    #[test]
+    fn rollout_steps() {
+        let controller = controller(r#"{"steps": [1, 50, 100], "bake_time": "1ms"}"#, "steps");
+        assert_eq!(controller.percent(), 1.0);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(controller.percent(), 50.0);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(controller.percent(), 100.0);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(controller.percent(), 100.0);
+    }
+
+    #[test]
+    fn rollback_on_failures() {
+        let controller = controller(
+            r#"{"steps": [5, 100], "bake_time": "1h", "max_error_rate_increase": 0.15}"#,
+            "rollback",
+        );
+        for pos in 0..CANARY_MIN_REQUESTS {
+            controller.record("baseline", false);
+            controller.record("canary", pos == 0);
+        }
+        assert_eq!(controller.percent(), 5.0);
+        controller.record("canary", true);
+        assert_eq!(controller.percent(), 0.0);
+
+        assert_eq!(
+            apply("rollback", "canary", CanaryAction::Resume).as_deref(),
+            Some("rollback/canary: 5%, running\n")
+        );
+        assert_eq!(
+            apply("rollback", "canary", CanaryAction::Pause).as_deref(),
+            Some("rollback/canary: 5%, paused\n")
+        );
+        assert_eq!(apply("rollback", "unknown", CanaryAction::Pause), None);
+        assert!(status().contains("rollback/canary: 5%, paused\n"));
+    }
+}
@@ -1,13 +1,21 @@
-use clap::Parser;
+use crate::config::{runtime::RuntimeConfig, ConfigSource};
+use crate::log_governor::GovernorFilter;
+use clap::{ArgGroup, Parser, Subcommand};
 use regex::Regex;
 use tracing::debug;
-use tracing_subscriber::{filter::LevelFilter, fmt, EnvFilter};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+};
 
 const DEFAULT_ENV_REGEX: &str = "^HTTP_ENV_[a-zA-Z0-9_]+$";
 
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(author, version, about, subcommand_negates_reqs = true)]
+#[command(group(ArgGroup::new("config_source").required(true).args(["config", "config_dir"])))]
 pub struct CliConfig {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+
     /// Enable extreme logging (debug)
     #[arg(short, long)]
     debug: bool,
@@ -22,7 +30,19 @@ pub struct CliConfig {
 
     /// Path to config file
     #[arg(long, short)]
-    config: String,
+    config: Option<String>,
+
+    /// Path to folder with config files, all `*.yaml`/`*.yml`/`*.toml`/`*.json` files will be loaded
+    #[arg(long)]
+    config_dir: Option<String>,
+
+    /// Fail if config refers to unknown environment variables without default value
+    #[arg(long, global = true)]
+    strict_env: bool,
+
+    /// Abort startup if any target with `startup_check` is unreachable, otherwise just warn
+    #[arg(long)]
+    fail_fast_on_unreachable_targets: bool,
 
     /// Allowed environment variables mask (regex)
     #[arg(long, short, default_value_t = DEFAULT_ENV_REGEX.to_string(), value_parser=CliConfig::parse_env_mask)]
@@ -31,6 +51,42 @@ pub struct CliConfig {
     /// Enable health check responder on the specified port
     #[arg(long, short = 'p', value_parser=CliConfig::parse_health_check_port)]
     pub health_check_port: Option<u16>,
+
+    /// Enable admin API on the specified port
+    #[arg(long, value_parser=CliConfig::parse_admin_port)]
+    pub admin_port: Option<u16>,
+
+    /// Number of runtime worker threads, default is number of CPU cores
+    #[arg(long, value_parser=CliConfig::parse_threads)]
+    worker_threads: Option<usize>,
+
+    /// Maximum number of runtime threads for blocking operations
+    #[arg(long, value_parser=CliConfig::parse_threads)]
+    max_blocking_threads: Option<usize>,
+
+    /// Run as Windows service, the process should be started by the service control manager
+    #[cfg(windows)]
+    #[arg(long)]
+    service: bool,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// Load and validate config file, then exit
+    #[command(group(ArgGroup::new("config_source").required(true).args(["config", "config_dir"])))]
+    Validate {
+        /// Path to config file
+        #[arg(long, short)]
+        config: Option<String>,
+        /// Path to folder with config files, all `*.yaml`/`*.yml`/`*.toml`/`*.json` files will be loaded
+        #[arg(long)]
+        config_dir: Option<String>,
+        /// Print effective config in JSON with source of each value
+        #[arg(long)]
+        dump_config: bool,
+    },
+    /// Print JSON schema of the config file and exit
+    Schema,
 }
 
 impl CliConfig {
@@ -46,12 +102,13 @@ impl CliConfig {
 
     pub fn from_config_path(config: String) -> CliConfig {
         Self {
-            config,
+            config: Some(config),
             ..Self::default()
         }
     }
 
-    /// Creates global logger and set requested log level and format
+    /// Creates global logger and set requested log level and format,
+    /// repeated warnings and errors are governed if config enables it
     fn setup_logger(&self) {
         let level_filter = if self.debug {
             LevelFilter::DEBUG
@@ -64,13 +121,13 @@ impl CliConfig {
         let log_filter = EnvFilter::from_default_env().add_directive(level_filter.into());
         let log_format = fmt::format().with_level(true).with_target(self.debug);
 
-        let subscriber = tracing_subscriber::fmt().with_env_filter(log_filter);
+        let subscriber = tracing_subscriber::registry().with(log_filter);
         if self.json_log {
-            subscriber
-                .event_format(log_format.json().flatten_event(true))
-                .init();
+            let layer = fmt::layer().event_format(log_format.json().flatten_event(true));
+            subscriber.with(layer.with_filter(GovernorFilter)).init();
         } else {
-            subscriber.event_format(log_format.compact()).init();
+            let layer = fmt::layer().event_format(log_format.compact());
+            subscriber.with(layer.with_filter(GovernorFilter)).init();
         };
     }
 
@@ -88,12 +145,21 @@ impl CliConfig {
 
     /// Parse port number string into u16 and validate range
     fn parse_health_check_port(port: &str) -> Result<u16, String> {
+        Self::parse_port(port, "health check")
+    }
+
+    /// Parse port number string into u16 and validate range
+    fn parse_admin_port(port: &str) -> Result<u16, String> {
+        Self::parse_port(port, "admin")
+    }
+
+    fn parse_port(port: &str, name: &str) -> Result<u16, String> {
         match port.parse::<u16>() {
             Ok(port) => {
                 if port > 0 {
                     Ok(port)
                 } else {
-                    Err("health check port number should be in range 1..65535".into())
+                    Err(format!("{name} port number should be in range 1..65535"))
                 }
             }
             Err(e) => Err(format!(
@@ -103,26 +169,81 @@ impl CliConfig {
         }
     }
 
-    /// Getter for config path
-    pub fn config_path(&self) -> String {
-        self.config.to_string()
+    /// Parse number of threads and validate it's positive
+    fn parse_threads(threads: &str) -> Result<usize, String> {
+        match threads.parse::<usize>() {
+            Ok(0) => Err("number of threads should be greater than zero".into()),
+            Ok(threads) => Ok(threads),
+            Err(e) => Err(format!("unable to parse `{threads}`: {e}")),
+        }
+    }
+
+    /// Returns settings of the main runtime
+    pub fn runtime_config(&self) -> RuntimeConfig {
+        RuntimeConfig::new(self.worker_threads, self.max_blocking_threads)
+    }
+
+    /// Getter for config source, the subcommand's one takes precedence
+    pub fn config_source(&self) -> ConfigSource {
+        let (config, config_dir) = match &self.command {
+            Some(CliCommand::Validate {
+                config, config_dir, ..
+            }) => (config, config_dir),
+            _ => (&self.config, &self.config_dir),
+        };
+
+        if let Some(config_dir) = config_dir {
+            ConfigSource::Dir(config_dir.clone())
+        } else {
+            ConfigSource::File(config.clone().unwrap_or_default())
+        }
+    }
+
+    /// Getter for requested subcommand
+    pub fn command(&self) -> Option<&CliCommand> {
+        self.command.as_ref()
+    }
+
+    /// Getter for strict environment expansion mode
+    pub fn strict_env(&self) -> bool {
+        self.strict_env
+    }
+
+    /// Getter for fail-fast mode of the targets' startup check
+    pub fn fail_fast_on_unreachable_targets(&self) -> bool {
+        self.fail_fast_on_unreachable_targets
     }
 
     /// Getter for environment variables mask
     pub fn env_mask(&self) -> &str {
         self.env_mask.as_ref()
     }
+
+    /// Getter for Windows service mode
+    #[cfg(windows)]
+    pub fn service(&self) -> bool {
+        self.service
+    }
 }
 
 impl Default for CliConfig {
     fn default() -> Self {
         Self {
+            command: None,
             debug: false,
             verbose: false,
             json_log: false,
-            config: "".to_owned(),
+            config: None,
+            config_dir: None,
+            strict_env: false,
+            fail_fast_on_unreachable_targets: false,
             env_mask: DEFAULT_ENV_REGEX.to_owned(),
             health_check_port: None,
+            admin_port: None,
+            worker_threads: None,
+            max_blocking_threads: None,
+            #[cfg(windows)]
+            service: false,
         }
     }
 }
@@ -161,6 +282,92 @@ mod tests {
         assert_ron_snapshot!(CliConfig::parse_env_mask("**"));
     }
 
+    #[test]
+    fn parse_subcommands() {
+        let cli = CliConfig::try_parse_from(["http-dragonfly", "-c", "config.yaml"]).unwrap();
+        assert_eq!(cli.command(), None);
+        assert_eq!(cli.config_source(), "config.yaml".into());
+
+        let cli = CliConfig::try_parse_from(["http-dragonfly", "--config-dir", "configs"]).unwrap();
+        assert_eq!(cli.config_source(), ConfigSource::Dir("configs".into()));
+
+        let cli =
+            CliConfig::try_parse_from(["http-dragonfly", "validate", "-c", "config.yaml"]).unwrap();
+        assert_eq!(
+            cli.command(),
+            Some(&CliCommand::Validate {
+                config: Some("config.yaml".into()),
+                config_dir: None,
+                dump_config: false,
+            })
+        );
+        assert_eq!(cli.config_source(), "config.yaml".into());
+
+        let cli =
+            CliConfig::try_parse_from(["http-dragonfly", "validate", "--config-dir", "configs"])
+                .unwrap();
+        assert_eq!(cli.config_source(), ConfigSource::Dir("configs".into()));
+
+        let cli = CliConfig::try_parse_from(["http-dragonfly", "-c", "config.yaml"]).unwrap();
+        assert!(!cli.strict_env());
+        let cli = CliConfig::try_parse_from([
+            "http-dragonfly",
+            "validate",
+            "-c",
+            "config.yaml",
+            "--strict-env",
+        ])
+        .unwrap();
+        assert!(cli.strict_env());
+
+        let cli = CliConfig::try_parse_from([
+            "http-dragonfly",
+            "-c",
+            "config.yaml",
+            "--fail-fast-on-unreachable-targets",
+        ])
+        .unwrap();
+        assert!(cli.fail_fast_on_unreachable_targets());
+
+        let cli = CliConfig::try_parse_from(["http-dragonfly", "-c", "config.yaml"]).unwrap();
+        assert_eq!(cli.runtime_config().worker_threads(), None);
+        assert_eq!(cli.runtime_config().max_blocking_threads(), None);
+        let cli = CliConfig::try_parse_from([
+            "http-dragonfly",
+            "-c",
+            "config.yaml",
+            "--worker-threads",
+            "4",
+            "--max-blocking-threads",
+            "32",
+        ])
+        .unwrap();
+        assert_eq!(cli.runtime_config().worker_threads(), Some(4));
+        assert_eq!(cli.runtime_config().max_blocking_threads(), Some(32));
+        assert!(CliConfig::try_parse_from([
+            "http-dragonfly",
+            "-c",
+            "config.yaml",
+            "--worker-threads",
+            "0"
+        ])
+        .is_err());
+
+        let cli = CliConfig::try_parse_from(["http-dragonfly", "schema"]).unwrap();
+        assert_eq!(cli.command(), Some(&CliCommand::Schema));
+
+        assert!(CliConfig::try_parse_from(["http-dragonfly"]).is_err());
+        assert!(CliConfig::try_parse_from(["http-dragonfly", "validate"]).is_err());
+        assert!(CliConfig::try_parse_from([
+            "http-dragonfly",
+            "-c",
+            "config.yaml",
+            "--config-dir",
+            "configs"
+        ])
+        .is_err());
+    }
+
     #[test]
     fn parse_good_health_check_port() {
         assert_eq!(CliConfig::parse_health_check_port("123"), Ok(123));
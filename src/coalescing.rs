@@ -0,0 +1,183 @@
+use crate::{cache::CachedResponse, config::coalescing::CoalescingConfig};
+use http_body_util::Full;
+use hyper::{body::Bytes, header::HeaderName, Method, Request, Response};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+
+/// Header which is added to the responses shared with the coalesced requests
+pub const COALESCING_HEADER: &str = "x-splitter-coalesced";
+
+/// Tracks identical GET requests in flight, so only the first one is passed to targets
+#[derive(Debug)]
+pub struct Coalescer {
+    cfg: CoalescingConfig,
+    vary: Vec<HeaderName>,
+    in_flight: Mutex<HashMap<String, watch::Receiver<Option<CachedResponse>>>>,
+}
+
+pub enum Coalescing {
+    /// Request can't be coalesced, or the leading request has been completed without response to share
+    Skipped,
+    /// The first request in flight, its response should be passed to the guard
+    Leader(CoalescingGuard),
+    /// Response of the leading identical request
+    Coalesced(Response<Full<Bytes>>),
+}
+
+/// Keeps key of the leading request, key is released when response is shared or guard is dropped
+pub struct CoalescingGuard {
+    coalescer: Arc<Coalescer>,
+    key: String,
+    sender: watch::Sender<Option<CachedResponse>>,
+}
+
+impl Coalescer {
+    pub fn new(cfg: &CoalescingConfig) -> Self {
+        Self {
+            vary: cfg.vary(),
+            cfg: cfg.clone(),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns key of the request, or `None` if request can't be coalesced
+    fn key<B>(&self, req: &Request<B>) -> Option<String> {
+        if req.method() != Method::GET || !self.cfg.is_path_coalesced(req.uri().path()) {
+            return None;
+        }
+
+        let mut key = format!("{} {}", req.method(), req.uri());
+        for name in &self.vary {
+            let values: Vec<&str> = req
+                .headers()
+                .get_all(name)
+                .iter()
+                .map(|v| v.to_str().unwrap_or_default())
+                .collect();
+            key.push_str(&format!("|{name}={}", values.join(",")));
+        }
+
+        Some(key)
+    }
+
+    /// Verifies if identical request is in flight already and waits for its response
+    pub async fn check<B>(self: &Arc<Self>, req: &Request<B>) -> Coalescing {
+        let Some(key) = self.key(req) else {
+            return Coalescing::Skipped;
+        };
+
+        let mut receiver = {
+            let mut in_flight = self
+                .in_flight
+                .lock()
+                .expect("unable to lock coalescing state, looks like a BUG");
+            match in_flight.get(&key) {
+                Some(receiver) => receiver.clone(),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(key.clone(), receiver);
+                    return Coalescing::Leader(CoalescingGuard {
+                        coalescer: self.clone(),
+                        key,
+                        sender,
+                    });
+                }
+            }
+        };
+
+        let response = match receiver.wait_for(Option::is_some).await {
+            Ok(response) => response.clone(),
+            // Leading request has been completed without response to share
+            Err(_) => None,
+        };
+        match response {
+            Some(response) => Coalescing::Coalesced(response.into_response(COALESCING_HEADER)),
+            None => Coalescing::Skipped,
+        }
+    }
+}
+
+impl CoalescingGuard {
+    /// Shares response with all identical requests which are waiting for it,
+    /// requests which come after it are passed to targets again
+    pub async fn complete(self, resp: &Response<Full<Bytes>>) {
+        let response = CachedResponse::from_response(resp).await;
+        let _ = self.sender.send(Some(response));
+    }
+}
+
+impl Drop for CoalescingGuard {
+    fn drop(&mut self) {
+        self.coalescer
+            .in_flight
+            .lock()
+            .expect("unable to lock coalescing state, looks like a BUG")
+            .remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coalescer() -> Arc<Coalescer> {
+        let cfg = serde_json::from_str(r#"{"vary": ["accept"]}"#).unwrap();
+        Arc::new(Coalescer::new(&cfg))
+    }
+
+    fn request(method: Method, accept: &str) -> Request<()> {
+        Request::builder()
+            .method(method)
+            .uri("/users?page=1")
+            .header("accept", accept)
+            .body(())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn identical_requests_share_response() {
+        let coalescer = coalescer();
+        let Coalescing::Leader(guard) = coalescer.check(&request(Method::GET, "*/*")).await else {
+            panic!("first request should lead");
+        };
+        assert!(matches!(
+            coalescer.check(&request(Method::POST, "*/*")).await,
+            Coalescing::Skipped
+        ));
+        assert!(matches!(
+            coalescer.check(&request(Method::GET, "text/html")).await,
+            Coalescing::Leader(_)
+        ));
+
+        // Follower is polled first, so it waits for the leader's response
+        let (req, resp) = (
+            request(Method::GET, "*/*"),
+            Response::new(Full::from("shared")),
+        );
+        let (follower, _) = tokio::join!(coalescer.check(&req), guard.complete(&resp));
+        let Coalescing::Coalesced(resp) = follower else {
+            panic!("identical request should get shared response");
+        };
+        assert_eq!(resp.headers()[COALESCING_HEADER], "hit");
+
+        // Key is released after completion
+        assert!(matches!(
+            coalescer.check(&request(Method::GET, "*/*")).await,
+            Coalescing::Leader(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn dropped_leader_releases_followers() {
+        let coalescer = coalescer();
+        let Coalescing::Leader(guard) = coalescer.check(&request(Method::GET, "*/*")).await else {
+            panic!("first request should lead");
+        };
+        let req = request(Method::GET, "*/*");
+        let (follower, _) = tokio::join!(coalescer.check(&req), async { drop(guard) });
+        assert!(matches!(follower, Coalescing::Skipped));
+    }
+}
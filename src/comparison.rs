@@ -0,0 +1,539 @@
+use crate::{
+    config::{capture::ResponseCapture, comparison::ComparisonConfig, target::TargetConfig},
+    handler::ResponsesMap,
+    ranges,
+    streaming::full_bytes,
+};
+use http_body_util::Full;
+use hyper::{body::Bytes, header::HeaderName, Method, Response, StatusCode, Uri};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, LazyLock, Mutex, Weak},
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, info};
+use uuid::Uuid;
+
+/// Length of the body hash prefix in the records, it's enough to tell bodies apart
+const BODY_HASH_PREFIX_LEN: usize = 16;
+
+static REGISTRY: LazyLock<Mutex<Vec<Weak<ComparisonReport>>>> =
+    LazyLock::new(|| Mutex::new(vec![]));
+
+/// Compares responses of the listener's targets with the baseline one and keeps the latest mismatches,
+/// state is shared by all workers of the listener and exposed by the admin API
+#[derive(Debug)]
+pub struct ComparisonReport {
+    listener_id: String,
+    /// ID of the baseline target or path of the baseline capture
+    baseline: String,
+    capture: Option<ResponseCapture>,
+    headers: Vec<HeaderName>,
+    body: bool,
+    max_records: usize,
+    state: Mutex<ReportState>,
+}
+
+#[derive(Debug, Default)]
+struct ReportState {
+    compared: u64,
+    mismatched: u64,
+    /// Counters since the last summary log entry
+    interval_compared: u64,
+    interval_mismatched: BTreeMap<String, u64>,
+    records: VecDeque<MismatchRecord>,
+}
+
+/// Request which responses differ from the baseline one
+#[derive(Serialize, Debug, Clone)]
+pub struct MismatchRecord {
+    time: String,
+    request_id: String,
+    method: String,
+    path: String,
+    targets: Vec<TargetMismatch>,
+}
+
+/// Differences of the single target's response from the baseline one
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TargetMismatch {
+    target: String,
+    differences: Vec<Difference>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Difference {
+    field: String,
+    baseline: String,
+    actual: String,
+}
+
+/// State of the listener's comparison as it's returned by the admin API
+#[derive(Serialize)]
+struct ReportSnapshot<'a> {
+    listener: &'a str,
+    baseline: &'a str,
+    compared: u64,
+    mismatched: u64,
+    records: &'a VecDeque<MismatchRecord>,
+}
+
+impl ComparisonReport {
+    /// Creates report and starts periodic summary logging, it's stopped when report is dropped
+    pub fn new(cfg: &ComparisonConfig, listener_id: String) -> Arc<Self> {
+        let report = Arc::new(Self {
+            listener_id,
+            baseline: cfg
+                .baseline_capture()
+                .map(ResponseCapture::path)
+                .or(cfg.baseline())
+                .unwrap_or_default()
+                .into(),
+            capture: cfg.baseline_capture().cloned(),
+            headers: cfg.headers(),
+            body: cfg.body(),
+            max_records: cfg.max_records(),
+            state: Mutex::new(ReportState::default()),
+        });
+        REGISTRY
+            .lock()
+            .expect("unable to lock comparison registry, looks like a BUG")
+            .push(Arc::downgrade(&report));
+        tokio::spawn(summary_loop(
+            Arc::downgrade(&report),
+            cfg.summary_interval(),
+        ));
+
+        report
+    }
+
+    /// Compares responses of the request's targets with the baseline one, targets which haven't responded
+    /// or have been cancelled aren't compared, as well as requests without baseline response;
+    /// recorded baseline response is looked up by the request's method and path with query
+    pub fn compare(
+        &self,
+        req_id: &Uuid,
+        method: &Method,
+        uri: &Uri,
+        responses: &ResponsesMap,
+        targets: &[TargetConfig],
+    ) {
+        let captured;
+        let baseline = match &self.capture {
+            Some(capture) => {
+                let path = uri.path_and_query().map_or(uri.path(), |p| p.as_str());
+                captured = capture.response(method, path);
+                captured.as_ref()
+            }
+            None => responses
+                .get(&self.baseline)
+                .and_then(|(resp, _)| resp.as_ref()),
+        };
+        let Some(baseline) = baseline else {
+            debug!("{req_id}: comparison skipped, no baseline response");
+            return;
+        };
+
+        let mut compared = false;
+        let mut mismatches = vec![];
+        for id in targets.iter().map(TargetConfig::id) {
+            if self.capture.is_none() && id == self.baseline {
+                continue;
+            }
+            let Some((resp, _)) = responses.get(&id) else {
+                continue;
+            };
+            compared = true;
+            let differences = self.differences(baseline, resp.as_ref());
+            if !differences.is_empty() {
+                mismatches.push(TargetMismatch {
+                    target: id,
+                    differences,
+                });
+            }
+        }
+        if !compared {
+            return;
+        }
+
+        let mut state = self.lock();
+        state.compared += 1;
+        state.interval_compared += 1;
+        if mismatches.is_empty() {
+            return;
+        }
+        debug!(
+            "{req_id}: responses differ from baseline `{}`: {}",
+            self.baseline,
+            mismatches
+                .iter()
+                .map(|m| m.target.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        state.mismatched += 1;
+        for mismatch in &mismatches {
+            *state
+                .interval_mismatched
+                .entry(mismatch.target.clone())
+                .or_default() += 1;
+        }
+        state.records.push_back(MismatchRecord {
+            time: humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            request_id: req_id.to_string(),
+            method: method.to_string(),
+            path: uri.path().into(),
+            targets: mismatches,
+        });
+        while state.records.len() > self.max_records {
+            state.records.pop_front();
+        }
+    }
+
+    fn differences(
+        &self,
+        baseline: &Response<Full<Bytes>>,
+        actual: Option<&Response<Full<Bytes>>>,
+    ) -> Vec<Difference> {
+        let Some(actual) = actual else {
+            return vec![Difference::new(
+                "response",
+                baseline.status().as_str(),
+                "none",
+            )];
+        };
+
+        // Partial response is compared with the same range of the full one,
+        // since a mirror may be requested for the full body instead of the range
+        let range = match (
+            ranges::content_range(baseline),
+            ranges::content_range(actual),
+        ) {
+            (Some(range), None) if actual.status() == StatusCode::OK => Some(range),
+            (None, Some(range)) if baseline.status() == StatusCode::OK => Some(range),
+            _ => None,
+        };
+        let mut differences = vec![];
+        if baseline.status() != actual.status() && range.is_none() {
+            differences.push(Difference::new(
+                "status",
+                baseline.status().as_str(),
+                actual.status().as_str(),
+            ));
+        }
+        for name in &self.headers {
+            let baseline = header_values(baseline, name);
+            let actual = header_values(actual, name);
+            if baseline != actual {
+                differences.push(Difference::new(format!("header:{name}"), baseline, actual));
+            }
+        }
+        if self.body {
+            let partial = |resp: &Response<Full<Bytes>>| match range {
+                Some(range) if resp.status() == StatusCode::OK => {
+                    ranges::slice(&full_bytes(resp.body()), range)
+                }
+                _ => full_bytes(resp.body()),
+            };
+            let baseline = partial(baseline);
+            let actual = partial(actual);
+            if baseline != actual {
+                differences.push(Difference::new(
+                    "body",
+                    body_summary(&baseline),
+                    body_summary(&actual),
+                ));
+            }
+        }
+
+        differences
+    }
+
+    /// Writes summary of the comparisons since the previous summary, nothing is written if there were none
+    fn log_summary(&self) {
+        let mut state = self.lock();
+        if state.interval_compared == 0 {
+            return;
+        }
+        let mismatched = state
+            .interval_mismatched
+            .iter()
+            .map(|(target, count)| format!("{target}={count}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        info!(
+            listener = self.listener_id,
+            baseline = self.baseline,
+            compared = state.interval_compared,
+            mismatched = %mismatched,
+            "comparison summary"
+        );
+        state.interval_compared = 0;
+        state.interval_mismatched.clear();
+    }
+
+    fn snapshot(&self) -> serde_json::Value {
+        let state = self.lock();
+        serde_json::to_value(ReportSnapshot {
+            listener: &self.listener_id,
+            baseline: &self.baseline,
+            compared: state.compared,
+            mismatched: state.mismatched,
+            records: &state.records,
+        })
+        .expect("unable to serialize comparison report, looks like a BUG")
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ReportState> {
+        self.state
+            .lock()
+            .expect("unable to lock comparison state, looks like a BUG")
+    }
+}
+
+impl Difference {
+    fn new(
+        field: impl Into<String>,
+        baseline: impl Into<String>,
+        actual: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            baseline: baseline.into(),
+            actual: actual.into(),
+        }
+    }
+}
+
+async fn summary_loop(report: Weak<ComparisonReport>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    // The first tick is immediate
+    ticks.tick().await;
+    loop {
+        ticks.tick().await;
+        match report.upgrade() {
+            Some(report) => report.log_summary(),
+            None => break,
+        }
+    }
+}
+
+/// Values of the header joined by comma, empty if there is no such header
+fn header_values(resp: &Response<Full<Bytes>>, name: &HeaderName) -> String {
+    resp.headers()
+        .get_all(name)
+        .iter()
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Bodies aren't kept in the records since they may be huge or sensitive, their length and hash are kept instead
+fn body_summary(body: &Bytes) -> String {
+    let sha256 = format!("{:x}", Sha256::digest(body));
+    format!(
+        "{} bytes, sha256:{}",
+        body.len(),
+        &sha256[..BODY_HASH_PREFIX_LEN]
+    )
+}
+
+/// Returns state of all listeners' comparisons with the kept mismatch records as JSON
+pub fn status() -> String {
+    let reports: Vec<serde_json::Value> = reports().iter().map(|r| r.snapshot()).collect();
+    serde_json::Value::Array(reports).to_string()
+}
+
+/// Drops all kept mismatch records and resets counters
+pub fn clear() {
+    for report in reports() {
+        let mut state = report.lock();
+        *state = ReportState::default();
+    }
+}
+
+fn reports() -> Vec<Arc<ComparisonReport>> {
+    let mut registry = REGISTRY
+        .lock()
+        .expect("unable to lock comparison registry, looks like a BUG");
+    registry.retain(|r| r.strong_count() > 0);
+    registry.iter().filter_map(Weak::upgrade).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::test_context::get_test_ctx;
+    use std::collections::HashMap;
+
+    fn response(status: u16, content_type: &str, body: &'static str) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(status)
+            .header("content-type", content_type)
+            .body(Full::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn mismatches_are_recorded() {
+        let cfg: ComparisonConfig = serde_json::from_str(
+            r#"{"baseline": "primary", "headers": ["content-type"], "max_records": 2}"#,
+        )
+        .unwrap();
+        let targets: Vec<TargetConfig> = ["primary", "shadow", "cancelled"]
+            .iter()
+            .map(|id| {
+                serde_json::from_str(&format!(r#"{{"id": "{id}", "url": "http://{id}/"}}"#))
+                    .unwrap()
+            })
+            .collect();
+        let report = ComparisonReport::new(&cfg, "comparison".into());
+        let ctx = get_test_ctx();
+        let mut responses: ResponsesMap = HashMap::new();
+        let req_id = Uuid::new_v4();
+
+        responses.insert(
+            "primary".into(),
+            (Some(response(200, "text/plain", "a")), ctx),
+        );
+        responses.insert(
+            "shadow".into(),
+            (Some(response(200, "text/plain", "a")), ctx),
+        );
+        report.compare(
+            &req_id,
+            &Method::GET,
+            &Uri::from_static("/same"),
+            &responses,
+            &targets,
+        );
+        assert_eq!(report.lock().compared, 1);
+        assert!(report.lock().records.is_empty());
+
+        responses.insert(
+            "shadow".into(),
+            (Some(response(500, "text/html", "b")), ctx),
+        );
+        for path in ["/first", "/second", "/third"] {
+            let uri = Uri::from_static(path);
+            report.compare(&req_id, &Method::POST, &uri, &responses, &targets);
+        }
+        responses.insert("shadow".into(), (None, ctx));
+        report.compare(
+            &req_id,
+            &Method::GET,
+            &Uri::from_static("/none"),
+            &responses,
+            &targets,
+        );
+        // No baseline response, nothing to compare with
+        responses.insert("primary".into(), (None, ctx));
+        report.compare(
+            &req_id,
+            &Method::GET,
+            &Uri::from_static("/skipped"),
+            &responses,
+            &targets,
+        );
+
+        let state = report.lock();
+        assert_eq!((state.compared, state.mismatched), (5, 4));
+        assert_eq!(state.interval_mismatched["shadow"], 4);
+        let paths: Vec<&str> = state.records.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["/third", "/none"]);
+        assert_eq!(
+            state.records[0].targets,
+            [TargetMismatch {
+                target: "shadow".into(),
+                differences: vec![
+                    Difference::new("status", "200", "500"),
+                    Difference::new("header:content-type", "text/plain", "text/html"),
+                    Difference::new(
+                        "body",
+                        "1 bytes, sha256:ca978112ca1bbdca",
+                        "1 bytes, sha256:3e23e8160039594a"
+                    ),
+                ],
+            }]
+        );
+        assert_eq!(
+            state.records[1].targets[0].differences,
+            [Difference::new("response", "200", "none")]
+        );
+    }
+
+    #[tokio::test]
+    async fn captured_baseline() {
+        let cfg: ComparisonConfig = serde_json::from_str(
+            r#"{"baseline_capture": "tests/captures/baseline.jsonl", "headers": ["content-type"]}"#,
+        )
+        .unwrap();
+        let targets: Vec<TargetConfig> =
+            vec![serde_json::from_str(r#"{"id": "v2", "url": "http://v2/"}"#).unwrap()];
+        let report = ComparisonReport::new(&cfg, "replay".into());
+        let ctx = get_test_ctx();
+        let mut responses: ResponsesMap = HashMap::new();
+        let req_id = Uuid::new_v4();
+
+        responses.insert(
+            "v2".into(),
+            (Some(response(200, "application/json", r#"{"id":1}"#)), ctx),
+        );
+        for uri in ["/items?id=1", "/items?id=2", "/items?id=3"] {
+            let uri = Uri::from_static(uri);
+            report.compare(&req_id, &Method::GET, &uri, &responses, &targets);
+        }
+
+        let state = report.lock();
+        assert_eq!((state.compared, state.mismatched), (2, 1));
+        assert_eq!(state.records[0].path, "/items");
+        assert_eq!(
+            state.records[0].targets[0].differences[0],
+            Difference::new("status", "404", "200")
+        );
+        assert_eq!(report.baseline, "tests/captures/baseline.jsonl");
+    }
+
+    #[tokio::test]
+    async fn partial_response_is_compared_with_full_one() {
+        let cfg: ComparisonConfig = serde_json::from_str(r#"{"baseline": "primary"}"#).unwrap();
+        let targets: Vec<TargetConfig> = ["primary", "mirror"]
+            .iter()
+            .map(|id| {
+                serde_json::from_str(&format!(r#"{{"id": "{id}", "url": "http://{id}/"}}"#))
+                    .unwrap()
+            })
+            .collect();
+        let report = ComparisonReport::new(&cfg, "ranges".into());
+        let ctx = get_test_ctx();
+        let mut responses: ResponsesMap = HashMap::new();
+        let req_id = Uuid::new_v4();
+        let partial = Response::builder()
+            .status(206)
+            .header("content-range", "bytes 2-4/10")
+            .body(Full::from("234"))
+            .unwrap();
+
+        responses.insert("primary".into(), (Some(partial), ctx));
+        for body in ["0123456789", "01xxx56789"] {
+            responses.insert(
+                "mirror".into(),
+                (Some(response(200, "text/plain", body)), ctx),
+            );
+            report.compare(
+                &req_id,
+                &Method::GET,
+                &Uri::from_static("/file"),
+                &responses,
+                &targets,
+            );
+        }
+
+        let state = report.lock();
+        assert_eq!((state.compared, state.mismatched), (2, 1));
+        assert_eq!(state.records[0].targets[0].differences.len(), 1);
+        assert_eq!(state.records[0].targets[0].differences[0].field, "body");
+    }
+}
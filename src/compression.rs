@@ -0,0 +1,290 @@
+use crate::config::compression::{ContentEncoding, ListenerCompressionConfig};
+use flate2::{
+    read::{DeflateDecoder, GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::Bytes,
+    header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY},
+    HeaderMap, Response, StatusCode,
+};
+use std::io::{self, Read, Write};
+use tracing::warn;
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW_SIZE: u32 = 22;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encoding of the target's response which has been decompressed, kept in the response extensions
+/// to compress it back before sending to the requester
+#[derive(Clone, Copy, Debug)]
+pub struct Decompressed(pub ContentEncoding);
+
+/// Compresses data with specified encoding, `deflate` means zlib format as HTTP requires
+pub fn encode(encoding: ContentEncoding, data: &[u8]) -> io::Result<Bytes> {
+    let data = match encoding {
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        ContentEncoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        ContentEncoding::Br => {
+            let mut encoder = brotli::CompressorWriter::new(
+                vec![],
+                BROTLI_BUFFER_SIZE,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW_SIZE,
+            );
+            encoder.write_all(data)?;
+            encoder.into_inner()
+        }
+        ContentEncoding::Zstd => zstd::encode_all(data, ZSTD_LEVEL)?,
+    };
+
+    Ok(data.into())
+}
+
+/// Decompresses data with specified encoding,
+/// raw deflate stream is accepted as well since some servers send it instead of zlib one
+pub fn decode(encoding: ContentEncoding, data: &[u8]) -> io::Result<Bytes> {
+    decode_at_most(encoding, data, u64::MAX).map(Bytes::from)
+}
+
+/// Decompresses data like `decode`, but stops after `limit` bytes of the decompressed data
+fn decode_at_most(encoding: ContentEncoding, data: &[u8], limit: u64) -> io::Result<Vec<u8>> {
+    let mut decoded = vec![];
+    match encoding {
+        ContentEncoding::Gzip => GzDecoder::new(data).take(limit).read_to_end(&mut decoded)?,
+        ContentEncoding::Deflate => {
+            match ZlibDecoder::new(data).take(limit).read_to_end(&mut decoded) {
+                Ok(size) => size,
+                Err(_) => {
+                    decoded.clear();
+                    DeflateDecoder::new(data)
+                        .take(limit)
+                        .read_to_end(&mut decoded)?
+                }
+            }
+        }
+        ContentEncoding::Br => brotli::Decompressor::new(data, BROTLI_BUFFER_SIZE)
+            .take(limit)
+            .read_to_end(&mut decoded)?,
+        ContentEncoding::Zstd => zstd::Decoder::new(data)?
+            .take(limit)
+            .read_to_end(&mut decoded)?,
+    };
+
+    Ok(decoded)
+}
+
+/// Decompresses request body to inspect it, `None` if body isn't compressed with supported encoding;
+/// error is the status to reject request with: body is too large after decompression or it's broken
+pub fn decompress_request(
+    headers: &HeaderMap,
+    body: &Bytes,
+    max_size: usize,
+) -> Result<Option<(Bytes, ContentEncoding)>, (StatusCode, String)> {
+    let Some(encoding) = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ContentEncoding::from_header)
+    else {
+        return Ok(None);
+    };
+    if body.is_empty() {
+        return Ok(None);
+    }
+
+    // One extra byte is read to detect body above the limit without decompressing it entirely
+    match decode_at_most(encoding, body, max_size as u64 + 1) {
+        Ok(decoded) if decoded.len() > max_size => Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("decompressed `{encoding}` body is larger than {max_size} bytes"),
+        )),
+        Ok(decoded) => Ok(Some((decoded.into(), encoding))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("broken `{encoding}` body: {e}"),
+        )),
+    }
+}
+
+/// Verifies if the `Accept-Encoding` request header allows specified encoding,
+/// explicit value of the encoding takes precedence over the `*` one
+pub fn is_accepted(headers: &HeaderMap, encoding: ContentEncoding) -> bool {
+    let name = encoding.to_string();
+    let mut exact = None;
+    let mut wildcard = None;
+    for item in headers
+        .get_all(ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+    {
+        let mut params = item.split(';').map(str::trim);
+        let coding = params.next().unwrap_or_default();
+        let quality = params
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if coding.eq_ignore_ascii_case(&name) {
+            exact = Some(quality);
+        } else if coding == "*" {
+            wildcard = Some(quality);
+        }
+    }
+
+    exact.or(wildcard).is_some_and(|quality| quality > 0.0)
+}
+
+/// Decompresses body if it's compressed with supported encoding and updates headers accordingly,
+/// returns body as is if it isn't compressed or can't be decompressed
+pub fn decompress(headers: &mut HeaderMap, body: Bytes) -> (Bytes, Option<ContentEncoding>) {
+    let Some(encoding) = headers
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ContentEncoding::from_header)
+    else {
+        return (body, None);
+    };
+
+    match decode(encoding, &body) {
+        Ok(decoded) => {
+            headers.remove(CONTENT_ENCODING);
+            headers.remove(CONTENT_LENGTH);
+            (decoded, Some(encoding))
+        }
+        Err(e) => {
+            warn!(error = %e, "unable to decompress `{encoding}` response body, pass it as is");
+            (body, None)
+        }
+    }
+}
+
+/// Compresses body of the decompressed target's response back if the requester accepts its original encoding
+pub async fn recompress_response(
+    resp: Response<Full<Bytes>>,
+    req_headers: &HeaderMap,
+) -> Response<Full<Bytes>> {
+    let Some(Decompressed(encoding)) = resp.extensions().get::<Decompressed>().copied() else {
+        return resp;
+    };
+    if !is_accepted(req_headers, encoding) {
+        return resp;
+    }
+
+    let (mut parts, body) = resp.into_parts();
+    let body = body
+        .collect()
+        .await
+        .expect("infallible body, looks like a BUG")
+        .to_bytes();
+    match encode(encoding, &body) {
+        Ok(encoded) => {
+            parts.extensions.remove::<Decompressed>();
+            parts.headers.remove(CONTENT_LENGTH);
+            parts
+                .headers
+                .insert(CONTENT_ENCODING, encoding.header_value());
+            Response::from_parts(parts, Full::from(encoded))
+        }
+        Err(e) => {
+            warn!(error = %e, "unable to compress response body with `{encoding}`, send it uncompressed");
+            Response::from_parts(parts, Full::from(body))
+        }
+    }
+}
+
+/// Compresses the final response with the first of configured encodings which is accepted by the requester,
+/// responses which are compressed already, too small or have not compressible content type are sent as is
+pub async fn compress_response(
+    resp: Response<Full<Bytes>>,
+    req_headers: &HeaderMap,
+    cfg: &ListenerCompressionConfig,
+) -> Response<Full<Bytes>> {
+    let compressible = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| cfg.is_compressible(content_type));
+    if !compressible
+        || resp.headers().contains_key(CONTENT_ENCODING)
+        || resp.status() == StatusCode::NO_CONTENT
+        || resp.status() == StatusCode::NOT_MODIFIED
+    {
+        return resp;
+    }
+
+    let (mut parts, body) = resp.into_parts();
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+    let body = body
+        .collect()
+        .await
+        .expect("infallible body, looks like a BUG")
+        .to_bytes();
+    let encoding = cfg
+        .encodings()
+        .iter()
+        .find(|encoding| is_accepted(req_headers, **encoding));
+    let Some(encoding) = encoding.filter(|_| body.len() >= cfg.min_size()) else {
+        return Response::from_parts(parts, Full::from(body));
+    };
+
+    match encode(*encoding, &body) {
+        Ok(encoded) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            parts
+                .headers
+                .insert(CONTENT_ENCODING, encoding.header_value());
+            Response::from_parts(parts, Full::from(encoded))
+        }
+        Err(e) => {
+            warn!(error = %e, "unable to compress response body with `{encoding}`, send it uncompressed");
+            Response::from_parts(parts, Full::from(body))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode() {
+        let data = "some data to compress ".repeat(100);
+        for encoding in [
+            ContentEncoding::Gzip,
+            ContentEncoding::Deflate,
+            ContentEncoding::Br,
+            ContentEncoding::Zstd,
+        ] {
+            let encoded = encode(encoding, data.as_bytes()).unwrap();
+            assert!(encoded.len() < data.len());
+            assert_eq!(decode(encoding, &encoded).unwrap(), data.as_bytes());
+        }
+    }
+
+    #[test]
+    fn accept_encoding() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_accepted(&headers, ContentEncoding::Gzip));
+
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("br, GZIP;q=0.8"));
+        assert!(is_accepted(&headers, ContentEncoding::Gzip));
+        assert!(!is_accepted(&headers, ContentEncoding::Deflate));
+
+        headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("*, gzip;q=0"));
+        assert!(!is_accepted(&headers, ContentEncoding::Gzip));
+        assert!(is_accepted(&headers, ContentEncoding::Deflate));
+    }
+}
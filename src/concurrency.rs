@@ -0,0 +1,185 @@
+use crate::{
+    config::{concurrency::ConcurrencyConfig, target::TargetPriority},
+    metrics::{self, MetricsSource},
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::oneshot;
+
+/// Limits target requests in progress of the listener, all its workers share the same limiter;
+/// free slot is handed over to the waiting high priority request first, then to the low priority one
+#[derive(Debug)]
+pub struct ConcurrencyLimiter {
+    listener_id: String,
+    limit: usize,
+    low_priority_queue_timeout: Option<Duration>,
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    available: usize,
+    high: VecDeque<oneshot::Sender<ConcurrencyPermit>>,
+    low: VecDeque<oneshot::Sender<ConcurrencyPermit>>,
+}
+
+/// Slot of the target request in progress, it's passed to the next waiting request when dropped
+#[derive(Debug)]
+pub struct ConcurrencyPermit(Option<Arc<ConcurrencyLimiter>>);
+
+impl ConcurrencyLimiter {
+    pub fn new(cfg: &ConcurrencyConfig, listener_id: String) -> Arc<Self> {
+        let limiter = Arc::new(Self {
+            listener_id,
+            limit: cfg.limit(),
+            low_priority_queue_timeout: cfg.low_priority_queue_timeout(),
+            state: Mutex::new(LimiterState {
+                available: cfg.limit(),
+                high: VecDeque::new(),
+                low: VecDeque::new(),
+            }),
+        });
+        metrics::register(limiter.clone());
+
+        limiter
+    }
+
+    /// Waits for a free slot, returns `None` if low priority request is shed
+    /// because it has waited longer than the queue timeout
+    pub async fn acquire(self: &Arc<Self>, priority: TargetPriority) -> Option<ConcurrencyPermit> {
+        let receiver = {
+            let mut state = self.lock();
+            // There are no waiting requests while there are free slots
+            if state.available > 0 {
+                state.available -= 1;
+                return Some(ConcurrencyPermit(Some(self.clone())));
+            }
+            let (sender, receiver) = oneshot::channel();
+            match priority {
+                TargetPriority::High => state.high.push_back(sender),
+                TargetPriority::Low => state.low.push_back(sender),
+            }
+            receiver
+        };
+        // Permit which is sent to the abandoned request is dropped, so it goes to the next one
+        match (priority, self.low_priority_queue_timeout) {
+            (TargetPriority::Low, Some(timeout)) => tokio::time::timeout(timeout, receiver)
+                .await
+                .ok()
+                .and_then(Result::ok),
+            _ => receiver.await.ok(),
+        }
+    }
+
+    /// Hands slot over to the first waiting request or returns it to the pool
+    fn release(self: &Arc<Self>) {
+        let mut state = self.lock();
+        let mut permit = ConcurrencyPermit(Some(self.clone()));
+        while let Some(sender) = state.high.pop_front().or_else(|| state.low.pop_front()) {
+            match sender.send(permit) {
+                Ok(()) => return,
+                Err(returned) => permit = returned,
+            }
+        }
+        state.available += 1;
+        // Returned permit mustn't release the slot once again
+        permit.0 = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state
+            .lock()
+            .expect("unable to lock concurrency limiter state, looks like a BUG")
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.0.take() {
+            limiter.release();
+        }
+    }
+}
+
+impl MetricsSource for ConcurrencyLimiter {
+    fn write_metrics(&self, out: &mut String) {
+        let state = self.lock();
+        metrics::gauge(
+            out,
+            "target_requests_in_progress",
+            [("listener", self.listener_id.as_str())],
+            (self.limit - state.available) as f64,
+        );
+        for (priority, queue) in [
+            (TargetPriority::High, &state.high),
+            (TargetPriority::Low, &state.low),
+        ] {
+            metrics::gauge(
+                out,
+                "target_requests_queued",
+                [
+                    ("listener", self.listener_id.as_str()),
+                    ("priority", priority.to_string().as_str()),
+                ],
+                queue.iter().filter(|sender| !sender.is_closed()).count() as f64,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(cfg: &str) -> Arc<ConcurrencyLimiter> {
+        let cfg: ConcurrencyConfig = serde_json::from_str(cfg).unwrap();
+        ConcurrencyLimiter::new(&cfg, "listener".into())
+    }
+
+    #[tokio::test]
+    async fn high_priority_goes_first() {
+        let limiter = limiter(r#"{"limit": 1}"#);
+        let permit = limiter.acquire(TargetPriority::High).await.unwrap();
+
+        let low = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(TargetPriority::Low).await.is_some() }
+        });
+        tokio::task::yield_now().await;
+        let high = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                let permit = limiter.acquire(TargetPriority::High).await;
+                // Low priority request is still waiting while this one is in progress
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                permit.is_some()
+            }
+        });
+        tokio::task::yield_now().await;
+        // Slot goes to the high priority request which has come later
+        drop(permit);
+        {
+            let state = limiter.lock();
+            assert_eq!((state.available, state.low.len()), (0, 1));
+        }
+
+        assert!(high.await.unwrap());
+        assert!(low.await.unwrap());
+        assert_eq!(limiter.lock().available, 1);
+    }
+
+    #[tokio::test]
+    async fn low_priority_is_shed() {
+        let limiter = limiter(r#"{"limit": 1, "low_priority_queue_timeout": "10ms"}"#);
+        let permit = limiter.acquire(TargetPriority::High).await.unwrap();
+        assert!(limiter.acquire(TargetPriority::Low).await.is_none());
+
+        // Slot isn't lost because of the abandoned request
+        drop(permit);
+        assert!(limiter.acquire(TargetPriority::Low).await.is_some());
+        assert_eq!(limiter.lock().available, 1);
+    }
+}
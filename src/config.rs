@@ -1,21 +1,103 @@
+pub mod acme;
+pub mod audit;
+pub mod body_codec;
+pub mod body_logging;
+pub mod body_transform;
+pub mod buffer_pool;
+pub mod cache;
+pub mod canary;
+pub mod capture;
+pub mod coalescing;
+pub mod comparison;
+pub mod compression;
+pub mod concurrency;
+pub mod connections;
+pub mod cookies;
+pub mod cors;
+pub mod deduplication;
+pub mod defaults;
+pub mod deprecation;
+pub mod diff;
+pub mod discovery;
+pub mod dns;
+pub mod enrichment;
+pub mod expressions;
+pub mod failure;
+pub mod faults;
+pub mod forward_proxy;
+pub mod forwarding;
+pub mod header_limits;
 pub mod headers;
+pub mod hooks;
+pub mod labels;
 pub mod listener;
+pub mod logging;
+pub mod normalization;
+pub mod openapi;
+pub mod pipeline;
+pub mod plugins;
+pub mod protobuf;
+pub mod provenance;
+pub mod proxy_headers;
+pub mod query;
+pub mod ranges;
 pub mod response;
+pub mod retry_queue;
+pub mod runtime;
+pub mod sampling;
+pub mod script;
+pub mod security_headers;
+pub mod server_tls;
+pub mod slo;
+pub mod socket;
+pub mod static_routes;
 pub mod target;
+pub mod templates;
+pub mod tenants;
+pub mod websocket;
 
-use crate::context::Context;
+use crate::{
+    context::{secrets::SECRET_CTX_PREFIX, Context},
+    redaction,
+};
+use defaults::DefaultsConfig;
+use diff::ConfigDiff;
+use expressions::Expressions;
+use figment::{
+    providers::{Env, Format, Json, Serialized, Toml, Yaml},
+    value::{Dict, Tag, Value},
+    Figment,
+};
+use hooks::HooksConfig;
+use hyper::header::HeaderName;
 use listener::ListenerConfig;
-use serde::Deserialize;
-use shellexpand::env_with_context_no_errors;
+use logging::LoggingConfig;
+use provenance::{Provenance, Source};
+use schemars::{schema::Schema, schema_for, JsonSchema};
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use server_tls::{ServerProtocol, ServerTlsConfig};
+use shellexpand::{env_with_context, env_with_context_no_errors};
 use std::{
-    fs::File,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs::{self, File},
     io,
     io::{BufReader, Read},
-    sync::OnceLock,
+    net::SocketAddr,
+    path::{Path, PathBuf},
 };
-use tracing::{debug, info};
+use templates::{TargetTemplate, TargetTemplates, TEMPLATE_KEY};
+use tracing::{debug, info, warn};
 
-static APP_CONFIG: OnceLock<AppConfig> = OnceLock::new();
+/// Prefix of environment variables which override config values,
+/// like `HTTP_DRAGONFLY__LISTENERS__0__TIMEOUT=5s`
+const ENV_OVERRIDE_PREFIX: &str = "HTTP_DRAGONFLY__";
+/// Prefix of context variables which are populated at request time (except secrets),
+/// so they are never resolved during config loading
+const RUNTIME_CONTEXT_PREFIX: &str = "CTX_";
 
 #[derive(thiserror::Error)]
 pub enum ConfigError {
@@ -24,13 +106,256 @@ pub enum ConfigError {
         #[from]
         cause: io::Error,
     },
-    #[error("unable to parse config: {}", .cause)]
+    #[error("unable to parse config: {}", parse_error_message(.path, .cause, .location.as_ref()))]
     ParseConfigFile {
-        #[from]
-        cause: serde_yaml::Error,
+        path: String,
+        cause: Box<figment::Error>,
+        /// Tag of the invalid value, it refers to the source of the value
+        tag: Option<Tag>,
+        location: Option<serde_yaml::Location>,
     },
     #[error("invalid config: {}", .cause)]
     ValidateConfig { cause: String },
+    #[error("{}: {}", .file, .cause)]
+    InFile {
+        file: String,
+        cause: Box<ConfigError>,
+    },
+}
+
+impl ConfigError {
+    /// Wraps error with the name of the file which caused it
+    fn in_file(self, file: &Path) -> Self {
+        ConfigError::InFile {
+            file: file.display().to_string(),
+            cause: Box::new(self),
+        }
+    }
+}
+
+impl ConfigError {
+    /// Wraps error of the value deserialization, path of the error is formatted like `listeners[0].targets[1]`,
+    /// and tag of the invalid value is kept to find its source later
+    fn parse(cause: figment::Error, value: &Value) -> Self {
+        let mut path = String::new();
+        let mut node = Some(value);
+        for key in &cause.path {
+            node = match node {
+                Some(Value::Array(_, items)) => {
+                    path.push_str(&format!("[{key}]"));
+                    key.parse::<usize>().ok().and_then(|i| items.get(i))
+                }
+                Some(Value::Dict(_, dict)) => {
+                    push_path_key(&mut path, key);
+                    dict.get(key)
+                }
+                _ => {
+                    push_path_segment(&mut path, key);
+                    None
+                }
+            };
+        }
+
+        ConfigError::ParseConfigFile {
+            path,
+            cause: Box::new(cause),
+            tag: node.map(Value::tag),
+            location: None,
+        }
+    }
+
+    /// Adds line and column of the invalid value if it comes from YAML content with known tag
+    fn locate(self, contents: &HashMap<Tag, String>) -> Self {
+        match self {
+            ConfigError::ParseConfigFile {
+                path,
+                cause,
+                tag: Some(tag),
+                location: None,
+            } => {
+                let location = contents
+                    .get(&tag)
+                    .and_then(|content| locate_yaml_value(content, &cause.path));
+                ConfigError::ParseConfigFile {
+                    path,
+                    cause,
+                    tag: Some(tag),
+                    location,
+                }
+            }
+            ConfigError::InFile { file, cause } => ConfigError::InFile {
+                file,
+                cause: Box::new(cause.locate(contents)),
+            },
+            e => e,
+        }
+    }
+}
+
+impl From<figment::Error> for ConfigError {
+    fn from(cause: figment::Error) -> Self {
+        let mut path = String::new();
+        for key in &cause.path {
+            push_path_segment(&mut path, key);
+        }
+
+        Self::ParseConfigFile {
+            path,
+            cause: Box::new(cause),
+            tag: None,
+            location: None,
+        }
+    }
+}
+
+/// Appends key of the dictionary to the formatted error path
+fn push_path_key(path: &mut String, key: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(key);
+}
+
+/// Appends segment of unknown container to the formatted error path, numeric ones are treated as indexes
+fn push_path_segment(path: &mut String, segment: &str) {
+    if segment.parse::<usize>().is_ok() {
+        path.push_str(&format!("[{segment}]"));
+    } else {
+        push_path_key(path, segment);
+    }
+}
+
+/// Formats parse error as `path.to[0].key: message at line L column C`, without figment's profile and source details
+fn parse_error_message(
+    path: &str,
+    e: &figment::Error,
+    location: Option<&serde_yaml::Location>,
+) -> String {
+    let mut message = if path.is_empty() {
+        e.kind.to_string()
+    } else {
+        format!("{path}: {}", e.kind)
+    };
+    if let Some(location) = location {
+        message.push_str(&format!(
+            " at line {} column {}",
+            location.line(),
+            location.column()
+        ));
+    }
+
+    message
+}
+
+/// Finds position of the value in YAML content by the path of keys (or array indexes)
+fn locate_yaml_value(content: &str, path: &[String]) -> Option<serde_yaml::Location> {
+    YamlLocator(path)
+        .deserialize(serde_yaml::Deserializer::from_str(content))
+        .err()
+        .and_then(|e| e.location())
+}
+
+/// Walks YAML document down to the value by its path and fails there,
+/// so the deserializer reports position of the value in the error
+struct YamlLocator<'p>(&'p [String]);
+
+/// Visitor which rejects any value
+struct RejectAny;
+
+impl<'de> Visitor<'de> for RejectAny {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("nothing")
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for YamlLocator<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        if self.0.is_empty() {
+            deserializer.deserialize_any(RejectAny)
+        } else {
+            deserializer.deserialize_any(self)
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for YamlLocator<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let Some((key, rest)) = self.0.split_first() else {
+            return Ok(());
+        };
+        while let Some(next) = map.next_key::<String>()? {
+            if next == *key {
+                map.next_value_seed(YamlLocator(rest))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let Some((index, rest)) = self.0.split_first() else {
+            return Ok(());
+        };
+        let index = index.parse::<usize>().ok();
+        for i in 0.. {
+            let next = if Some(i) == index {
+                seq.next_element_seed(YamlLocator(rest))?
+            } else {
+                seq.next_element::<IgnoredAny>()?.map(|_| ())
+            };
+            if next.is_none() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Path goes deeper than the document, so the value isn't found
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+}
+
+/// Sockets can't be bound both if they have the same port and address, or one of them listens on all addresses
+/// of the other's family; `[::]` is dual-stack by default, so it covers IPv4 addresses too
+fn sockets_conflict(socket: SocketAddr, other: SocketAddr) -> bool {
+    let covers = |socket: SocketAddr, other: SocketAddr| {
+        socket.ip().is_unspecified() && (socket.is_ipv4() == other.is_ipv4() || socket.is_ipv6())
+    };
+
+    socket.port() == other.port()
+        && (socket.ip() == other.ip() || covers(socket, other) || covers(other, socket))
 }
 
 impl std::fmt::Debug for ConfigError {
@@ -41,43 +366,756 @@ impl std::fmt::Debug for ConfigError {
 
 pub trait ConfigValidator {
     fn validate(&self) -> Result<(), ConfigError>;
+
+    /// Returns suspicious settings of the valid config, they're reported without refusing the config
+    fn lint(&self) -> Vec<ConfigWarning> {
+        vec![]
+    }
+}
+
+/// Suspicious but valid setting, like a condition which can never match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigWarning(String);
+
+impl ConfigWarning {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self(message.into())
+    }
+
+    /// Prefixes message with the scope of the setting, like `listener `main``
+    fn within(self, scope: &str) -> Self {
+        Self(format!("{scope}: {}", self.0))
+    }
+}
+
+impl std::fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
 }
 
-#[derive(Deserialize, Debug)]
+/// Where to load config from
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigSource {
+    /// Single config file, may include other files
+    File(String),
+    /// All `*.yaml`/`*.yml`/`*.toml`/`*.json` files of the folder
+    Dir(String),
+}
+
+impl From<String> for ConfigSource {
+    fn from(value: String) -> Self {
+        ConfigSource::File(value)
+    }
+}
+
+impl From<&str> for ConfigSource {
+    fn from(value: &str) -> Self {
+        ConfigSource::File(value.into())
+    }
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::File(path) => write!(f, "{path}"),
+            ConfigSource::Dir(path) => write!(f, "{path}/*"),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AppConfig {
+    #[serde(default)]
     listeners: Vec<ListenerConfig>,
+    #[serde(default = "AppConfig::default_sensitive_headers")]
+    sensitive_headers: Vec<String>,
+    #[serde(default)]
+    hooks: HooksConfig,
+    #[serde(default)]
+    logging: LoggingConfig,
+    /// Listeners as they're loaded, with templates and defaults applied, to compare configs
+    #[serde(skip)]
+    #[schemars(skip)]
+    raw_listeners: Vec<Value>,
+    /// Sources of the loaded values, to dump effective config
+    #[serde(skip)]
+    #[schemars(skip)]
+    provenance: Provenance,
+}
+
+/// Format of the config file, detected by file extension or content type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+/// State shared between all files loaded as a part of a single config
+#[derive(Debug, Default)]
+struct LoadState {
+    loaded: HashSet<PathBuf>,
+    strict_env: bool,
+    /// Level of the included file, top level files have zero
+    depth: usize,
+    provenance: Provenance,
+    /// Expanded content of the loaded YAML files by the tag of their values, to locate invalid values
+    contents: HashMap<Tag, String>,
+}
+
+impl ConfigFormat {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            "json" => Some(ConfigFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Detects format by media type, parameters like `charset` are ignored
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let media_type = content_type.split(';').next()?.trim();
+        match media_type.to_ascii_lowercase().as_str() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => {
+                Some(ConfigFormat::Yaml)
+            }
+            "application/toml" | "text/toml" => Some(ConfigFormat::Toml),
+            "application/json" => Some(ConfigFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for AppConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AppConfig")
+            .field("listeners", &self.listeners)
+            .field("sensitive_headers", &self.sensitive_headers)
+            .field("hooks", &self.hooks)
+            .field("logging", &self.logging)
+            .finish()
+    }
 }
 
 impl AppConfig {
-    pub fn new<'a>(filename: String, ctx: &'a Context<'a>) -> Result<&'a AppConfig, ConfigError> {
-        let config = AppConfig::from_file(&filename, ctx)?;
-        Ok(APP_CONFIG.get_or_init(|| config))
+    /// Loads config from the source,
+    /// in `strict_env` mode any unresolved non-context variable reference is an error
+    pub fn new(
+        source: impl Into<ConfigSource>,
+        ctx: &Context,
+        strict_env: bool,
+    ) -> Result<AppConfig, ConfigError> {
+        let mut state = LoadState {
+            strict_env,
+            ..Default::default()
+        };
+        let config = match source.into() {
+            ConfigSource::File(filename) => AppConfig::from_file(&filename, ctx, &mut state),
+            ConfigSource::Dir(dirname) => AppConfig::from_dir(&dirname, ctx, &mut state),
+        }?;
+        redaction::set_sensitive_headers(config.sensitive_headers());
+
+        Ok(config)
+    }
+
+    /// Loads candidate config from the content, like the config file but without environment overrides;
+    /// relative `include` patterns are resolved against the current folder
+    pub fn from_content(
+        content: &str,
+        format: ConfigFormat,
+        ctx: &Context,
+        strict_env: bool,
+    ) -> Result<AppConfig, ConfigError> {
+        let mut state = LoadState {
+            strict_env,
+            ..Default::default()
+        };
+
+        AppConfig::parse_content(content, format, ctx, Source::Content, &mut state)
+            .and_then(|config| AppConfig::load_includes(config, Path::new(""), ctx, &mut state))
+            .and_then(|config| {
+                AppConfig::from_value(
+                    config,
+                    std::iter::empty::<(&str, String)>(),
+                    std::mem::take(&mut state.provenance),
+                )
+            })
+            .map_err(|e| e.locate(&state.contents))
     }
 
-    fn from_file(filename: &String, ctx: &Context) -> Result<AppConfig, ConfigError> {
+    /// Compares listeners of this config with the candidate ones
+    pub fn diff(&self, candidate: &AppConfig) -> ConfigDiff {
+        ConfigDiff::new(self, candidate)
+    }
+
+    fn from_file(
+        filename: &String,
+        ctx: &Context,
+        state: &mut LoadState,
+    ) -> Result<AppConfig, ConfigError> {
         info!("Loading config: {filename}");
-        let mut file = File::open(filename)?;
-        AppConfig::from_reader(&mut file, ctx)
+
+        AppConfig::load_file(Path::new(filename), ctx, state)
+            .and_then(|config| {
+                AppConfig::from_value(
+                    config,
+                    Env::prefixed(ENV_OVERRIDE_PREFIX).split("__").iter(),
+                    std::mem::take(&mut state.provenance),
+                )
+            })
+            .map_err(|e| e.locate(&state.contents))
+    }
+
+    fn from_dir(
+        dirname: &String,
+        ctx: &Context,
+        state: &mut LoadState,
+    ) -> Result<AppConfig, ConfigError> {
+        info!("Loading config folder: {dirname}");
+        let mut files = fs::read_dir(dirname)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && ConfigFormat::from_path(path).is_some())
+            .collect::<Vec<_>>();
+        files.sort();
+
+        let mut config = Value::from(Dict::new());
+        files
+            .iter()
+            .try_for_each(|file| AppConfig::merge_file(&mut config, file, ctx, state))
+            .and_then(|_| {
+                AppConfig::from_value(
+                    config,
+                    Env::prefixed(ENV_OVERRIDE_PREFIX).split("__").iter(),
+                    std::mem::take(&mut state.provenance),
+                )
+            })
+            .map_err(|e| e.locate(&state.contents))
+    }
+
+    /// Applies overrides to the loaded config, deserializes and validates it
+    fn from_value(
+        mut config: Value,
+        overrides: impl Iterator<Item = (impl AsRef<str>, String)>,
+        mut provenance: Provenance,
+    ) -> Result<AppConfig, ConfigError> {
+        for (key, value) in overrides {
+            let key = key.as_ref();
+            debug!("Config override: {key}={value}");
+            let path: Vec<&str> = key.split('.').collect();
+            let value = value.parse().unwrap_or_else(|_| Value::from(value));
+            let value = provenance.track(&value, Source::Override(key.into()));
+            if !Self::override_value(&mut config, &path, value) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("unable to apply override of `{key}`, path doesn't exist"),
+                });
+            }
+        }
+
+        AppConfig::apply_expressions(&mut config, &mut provenance)?;
+        AppConfig::apply_templates(&mut config, &mut provenance)?;
+        AppConfig::apply_defaults(&mut config, &mut provenance)?;
+        provenance.resolve(&config);
+
+        let raw_listeners = match config.find_ref("listeners") {
+            Some(Value::Array(_, listeners)) => listeners.clone(),
+            _ => vec![],
+        };
+        let mut config: AppConfig = Figment::from(Serialized::defaults(&config))
+            .extract()
+            .map_err(|e| ConfigError::parse(e, &config))?;
+        config.raw_listeners = raw_listeners;
+        config.provenance = provenance;
+        debug!(
+            "Application config: {}",
+            redaction::mask_secrets(&format!("{config:#?}"))
+        );
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validates `expressions` section and resolves all references to the named expressions
+    fn apply_expressions(
+        config: &mut Value,
+        provenance: &mut Provenance,
+    ) -> Result<(), ConfigError> {
+        let Value::Dict(_, dict) = config else {
+            return Ok(());
+        };
+        let Some(expressions) = dict.remove("expressions") else {
+            return Ok(());
+        };
+
+        let _: Expressions = AppConfig::extract_section("expressions", &expressions)?;
+        let Value::Dict(_, expressions) = expressions else {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`expressions` should be a dictionary".into(),
+            });
+        };
+
+        let expressions =
+            provenance.track_dict(&expressions, |name| Source::Expression(name.into()));
+        expressions::resolve(&expressions, config)
     }
 
-    fn from_reader(reader: &mut dyn Read, ctx: &Context) -> Result<AppConfig, ConfigError> {
-        let mut reader = BufReader::new(reader);
+    /// Validates `target_templates` section and materializes all targets which refer to templates
+    fn apply_templates(config: &mut Value, provenance: &mut Provenance) -> Result<(), ConfigError> {
+        let Value::Dict(_, dict) = config else {
+            return Ok(());
+        };
+        let templates = dict
+            .remove("target_templates")
+            .unwrap_or_else(|| Value::from(Dict::new()));
+
+        let _: TargetTemplates = AppConfig::extract_section("target_templates", &templates)?;
+        let Value::Dict(_, templates) = templates else {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`target_templates` should be a dictionary".into(),
+            });
+        };
+
+        if let Some(Value::Array(_, listeners)) = dict.get_mut("listeners") {
+            let templates = provenance.track_dict(&templates, |name| Source::Template(name.into()));
+            TargetTemplate::apply(&templates, listeners)?;
+        }
+
+        Ok(())
+    }
+
+    /// Validates `defaults` section and applies it to all listeners and targets
+    fn apply_defaults(config: &mut Value, provenance: &mut Provenance) -> Result<(), ConfigError> {
+        let Value::Dict(_, dict) = config else {
+            return Ok(());
+        };
+        let Some(defaults) = dict.remove("defaults") else {
+            return Ok(());
+        };
+
+        let typed_defaults: DefaultsConfig = AppConfig::extract_section("defaults", &defaults)?;
+        typed_defaults.validate()?;
+
+        if let Some(Value::Array(_, listeners)) = dict.get_mut("listeners") {
+            DefaultsConfig::apply(&provenance.track(&defaults, Source::Defaults), listeners);
+        }
+
+        Ok(())
+    }
+
+    /// Deserializes raw section of the config, error path is prefixed with the section name
+    fn extract_section<T: DeserializeOwned>(name: &str, value: &Value) -> Result<T, ConfigError> {
+        Figment::from(Serialized::default(name, value))
+            .extract_inner(name)
+            .map_err(|mut e: figment::Error| {
+                // figment appends key of the section to the end of the path
+                if e.path.last().is_some_and(|last| last == name) {
+                    e.path.pop();
+                }
+                e.path.insert(0, name.into());
+                let section = Dict::from([(name.to_string(), value.clone())]);
+                ConfigError::parse(e, &Value::from(section))
+            })
+    }
+
+    /// Sets value by the path of keys (or array indexes),
+    /// missing dictionary keys are created, but arrays can't be extended
+    fn override_value(target: &mut Value, path: &[&str], value: Value) -> bool {
+        let Some((key, rest)) = path.split_first() else {
+            *target = value;
+            return true;
+        };
+
+        match target {
+            Value::Dict(_, dict) => {
+                let next = dict
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::from(Dict::new()));
+                Self::override_value(next, rest, value)
+            }
+            Value::Array(_, array) => {
+                match key.parse::<usize>().ok().and_then(|i| array.get_mut(i)) {
+                    Some(next) => Self::override_value(next, rest, value),
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Loads single file and all files included by it, without validation
+    fn load_file(path: &Path, ctx: &Context, state: &mut LoadState) -> Result<Value, ConfigError> {
+        let canonical = path.canonicalize()?;
+        if !state.loaded.insert(canonical) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "file is loaded more than once, check `include` patterns".into(),
+            });
+        }
+
+        let config = AppConfig::read_file(path, ctx, state)?;
+
+        AppConfig::load_includes(config, path.parent().unwrap_or(Path::new("")), ctx, state)
+    }
+
+    /// Loads files included by the config, patterns are relative to the base folder
+    fn load_includes(
+        mut config: Value,
+        base_dir: &Path,
+        ctx: &Context,
+        state: &mut LoadState,
+    ) -> Result<Value, ConfigError> {
+        let include = match &mut config {
+            Value::Dict(_, dict) => dict.remove("include"),
+            _ => None,
+        };
+        let include: Vec<String> = match include {
+            Some(include) => include.deserialize()?,
+            None => vec![],
+        };
+
+        for pattern in include {
+            let full_pattern = base_dir.join(&pattern);
+            let full_pattern = full_pattern.to_string_lossy();
+            let mut files = glob::glob(&full_pattern)
+                .map_err(|e| ConfigError::ValidateConfig {
+                    cause: format!("invalid include pattern `{pattern}`: {e}"),
+                })?
+                .filter_map(Result::ok)
+                .collect::<Vec<_>>();
+            files.sort();
+
+            if files.is_empty() {
+                warn!("include pattern `{full_pattern}` doesn't match any file");
+            }
+            state.depth += 1;
+            for file in files {
+                AppConfig::merge_file(&mut config, &file, ctx, state)?;
+            }
+            state.depth -= 1;
+        }
+
+        Ok(config)
+    }
+
+    /// Loads file and appends its listeners to the target config,
+    /// any error is reported with the name of the file which caused it
+    fn merge_file(
+        config: &mut Value,
+        path: &Path,
+        ctx: &Context,
+        state: &mut LoadState,
+    ) -> Result<(), ConfigError> {
+        info!("Loading included config: {}", path.display());
+        let included = AppConfig::load_file(path, ctx, state).map_err(|e| e.in_file(path))?;
+
+        // Collect templates before listeners validation, so included listeners may use them
+        if let Some(Value::Dict(_, included_templates)) = included.find_ref("target_templates") {
+            let _: TargetTemplates = AppConfig::extract_section(
+                "target_templates",
+                &Value::from(included_templates.clone()),
+            )
+            .map_err(|e| e.in_file(path))?;
+            if let Value::Dict(_, dict) = config {
+                let templates = dict
+                    .entry("target_templates".into())
+                    .or_insert_with(|| Value::from(Dict::new()));
+                if let Value::Dict(_, templates) = templates {
+                    for (name, template) in included_templates {
+                        if templates.insert(name.clone(), template.clone()).is_some() {
+                            return Err(ConfigError::ValidateConfig {
+                                cause: format!(
+                                    "target template `{name}` is defined more than once"
+                                ),
+                            }
+                            .in_file(path));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Collect expressions before listeners validation, so included listeners may refer to them
+        if let Some(Value::Dict(_, included_expressions)) = included.find_ref("expressions") {
+            let _: Expressions = AppConfig::extract_section(
+                "expressions",
+                &Value::from(included_expressions.clone()),
+            )
+            .map_err(|e| e.in_file(path))?;
+            if let Value::Dict(_, dict) = config {
+                let expressions = dict
+                    .entry("expressions".into())
+                    .or_insert_with(|| Value::from(Dict::new()));
+                if let Value::Dict(_, expressions) = expressions {
+                    for (name, expression) in included_expressions {
+                        if expressions
+                            .insert(name.clone(), expression.clone())
+                            .is_some()
+                        {
+                            return Err(ConfigError::ValidateConfig {
+                                cause: format!("expression `{name}` is defined more than once"),
+                            }
+                            .in_file(path));
+                        }
+                    }
+                }
+            }
+        }
+
+        // Validate listeners of the included file before merging
+        let mut listeners = included
+            .find_ref("listeners")
+            .cloned()
+            .unwrap_or(Value::from(Vec::<Value>::new()));
+        if let (Value::Array(_, listeners), Some(Value::Dict(_, templates))) =
+            (&mut listeners, config.find_ref("target_templates"))
+        {
+            let templates = state
+                .provenance
+                .track_dict(templates, |name| Source::Template(name.into()));
+            TargetTemplate::apply(&templates, listeners).map_err(|e| e.in_file(path))?;
+        }
+        if let Some(Value::Dict(_, expressions)) = config.find_ref("expressions") {
+            let expressions = state
+                .provenance
+                .track_dict(expressions, |name| Source::Expression(name.into()));
+            expressions::resolve(&expressions, &mut listeners).map_err(|e| e.in_file(path))?;
+        }
+        let typed_listeners: Vec<ListenerConfig> =
+            AppConfig::extract_section("listeners", &listeners).map_err(|e| e.in_file(path))?;
+        for listener in &typed_listeners {
+            listener.validate().map_err(|e| e.in_file(path))?;
+        }
+
+        let Value::Array(_, listeners) = listeners else {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`listeners` should be a list".into(),
+            }
+            .in_file(path));
+        };
+        if let Value::Dict(_, dict) = config {
+            if let Some(sensitive_headers) = included.find_ref("sensitive_headers").cloned() {
+                if dict.contains_key("sensitive_headers") {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: "`sensitive_headers` is defined in more than one file".into(),
+                    }
+                    .in_file(path));
+                }
+                dict.insert("sensitive_headers".into(), sensitive_headers);
+            }
+            if let Some(hooks) = included.find_ref("hooks").cloned() {
+                if dict.contains_key("hooks") {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: "`hooks` section is defined in more than one file".into(),
+                    }
+                    .in_file(path));
+                }
+                dict.insert("hooks".into(), hooks);
+            }
+            if let Some(logging) = included.find_ref("logging").cloned() {
+                if dict.contains_key("logging") {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: "`logging` section is defined in more than one file".into(),
+                    }
+                    .in_file(path));
+                }
+                dict.insert("logging".into(), logging);
+            }
+            if let Some(defaults) = included.find("defaults") {
+                if dict.contains_key("defaults") {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: "`defaults` section is defined in more than one file".into(),
+                    }
+                    .in_file(path));
+                }
+                dict.insert("defaults".into(), defaults);
+            }
+
+            let own = dict
+                .entry("listeners".into())
+                .or_insert_with(|| Value::from(Vec::<Value>::new()));
+            if let Value::Array(_, own) = own {
+                own.extend(listeners);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads single file and parses it according to the file extension
+    fn read_file(path: &Path, ctx: &Context, state: &mut LoadState) -> Result<Value, ConfigError> {
+        let mut reader = BufReader::new(File::open(path)?);
         let mut buf = String::new();
         reader.read_to_string(&mut buf)?;
-        let config = env_with_context_no_errors(&buf, |v| ctx.get(&v.into()));
-        let config: AppConfig = serde_yaml::from_str(&config)?;
+        let format = ConfigFormat::from_path(path).unwrap_or(ConfigFormat::Yaml);
+        let source = if state.depth == 0 {
+            Source::File(path.display().to_string())
+        } else {
+            Source::Include(path.display().to_string())
+        };
+
+        AppConfig::parse_content(&buf, format, ctx, source, state)
+    }
+
+    /// Expands context variables and parses content of the config,
+    /// values with expanded variables are tracked separately from the rest of the content
+    fn parse_content(
+        content: &str,
+        format: ConfigFormat,
+        ctx: &Context,
+        source: Source,
+        state: &mut LoadState,
+    ) -> Result<Value, ConfigError> {
+        let expanded = AppConfig::expand_env(content, ctx, state.strict_env)?;
+        let mut config = AppConfig::parse_string(&expanded, format)?;
+
+        let origin = match &source {
+            Source::File(path) | Source::Include(path) => path.clone(),
+            _ => source.to_string(),
+        };
+        // Root dictionary has the default tag, but all nested values share the tag of the content
+        if let Some(tag) = config.as_dict().and_then(|dict| dict.values().next()) {
+            state.provenance.register(tag.tag(), source);
+            if format == ConfigFormat::Yaml {
+                state.contents.insert(tag.tag(), expanded.clone());
+            }
+        }
+        if expanded != content {
+            // Content may be invalid without expansion, so env values are just not tracked then
+            if let Ok(raw) = AppConfig::parse_string(content, format) {
+                state.provenance.track_env(&mut config, &raw, &origin);
+            }
+        }
+
+        Ok(config)
+    }
 
-        debug!("Application config: {:#?}", config);
-        match config.validate() {
-            Ok(_) => Ok(config),
-            Err(e) => Err(e),
+    fn parse_string(content: &str, format: ConfigFormat) -> Result<Value, ConfigError> {
+        let figment = match format {
+            ConfigFormat::Yaml => Figment::from(Yaml::string(content)),
+            ConfigFormat::Toml => Figment::from(Toml::string(content)),
+            ConfigFormat::Json => Figment::from(Json::string(content)),
+        };
+
+        Ok(figment.extract()?)
+    }
+
+    /// Expands context variables, unknown variables without default value are left as is,
+    /// in strict mode all unknown non-context variables are collected and reported as an error
+    fn expand_env(buf: &str, ctx: &Context, strict_env: bool) -> Result<String, ConfigError> {
+        if !strict_env {
+            return Ok(env_with_context_no_errors(buf, |v| ctx.get(&v.into())).into());
         }
+
+        // Each pass fails on the first unknown variable without default value,
+        // so repeat it with all already found variables allowed to stay unresolved
+        let mut unresolved: Vec<String> = vec![];
+        loop {
+            let expanded = env_with_context(buf, |v| match ctx.get(&v.into()) {
+                Some(value) => Ok(Some(value)),
+                None if (v.starts_with(RUNTIME_CONTEXT_PREFIX)
+                    && !v.starts_with(SECRET_CTX_PREFIX))
+                    || unresolved.iter().any(|u| u == v) =>
+                {
+                    Ok(None)
+                }
+                None => Err(()),
+            });
+            match expanded {
+                Ok(expanded) if unresolved.is_empty() => return Ok(expanded.into()),
+                Ok(_) => break,
+                Err(e) => unresolved.push(e.var_name),
+            }
+        }
+
+        let unresolved = unresolved
+            .iter()
+            .map(|v| format!("`${{{v}}}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Err(ConfigError::ValidateConfig {
+            cause: format!("unresolved environment variables: {unresolved}"),
+        })
     }
 
     pub fn listeners(&self) -> &[ListenerConfig] {
         self.listeners.as_ref()
     }
+
+    fn default_sensitive_headers() -> Vec<String> {
+        redaction::DEFAULT_SENSITIVE_HEADERS
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// Returns names of the headers which values are masked in logs and config dump
+    pub fn sensitive_headers(&self) -> &[String] {
+        &self.sensitive_headers
+    }
+
+    /// Returns hooks of the process lifecycle events
+    pub fn hooks(&self) -> &HooksConfig {
+        &self.hooks
+    }
+
+    /// Returns process-wide logging settings
+    pub fn logging(&self) -> &LoggingConfig {
+        &self.logging
+    }
+
+    /// Returns JSON schema of the whole config as a pretty-printed string
+    pub fn json_schema() -> String {
+        let mut schema = schema_for!(AppConfig);
+        // `include` is resolved by loader and isn't a part of the AppConfig itself
+        if let Some(object) = schema.schema.object.as_mut() {
+            let mut include = schema_for!(Vec<String>).schema;
+            include.metadata().description = Some("List of glob patterns of files to load listeners from, relative paths are resolved against the folder of the including file".into());
+            object.properties.insert("include".into(), include.into());
+
+            let defaults = schema_for!(DefaultsConfig);
+            schema.definitions.extend(defaults.definitions);
+            let mut defaults = defaults.schema;
+            defaults.metadata().description = Some("Default parameters of all listeners and targets, any parameter of particular listener or target takes precedence".into());
+            object.properties.insert("defaults".into(), defaults.into());
+
+            let expressions = schema_for!(Expressions);
+            let mut expressions = expressions.schema;
+            expressions.metadata().description = Some("Named regexes and JQ-like expressions, any string parameter may refer to them as `{expression: name}`".into());
+            object
+                .properties
+                .insert("expressions".into(), expressions.into());
+
+            let templates = schema_for!(TargetTemplates);
+            schema.definitions.extend(templates.definitions);
+            let mut templates = templates.schema;
+            templates.metadata().description = Some("Named reusable parts of targets config, target refers to template by `template` parameter".into());
+            object
+                .properties
+                .insert("target_templates".into(), templates.into());
+        }
+        // Target may get any parameter from the template, so it has optional `template` reference and no required parameters
+        if let Some(Schema::Object(target)) = schema.definitions.get_mut("TargetConfig") {
+            let mut template = schema_for!(String).schema;
+            template.metadata().description =
+                Some("Name of the target template to inherit parameters from".into());
+            let target = target.object();
+            target
+                .properties
+                .insert(TEMPLATE_KEY.into(), template.into());
+            target.required.clear();
+        }
+        serde_json::to_string_pretty(&schema)
+            .expect("unable to serialize config schema, looks like a BUG")
+    }
+
+    /// Returns effective config in JSON, with includes, templates, expressions, defaults and overrides applied;
+    /// each value is `{"value": ..., "source": ...}`, parameters which aren't set have the `default` source
+    pub fn dump(&self) -> String {
+        let schema = serde_json::to_value(schema_for!(AppConfig))
+            .expect("unable to serialize config schema, looks like a BUG");
+        serde_json::to_string_pretty(&self.provenance.dump(&schema))
+            .expect("unable to serialize config dump, looks like a BUG")
+    }
 }
 
 impl ConfigValidator for AppConfig {
@@ -91,9 +1129,124 @@ impl ConfigValidator for AppConfig {
         for listener in self.listeners() {
             listener.validate()?;
         }
+        self.hooks.validate()?;
+        self.logging.validate()?;
+
+        if let Some(name) = self
+            .sensitive_headers
+            .iter()
+            .find(|name| HeaderName::from_bytes(name.as_bytes()).is_err())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("invalid sensitive header name `{name}`"),
+            });
+        }
+
+        // Conflicts between listeners are reported by config loading instead of failed bind of the listener
+        for (pos, listener) in self.listeners().iter().enumerate() {
+            for other in &self.listeners()[..pos] {
+                if listener.id() == other.id() {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!("listener ID `{}` is defined more than once", listener.id()),
+                    });
+                }
+                if let (Some(acme), Some(other_acme)) = (
+                    listener.server_tls().and_then(ServerTlsConfig::acme),
+                    other.server_tls().and_then(ServerTlsConfig::acme),
+                ) {
+                    if acme.cache_dir() == other_acme.cache_dir()
+                        && acme.domains() != other_acme.domains()
+                    {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "listeners `{}` and `{}` have different ACME `domains` in the same `cache_dir`",
+                                other.id(),
+                                listener.id()
+                            ),
+                        });
+                    }
+                }
+                if listener.is_sni_shared_with(other) {
+                    let (names, other_names) = (
+                        listener
+                            .server_tls()
+                            .map(ServerTlsConfig::server_names)
+                            .unwrap_or_default(),
+                        other
+                            .server_tls()
+                            .map(ServerTlsConfig::server_names)
+                            .unwrap_or_default(),
+                    );
+                    if names.is_empty() && other_names.is_empty() {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "listeners `{}` and `{}` share socket {}, so at least one of them should have TLS `server_names`",
+                                other.id(),
+                                listener.id(),
+                                listener.on()
+                            ),
+                        });
+                    }
+                    if let Some(name) = names.iter().find(|name| {
+                        other_names
+                            .iter()
+                            .any(|other| other.eq_ignore_ascii_case(name))
+                    }) {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "TLS server name `{name}` is defined by both listeners `{}` and `{}`",
+                                other.id(),
+                                listener.id()
+                            ),
+                        });
+                    }
+                    if [listener, other].iter().any(|listener| {
+                        listener
+                            .server_tls()
+                            .is_some_and(|tls| tls.protocol() == ServerProtocol::H3)
+                    }) {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "HTTP/3 isn't supported by listeners `{}` and `{}` which share socket {}",
+                                other.id(),
+                                listener.id(),
+                                listener.on()
+                            ),
+                        });
+                    }
+                    continue;
+                }
+                let (socket, other_socket) = (listener.socket(), other.socket());
+                if sockets_conflict(socket, other_socket) {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "listeners `{}` ({}) and `{}` ({}) conflict on port {}",
+                            other.id(),
+                            other.on(),
+                            listener.id(),
+                            listener.on(),
+                            socket.port()
+                        ),
+                    });
+                }
+            }
+        }
 
         Ok(())
     }
+
+    fn lint(&self) -> Vec<ConfigWarning> {
+        self.listeners()
+            .iter()
+            .flat_map(|listener| {
+                let scope = format!("listener `{}`", listener.id());
+                listener
+                    .lint()
+                    .into_iter()
+                    .map(move |warning| warning.within(&scope))
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -101,7 +1254,7 @@ mod test {
     use super::*;
     use crate::context::test_context;
     use insta::{assert_debug_snapshot, glob};
-    use std::io::ErrorKind;
+    use std::time::Duration;
 
     const TEST_CONFIGS_FOLDER: &str = "../tests/configs";
 
@@ -113,7 +1266,8 @@ mod test {
             "good/*.yaml",
             |path| assert_debug_snapshot!(AppConfig::from_file(
                 &String::from(path.to_str().unwrap()),
-                ctx
+                ctx,
+                &mut LoadState::default()
             ))
         );
     }
@@ -128,14 +1282,214 @@ mod test {
                 r#"unable to parse config: listeners\[0\]\.targets\[1\]\.condition: invalid config: found "/" but expected one of "(.+)" at line 9 column 18,"#,
                 r#"unable to parse config: listeners[0].targets[1].condition: invalid config: found "/" but expected one of "[LIST OF ALLOWED JQ STATEMENTS]" at line 9 column 18,"#
             )]},
-            {assert_debug_snapshot!(AppConfig::from_file(&String::from(path.to_str().unwrap()),ctx));})
+            {assert_debug_snapshot!(AppConfig::from_file(&String::from(path.to_str().unwrap()),ctx,&mut LoadState::default()));})
+        );
+    }
+
+    #[test]
+    fn independent_configs() {
+        let ctx = test_context::get_test_ctx();
+        let minimal = AppConfig::new("tests/configs/good/01-minimal.yaml", ctx, false).unwrap();
+        let simple =
+            AppConfig::new("tests/configs/good/02-simple-config.yaml", ctx, false).unwrap();
+
+        assert_eq!(minimal.listeners()[0].id(), "LISTENER-0.0.0.0:8080");
+        assert_eq!(simple.listeners()[0].id(), "Listener-1");
+    }
+
+    #[test]
+    fn include_config() {
+        let ctx = test_context::get_test_ctx();
+        let config = AppConfig::new("tests/configs/include/main.yaml", ctx, false).unwrap();
+        let ids: Vec<String> = config.listeners().iter().map(ListenerConfig::id).collect();
+        assert_eq!(ids, vec!["main", "first", "second"]);
+    }
+
+    #[test]
+    fn include_templates() {
+        let ctx = test_context::get_test_ctx();
+        let config = AppConfig::new("tests/configs/include/templates.yaml", ctx, false).unwrap();
+        let targets: Vec<(&str, Duration)> = config
+            .listeners()
+            .iter()
+            .flat_map(|l| l.targets().iter().map(|t| (t.url(), *t.timeout())))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("https://www.google.com/", Duration::from_secs(5)),
+                ("https://www.google.com/", Duration::from_secs(15))
+            ]
+        );
+    }
+
+    #[test]
+    fn config_dir() {
+        let ctx = test_context::get_test_ctx();
+        let config = AppConfig::new(
+            ConfigSource::Dir("tests/configs/include/listeners".into()),
+            ctx,
+            false,
+        )
+        .unwrap();
+        let ids: Vec<String> = config.listeners().iter().map(ListenerConfig::id).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn wrong_include() {
+        let ctx = test_context::get_test_ctx();
+        assert_debug_snapshot!(AppConfig::new(
+            "tests/configs/include/wrong.yaml",
+            ctx,
+            false
+        ));
+        assert_debug_snapshot!(AppConfig::new(
+            "tests/configs/include/recursive.yaml",
+            ctx,
+            false
+        ));
+        assert_debug_snapshot!(AppConfig::new(
+            ConfigSource::Dir("tests/configs/include/wrong".into()),
+            ctx,
+            false
+        ));
+    }
+
+    #[test]
+    fn strict_env() {
+        let ctx = test_context::get_test_ctx();
+        assert!(AppConfig::new("tests/configs/env/strict.yaml", ctx, false).is_ok());
+        assert_debug_snapshot!(AppConfig::new("tests/configs/env/strict.yaml", ctx, true));
+
+        let config = AppConfig::new("tests/configs/env/defaults.yaml", ctx, true).unwrap();
+        assert_eq!(config.listeners()[0].id(), "default-id");
+        assert_eq!(config.listeners()[0].socket().port(), 8080);
+    }
+
+    #[test]
+    fn config_formats() {
+        let ctx = test_context::get_test_ctx();
+        let yaml = AppConfig::new("tests/configs/formats/config.yaml", ctx, false).unwrap();
+        let toml = AppConfig::new("tests/configs/formats/config.toml", ctx, false).unwrap();
+        let json = AppConfig::new("tests/configs/formats/config.json", ctx, false).unwrap();
+
+        assert_eq!(format!("{yaml:?}"), format!("{toml:?}"));
+        assert_eq!(format!("{yaml:?}"), format!("{json:?}"));
+    }
+
+    #[test]
+    fn config_overrides() {
+        let ctx = test_context::get_test_ctx();
+        let path = Path::new("tests/configs/formats/config.yaml");
+
+        let value = AppConfig::load_file(path, ctx, &mut LoadState::default()).unwrap();
+        let config = AppConfig::from_value(
+            value,
+            [("listeners.0.timeout", "15s".to_string())].into_iter(),
+            Provenance::default(),
+        )
+        .unwrap();
+        assert_eq!(config.listeners()[0].timeout(), Duration::from_secs(15));
+
+        let value = AppConfig::load_file(path, ctx, &mut LoadState::default()).unwrap();
+        assert_debug_snapshot!(AppConfig::from_value(
+            value,
+            [("listeners.1.timeout", "15s".to_string())].into_iter(),
+            Provenance::default(),
+        ));
+
+        let value = AppConfig::load_file(path, ctx, &mut LoadState::default()).unwrap();
+        assert_debug_snapshot!(AppConfig::from_value(
+            value,
+            [("listeners.0.targets.0.timeout", "wrong".to_string())].into_iter(),
+            Provenance::default(),
+        ));
+    }
+
+    #[test]
+    fn config_provenance() {
+        let ctx = test_context::get_test_ctx();
+        let path = Path::new("tests/configs/provenance/main.yaml");
+        let mut state = LoadState::default();
+        let value = AppConfig::load_file(path, ctx, &mut state).unwrap();
+        let config = AppConfig::from_value(
+            value,
+            [("listeners.1.targets.0.timeout", "15s".to_string())].into_iter(),
+            state.provenance,
+        )
+        .unwrap();
+        let dump: serde_json::Value = serde_json::from_str(&config.dump()).unwrap();
+        let source = |path: &str| dump.pointer(&format!("{path}/source")).unwrap().clone();
+
+        let main = "file:tests/configs/provenance/main.yaml";
+        assert_eq!(source("/listeners/0/id"), main);
+        assert_eq!(
+            source("/listeners/0/listen_on"),
+            "env:tests/configs/provenance/main.yaml"
+        );
+        assert_eq!(source("/listeners/0/timeout"), "defaults");
+        assert_eq!(source("/listeners/0/targets/0/url"), main);
+        assert_eq!(source("/listeners/0/targets/0/timeout"), "template:slow");
+        assert_eq!(source("/listeners/0/targets/0/weight"), "default");
+        assert_eq!(dump["listeners"][0]["headers"][0]["value"]["value"], "***");
+        assert_eq!(
+            source("/listeners/1/id"),
+            "include:tests/configs/provenance/listeners.yaml"
+        );
+        assert_eq!(
+            source("/listeners/1/response/failed_status_regex"),
+            "expression:client_errors"
+        );
+        assert_eq!(
+            dump["listeners"][1]["targets"][0]["timeout"],
+            serde_json::json!({"value": "15s", "source": "override:listeners.1.targets.0.timeout"})
+        );
+        assert_eq!(source("/sensitive_headers"), "default");
+    }
+
+    #[test]
+    fn lint_warnings() {
+        let ctx = test_context::get_test_ctx();
+        let config = AppConfig::new("tests/configs/lint/warnings.yaml", ctx, false).unwrap();
+        let warnings: Vec<String> = config.lint().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            warnings,
+            [
+                "listener `suspicious`: response `override` is empty, so it has no effect",
+                "listener `suspicious`: response `target_selector` isn't used by strategy `ok_then_failed`",
+                "listener `suspicious`: `coalescing` has no effect since GET method isn't allowed",
+                "listener `suspicious`: target `never`: `condition` `1 == 2` can never be true, so the target is never requested",
+                "listener `suspicious`: target `legacy`: `timeout` 30s isn't less than `total_timeout` 10s, so it never fires",
+                "listener `suspicious`: target `legacy`: sunset date Wed, 01 Jan 2020 00:00:00 GMT of the deprecated target has passed",
+            ]
         );
+
+        let minimal = AppConfig::new("tests/configs/good/01-minimal.yaml", ctx, false).unwrap();
+        assert!(minimal.lint().is_empty());
+    }
+
+    #[test]
+    fn sockets_conflict_by_family() {
+        let conflict = |socket: &str, other: &str| {
+            sockets_conflict(socket.parse().unwrap(), other.parse().unwrap())
+        };
+
+        assert!(conflict("10.0.0.1:8080", "10.0.0.1:8080"));
+        assert!(conflict("0.0.0.0:8080", "10.0.0.1:8080"));
+        assert!(conflict("[::]:8080", "[::1]:8080"));
+        assert!(conflict("127.0.0.1:8080", "[::]:8080"));
+        assert!(conflict("0.0.0.0:8080", "[::]:8080"));
+        assert!(!conflict("0.0.0.0:8080", "[::1]:8080"));
+        assert!(!conflict("[::1]:8080", "0.0.0.0:8080"));
+        assert!(!conflict("0.0.0.0:8080", "0.0.0.0:8081"));
+        assert!(!conflict("10.0.0.1:8080", "10.0.0.2:8080"));
     }
 
     #[test]
     fn errors() {
         assert_debug_snapshot!(ConfigError::LoadConfig {
-            cause: io::Error::new(ErrorKind::Other, "snapshot test cause")
+            cause: io::Error::other("snapshot test cause")
         });
         assert_debug_snapshot!(ConfigError::ValidateConfig {
             cause: "snapshot test cause".to_string()
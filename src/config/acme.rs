@@ -0,0 +1,105 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::Uri;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Let's Encrypt production directory
+const DEFAULT_ACME_DIRECTORY: &str = "https://acme-v02.api.letsencrypt.org/directory";
+const DEFAULT_ACME_RENEW_BEFORE_SEC: u64 = 30 * 24 * 3600;
+
+/// Certificate of the listener which is obtained and renewed automatically from ACME server (like Let's Encrypt),
+/// domains are validated by TLS-ALPN-01 challenge on the listener's port
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AcmeConfig {
+    domains: Vec<String>,
+    email: Option<String>,
+    cache_dir: PathBuf,
+    #[serde(default = "AcmeConfig::default_directory")]
+    directory: String,
+    directory_ca: Option<PathBuf>,
+    #[serde(with = "humantime_serde", default = "AcmeConfig::default_renew_before")]
+    #[schemars(with = "Option<String>")]
+    renew_before: Duration,
+}
+
+impl AcmeConfig {
+    fn default_directory() -> String {
+        DEFAULT_ACME_DIRECTORY.into()
+    }
+
+    fn default_renew_before() -> Duration {
+        Duration::from_secs(DEFAULT_ACME_RENEW_BEFORE_SEC)
+    }
+
+    /// Domains of the certificate, the first one is its subject
+    pub fn domains(&self) -> &[String] {
+        &self.domains
+    }
+
+    /// Contact of the ACME account, expiration notices are sent to it
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Directory to store the account credentials and the issued certificate in, so they survive restarts
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Directory URL of the ACME server
+    pub fn directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// CA certificate of the private ACME server, system roots are used if it isn't defined
+    pub fn directory_ca(&self) -> Option<&Path> {
+        self.directory_ca.as_deref()
+    }
+
+    /// Returns how long before expiration the certificate is renewed
+    pub fn renew_before(&self) -> Duration {
+        self.renew_before
+    }
+}
+
+impl ConfigValidator for AcmeConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.domains.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "ACME `domains` should have at least one domain".into(),
+            });
+        }
+        if let Some(domain) = self
+            .domains
+            .iter()
+            .find(|domain| domain.is_empty() || domain.contains(['*', '/', ':']))
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "invalid ACME domain `{domain}`, wildcards can't be validated by TLS-ALPN-01 challenge"
+                ),
+            });
+        }
+        if self
+            .directory
+            .parse::<Uri>()
+            .map_or(true, |uri| uri.scheme_str() != Some("https"))
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("ACME `directory` should be HTTPS URL: `{}`", self.directory),
+            });
+        }
+        if self.renew_before.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "ACME `renew_before` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
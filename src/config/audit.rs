@@ -0,0 +1,86 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{header::HeaderName, Uri};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{path::Path, path::PathBuf, time::Duration};
+
+const DEFAULT_AUDIT_TIMEOUT_SEC: u64 = 10;
+
+/// Audit trail of the listener's requests, each record is chained to the previous one by its hash
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AuditConfig {
+    file: Option<PathBuf>,
+    url: Option<String>,
+    #[serde(with = "humantime_serde", default = "AuditConfig::default_timeout")]
+    #[schemars(with = "Option<String>")]
+    timeout: Duration,
+    identity_header: Option<String>,
+}
+
+/// Destination of the audit records
+pub enum AuditSink<'a> {
+    /// File to append records to, one JSON per line
+    File(&'a Path),
+    /// Endpoint to `POST` each record to
+    Http(&'a str),
+}
+
+impl AuditConfig {
+    fn default_timeout() -> Duration {
+        Duration::from_secs(DEFAULT_AUDIT_TIMEOUT_SEC)
+    }
+
+    pub fn sink(&self) -> AuditSink<'_> {
+        if let Some(file) = &self.file {
+            AuditSink::File(file)
+        } else {
+            AuditSink::Http(
+                self.url
+                    .as_ref()
+                    .expect("audit sink isn't defined, looks like a BUG"),
+            )
+        }
+    }
+
+    /// Returns timeout of the request to the HTTP sink
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns request header which identifies requester, like authenticated user set by the gateway
+    pub fn identity_header(&self) -> Option<&str> {
+        self.identity_header.as_deref()
+    }
+}
+
+impl ConfigValidator for AuditConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.file.is_some() == self.url.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "exactly one of `file` or `url` audit sinks should be defined".into(),
+            });
+        }
+        if let Some(url) = &self.url {
+            if url.parse::<Uri>().map_or(true, |uri| uri.host().is_none()) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid audit sink url `{url}`"),
+                });
+            }
+        }
+        if self.timeout.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "audit `timeout` should be greater than zero".into(),
+            });
+        }
+        if let Some(header) = &self.identity_header {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid audit `identity_header` `{header}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
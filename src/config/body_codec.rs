@@ -0,0 +1,241 @@
+use super::{protobuf::ProtoSchema, ConfigError, ConfigValidator};
+use hyper::{header::CONTENT_TYPE, HeaderMap};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+/// Max depth of the nested msgpack arrays and maps
+const MAX_MSGPACK_DEPTH: usize = 64;
+/// Compression flag and length of the message in the gRPC frame
+const GRPC_FRAME_HEADER_LEN: usize = 5;
+
+/// Decoding of the request body to the JSON-like value which is used as `.body` of the targets' conditions
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BodyCodecConfig {
+    #[serde(default)]
+    codec: BodyCodec,
+    protobuf: Option<ProtobufConfig>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyCodec {
+    /// Codec is selected by `Content-Type` of the request, JSON is used if it's unknown
+    #[default]
+    Auto,
+    Json,
+    Msgpack,
+    FormUrlencoded,
+    /// Requires `protobuf` schema, gRPC framed messages are accepted too
+    Protobuf,
+}
+
+/// Schema of the protobuf bodies
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProtobufConfig {
+    /// Path to the `.proto` file
+    #[schemars(with = "String")]
+    schema: ProtoSchema,
+    /// Name of the message of the body, with or without package
+    message: String,
+}
+
+impl BodyCodecConfig {
+    pub fn codec(&self) -> BodyCodec {
+        self.codec
+    }
+
+    /// Returns decoded body, it's an empty object if body is empty or can't be decoded
+    pub fn decode(&self, headers: &HeaderMap, body: &[u8]) -> Value {
+        let codec = match self.codec {
+            BodyCodec::Auto => self.detect(headers),
+            codec => codec,
+        };
+        let decoded = match codec {
+            BodyCodec::Auto | BodyCodec::Json => serde_json::from_slice(body).ok(),
+            BodyCodec::Msgpack => decode_msgpack(body),
+            BodyCodec::FormUrlencoded => Some(decode_form(body)),
+            BodyCodec::Protobuf => self.protobuf.as_ref().and_then(|protobuf| {
+                let body = if is_grpc(headers) {
+                    body.get(GRPC_FRAME_HEADER_LEN..)?
+                } else {
+                    body
+                };
+                protobuf.schema.decode(&protobuf.message, body)
+            }),
+        };
+
+        decoded.unwrap_or(json!({}))
+    }
+
+    fn detect(&self, headers: &HeaderMap) -> BodyCodec {
+        match content_type(headers).as_deref() {
+            Some("application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack") => {
+                BodyCodec::Msgpack
+            }
+            Some("application/x-www-form-urlencoded") => BodyCodec::FormUrlencoded,
+            Some(
+                "application/protobuf"
+                | "application/x-protobuf"
+                | "application/grpc"
+                | "application/grpc+proto",
+            ) if self.protobuf.is_some() => BodyCodec::Protobuf,
+            _ => BodyCodec::Json,
+        }
+    }
+}
+
+/// Returns media type of the request without parameters, in lowercase
+fn content_type(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let media_type = value.split(';').next().unwrap_or_default();
+    Some(media_type.trim().to_lowercase())
+}
+
+fn is_grpc(headers: &HeaderMap) -> bool {
+    content_type(headers).is_some_and(|t| t.starts_with("application/grpc"))
+}
+
+/// Repeated keys are collected to arrays
+fn decode_form(body: &[u8]) -> Value {
+    let mut object = Map::new();
+    for (key, value) in form_urlencoded::parse(body) {
+        let value = Value::from(value.into_owned());
+        match object.get_mut(key.as_ref()) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                object.insert(key.into_owned(), value);
+            }
+        }
+    }
+
+    Value::Object(object)
+}
+
+/// Decodes single msgpack value; binary values are decoded as text, extensions as `null`,
+/// keys of the maps which aren't strings are converted to their JSON text
+fn decode_msgpack(body: &[u8]) -> Option<Value> {
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(body);
+    deserializer.set_max_depth(MAX_MSGPACK_DEPTH);
+    let value = rmpv::Value::deserialize(&mut deserializer).ok()?;
+
+    Some(msgpack_to_json(value))
+}
+
+fn msgpack_to_json(value: rmpv::Value) -> Value {
+    match value {
+        rmpv::Value::Nil | rmpv::Value::Ext(..) => Value::Null,
+        rmpv::Value::Boolean(value) => value.into(),
+        rmpv::Value::Integer(value) => value
+            .as_i64()
+            .map(Value::from)
+            .or_else(|| value.as_u64().map(Value::from))
+            .unwrap_or_default(),
+        rmpv::Value::F32(value) => value.into(),
+        rmpv::Value::F64(value) => value.into(),
+        rmpv::Value::String(value) => String::from_utf8_lossy(value.as_bytes()).into(),
+        rmpv::Value::Binary(value) => String::from_utf8_lossy(&value).into(),
+        rmpv::Value::Array(values) => values.into_iter().map(msgpack_to_json).collect(),
+        rmpv::Value::Map(entries) => Value::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match msgpack_to_json(key) {
+                        Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, msgpack_to_json(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl ConfigValidator for BodyCodecConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.codec == BodyCodec::Protobuf && self.protobuf.is_none() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`protobuf` body codec requires `protobuf` schema".into(),
+            });
+        }
+        if let Some(protobuf) = &self.protobuf {
+            if protobuf.schema.message_name(&protobuf.message).is_none() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "protobuf message `{}` isn't defined in schema `{:?}`",
+                        protobuf.message, protobuf.schema
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    fn headers(content_type: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        headers
+    }
+
+    #[test]
+    fn decode_msgpack_body() {
+        let cfg = BodyCodecConfig::default();
+        // {"a": [1, -2, "xy", nil, true], "b": 1.5, 300: -200}
+        let mut body = vec![
+            0x83, 0xa1, b'a', 0x95, 0x01, 0xfe, 0xa2, b'x', b'y', 0xc0, 0xc3,
+        ];
+        body.extend([0xa1, b'b', 0xcb]);
+        body.extend(1.5f64.to_be_bytes());
+        body.extend([0xcd, 0x01, 0x2c, 0xd1, 0xff, 0x38]);
+
+        assert_eq!(
+            cfg.decode(&headers("application/msgpack"), &body),
+            json!({"a": [1, -2, "xy", null, true], "b": 1.5, "300": -200})
+        );
+        assert_eq!(
+            cfg.decode(&headers("application/x-msgpack"), &body[..5]),
+            json!({})
+        );
+        // Without content type body is expected to be JSON
+        assert_eq!(cfg.decode(&HeaderMap::new(), &body), json!({}));
+    }
+
+    #[test]
+    fn decode_form_body() {
+        let cfg = BodyCodecConfig::default();
+        assert_eq!(
+            cfg.decode(
+                &headers("application/x-www-form-urlencoded; charset=utf-8"),
+                b"user=john+doe&role=admin&role=dev&empty="
+            ),
+            json!({"user": "john doe", "role": ["admin", "dev"], "empty": ""})
+        );
+
+        let cfg: BodyCodecConfig = serde_json::from_str(r#"{"codec": "form_urlencoded"}"#).unwrap();
+        assert_eq!(
+            cfg.decode(&headers("application/json"), b"a=1"),
+            json!({"a": "1"})
+        );
+    }
+
+    #[test]
+    fn protobuf_requires_schema() {
+        let cfg: BodyCodecConfig = serde_json::from_str(r#"{"codec": "protobuf"}"#).unwrap();
+        assert!(cfg.validate().is_err());
+        // Body can't be decoded without schema
+        assert_eq!(
+            cfg.decode(&headers("application/x-protobuf"), b"{\"a\": 1}"),
+            json!({})
+        );
+    }
+}
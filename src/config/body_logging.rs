@@ -0,0 +1,98 @@
+use super::{ConfigError, ConfigValidator};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+const DEFAULT_BODY_LOGGING_MAX_SIZE: usize = 4096;
+const DEFAULT_BODY_LOGGING_MASK: &str = "***";
+
+/// Logging of request and targets' response bodies, sensitive parts are masked before logging
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BodyLoggingConfig {
+    #[serde(default = "BodyLoggingConfig::default_true")]
+    request: bool,
+    #[serde(default = "BodyLoggingConfig::default_true")]
+    response: bool,
+    #[serde(default = "BodyLoggingConfig::default_max_size")]
+    max_size: usize,
+    #[serde(default = "BodyLoggingConfig::default_mask")]
+    mask: String,
+    #[serde(default)]
+    redact: Vec<RedactionRule>,
+}
+
+/// Part of the body to mask
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RedactionRule {
+    /// Dot separated path to the value in JSON body, `*` matches any field or array item
+    JsonPath(String),
+    /// Regex to match in the body of any content
+    Regex(String),
+}
+
+impl BodyLoggingConfig {
+    fn default_true() -> bool {
+        true
+    }
+
+    fn default_max_size() -> usize {
+        DEFAULT_BODY_LOGGING_MAX_SIZE
+    }
+
+    fn default_mask() -> String {
+        DEFAULT_BODY_LOGGING_MASK.into()
+    }
+
+    pub fn request(&self) -> bool {
+        self.request
+    }
+
+    pub fn response(&self) -> bool {
+        self.response
+    }
+
+    /// Returns max size of the logged part of the body in bytes
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn mask(&self) -> &str {
+        &self.mask
+    }
+
+    pub fn redact(&self) -> &[RedactionRule] {
+        &self.redact
+    }
+}
+
+impl ConfigValidator for BodyLoggingConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_size == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "body logging `max_size` should be greater than zero".into(),
+            });
+        }
+        for rule in &self.redact {
+            match rule {
+                RedactionRule::JsonPath(path) => {
+                    if path.trim_start_matches("$.").split('.').any(str::is_empty) {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!("invalid body redaction JSON path `{path}`"),
+                        });
+                    }
+                }
+                RedactionRule::Regex(regex) => {
+                    if let Err(e) = Regex::new(regex) {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!("invalid body redaction regex `{regex}`: {e}"),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+use super::{target::filter_parse_ctx, ConfigError, ConfigValidator};
+use crate::streaming::full_bytes;
+use http_body_util::Full;
+use hyper::{
+    body::Bytes,
+    header::{CONTENT_ENCODING, CONTENT_LENGTH},
+    Response,
+};
+use jaq_interpret::{Ctx, Filter, FilterT, RcIter, Val};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use tracing::{debug, warn};
+
+/// Rewrites body of the selected target's response before returning it to the client
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BodyTransformConfig {
+    #[schemars(with = "Option<String>")]
+    jq: Option<JqFilter>,
+    #[serde(default)]
+    replace: Vec<ReplaceRule>,
+}
+
+/// Replaces all matches of the `regex` in the text body or path, `$1`, `$2`, etc. refer to capture groups
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ReplaceRule {
+    #[schemars(with = "String")]
+    regex: BodyRegex,
+    with: String,
+}
+
+/// JQ filter, it's compiled once during config loading
+pub struct JqFilter {
+    source: String,
+    filter: Filter,
+}
+
+/// Regex to replace in the body, it's compiled once during config loading
+pub struct BodyRegex(Regex);
+
+impl BodyTransformConfig {
+    /// Applies JQ filter to JSON body and then replacements to text body,
+    /// compressed bodies and bodies which can't be transformed are returned as is
+    pub fn transform(&self, resp: Response<Full<Bytes>>) -> Response<Full<Bytes>> {
+        if resp.headers().contains_key(CONTENT_ENCODING) {
+            debug!("body transform: skip encoded body");
+            return resp;
+        }
+
+        let (mut parts, body) = resp.into_parts();
+        let body = full_bytes(&body);
+
+        let body = match self.transform_body(&body) {
+            Some(transformed) => {
+                parts.headers.remove(CONTENT_LENGTH);
+                transformed
+            }
+            None => body,
+        };
+
+        Response::from_parts(parts, Full::new(body))
+    }
+
+    fn transform_body(&self, body: &Bytes) -> Option<Bytes> {
+        let mut transformed = None;
+
+        if let Some(jq) = &self.jq {
+            match serde_json::from_slice::<serde_json::Value>(body) {
+                Ok(json) => transformed = jq.run(json).map(Bytes::from),
+                Err(_) => debug!("body transform: body isn't JSON, skip jq filter"),
+            }
+        }
+
+        if !self.replace.is_empty() {
+            let current = transformed.as_ref().unwrap_or(body);
+            match std::str::from_utf8(current) {
+                Ok(text) => {
+                    let mut text = text.to_string();
+                    for rule in &self.replace {
+                        text = rule.regex.0.replace_all(&text, &rule.with).into_owned();
+                    }
+                    transformed = Some(Bytes::from(text));
+                }
+                Err(_) => debug!("body transform: body isn't text, skip replacements"),
+            }
+        }
+
+        transformed
+    }
+}
+
+impl ReplaceRule {
+    /// Returns text with all matches replaced, or `None` if there are no matches
+    pub fn rewrite(&self, text: &str) -> Option<String> {
+        self.regex
+            .0
+            .is_match(text)
+            .then(|| self.regex.0.replace_all(text, &self.with).into_owned())
+    }
+}
+
+impl ConfigValidator for BodyTransformConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.jq.is_none() && self.replace.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`body_transform` should have `jq` filter or at least one `replace` rule"
+                    .into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl JqFilter {
+    /// Returns serialized first output of the filter, or `None` if filter fails or has no output
+    fn run(&self, json: serde_json::Value) -> Option<String> {
+        let inputs = RcIter::new(core::iter::empty());
+        let mut out = self.filter.run((Ctx::new([], &inputs), Val::from(json)));
+
+        match out.next() {
+            Some(Ok(val)) => Some(serde_json::Value::from(val).to_string()),
+            Some(Err(e)) => {
+                warn!("body transform: jq filter failed, body is kept as is: {e}");
+                None
+            }
+            None => {
+                warn!("body transform: jq filter has no output, body is kept as is");
+                None
+            }
+        }
+    }
+
+    /// Returns the first output of the filter, or `None` if filter fails or has no output
+    pub fn first(&self, json: serde_json::Value) -> Option<serde_json::Value> {
+        let inputs = RcIter::new(core::iter::empty());
+        let mut out = self.filter.run((Ctx::new([], &inputs), Val::from(json)));
+
+        match out.next() {
+            Some(Ok(val)) => Some(serde_json::Value::from(val)),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the first output of the filter is `true`
+    pub fn is_true(&self, json: &serde_json::Value) -> bool {
+        let inputs = RcIter::new(core::iter::empty());
+        let mut out = self
+            .filter
+            .run((Ctx::new([], &inputs), Val::from(json.clone())));
+
+        matches!(out.next(), Some(Ok(Val::Bool(true))))
+    }
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        let mut defs = filter_parse_ctx();
+        let (f, errs) = jaq_parse::parse(value, jaq_parse::main());
+        if let Some(e) = errs.first() {
+            return Err(format!("invalid jq filter `{value}`: {e}"));
+        }
+        let Some(f) = f else {
+            return Err(format!("invalid jq filter `{value}`"));
+        };
+        let filter = defs.compile(f);
+        if let Some((e, _)) = defs.errs.first() {
+            return Err(format!("unable to compile jq filter `{value}`: {e}"));
+        }
+
+        Ok(Self {
+            source: value.into(),
+            filter,
+        })
+    }
+}
+
+impl std::fmt::Debug for JqFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Serialize for JqFilter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for JqFilter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct JqFilterVisitor;
+        impl Visitor<'_> for JqFilterVisitor {
+            type Value = JqFilter;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("JQ-like filter to transform JSON body")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                JqFilter::from_str(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(JqFilterVisitor)
+    }
+}
+
+impl std::fmt::Debug for BodyRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.as_str().fmt(f)
+    }
+}
+
+impl Serialize for BodyRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for BodyRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BodyRegexVisitor;
+        impl Visitor<'_> for BodyRegexVisitor {
+            type Value = BodyRegex;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("regex to replace in the body")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Regex::new(v)
+                    .map(BodyRegex)
+                    .map_err(|e| E::custom(format!("invalid body regex `{v}`: {e}")))
+            }
+        }
+
+        deserializer.deserialize_string(BodyRegexVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body(resp: Response<Full<Bytes>>) -> String {
+        String::from_utf8(full_bytes(resp.body()).to_vec()).unwrap()
+    }
+
+    #[test]
+    fn transform_json_body() {
+        let cfg: BodyTransformConfig = serde_json::from_str(
+            r#"{"jq": "{url: .url}", "replace": [{"regex": "http://backend\\.local(/\\w*)", "with": "https://example.com$1"}]}"#,
+        )
+        .unwrap();
+        let resp = Response::builder()
+            .header(CONTENT_LENGTH, "50")
+            .body(Full::from(
+                r#"{"internal":1,"url":"http://backend.local/items"}"#,
+            ))
+            .unwrap();
+
+        let resp = cfg.transform(resp);
+        assert!(!resp.headers().contains_key(CONTENT_LENGTH));
+        assert_eq!(body(resp), r#"{"url":"https://example.com/items"}"#);
+    }
+
+    #[test]
+    fn keep_non_json_body() {
+        let cfg: BodyTransformConfig = serde_json::from_str(r#"{"jq": ".field"}"#).unwrap();
+        let resp = Response::builder()
+            .header(CONTENT_LENGTH, "4")
+            .body(Full::from("text"))
+            .unwrap();
+
+        let resp = cfg.transform(resp);
+        assert!(resp.headers().contains_key(CONTENT_LENGTH));
+        assert_eq!(body(resp), "text");
+    }
+}
@@ -0,0 +1,49 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+const DEFAULT_BUFFER_POOL_CHUNK_SIZE: usize = 16 * 1024;
+const DEFAULT_BUFFER_POOL_MAX_CHUNKS: usize = 1024;
+
+/// Pool of the body buffers which are reused by requests of the listener instead of allocating new ones
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BufferPoolConfig {
+    #[serde(default = "BufferPoolConfig::default_chunk_size")]
+    chunk_size: usize,
+    #[serde(default = "BufferPoolConfig::default_max_chunks")]
+    max_chunks: usize,
+}
+
+impl BufferPoolConfig {
+    fn default_chunk_size() -> usize {
+        DEFAULT_BUFFER_POOL_CHUNK_SIZE
+    }
+
+    fn default_max_chunks() -> usize {
+        DEFAULT_BUFFER_POOL_MAX_CHUNKS
+    }
+
+    /// Initial capacity of each buffer in bytes, larger bodies grow the buffer
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Max number of idle buffers kept in the pool, buffers above it are freed
+    pub fn max_chunks(&self) -> usize {
+        self.max_chunks
+    }
+}
+
+impl ConfigValidator for BufferPoolConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.chunk_size == 0 || self.max_chunks == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "buffer pool `chunk_size` and `max_chunks` should be greater than zero"
+                    .into(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::header::HeaderName;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{path::PathBuf, time::Duration};
+
+const DEFAULT_CACHE_MAX_ENTRIES: usize = 1000;
+const DEFAULT_CACHE_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Cache of the final responses to `GET` and `HEAD` requests,
+/// keyed by method, path with query and values of the `vary` request headers
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CacheConfig {
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    ttl: Duration,
+    #[serde(default = "CacheConfig::default_max_entries")]
+    max_entries: usize,
+    #[serde(default = "CacheConfig::default_max_body_size")]
+    max_body_size: usize,
+    #[serde(default)]
+    vary: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl CacheConfig {
+    fn default_max_entries() -> usize {
+        DEFAULT_CACHE_MAX_ENTRIES
+    }
+
+    fn default_max_body_size() -> usize {
+        DEFAULT_CACHE_MAX_BODY_SIZE
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size
+    }
+
+    /// Returns names of the request headers which are part of the cache key
+    pub fn vary(&self) -> Vec<HeaderName> {
+        self.vary
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .expect("invalid header name, looks like a BUG")
+            })
+            .collect()
+    }
+
+    /// Returns folder to store cached responses in, responses are kept in memory if it's not defined
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+}
+
+impl ConfigValidator for CacheConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.ttl.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "cache `ttl` should be greater than zero".into(),
+            });
+        }
+        if self.max_entries == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "cache `max_entries` should be greater than zero".into(),
+            });
+        }
+        for name in &self.vary {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid cache `vary` header name `{name}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_CANARY_MAX_ERROR_RATE_INCREASE: f64 = 0.05;
+
+/// Gradual rollout of the target: percent of the responses selected from it grows step by step,
+/// rollout is rolled back if the target fails more often than other ones
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CanaryConfig {
+    steps: Vec<f64>,
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    bake_time: Duration,
+    #[serde(default = "CanaryConfig::default_max_error_rate_increase")]
+    max_error_rate_increase: f64,
+}
+
+impl CanaryConfig {
+    fn default_max_error_rate_increase() -> f64 {
+        DEFAULT_CANARY_MAX_ERROR_RATE_INCREASE
+    }
+
+    /// Returns percents of the responses to select from the target on each step
+    pub fn steps(&self) -> &[f64] {
+        &self.steps
+    }
+
+    /// Returns time to stay on each step before the next one
+    pub fn bake_time(&self) -> Duration {
+        self.bake_time
+    }
+
+    /// Returns excess of the target's failures rate over the other targets' one which causes rollback
+    pub fn max_error_rate_increase(&self) -> f64 {
+        self.max_error_rate_increase
+    }
+}
+
+impl ConfigValidator for CanaryConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.steps.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "canary `steps` should contain at least one step".into(),
+            });
+        }
+        if self.steps.iter().any(|step| *step <= 0.0 || *step > 100.0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "canary `steps` should be percents in range 0..=100 excluding zero".into(),
+            });
+        }
+        if self.steps.windows(2).any(|steps| steps[0] >= steps[1]) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "canary `steps` should be in ascending order".into(),
+            });
+        }
+        if self.bake_time.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "canary `bake_time` should be greater than zero".into(),
+            });
+        }
+        if self.max_error_rate_increase <= 0.0 || self.max_error_rate_increase >= 1.0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "canary `max_error_rate_increase` should be in range 0.0..1.0 exclusively, but it's {}",
+                    self.max_error_rate_increase
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use http_body_util::Full;
+use hyper::{body::Bytes, Method, Response};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Responses recorded earlier (in production, for example), keyed by the request's method and path with query.
+/// Capture is a JSON Lines file, each line is a single response, the latest one of the same request wins:
+/// `{"method": "GET", "path": "/items?id=1", "status": 200, "headers": {"content-type": "text/plain"}, "body": "..."}`
+#[derive(Clone)]
+pub struct ResponseCapture {
+    path: String,
+    responses: Arc<HashMap<(Method, String), CapturedResponse>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CapturedEntry {
+    #[serde(default = "CapturedEntry::default_method")]
+    method: String,
+    path: String,
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: String,
+}
+
+struct CapturedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+}
+
+impl CapturedEntry {
+    fn default_method() -> String {
+        Method::GET.to_string()
+    }
+}
+
+impl ResponseCapture {
+    fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to load response capture `{path}`: {e}"))?;
+        Self::from_lines(path, &content)
+    }
+
+    fn from_lines(path: &str, content: &str) -> Result<Self, String> {
+        let mut responses = HashMap::new();
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = |e: &dyn std::fmt::Display| {
+                format!(
+                    "invalid response capture `{path}`, line {}: {e}",
+                    line_no + 1
+                )
+            };
+            let entry: CapturedEntry = serde_json::from_str(line).map_err(|e| invalid(&e))?;
+            let method = Method::from_bytes(entry.method.to_uppercase().as_bytes())
+                .map_err(|e| invalid(&e))?;
+            let response = CapturedResponse {
+                status: entry.status,
+                headers: entry.headers.into_iter().collect(),
+                body: entry.body.into(),
+            };
+            // Verify the response once, so it's always built successfully later
+            response.to_response().map_err(|e| invalid(&e))?;
+            responses.insert((method, entry.path), response);
+        }
+
+        Ok(Self {
+            path: path.into(),
+            responses: Arc::new(responses),
+        })
+    }
+
+    /// Returns path of the capture file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns recorded response of the request, `path` includes query
+    pub fn response(&self, method: &Method, path: &str) -> Option<Response<Full<Bytes>>> {
+        self.responses
+            .get(&(method.clone(), path.into()))
+            .and_then(|response| response.to_response().ok())
+    }
+}
+
+impl CapturedResponse {
+    fn to_response(&self) -> Result<Response<Full<Bytes>>, hyper::http::Error> {
+        let mut builder = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder.body(Full::new(self.body.clone()))
+    }
+}
+
+impl std::fmt::Debug for ResponseCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+impl Serialize for ResponseCapture {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl<'de> Deserialize<'de> for ResponseCapture {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ResponseCaptureVisitor;
+        impl Visitor<'_> for ResponseCaptureVisitor {
+            type Value = ResponseCapture;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("path to response capture file")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                ResponseCapture::from_file(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(ResponseCaptureVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_capture() {
+        let capture = ResponseCapture::from_lines(
+            "capture.jsonl",
+            r#"{"path": "/items?id=1", "status": 200, "body": "old"}
+
+{"method": "get", "path": "/items?id=1", "status": 200, "headers": {"content-type": "text/plain"}, "body": "new"}
+{"method": "DELETE", "path": "/items?id=1", "status": 204}
+"#,
+        )
+        .unwrap();
+
+        let resp = capture.response(&Method::GET, "/items?id=1").unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()["content-type"], "text/plain");
+        assert_eq!(
+            capture
+                .response(&Method::DELETE, "/items?id=1")
+                .unwrap()
+                .status(),
+            204
+        );
+        assert!(capture.response(&Method::GET, "/items").is_none());
+
+        assert_eq!(
+            ResponseCapture::from_lines("capture.jsonl", r#"{"path": "/", "status": 1000}"#)
+                .unwrap_err(),
+            "invalid response capture `capture.jsonl`, line 1: invalid status code"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::header::{HeaderName, AUTHORIZATION, COOKIE};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Coalescing of concurrent identical GET requests into a single fan-out
+#[derive(Deserialize, Debug, Default, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CoalescingConfig {
+    #[serde(default)]
+    vary: Vec<String>,
+    #[serde(default)]
+    paths: Vec<String>,
+}
+
+impl CoalescingConfig {
+    /// Returns names of the request headers which distinguish identical requests,
+    /// credentials are always among them to never share response between different clients
+    pub fn vary(&self) -> Vec<HeaderName> {
+        let mut vary = vec![AUTHORIZATION, COOKIE];
+        for name in &self.vary {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .expect("invalid header name, looks like a BUG");
+            if !vary.contains(&name) {
+                vary.push(name);
+            }
+        }
+
+        vary
+    }
+
+    /// Returns `true` if requests to the path are coalesced, all paths are if prefixes aren't configured
+    pub fn is_path_coalesced(&self, path: &str) -> bool {
+        self.paths.is_empty() || self.paths.iter().any(|prefix| path.starts_with(prefix))
+    }
+}
+
+impl ConfigValidator for CoalescingConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for name in &self.vary {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid coalescing vary header name `{name}`"),
+                });
+            }
+        }
+        if let Some(path) = self.paths.iter().find(|path| !path.starts_with('/')) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("coalescing path `{path}` should start with `/`"),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vary_and_paths() {
+        let cfg: CoalescingConfig =
+            serde_json::from_str(r#"{"vary": ["Accept", "authorization"], "paths": ["/api/"]}"#)
+                .unwrap();
+        assert!(cfg.validate().is_ok());
+        assert_eq!(
+            cfg.vary(),
+            vec![AUTHORIZATION, COOKIE, HeaderName::from_static("accept")]
+        );
+        assert!(cfg.is_path_coalesced("/api/users"));
+        assert!(!cfg.is_path_coalesced("/health"));
+        assert!(CoalescingConfig::default().is_path_coalesced("/health"));
+
+        let cfg: CoalescingConfig = serde_json::from_str(r#"{"paths": ["api"]}"#).unwrap();
+        assert!(cfg.validate().is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use super::{capture::ResponseCapture, ConfigError, ConfigValidator};
+use hyper::header::HeaderName;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_COMPARISON_MAX_RECORDS: usize = 100;
+const DEFAULT_COMPARISON_SUMMARY_INTERVAL_SEC: u64 = 60;
+
+/// Comparison of the targets' responses with the baseline one, mismatches are kept to review them later.
+/// Baseline is either the listener's target or responses recorded earlier.
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ComparisonConfig {
+    baseline: Option<String>,
+    #[schemars(with = "Option<String>")]
+    baseline_capture: Option<ResponseCapture>,
+    #[serde(default)]
+    headers: Vec<String>,
+    #[serde(default = "ComparisonConfig::default_body")]
+    body: bool,
+    #[serde(default = "ComparisonConfig::default_max_records")]
+    max_records: usize,
+    #[serde(
+        with = "humantime_serde",
+        default = "ComparisonConfig::default_summary_interval"
+    )]
+    #[schemars(with = "Option<String>")]
+    summary_interval: Duration,
+}
+
+impl ComparisonConfig {
+    fn default_body() -> bool {
+        true
+    }
+
+    fn default_max_records() -> usize {
+        DEFAULT_COMPARISON_MAX_RECORDS
+    }
+
+    fn default_summary_interval() -> Duration {
+        Duration::from_secs(DEFAULT_COMPARISON_SUMMARY_INTERVAL_SEC)
+    }
+
+    /// Returns ID of the target which responses are compared with others
+    pub fn baseline(&self) -> Option<&str> {
+        self.baseline.as_deref()
+    }
+
+    /// Returns recorded responses which are compared with the targets' ones
+    pub fn baseline_capture(&self) -> Option<&ResponseCapture> {
+        self.baseline_capture.as_ref()
+    }
+
+    /// Returns names of the response headers to compare
+    pub fn headers(&self) -> Vec<HeaderName> {
+        self.headers
+            .iter()
+            .map(|name| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .expect("invalid header name, looks like a BUG")
+            })
+            .collect()
+    }
+
+    /// Returns `true` if response bodies should be compared
+    pub fn body(&self) -> bool {
+        self.body
+    }
+
+    /// Returns max number of the mismatch records to keep, the oldest ones are dropped
+    pub fn max_records(&self) -> usize {
+        self.max_records
+    }
+
+    pub fn summary_interval(&self) -> Duration {
+        self.summary_interval
+    }
+}
+
+impl ConfigValidator for ComparisonConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.baseline.is_some() == self.baseline_capture.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause:
+                    "exactly one of comparison `baseline` or `baseline_capture` should be defined"
+                        .into(),
+            });
+        }
+        if let Some(name) = self
+            .headers
+            .iter()
+            .find(|name| HeaderName::from_bytes(name.as_bytes()).is_err())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("invalid comparison header name `{name}`"),
+            });
+        }
+        if self.max_records == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "comparison `max_records` should be greater than zero".into(),
+            });
+        }
+        if self.summary_interval.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "comparison `summary_interval` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
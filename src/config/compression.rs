@@ -0,0 +1,207 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::header::HeaderValue;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use strum_macros::Display;
+
+/// Compression of the request body sent to the target and handling of the compressed target's response
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TargetCompressionConfig {
+    request: Option<ContentEncoding>,
+    #[serde(default)]
+    response: ResponseDecompression,
+}
+
+const DEFAULT_COMPRESSION_MIN_SIZE: usize = 1024;
+const DEFAULT_COMPRESSIBLE_CONTENT_TYPES: [&str; 5] = [
+    "text/*",
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "image/svg+xml",
+];
+
+/// Compression of the final response sent to the requester according to its `Accept-Encoding` header
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ListenerCompressionConfig {
+    encodings: Vec<ContentEncoding>,
+    #[serde(default = "ListenerCompressionConfig::default_min_size")]
+    min_size: usize,
+    #[serde(default = "ListenerCompressionConfig::default_content_types")]
+    content_types: Vec<String>,
+}
+
+const DEFAULT_REQUEST_DECOMPRESSION_MAX_SIZE: usize = 10 * 1024 * 1024;
+
+/// Decompression of the compressed request body to inspect it by conditions, OpenAPI validation,
+/// body logging and plugins
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RequestDecompressionConfig {
+    #[serde(default = "RequestDecompressionConfig::default_max_size")]
+    max_size: usize,
+    #[serde(default)]
+    forward: RequestBodyForward,
+}
+
+/// Which body of the compressed request is sent to targets
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum RequestBodyForward {
+    /// Body is sent in the original encoding, it's compressed back if plugins have changed it
+    #[default]
+    Original,
+    /// Body is sent decompressed, target's request compression is applied if configured
+    Decompressed,
+}
+
+/// Supported content encodings
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+    Br,
+    Zstd,
+}
+
+/// What to do with compressed response of the target
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum ResponseDecompression {
+    /// Response body is passed as is
+    #[default]
+    Pass,
+    /// Response body is decompressed and sent to the requester uncompressed
+    Decompress,
+    /// Response body is decompressed and compressed back if the requester accepts the original encoding
+    Recompress,
+}
+
+impl TargetCompressionConfig {
+    /// Returns encoding to compress request body with, if any
+    pub fn request(&self) -> Option<ContentEncoding> {
+        self.request
+    }
+
+    pub fn response(&self) -> ResponseDecompression {
+        self.response
+    }
+}
+
+impl ContentEncoding {
+    /// Parses value of the `Content-Encoding` header, multiple encodings aren't supported
+    pub fn from_header(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "deflate" => Some(Self::Deflate),
+            "br" => Some(Self::Br),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn header_value(&self) -> HeaderValue {
+        match self {
+            Self::Gzip => HeaderValue::from_static("gzip"),
+            Self::Deflate => HeaderValue::from_static("deflate"),
+            Self::Br => HeaderValue::from_static("br"),
+            Self::Zstd => HeaderValue::from_static("zstd"),
+        }
+    }
+}
+
+impl ListenerCompressionConfig {
+    fn default_min_size() -> usize {
+        DEFAULT_COMPRESSION_MIN_SIZE
+    }
+
+    fn default_content_types() -> Vec<String> {
+        DEFAULT_COMPRESSIBLE_CONTENT_TYPES
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    /// Returns encodings in order of preference
+    pub fn encodings(&self) -> &[ContentEncoding] {
+        &self.encodings
+    }
+
+    pub fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Verifies if response with the content type should be compressed,
+    /// `type/*` pattern matches all subtypes of the type
+    pub fn is_compressible(&self, content_type: &str) -> bool {
+        is_content_type_matched(&self.content_types, content_type)
+    }
+}
+
+/// Verifies if content type (parameters are ignored) matches one of the patterns,
+/// `type/*` pattern matches all subtypes of the type
+pub fn is_content_type_matched(patterns: &[String], content_type: &str) -> bool {
+    let content_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_suffix("/*") {
+            Some(main_type) => content_type
+                .split_once('/')
+                .is_some_and(|(t, _)| t == main_type),
+            None => pattern == content_type,
+        }
+    })
+}
+
+impl RequestDecompressionConfig {
+    fn default_max_size() -> usize {
+        DEFAULT_REQUEST_DECOMPRESSION_MAX_SIZE
+    }
+
+    /// Returns max size of the decompressed body in bytes, larger requests are rejected
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn forward(&self) -> RequestBodyForward {
+        self.forward
+    }
+}
+
+impl ConfigValidator for RequestDecompressionConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_size == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "request decompression `max_size` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigValidator for ListenerCompressionConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.encodings.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one compression encoding should be specified".into(),
+            });
+        }
+        if self.content_types.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one compressible content type should be specified".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
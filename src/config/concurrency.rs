@@ -0,0 +1,40 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Limit of the target requests in progress of the listener,
+/// requests above it wait for a free slot, high priority ones go first
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConcurrencyConfig {
+    limit: usize,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    low_priority_queue_timeout: Option<Duration>,
+}
+
+impl ConcurrencyConfig {
+    /// Returns max number of the target requests in progress
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns time which low priority request waits for a free slot before it's shed,
+    /// it waits until the target's timeout if it isn't defined
+    pub fn low_priority_queue_timeout(&self) -> Option<Duration> {
+        self.low_priority_queue_timeout
+    }
+}
+
+impl ConfigValidator for ConcurrencyConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.limit == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "concurrency `limit` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
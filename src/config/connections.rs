@@ -0,0 +1,135 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_TCP_KEEPALIVE_TIME_SEC: u64 = 60;
+
+/// Lifetime limits of the listener's client connections
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionsConfig {
+    #[serde(default = "ConnectionsConfig::default_keep_alive")]
+    keep_alive: bool,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    idle_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    max_lifetime: Option<Duration>,
+    tcp_keepalive: Option<TcpKeepaliveConfig>,
+    idle_shedding_threshold: Option<usize>,
+}
+
+/// TCP keepalive probes of the client connections, to detect dead peers
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TcpKeepaliveConfig {
+    #[serde(with = "humantime_serde", default = "TcpKeepaliveConfig::default_time")]
+    #[schemars(with = "Option<String>")]
+    time: Duration,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl ConnectionsConfig {
+    fn default_keep_alive() -> bool {
+        true
+    }
+
+    /// Returns `false` if HTTP/1 connection should be closed after each response
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    /// Returns time after which connection without requests in progress is closed
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// Returns number of requests after which connection is closed
+    pub fn max_requests(&self) -> Option<usize> {
+        self.max_requests
+    }
+
+    /// Returns time after which connection is closed once its requests in progress are completed
+    pub fn max_lifetime(&self) -> Option<Duration> {
+        self.max_lifetime
+    }
+
+    /// Returns TCP keepalive parameters of the accepted sockets, OS defaults are used if it isn't defined
+    pub fn tcp_keepalive(&self) -> Option<&TcpKeepaliveConfig> {
+        self.tcp_keepalive.as_ref()
+    }
+
+    /// Returns number of open connections above which idle connections are closed, it's a count, not memory limit
+    pub fn idle_shedding_threshold(&self) -> Option<usize> {
+        self.idle_shedding_threshold
+    }
+}
+
+impl TcpKeepaliveConfig {
+    fn default_time() -> Duration {
+        Duration::from_secs(DEFAULT_TCP_KEEPALIVE_TIME_SEC)
+    }
+
+    /// Returns idle time before the first probe
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// Returns interval between probes, system default is used if it isn't defined
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Returns number of failed probes to drop connection, system default is used if it isn't defined
+    pub fn retries(&self) -> Option<u32> {
+        self.retries
+    }
+}
+
+impl ConfigValidator for ConnectionsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let zero_durations = [
+            ("idle_timeout", self.idle_timeout),
+            ("max_lifetime", self.max_lifetime),
+            (
+                "tcp_keepalive.time",
+                self.tcp_keepalive.as_ref().map(|k| k.time),
+            ),
+            (
+                "tcp_keepalive.interval",
+                self.tcp_keepalive.as_ref().and_then(|k| k.interval),
+            ),
+        ];
+        if let Some((name, _)) = zero_durations
+            .iter()
+            .find(|(_, value)| value.is_some_and(|d| d.is_zero()))
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("connections `{name}` should be greater than zero"),
+            });
+        }
+        let zero_counts = [
+            ("max_requests", self.max_requests),
+            ("idle_shedding_threshold", self.idle_shedding_threshold),
+            (
+                "tcp_keepalive.retries",
+                self.tcp_keepalive
+                    .as_ref()
+                    .and_then(|k| k.retries.map(|r| r as usize)),
+            ),
+        ];
+        if let Some((name, _)) = zero_counts.iter().find(|(_, value)| *value == Some(0)) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("connections `{name}` should be greater than zero"),
+            });
+        }
+
+        Ok(())
+    }
+}
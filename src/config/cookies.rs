@@ -0,0 +1,118 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{
+    header::{HeaderValue, COOKIE, SET_COOKIE},
+    HeaderMap,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Cookie level transformations of requests to the target and its responses
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CookiesConfig {
+    #[serde(default)]
+    drop: Vec<String>,
+    #[serde(default)]
+    rename: BTreeMap<String, String>,
+    #[serde(default)]
+    strip_set_cookie: bool,
+}
+
+impl CookiesConfig {
+    /// Drops and renames cookies of the request, all cookies are merged into the single `Cookie` header
+    pub fn transform_request(&self, headers: &mut HeaderMap) {
+        if self.drop.is_empty() && self.rename.is_empty() {
+            return;
+        }
+
+        let cookies: Vec<String> = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .map(str::trim)
+            .filter(|cookie| !cookie.is_empty())
+            .filter_map(|cookie| {
+                let (name, value) = cookie.split_once('=').unwrap_or((cookie, ""));
+                if self.drop.iter().any(|drop| drop == name) {
+                    debug!("cookie drop: name={name}");
+                    return None;
+                }
+                match self.rename.get(name) {
+                    Some(new_name) => {
+                        debug!("cookie rename: name={name}, new={new_name}");
+                        Some(format!("{new_name}={value}"))
+                    }
+                    None => Some(cookie.to_string()),
+                }
+            })
+            .collect();
+
+        headers.remove(COOKIE);
+        if !cookies.is_empty() {
+            if let Ok(value) = HeaderValue::from_str(&cookies.join("; ")) {
+                headers.insert(COOKIE, value);
+            }
+        }
+    }
+
+    /// Removes `Set-Cookie` headers from the target's response if it's configured
+    pub fn transform_response(&self, headers: &mut HeaderMap) {
+        if self.strip_set_cookie && headers.remove(SET_COOKIE).is_some() {
+            debug!("set-cookie stripped");
+        }
+    }
+}
+
+impl ConfigValidator for CookiesConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let names = self
+            .drop
+            .iter()
+            .chain(self.rename.keys())
+            .chain(self.rename.values());
+        for name in names {
+            if name.is_empty()
+                || name
+                    .chars()
+                    .any(|c| c.is_whitespace() || c.is_control() || "=;,\"".contains(c))
+            {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid cookie name `{name}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cookies_transforms() {
+        let cfg: CookiesConfig = serde_json::from_str(
+            r#"{"drop": ["session"], "rename": {"staging_session": "session"}, "strip_set_cookie": true}"#,
+        )
+        .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("session=prod; theme=dark"));
+        headers.append(COOKIE, HeaderValue::from_static("staging_session=stage"));
+        cfg.transform_request(&mut headers);
+        assert_eq!(
+            headers.get_all(COOKIE).iter().collect::<Vec<_>>(),
+            ["theme=dark; session=stage"]
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.append(SET_COOKIE, HeaderValue::from_static("session=stage"));
+        headers.append(SET_COOKIE, HeaderValue::from_static("theme=light"));
+        cfg.transform_response(&mut headers);
+        assert!(headers.is_empty());
+    }
+}
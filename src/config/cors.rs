@@ -0,0 +1,108 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{header::HeaderName, Method};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const ANY: &str = "*";
+const DEFAULT_CORS_METHODS: [&str; 3] = ["GET", "HEAD", "POST"];
+
+/// Cross-origin resource sharing: preflight requests are answered by the listener itself,
+/// CORS headers are added to the responses of the allowed origins
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    allowed_origins: Vec<String>,
+    #[serde(default = "CorsConfig::default_allowed_methods")]
+    allowed_methods: Vec<String>,
+    #[serde(default)]
+    allowed_headers: Vec<String>,
+    #[serde(default)]
+    expose_headers: Vec<String>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    max_age: Option<Duration>,
+    #[serde(default)]
+    allow_credentials: bool,
+}
+
+impl CorsConfig {
+    fn default_allowed_methods() -> Vec<String> {
+        DEFAULT_CORS_METHODS.into_iter().map(String::from).collect()
+    }
+
+    /// Returns `true` if requests from the origin are allowed
+    pub fn is_origin_allowed(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == ANY || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Returns `true` if any origin is allowed, so `*` can be sent instead of the request's origin
+    pub fn is_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == ANY)
+    }
+
+    pub fn is_method_allowed(&self, method: &str) -> bool {
+        self.allowed_methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    pub fn allowed_methods(&self) -> &[String] {
+        &self.allowed_methods
+    }
+
+    /// Returns `true` if any request header is allowed
+    pub fn is_any_header(&self) -> bool {
+        self.allowed_headers.iter().any(|allowed| allowed == ANY)
+    }
+
+    pub fn allowed_headers(&self) -> &[String] {
+        &self.allowed_headers
+    }
+
+    pub fn expose_headers(&self) -> &[String] {
+        &self.expose_headers
+    }
+
+    /// Returns how long the preflight response can be cached by the browser
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn allow_credentials(&self) -> bool {
+        self.allow_credentials
+    }
+}
+
+impl ConfigValidator for CorsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.allowed_origins.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one of CORS `allowed_origins` should be defined".into(),
+            });
+        }
+        if self.allow_credentials && self.is_any_origin() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "CORS `allow_credentials` can't be used with any (`*`) origin".into(),
+            });
+        }
+        for method in &self.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid CORS method `{method}`"),
+                });
+            }
+        }
+        for header in self.allowed_headers.iter().chain(&self.expose_headers) {
+            if header != ANY && HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid CORS header name `{header}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
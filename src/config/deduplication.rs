@@ -0,0 +1,67 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::header::HeaderName;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_DEDUPLICATION_HEADER: &str = "idempotency-key";
+const DEFAULT_DEDUPLICATION_MAX_ENTRIES: usize = 10000;
+
+/// Deduplication of requests with the same idempotency key during the window
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DeduplicationConfig {
+    #[serde(default = "DeduplicationConfig::default_header")]
+    header: String,
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    window: Duration,
+    #[serde(default = "DeduplicationConfig::default_max_entries")]
+    max_entries: usize,
+}
+
+impl DeduplicationConfig {
+    fn default_header() -> String {
+        DEFAULT_DEDUPLICATION_HEADER.into()
+    }
+
+    fn default_max_entries() -> usize {
+        DEFAULT_DEDUPLICATION_MAX_ENTRIES
+    }
+
+    /// Returns name of the request header with idempotency key
+    pub fn header(&self) -> HeaderName {
+        HeaderName::from_bytes(self.header.as_bytes())
+            .expect("invalid header name, looks like a BUG")
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+}
+
+impl ConfigValidator for DeduplicationConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if HeaderName::from_bytes(self.header.as_bytes()).is_err() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("invalid deduplication header name `{}`", self.header),
+            });
+        }
+        if self.window.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "deduplication `window` should be greater than zero".into(),
+            });
+        }
+        if self.max_entries == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "deduplication `max_entries` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use super::{
+    headers::HeaderTransform,
+    listener::{ResponseStrategy, TlsConfig},
+    response::{ResponseConfig, ResponseStatus},
+    target::{BindAddress, TargetOnErrorAction},
+    ConfigError, ConfigValidator,
+};
+use figment::value::Value;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Default parameters of all listeners and targets,
+/// any parameter defined in particular listener or target takes precedence over the default one.
+/// Used to validate `defaults` section only, defaults are applied to the raw config before deserialization.
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+pub struct DefaultsConfig {
+    listener: Option<ListenerDefaults>,
+    target: Option<TargetDefaults>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct ListenerDefaults {
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    timeout: Option<Duration>,
+    strategy: Option<ResponseStrategy>,
+    headers: Option<Vec<HeaderTransform>>,
+    response: Option<ResponseConfig>,
+    tls: Option<TlsConfig>,
+}
+
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct TargetDefaults {
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    connect_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    read_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    total_timeout: Option<Duration>,
+    headers: Option<Vec<HeaderTransform>>,
+    on_error: Option<TargetOnErrorAction>,
+    error_status: Option<ResponseStatus>,
+    tls: Option<TlsConfig>,
+    bind_address: Option<BindAddress>,
+    interface: Option<String>,
+}
+
+impl ConfigValidator for DefaultsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(response) = self
+            .listener
+            .as_ref()
+            .and_then(|listener| listener.response.as_ref())
+        {
+            response.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DefaultsConfig {
+    /// Applies raw defaults section to all raw listeners and their targets
+    pub fn apply(defaults: &Value, listeners: &mut [Value]) {
+        let listener_defaults = defaults.find_ref("listener");
+        let target_defaults = defaults.find_ref("target");
+
+        for listener in listeners {
+            if let Some(listener_defaults) = listener_defaults {
+                Self::merge(listener, listener_defaults);
+            }
+            if let (Some(target_defaults), Value::Dict(_, listener)) = (target_defaults, listener) {
+                if let Some(Value::Array(_, targets)) = listener.get_mut("targets") {
+                    for target in targets {
+                        Self::merge_target(target, target_defaults);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds missing keys of the default raw target to the target one,
+    /// `error_status` makes sense with default `on_error` action only, so it's skipped if target has own action
+    pub(super) fn merge_target(target: &mut Value, default: &Value) {
+        if target.find_ref("on_error").is_some() {
+            let mut default = default.clone();
+            if let Value::Dict(_, dict) = &mut default {
+                dict.remove("error_status");
+            }
+            Self::merge(target, &default);
+        } else {
+            Self::merge(target, default);
+        }
+    }
+
+    /// Adds missing keys of the default value to the target one,
+    /// nested dictionaries are merged recursively, lists and other values aren't merged
+    fn merge(target: &mut Value, default: &Value) {
+        if let (Value::Dict(_, target), Value::Dict(_, default)) = (target, default) {
+            for (key, default) in default {
+                match target.get_mut(key) {
+                    Some(value) => Self::merge(value, default),
+                    None => {
+                        target.insert(key.clone(), default.clone());
+                    }
+                }
+            }
+        }
+    }
+}
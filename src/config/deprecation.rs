@@ -0,0 +1,80 @@
+use super::ConfigError;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer,
+};
+use std::time::SystemTime;
+
+/// Date after which deprecated target is going to be removed,
+/// it's either a date like `2025-12-31` (midnight UTC) or RFC 3339 timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sunset(SystemTime);
+
+impl Sunset {
+    fn from_str(value: &str) -> Result<Self, ConfigError> {
+        let timestamp = if value.len() == 10 {
+            format!("{value}T00:00:00Z")
+        } else {
+            value.to_string()
+        };
+
+        humantime::parse_rfc3339_weak(&timestamp)
+            .map(Self)
+            .map_err(|e| ConfigError::ValidateConfig {
+                cause: format!("invalid sunset date `{value}`: {e}"),
+            })
+    }
+
+    /// Returns sunset in HTTP-date format, as it's used in the `Sunset` header
+    pub fn http_date(&self) -> String {
+        httpdate::fmt_http_date(self.0)
+    }
+
+    /// Returns `true` if the date has passed already
+    pub fn is_passed(&self) -> bool {
+        self.0 <= SystemTime::now()
+    }
+}
+
+impl<'de> Deserialize<'de> for Sunset {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SunsetVisitor;
+        impl Visitor<'_> for SunsetVisitor {
+            type Value = Sunset;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("date like `2025-12-31` or RFC 3339 timestamp")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Sunset::from_str(v).map_err(|e| E::custom(e))
+            }
+        }
+
+        deserializer.deserialize_string(SunsetVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sunset() {
+        let sunset: Sunset = serde_json::from_str(r#""2025-12-31""#).unwrap();
+        assert_eq!(sunset.http_date(), "Wed, 31 Dec 2025 00:00:00 GMT");
+        assert!(sunset.is_passed());
+
+        let sunset: Sunset = serde_json::from_str(r#""2999-06-01T12:30:00Z""#).unwrap();
+        assert_eq!(sunset.http_date(), "Sat, 01 Jun 2999 12:30:00 GMT");
+        assert!(!sunset.is_passed());
+
+        assert!(serde_json::from_str::<Sunset>(r#""31.12.2025""#).is_err());
+    }
+}
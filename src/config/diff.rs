@@ -0,0 +1,204 @@
+use super::{listener::ListenerConfig, AppConfig};
+use figment::value::{Dict, Value};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Parameters which are applied when listener starts: its sockets, runtime, connections tracker and TLS termination
+const RESTART_REQUIRED_FIELDS: [&str; 7] = [
+    "listen_on",
+    "workers",
+    "reuse_port",
+    "socket",
+    "runtime",
+    "connections",
+    "server_tls",
+];
+
+/// Difference between the running config and the candidate one, listeners are matched by ID
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct ConfigDiff {
+    added: Vec<String>,
+    removed: Vec<String>,
+    changed: Vec<ListenerChange>,
+    sensitive_headers_changed: bool,
+}
+
+/// Changed listener with names of its changed top-level parameters
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ListenerChange {
+    id: String,
+    fields: Vec<String>,
+    restart_required: bool,
+}
+
+impl ConfigDiff {
+    pub(super) fn new(running: &AppConfig, candidate: &AppConfig) -> Self {
+        let mut diff = ConfigDiff {
+            sensitive_headers_changed: running.sensitive_headers != candidate.sensitive_headers,
+            ..Default::default()
+        };
+        for (pos, listener) in candidate.listeners.iter().enumerate() {
+            let Some(running_pos) = running
+                .listeners
+                .iter()
+                .position(|l| l.id() == listener.id())
+            else {
+                diff.added.push(listener.id());
+                continue;
+            };
+            let fields = changed_fields(
+                running.raw_listeners.get(running_pos),
+                candidate.raw_listeners.get(pos),
+            );
+            if fields.is_empty() {
+                continue;
+            }
+            // Retry queue and audit files are owned by the running listener until it's stopped
+            let restart_required = fields
+                .iter()
+                .any(|field| RESTART_REQUIRED_FIELDS.contains(&field.as_str()))
+                || owns_files(&running.listeners[running_pos])
+                || owns_files(listener);
+            diff.changed.push(ListenerChange {
+                id: listener.id(),
+                fields,
+                restart_required,
+            });
+        }
+        diff.removed = running
+            .listeners
+            .iter()
+            .map(ListenerConfig::id)
+            .filter(|id| !candidate.listeners.iter().any(|l| l.id() == *id))
+            .collect();
+
+        diff
+    }
+
+    /// Returns `true` if candidate config can't be applied without restart
+    pub fn is_restart_required(&self) -> bool {
+        !self.added.is_empty()
+            || !self.removed.is_empty()
+            || self.changed.iter().any(|change| change.restart_required)
+    }
+
+    /// Returns IDs of the changed listeners
+    pub fn changed(&self) -> impl Iterator<Item = &str> {
+        self.changed.iter().map(|change| change.id.as_str())
+    }
+
+    /// Returns diff as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self)
+            .expect("unable to serialize config diff, looks like a BUG")
+    }
+}
+
+/// Returns sorted names of the top-level parameters which differ
+fn changed_fields(running: Option<&Value>, candidate: Option<&Value>) -> Vec<String> {
+    let empty = Dict::new();
+    let as_dict = |value: Option<&Value>| match value {
+        Some(Value::Dict(_, dict)) => dict.clone(),
+        _ => empty.clone(),
+    };
+    let (running, candidate) = (as_dict(running), as_dict(candidate));
+
+    running
+        .keys()
+        .chain(candidate.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|key| running.get(*key) != candidate.get(*key))
+        .cloned()
+        .collect()
+}
+
+fn owns_files(listener: &ListenerConfig) -> bool {
+    listener.audit().is_some() || listener.targets().iter().any(|t| t.retry_queue().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ConfigFormat, context::test_context};
+
+    fn config(content: &str) -> AppConfig {
+        AppConfig::from_content(
+            content,
+            ConfigFormat::Yaml,
+            test_context::get_test_ctx(),
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn listeners_diff() {
+        let running = config(
+            r#"
+            listeners:
+              - id: same
+                listen_on: "*:8001"
+                targets: [{url: "https://example.com/"}]
+              - id: changed
+                listen_on: "*:8002"
+                targets: [{url: "https://example.com/"}]
+              - id: moved
+                listen_on: "*:8003"
+                targets: [{url: "https://example.com/"}]
+              - id: removed
+                listen_on: "*:8004"
+                targets: [{url: "https://example.com/"}]
+            "#,
+        );
+        let candidate = config(
+            r#"
+            listeners:
+              - id: same
+                listen_on: "*:8001"
+                targets: [{url: "https://example.com/"}]
+              - id: changed
+                listen_on: "*:8002"
+                timeout: 5s
+                targets: [{url: "https://example.org/"}]
+              - id: moved
+                listen_on: "*:8005"
+                targets: [{url: "https://example.com/"}]
+              - id: added
+                listen_on: "*:8006"
+                targets: [{url: "https://example.com/"}]
+            "#,
+        );
+
+        let diff = running.diff(&candidate);
+        assert_eq!(diff.added, ["added"]);
+        assert_eq!(diff.removed, ["removed"]);
+        assert_eq!(diff.changed().collect::<Vec<_>>(), ["changed", "moved"]);
+        assert_eq!(diff.changed[0].fields, ["targets", "timeout"]);
+        assert!(!diff.changed[0].restart_required);
+        assert!(diff.changed[1].restart_required);
+        assert!(!diff.sensitive_headers_changed);
+        assert!(diff.is_restart_required());
+
+        let diff = running.diff(&config(
+            r#"
+            listeners:
+              - id: same
+                listen_on: "*:8001"
+                targets: [{url: "https://example.com/"}]
+              - id: changed
+                listen_on: "*:8002"
+                strategy: always_override
+                targets: [{url: "https://example.com/"}]
+              - id: moved
+                listen_on: "*:8003"
+                targets: [{url: "https://example.com/"}]
+              - id: removed
+                listen_on: "*:8004"
+                targets: [{url: "https://example.com/"}]
+            "#,
+        ));
+        assert_eq!(diff.changed().collect::<Vec<_>>(), ["changed"]);
+        assert!(!diff.is_restart_required());
+    }
+}
@@ -0,0 +1,205 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{http::uri::Authority, Uri};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_DISCOVERY_REFRESH_INTERVAL_SEC: u64 = 30;
+const DEFAULT_CONSUL_ADDRESS: &str = "http://127.0.0.1:8500";
+
+/// Discovery of the target's instances, host and port of the target's URL are replaced
+/// with the address of one of the discovered instances
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    dns_srv: Option<String>,
+    kubernetes: Option<KubernetesDiscoveryConfig>,
+    consul: Option<ConsulDiscoveryConfig>,
+    file: Option<String>,
+    instances: Option<Vec<String>>,
+    #[serde(
+        with = "humantime_serde",
+        default = "DiscoveryConfig::default_refresh_interval"
+    )]
+    #[schemars(with = "Option<String>")]
+    refresh_interval: Duration,
+    #[serde(default)]
+    policy: BalancingPolicy,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    failure_cooldown: Option<Duration>,
+}
+
+/// Endpoints of the service in the same cluster, it's queried using in-cluster service account
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct KubernetesDiscoveryConfig {
+    namespace: Option<String>,
+    service: String,
+    port: Option<String>,
+}
+
+/// Healthy instances of the service registered in Consul
+#[derive(Deserialize, Debug, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ConsulDiscoveryConfig {
+    #[serde(default = "ConsulDiscoveryConfig::default_address")]
+    address: String,
+    service: String,
+    tag: Option<String>,
+    datacenter: Option<String>,
+    token: Option<String>,
+}
+
+/// Source of the target's instances
+pub enum DiscoverySource<'a> {
+    /// Name of the DNS SRV record
+    DnsSrv(&'a str),
+    Kubernetes(&'a KubernetesDiscoveryConfig),
+    Consul(&'a ConsulDiscoveryConfig),
+    /// Path to JSON or YAML file with the list of instances
+    File(&'a str),
+    /// Static list of the instances, like replicas of the target group
+    Static(&'a [String]),
+}
+
+/// How to select instance for each request
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalancingPolicy {
+    #[default]
+    RoundRobin,
+    Random,
+    /// Instance with the least number of requests waiting for response headers
+    LeastConnections,
+}
+
+impl DiscoveryConfig {
+    fn default_refresh_interval() -> Duration {
+        Duration::from_secs(DEFAULT_DISCOVERY_REFRESH_INTERVAL_SEC)
+    }
+
+    pub fn source(&self) -> DiscoverySource<'_> {
+        if let Some(name) = &self.dns_srv {
+            DiscoverySource::DnsSrv(name)
+        } else if let Some(kubernetes) = &self.kubernetes {
+            DiscoverySource::Kubernetes(kubernetes)
+        } else if let Some(consul) = &self.consul {
+            DiscoverySource::Consul(consul)
+        } else if let Some(path) = &self.file {
+            DiscoverySource::File(path)
+        } else if let Some(instances) = &self.instances {
+            DiscoverySource::Static(instances)
+        } else {
+            panic!("discovery source should be validated, looks like a BUG")
+        }
+    }
+
+    /// Returns how long the list of instances is used before the next discovery
+    pub fn refresh_interval(&self) -> Duration {
+        self.refresh_interval
+    }
+
+    pub fn policy(&self) -> BalancingPolicy {
+        self.policy
+    }
+
+    /// Returns how long the failed instance isn't selected, failures aren't tracked if it isn't defined
+    pub fn failure_cooldown(&self) -> Option<Duration> {
+        self.failure_cooldown
+    }
+}
+
+impl KubernetesDiscoveryConfig {
+    /// Returns namespace of the service, default is the namespace of the service account
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Returns name of the service port, the first one is used if it isn't defined
+    pub fn port(&self) -> Option<&str> {
+        self.port.as_deref()
+    }
+}
+
+impl ConsulDiscoveryConfig {
+    fn default_address() -> String {
+        DEFAULT_CONSUL_ADDRESS.into()
+    }
+
+    /// Returns URL of the Consul agent's HTTP API
+    pub fn address(&self) -> &str {
+        self.address.trim_end_matches('/')
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Returns tag to filter instances by
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    /// Returns datacenter to query, default is the agent's one
+    pub fn datacenter(&self) -> Option<&str> {
+        self.datacenter.as_deref()
+    }
+
+    /// Returns ACL token
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+impl ConfigValidator for DiscoveryConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let sources = [
+            self.dns_srv.is_some(),
+            self.kubernetes.is_some(),
+            self.consul.is_some(),
+            self.file.is_some(),
+            self.instances.is_some(),
+        ];
+        if sources.into_iter().filter(|defined| *defined).count() != 1 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "exactly one of `dns_srv`, `kubernetes`, `consul`, `file` or `instances` discovery sources should be defined".into(),
+            });
+        }
+        if let Some(instances) = &self.instances {
+            if instances.is_empty() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: "discovery `instances` should contain at least one instance".into(),
+                });
+            }
+            if let Some(instance) = instances.iter().find(|i| i.parse::<Authority>().is_err()) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid address of the discovery instance `{instance}`"),
+                });
+            }
+        }
+        if self.failure_cooldown == Some(Duration::ZERO) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "discovery `failure_cooldown` should be greater than zero".into(),
+            });
+        }
+        if let Some(consul) = &self.consul {
+            if consul.address().parse::<Uri>().is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid Consul address `{}`", consul.address),
+                });
+            }
+        }
+        if self.refresh_interval.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "discovery `refresh_interval` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Handling of the target's host name resolution failures
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TargetDnsConfig {
+    #[serde(default)]
+    on_failure: DnsFailureAction,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    negative_ttl: Option<Duration>,
+}
+
+/// What to do with the target if its host name can't be resolved
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsFailureAction {
+    /// Target is failed, its `on_error` action is applied
+    #[default]
+    Fail,
+    /// Target's response is dropped silently, it isn't counted as the target's failure
+    Skip,
+}
+
+impl TargetDnsConfig {
+    pub fn on_failure(&self) -> DnsFailureAction {
+        self.on_failure
+    }
+
+    /// Returns time to remember failed resolution of the host name, each request resolves it if it isn't defined
+    pub fn negative_ttl(&self) -> Option<Duration> {
+        self.negative_ttl
+    }
+}
+
+impl ConfigValidator for TargetDnsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.negative_ttl == Some(Duration::ZERO) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "dns `negative_ttl` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
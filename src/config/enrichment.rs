@@ -0,0 +1,320 @@
+use super::{body_transform::JqFilter, ConfigError, ConfigValidator};
+use hyper::{HeaderMap, Uri};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_ENRICHMENT_TIMEOUT_MSEC: u64 = 500;
+const DEFAULT_ENRICHMENT_CACHE_TTL_SEC: u64 = 60;
+const DEFAULT_ENRICHMENT_MAX_CACHE_ENTRIES: usize = 10000;
+const DEFAULT_ENRICHMENT_MAX_VALUE_SIZE: usize = 64 * 1024;
+const DEFAULT_REDIS_PORT: u16 = 6379;
+/// Placeholder of the request's key in the lookup URL and Redis key
+pub const ENRICHMENT_KEY_PLACEHOLDER: &str = "{key}";
+
+/// Enrichment of the request: key extracted from the request is looked up against external source,
+/// the result is exposed as `CTX_ENRICHMENT_*` context variables for conditions, headers and URLs
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct EnrichmentConfig {
+    key: EnrichmentKey,
+    http: Option<String>,
+    redis: Option<RedisLookupConfig>,
+    #[serde(
+        with = "humantime_serde",
+        default = "EnrichmentConfig::default_timeout"
+    )]
+    #[schemars(with = "Option<String>")]
+    timeout: Duration,
+    #[serde(
+        with = "humantime_serde",
+        default = "EnrichmentConfig::default_cache_ttl"
+    )]
+    #[schemars(with = "Option<String>")]
+    cache_ttl: Duration,
+    #[serde(default = "EnrichmentConfig::default_max_cache_entries")]
+    max_cache_entries: usize,
+    #[serde(default = "EnrichmentConfig::default_max_value_size")]
+    max_value_size: usize,
+    #[serde(default)]
+    on_error: EnrichmentErrorAction,
+}
+
+/// Where to take the key to look up from
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum EnrichmentKey {
+    /// Value of the request header
+    Header(String),
+    /// First output of JQ-like filter on the JSON body
+    Jq(#[schemars(with = "String")] JqFilter),
+}
+
+/// Value of the key is read by Redis `GET` command
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RedisLookupConfig {
+    url: String,
+    #[serde(default = "RedisLookupConfig::default_key")]
+    key: String,
+}
+
+/// Connection parameters of the Redis server, `AUTH` is sent if password is defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedisServer {
+    pub address: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub db: u32,
+}
+
+/// What to do with the request if lookup has failed, missing key or value isn't a failure
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrichmentErrorAction {
+    /// Request is processed without enrichment variables
+    #[default]
+    Continue,
+    /// Request is rejected with `502`
+    Reject,
+}
+
+/// Source of the enrichment values
+pub enum EnrichmentSource<'a> {
+    /// URL template to `GET` value from
+    Http(&'a str),
+    Redis(&'a RedisLookupConfig),
+}
+
+impl EnrichmentConfig {
+    fn default_timeout() -> Duration {
+        Duration::from_millis(DEFAULT_ENRICHMENT_TIMEOUT_MSEC)
+    }
+
+    fn default_cache_ttl() -> Duration {
+        Duration::from_secs(DEFAULT_ENRICHMENT_CACHE_TTL_SEC)
+    }
+
+    fn default_max_cache_entries() -> usize {
+        DEFAULT_ENRICHMENT_MAX_CACHE_ENTRIES
+    }
+
+    fn default_max_value_size() -> usize {
+        DEFAULT_ENRICHMENT_MAX_VALUE_SIZE
+    }
+
+    /// Returns key of the request to look up, `None` if request doesn't have it
+    pub fn key(&self, headers: &HeaderMap, body: &[u8]) -> Option<String> {
+        let key = match &self.key {
+            EnrichmentKey::Header(name) => headers.get(name)?.to_str().ok()?.to_string(),
+            EnrichmentKey::Jq(filter) => {
+                let json = serde_json::from_slice(body).ok()?;
+                match filter.first(json)? {
+                    serde_json::Value::Null => return None,
+                    serde_json::Value::String(key) => key,
+                    value => value.to_string(),
+                }
+            }
+        };
+
+        (!key.is_empty()).then_some(key)
+    }
+
+    pub fn source(&self) -> EnrichmentSource<'_> {
+        match (&self.http, &self.redis) {
+            (Some(url), _) => EnrichmentSource::Http(url),
+            (None, Some(redis)) => EnrichmentSource::Redis(redis),
+            (None, None) => panic!("enrichment source isn't defined, looks like a BUG"),
+        }
+    }
+
+    /// Returns time to wait for the lookup
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns time to keep looked up values, missing ones are cached as well
+    pub fn cache_ttl(&self) -> Duration {
+        self.cache_ttl
+    }
+
+    pub fn max_cache_entries(&self) -> usize {
+        self.max_cache_entries
+    }
+
+    /// Returns max size of the looked up value in bytes, larger values are lookup failures
+    pub fn max_value_size(&self) -> usize {
+        self.max_value_size
+    }
+
+    pub fn on_error(&self) -> EnrichmentErrorAction {
+        self.on_error
+    }
+}
+
+impl RedisLookupConfig {
+    fn default_key() -> String {
+        ENRICHMENT_KEY_PLACEHOLDER.into()
+    }
+
+    /// Returns Redis key template of the request's key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns connection parameters of the Redis URL: `redis://[[user]:password@]host[:port][/db]`,
+    /// user and password are percent-decoded
+    pub fn server(&self) -> Option<RedisServer> {
+        let uri: Uri = self.url.parse().ok()?;
+        if uri.scheme_str() != Some("redis") {
+            return None;
+        }
+        let authority = uri.authority()?;
+        let (user, password) = match authority.as_str().rsplit_once('@') {
+            Some((userinfo, _)) => match userinfo.split_once(':') {
+                Some((user, password)) => (percent_decode(user)?, percent_decode(password)?),
+                None => (percent_decode(userinfo)?, None),
+            },
+            None => (None, None),
+        };
+        let address = format!(
+            "{}:{}",
+            authority.host(),
+            authority.port_u16().unwrap_or(DEFAULT_REDIS_PORT)
+        );
+        let db = match uri.path().trim_start_matches('/') {
+            "" => 0,
+            db => db.parse().ok()?,
+        };
+
+        Some(RedisServer {
+            address,
+            user,
+            password,
+            db,
+        })
+    }
+}
+
+/// Returns `None` inside if the value is empty, outer `None` means invalid UTF-8
+fn percent_decode(value: &str) -> Option<Option<String>> {
+    let value = percent_encoding::percent_decode_str(value)
+        .decode_utf8()
+        .ok()?;
+    Some((!value.is_empty()).then(|| value.into_owned()))
+}
+
+impl ConfigValidator for EnrichmentConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.http.is_some() == self.redis.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "exactly one of enrichment `http` or `redis` should be defined".into(),
+            });
+        }
+        if let EnrichmentKey::Header(name) = &self.key {
+            if hyper::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid enrichment key header name `{name}`"),
+                });
+            }
+        }
+        if let Some(url) = &self.http {
+            let uri = url
+                .replace(ENRICHMENT_KEY_PLACEHOLDER, "key")
+                .parse::<Uri>();
+            if uri.map_or(true, |uri| uri.host().is_none()) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid enrichment `http` url `{url}`"),
+                });
+            }
+        }
+        if let Some(redis) = &self.redis {
+            if redis.server().is_none() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "invalid enrichment `redis` url `{}`, it should be `redis://[[user]:password@]host[:port][/db]`",
+                        redis.url
+                    ),
+                });
+            }
+        }
+        if self.timeout.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "enrichment `timeout` should be greater than zero".into(),
+            });
+        }
+        if self.max_cache_entries == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "enrichment `max_cache_entries` should be greater than zero".into(),
+            });
+        }
+        if self.max_value_size == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "enrichment `max_value_size` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_key() {
+        let cfg: EnrichmentConfig = serde_json::from_str(
+            r#"{"key": {"jq": ".tenant.id"}, "http": "http://mapping/tenants/{key}"}"#,
+        )
+        .unwrap();
+        assert!(cfg.validate().is_ok());
+        let headers = HeaderMap::new();
+        assert_eq!(
+            cfg.key(&headers, br#"{"tenant": {"id": "acme"}}"#),
+            Some("acme".into())
+        );
+        assert_eq!(
+            cfg.key(&headers, br#"{"tenant": {"id": 42}}"#),
+            Some("42".into())
+        );
+        assert_eq!(cfg.key(&headers, br#"{"user": 1}"#), None);
+        assert_eq!(cfg.key(&headers, b"not json"), None);
+    }
+
+    #[test]
+    fn redis_server() {
+        let redis = |url: &str| RedisLookupConfig {
+            url: url.into(),
+            key: RedisLookupConfig::default_key(),
+        };
+        let server = |address: &str, user: Option<&str>, password: Option<&str>, db| {
+            Some(RedisServer {
+                address: address.into(),
+                user: user.map(Into::into),
+                password: password.map(Into::into),
+                db,
+            })
+        };
+        assert_eq!(
+            redis("redis://cache.local").server(),
+            server("cache.local:6379", None, None, 0)
+        );
+        assert_eq!(
+            redis("redis://:secret@127.0.0.1:6380/2").server(),
+            server("127.0.0.1:6380", None, Some("secret"), 2)
+        );
+        // ACL user, password is split by the first colon and both are percent-decoded
+        assert_eq!(
+            redis("redis://app%40eu:p%40ss:w0rd@cache.local").server(),
+            server("cache.local:6379", Some("app@eu"), Some("p@ss:w0rd"), 0)
+        );
+        assert_eq!(
+            redis("redis://app@cache.local").server(),
+            server("cache.local:6379", Some("app"), None, 0)
+        );
+        assert_eq!(redis("redis://:%FF@cache.local").server(), None);
+        assert_eq!(redis("http://cache.local").server(), None);
+        assert_eq!(redis("redis://cache.local/db").server(), None);
+    }
+}
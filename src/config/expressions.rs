@@ -0,0 +1,75 @@
+use super::ConfigError;
+use figment::value::{Dict, Value};
+use std::collections::HashMap;
+
+/// Key of the parameter which refers to the named expression instead of the literal value
+pub const EXPRESSION_KEY: &str = "expression";
+
+/// Named regexes and JQ-like snippets, any string parameter may refer to them as `{expression: name}`.
+/// Used to validate `expressions` section only, references are resolved in the raw config before deserialization.
+pub type Expressions = HashMap<String, String>;
+
+/// Replaces all references to the named expressions in the raw config value with the expressions themselves
+pub fn resolve(expressions: &Dict, value: &mut Value) -> Result<(), ConfigError> {
+    match value {
+        Value::Dict(_, dict) => {
+            if let Some(name) = reference(dict) {
+                let expression =
+                    expressions
+                        .get(&name)
+                        .ok_or_else(|| ConfigError::ValidateConfig {
+                            cause: format!("expression `{name}` isn't defined"),
+                        })?;
+                *value = expression.clone();
+                return Ok(());
+            }
+            dict.values_mut()
+                .try_for_each(|value| resolve(expressions, value))
+        }
+        Value::Array(_, array) => array
+            .iter_mut()
+            .try_for_each(|value| resolve(expressions, value)),
+        _ => Ok(()),
+    }
+}
+
+/// Returns name of the expression if the dictionary is a reference, i.e. `{expression: name}`
+fn reference(dict: &Dict) -> Option<String> {
+    if dict.len() != 1 {
+        return None;
+    }
+
+    dict.get(EXPRESSION_KEY)?.as_str().map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resolve_references() {
+        let mut expressions = Dict::new();
+        expressions.insert("server_errors".into(), Value::from("5\\d\\d"));
+        let mut value = Value::serialize(json!({
+            "regex": {"expression": "server_errors"},
+            "list": [{"expression": "server_errors"}, "literal"],
+            "other": {"expression": "server_errors", "extra": true},
+        }))
+        .unwrap();
+
+        resolve(&expressions, &mut value).unwrap();
+        let resolved: serde_json::Value = value.deserialize().unwrap();
+        assert_eq!(
+            resolved,
+            json!({
+                "regex": "5\\d\\d",
+                "list": ["5\\d\\d", "literal"],
+                "other": {"expression": "server_errors", "extra": true},
+            })
+        );
+
+        let mut value = Value::serialize(json!({"expression": "unknown"})).unwrap();
+        assert!(resolve(&expressions, &mut value).is_err());
+    }
+}
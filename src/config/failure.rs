@@ -0,0 +1,231 @@
+use super::{
+    body_transform::JqFilter,
+    response::{ResponseBehavior, ResponseStatus, StatusRegex},
+    AppConfig, ConfigError, ConfigValidator,
+};
+use crate::streaming::{full_bytes, Trailers};
+use http_body_util::Full;
+use hyper::{body::Bytes, Response};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+
+/// Time to the response headers of the target, it's attached to the target's response
+#[derive(Clone, Copy, Debug)]
+pub struct Elapsed(pub Duration);
+
+/// Classifies target's response as failed or ok for the response strategies
+pub trait FailureClassifier {
+    /// Returns `None` if classifier can't decide, so the response is classified by the next one
+    fn is_failed(&self, resp: &Response<Full<Bytes>>) -> Option<bool>;
+}
+
+/// Classifies responses of the single target by the same chain as response strategies do:
+/// target's failure policy, then listener's `failure_detection` and `failed_status_regex`.
+/// It owns the config, so it's used by SLOs, sampling, target selection, retry queues and discovery
+#[derive(Clone)]
+pub struct TargetFailureClassifier {
+    app_config: Arc<AppConfig>,
+    listener_index: usize,
+    target_id: String,
+}
+
+impl std::fmt::Debug for TargetFailureClassifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TargetFailureClassifier")
+            .field("listener_index", &self.listener_index)
+            .field("target_id", &self.target_id)
+            .finish()
+    }
+}
+
+impl TargetFailureClassifier {
+    pub fn new(app_config: Arc<AppConfig>, listener_index: usize, target_id: String) -> Self {
+        Self {
+            app_config,
+            listener_index,
+            target_id,
+        }
+    }
+
+    /// Returns `true` if the response is failed
+    pub fn is_failed(&self, resp: &Response<Full<Bytes>>) -> bool {
+        let listener = &self.app_config.listeners()[self.listener_index];
+        let target = listener
+            .targets()
+            .iter()
+            .find(|target| target.id() == self.target_id);
+
+        listener.response().is_failed(resp, target)
+    }
+
+    /// Classifies response by its status and headers before the body is received,
+    /// classifiers which need body or trailers can't decide, so the next ones are applied
+    pub fn is_failed_head<B>(&self, resp: &Response<B>, elapsed: Duration) -> bool {
+        let mut head = Response::new(Full::default());
+        *head.status_mut() = resp.status();
+        *head.headers_mut() = resp.headers().clone();
+        head.extensions_mut().insert(Elapsed(elapsed));
+
+        self.is_failed(&head)
+    }
+}
+
+/// Target's own policy to classify its responses, it takes precedence over the listener's `failure_detection`
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum FailurePolicy {
+    /// Response status matches the regex
+    StatusRegex(#[schemars(with = "String")] StatusRegex),
+    /// Response status is one of the list
+    StatusList(Vec<ResponseStatus>),
+    /// Response headers are received later than the threshold, faster responses are classified by status
+    LatencyThreshold(
+        #[serde(with = "humantime_serde")]
+        #[schemars(with = "String")]
+        Duration,
+    ),
+    /// JQ-like filter returns `true` on the JSON body, other responses are classified by status
+    Jq(#[schemars(with = "String")] JqFilter),
+    /// Non-zero `grpc-status` trailer (or header), responses without it are classified by status
+    GrpcStatus,
+}
+
+/// Non-zero `grpc-status` trailer (or header) of the response
+pub struct GrpcStatus;
+
+impl FailureClassifier for StatusRegex {
+    fn is_failed(&self, resp: &Response<Full<Bytes>>) -> Option<bool> {
+        Some(self.is_match(resp.status().as_str()))
+    }
+}
+
+impl FailureClassifier for GrpcStatus {
+    fn is_failed(&self, resp: &Response<Full<Bytes>>) -> Option<bool> {
+        grpc_status(resp).map(|status| status != "0")
+    }
+}
+
+impl FailureClassifier for FailurePolicy {
+    fn is_failed(&self, resp: &Response<Full<Bytes>>) -> Option<bool> {
+        match self {
+            FailurePolicy::StatusRegex(regex) => regex.is_failed(resp),
+            FailurePolicy::StatusList(list) => Some(list.contains(&resp.status().as_u16())),
+            FailurePolicy::LatencyThreshold(threshold) => resp
+                .extensions()
+                .get::<Elapsed>()
+                .filter(|Elapsed(elapsed)| elapsed > threshold)
+                .map(|_| true),
+            FailurePolicy::Jq(filter) => {
+                let body = full_bytes(resp.body());
+                serde_json::from_slice(&body)
+                    .ok()
+                    .filter(|json| filter.is_true(json))
+                    .map(|_| true)
+            }
+            FailurePolicy::GrpcStatus => GrpcStatus.is_failed(resp),
+        }
+    }
+}
+
+impl ConfigValidator for FailurePolicy {
+    fn validate(&self) -> Result<(), ConfigError> {
+        match self {
+            FailurePolicy::StatusList(list) if list.is_empty() => {
+                Err(ConfigError::ValidateConfig {
+                    cause: "failure policy `status_list` should contain at least one status".into(),
+                })
+            }
+            FailurePolicy::LatencyThreshold(threshold) if threshold.is_zero() => {
+                Err(ConfigError::ValidateConfig {
+                    cause: "failure policy `latency_threshold` should be greater than zero".into(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns `grpc-status` of the response: from trailers or from headers in case of trailers-only response
+fn grpc_status(resp: &Response<Full<Bytes>>) -> Option<String> {
+    resp.extensions()
+        .get::<Trailers>()
+        .and_then(|Trailers(trailers)| trailers.get(GRPC_STATUS_HEADER))
+        .or_else(|| resp.headers().get(GRPC_STATUS_HEADER))
+        .map(|status| status.to_str().unwrap_or_default().trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str, elapsed: Duration) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(status)
+            .extension(Elapsed(elapsed))
+            .body(Full::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[test]
+    fn policies() {
+        let fast_ok = response(200, r#"{"status": "ok"}"#, Duration::from_millis(10));
+        let slow_error = response(404, r#"{"status": "error"}"#, Duration::from_secs(3));
+
+        let policy: FailurePolicy = serde_json::from_str(r#"{"status_list": [404, 503]}"#).unwrap();
+        assert_eq!(policy.is_failed(&fast_ok), Some(false));
+        assert_eq!(policy.is_failed(&slow_error), Some(true));
+
+        let policy: FailurePolicy = serde_json::from_str(r#"{"latency_threshold": "2s"}"#).unwrap();
+        assert_eq!(policy.is_failed(&fast_ok), None);
+        assert_eq!(policy.is_failed(&slow_error), Some(true));
+
+        let policy: FailurePolicy =
+            serde_json::from_str(r#"{"jq": ".status == \"error\""}"#).unwrap();
+        assert_eq!(policy.is_failed(&fast_ok), None);
+        assert_eq!(policy.is_failed(&slow_error), Some(true));
+
+        let policy: FailurePolicy = serde_json::from_str(r#""grpc_status""#).unwrap();
+        assert_eq!(policy.is_failed(&fast_ok), None);
+    }
+
+    #[test]
+    fn target_classifier() {
+        use crate::{
+            config::ConfigFormat,
+            context::{Context, RootOsEnvironment},
+        };
+
+        let config = r#"
+            listeners:
+              - targets:
+                  - id: policy
+                    url: http://localhost:3000/
+                    failure_policy:
+                      jq: .status == "error"
+                  - id: listener
+                    url: http://localhost:3001/
+                response:
+                  failed_status_regex: "5\\d{2}"
+        "#;
+        let ctx = Context::root(RootOsEnvironment::new("^PATH$"));
+        let app_config =
+            Arc::new(AppConfig::from_content(config, ConfigFormat::Yaml, &ctx, false).unwrap());
+        let policy = TargetFailureClassifier::new(app_config.clone(), 0, "policy".into());
+        let listener = TargetFailureClassifier::new(app_config, 0, "listener".into());
+
+        let error = response(200, r#"{"status": "error"}"#, Duration::ZERO);
+        let not_found = response(404, "", Duration::ZERO);
+        let unavailable = response(503, "", Duration::ZERO);
+
+        assert!(policy.is_failed(&error));
+        assert!(!listener.is_failed(&error));
+        assert!(!policy.is_failed(&not_found));
+        assert!(policy.is_failed(&unavailable));
+        // Body isn't available before it's received
+        assert!(!policy.is_failed_head(&error, Duration::ZERO));
+        assert!(listener.is_failed_head(&unavailable, Duration::ZERO));
+    }
+}
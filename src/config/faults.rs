@@ -0,0 +1,112 @@
+use super::{response::ResponseStatus, ConfigError, ConfigValidator};
+use hyper::StatusCode;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Header which is added to the responses of the aborted requests
+pub const FAULT_HEADER: &str = "x-splitter-fault";
+
+/// Global switch of the fault injection, it's controlled by the admin API
+static FAULTS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Faults which are injected into the requests before forwarding them
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct FaultsConfig {
+    delay: Option<DelayFaultConfig>,
+    abort: Option<AbortFaultConfig>,
+}
+
+/// Delays request for `duration` plus random value up to `jitter` with configured probability
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DelayFaultConfig {
+    probability: f64,
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    duration: Duration,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    jitter: Duration,
+}
+
+/// Responds with `status` instead of forwarding request with configured probability
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AbortFaultConfig {
+    probability: f64,
+    status: ResponseStatus,
+}
+
+impl FaultsConfig {
+    /// Returns `true` if faults are injected, it's possible to switch them off in runtime
+    pub fn is_enabled() -> bool {
+        FAULTS_ENABLED.load(Ordering::Relaxed)
+    }
+
+    /// Enables or disables injection of all configured faults
+    pub fn set_enabled(enabled: bool) {
+        FAULTS_ENABLED.store(enabled, Ordering::Relaxed)
+    }
+
+    /// Returns delay to inject into the current request, if any
+    pub fn delay(&self) -> Option<Duration> {
+        let delay = self.delay.as_ref()?;
+        if !Self::is_enabled() || !Self::happened(delay.probability) {
+            return None;
+        }
+
+        let jitter = delay.jitter.as_millis() as u64;
+        let jitter = if jitter > 0 {
+            Duration::from_millis(fastrand::u64(0..=jitter))
+        } else {
+            Duration::ZERO
+        };
+        Some(delay.duration + jitter)
+    }
+
+    /// Returns status to abort the current request with, if any
+    pub fn abort(&self) -> Option<StatusCode> {
+        let abort = self.abort.as_ref()?;
+        if !Self::is_enabled() || !Self::happened(abort.probability) {
+            return None;
+        }
+
+        StatusCode::from_u16(abort.status).ok()
+    }
+
+    fn happened(probability: f64) -> bool {
+        probability >= 1.0 || fastrand::f64() < probability
+    }
+}
+
+impl ConfigValidator for FaultsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let probabilities = [
+            self.delay.as_ref().map(|d| ("delay", d.probability)),
+            self.abort.as_ref().map(|a| ("abort", a.probability)),
+        ];
+        for (fault, probability) in probabilities.into_iter().flatten() {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`{fault}` fault `probability` should be in range 0.0..1.0, but it's {probability}"
+                    ),
+                });
+            }
+        }
+        if let Some(abort) = &self.abort {
+            if !(100..=599).contains(&abort.status) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid `abort` fault status {}", abort.status),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use super::{server_tls::ServerTlsConfig, ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Listener acts as an explicit forward proxy: requests in absolute form (`GET http://host/path`)
+/// and requests inside `CONNECT` tunnels are split to the listener's targets
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ForwardProxyConfig {
+    #[serde(default)]
+    hosts: Vec<String>,
+    #[serde(default = "ForwardProxyConfig::default_connect")]
+    connect: bool,
+    tunnel_tls: Option<ServerTlsConfig>,
+}
+
+impl ForwardProxyConfig {
+    fn default_connect() -> bool {
+        true
+    }
+
+    /// Returns `true` if requests to the host may be proxied,
+    /// any host is allowed if list is empty, `*.example.com` matches subdomains of `example.com`
+    pub fn is_host_allowed(&self, host: &str) -> bool {
+        self.hosts.is_empty()
+            || self
+                .hosts
+                .iter()
+                .any(|allowed| match allowed.strip_prefix('*') {
+                    Some(suffix) => {
+                        host.len() > suffix.len()
+                            && host[host.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+                    }
+                    None => host.eq_ignore_ascii_case(allowed),
+                })
+    }
+
+    /// Returns `true` if `CONNECT` tunnels are accepted
+    pub fn connect(&self) -> bool {
+        self.connect
+    }
+
+    /// Returns certificate to terminate TLS inside the tunnels,
+    /// without it only plaintext HTTP is accepted inside the tunnels
+    pub fn tunnel_tls(&self) -> Option<&ServerTlsConfig> {
+        self.tunnel_tls.as_ref()
+    }
+}
+
+impl ConfigValidator for ForwardProxyConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for host in &self.hosts {
+            let name = host.strip_prefix("*.").unwrap_or(host);
+            if name.is_empty() || name.contains(['*', '/', ':']) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid forward proxy host `{host}`, it should be a host name or `*.domain`"),
+                });
+            }
+        }
+        if self.tunnel_tls.is_some() && !self.connect {
+            return Err(ConfigError::ValidateConfig {
+                cause: "forward proxy `tunnel_tls` requires `connect` to be enabled".into(),
+            });
+        }
+        if let Some(tunnel_tls) = &self.tunnel_tls {
+            if tunnel_tls.acme().is_some() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: "forward proxy `tunnel_tls` doesn't support `acme` certificate".into(),
+                });
+            }
+            tunnel_tls.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_hosts() {
+        let cfg: ForwardProxyConfig =
+            serde_json::from_str(r#"{"hosts": ["api.example.com", "*.test.local"]}"#).unwrap();
+        assert!(cfg.validate().is_ok());
+        assert!(cfg.is_host_allowed("API.example.com"));
+        assert!(cfg.is_host_allowed("svc.test.local"));
+        assert!(!cfg.is_host_allowed("test.local"));
+        assert!(!cfg.is_host_allowed("example.com"));
+
+        let cfg: ForwardProxyConfig = serde_json::from_str("{}").unwrap();
+        assert!(cfg.is_host_allowed("any.host"));
+        assert!(cfg.connect());
+
+        let cfg: ForwardProxyConfig = serde_json::from_str(r#"{"hosts": ["*"]}"#).unwrap();
+        assert!(cfg.validate().is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use super::normalization::strip_hop_by_hop_headers;
+use hyper::{
+    header::{HeaderValue, CONTENT_LENGTH, TRAILER},
+    HeaderMap,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Policy of the headers and trailers forwarding to the targets
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ForwardingConfig {
+    #[serde(default = "ForwardingConfig::default_strip_hop_by_hop_headers")]
+    strip_hop_by_hop_headers: bool,
+    #[serde(default)]
+    trailers: bool,
+}
+
+impl ForwardingConfig {
+    fn default_strip_hop_by_hop_headers() -> bool {
+        true
+    }
+
+    /// Returns `true` if trailers of the requester should be forwarded to targets
+    pub fn trailers(&self) -> bool {
+        self.trailers
+    }
+
+    /// Prepares headers of the target request: drops hop-by-hop headers if it's configured,
+    /// and declares forwarded trailers, so the request is chunked and trailers are sent after the body
+    pub fn apply(&self, headers: &mut HeaderMap, trailers: Option<&HeaderMap>) {
+        if self.strip_hop_by_hop_headers {
+            strip_hop_by_hop_headers(headers);
+        }
+        match trailers {
+            Some(trailers) if !trailers.is_empty() => {
+                let names = trailers
+                    .keys()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                headers.remove(CONTENT_LENGTH);
+                if let Ok(names) = HeaderValue::from_str(&names) {
+                    headers.insert(TRAILER, names);
+                }
+            }
+            _ => {
+                headers.remove(TRAILER);
+            }
+        }
+    }
+}
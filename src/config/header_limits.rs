@@ -0,0 +1,81 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::HeaderMap;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Limits of the request headers, size of each header is a sum of its name and value lengths
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HeaderLimitsConfig {
+    max_count: Option<usize>,
+    max_size: Option<usize>,
+}
+
+impl HeaderLimitsConfig {
+    /// Verifies headers against the limits, returns description of the exceeded limit
+    pub fn check(&self, headers: &HeaderMap) -> Result<(), String> {
+        let count = headers.len();
+        if let Some(max_count) = self.max_count {
+            if count > max_count {
+                return Err(format!("{count} headers, limit is {max_count}"));
+            }
+        }
+        if let Some(max_size) = self.max_size {
+            let size: usize = headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum();
+            if size > max_size {
+                return Err(format!("{size} bytes of headers, limit is {max_size}"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigValidator for HeaderLimitsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_count.is_none() && self.max_size.is_none() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one of `max_count` or `max_size` header limits should be defined"
+                    .into(),
+            });
+        }
+        if self.max_count == Some(0) || self.max_size == Some(0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "header limits should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::{HeaderValue, COOKIE, HOST};
+
+    #[test]
+    fn check_header_limits() {
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("localhost"));
+        headers.append(COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+
+        let limits = |cfg| serde_json::from_str::<HeaderLimitsConfig>(cfg).unwrap();
+        assert_eq!(
+            limits(r#"{"max_count": 3, "max_size": 31}"#).check(&headers),
+            Ok(())
+        );
+        assert_eq!(
+            limits(r#"{"max_count": 2}"#).check(&headers),
+            Err("3 headers, limit is 2".into())
+        );
+        assert_eq!(
+            limits(r#"{"max_size": 30}"#).check(&headers),
+            Err("31 bytes of headers, limit is 30".into())
+        );
+    }
+}
@@ -1,29 +1,49 @@
-use crate::context::Context;
+use super::script::{context_map, headers_map, Script};
+use crate::{context::Context, redaction};
 use hyper::{
     header::{HeaderName, HeaderValue},
     HeaderMap,
 };
+use rhai::{Map, Scope};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Deserializer, Serialize,
 };
 use shellexpand::env_with_context_no_errors;
-use tracing::debug;
+use tracing::{debug, warn};
 
 pub type HeadersTransformsList = Vec<HeaderTransform>;
 
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Clone, PartialEq, Serialize)]
 pub struct HeaderTransform {
     action: HeaderTransformActon,
     value: Option<String>,
 }
 
+/// Value of the sensitive header is masked, since config dump is logged
+impl std::fmt::Debug for HeaderTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match &self.action {
+            HeaderTransformActon::Add(name) | HeaderTransformActon::Update(name) => self
+                .value
+                .as_deref()
+                .map(|value| redaction::value(name, value)),
+            _ => self.value.as_deref(),
+        };
+        f.debug_struct("HeaderTransform")
+            .field("action", &self.action)
+            .field("value", &value)
+            .finish()
+    }
+}
+
 impl HeaderTransform {
-    fn action(&self) -> &HeaderTransformActon {
+    pub fn action(&self) -> &HeaderTransformActon {
         &self.action
     }
 
-    fn value(&self) -> Option<&String> {
+    pub fn value(&self) -> Option<&String> {
         self.value.as_ref()
     }
 }
@@ -34,6 +54,7 @@ pub enum HeaderTransformActon {
     Add(String),
     Update(String),
     Drop(String),
+    Script(Script),
 }
 
 impl<'de> Deserialize<'de> for HeaderTransform {
@@ -47,6 +68,7 @@ impl<'de> Deserialize<'de> for HeaderTransform {
             Drop,
             Add,
             Update,
+            Script,
             Value,
         }
 
@@ -86,6 +108,12 @@ impl<'de> Deserialize<'de> for HeaderTransform {
                             }
                             action = Some(HeaderTransformActon::Update(map.next_value::<String>()?))
                         }
+                        Fields::Script => {
+                            if action.is_some() {
+                                return Err(de::Error::duplicate_field("script"));
+                            }
+                            action = Some(HeaderTransformActon::Script(map.next_value::<Script>()?))
+                        }
                         Fields::Value => {
                             if value.is_some() {
                                 return Err(de::Error::duplicate_field("value"));
@@ -109,21 +137,57 @@ impl<'de> Deserialize<'de> for HeaderTransform {
                                 ));
                             }
                         }
+                        HeaderTransformActon::Script(_) => {
+                            if value.is_some() {
+                                return Err(de::Error::custom(
+                                    "unknown field `value` in action script",
+                                ));
+                            }
+                        }
                     }
                     Ok(HeaderTransform { action, value })
                 } else {
                     Err(de::Error::missing_field(
-                        "action should be one of add/drop/update",
+                        "action should be one of add/drop/update/script",
                     ))
                 }
             }
         }
 
-        const FIELDS: &[&str] = &["add", "drop", "update", "value"];
+        const FIELDS: &[&str] = &["add", "drop", "update", "script", "value"];
         deserializer.deserialize_struct("HeaderAction", FIELDS, HeaderTransformVisitor)
     }
 }
 
+// Shape of the HeaderTransform as it's written in the config, used to generate JSON schema only
+/// Header transformation: exactly one of `add`, `update`, `drop` or `script` action
+#[derive(JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct HeaderTransformSchema {
+    /// Name of the header to add
+    add: Option<String>,
+    /// Name of the header to update
+    update: Option<String>,
+    /// Name of the header to drop, `*` means all headers
+    drop: Option<String>,
+    /// Rhai script which changes `headers` (or `query` for query transformations) map,
+    /// context variables are in `ctx` map
+    script: Option<String>,
+    /// New header value, required for `add` and `update` actions
+    value: Option<String>,
+}
+
+impl JsonSchema for HeaderTransform {
+    fn schema_name() -> String {
+        "HeaderTransform".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        HeaderTransformSchema::json_schema(gen)
+    }
+}
+
 pub trait HeadersTransformator<'a> {
     fn transform(&'a self, headers: &'a mut HeaderMap, ctx: &Context);
 }
@@ -135,28 +199,43 @@ impl<'a> HeadersTransformator<'a> for HeadersTransformsList {
                 HeaderTransformActon::Add(key) => {
                     if !headers.contains_key(key.clone()) {
                         let value = transform.value().as_ref().unwrap().as_str();
-                        let value = env_with_context_no_errors(value, |v| ctx.get(&v.into()));
+                        let value = env_with_context_no_errors(value, |v| ctx.lookup(v));
                         let key = HeaderName::from_bytes(key.as_bytes()).unwrap();
-                        debug!("add: name={key}, value={value}");
-                        headers.insert(&key, HeaderValue::from_str(&value).unwrap());
+                        let Ok(header_value) = HeaderValue::from_str(&value) else {
+                            warn!("add: invalid value of header `{key}` is skipped");
+                            continue;
+                        };
+                        debug!(
+                            "add: name={key}, value={}",
+                            redaction::value(key.as_str(), &value)
+                        );
+                        headers.insert(&key, header_value);
                     }
                 }
                 HeaderTransformActon::Update(key) => {
                     if headers.contains_key(&key) {
                         let value = transform.value().as_ref().unwrap().as_str();
-                        let value = env_with_context_no_errors(value, |v| ctx.get(&v.into()));
+                        let value = env_with_context_no_errors(value, |v| ctx.lookup(v));
                         let debug_key = key.clone();
                         let key = HeaderName::from_bytes(key.as_bytes()).unwrap();
-                        let old = headers.insert(key, HeaderValue::from_str(&value).unwrap());
+                        let Ok(header_value) = HeaderValue::from_str(&value) else {
+                            warn!("update: invalid value of header `{key}` is skipped");
+                            continue;
+                        };
+                        let old = headers.insert(key, header_value);
                         if let Some(old) = old {
                             debug!(
                                 "update: name={}, old={}, new={}",
                                 debug_key,
-                                old.to_str().unwrap(),
-                                value
+                                redaction::value(&debug_key, old.to_str().unwrap()),
+                                redaction::value(&debug_key, &value)
                             );
                         } else {
-                            debug!("update: name={}, old=, new={}", debug_key, value);
+                            debug!(
+                                "update: name={}, old=, new={}",
+                                debug_key,
+                                redaction::value(&debug_key, &value)
+                            );
                         }
                     }
                 }
@@ -168,17 +247,67 @@ impl<'a> HeadersTransformator<'a> for HeadersTransformsList {
                         let debug_key = key.clone();
                         let old = headers.remove(key);
                         if let Some(old) = old {
-                            debug!("drop: name={}, old={}", debug_key, old.to_str().unwrap());
+                            debug!(
+                                "drop: name={}, old={}",
+                                debug_key,
+                                redaction::value(&debug_key, old.to_str().unwrap())
+                            );
                         } else {
                             debug!("drop: name={}, old=", debug_key);
                         }
                     }
                 }
+                HeaderTransformActon::Script(script) => transform_by_script(&script, headers, ctx),
             };
         }
     }
 }
 
+/// Runs script with `headers` and `ctx` maps and applies changes of `headers` map:
+/// removed headers are dropped, added or changed ones are set, untouched ones are kept as is
+fn transform_by_script(script: &Script, headers: &mut HeaderMap, ctx: &Context) {
+    let original = headers_map(headers);
+    let mut scope = Scope::new();
+    scope.push("headers", original.clone());
+    scope.push_constant("ctx", context_map(ctx));
+
+    if let Err(e) = script.run(&mut scope) {
+        warn!("script: unable to transform headers, they're kept as is: {e}");
+        return;
+    }
+    let Some(changed) = scope.get_value::<Map>("headers") else {
+        warn!("script: `headers` isn't a map anymore, headers are kept as is");
+        return;
+    };
+
+    for name in original.keys().filter(|name| !changed.contains_key(*name)) {
+        debug!("script: drop name={name}");
+        headers.remove(name.as_str());
+    }
+    for (name, value) in changed {
+        let value = value.to_string();
+        if original
+            .get(&name)
+            .is_some_and(|old| old.to_string() == value)
+        {
+            continue;
+        }
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            (Ok(key), Ok(value)) => {
+                debug!(
+                    "script: set name={key}, value={:?}",
+                    redaction::value(key.as_str(), value.to_str().unwrap_or_default())
+                );
+                headers.insert(key, value);
+            }
+            _ => warn!("script: invalid header `{name}: {value}` is skipped"),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use insta::{assert_debug_snapshot, assert_ron_snapshot};
@@ -262,4 +391,27 @@ mod tests {
         // After transformation
         assert_debug_snapshot!(headers);
     }
+
+    #[test]
+    fn script_transform() {
+        let ctx = get_test_ctx();
+        let transforms: HeadersTransformsList = serde_json::from_str(
+            r#"[{"script": "if \"x-drop\" in headers { headers.remove(\"x-drop\"); headers[\"x-env\"] = ctx.TEST_ENV_HEADER_TO_ADD; }"}]"#,
+        )
+        .unwrap();
+        assert!(
+            serde_json::from_str::<HeaderTransform>(r#"{"script": "1", "value": "1"}"#).is_err()
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-drop", "1".parse().unwrap());
+        headers.append("x-multi", "1".parse().unwrap());
+        headers.append("x-multi", "2".parse().unwrap());
+
+        transforms.transform(&mut headers, ctx);
+        assert_eq!(headers.get("x-drop"), None);
+        assert_eq!(headers["x-env"], "TEST_ENV_HEADER_VALUE");
+        // Untouched header keeps all its values
+        assert_eq!(headers.get_all("x-multi").iter().count(), 2);
+    }
 }
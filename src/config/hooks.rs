@@ -0,0 +1,105 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::Uri;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_HOOK_TIMEOUT_SEC: u64 = 10;
+
+/// Hooks which are called on the lifecycle events of the process, in order of declaration
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HooksConfig {
+    #[serde(default)]
+    startup: Vec<HookConfig>,
+    #[serde(default)]
+    shutdown: Vec<HookConfig>,
+}
+
+/// Single hook: HTTP callback or command
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    url: Option<String>,
+    command: Option<Vec<String>>,
+    #[serde(with = "humantime_serde", default = "HookConfig::default_timeout")]
+    #[schemars(with = "Option<String>")]
+    timeout: Duration,
+}
+
+/// What the hook does
+pub enum HookAction<'a> {
+    /// Endpoint to `POST` the event to
+    Http(&'a str),
+    /// Program and its arguments to execute
+    Command(&'a [String]),
+}
+
+impl HooksConfig {
+    /// Returns hooks which are called when all listeners are ready to serve requests
+    pub fn startup(&self) -> &[HookConfig] {
+        &self.startup
+    }
+
+    /// Returns hooks which are called on the shutdown signal, while listeners complete requests in progress
+    pub fn shutdown(&self) -> &[HookConfig] {
+        &self.shutdown
+    }
+}
+
+impl HookConfig {
+    fn default_timeout() -> Duration {
+        Duration::from_secs(DEFAULT_HOOK_TIMEOUT_SEC)
+    }
+
+    pub fn action(&self) -> HookAction<'_> {
+        match (&self.url, &self.command) {
+            (Some(url), _) => HookAction::Http(url),
+            (None, Some(command)) => HookAction::Command(command),
+            (None, None) => panic!("hook action isn't defined, looks like a BUG"),
+        }
+    }
+
+    /// Returns time to wait for the hook to complete
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl ConfigValidator for HooksConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        self.startup
+            .iter()
+            .chain(&self.shutdown)
+            .try_for_each(HookConfig::validate)
+    }
+}
+
+impl ConfigValidator for HookConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.url.is_some() == self.command.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "exactly one of `url` or `command` should be defined in the hook".into(),
+            });
+        }
+        if let Some(url) = &self.url {
+            if url.parse::<Uri>().map_or(true, |uri| uri.host().is_none()) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid hook url `{url}`"),
+                });
+            }
+        }
+        if self.command.as_ref().is_some_and(Vec::is_empty) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "hook `command` should contain at least the program to execute".into(),
+            });
+        }
+        if self.timeout.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "hook `timeout` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
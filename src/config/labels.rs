@@ -0,0 +1,59 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Static labels of the listener or target, they're attached to the log entries
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(transparent)]
+pub struct Labels(BTreeMap<String, String>);
+
+impl Labels {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.0.iter()
+    }
+
+    /// Returns value of the label
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+}
+
+/// Formats labels as comma separated `name=value` pairs
+impl Display for Labels {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, (name, value)) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{name}={value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigValidator for Labels {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for name in self.0.keys() {
+            let mut chars = name.chars();
+            let valid = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "invalid label name `{name}`, it should match `[a-zA-Z_][a-zA-Z0-9_]*`"
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,10 +1,49 @@
 use super::{
+    audit::AuditConfig,
+    body_codec::BodyCodecConfig,
+    body_logging::BodyLoggingConfig,
+    buffer_pool::BufferPoolConfig,
+    cache::CacheConfig,
+    coalescing::CoalescingConfig,
+    comparison::ComparisonConfig,
+    compression::{ListenerCompressionConfig, RequestDecompressionConfig},
+    concurrency::ConcurrencyConfig,
+    connections::ConnectionsConfig,
+    cors::CorsConfig,
+    deduplication::DeduplicationConfig,
+    enrichment::EnrichmentConfig,
+    faults::FaultsConfig,
+    forward_proxy::ForwardProxyConfig,
+    forwarding::ForwardingConfig,
+    header_limits::HeaderLimitsConfig,
     headers::HeaderTransform,
-    response::{ResponseBehavior, ResponseConfig},
+    labels::Labels,
+    normalization::NormalizationConfig,
+    openapi::OpenApiSpec,
+    pipeline::PipelineConfig,
+    plugins::PluginConfig,
+    proxy_headers::ProxyHeadersConfig,
+    query::QueryTransformsList,
+    ranges::RangesConfig,
+    response::{ResponseBehavior, ResponseConfig, TargetSelection},
+    runtime::RuntimeConfig,
+    security_headers::SecurityHeadersConfig,
+    server_tls::ServerTlsConfig,
+    socket::SocketConfig,
+    static_routes::StaticRouteConfig,
     target::{TargetConfig, TargetConfigList},
-    ConfigValidator,
+    tenants::TenantsConfig,
+    websocket::WebSocketConfig,
+    ConfigValidator, ConfigWarning,
 };
-use crate::{config, config::target::TargetConditionConfig, config::ConfigError};
+use crate::{
+    ca_bundle, config,
+    config::target::{TargetConditionConfig, TargetPriority},
+    config::ConfigError,
+    strategy,
+};
+use hyper::Method;
+use schemars::JsonSchema;
 use serde::{
     de::{self, Visitor},
     Deserialize, Deserializer, Serialize,
@@ -13,6 +52,7 @@ use std::{
     collections::HashSet,
     fmt::Display,
     net::{Ipv4Addr, SocketAddr},
+    path::Path,
     str::FromStr,
     time::Duration,
 };
@@ -21,39 +61,95 @@ use tracing::debug;
 
 const DEFAULT_LISTENER_PORT: u16 = 8080;
 const DEFAULT_LISTENER_TIMEOUT_SEC: u64 = 10;
+const DEFAULT_LISTENER_WORKERS: usize = 1;
+const DEFAULT_CA_RELOAD_INTERVAL_SEC: u64 = 300;
 const INVALID_IP_ADDRESS_ERROR: &str = "IP address isn't valid";
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ListenerConfig {
     id: Option<String>,
     #[serde(default)]
+    #[schemars(with = "String")]
     listen_on: ListenOn,
     #[serde(
         with = "humantime_serde",
         default = "ListenerConfig::default_listener_timeout"
     )]
+    #[schemars(with = "String")]
     timeout: Duration,
     #[serde(default)]
     strategy: ResponseStrategy,
     headers: Option<Vec<HeaderTransform>>,
+    forwarding: Option<ForwardingConfig>,
+    proxy_headers: Option<ProxyHeadersConfig>,
+    header_limits: Option<HeaderLimitsConfig>,
+    normalization: Option<NormalizationConfig>,
+    enrichment: Option<EnrichmentConfig>,
+    #[serde(default)]
+    body_codec: BodyCodecConfig,
+    #[serde(default)]
+    pipeline: PipelineConfig,
+    query: Option<QueryTransformsList>,
     methods: Option<HashSet<HttpMethod>>,
+    #[serde(default)]
+    static_routes: Vec<StaticRouteConfig>,
+    #[serde(default)]
+    labels: Labels,
     targets: TargetConfigList,
+    tenants: Option<TenantsConfig>,
     #[serde(default)]
     response: ResponseConfig,
     #[serde(default)]
     tls: TlsConfig,
+    server_tls: Option<ServerTlsConfig>,
+    websocket: Option<WebSocketConfig>,
+    forward_proxy: Option<ForwardProxyConfig>,
+    ranges: Option<RangesConfig>,
+    #[serde(default)]
+    streaming: bool,
+    #[serde(default)]
+    body_annotations: bool,
+    cache: Option<CacheConfig>,
+    deduplication: Option<DeduplicationConfig>,
+    coalescing: Option<CoalescingConfig>,
+    faults: Option<FaultsConfig>,
+    compression: Option<ListenerCompressionConfig>,
+    request_decompression: Option<RequestDecompressionConfig>,
+    cors: Option<CorsConfig>,
+    security_headers: Option<SecurityHeadersConfig>,
+    body_logging: Option<BodyLoggingConfig>,
+    audit: Option<AuditConfig>,
+    comparison: Option<ComparisonConfig>,
+    #[schemars(with = "Option<String>")]
+    openapi_spec: Option<OpenApiSpec>,
+    #[serde(default)]
+    plugins: Vec<PluginConfig>,
+    #[serde(default = "ListenerConfig::default_workers")]
+    workers: usize,
+    #[serde(default)]
+    reuse_port: bool,
+    #[serde(default)]
+    socket: SocketConfig,
+    connections: Option<ConnectionsConfig>,
+    concurrency: Option<ConcurrencyConfig>,
+    buffer_pool: Option<BufferPoolConfig>,
+    runtime: Option<RuntimeConfig>,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct TlsConfig {
     #[serde(default)]
     pub verify: TlsVerifyConfig,
     pub ca: Option<String>,
+    pub ca_dir: Option<String>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    pub ca_reload_interval: Option<Duration>,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields, rename_all = "lowercase")]
 pub enum TlsVerifyConfig {
     No,
@@ -61,11 +157,47 @@ pub enum TlsVerifyConfig {
     Yes,
 }
 
+impl TlsConfig {
+    /// Returns how often CAs from `ca_dir` are re-read
+    pub fn ca_reload_interval(&self) -> Duration {
+        self.ca_reload_interval
+            .unwrap_or(Duration::from_secs(DEFAULT_CA_RELOAD_INTERVAL_SEC))
+    }
+}
+
+impl ConfigValidator for TlsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(ca_dir) = &self.ca_dir {
+            ca_bundle::load_ca_dir(Path::new(ca_dir)).map_err(|e| ConfigError::ValidateConfig {
+                cause: format!("unable to load CA directory `{ca_dir}`: {e}"),
+            })?;
+        } else if self.ca_reload_interval.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`ca_reload_interval` requires `ca_dir` to be set".into(),
+            });
+        }
+        if self
+            .ca_reload_interval
+            .is_some_and(|interval| interval.is_zero())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`ca_reload_interval` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
 impl ListenerConfig {
     fn default_listener_timeout() -> Duration {
         Duration::from_secs(DEFAULT_LISTENER_TIMEOUT_SEC)
     }
 
+    fn default_workers() -> usize {
+        DEFAULT_LISTENER_WORKERS
+    }
+
     /// Returns the name of this [`ListenerConfig`].
     pub fn id(&self) -> String {
         if let Some(name) = &self.id {
@@ -101,6 +233,11 @@ impl ListenerConfig {
         self.timeout
     }
 
+    /// Returns paths which are answered by listener itself without requesting targets
+    pub fn static_routes(&self) -> &[StaticRouteConfig] {
+        &self.static_routes
+    }
+
     /// Returns the headers of this [`ListenerConfig`].
     pub fn headers(&self) -> Option<&Vec<HeaderTransform>> {
         self.headers.as_ref()
@@ -111,6 +248,18 @@ impl ListenerConfig {
         self.targets.as_ref()
     }
 
+    /// Returns `true` if any target has condition expression, so conditions need an input
+    pub fn has_filter_conditions(&self) -> bool {
+        self.targets
+            .iter()
+            .any(|target| matches!(target.condition(), Some(TargetConditionConfig::Filter(_))))
+    }
+
+    /// Returns per-tenant groups of targets, request is sent to all targets if it isn't defined
+    pub fn tenants(&self) -> Option<&TenantsConfig> {
+        self.tenants.as_ref()
+    }
+
     /// Returns a reference to the response of this [`ListenerConfig`].
     pub fn response(&self) -> &ResponseConfig {
         &self.response
@@ -127,6 +276,351 @@ impl ListenerConfig {
         &self.tls
     }
 
+    /// Returns TLS termination of the listener's connections, listener serves plaintext HTTP only if it isn't defined
+    pub fn server_tls(&self) -> Option<&ServerTlsConfig> {
+        self.server_tls.as_ref()
+    }
+
+    /// Returns `true` if listeners are served on the same socket and distinguished by SNI host name,
+    /// so both terminate TLS and don't accept plaintext connections
+    pub fn is_sni_shared_with(&self, other: &ListenerConfig) -> bool {
+        self.socket() == other.socket()
+            && [self, other].iter().all(|listener| {
+                listener
+                    .server_tls()
+                    .is_some_and(|tls| !tls.accept_plaintext())
+            })
+    }
+
+    pub fn websocket(&self) -> Option<&WebSocketConfig> {
+        self.websocket.as_ref()
+    }
+
+    /// Returns forward proxy mode of the listener, if it's enabled
+    pub fn forward_proxy(&self) -> Option<&ForwardProxyConfig> {
+        self.forward_proxy.as_ref()
+    }
+
+    /// Returns handling of the ranged requests, range is forwarded to all targets if it isn't defined
+    pub fn ranges(&self) -> Option<&RangesConfig> {
+        self.ranges.as_ref()
+    }
+
+    /// Returns `true` if response of the selected target should be streamed instead of buffered
+    pub fn streaming(&self) -> bool {
+        self.streaming
+    }
+
+    /// Returns `true` if SHA-256 and length of the request body should be added to the target requests as headers
+    pub fn body_annotations(&self) -> bool {
+        self.body_annotations
+    }
+
+    pub fn cache(&self) -> Option<&CacheConfig> {
+        self.cache.as_ref()
+    }
+
+    pub fn deduplication(&self) -> Option<&DeduplicationConfig> {
+        self.deduplication.as_ref()
+    }
+
+    /// Returns coalescing of the concurrent identical GET requests into a single fan-out
+    pub fn coalescing(&self) -> Option<&CoalescingConfig> {
+        self.coalescing.as_ref()
+    }
+
+    /// Returns comparison of the targets' responses with the baseline one
+    pub fn comparison(&self) -> Option<&ComparisonConfig> {
+        self.comparison.as_ref()
+    }
+
+    /// Returns faults to inject into requests before forwarding them to any target
+    pub fn faults(&self) -> Option<&FaultsConfig> {
+        self.faults.as_ref()
+    }
+
+    /// Returns number of sockets to accept connections on
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Returns `true` if socket(s) should be bound with `SO_REUSEPORT` option,
+    /// it's always the case if there are several workers
+    pub fn reuse_port(&self) -> bool {
+        self.reuse_port || self.workers > 1
+    }
+
+    /// Returns options of the listener's sockets and accepted connections
+    pub fn socket_options(&self) -> &SocketConfig {
+        &self.socket
+    }
+
+    /// Returns settings of the dedicated runtime to isolate this listener from others
+    pub fn runtime(&self) -> Option<&RuntimeConfig> {
+        self.runtime.as_ref()
+    }
+
+    fn validate_workers(&self) -> Result<(), ConfigError> {
+        if self.workers == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "listener `workers` should be greater than zero".into(),
+            });
+        }
+        if self.reuse_port() && !cfg!(unix) {
+            return Err(ConfigError::ValidateConfig {
+                cause:
+                    "`reuse_port` and several `workers` are supported on Unix-like platforms only"
+                        .into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_retry_queues(&self) -> Result<(), ConfigError> {
+        let mut paths = HashSet::new();
+        for target in self.targets().iter() {
+            if let Some(queue) = target.retry_queue() {
+                // Each queue is drained by its own worker, so directory can't be shared
+                if !paths.insert(queue.path()) {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "retry queue path `{}` of target `{}` is used by another target",
+                            queue.path().display(),
+                            target.id()
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns limits of the client connections' lifetime, defaults of the HTTP server are used if it isn't defined
+    pub fn connections(&self) -> Option<&ConnectionsConfig> {
+        self.connections.as_ref()
+    }
+
+    /// Returns limit of the target requests in progress, they aren't limited if it isn't defined
+    pub fn concurrency(&self) -> Option<&ConcurrencyConfig> {
+        self.concurrency.as_ref()
+    }
+
+    /// Returns pool of the body buffers, new buffer is allocated for each body if it isn't defined
+    pub fn buffer_pool(&self) -> Option<&BufferPoolConfig> {
+        self.buffer_pool.as_ref()
+    }
+
+    /// Returns compression of the final responses sent to the requester
+    pub fn compression(&self) -> Option<&ListenerCompressionConfig> {
+        self.compression.as_ref()
+    }
+
+    /// Returns decompression of the compressed request bodies, they're inspected as is if it isn't defined
+    pub fn request_decompression(&self) -> Option<&RequestDecompressionConfig> {
+        self.request_decompression.as_ref()
+    }
+
+    pub fn cors(&self) -> Option<&CorsConfig> {
+        self.cors.as_ref()
+    }
+
+    /// Returns transformations of the query parameters of requests to all targets
+    pub fn query(&self) -> Option<&QueryTransformsList> {
+        self.query.as_ref()
+    }
+
+    /// Returns policy of the headers and trailers forwarding to targets
+    pub fn forwarding(&self) -> Option<&ForwardingConfig> {
+        self.forwarding.as_ref()
+    }
+
+    /// Returns normalization which is applied to the request before any other processing
+    pub fn normalization(&self) -> Option<&NormalizationConfig> {
+        self.normalization.as_ref()
+    }
+
+    /// Returns lookup of the request's key against external source, its result is exposed as context variables
+    pub fn enrichment(&self) -> Option<&EnrichmentConfig> {
+        self.enrichment.as_ref()
+    }
+
+    /// Returns decoding of the request body which is used by the targets' conditions
+    pub fn body_codec(&self) -> &BodyCodecConfig {
+        &self.body_codec
+    }
+
+    /// Returns order of the request processing stages before it's forwarded to the targets
+    pub fn pipeline(&self) -> &PipelineConfig {
+        &self.pipeline
+    }
+
+    /// Returns management policy of the `User-Agent` and `Via` headers of the target requests
+    pub fn proxy_headers(&self) -> Option<&ProxyHeadersConfig> {
+        self.proxy_headers.as_ref()
+    }
+
+    /// Returns limits of the incoming request headers
+    pub fn header_limits(&self) -> Option<&HeaderLimitsConfig> {
+        self.header_limits.as_ref()
+    }
+
+    /// Returns static labels which are attached to the log entries of the listener
+    pub fn labels(&self) -> &Labels {
+        &self.labels
+    }
+
+    pub fn security_headers(&self) -> Option<&SecurityHeadersConfig> {
+        self.security_headers.as_ref()
+    }
+
+    pub fn body_logging(&self) -> Option<&BodyLoggingConfig> {
+        self.body_logging.as_ref()
+    }
+
+    /// Returns audit trail config, a record is written for each request if it's defined
+    pub fn audit(&self) -> Option<&AuditConfig> {
+        self.audit.as_ref()
+    }
+
+    /// Returns OpenAPI spec which requests should conform to, other requests are rejected
+    pub fn openapi_spec(&self) -> Option<&OpenApiSpec> {
+        self.openapi_spec.as_ref()
+    }
+
+    /// Returns WASM plugins which are called in order of declaration on each hook
+    pub fn plugins(&self) -> &[PluginConfig] {
+        &self.plugins
+    }
+
+    /// Returns `true` if response of the target which is selected before requesting targets is passed
+    /// to the requester as is, so its body can be streamed
+    pub fn is_selected_response_passed(&self) -> bool {
+        matches!(
+            self.strategy(),
+            ResponseStrategy::AlwaysTargetId | ResponseStrategy::ConditionalRouting
+        ) && !self.response().is_body_overridden()
+            && !self.response().is_body_transformed()
+    }
+
+    fn validate_streaming(&self) -> Result<(), ConfigError> {
+        if !self.streaming {
+            return Ok(());
+        }
+
+        // Target to stream response from should be known before any response is received
+        if !matches!(
+            self.strategy(),
+            ResponseStrategy::AlwaysTargetId | ResponseStrategy::ConditionalRouting
+        ) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`streaming` can't be used with strategy `{}`, only `always_target_id` or `conditional_routing` are allowed",
+                    self.strategy()
+                ),
+            });
+        }
+        if self.response().is_body_overridden() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`streaming` can't be used with response `override.body`".into(),
+            });
+        }
+        if self.response().is_body_transformed() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`streaming` can't be used with response `body_transform`".into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_max_wait(&self) -> Result<(), ConfigError> {
+        match self.response().max_wait() {
+            Some(max_wait) if max_wait >= self.timeout => Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "response `max_wait` should be less than listener `timeout` {}",
+                    humantime::format_duration(self.timeout)
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_canary(&self) -> Result<(), ConfigError> {
+        let canaries: Vec<String> = self
+            .targets()
+            .iter()
+            .filter(|t| t.canary().is_some())
+            .map(TargetConfig::id)
+            .collect();
+        if canaries.is_empty() {
+            return Ok(());
+        }
+        if canaries.len() > 1 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "only one target can have `canary` defined, but there are {}",
+                    canaries.join(", ")
+                ),
+            });
+        }
+        if self.response().target_selection() != Some(TargetSelection::WeightedRandom) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`canary` of target `{}` requires response `target_selection` `{}`",
+                    canaries[0],
+                    TargetSelection::WeightedRandom
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_comparison(&self) -> Result<(), ConfigError> {
+        let Some(comparison) = self.comparison() else {
+            return Ok(());
+        };
+        comparison.validate()?;
+        // Recorded responses are compared with all targets
+        let Some(baseline) = comparison.baseline() else {
+            return Ok(());
+        };
+        if !self.targets().iter().any(|t| t.id() == baseline) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("comparison `baseline` refers to unknown target `{baseline}`"),
+            });
+        }
+        if self.targets().len() < 2 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "comparison requires at least two targets".into(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_priority(&self) -> Result<(), ConfigError> {
+        if self.concurrency.is_some() {
+            return Ok(());
+        }
+        match self
+            .targets()
+            .iter()
+            .find(|t| t.priority() == TargetPriority::Low)
+        {
+            Some(target) => Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`{}` priority of target `{}` requires listener `concurrency` limit",
+                    TargetPriority::Low,
+                    target.id()
+                ),
+            }),
+            None => Ok(()),
+        }
+    }
+
     fn validate_strategy(&self) -> Result<(), ConfigError> {
         // Validate strategy requirements
         match self.strategy() {
@@ -161,9 +655,18 @@ impl ListenerConfig {
             ResponseStrategy::AlwaysTargetId
             | ResponseStrategy::FailedThenTargetId
             | ResponseStrategy::OkThenTargetId => {
-                // Make sure that target_selector has valid target_id specified if strategy is *_target_id
+                // Make sure that target_selector has valid target_id specified if strategy is *_target_id,
+                // or target is selected on each request
                 let target_ids: Vec<String> = self.targets().iter().map(TargetConfig::id).collect();
-                if let Some(target_id) = self.response().target_selector() {
+                if let Some(selection) = self.response().target_selection() {
+                    if self.response().target_selector().is_some() {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "`target_selector` can't be used with `target_selection` `{selection}`"
+                            ),
+                        });
+                    }
+                } else if let Some(target_id) = self.response().target_selector() {
                     if !target_ids.contains(target_id) {
                         return Err(ConfigError::ValidateConfig {
                             cause: format!(
@@ -175,20 +678,194 @@ impl ListenerConfig {
                 } else {
                     return Err(ConfigError::ValidateConfig {
                         cause: format!(
-                            "`target_selector` should be specified for strategy `{}`",
+                            "`target_selector` or `target_selection` should be specified for strategy `{}`",
+                            self.strategy()
+                        ),
+                    });
+                }
+            }
+            ResponseStrategy::Script if self.response().script().is_none() => {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "response `script` should be specified for strategy `{}`",
+                        self.strategy()
+                    ),
+                });
+            }
+            ResponseStrategy::Custom => match self.response().custom_strategy() {
+                None => {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "response `custom_strategy` should be specified for strategy `{}`",
+                            self.strategy()
+                        ),
+                    });
+                }
+                Some(name) if strategy::get(name).is_none() => {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!("custom strategy `{name}` isn't registered"),
+                    });
+                }
+                Some(_) => {}
+            },
+            ResponseStrategy::PreferredThenFallback => {
+                let Some(preferred) = self.response().preferred_target_id() else {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "response `preferred_target_id` should be specified for strategy `{}`",
                             self.strategy()
                         ),
                     });
+                };
+                let mut order = vec![preferred];
+                for target_id in self.response().fallback_order() {
+                    if order.contains(&target_id.as_str()) {
+                        return Err(ConfigError::ValidateConfig {
+                            cause: format!(
+                                "target_id `{target_id}` is listed more than once in response `preferred_target_id` and `fallback_order`"
+                            ),
+                        });
+                    }
+                    order.push(target_id);
+                }
+                if let Some(target_id) = order
+                    .into_iter()
+                    .find(|id| !self.targets().iter().any(|t| t.id() == *id))
+                {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "response `preferred_target_id` or `fallback_order` points to unknown target_id `{target_id}`"
+                        ),
+                    });
                 }
             }
             _ => {}
         };
+        if self.response().target_selection().is_some()
+            && !matches!(
+                self.strategy(),
+                ResponseStrategy::AlwaysTargetId
+                    | ResponseStrategy::FailedThenTargetId
+                    | ResponseStrategy::OkThenTargetId
+            )
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "response `target_selection` can't be used with strategy `{}`",
+                    self.strategy()
+                ),
+            });
+        }
+        if self.response().script().is_some()
+            && !matches!(self.strategy(), ResponseStrategy::Script)
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "response `script` can't be used with strategy `{}`",
+                    self.strategy()
+                ),
+            });
+        }
+        if (self.response().preferred_target_id().is_some()
+            || !self.response().fallback_order().is_empty())
+            && !matches!(self.strategy(), ResponseStrategy::PreferredThenFallback)
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "response `preferred_target_id` and `fallback_order` can't be used with strategy `{}`",
+                    self.strategy()
+                ),
+            });
+        }
+        if self.response().custom_strategy().is_some()
+            && !matches!(self.strategy(), ResponseStrategy::Custom)
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "response `custom_strategy` can't be used with strategy `{}`",
+                    self.strategy()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_tenants_strategy(&self, tenants: &TenantsConfig) -> Result<(), ConfigError> {
+        if !tenants.has_target_selector() {
+            return Ok(());
+        }
+        if !matches!(
+            self.strategy(),
+            ResponseStrategy::AlwaysTargetId
+                | ResponseStrategy::FailedThenTargetId
+                | ResponseStrategy::OkThenTargetId
+        ) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`target_selector` of `tenants` group can't be used with strategy `{}`",
+                    self.strategy()
+                ),
+            });
+        }
+        if let Some(selection) = self.response().target_selection() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`target_selector` of `tenants` group can't be used with `target_selection` `{selection}`"
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn validate_chained_targets(&self) -> Result<(), ConfigError> {
+        for (pos, target) in self.targets().iter().enumerate() {
+            let Some(after) = target.after() else {
+                continue;
+            };
+            // Single target is selected before any request in case of conditional routing
+            if matches!(self.strategy(), ResponseStrategy::ConditionalRouting) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`after` can't be used with strategy `{}`, target `{}`",
+                        self.strategy(),
+                        target.id()
+                    ),
+                });
+            }
+            // Preceding target should be declared before, so there are no cycles
+            if !self.targets()[..pos].iter().any(|t| t.id() == after) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`after` of target `{}` points to `{after}` which isn't declared before it",
+                        target.id()
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_copy_headers(&self) -> Result<(), ConfigError> {
+        let target_ids: Vec<String> = self.targets().iter().map(TargetConfig::id).collect();
+        for copy in self.response().copy_headers_config().iter().flatten() {
+            if !target_ids.iter().any(|id| id == copy.from()) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`copy_headers` points to unknown target_id `{}`",
+                        copy.from()
+                    ),
+                });
+            }
+        }
 
         Ok(())
     }
 }
 
-#[derive(Deserialize, Debug, Default, Display)]
+#[derive(Deserialize, Debug, Default, Display, JsonSchema)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 #[strum(serialize_all = "snake_case")]
 pub enum ResponseStrategy {
@@ -202,12 +879,17 @@ pub enum ResponseStrategy {
     #[default]
     FailedThenOverride,
     ConditionalRouting,
+    Script,
+    Custom,
+    PreferredThenFallback,
 }
 
-#[derive(Deserialize, Debug, EnumString, PartialEq, Eq, Hash, Serialize)]
+#[derive(
+    Deserialize, Debug, Clone, Copy, EnumString, PartialEq, Eq, Hash, Serialize, JsonSchema,
+)]
 #[serde(deny_unknown_fields, rename_all = "UPPERCASE")]
 #[strum(ascii_case_insensitive)]
-enum HttpMethod {
+pub enum HttpMethod {
     Get,
     Post,
     Put,
@@ -217,6 +899,20 @@ enum HttpMethod {
     Head,
 }
 
+impl From<HttpMethod> for Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Patch => Method::PATCH,
+            HttpMethod::Delete => Method::DELETE,
+            HttpMethod::Options => Method::OPTIONS,
+            HttpMethod::Head => Method::HEAD,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Serialize)]
 struct ListenOn {
     ip: Ipv4Addr,
@@ -314,12 +1010,129 @@ impl<'de> Deserialize<'de> for ListenOn {
 
 impl ConfigValidator for ListenerConfig {
     fn validate(&self) -> Result<(), config::ConfigError> {
+        self.tls.validate()?;
         self.targets().validate()?;
         self.response().validate()?;
         self.validate_strategy()?;
+        self.validate_copy_headers()?;
+        self.validate_chained_targets()?;
+        self.validate_streaming()?;
+        self.validate_max_wait()?;
+        self.validate_canary()?;
+        self.validate_priority()?;
+        self.validate_workers()?;
+        self.validate_retry_queues()?;
+        if let Some(cache) = self.cache() {
+            cache.validate()?;
+        }
+        if let Some(deduplication) = self.deduplication() {
+            deduplication.validate()?;
+        }
+        if let Some(coalescing) = self.coalescing() {
+            coalescing.validate()?;
+        }
+        if let Some(faults) = self.faults() {
+            faults.validate()?;
+        }
+        if let Some(compression) = self.compression() {
+            compression.validate()?;
+        }
+        if let Some(request_decompression) = self.request_decompression() {
+            request_decompression.validate()?;
+        }
+        if let Some(cors) = self.cors() {
+            cors.validate()?;
+        }
+        if let Some(security_headers) = self.security_headers() {
+            security_headers.validate()?;
+        }
+        self.labels.validate()?;
+        if let Some(header_limits) = self.header_limits() {
+            header_limits.validate()?;
+        }
+        if let Some(proxy_headers) = self.proxy_headers() {
+            proxy_headers.validate()?;
+        }
+        for route in self.static_routes() {
+            route.validate()?;
+        }
+        if let Some(body_logging) = self.body_logging() {
+            body_logging.validate()?;
+        }
+        if let Some(audit) = self.audit() {
+            audit.validate()?;
+        }
+        self.validate_comparison()?;
+        for plugin in self.plugins() {
+            plugin.validate()?;
+        }
+        if let Some(runtime) = self.runtime() {
+            runtime.validate()?;
+        }
+        self.socket.validate()?;
+        self.body_codec.validate()?;
+        self.pipeline.validate()?;
+        if let Some(enrichment) = self.enrichment() {
+            enrichment.validate()?;
+        }
+        if let Some(connections) = self.connections() {
+            connections.validate()?;
+        }
+        if let Some(concurrency) = self.concurrency() {
+            concurrency.validate()?;
+        }
+        if let Some(buffer_pool) = self.buffer_pool() {
+            buffer_pool.validate()?;
+        }
+        if let Some(tenants) = self.tenants() {
+            tenants.validate()?;
+            tenants.validate_targets(self.targets())?;
+            self.validate_tenants_strategy(tenants)?;
+        }
+        if let Some(websocket) = self.websocket() {
+            websocket.validate()?;
+            websocket.validate_targets(self.targets())?;
+        }
+        if let Some(forward_proxy) = self.forward_proxy() {
+            forward_proxy.validate()?;
+        }
+        if let Some(server_tls) = self.server_tls() {
+            server_tls.validate()?;
+        }
+        if let Some(ranges) = self.ranges() {
+            ranges.validate_targets(self.targets())?;
+        }
 
         Ok(())
     }
+
+    fn lint(&self) -> Vec<ConfigWarning> {
+        let mut warnings = self.response().lint();
+        if self.response().target_selector().is_some()
+            && !matches!(
+                self.strategy(),
+                ResponseStrategy::AlwaysTargetId
+                    | ResponseStrategy::FailedThenTargetId
+                    | ResponseStrategy::OkThenTargetId
+            )
+        {
+            warnings.push(ConfigWarning::new(format!(
+                "response `target_selector` isn't used by strategy `{}`",
+                self.strategy()
+            )));
+        }
+        if self.coalescing().is_some() && !self.is_method_allowed(Method::GET.as_str()) {
+            warnings.push(ConfigWarning::new(
+                "`coalescing` has no effect since GET method isn't allowed",
+            ));
+        }
+        for target in self.targets() {
+            let scope = format!("target `{}`", target.id());
+            warnings.extend(target.lint().into_iter().map(|w| w.within(&scope)));
+        }
+
+        warnings
+    }
 }
 
 #[cfg(test)]
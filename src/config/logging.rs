@@ -0,0 +1,116 @@
+use super::{ConfigError, ConfigValidator};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_GOVERNOR_INTERVAL_SEC: u64 = 60;
+const DEFAULT_GOVERNOR_LIMIT: usize = 5;
+
+/// Process-wide logging settings
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LoggingConfig {
+    governor: Option<LogGovernorConfig>,
+}
+
+/// Deduplication of the repeated warnings and errors: identical messages above the limit are suppressed
+/// and reported by periodic summaries with the number of suppressed ones
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LogGovernorConfig {
+    #[serde(
+        with = "humantime_serde",
+        default = "LogGovernorConfig::default_interval"
+    )]
+    #[schemars(with = "Option<String>")]
+    interval: Duration,
+    #[serde(default = "LogGovernorConfig::default_limit")]
+    limit: usize,
+    #[serde(default)]
+    classes: Vec<LogClassConfig>,
+}
+
+/// Rate of the messages which match the pattern, the first matching class is applied
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LogClassConfig {
+    /// Regex of the message
+    pattern: String,
+    #[serde(with = "humantime_serde", default)]
+    #[schemars(with = "Option<String>")]
+    interval: Option<Duration>,
+    limit: Option<usize>,
+}
+
+impl LoggingConfig {
+    pub fn governor(&self) -> Option<&LogGovernorConfig> {
+        self.governor.as_ref()
+    }
+}
+
+impl LogGovernorConfig {
+    fn default_interval() -> Duration {
+        Duration::from_secs(DEFAULT_GOVERNOR_INTERVAL_SEC)
+    }
+
+    fn default_limit() -> usize {
+        DEFAULT_GOVERNOR_LIMIT
+    }
+
+    /// Returns period of the summaries
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns number of identical messages which are logged as is during the interval
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn classes(&self) -> &[LogClassConfig] {
+        &self.classes
+    }
+}
+
+impl LogClassConfig {
+    pub fn pattern(&self) -> Regex {
+        Regex::new(&self.pattern).expect("invalid log class pattern, looks like a BUG")
+    }
+
+    /// Returns period of the summaries, governor's one if it isn't defined
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Returns number of identical messages which are logged as is, governor's one if it isn't defined
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+}
+
+impl ConfigValidator for LoggingConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let Some(governor) = &self.governor else {
+            return Ok(());
+        };
+        let intervals = governor.classes.iter().filter_map(LogClassConfig::interval);
+        if std::iter::once(governor.interval)
+            .chain(intervals)
+            .any(|interval| interval.is_zero())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: "log governor `interval` should be greater than zero".into(),
+            });
+        }
+        for class in &governor.classes {
+            if let Err(e) = Regex::new(&class.pattern) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid log class pattern `{}`: {e}", class.pattern),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
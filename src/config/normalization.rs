@@ -0,0 +1,219 @@
+use hyper::{
+    header::{HeaderName, HeaderValue, CONNECTION, HOST, TE},
+    http::uri::{Authority, Parts, PathAndQuery},
+    HeaderMap, Request, Uri,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::borrow::Cow;
+
+/// Hop-by-hop headers which are meaningful for the single connection only, besides ones listed in `Connection`
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Normalization of the request before routing, conditions evaluation and forwarding,
+/// so equivalent requests are handled in the same way regardless of their raw form
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NormalizationConfig {
+    #[serde(default = "NormalizationConfig::default_enabled")]
+    merge_slashes: bool,
+    #[serde(default = "NormalizationConfig::default_enabled")]
+    resolve_dot_segments: bool,
+    #[serde(default)]
+    lowercase_host: bool,
+    #[serde(default = "NormalizationConfig::default_enabled")]
+    strip_hop_by_hop_headers: bool,
+}
+
+impl NormalizationConfig {
+    fn default_enabled() -> bool {
+        true
+    }
+
+    /// Normalizes path, host and headers of the request in place,
+    /// handshake headers of the proxied upgrade requests are kept if `keep_upgrade` is `true`
+    pub fn apply<B>(&self, req: &mut Request<B>, keep_upgrade: bool) {
+        if self.merge_slashes || self.resolve_dot_segments {
+            self.normalize_path(req.uri_mut());
+        }
+        if self.lowercase_host {
+            lowercase_host(req);
+        }
+        if self.strip_hop_by_hop_headers && !keep_upgrade {
+            strip_hop_by_hop_headers(req.headers_mut());
+        }
+    }
+
+    fn normalize_path(&self, uri: &mut Uri) {
+        let path = match self.normalized_path(uri.path()) {
+            Cow::Borrowed(_) => return,
+            Cow::Owned(path) => path,
+        };
+        let path_and_query = match uri.query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        };
+        let mut parts = Parts::from(std::mem::take(uri));
+        parts.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+        if let Ok(normalized) = Uri::from_parts(parts) {
+            *uri = normalized;
+        }
+    }
+
+    /// Returns path with merged duplicate slashes and resolved `.` and `..` segments, trailing slash is kept;
+    /// paths which don't start with slash, like `*`, are kept as is
+    fn normalized_path<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        let Some(relative) = path.strip_prefix('/') else {
+            return Cow::Borrowed(path);
+        };
+        let segments: Vec<&str> = relative.split('/').collect();
+        let last = segments.len() - 1;
+        let mut normalized: Vec<&str> = vec![];
+        for (pos, segment) in segments.into_iter().enumerate() {
+            match segment {
+                "." if self.resolve_dot_segments => {
+                    if pos == last {
+                        normalized.push("");
+                    }
+                }
+                ".." if self.resolve_dot_segments => {
+                    normalized.pop();
+                    if pos == last {
+                        normalized.push("");
+                    }
+                }
+                "" if self.merge_slashes && pos != last => {}
+                segment => normalized.push(segment),
+            }
+        }
+
+        let normalized = normalized.join("/");
+        if normalized == relative {
+            Cow::Borrowed(path)
+        } else {
+            Cow::Owned(format!("/{normalized}"))
+        }
+    }
+}
+
+/// Lowercases `Host` header and authority of the URI, if it's present
+fn lowercase_host<B>(req: &mut Request<B>) {
+    if let Some(host) = req.headers().get(HOST) {
+        if host.as_bytes().iter().any(u8::is_ascii_uppercase) {
+            let host = host.as_bytes().to_ascii_lowercase();
+            if let Ok(host) = HeaderValue::from_bytes(&host) {
+                req.headers_mut().insert(HOST, host);
+            }
+        }
+    }
+    let authority = req
+        .uri()
+        .authority()
+        .map(Authority::as_str)
+        .filter(|a| a.bytes().any(|b| b.is_ascii_uppercase()))
+        .map(str::to_ascii_lowercase);
+    if let Some(authority) = authority {
+        let mut parts = Parts::from(std::mem::take(req.uri_mut()));
+        parts.authority = Authority::try_from(authority).ok();
+        if let Ok(uri) = Uri::from_parts(parts) {
+            *req.uri_mut() = uri;
+        }
+    }
+}
+
+/// Drops hop-by-hop headers and headers listed in `Connection`, `TE: trailers` is kept since it's end-to-end
+/// for gRPC and similar protocols
+pub(super) fn strip_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let listed: Vec<HeaderName> = headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect();
+    let keep_te = headers
+        .get_all(TE)
+        .iter()
+        .any(|value| value.as_bytes().eq_ignore_ascii_case(b"trailers"));
+
+    for name in listed {
+        headers.remove(name);
+    }
+    for name in HOP_BY_HOP_HEADERS {
+        headers.remove(name);
+    }
+    if keep_te {
+        headers.insert(TE, HeaderValue::from_static("trailers"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::UPGRADE;
+
+    fn normalization(cfg: &str) -> NormalizationConfig {
+        serde_json::from_str(cfg).unwrap()
+    }
+
+    #[test]
+    fn normalize_path() {
+        let cfg = normalization("{}");
+        for (path, expected) in [
+            ("/", "/"),
+            ("*", "*"),
+            ("/api/v1/users", "/api/v1/users"),
+            ("//api///v1//users", "/api/v1/users"),
+            ("/api/v1/users/", "/api/v1/users/"),
+            ("/api//v1//", "/api/v1/"),
+            ("/api/./v1/../v2/users", "/api/v2/users"),
+            ("/api/v1/..", "/api/"),
+            ("/api/v1/.", "/api/v1/"),
+            ("/../../api", "/api"),
+            ("/api/%2e%2e/v1", "/api/%2e%2e/v1"),
+        ] {
+            assert_eq!(cfg.normalized_path(path), expected, "{path}");
+        }
+
+        let cfg = normalization(r#"{"resolve_dot_segments": false}"#);
+        assert_eq!(cfg.normalized_path("//api/../v1"), "/api/../v1");
+        let cfg = normalization(r#"{"merge_slashes": false}"#);
+        assert_eq!(cfg.normalized_path("//api/../v1"), "//v1");
+
+        let mut req = Request::get("http://Example.COM//api/./users?q=a//b")
+            .header(HOST, "Example.COM")
+            .body(())
+            .unwrap();
+        normalization(r#"{"lowercase_host": true}"#).apply(&mut req, false);
+        assert_eq!(req.uri(), "http://example.com/api/users?q=a//b");
+        assert_eq!(req.headers()[HOST], "example.com");
+    }
+
+    #[test]
+    fn strip_hop_by_hop() {
+        let mut req = Request::get("/")
+            .header(CONNECTION, "keep-alive, x-hop")
+            .header("keep-alive", "timeout=5")
+            .header("x-hop", "yes")
+            .header(TE, "trailers")
+            .header(UPGRADE, "websocket")
+            .header("x-end-to-end", "yes")
+            .body(())
+            .unwrap();
+        normalization("{}").apply(&mut req, true);
+        assert_eq!(req.headers().len(), 6);
+
+        normalization("{}").apply(&mut req, false);
+        let names: Vec<&str> = req.headers().keys().map(HeaderName::as_str).collect();
+        assert_eq!(names, ["x-end-to-end", "te"]);
+    }
+}
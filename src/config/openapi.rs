@@ -0,0 +1,629 @@
+use figment::{
+    providers::{Format, Yaml},
+    Figment,
+};
+use hyper::{body::Bytes, header::CONTENT_TYPE, HeaderMap, Request, Uri};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Max depth of the nested schemas and references, to protect from recursive specs
+const MAX_SCHEMA_DEPTH: usize = 64;
+
+/// OpenAPI 3 spec to validate requests against, it's loaded from YAML or JSON file during config loading.
+///
+/// Supported subset: path templates, path/query/header parameters, request body content type and JSON schema
+/// keywords `type`, `nullable`, `enum`, `properties`, `required`, `additionalProperties`, `items`,
+/// `minimum`/`maximum`, `minLength`/`maxLength`, `minItems`/`maxItems`, `allOf`, `anyOf`, `oneOf`
+/// and local `$ref`s.
+pub struct OpenApiSpec {
+    path: String,
+    root: Value,
+    base_paths: Vec<String>,
+    paths: Vec<PathTemplate>,
+}
+
+/// Operation of the spec which request matches to
+pub struct OpenApiOperation<'a> {
+    request_body: Option<&'a Value>,
+}
+
+struct PathTemplate {
+    template: String,
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    Literal(String),
+    Param(String),
+}
+
+impl OpenApiSpec {
+    /// Checks path, method and parameters of the request, so it can be rejected before its body is received,
+    /// returns matched operation to check body against it
+    pub fn validate_head<B>(&self, req: &Request<B>) -> Result<OpenApiOperation<'_>, Vec<String>> {
+        let Some((template, path_params)) = self.find_path(req.uri()) else {
+            return Err(vec![format!("path `{}` isn't defined", req.uri().path())]);
+        };
+        let path_item = self.resolve(&self.root["paths"][&template.template]);
+        let method = req.method().as_str().to_lowercase();
+        let Some(operation) = path_item.get(&method).map(|op| self.resolve(op)) else {
+            return Err(vec![format!(
+                "method `{}` isn't defined for path `{}`",
+                req.method(),
+                template.template
+            )]);
+        };
+
+        let mut errors = vec![];
+        self.check_parameters(path_item, operation, req, &path_params, &mut errors);
+        if errors.is_empty() {
+            Ok(OpenApiOperation {
+                request_body: operation.get("requestBody").map(|b| self.resolve(b)),
+            })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Checks request body against the operation matched by [`OpenApiSpec::validate_head`]
+    pub fn validate_body(
+        &self,
+        operation: &OpenApiOperation,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        if let Some(request_body) = operation.request_body {
+            self.check_body(request_body, headers, body, &mut errors);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the most specific matching template (with the most literal segments) and values of its parameters
+    fn find_path(&self, uri: &Uri) -> Option<(&PathTemplate, HashMap<&str, String>)> {
+        let path = uri.path();
+        let mut found: Option<(&PathTemplate, HashMap<&str, String>, usize)> = None;
+        for base in &self.base_paths {
+            let Some(path) = path.strip_prefix(base.as_str()) else {
+                continue;
+            };
+            let segments: Vec<&str> = path.split('/').skip(1).collect();
+            for template in &self.paths {
+                if template.segments.len() != segments.len() {
+                    continue;
+                }
+                let mut params = HashMap::new();
+                let mut literals = 0;
+                let matched = template
+                    .segments
+                    .iter()
+                    .zip(&segments)
+                    .all(|(t, s)| match t {
+                        Segment::Literal(literal) => {
+                            literals += 1;
+                            literal == s
+                        }
+                        Segment::Param(name) => {
+                            params.insert(name.as_str(), percent_decode(s));
+                            !s.is_empty()
+                        }
+                    });
+                if matched && found.as_ref().is_none_or(|(_, _, best)| literals > *best) {
+                    found = Some((template, params, literals));
+                }
+            }
+        }
+
+        found.map(|(template, params, _)| (template, params))
+    }
+
+    fn check_parameters<B>(
+        &self,
+        path_item: &Value,
+        operation: &Value,
+        req: &Request<B>,
+        path_params: &HashMap<&str, String>,
+        errors: &mut Vec<String>,
+    ) {
+        // Operation's parameters override path item's ones with the same name and location
+        let mut params: Vec<&Value> = vec![];
+        for param in [path_item, operation]
+            .iter()
+            .filter_map(|v| v.get("parameters")?.as_array())
+            .flatten()
+            .map(|p| self.resolve(p))
+        {
+            params.retain(|p| p["name"] != param["name"] || p["in"] != param["in"]);
+            params.push(param);
+        }
+        let query: Vec<(String, String)> =
+            form_urlencoded::parse(req.uri().query().unwrap_or_default().as_bytes())
+                .into_owned()
+                .collect();
+
+        for param in params {
+            let (Some(name), Some(location)) = (param["name"].as_str(), param["in"].as_str())
+            else {
+                continue;
+            };
+            let values: Vec<String> = match location {
+                "path" => path_params.get(name).cloned().into_iter().collect(),
+                "query" => query
+                    .iter()
+                    .filter(|(n, _)| n == name)
+                    .map(|(_, v)| v.clone())
+                    .collect(),
+                "header" => req
+                    .headers()
+                    .get_all(name)
+                    .iter()
+                    .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned())
+                    .collect(),
+                _ => continue,
+            };
+            let location = format!("{location} parameter `{name}`");
+            if values.is_empty() {
+                if param["required"] == Value::Bool(true) {
+                    errors.push(format!("{location} is required"));
+                }
+                continue;
+            }
+            let Some(schema) = param.get("schema").map(|s| self.resolve(s)) else {
+                continue;
+            };
+            let value = if self.types(schema).contains(&"array") {
+                let items = self.resolve(&schema["items"]);
+                Value::Array(values.iter().map(|v| self.coerce(items, v)).collect())
+            } else {
+                self.coerce(schema, &values[0])
+            };
+            self.check_schema(schema, &value, &location, 0, errors);
+        }
+    }
+
+    fn check_body(
+        &self,
+        request_body: &Value,
+        headers: &HeaderMap,
+        body: &Bytes,
+        errors: &mut Vec<String>,
+    ) {
+        if body.is_empty() {
+            if request_body["required"] == Value::Bool(true) {
+                errors.push("request body is required".into());
+            }
+            return;
+        }
+        let Some(content) = request_body["content"].as_object() else {
+            return;
+        };
+        let content_type = headers
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .map(|v| v.trim().to_lowercase())
+            .unwrap_or_default();
+        let media = content.get(&content_type).or_else(|| {
+            let (kind, _) = content_type.split_once('/')?;
+            content
+                .get(&format!("{kind}/*"))
+                .or_else(|| content.get("*/*"))
+        });
+        let Some(media) = media else {
+            errors.push(format!("content type `{content_type}` isn't allowed"));
+            return;
+        };
+
+        let is_json = content_type == "application/json" || content_type.ends_with("+json");
+        if let (true, Some(schema)) = (is_json, media.get("schema")) {
+            match serde_json::from_slice::<Value>(body) {
+                Ok(value) => self.check_schema(schema, &value, "body `$`", 0, errors),
+                Err(e) => errors.push(format!("body isn't a valid JSON: {e}")),
+            }
+        }
+    }
+
+    /// Validates value against the schema, location is a human-readable place of the value in the request
+    fn check_schema(
+        &self,
+        schema: &Value,
+        value: &Value,
+        location: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        if depth > MAX_SCHEMA_DEPTH {
+            errors.push(format!("{location}: schema is too deep"));
+            return;
+        }
+        let schema = self.resolve(schema);
+
+        if value.is_null() && schema["nullable"] == Value::Bool(true) {
+            return;
+        }
+        let types = self.types(schema);
+        if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+            errors.push(format!(
+                "{location}: expected {}, got {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                errors.push(format!("{location}: value {value} isn't allowed"));
+            }
+        }
+
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                if schema["minimum"].as_f64().is_some_and(|min| n < min) {
+                    errors.push(format!("{location}: should be >= {}", schema["minimum"]));
+                }
+                if schema["maximum"].as_f64().is_some_and(|max| n > max) {
+                    errors.push(format!("{location}: should be <= {}", schema["maximum"]));
+                }
+            }
+            Value::String(s) => {
+                let len = s.chars().count() as u64;
+                if schema["minLength"].as_u64().is_some_and(|min| len < min) {
+                    errors.push(format!(
+                        "{location}: should be at least {} characters",
+                        schema["minLength"]
+                    ));
+                }
+                if schema["maxLength"].as_u64().is_some_and(|max| len > max) {
+                    errors.push(format!(
+                        "{location}: should be at most {} characters",
+                        schema["maxLength"]
+                    ));
+                }
+            }
+            Value::Array(items) => {
+                let len = items.len() as u64;
+                if schema["minItems"].as_u64().is_some_and(|min| len < min) {
+                    errors.push(format!(
+                        "{location}: should have at least {} items",
+                        schema["minItems"]
+                    ));
+                }
+                if schema["maxItems"].as_u64().is_some_and(|max| len > max) {
+                    errors.push(format!(
+                        "{location}: should have at most {} items",
+                        schema["maxItems"]
+                    ));
+                }
+                if let Some(items_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        let location = format!("{}[{i}]`", location.trim_end_matches('`'));
+                        self.check_schema(items_schema, item, &location, depth + 1, errors);
+                    }
+                }
+            }
+            Value::Object(fields) => self.check_object(schema, fields, location, depth, errors),
+            _ => {}
+        }
+
+        if let Some(all) = schema["allOf"].as_array() {
+            for sub in all {
+                self.check_schema(sub, value, location, depth + 1, errors);
+            }
+        }
+        for (keyword, exactly_one) in [("anyOf", false), ("oneOf", true)] {
+            if let Some(variants) = schema[keyword].as_array() {
+                let matched = variants
+                    .iter()
+                    .filter(|sub| {
+                        let mut sub_errors = vec![];
+                        self.check_schema(sub, value, location, depth + 1, &mut sub_errors);
+                        sub_errors.is_empty()
+                    })
+                    .count();
+                if matched == 0 || (exactly_one && matched > 1) {
+                    errors.push(format!(
+                        "{location}: should match {} of `{keyword}` schemas, matches {matched}",
+                        if exactly_one { "exactly one" } else { "any" }
+                    ));
+                }
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Value,
+        fields: &Map<String, Value>,
+        location: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        let location = location.trim_end_matches('`');
+        if let Some(required) = schema["required"].as_array() {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    errors.push(format!("{location}.{name}`: is required"));
+                }
+            }
+        }
+        let properties = schema["properties"].as_object();
+        for (name, field) in fields {
+            let field_location = format!("{location}.{name}`");
+            match (
+                properties.and_then(|p| p.get(name)),
+                &schema["additionalProperties"],
+            ) {
+                (Some(field_schema), _) => {
+                    self.check_schema(field_schema, field, &field_location, depth + 1, errors)
+                }
+                (None, Value::Bool(false)) => {
+                    errors.push(format!("{field_location}: isn't allowed"))
+                }
+                (None, additional @ Value::Object(_)) => {
+                    self.check_schema(additional, field, &field_location, depth + 1, errors)
+                }
+                (None, _) => {}
+            }
+        }
+    }
+
+    /// Follows local `$ref`s, unresolved reference is returned as is, so it matches anything
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> &'a Value {
+        for _ in 0..MAX_SCHEMA_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            match reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+            {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    /// Returns allowed types of the schema, both OpenAPI 3.0 string and 3.1 list forms are supported
+    fn types<'a>(&self, schema: &'a Value) -> Vec<&'a str> {
+        match &schema["type"] {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Converts parameter's string value to JSON value of the schema's type, if it's possible
+    fn coerce(&self, schema: &Value, value: &str) -> Value {
+        let types = self.types(schema);
+        if types.contains(&"integer") {
+            if let Ok(n) = value.parse::<i64>() {
+                return n.into();
+            }
+        }
+        if types.contains(&"number") {
+            if let Some(n) = value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+            {
+                return Value::Number(n);
+            }
+        }
+        if types.contains(&"boolean") {
+            if let Ok(b) = value.parse::<bool>() {
+                return b.into();
+            }
+        }
+        value.into()
+    }
+
+    fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("unable to load OpenAPI spec `{path}`: {e}"))?;
+        // YAML is a superset of JSON, so both formats are parsed the same way
+        let root: Value = Figment::from(Yaml::string(&content))
+            .extract()
+            .map_err(|e| format!("invalid OpenAPI spec `{path}`: {e}"))?;
+        let Some(paths) = root["paths"].as_object() else {
+            return Err(format!("OpenAPI spec `{path}` has no `paths`"));
+        };
+
+        let paths = paths
+            .keys()
+            .map(|template| PathTemplate {
+                template: template.clone(),
+                segments: template
+                    .split('/')
+                    .skip(1)
+                    .map(
+                        |s| match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                            Some(name) => Segment::Param(name.into()),
+                            None => Segment::Literal(s.into()),
+                        },
+                    )
+                    .collect(),
+            })
+            .collect();
+        // Path of the servers' URLs is a prefix of all paths
+        let mut base_paths: Vec<String> = root["servers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|server| server["url"].as_str())
+            .map(|url| {
+                // Absolute URL is stripped to its path, relative one is a path itself
+                let path = match url.split_once("://") {
+                    Some((_, rest)) => rest.find('/').map_or("", |pos| &rest[pos..]),
+                    None => url,
+                };
+                path.trim_end_matches('/').to_string()
+            })
+            .collect();
+        if base_paths.is_empty() {
+            base_paths.push(String::new());
+        }
+        base_paths.sort();
+        base_paths.dedup();
+
+        Ok(Self {
+            path: path.into(),
+            root,
+            base_paths,
+            paths,
+        })
+    }
+}
+
+fn is_type(value: &Value, t: &str) -> bool {
+    match t {
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn percent_decode(segment: &str) -> String {
+    form_urlencoded::parse(format!("v={}", segment.replace('+', "%2B")).as_bytes())
+        .next()
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_default()
+}
+
+impl std::fmt::Debug for OpenApiSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+impl Serialize for OpenApiSpec {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl<'de> Deserialize<'de> for OpenApiSpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct OpenApiSpecVisitor;
+        impl Visitor<'_> for OpenApiSpecVisitor {
+            type Value = OpenApiSpec;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("path to OpenAPI spec file")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                OpenApiSpec::from_file(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(OpenApiSpecVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(
+        spec: &OpenApiSpec,
+        method: &str,
+        uri: &str,
+        body: &str,
+    ) -> Result<(), Vec<String>> {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(CONTENT_TYPE, "application/json; charset=utf-8")
+            .body(())
+            .unwrap();
+        let operation = spec.validate_head(&req)?;
+        spec.validate_body(&operation, req.headers(), &Bytes::from(body.to_string()))
+    }
+
+    #[test]
+    fn validate_requests() {
+        let spec = OpenApiSpec::from_file("tests/openapi/petstore.yaml").unwrap();
+
+        assert_eq!(validate(&spec, "GET", "/v1/pets?limit=10", ""), Ok(()));
+        assert_eq!(validate(&spec, "GET", "/v1/pets/mine", ""), Ok(()));
+        assert_eq!(validate(&spec, "GET", "/v1/pets/42", ""), Ok(()));
+        assert_eq!(
+            validate(
+                &spec,
+                "POST",
+                "/v1/pets",
+                r#"{"name": "Rex", "tags": ["dog"]}"#
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            validate(&spec, "GET", "/v2/pets", ""),
+            Err(vec!["path `/v2/pets` isn't defined".into()])
+        );
+        assert_eq!(
+            validate(&spec, "DELETE", "/v1/pets", ""),
+            Err(vec!["method `DELETE` isn't defined for path `/pets`".into()])
+        );
+        assert_eq!(
+            validate(&spec, "GET", "/v1/pets/rex", ""),
+            Err(vec![
+                "path parameter `petId`: expected integer, got string".into()
+            ])
+        );
+        assert_eq!(
+            validate(&spec, "GET", "/v1/pets?limit=1000", ""),
+            Err(vec!["query parameter `limit`: should be <= 100".into()])
+        );
+        assert_eq!(
+            validate(&spec, "POST", "/v1/pets", ""),
+            Err(vec!["request body is required".into()])
+        );
+        assert_eq!(
+            validate(&spec, "POST", "/v1/pets", r#"{"tags": [1], "age": 1}"#),
+            Err(vec![
+                "body `$.name`: is required".into(),
+                "body `$.age`: isn't allowed".into(),
+                "body `$.tags[0]`: expected string, got integer".into(),
+            ])
+        );
+        assert!(validate(&spec, "POST", "/v1/pets", "not json").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use strum_macros::Display;
+
+/// Stage of the request processing before it's forwarded to the targets
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PipelineStage {
+    /// Listener's request normalization
+    Normalization,
+    /// Plugins' `on_request` hooks
+    Plugins,
+    /// Listener's headers transformations
+    Headers,
+    /// Evaluation of the targets' conditions
+    Conditions,
+}
+
+/// Order of the request processing stages, targets are requested after all of them.
+/// Default order is `normalization`, `plugins`, `conditions`, `headers`,
+/// so conditions see request as it's received (or changed by plugins) without listener's headers.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+#[serde(transparent)]
+pub struct PipelineConfig(Vec<PipelineStage>);
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self(vec![
+            PipelineStage::Normalization,
+            PipelineStage::Plugins,
+            PipelineStage::Conditions,
+            PipelineStage::Headers,
+        ])
+    }
+}
+
+impl PipelineConfig {
+    /// Returns stages in order of processing
+    pub fn stages(&self) -> &[PipelineStage] {
+        &self.0
+    }
+
+    /// Returns stages which are processed before the specified one
+    pub fn before(&self, stage: PipelineStage) -> &[PipelineStage] {
+        let pos = self.position(stage);
+        &self.0[..pos]
+    }
+
+    /// Returns `true` if the `first` stage is processed before the `second` one
+    pub fn is_before(&self, first: PipelineStage, second: PipelineStage) -> bool {
+        self.position(first) < self.position(second)
+    }
+
+    fn position(&self, stage: PipelineStage) -> usize {
+        self.0
+            .iter()
+            .position(|s| *s == stage)
+            .expect("pipeline stage is absent, looks like a BUG")
+    }
+}
+
+impl ConfigValidator for PipelineConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for stage in Self::default().0 {
+            let count = self.0.iter().filter(|s| **s == stage).count();
+            if count != 1 {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "pipeline stage `{stage}` should be present exactly once, found {count}"
+                    ),
+                });
+            }
+        }
+        // Normalized request is used to match static routes, cache and deduplication keys,
+        // so it's applied before anything else
+        if self.0.first() != Some(&PipelineStage::Normalization) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "pipeline should start with `normalization` stage".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline_order() {
+        let pipeline: PipelineConfig =
+            serde_json::from_str(r#"["normalization", "headers", "plugins", "conditions"]"#)
+                .unwrap();
+        assert!(pipeline.validate().is_ok());
+        assert_eq!(
+            pipeline.before(PipelineStage::Plugins),
+            [PipelineStage::Normalization, PipelineStage::Headers]
+        );
+        assert!(pipeline.is_before(PipelineStage::Headers, PipelineStage::Conditions));
+        assert!(
+            !PipelineConfig::default().is_before(PipelineStage::Headers, PipelineStage::Conditions)
+        );
+
+        let pipeline: PipelineConfig =
+            serde_json::from_str(r#"["normalization", "headers", "conditions"]"#).unwrap();
+        assert!(pipeline.validate().is_err());
+    }
+}
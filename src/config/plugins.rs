@@ -0,0 +1,87 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_PLUGIN_FUEL: u64 = 10_000_000;
+const DEFAULT_PLUGIN_MAX_MEMORY: usize = 16 * 1024 * 1024;
+/// Size of the single WASM memory page
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// WASM plugin which is called on the request processing hooks
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    path: PathBuf,
+    #[serde(default)]
+    config: serde_json::Value,
+    #[serde(default = "default_plugin_fuel")]
+    fuel: u64,
+    #[serde(default = "default_plugin_max_memory")]
+    max_memory: usize,
+}
+
+fn default_plugin_fuel() -> u64 {
+    DEFAULT_PLUGIN_FUEL
+}
+
+fn default_plugin_max_memory() -> usize {
+    DEFAULT_PLUGIN_MAX_MEMORY
+}
+
+impl PluginConfig {
+    /// Path to the WASM module, binary or text format
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Arbitrary plugin's config which is passed to each hook as is
+    pub fn config(&self) -> &serde_json::Value {
+        &self.config
+    }
+
+    /// Maximum number of instructions which single hook call may execute
+    pub fn fuel(&self) -> u64 {
+        self.fuel
+    }
+
+    /// Maximum size of the plugin's memory in bytes
+    pub fn max_memory(&self) -> usize {
+        self.max_memory
+    }
+}
+
+impl ConfigValidator for PluginConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !self.path.is_file() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("plugin module `{}` doesn't exist", self.path.display()),
+            });
+        }
+        if !cfg!(feature = "wasm-plugins") {
+            return Err(ConfigError::ValidateConfig {
+                cause:
+                    "plugins aren't supported, application is built without `wasm-plugins` feature"
+                        .into(),
+            });
+        }
+        if self.fuel == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "plugin `{}` should have non-zero `fuel`",
+                    self.path.display()
+                ),
+            });
+        }
+        if self.max_memory < WASM_PAGE_SIZE {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "plugin `{}` `max_memory` should be at least {WASM_PAGE_SIZE} bytes",
+                    self.path.display()
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use prost_reflect::{DescriptorPool, DynamicMessage, SerializeOptions};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
+use std::path::Path;
+
+/// Protobuf schema to decode message bodies, it's compiled from `.proto` file during config loading.
+///
+/// Imports are resolved relative to the schema's directory, well-known `google/protobuf/*.proto` types are built in.
+pub struct ProtoSchema {
+    path: String,
+    pool: DescriptorPool,
+}
+
+impl ProtoSchema {
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let include = Path::new(path)
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let pool = protox::Compiler::new([include])
+            .and_then(|mut compiler| {
+                compiler.include_imports(true).open_file(path)?;
+                Ok(compiler.descriptor_pool())
+            })
+            .map_err(|e| format!("invalid protobuf schema `{path}`: {e}"))?;
+
+        Ok(Self {
+            path: path.into(),
+            pool,
+        })
+    }
+
+    /// Returns full name of the message if it's declared, `package.Message` or `Message` without package
+    pub fn message_name(&self, name: &str) -> Option<String> {
+        if self.pool.get_message_by_name(name).is_some() {
+            return Some(name.into());
+        }
+        // Name without package is matched by suffix if it's unique
+        let suffix = format!(".{name}");
+        let mut found = self
+            .pool
+            .all_messages()
+            .filter(|message| message.full_name().ends_with(&suffix));
+        match (found.next(), found.next()) {
+            (Some(found), None) => Some(found.full_name().into()),
+            _ => None,
+        }
+    }
+
+    /// Decodes message as JSON object with field names as keys, unknown fields are dropped
+    pub fn decode(&self, message: &str, data: &[u8]) -> Option<Value> {
+        let descriptor = self
+            .pool
+            .get_message_by_name(message)
+            .or_else(|| self.pool.get_message_by_name(&self.message_name(message)?))?;
+        let message = DynamicMessage::decode(descriptor, data).ok()?;
+        let options = SerializeOptions::new()
+            .use_proto_field_name(true)
+            .stringify_64_bit_integers(false);
+
+        message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .ok()
+    }
+}
+
+impl std::fmt::Debug for ProtoSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+impl Serialize for ProtoSchema {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.path)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtoSchema {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ProtoSchemaVisitor;
+        impl Visitor<'_> for ProtoSchemaVisitor {
+            type Value = ProtoSchema;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("path to protobuf schema file")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                ProtoSchema::from_file(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(ProtoSchemaVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn decode_message() {
+        let schema = ProtoSchema::from_file("tests/protobuf/order.proto").unwrap();
+        assert_eq!(schema.message_name("Order"), Some("shop.Order".into()));
+        assert_eq!(schema.message_name("shop.Order"), Some("shop.Order".into()));
+        assert_eq!(schema.message_name("Unknown"), None);
+
+        let mut data = vec![0x0a, 0x03, b'o', b'-', b'1', 0x10, 0x01];
+        // Repeated tags ["a", "b"]
+        data.extend([0x1a, 0x01, b'a', 0x1a, 0x01, b'b']);
+        // Imported google.protobuf.Timestamp {seconds: 1700000000}
+        data.extend([0x22, 0x06, 0x08, 0x80, 0xe2, 0xcf, 0xaa, 0x06]);
+        // Unknown field
+        data.extend([0x40, 0x05]);
+
+        assert_eq!(
+            schema.decode("Order", &data),
+            Some(json!({
+                "id": "o-1",
+                "priority": "URGENT",
+                "tags": ["a", "b"],
+                "created_at": "2023-11-14T22:13:20Z"
+            }))
+        );
+        assert_eq!(schema.decode("shop.Order", &[0x0a, 0x05, b'a']), None);
+    }
+
+    #[test]
+    fn invalid_schema() {
+        assert!(ProtoSchema::from_file("tests/protobuf/unknown.proto").is_err());
+        assert!(
+            ProtoSchema::from_file("tests/configs/wrong/168-body-codec-unknown-message.yaml")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{context::secrets::SECRET_CTX_PREFIX, redaction};
+use figment::{
+    providers::Serialized,
+    value::{Dict, Tag, Value},
+    Figment,
+};
+use serde_json::{json, Map};
+use std::{collections::HashMap, fmt::Display};
+
+/// Key of the wrapped value in the temporary provider, which is used to assign a new tag to the value
+const TRACK_KEY: &str = "value";
+
+/// Origin of the config value, it's shown in the config dump
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Parameter isn't set, so its default value is used
+    Default,
+    /// Config file (or file of the config folder)
+    File(String),
+    /// File which is included by `include` patterns
+    Include(String),
+    /// Config which is posted to the admin API
+    Content,
+    /// Value of the file with expanded environment variables
+    Env(String),
+    /// Value of the file with expanded secret, it's masked in the dump
+    Secret(String),
+    /// Environment override like `HTTP_DRAGONFLY__LISTENERS__0__TIMEOUT`, keeps path of the value
+    Override(String),
+    /// Target template with the name
+    Template(String),
+    /// `defaults` section
+    Defaults,
+    /// Named expression
+    Expression(String),
+}
+
+/// Sources of the raw config values, values of the same origin share figment's tag,
+/// values which are created by the loader itself have no known source
+#[derive(Debug, Default)]
+pub struct Provenance {
+    sources: HashMap<Tag, Source>,
+    resolved: Option<Value>,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file:{path}"),
+            Source::Include(path) => write!(f, "include:{path}"),
+            Source::Content => write!(f, "content"),
+            Source::Env(path) | Source::Secret(path) => write!(f, "env:{path}"),
+            Source::Override(path) => write!(f, "override:{path}"),
+            Source::Template(name) => write!(f, "template:{name}"),
+            Source::Defaults => write!(f, "defaults"),
+            Source::Expression(name) => write!(f, "expression:{name}"),
+        }
+    }
+}
+
+impl Provenance {
+    /// Registers source of all values with the tag
+    pub fn register(&mut self, tag: Tag, source: Source) {
+        self.sources.insert(tag, source);
+    }
+
+    /// Returns copy of the value with a new tag, which refers to the source
+    pub fn track(&mut self, value: &Value, source: Source) -> Value {
+        let tracked = Figment::from(Serialized::default(TRACK_KEY, value))
+            .extract_inner::<Value>(TRACK_KEY)
+            .unwrap_or_else(|_| value.clone());
+        self.register(tracked.tag(), source);
+        tracked
+    }
+
+    /// Returns copy of the dictionary, each item is tracked by its own source
+    pub fn track_dict(&mut self, dict: &Dict, source: impl Fn(&str) -> Source) -> Dict {
+        dict.iter()
+            .map(|(name, value)| (name.clone(), self.track(value, source(name))))
+            .collect()
+    }
+
+    /// Tracks values of the parsed file which differ from the ones in the same file without expanded variables,
+    /// so they come from environment (or secrets); structure of both values is expected to be the same
+    pub fn track_env(&mut self, expanded: &mut Value, raw: &Value, path: &str) {
+        match (expanded, raw) {
+            (Value::Dict(_, expanded), Value::Dict(_, raw)) => {
+                for (key, value) in expanded {
+                    if let Some(raw) = raw.get(key) {
+                        self.track_env(value, raw, path);
+                    }
+                }
+            }
+            (Value::Array(_, expanded), Value::Array(_, raw)) => {
+                for (value, raw) in expanded.iter_mut().zip(raw) {
+                    self.track_env(value, raw, path);
+                }
+            }
+            (Value::Dict(..) | Value::Array(..), _) => {}
+            (expanded, raw) => {
+                let Some(raw) = raw.as_str().filter(|raw| raw.contains('$')) else {
+                    return;
+                };
+                if expanded.as_str() != Some(raw) {
+                    let source = if raw.contains(SECRET_CTX_PREFIX) {
+                        Source::Secret(path.into())
+                    } else {
+                        Source::Env(path.into())
+                    };
+                    *expanded = self.track(expanded, source);
+                }
+            }
+        }
+    }
+
+    /// Keeps final raw config to dump it later
+    pub fn resolve(&mut self, config: &Value) {
+        self.resolved = Some(config.clone());
+    }
+
+    /// Returns resolved config where each value is replaced with `{"value": ..., "source": ...}`,
+    /// missing parameters with default values are added using JSON schema of the config,
+    /// sensitive header values and secrets are masked
+    pub fn dump(&self, schema: &serde_json::Value) -> serde_json::Value {
+        let Some(config) = &self.resolved else {
+            return json!({});
+        };
+        Dump {
+            provenance: self,
+            schema,
+        }
+        .value(config, Some(schema))
+    }
+
+    fn source(&self, value: &Value) -> Option<&Source> {
+        self.sources.get(&value.tag())
+    }
+}
+
+/// Walks resolved config together with its JSON schema
+struct Dump<'a> {
+    provenance: &'a Provenance,
+    schema: &'a serde_json::Value,
+}
+
+impl Dump<'_> {
+    fn value(&self, value: &Value, schema: Option<&serde_json::Value>) -> serde_json::Value {
+        let schema = schema.and_then(|schema| self.object(schema));
+        match value {
+            Value::Dict(_, dict) => {
+                let properties = schema.and_then(|schema| schema.get("properties"));
+                let mut dump: Map<String, serde_json::Value> = dict
+                    .iter()
+                    .map(|(key, value)| {
+                        let schema = properties.and_then(|p| p.get(key));
+                        let value = if Self::is_sensitive_header_value(dict, key) {
+                            self.masked(value)
+                        } else {
+                            self.value(value, schema)
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect();
+                if let Some(serde_json::Value::Object(properties)) = properties {
+                    for (key, property) in properties {
+                        match property.get("default") {
+                            Some(default) if !default.is_null() && !dump.contains_key(key) => {
+                                dump.insert(
+                                    key.clone(),
+                                    Self::leaf(default.clone(), &Source::Default),
+                                );
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                serde_json::Value::Object(dump)
+            }
+            Value::Array(_, array) => {
+                let items = schema.and_then(|schema| schema.get("items"));
+                array.iter().map(|value| self.value(value, items)).collect()
+            }
+            _ => match self.provenance.source(value) {
+                Some(source @ Source::Secret(_)) => Self::leaf(json!(redaction::MASK), source),
+                source => Self::leaf(
+                    serde_json::to_value(value).unwrap_or_default(),
+                    source.unwrap_or(&Source::Default),
+                ),
+            },
+        }
+    }
+
+    fn masked(&self, value: &Value) -> serde_json::Value {
+        let source = self.provenance.source(value).unwrap_or(&Source::Default);
+        Self::leaf(json!(redaction::MASK), source)
+    }
+
+    fn leaf(value: serde_json::Value, source: &Source) -> serde_json::Value {
+        json!({"value": value, "source": source.to_string()})
+    }
+
+    /// Value of the `add` or `update` header transformation
+    fn is_sensitive_header_value(dict: &Dict, key: &str) -> bool {
+        key == "value"
+            && ["add", "update"].iter().any(|action| {
+                dict.get(*action)
+                    .and_then(Value::as_str)
+                    .is_some_and(redaction::is_sensitive)
+            })
+    }
+
+    /// Resolves schema of the object: references, single `allOf` and optional values,
+    /// alternatives of the enums are ambiguous, so their defaults aren't shown
+    fn object<'s>(&'s self, schema: &'s serde_json::Value) -> Option<&'s serde_json::Value> {
+        if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+            let name = reference.rsplit('/').next()?;
+            return self.object(self.schema.get("definitions")?.get(name)?);
+        }
+        for key in ["allOf", "anyOf"] {
+            if let Some(serde_json::Value::Array(variants)) = schema.get(key) {
+                let mut variants = variants
+                    .iter()
+                    .filter(|v| v.get("type").and_then(|t| t.as_str()) != Some("null"));
+                return match (variants.next(), variants.next()) {
+                    (Some(variant), None) => self.object(variant),
+                    _ => None,
+                };
+            }
+        }
+
+        Some(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_env_values() {
+        let mut provenance = Provenance::default();
+        let raw = Value::serialize(json!({
+            "id": "${ID:-first}",
+            "port": "${PORT}",
+            "url": "https://${CTX_SECRET_TOKEN}@example.com/",
+            "path": "${CTX_REQUEST_PATH}",
+            "list": ["literal"],
+        }))
+        .unwrap();
+        let mut expanded = Value::serialize(json!({
+            "id": "first",
+            "port": 8080,
+            "url": "https://secret@example.com/",
+            "path": "${CTX_REQUEST_PATH}",
+            "list": ["literal"],
+        }))
+        .unwrap();
+        provenance.register(expanded.tag(), Source::File("config.yaml".into()));
+        provenance.track_env(&mut expanded, &raw, "config.yaml");
+        provenance.resolve(&expanded);
+
+        assert_eq!(
+            provenance.dump(&json!({})),
+            json!({
+                "id": {"value": "first", "source": "env:config.yaml"},
+                "port": {"value": 8080, "source": "env:config.yaml"},
+                "url": {"value": "***", "source": "env:config.yaml"},
+                "path": {"value": "${CTX_REQUEST_PATH}", "source": "file:config.yaml"},
+                "list": [{"value": "literal", "source": "file:config.yaml"}],
+            })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{
+    header::{HeaderName, HeaderValue, USER_AGENT, VIA},
+    HeaderMap, Version,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+const DEFAULT_PSEUDONYM: &str = env!("CARGO_PKG_NAME");
+const SPLITTER_PRODUCT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Management of the `User-Agent` and `Via` headers of the target requests,
+/// and detection of the requests which have passed this splitter already
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyHeadersConfig {
+    #[serde(default)]
+    user_agent: ProxyHeaderMode,
+    #[serde(default = "ProxyHeadersConfig::default_via")]
+    via: ProxyHeaderMode,
+    #[serde(default = "ProxyHeadersConfig::default_pseudonym")]
+    pseudonym: String,
+    #[serde(default = "ProxyHeadersConfig::default_loop_detection")]
+    loop_detection: bool,
+}
+
+/// How splitter's identity is added to the header of the target request
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyHeaderMode {
+    /// Requester's value is forwarded as is
+    #[default]
+    Preserve,
+    /// Requester's value is replaced with splitter's identity
+    Replace,
+    /// Splitter's identity is added to the requester's value
+    Append,
+}
+
+impl ProxyHeadersConfig {
+    fn default_via() -> ProxyHeaderMode {
+        ProxyHeaderMode::Append
+    }
+
+    fn default_pseudonym() -> String {
+        DEFAULT_PSEUDONYM.into()
+    }
+
+    fn default_loop_detection() -> bool {
+        true
+    }
+
+    /// Returns `true` if request has passed splitter with the same pseudonym already,
+    /// so forwarding it again would create a loop
+    pub fn is_loop(&self, headers: &HeaderMap) -> bool {
+        self.loop_detection
+            && headers
+                .get_all(VIA)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .filter_map(|entry| entry.split_whitespace().nth(1))
+                .any(|received_by| received_by.eq_ignore_ascii_case(&self.pseudonym))
+    }
+
+    /// Sets `User-Agent` and `Via` headers of the target request according to the configured modes,
+    /// `version` is the protocol version of the incoming request
+    pub fn apply(&self, headers: &mut HeaderMap, version: Version) {
+        Self::apply_mode(headers, USER_AGENT, self.user_agent, SPLITTER_PRODUCT, " ");
+
+        let protocol = match version {
+            Version::HTTP_09 => "0.9",
+            Version::HTTP_10 => "1.0",
+            Version::HTTP_2 => "2",
+            Version::HTTP_3 => "3",
+            _ => "1.1",
+        };
+        let via = format!("{protocol} {}", self.pseudonym);
+        Self::apply_mode(headers, VIA, self.via, &via, ", ");
+    }
+
+    fn apply_mode(
+        headers: &mut HeaderMap,
+        name: HeaderName,
+        mode: ProxyHeaderMode,
+        identity: &str,
+        separator: &str,
+    ) {
+        let value = match mode {
+            ProxyHeaderMode::Preserve => return,
+            ProxyHeaderMode::Replace => identity.to_string(),
+            ProxyHeaderMode::Append => {
+                let current = headers
+                    .get_all(&name)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect::<Vec<_>>()
+                    .join(separator);
+                if current.is_empty() {
+                    identity.to_string()
+                } else {
+                    format!("{current}{separator}{identity}")
+                }
+            }
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+impl ConfigValidator for ProxyHeadersConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let valid = !self.pseudonym.is_empty()
+            && self
+                .pseudonym
+                .bytes()
+                .all(|c| c.is_ascii_graphic() && c != b',');
+        if !valid {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "invalid `pseudonym` `{}` of the proxy headers, it should be a single token without commas",
+                    self.pseudonym
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_proxy_headers() {
+        let cfg: ProxyHeadersConfig =
+            serde_json::from_str(r#"{"user_agent": "append", "pseudonym": "edge-1"}"#).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("curl/8.0"));
+        headers.insert(VIA, HeaderValue::from_static("1.0 fred"));
+        assert!(!cfg.is_loop(&headers));
+
+        cfg.apply(&mut headers, Version::HTTP_11);
+        assert_eq!(headers[USER_AGENT], format!("curl/8.0 {SPLITTER_PRODUCT}"));
+        assert_eq!(headers[VIA], "1.0 fred, 1.1 edge-1");
+        assert!(cfg.is_loop(&headers));
+
+        let cfg: ProxyHeadersConfig =
+            serde_json::from_str(r#"{"user_agent": "replace", "via": "replace"}"#).unwrap();
+        cfg.apply(&mut headers, Version::HTTP_2);
+        assert_eq!(headers[USER_AGENT], SPLITTER_PRODUCT);
+        assert_eq!(headers[VIA], format!("2 {DEFAULT_PSEUDONYM}"));
+
+        let cfg: ProxyHeadersConfig =
+            serde_json::from_str(r#"{"via": "preserve", "loop_detection": false}"#).unwrap();
+        cfg.apply(&mut headers, Version::HTTP_11);
+        assert_eq!(headers[USER_AGENT], SPLITTER_PRODUCT);
+        assert_eq!(headers[VIA], format!("2 {DEFAULT_PSEUDONYM}"));
+        assert!(!cfg.is_loop(&headers));
+    }
+}
@@ -0,0 +1,176 @@
+use super::{
+    headers::{HeaderTransform, HeaderTransformActon},
+    script::{context_map, Script},
+};
+use crate::context::Context;
+use rhai::{Map, Scope};
+use shellexpand::env_with_context_no_errors;
+use tracing::{debug, warn};
+
+/// Query parameters transformations have the same shape as headers ones
+pub type QueryTransformsList = Vec<HeaderTransform>;
+
+pub trait QueryTransformator {
+    /// Returns transformed query string, parameters which aren't changed are kept as is
+    fn transform_query(&self, query: Option<&str>, ctx: &Context) -> Option<String>;
+}
+
+impl QueryTransformator for QueryTransformsList {
+    fn transform_query(&self, query: Option<&str>, ctx: &Context) -> Option<String> {
+        // Decoded name and raw `name=value` pair
+        let mut params: Vec<(String, String)> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| (decoded_name(pair), pair.to_string()))
+            .collect();
+
+        for transform in self {
+            let value = || {
+                let value = transform.value().map(String::as_str).unwrap_or_default();
+                env_with_context_no_errors(value, |v| ctx.lookup(v)).into_owned()
+            };
+            match transform.action() {
+                HeaderTransformActon::Add(name) => {
+                    if !params.iter().any(|(n, _)| n == name) {
+                        let value = value();
+                        debug!("query add: name={name}, value={value}");
+                        params.push((name.clone(), encoded_pair(name, &value)));
+                    }
+                }
+                HeaderTransformActon::Update(name) => {
+                    if let Some(pos) = params.iter().position(|(n, _)| n == name) {
+                        let value = value();
+                        debug!("query update: name={name}, value={value}");
+                        params[pos].1 = encoded_pair(name, &value);
+                        // All other values of the parameter are replaced by the first one
+                        let mut first = true;
+                        params.retain(|(n, _)| n != name || std::mem::take(&mut first));
+                    }
+                }
+                HeaderTransformActon::Drop(name) => {
+                    debug!("query drop: name={name}");
+                    match name.strip_suffix('*') {
+                        Some(prefix) => params.retain(|(n, _)| !n.starts_with(prefix)),
+                        None => params.retain(|(n, _)| n != name),
+                    }
+                }
+                HeaderTransformActon::Script(script) => {
+                    transform_by_script(script, &mut params, ctx)
+                }
+            }
+        }
+
+        let query: Vec<String> = params.into_iter().map(|(_, pair)| pair).collect();
+        (!query.is_empty()).then(|| query.join("&"))
+    }
+}
+
+/// Runs script with `query` and `ctx` maps and applies changes of `query` map,
+/// only the first value of each parameter is present in the map
+fn transform_by_script(script: &Script, params: &mut Vec<(String, String)>, ctx: &Context) {
+    let mut original = Map::new();
+    for (_, pair) in params.iter() {
+        if let Some((name, value)) = form_urlencoded::parse(pair.as_bytes()).next() {
+            original
+                .entry(name.as_ref().into())
+                .or_insert_with(|| value.into_owned().into());
+        }
+    }
+    let mut scope = Scope::new();
+    scope.push("query", original.clone());
+    scope.push_constant("ctx", context_map(ctx));
+
+    if let Err(e) = script.run(&mut scope) {
+        warn!("script: unable to transform query, it's kept as is: {e}");
+        return;
+    }
+    let Some(changed) = scope.get_value::<Map>("query") else {
+        warn!("script: `query` isn't a map anymore, query is kept as is");
+        return;
+    };
+
+    params.retain(|(n, _)| changed.contains_key(n.as_str()));
+    for (name, value) in changed {
+        let value = value.to_string();
+        if original
+            .get(&name)
+            .is_some_and(|old| old.to_string() == value)
+        {
+            continue;
+        }
+        debug!("query script: set name={name}, value={value}");
+        let pair = encoded_pair(&name, &value);
+        match params.iter().position(|(n, _)| n == name.as_str()) {
+            Some(pos) => {
+                params[pos].1 = pair;
+                let mut first = true;
+                params.retain(|(n, _)| n != name.as_str() || std::mem::take(&mut first));
+            }
+            None => params.push((name.to_string(), pair)),
+        }
+    }
+}
+
+fn decoded_name(pair: &str) -> String {
+    form_urlencoded::parse(pair.as_bytes())
+        .next()
+        .map(|(name, _)| name.into_owned())
+        .unwrap_or_default()
+}
+
+fn encoded_pair(name: &str, value: &str) -> String {
+    form_urlencoded::Serializer::new(String::new())
+        .append_pair(name, value)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::test_context::get_test_ctx;
+
+    #[test]
+    fn query_transforms() {
+        let ctx = get_test_ctx();
+        let transforms: QueryTransformsList = serde_json::from_str(
+            r#"
+        [
+            {"add": "dry_run", "value": "true"},
+            {"add": "existing", "value": "wrong"},
+            {"update": "existing", "value": "new value"},
+            {"update": "non_existing", "value": "wrong"},
+            {"drop": "utm_*"},
+            {"drop": "to drop"},
+            {"add": "env", "value": "${TEST_ENV_HEADER_TO_ADD}"}
+        ]
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            transforms.transform_query(
+                Some("q=a%20b&existing=1&utm_source=x&existing=2&to+drop=1&utm_medium=y"),
+                ctx
+            ),
+            Some("q=a%20b&existing=new+value&dry_run=true&env=TEST_ENV_HEADER_VALUE".into())
+        );
+
+        let drop_all: QueryTransformsList = serde_json::from_str(r#"[{"drop": "*"}]"#).unwrap();
+        assert_eq!(drop_all.transform_query(Some("a=1&b=2"), ctx), None);
+    }
+
+    #[test]
+    fn query_script() {
+        let ctx = get_test_ctx();
+        let transforms: QueryTransformsList = serde_json::from_str(
+            r#"[{"script": "query.remove(\"debug\"); query.page = parse_int(query.page) + 1; query.env = ctx.TEST_ENV_HEADER_TO_ADD;"}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            transforms.transform_query(Some("q=a%20b&debug=1&page=1&page=5"), ctx),
+            Some("q=a%20b&page=2&env=TEST_ENV_HEADER_VALUE".into())
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use super::{target::TargetConfig, ConfigError};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Handling of the requests with `Range` header: the range is forwarded to the primary target only,
+/// other targets (mirrors) are requested for the full body or aren't requested at all
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RangesConfig {
+    primary: Option<String>,
+    #[serde(default)]
+    mirrors: MirrorRanges,
+}
+
+/// What to do with ranged request for the mirror targets
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub enum MirrorRanges {
+    /// Request full body, `Range` and `If-Range` headers are removed
+    #[default]
+    Full,
+    /// Don't request mirrors at all
+    Skip,
+}
+
+impl RangesConfig {
+    /// Returns ID of the target which gets the range, the strategy's selected target is used if it isn't defined
+    pub fn primary(&self) -> Option<&str> {
+        self.primary.as_deref()
+    }
+
+    pub fn mirrors(&self) -> MirrorRanges {
+        self.mirrors
+    }
+
+    pub fn validate_targets(&self, targets: &[TargetConfig]) -> Result<(), ConfigError> {
+        match &self.primary {
+            Some(primary) if !targets.iter().any(|t| &t.id() == primary) => {
+                Err(ConfigError::ValidateConfig {
+                    cause: format!("`ranges` points to unknown primary target `{primary}`"),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,70 +1,470 @@
 use super::{
+    body_transform::BodyTransformConfig,
+    failure::{FailureClassifier, GrpcStatus},
     headers::{HeaderTransform, HeadersTransformator},
-    ConfigValidator,
+    listener::ResponseStrategy,
+    script::{context_map, response_map, Script},
+    target::TargetConfig,
+    ConfigValidator, ConfigWarning,
 };
 use crate::{
     config,
     context::Context,
     handler::{ResponseResult, ResponsesMap},
+    ranges,
+    strategy::{self, StrategyDecision, StrategyTarget},
 };
 use http_body_util::Full;
-use hyper::{body::Bytes, header::CONTENT_LENGTH, http::Error, Response, StatusCode};
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue, CONTENT_LENGTH},
+    http::Error,
+    HeaderMap, Response, StatusCode,
+};
 use regex::Regex;
-use serde::{Deserialize, Serialize};
+use rhai::{Map, Scope};
+use schemars::JsonSchema;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 use shellexpand::env_with_context_no_errors;
-use tracing::debug;
+use std::{collections::BTreeMap, time::Duration};
+use strum_macros::Display;
+use tracing::{debug, warn};
 
 pub type ResponseStatus = u16;
 
 const UNABLE_TO_CREATE_RESPONSE_ERROR: &str = "unable to create response, looks like a BUG";
+/// Request header to ask for explanation, and response header with it
+pub const EXPLAIN_HEADER: &str = "x-splitter-explain";
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields, default)]
 pub struct ResponseConfig {
     target_selector: Option<String>,
-    failed_status_regex: String,
+    target_selection: Option<TargetSelection>,
+    #[schemars(with = "String")]
+    failed_status_regex: StatusRegex,
     no_targets_status: ResponseStatus,
     #[serde(rename = "override")]
     override_config: Option<OverrideConfig>,
+    cancel_unneeded_targets: bool,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    max_wait: Option<Duration>,
+    headers: Option<Vec<HeaderTransform>>,
+    copy_headers: Option<Vec<CopyHeadersConfig>>,
+    debug_headers: bool,
+    explain: ExplainMode,
+    failure_detection: FailureDetection,
+    body_transform: Option<BodyTransformConfig>,
+    #[schemars(with = "Option<String>")]
+    script: Option<Script>,
+    custom_strategy: Option<String>,
+    preferred_target_id: Option<String>,
+    fallback_order: Option<Vec<String>>,
 }
 
 impl Default for ResponseConfig {
     fn default() -> Self {
         Self {
             target_selector: Default::default(),
-            failed_status_regex: "4\\d{2}|5\\d{2}".into(),
+            target_selection: None,
+            failed_status_regex: StatusRegex::from("4\\d{2}|5\\d{2}"),
             no_targets_status: 500,
             override_config: None,
+            cancel_unneeded_targets: false,
+            max_wait: None,
+            headers: None,
+            copy_headers: None,
+            debug_headers: false,
+            explain: ExplainMode::default(),
+            failure_detection: FailureDetection::default(),
+            body_transform: None,
+            script: None,
+            custom_strategy: None,
+            preferred_target_id: None,
+            fallback_order: None,
+        }
+    }
+}
+
+/// Regex to match response status, it's compiled once during config loading
+pub struct StatusRegex(Regex);
+
+impl StatusRegex {
+    pub fn is_match(&self, status: &str) -> bool {
+        self.0.is_match(status)
+    }
+}
+
+impl From<&str> for StatusRegex {
+    fn from(value: &str) -> Self {
+        Self(Regex::new(value).expect("invalid status regex"))
+    }
+}
+
+impl std::fmt::Debug for StatusRegex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.as_str().fmt(f)
+    }
+}
+
+impl Serialize for StatusRegex {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StatusRegexVisitor;
+        impl Visitor<'_> for StatusRegexVisitor {
+            type Value = StatusRegex;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("regex to match response status")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Regex::new(v)
+                    .map(StatusRegex)
+                    .map_err(|e| E::custom(format!("invalid status regex `{v}`: {e}")))
+            }
+        }
+
+        deserializer.deserialize_string(StatusRegexVisitor)
+    }
+}
+
+/// What to use to classify target's response as failed
+#[derive(Deserialize, Debug, Serialize, JsonSchema, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureDetection {
+    /// Response status matches `failed_status_regex`
+    #[default]
+    HttpStatus,
+    /// Non-zero `grpc-status` trailer (or header), HTTP status is used if there is no `grpc-status`
+    GrpcStatus,
+}
+
+/// How to select the target for `*_target_id` strategies on each request instead of the static `target_selector`
+#[derive(Deserialize, Debug, Serialize, JsonSchema, Clone, Copy, PartialEq, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TargetSelection {
+    /// Random target, probability is proportional to the target's `weight`
+    WeightedRandom,
+    /// Targets in turn
+    RoundRobin,
+    /// Target with the lowest rate of failures among its recent requests
+    LeastErrors,
+}
+
+/// When to explain routing decision of the request
+#[derive(Deserialize, Debug, Serialize, JsonSchema, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplainMode {
+    #[default]
+    Never,
+    /// Each request is explained
+    Always,
+    /// Requests with `X-Splitter-Explain: true` header are explained
+    OnHeader,
+}
+
+impl ExplainMode {
+    /// Returns `true` if routing decision of the request with these headers should be explained
+    pub fn is_requested(&self, headers: &HeaderMap) -> bool {
+        match self {
+            ExplainMode::Never => false,
+            ExplainMode::Always => true,
+            ExplainMode::OnHeader => headers
+                .get(EXPLAIN_HEADER)
+                .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"true")),
         }
     }
 }
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct OverrideConfig {
     status: Option<ResponseStatus>,
     body: Option<String>,
     headers: Option<Vec<HeaderTransform>>,
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, OverrideCaseConfig>")]
+    cases: BTreeMap<StatusCase, OverrideCaseConfig>,
+}
+
+/// Override of the response which matches particular status case,
+/// it replaces the default override config, so empty case passes response through as is
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OverrideCaseConfig {
+    status: Option<ResponseStatus>,
+    body: Option<String>,
+    headers: Option<Vec<HeaderTransform>>,
+}
+
+/// Key of the override case, cases are matched in order of declaration of the variants:
+/// from the most specific to the widest one
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusCase {
+    /// Target hasn't responded in time
+    Timeout,
+    /// Exact status code, like `404`
+    Code(ResponseStatus),
+    /// Inclusive range of status codes, like `500-503`
+    Range(ResponseStatus, ResponseStatus),
+    /// Status class, like `5xx`
+    Class(ResponseStatus),
+}
+
+/// Marks error response of the timed out target
+#[derive(Clone, Copy, Debug)]
+struct TimedOut;
+
+impl OverrideConfig {
+    /// Returns status, body and headers override for the response,
+    /// the first matched case replaces the default ones
+    fn parts(
+        &self,
+        status: StatusCode,
+        timed_out: bool,
+    ) -> (
+        Option<ResponseStatus>,
+        Option<&String>,
+        Option<&Vec<HeaderTransform>>,
+    ) {
+        match self
+            .cases
+            .iter()
+            .find(|(case, _)| case.is_match(status, timed_out))
+        {
+            Some((case, cfg)) => {
+                debug!("override case matched: {case}");
+                (cfg.status, cfg.body.as_ref(), cfg.headers.as_ref())
+            }
+            None => (self.status, self.body.as_ref(), self.headers.as_ref()),
+        }
+    }
+
+    fn is_body_overridden(&self) -> bool {
+        self.body.is_some() || self.cases.values().any(|cfg| cfg.body.is_some())
+    }
+
+    /// Returns `true` if override changes nothing
+    fn is_empty(&self) -> bool {
+        self.status.is_none()
+            && self.body.is_none()
+            && self.headers.is_none()
+            && self.cases.is_empty()
+    }
+}
+
+impl StatusCase {
+    fn is_match(&self, status: StatusCode, timed_out: bool) -> bool {
+        let status = status.as_u16();
+        match self {
+            StatusCase::Timeout => timed_out,
+            StatusCase::Code(code) => status == *code,
+            StatusCase::Range(from, to) => (*from..=*to).contains(&status),
+            StatusCase::Class(class) => status / 100 == *class,
+        }
+    }
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        let parse_status = |v: &str| {
+            v.parse::<ResponseStatus>()
+                .ok()
+                .filter(|s| (100..=599).contains(s))
+                .ok_or_else(|| format!("invalid status `{v}` in override case `{value}`"))
+        };
+
+        if value == "timeout" {
+            Ok(StatusCase::Timeout)
+        } else if let Some(class) = value.strip_suffix("xx") {
+            match class.parse::<ResponseStatus>() {
+                Ok(class) if (1..=5).contains(&class) => Ok(StatusCase::Class(class)),
+                _ => Err(format!("invalid status class in override case `{value}`")),
+            }
+        } else if let Some((from, to)) = value.split_once('-') {
+            let (from, to) = (parse_status(from)?, parse_status(to)?);
+            if from > to {
+                return Err(format!("invalid status range in override case `{value}`"));
+            }
+            Ok(StatusCase::Range(from, to))
+        } else {
+            Ok(StatusCase::Code(parse_status(value)?))
+        }
+    }
+}
+
+impl std::fmt::Display for StatusCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusCase::Timeout => write!(f, "timeout"),
+            StatusCase::Code(code) => write!(f, "{code}"),
+            StatusCase::Range(from, to) => write!(f, "{from}-{to}"),
+            StatusCase::Class(class) => write!(f, "{class}xx"),
+        }
+    }
+}
+
+impl std::fmt::Debug for StatusCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_string().fmt(f)
+    }
+}
+
+impl Serialize for StatusCase {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCase {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StatusCaseVisitor;
+        impl Visitor<'_> for StatusCaseVisitor {
+            type Value = StatusCase;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("status code, range of codes, class like `5xx`, or `timeout`")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                StatusCase::from_str(v).map_err(E::custom)
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                self.visit_str(&v.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(StatusCaseVisitor)
+    }
+}
+
+/// Headers to copy from the response of the particular target to the final response
+#[derive(Deserialize, Debug, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CopyHeadersConfig {
+    from: String,
+    headers: Vec<String>,
+}
+
+impl CopyHeadersConfig {
+    pub fn from(&self) -> &str {
+        &self.from
+    }
 }
 
 impl ConfigValidator for ResponseConfig {
     fn validate(&self) -> Result<(), config::ConfigError> {
+        for copy in self.copy_headers.iter().flatten() {
+            for header in &copy.headers {
+                if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    return Err(config::ConfigError::ValidateConfig {
+                        cause: format!(
+                            "invalid header name `{header}` to copy from target `{}`",
+                            copy.from
+                        ),
+                    });
+                }
+            }
+        }
+        if let Some(body_transform) = &self.body_transform {
+            body_transform.validate()?;
+        }
+        if self.max_wait == Some(Duration::ZERO) {
+            return Err(config::ConfigError::ValidateConfig {
+                cause: "response `max_wait` should be greater than zero".into(),
+            });
+        }
+
         Ok(())
     }
+
+    fn lint(&self) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        if self
+            .override_config
+            .as_ref()
+            .is_some_and(OverrideConfig::is_empty)
+        {
+            warnings.push(ConfigWarning::new(
+                "response `override` is empty, so it has no effect",
+            ));
+        }
+
+        warnings
+    }
 }
 
 pub trait ResponseBehavior {
     fn target_selector(&self) -> &Option<String>;
+    fn target_selection(&self) -> Option<TargetSelection>;
+    fn script(&self) -> Option<&Script>;
+    fn custom_strategy(&self) -> Option<&str>;
+    fn preferred_target_id(&self) -> Option<&str>;
+    fn fallback_order(&self) -> &[String];
+    fn cancel_unneeded_targets(&self) -> bool;
+    fn max_wait(&self) -> Option<Duration>;
+    fn copy_headers_config(&self) -> &Option<Vec<CopyHeadersConfig>>;
+    fn debug_headers(&self) -> bool;
+    fn explain(&self) -> ExplainMode;
+    fn is_body_overridden(&self) -> bool;
+    fn is_body_transformed(&self) -> bool;
+    fn target_response(&self, resp: Response<Full<Bytes>>, ctx: &Context) -> Response<Full<Bytes>>;
+    fn copy_headers(&self, responses: &ResponsesMap, resp: &mut Response<Full<Bytes>>);
+    fn is_response_determined(
+        &self,
+        strategy: &ResponseStrategy,
+        responses: &ResponsesMap,
+        targets: &[TargetConfig],
+        selected_target_id: &Option<String>,
+    ) -> bool;
     fn override_response(
-        &'static self,
+        &self,
         resp: Response<Full<Bytes>>,
         ctx: &Context,
     ) -> Response<Full<Bytes>>;
+    fn is_failed(&self, resp: &Response<Full<Bytes>>, target: Option<&TargetConfig>) -> bool;
     fn find_first_response(
         &self,
         responses: &ResponsesMap,
+        targets: &[TargetConfig],
         response_kind: ResponseKind,
     ) -> Option<String>;
+    fn find_preferred_response(
+        &self,
+        responses: &ResponsesMap,
+        targets: &[TargetConfig],
+    ) -> Option<String>;
     fn error_response(
         &self,
         e: ResponseResult,
@@ -72,30 +472,41 @@ pub trait ResponseBehavior {
     ) -> Response<Full<Bytes>>;
     fn empty_response(&self, status: ResponseStatus) -> Result<Response<Full<Bytes>>, Error>;
     fn override_empty_response(
-        &'static self,
+        &self,
         status: ResponseStatus,
         ctx: &Context,
     ) -> Result<Response<Full<Bytes>>, Error>;
-    fn no_target_response(&'static self, ctx: &Context) -> Result<Response<Full<Bytes>>, Error>;
+    fn no_target_response(&self, ctx: &Context) -> Result<Response<Full<Bytes>>, Error>;
     fn select_from_two_targets_response(
-        &'static self,
+        &self,
         first_target_id: Option<String>,
         second_target_id: Option<String>,
         responses: &mut ResponsesMap,
         ctx: &Context,
     ) -> Response<Full<Bytes>>;
     fn select_target_or_override_response(
-        &'static self,
+        &self,
         target_id: Option<String>,
         responses: &mut ResponsesMap,
         ctx: &Context,
     ) -> Response<Full<Bytes>>;
     fn select_target_or_error_response(
-        &'static self,
+        &self,
         target_id: Option<String>,
         responses: &mut ResponsesMap,
         ctx: &Context,
     ) -> Response<Full<Bytes>>;
+    fn select_by_script_response(
+        &self,
+        responses: &mut ResponsesMap,
+        ctx: &Context,
+    ) -> Response<Full<Bytes>>;
+    fn select_by_custom_strategy_response(
+        &self,
+        responses: &mut ResponsesMap,
+        targets: &[TargetConfig],
+        ctx: &Context,
+    ) -> Response<Full<Bytes>>;
 }
 
 #[derive(Debug)]
@@ -109,17 +520,157 @@ impl ResponseBehavior for ResponseConfig {
         &self.target_selector
     }
 
+    fn target_selection(&self) -> Option<TargetSelection> {
+        self.target_selection
+    }
+
+    fn script(&self) -> Option<&Script> {
+        self.script.as_ref()
+    }
+
+    /// Returns name of the registered strategy for `custom` response strategy
+    fn custom_strategy(&self) -> Option<&str> {
+        self.custom_strategy.as_deref()
+    }
+
+    /// Returns ID of the target which response is preferred for `preferred_then_fallback` strategy
+    fn preferred_target_id(&self) -> Option<&str> {
+        self.preferred_target_id.as_deref()
+    }
+
+    /// Returns IDs of the targets to fall back to, in order, if preferred target's response isn't OK
+    fn fallback_order(&self) -> &[String] {
+        self.fallback_order.as_deref().unwrap_or_default()
+    }
+
+    fn cancel_unneeded_targets(&self) -> bool {
+        self.cancel_unneeded_targets
+    }
+
+    /// Returns deadline of the response decision, targets which haven't responded yet aren't awaited after it
+    fn max_wait(&self) -> Option<Duration> {
+        self.max_wait
+    }
+
+    fn copy_headers_config(&self) -> &Option<Vec<CopyHeadersConfig>> {
+        &self.copy_headers
+    }
+
+    fn debug_headers(&self) -> bool {
+        self.debug_headers
+    }
+
+    fn explain(&self) -> ExplainMode {
+        self.explain
+    }
+
+    fn is_body_overridden(&self) -> bool {
+        self.override_config
+            .as_ref()
+            .is_some_and(OverrideConfig::is_body_overridden)
+    }
+
+    fn is_body_transformed(&self) -> bool {
+        self.body_transform.is_some()
+    }
+
+    /// Transforms body of the selected target's response and applies override to it
+    fn target_response(&self, resp: Response<Full<Bytes>>, ctx: &Context) -> Response<Full<Bytes>> {
+        let resp = match &self.body_transform {
+            Some(body_transform) => body_transform.transform(resp),
+            None => resp,
+        };
+        self.override_response(resp, ctx)
+    }
+
+    /// Copies configured headers from responses of other targets into the final response,
+    /// targets without response (failed, dropped or cancelled) are skipped
+    fn copy_headers(&self, responses: &ResponsesMap, resp: &mut Response<Full<Bytes>>) {
+        for copy in self.copy_headers.iter().flatten() {
+            let Some((Some(source), _)) = responses.get(&copy.from) else {
+                debug!("no response to copy headers from, target: {}", copy.from);
+                continue;
+            };
+            for header in &copy.headers {
+                let values: Vec<HeaderValue> =
+                    source.headers().get_all(header).iter().cloned().collect();
+                if values.is_empty() {
+                    continue;
+                }
+                let name = HeaderName::from_bytes(header.as_bytes())
+                    .expect("invalid header name, looks like a BUG");
+                debug!("copy: name={name}, from={}", copy.from);
+                resp.headers_mut().remove(&name);
+                for value in values {
+                    resp.headers_mut().append(&name, value);
+                }
+            }
+        }
+    }
+
+    /// Verifies if final response is already known with the responses received so far,
+    /// so the rest of the targets can be cancelled
+    fn is_response_determined(
+        &self,
+        strategy: &ResponseStrategy,
+        responses: &ResponsesMap,
+        targets: &[TargetConfig],
+        selected_target_id: &Option<String>,
+    ) -> bool {
+        match strategy {
+            // Override doesn't depend on targets, but all of them are queried intentionally
+            ResponseStrategy::AlwaysOverride
+            | ResponseStrategy::Script
+            | ResponseStrategy::Custom => false,
+            ResponseStrategy::OkThenOverride
+            | ResponseStrategy::OkThenTargetId
+            | ResponseStrategy::OkThenFailed => self
+                .find_first_response(responses, targets, ResponseKind::Ok)
+                .is_some(),
+            ResponseStrategy::FailedThenOverride
+            | ResponseStrategy::FailedThenTargetId
+            | ResponseStrategy::FailedThenOk => self
+                .find_first_response(responses, targets, ResponseKind::Failed)
+                .is_some(),
+            ResponseStrategy::AlwaysTargetId | ResponseStrategy::ConditionalRouting => {
+                selected_target_id
+                    .as_ref()
+                    .is_some_and(|id| responses.contains_key(id))
+            }
+            // Determined by the first OK response in order, or when all targets in order have responded
+            ResponseStrategy::PreferredThenFallback => {
+                for id in self.preferred_target_id.iter().chain(self.fallback_order()) {
+                    match responses.get(id) {
+                        None => return false,
+                        Some((Some(resp), _)) => {
+                            let target = targets.iter().find(|target| target.id() == *id);
+                            if !self.is_failed(resp, target) {
+                                return true;
+                            }
+                        }
+                        Some((None, _)) => {}
+                    }
+                }
+                true
+            }
+        }
+    }
+
     fn override_response(
-        &'static self,
+        &self,
         resp: Response<Full<Bytes>>,
         ctx: &Context,
     ) -> Response<Full<Bytes>> {
-        if let Some(cfg) = &self.override_config {
+        let resp = if let Some(cfg) = &self.override_config {
             let (resp_parts, resp_body) = resp.into_parts();
+            let (status, body, transforms) = cfg.parts(
+                resp_parts.status,
+                resp_parts.extensions.get::<TimedOut>().is_some(),
+            );
             let mut new_resp = Response::builder();
 
             // Set status
-            new_resp = if let Some(status) = cfg.status {
+            new_resp = if let Some(status) = status {
                 new_resp.status(status)
             } else {
                 new_resp.status(resp_parts.status)
@@ -127,18 +678,22 @@ impl ResponseBehavior for ResponseConfig {
 
             // Prepare headers
             let mut headers = resp_parts.headers;
-            if let Some(transforms) = &cfg.headers {
+            if let Some(transforms) = transforms {
                 transforms.transform(&mut headers, ctx)
             }
             for (k, v) in &headers {
                 new_resp = new_resp.header(k, v);
             }
+            // Keep trailers and other extensions of the original response
+            if let Some(extensions) = new_resp.extensions_mut() {
+                *extensions = resp_parts.extensions;
+            }
 
             // Prepare body
-            let body: Full<Bytes> = if let Some(body) = &cfg.body {
+            let body: Full<Bytes> = if let Some(body) = body {
                 // Remove Content-length header since it's incorrect now
                 headers.remove(CONTENT_LENGTH);
-                let body: String = env_with_context_no_errors(&body, |v| ctx.get(&v.into())).into();
+                let body: String = env_with_context_no_errors(&body, |v| ctx.lookup(v)).into();
                 Full::from(body)
             } else {
                 resp_body
@@ -148,29 +703,53 @@ impl ResponseBehavior for ResponseConfig {
             new_resp.body(body).expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
         } else {
             resp
+        };
+
+        // Common headers pipeline for any response
+        if let Some(transforms) = &self.headers {
+            let (mut resp_parts, resp_body) = resp.into_parts();
+            transforms.transform(&mut resp_parts.headers, ctx);
+            Response::from_parts(resp_parts, resp_body)
+        } else {
+            resp
         }
     }
 
+    /// Classifies response by the target's failure policy, then by the listener's `failure_detection`
+    /// and `failed_status_regex`; the first classifier which can decide wins
+    fn is_failed(&self, resp: &Response<Full<Bytes>>, target: Option<&TargetConfig>) -> bool {
+        let target_policy = target
+            .and_then(TargetConfig::failure_policy)
+            .map(|policy| policy as &dyn FailureClassifier);
+        let detection = match self.failure_detection {
+            FailureDetection::HttpStatus => None,
+            FailureDetection::GrpcStatus => Some(&GrpcStatus as &dyn FailureClassifier),
+        };
+
+        target_policy
+            .into_iter()
+            .chain(detection)
+            .chain([&self.failed_status_regex as &dyn FailureClassifier])
+            .find_map(|classifier| classifier.is_failed(resp))
+            .unwrap_or_default()
+    }
+
     fn find_first_response(
         &self,
         responses: &ResponsesMap,
+        targets: &[TargetConfig],
         response_kind: ResponseKind,
     ) -> Option<String> {
         debug!("looking for {:?}", response_kind);
 
-        let re = Regex::new(&self.failed_status_regex).unwrap_or_else(|_| {
-            panic!(
-                "unable parse regex expression: {}",
-                self.failed_status_regex
-            )
-        });
         for key in responses.keys() {
             let (resp, _) = responses
                 .get(key)
                 .expect("unable to get header value by key, looks like a BUG");
-            if let Some(resp) = resp {
-                let status: String = resp.status().to_string();
-                let is_failed = re.is_match(&status);
+            // Full body isn't a response to the requested range
+            if let Some(resp) = resp.as_ref().filter(|r| !ranges::is_full_content(r)) {
+                let target = targets.iter().find(|target| target.id() == *key);
+                let is_failed = self.is_failed(resp, target);
                 match response_kind {
                     ResponseKind::Ok => {
                         if !is_failed {
@@ -192,12 +771,41 @@ impl ResponseBehavior for ResponseConfig {
         None
     }
 
+    /// Returns the first OK response walking through preferred target and then fallback ones in order
+    fn find_preferred_response(
+        &self,
+        responses: &ResponsesMap,
+        targets: &[TargetConfig],
+    ) -> Option<String> {
+        for id in self.preferred_target_id.iter().chain(self.fallback_order()) {
+            if let Some((Some(resp), _)) = responses.get(id) {
+                // Full body isn't a response to the requested range
+                if ranges::is_full_content(resp) {
+                    continue;
+                }
+                let target = targets.iter().find(|target| target.id() == *id);
+                if !self.is_failed(resp, target) {
+                    debug!("found preferred target id={}", id);
+                    return Some(id.clone());
+                }
+            }
+        }
+
+        debug!("not found any OK target in preference order");
+        None
+    }
+
     fn error_response(
         &self,
         e: ResponseResult,
         status: &Option<ResponseStatus>,
     ) -> Response<Full<Bytes>> {
         let resp = Response::builder();
+        let resp = if matches!(e, ResponseResult::Timeout(_)) {
+            resp.extension(TimedOut)
+        } else {
+            resp
+        };
         let resp = if let Some(status) = status.to_owned() {
             resp.status(status)
         } else {
@@ -209,7 +817,8 @@ impl ResponseBehavior for ResponseConfig {
                         resp.status(StatusCode::INTERNAL_SERVER_ERROR)
                     }
                 }
-                ResponseResult::Timeout => resp.status(StatusCode::GATEWAY_TIMEOUT),
+                ResponseResult::Timeout(_) => resp.status(StatusCode::GATEWAY_TIMEOUT),
+                ResponseResult::Unavailable => resp.status(StatusCode::SERVICE_UNAVAILABLE),
                 _ => {
                     panic!("Looks like a BUG!")
                 }
@@ -227,7 +836,7 @@ impl ResponseBehavior for ResponseConfig {
     }
 
     fn override_empty_response(
-        &'static self,
+        &self,
         status: ResponseStatus,
         ctx: &Context,
     ) -> Result<Response<Full<Bytes>>, Error> {
@@ -235,13 +844,13 @@ impl ResponseBehavior for ResponseConfig {
         Ok(self.override_response(empty, ctx))
     }
 
-    fn no_target_response(&'static self, ctx: &Context) -> Result<Response<Full<Bytes>>, Error> {
+    fn no_target_response(&self, ctx: &Context) -> Result<Response<Full<Bytes>>, Error> {
         let empty: Response<Full<Bytes>> = self.empty_response(self.no_targets_status)?;
         Ok(self.override_response(empty, ctx))
     }
 
     fn select_from_two_targets_response(
-        &'static self,
+        &self,
         first_target_id: Option<String>,
         second_target_id: Option<String>,
         responses: &mut ResponsesMap,
@@ -251,7 +860,7 @@ impl ResponseBehavior for ResponseConfig {
             if let Some((resp, ctx)) = responses.remove(&target_id) {
                 let resp = resp.expect(UNABLE_TO_CREATE_RESPONSE_ERROR);
                 let ctx = ctx.with_response(&resp);
-                self.override_response(resp, &ctx)
+                self.target_response(resp, &ctx)
             } else {
                 self.select_from_two_targets_response(None, second_target_id, responses, ctx)
             }
@@ -259,7 +868,7 @@ impl ResponseBehavior for ResponseConfig {
             if let Some((resp, ctx)) = responses.remove(&target_id) {
                 if let Some(resp) = resp {
                     let ctx = ctx.with_response(&resp);
-                    self.override_response(resp, &ctx)
+                    self.target_response(resp, &ctx)
                 } else {
                     self.no_target_response(ctx)
                         .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
@@ -275,7 +884,7 @@ impl ResponseBehavior for ResponseConfig {
     }
 
     fn select_target_or_override_response(
-        &'static self,
+        &self,
         target_id: Option<String>,
         responses: &mut ResponsesMap,
         ctx: &Context,
@@ -284,7 +893,7 @@ impl ResponseBehavior for ResponseConfig {
             if let Some((resp, ctx)) = responses.remove(&target_id) {
                 let resp = resp.expect(UNABLE_TO_CREATE_RESPONSE_ERROR);
                 let ctx = ctx.with_response(&resp);
-                self.override_response(resp, &ctx)
+                self.target_response(resp, &ctx)
             } else {
                 self.override_empty_response(StatusCode::OK.into(), ctx)
                     .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
@@ -296,7 +905,7 @@ impl ResponseBehavior for ResponseConfig {
     }
 
     fn select_target_or_error_response(
-        &'static self,
+        &self,
         target_id: Option<String>,
         responses: &mut ResponsesMap,
         ctx: &Context,
@@ -305,7 +914,7 @@ impl ResponseBehavior for ResponseConfig {
             if let Some((resp, ctx)) = responses.remove(&target_id) {
                 if let Some(resp) = resp {
                     let ctx = ctx.with_response(&resp);
-                    self.override_response(resp, &ctx)
+                    self.target_response(resp, &ctx)
                 } else {
                     self.no_target_response(ctx)
                         .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
@@ -319,4 +928,118 @@ impl ResponseBehavior for ResponseConfig {
                 .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
         }
     }
+
+    /// Runs response script with all targets' responses: string result selects target's response,
+    /// integer result is a status of the overridden response, anything else means no target
+    fn select_by_script_response(
+        &self,
+        responses: &mut ResponsesMap,
+        ctx: &Context,
+    ) -> Response<Full<Bytes>> {
+        let script = self
+            .script
+            .as_ref()
+            .expect("response script isn't defined, looks like a BUG");
+        let targets: Map = responses
+            .iter()
+            .map(|(id, (resp, _))| {
+                let resp = resp.as_ref().map(response_map).map(Into::into);
+                (id.into(), resp.unwrap_or_default())
+            })
+            .collect();
+        let mut scope = Scope::new();
+        scope.push_constant("responses", targets);
+        scope.push_constant("ctx", context_map(ctx));
+
+        match script.run(&mut scope) {
+            Ok(result) if result.is_string() => {
+                let target_id = result.to_string();
+                debug!("script: selected target id={target_id}");
+                self.select_target_or_error_response(Some(target_id), responses, ctx)
+            }
+            Ok(result) if result.is_int() => {
+                let status = result
+                    .as_int()
+                    .ok()
+                    .and_then(|status| ResponseStatus::try_from(status).ok())
+                    .filter(|status| StatusCode::from_u16(*status).is_ok());
+                match status {
+                    Some(status) => self
+                        .override_empty_response(status, ctx)
+                        .expect(UNABLE_TO_CREATE_RESPONSE_ERROR),
+                    None => {
+                        warn!("script: invalid status `{result}`");
+                        self.no_target_response(ctx)
+                            .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
+                    }
+                }
+            }
+            Ok(_) => self
+                .no_target_response(ctx)
+                .expect(UNABLE_TO_CREATE_RESPONSE_ERROR),
+            Err(e) => {
+                warn!("script: unable to select response: {e}");
+                self.no_target_response(ctx)
+                    .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
+            }
+        }
+    }
+
+    /// Asks registered strategy to choose the response, targets which haven't been requested are skipped
+    fn select_by_custom_strategy_response(
+        &self,
+        responses: &mut ResponsesMap,
+        targets: &[TargetConfig],
+        ctx: &Context,
+    ) -> Response<Full<Bytes>> {
+        let name = self
+            .custom_strategy
+            .as_deref()
+            .expect("custom strategy isn't defined, looks like a BUG");
+        let Some(strategy) = strategy::get(name) else {
+            warn!("custom strategy `{name}` isn't registered");
+            return self
+                .no_target_response(ctx)
+                .expect(UNABLE_TO_CREATE_RESPONSE_ERROR);
+        };
+
+        let ids: Vec<String> = targets.iter().map(TargetConfig::id).collect();
+        let decision = {
+            let requested: Vec<StrategyTarget> = targets
+                .iter()
+                .zip(&ids)
+                .filter_map(|(target, id)| {
+                    let (resp, _) = responses.get(id)?;
+                    Some(StrategyTarget {
+                        id,
+                        labels: target.labels(),
+                        response: resp.as_ref(),
+                        failed: resp
+                            .as_ref()
+                            .is_none_or(|resp| self.is_failed(resp, Some(target))),
+                    })
+                })
+                .collect();
+            strategy.select(&requested)
+        };
+
+        match decision {
+            StrategyDecision::Target(target_id) => {
+                debug!("custom strategy: selected target id={target_id}");
+                self.select_target_or_error_response(Some(target_id), responses, ctx)
+            }
+            StrategyDecision::Status(status) if StatusCode::from_u16(status).is_ok() => self
+                .override_empty_response(status, ctx)
+                .expect(UNABLE_TO_CREATE_RESPONSE_ERROR),
+            StrategyDecision::Status(status) => {
+                warn!("custom strategy: invalid status `{status}`");
+                self.no_target_response(ctx)
+                    .expect(UNABLE_TO_CREATE_RESPONSE_ERROR)
+            }
+            StrategyDecision::Response(resp) => resp,
+            StrategyDecision::NoTarget => self
+                .no_target_response(ctx)
+                .expect(UNABLE_TO_CREATE_RESPONSE_ERROR),
+        }
+    }
 }
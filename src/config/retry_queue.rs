@@ -0,0 +1,136 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::header::HeaderName;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEFAULT_RETRY_QUEUE_MAX_ENTRIES: usize = 10_000;
+const DEFAULT_RETRY_QUEUE_MAX_AGE_SEC: u64 = 24 * 3600;
+const DEFAULT_RETRY_QUEUE_MIN_BACKOFF_SEC: u64 = 1;
+const DEFAULT_RETRY_QUEUE_MAX_BACKOFF_SEC: u64 = 300;
+
+/// On-disk queue of the target's failed requests, they're retried in background until target recovers
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RetryQueueConfig {
+    path: PathBuf,
+    #[serde(default = "RetryQueueConfig::default_max_entries")]
+    max_entries: usize,
+    #[serde(
+        with = "humantime_serde",
+        default = "RetryQueueConfig::default_max_age"
+    )]
+    #[schemars(with = "Option<String>")]
+    max_age: Duration,
+    #[serde(
+        with = "humantime_serde",
+        default = "RetryQueueConfig::default_min_backoff"
+    )]
+    #[schemars(with = "Option<String>")]
+    min_backoff: Duration,
+    #[serde(
+        with = "humantime_serde",
+        default = "RetryQueueConfig::default_max_backoff"
+    )]
+    #[schemars(with = "Option<String>")]
+    max_backoff: Duration,
+    #[serde(default)]
+    delivery: DeliveryMode,
+    dedup_header: Option<String>,
+}
+
+/// When request to the target is stored in the queue
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Request is stored after it's failed, requests in progress are lost on crash
+    #[default]
+    OnFailure,
+    /// Request is journaled before it's sent and removed when it's acknowledged by the target,
+    /// so requests in progress are redelivered after restart
+    WriteAhead,
+}
+
+impl RetryQueueConfig {
+    fn default_max_entries() -> usize {
+        DEFAULT_RETRY_QUEUE_MAX_ENTRIES
+    }
+
+    fn default_max_age() -> Duration {
+        Duration::from_secs(DEFAULT_RETRY_QUEUE_MAX_AGE_SEC)
+    }
+
+    fn default_min_backoff() -> Duration {
+        Duration::from_secs(DEFAULT_RETRY_QUEUE_MIN_BACKOFF_SEC)
+    }
+
+    fn default_max_backoff() -> Duration {
+        Duration::from_secs(DEFAULT_RETRY_QUEUE_MAX_BACKOFF_SEC)
+    }
+
+    /// Directory to store queued requests in, it's dedicated to the single target
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Max number of queued requests, the oldest ones are dropped above it
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// Max age of the queued request, older ones are dropped without retry
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    /// Returns delays between retries: the first one and the max one, delay is doubled after each failed retry
+    pub fn backoff(&self) -> (Duration, Duration) {
+        (self.min_backoff, self.max_backoff)
+    }
+
+    pub fn delivery(&self) -> DeliveryMode {
+        self.delivery
+    }
+
+    /// Returns name of the header with the key to deduplicate redelivered requests on the target's side
+    pub fn dedup_header(&self) -> Option<HeaderName> {
+        self.dedup_header.as_ref().map(|name| {
+            HeaderName::from_bytes(name.as_bytes()).expect("invalid header name, looks like a BUG")
+        })
+    }
+}
+
+impl ConfigValidator for RetryQueueConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_entries == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "retry queue `max_entries` should be greater than zero".into(),
+            });
+        }
+        if self.max_age.is_zero() || self.min_backoff.is_zero() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "retry queue `max_age` and `min_backoff` should be greater than zero".into(),
+            });
+        }
+        if self.max_backoff < self.min_backoff {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "retry queue `max_backoff` ({:?}) should be not less than `min_backoff` ({:?})",
+                    self.max_backoff, self.min_backoff
+                ),
+            });
+        }
+        if let Some(name) = &self.dedup_header {
+            if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid retry queue `dedup_header` name `{name}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::io;
+use tokio::runtime::{Builder, Runtime};
+
+/// Settings of the multi-thread Tokio runtime, default Tokio values are used for undefined ones
+#[derive(Deserialize, Debug, Default, Clone, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeConfig {
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+}
+
+impl RuntimeConfig {
+    pub fn new(worker_threads: Option<usize>, max_blocking_threads: Option<usize>) -> Self {
+        Self {
+            worker_threads,
+            max_blocking_threads,
+        }
+    }
+
+    pub fn worker_threads(&self) -> Option<usize> {
+        self.worker_threads
+    }
+
+    pub fn max_blocking_threads(&self) -> Option<usize> {
+        self.max_blocking_threads
+    }
+
+    /// Creates runtime with all drivers enabled, its threads are named by `name`
+    pub fn runtime(&self, name: &str) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all().thread_name(name);
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+
+        builder.build()
+    }
+}
+
+impl ConfigValidator for RuntimeConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.worker_threads == Some(0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "runtime `worker_threads` should be greater than zero".into(),
+            });
+        }
+        if self.max_blocking_threads == Some(0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "runtime `max_blocking_threads` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
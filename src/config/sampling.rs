@@ -0,0 +1,105 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_ADAPTIVE_SAMPLING_MIN_RATE: f64 = 0.01;
+
+/// Share of the requests which are sent to the target,
+/// it's reduced automatically while target is unhealthy if `adaptive` is defined
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SamplingConfig {
+    rate: f64,
+    adaptive: Option<AdaptiveSamplingConfig>,
+}
+
+/// Health thresholds of the target, sampling rate is halved each time they're exceeded
+/// and ramps back up to configured one while target is healthy
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveSamplingConfig {
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    max_latency: Option<Duration>,
+    max_error_rate: Option<f64>,
+    #[serde(default = "AdaptiveSamplingConfig::default_min_rate")]
+    min_rate: f64,
+}
+
+impl SamplingConfig {
+    /// Returns configured sampling rate, it's the upper limit of the adaptive one
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn adaptive(&self) -> Option<&AdaptiveSamplingConfig> {
+        self.adaptive.as_ref()
+    }
+}
+
+impl AdaptiveSamplingConfig {
+    fn default_min_rate() -> f64 {
+        DEFAULT_ADAPTIVE_SAMPLING_MIN_RATE
+    }
+
+    /// Returns average latency above which target is unhealthy
+    pub fn max_latency(&self) -> Option<Duration> {
+        self.max_latency
+    }
+
+    /// Returns ratio of the failed requests above which target is unhealthy
+    pub fn max_error_rate(&self) -> Option<f64> {
+        self.max_error_rate
+    }
+
+    /// Returns sampling rate which isn't reduced further
+    pub fn min_rate(&self) -> f64 {
+        self.min_rate
+    }
+}
+
+impl ConfigValidator for SamplingConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.rate <= 0.0 || self.rate > 1.0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "sampling `rate` should be in range 0.0..=1.0 excluding zero, but it's {}",
+                    self.rate
+                ),
+            });
+        }
+        let Some(adaptive) = self.adaptive() else {
+            return Ok(());
+        };
+        if adaptive.max_latency.is_none() && adaptive.max_error_rate.is_none() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one of `max_latency` or `max_error_rate` of adaptive sampling should be defined".into(),
+            });
+        }
+        if adaptive.max_latency == Some(Duration::ZERO) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "adaptive sampling `max_latency` should be greater than zero".into(),
+            });
+        }
+        if let Some(max_error_rate) = adaptive.max_error_rate {
+            if max_error_rate <= 0.0 || max_error_rate >= 1.0 {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "adaptive sampling `max_error_rate` should be in range 0.0..1.0 exclusively, but it's {max_error_rate}"
+                    ),
+                });
+            }
+        }
+        if adaptive.min_rate <= 0.0 || adaptive.min_rate > self.rate {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "adaptive sampling `min_rate` should be greater than zero and not greater than `rate`, but it's {}",
+                    adaptive.min_rate
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
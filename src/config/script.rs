@@ -0,0 +1,167 @@
+use crate::{context::Context, streaming::full_bytes};
+use http_body_util::Full;
+use hyper::{body::Bytes, HeaderMap, Response};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::sync::{Arc, OnceLock};
+
+/// Max number of operations which single script run may execute, to protect from endless loops
+const MAX_SCRIPT_OPERATIONS: u64 = 100_000;
+const MAX_SCRIPT_CALL_LEVELS: usize = 32;
+const MAX_SCRIPT_STRING_SIZE: usize = 1024 * 1024;
+
+/// Rhai script, it's compiled once during config loading
+#[derive(Clone)]
+pub struct Script {
+    source: String,
+    ast: Arc<AST>,
+}
+
+/// Engine is shared by all scripts, it has no registered functions except standard ones
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(MAX_SCRIPT_OPERATIONS)
+            .set_max_call_levels(MAX_SCRIPT_CALL_LEVELS)
+            .set_max_string_size(MAX_SCRIPT_STRING_SIZE);
+        engine
+    })
+}
+
+impl Script {
+    /// Runs script with the variables from the scope, returns value of the last statement
+    pub fn run(&self, scope: &mut Scope) -> Result<Dynamic, String> {
+        engine()
+            .eval_ast_with_scope::<Dynamic>(scope, &self.ast)
+            .map_err(|e| e.to_string())
+    }
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        let ast = engine()
+            .compile(value)
+            .map_err(|e| format!("invalid script: {e}"))?;
+
+        Ok(Self {
+            source: value.into(),
+            ast: Arc::new(ast),
+        })
+    }
+}
+
+/// Returns all context variables as a map, own variables override variables of the parents
+pub fn context_map(ctx: &Context) -> Map {
+    let mut map = Map::new();
+    for (name, value) in ctx {
+        map.entry(name.into())
+            .or_insert_with(|| value.clone().into());
+    }
+    map
+}
+
+/// Returns headers as a map of names to values, only the first value of each header is present
+pub fn headers_map(headers: &HeaderMap) -> Map {
+    let mut map = Map::new();
+    for (name, value) in headers {
+        if let Ok(value) = value.to_str() {
+            map.entry(name.as_str().into())
+                .or_insert_with(|| value.to_string().into());
+        }
+    }
+    map
+}
+
+/// Returns response as a map with `status`, `headers` and `body`, body is `()` if it isn't a valid UTF-8
+pub fn response_map(resp: &Response<Full<Bytes>>) -> Map {
+    let body = String::from_utf8(full_bytes(resp.body()).to_vec())
+        .ok()
+        .map(Dynamic::from)
+        .unwrap_or(Dynamic::UNIT);
+
+    let mut map = Map::new();
+    map.insert("status".into(), (resp.status().as_u16() as i64).into());
+    map.insert("headers".into(), headers_map(resp.headers()).into());
+    map.insert("body".into(), body);
+    map
+}
+
+impl PartialEq for Script {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl std::fmt::Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+impl Serialize for Script {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for Script {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ScriptVisitor;
+        impl Visitor<'_> for ScriptVisitor {
+            type Value = Script;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("Rhai script")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Script::from_str(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(ScriptVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_script() {
+        let script: Script =
+            serde_json::from_str(r#""if resp.status >= 500 { \"failed\" } else { resp.body }""#)
+                .unwrap();
+        let resp = Response::builder()
+            .status(503)
+            .header("x-header", "value")
+            .body(Full::from("body"))
+            .unwrap();
+        let map = response_map(&resp);
+        assert_eq!(map["status"].as_int(), Ok(503));
+        assert_eq!(
+            map["headers"].clone().cast::<Map>()["x-header"].to_string(),
+            "value"
+        );
+
+        let mut scope = Scope::new();
+        scope.push("resp", map);
+        assert_eq!(script.run(&mut scope).unwrap().to_string(), "failed");
+
+        assert!(serde_json::from_str::<Script>(r#""let x = ;""#).is_err());
+        let endless: Script = serde_json::from_str(r#""loop {}""#).unwrap();
+        assert!(endless.run(&mut Scope::new()).is_err());
+    }
+}
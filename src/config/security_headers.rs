@@ -0,0 +1,106 @@
+use super::{ConfigError, ConfigValidator};
+use hyper::{
+    header::{
+        HeaderName, HeaderValue, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY,
+        X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+    },
+    HeaderMap,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+const DEFAULT_HSTS: &str = "max-age=31536000; includeSubDomains";
+const DEFAULT_CONTENT_TYPE_OPTIONS: &str = "nosniff";
+const DEFAULT_FRAME_OPTIONS: &str = "DENY";
+const DEFAULT_REFERRER_POLICY: &str = "strict-origin-when-cross-origin";
+
+/// Security headers which are added to the final responses,
+/// each header has reasonable default value, empty value disables the header
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SecurityHeadersConfig {
+    #[serde(default = "SecurityHeadersConfig::default_hsts")]
+    hsts: String,
+    #[serde(default = "SecurityHeadersConfig::default_content_type_options")]
+    content_type_options: String,
+    #[serde(default = "SecurityHeadersConfig::default_frame_options")]
+    frame_options: String,
+    #[serde(default = "SecurityHeadersConfig::default_referrer_policy")]
+    referrer_policy: String,
+}
+
+impl SecurityHeadersConfig {
+    fn default_hsts() -> String {
+        DEFAULT_HSTS.into()
+    }
+
+    fn default_content_type_options() -> String {
+        DEFAULT_CONTENT_TYPE_OPTIONS.into()
+    }
+
+    fn default_frame_options() -> String {
+        DEFAULT_FRAME_OPTIONS.into()
+    }
+
+    fn default_referrer_policy() -> String {
+        DEFAULT_REFERRER_POLICY.into()
+    }
+
+    fn headers(&self) -> [(HeaderName, &String); 4] {
+        [
+            (STRICT_TRANSPORT_SECURITY, &self.hsts),
+            (X_CONTENT_TYPE_OPTIONS, &self.content_type_options),
+            (X_FRAME_OPTIONS, &self.frame_options),
+            (REFERRER_POLICY, &self.referrer_policy),
+        ]
+    }
+
+    /// Adds enabled security headers to the response, headers set by the target are kept as is
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        for (name, value) in self.headers() {
+            if value.is_empty() || headers.contains_key(&name) {
+                continue;
+            }
+            if let Ok(value) = HeaderValue::from_str(value) {
+                headers.insert(name, value);
+            }
+        }
+    }
+}
+
+impl ConfigValidator for SecurityHeadersConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (name, value) in self.headers() {
+            if HeaderValue::from_str(value).is_err() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("invalid value `{value}` of the security header `{name}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_security_headers() {
+        let cfg: SecurityHeadersConfig =
+            serde_json::from_str(r#"{"frame_options": "SAMEORIGIN", "hsts": ""}"#).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+        cfg.apply(&mut headers);
+
+        assert_eq!(headers.get(STRICT_TRANSPORT_SECURITY), None);
+        assert_eq!(
+            headers[X_CONTENT_TYPE_OPTIONS],
+            DEFAULT_CONTENT_TYPE_OPTIONS
+        );
+        assert_eq!(headers[X_FRAME_OPTIONS], "SAMEORIGIN");
+        assert_eq!(headers[REFERRER_POLICY], "no-referrer");
+        assert_eq!(headers.len(), 3);
+    }
+}
@@ -0,0 +1,200 @@
+use super::{acme::AcmeConfig, ConfigError, ConfigValidator};
+use crate::{
+    acme::{self, ACME_TLS_ALPN_NAME},
+    tls::ReloadableCertResolver,
+};
+use rustls::{
+    crypto::ring::sign::any_supported_type,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    sign::CertifiedKey,
+    ConfigBuilder, ServerConfig, WantsVerifier,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+/// TLS termination on the listener's port, plaintext connections may be accepted on the same port as well
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ServerTlsConfig {
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+    acme: Option<AcmeConfig>,
+    #[serde(default)]
+    accept_plaintext: bool,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    reload_interval: Option<Duration>,
+    #[serde(default)]
+    server_names: Vec<String>,
+    #[serde(default)]
+    protocol: ServerProtocol,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerProtocol {
+    /// HTTP/1.1 over TCP only
+    #[default]
+    Http1,
+    /// Experimental HTTP/3 over QUIC on the same UDP port in addition to HTTP/1.1 over TCP
+    H3,
+}
+
+impl ServerTlsConfig {
+    /// Returns `true` if protocol of the connection is detected by its first byte,
+    /// so both TLS and plaintext HTTP are served
+    pub fn accept_plaintext(&self) -> bool {
+        self.accept_plaintext
+    }
+
+    /// Returns how often certificate and key files are re-read, they're read once if it isn't defined
+    pub fn reload_interval(&self) -> Option<Duration> {
+        self.reload_interval
+    }
+
+    /// Returns SNI host names of the listener which shares its socket with other listeners,
+    /// either exact names or `*.example.com` wildcards
+    pub fn server_names(&self) -> &[String] {
+        &self.server_names
+    }
+
+    /// Returns protocol of the listener, HTTP/3 is served in addition to HTTP/1.1
+    pub fn protocol(&self) -> ServerProtocol {
+        self.protocol
+    }
+
+    /// Returns automatic certificate of the listener, it's used instead of `cert` and `key` files
+    pub fn acme(&self) -> Option<&AcmeConfig> {
+        self.acme.as_ref()
+    }
+
+    /// Loads certificate chain and private key, files are read when listener starts
+    /// and on new handshakes after each `reload_interval`, if it's defined
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>, anyhow::Error> {
+        let mut config = self.build_config(ServerConfig::builder())?;
+        // Upgrades, like websocket, are supported by HTTP/1 only
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        // Challenge handshakes of the ACME server are answered by the listener itself
+        if self.acme.is_some() {
+            config.alpn_protocols.push(ACME_TLS_ALPN_NAME.to_vec());
+        }
+
+        Ok(Arc::new(config))
+    }
+
+    /// Loads certificate chain and private key of HTTP/3 over QUIC, which requires TLS 1.3
+    pub fn quic_server_config(&self) -> Result<Arc<ServerConfig>, anyhow::Error> {
+        let mut config = self.build_config(ServerConfig::builder_with_protocol_versions(&[
+            &rustls::version::TLS13,
+        ]))?;
+        config.alpn_protocols = vec![b"h3".to_vec()];
+
+        Ok(Arc::new(config))
+    }
+
+    fn build_config(
+        &self,
+        builder: ConfigBuilder<ServerConfig, WantsVerifier>,
+    ) -> Result<ServerConfig, anyhow::Error> {
+        let builder = builder.with_no_client_auth();
+        let config = match (&self.acme, self.reload_interval) {
+            (Some(acme), _) => builder.with_cert_resolver(acme::resolver(acme)?),
+            (None, Some(interval)) => builder.with_cert_resolver(Arc::new(
+                ReloadableCertResolver::new(self.cert(), self.key(), interval)?,
+            )),
+            (None, None) => builder.with_single_cert(
+                load_certs(&std::fs::read(self.cert())?, self.cert())?,
+                load_private_key(&std::fs::read(self.key())?, self.key())?,
+            )?,
+        };
+
+        Ok(config)
+    }
+
+    fn cert(&self) -> &Path {
+        self.cert
+            .as_deref()
+            .expect("TLS `cert` isn't defined, looks like a BUG")
+    }
+
+    fn key(&self) -> &Path {
+        self.key
+            .as_deref()
+            .expect("TLS `key` isn't defined, looks like a BUG")
+    }
+}
+
+/// Builds signing certificate from PEM content of the certificate chain and private key files
+pub fn certified_key(
+    cert: &[u8],
+    cert_path: &Path,
+    key: &[u8],
+    key_path: &Path,
+) -> Result<CertifiedKey, anyhow::Error> {
+    let certs = load_certs(cert, cert_path)?;
+    let key = any_supported_type(&load_private_key(key, key_path)?)?;
+
+    Ok(CertifiedKey::new(certs, key))
+}
+
+fn load_certs(content: &[u8], path: &Path) -> Result<Vec<CertificateDer<'static>>, anyhow::Error> {
+    let certs = rustls_pemfile::certs(&mut &content[..]).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no certificates were found in `{}`",
+            path.display()
+        ));
+    }
+
+    Ok(certs)
+}
+
+fn load_private_key(content: &[u8], path: &Path) -> Result<PrivateKeyDer<'static>, anyhow::Error> {
+    rustls_pemfile::private_key(&mut &content[..])?
+        .ok_or_else(|| anyhow::anyhow!("no private key was found in `{}`", path.display()))
+}
+
+impl ConfigValidator for ServerTlsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self
+            .reload_interval
+            .is_some_and(|interval| interval.is_zero())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: "TLS `reload_interval` should be greater than zero".into(),
+            });
+        }
+        match (&self.cert, &self.key, &self.acme) {
+            (Some(_), Some(_), None) => {}
+            (None, None, Some(acme)) => {
+                if self.reload_interval.is_some() {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: "TLS `reload_interval` isn't applicable to `acme` certificate"
+                            .into(),
+                    });
+                }
+                acme.validate()?;
+            }
+            _ => {
+                return Err(ConfigError::ValidateConfig {
+                    cause: "TLS requires either both `cert` and `key`, or `acme`".into(),
+                })
+            }
+        }
+        if let Some(name) = self.server_names.iter().find(|name| {
+            let name = name.strip_prefix("*.").unwrap_or(name);
+            name.is_empty() || name.contains(['*', '/', ':']) || name.starts_with('.')
+        }) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("invalid TLS server name `{name}`"),
+            });
+        }
+
+        Ok(())
+    }
+}
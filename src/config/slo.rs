@@ -0,0 +1,94 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::time::Duration;
+
+const DEFAULT_SLO_WINDOW_SEC: u64 = 3600;
+const DEFAULT_SLO_WARN_BURN_RATE: f64 = 14.4;
+
+/// Service level objectives of the target, they're evaluated over the rolling window
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SloConfig {
+    availability: Option<f64>,
+    latency: Option<LatencyObjectiveConfig>,
+    #[serde(with = "humantime_serde", default = "SloConfig::default_window")]
+    #[schemars(with = "Option<String>")]
+    window: Duration,
+    #[serde(default = "SloConfig::default_warn_burn_rate")]
+    warn_burn_rate: f64,
+}
+
+/// Ratio of the responses which should be received faster than `threshold`
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LatencyObjectiveConfig {
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    threshold: Duration,
+    objective: f64,
+}
+
+impl SloConfig {
+    fn default_window() -> Duration {
+        Duration::from_secs(DEFAULT_SLO_WINDOW_SEC)
+    }
+
+    fn default_warn_burn_rate() -> f64 {
+        DEFAULT_SLO_WARN_BURN_RATE
+    }
+
+    /// Returns objective ratio of the successful responses
+    pub fn availability(&self) -> Option<f64> {
+        self.availability
+    }
+
+    /// Returns latency threshold and objective ratio of the responses faster than threshold
+    pub fn latency(&self) -> Option<(Duration, f64)> {
+        self.latency.as_ref().map(|l| (l.threshold, l.objective))
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns burn rate which is reported with warning
+    pub fn warn_burn_rate(&self) -> f64 {
+        self.warn_burn_rate
+    }
+}
+
+impl ConfigValidator for SloConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.availability.is_none() && self.latency.is_none() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "at least one of `availability` or `latency` SLO should be defined".into(),
+            });
+        }
+        let objectives = [
+            self.availability.map(|o| ("availability", o)),
+            self.latency.as_ref().map(|l| ("latency", l.objective)),
+        ];
+        for (slo, objective) in objectives.into_iter().flatten() {
+            if objective <= 0.0 || objective >= 1.0 {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`{slo}` SLO objective should be in range 0.0..1.0 exclusively, but it's {objective}"
+                    ),
+                });
+            }
+        }
+        if self.window.as_secs() < 60 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "SLO `window` should be at least 1m".into(),
+            });
+        }
+        if self.warn_burn_rate <= 0.0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "SLO `warn_burn_rate` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use super::{ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+const DEFAULT_LISTEN_BACKLOG: u32 = 1024;
+
+/// Low-level options of the listener's sockets and accepted connections
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SocketConfig {
+    #[serde(default)]
+    tcp_nodelay: bool,
+    #[serde(default = "SocketConfig::default_backlog")]
+    backlog: u32,
+    bind_device: Option<String>,
+    recv_buffer_size: Option<u32>,
+}
+
+impl Default for SocketConfig {
+    fn default() -> Self {
+        Self {
+            tcp_nodelay: false,
+            backlog: Self::default_backlog(),
+            bind_device: None,
+            recv_buffer_size: None,
+        }
+    }
+}
+
+impl SocketConfig {
+    fn default_backlog() -> u32 {
+        DEFAULT_LISTEN_BACKLOG
+    }
+
+    /// Returns `true` if Nagle's algorithm should be disabled on the accepted connections
+    pub fn tcp_nodelay(&self) -> bool {
+        self.tcp_nodelay
+    }
+
+    /// Returns max length of the queue of connections which aren't accepted yet
+    pub fn backlog(&self) -> u32 {
+        self.backlog
+    }
+
+    /// Returns name of the network interface to bind sockets to (`SO_BINDTODEVICE`)
+    pub fn bind_device(&self) -> Option<&str> {
+        self.bind_device.as_deref()
+    }
+
+    /// Returns size of the receive buffer (`SO_RCVBUF`), OS default is used if it isn't defined
+    pub fn recv_buffer_size(&self) -> Option<u32> {
+        self.recv_buffer_size
+    }
+}
+
+impl ConfigValidator for SocketConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.backlog == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: "socket `backlog` should be greater than zero".into(),
+            });
+        }
+        if self.bind_device.as_ref().is_some_and(String::is_empty) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "socket `bind_device` shouldn't be empty".into(),
+            });
+        }
+        if !cfg!(target_os = "linux") && self.bind_device.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "socket `bind_device` is supported on Linux only".into(),
+            });
+        }
+        if self.recv_buffer_size == Some(0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: "socket `recv_buffer_size` should be greater than zero".into(),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use super::{listener::HttpMethod, ConfigError, ConfigValidator};
+use http_body_util::Full;
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue, CONTENT_TYPE},
+    http, Method, Response,
+};
+use schemars::JsonSchema;
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer,
+};
+use std::collections::BTreeMap;
+
+/// Content types of the files which are guessed by extension, if route has no `content-type` header
+const CONTENT_TYPES: &[(&str, &str)] = &[
+    ("css", "text/css"),
+    ("html", "text/html; charset=utf-8"),
+    ("ico", "image/x-icon"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("png", "image/png"),
+    ("svg", "image/svg+xml"),
+    ("txt", "text/plain; charset=utf-8"),
+];
+
+/// Path which is answered by listener itself from config or file, without requesting any target
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StaticRouteConfig {
+    path: String,
+    #[serde(default = "StaticRouteConfig::default_methods")]
+    methods: Vec<HttpMethod>,
+    #[serde(default = "StaticRouteConfig::default_status")]
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+    #[schemars(with = "Option<String>")]
+    file: Option<StaticFile>,
+}
+
+/// Content of the file which is loaded once with config
+pub struct StaticFile {
+    path: String,
+    content: Bytes,
+}
+
+impl StaticRouteConfig {
+    fn default_methods() -> Vec<HttpMethod> {
+        vec![HttpMethod::Get, HttpMethod::Head]
+    }
+
+    fn default_status() -> u16 {
+        200
+    }
+
+    /// Returns `true` if route answers the request, path is matched exactly
+    pub fn is_match(&self, method: &Method, path: &str) -> bool {
+        self.path == path && self.methods.iter().any(|m| Method::from(*m) == method)
+    }
+
+    /// Creates route's response, content type of the file is guessed by its extension if it isn't configured
+    pub fn response(&self) -> Result<Response<Full<Bytes>>, http::Error> {
+        let mut resp = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            resp = resp.header(name, value);
+        }
+        let body = match (&self.body, &self.file) {
+            (Some(body), _) => Bytes::from(body.clone()),
+            (None, Some(file)) => {
+                if !self.has_content_type() {
+                    if let Some(content_type) = file.content_type() {
+                        resp = resp.header(CONTENT_TYPE, content_type);
+                    }
+                }
+                file.content.clone()
+            }
+            (None, None) => Bytes::new(),
+        };
+
+        resp.body(Full::new(body))
+    }
+
+    fn has_content_type(&self) -> bool {
+        self.headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+    }
+}
+
+impl StaticFile {
+    fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read(path)
+            .map_err(|e| format!("unable to load static route file `{path}`: {e}"))?;
+        Ok(Self {
+            path: path.into(),
+            content: content.into(),
+        })
+    }
+
+    fn content_type(&self) -> Option<&'static str> {
+        let (_, extension) = self.path.rsplit_once('.')?;
+        CONTENT_TYPES
+            .iter()
+            .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+            .map(|(_, content_type)| *content_type)
+    }
+}
+
+impl ConfigValidator for StaticRouteConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if !self.path.starts_with('/') {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("static route path `{}` should start with `/`", self.path),
+            });
+        }
+        if self.methods.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "static route `{}` should have at least one method",
+                    self.path
+                ),
+            });
+        }
+        if self.body.is_some() && self.file.is_some() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "static route `{}` can't have both `body` and `file`",
+                    self.path
+                ),
+            });
+        }
+        if let Some((name, value)) = self.headers.iter().find(|(name, value)| {
+            HeaderName::from_bytes(name.as_bytes()).is_err()
+                || HeaderValue::from_str(value).is_err()
+        }) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "invalid header `{name}: {value}` of the static route `{}`",
+                    self.path
+                ),
+            });
+        }
+        if let Err(e) = self.response() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("invalid static route `{}`: {e}", self.path),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for StaticFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.path.fmt(f)
+    }
+}
+
+impl<'de> Deserialize<'de> for StaticFile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct StaticFileVisitor;
+        impl Visitor<'_> for StaticFileVisitor {
+            type Value = StaticFile;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("path to static route file")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                StaticFile::from_file(v).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_string(StaticFileVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn static_route_response() {
+        let route: StaticRouteConfig = serde_json::from_str(
+            r#"{"path": "/maintenance", "file": "tests/static/maintenance.html", "status": 503}"#,
+        )
+        .unwrap();
+        assert!(route.is_match(&Method::GET, "/maintenance"));
+        assert!(route.is_match(&Method::HEAD, "/maintenance"));
+        assert!(!route.is_match(&Method::POST, "/maintenance"));
+        assert!(!route.is_match(&Method::GET, "/maintenance/"));
+
+        let resp = route.response().unwrap();
+        assert_eq!(resp.status(), 503);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "text/html; charset=utf-8");
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.starts_with(b"<!DOCTYPE html>"));
+
+        let route: StaticRouteConfig = serde_json::from_str(
+            r#"{"path": "/ready", "methods": ["GET"], "headers": {"content-type": "text/plain"}, "body": "ok"}"#,
+        )
+        .unwrap();
+        let resp = route.response().unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(resp.into_body().collect().await.unwrap().to_bytes(), "ok");
+    }
+}
@@ -1,60 +1,170 @@
 use super::{
+    body_transform::ReplaceRule,
+    canary::CanaryConfig,
+    compression::{self, TargetCompressionConfig},
+    cookies::CookiesConfig,
+    deprecation::Sunset,
+    discovery::DiscoveryConfig,
+    dns::TargetDnsConfig,
+    failure::FailurePolicy,
+    faults::FaultsConfig,
+    header_limits::HeaderLimitsConfig,
     headers::HeaderTransform,
-    listener::{TlsConfig, TlsVerifyConfig},
+    labels::Labels,
+    listener::{HttpMethod, TlsConfig, TlsVerifyConfig},
+    query::QueryTransformsList,
     response::ResponseStatus,
-    ConfigValidator,
+    retry_queue::RetryQueueConfig,
+    sampling::SamplingConfig,
+    slo::SloConfig,
+    ConfigValidator, ConfigWarning,
+};
+use crate::{
+    ca_bundle::ReloadableCaVerifier,
+    config::ConfigError,
+    context::Context,
+    dns::PreferredFamilyResolver,
+    maintenance, redaction,
+    streaming::{full_bytes, RequestBody},
 };
-use crate::{config::ConfigError, context::Context};
 use http_body_util::Full;
-use hyper::{body::Bytes, http::request::Parts, Uri};
+use hyper::{
+    body::Bytes,
+    header::{HeaderValue, CONTENT_TYPE, RETRY_AFTER},
+    http::request::Parts,
+    HeaderMap, Response, Uri,
+};
 use hyper_rustls::HttpsConnectorBuilder;
 use hyper_util::{
     client::legacy::{connect::HttpConnector, Client},
     rt::TokioExecutor,
 };
 use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
+use regex::Regex;
 use rustls::{
     client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
     pki_types::CertificateDer,
     ClientConfig, RootCertStore, SignatureScheme,
 };
+use schemars::JsonSchema;
 use serde::{
     de::{self, Visitor},
     Deserialize, Deserializer,
 };
-use serde_json::{json, value::Value as JsonValue, Value};
+use serde_json::{json, Value};
+use shellexpand::env_with_context_no_errors;
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
     io::BufReader,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     sync::{Arc, LazyLock, RwLock},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
+use strum_macros::Display;
+use tower_service::Service;
 use tracing::{debug, error};
 
 const DEFAULT_TARGET_TIMEOUT_SEC: u64 = 60;
+/// Connection Attempt Delay recommended by RFC 8305
+const DEFAULT_HAPPY_EYEBALLS_DELAY_MS: u64 = 250;
+const DEFAULT_TARGET_WEIGHT: u32 = 1;
+/// Substitution of placeholders to validate URL which host is defined by placeholder
+const PLACEHOLDER_HOST: &str = "placeholder";
 
 pub type TargetConfigList = Vec<TargetConfig>;
-type HttpsClient = Client<hyper_rustls::HttpsConnector<HttpConnector>, Full<Bytes>>;
+type HttpsConnector = hyper_rustls::HttpsConnector<HttpConnector<PreferredFamilyResolver>>;
+pub(crate) type HttpsClient = Client<HttpsConnector, RequestBody>;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct TargetConfig {
     id: Option<String>,
     url: String,
     headers: Option<Vec<HeaderTransform>>,
+    header_limits: Option<HeaderLimitsConfig>,
+    query: Option<QueryTransformsList>,
+    cookies: Option<CookiesConfig>,
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    host_header: HostHeader,
+    #[serde(default)]
+    forward_path: bool,
+    path_rewrite: Option<ReplaceRule>,
+    method_override: Option<HttpMethod>,
     body: Option<String>,
     #[serde(
         with = "humantime_serde",
         default = "TargetConfig::default_target_timeout"
     )]
+    #[schemars(with = "String")]
     timeout: Duration,
     #[serde(default)]
     on_error: TargetOnErrorAction,
     error_status: Option<ResponseStatus>,
+    failure_policy: Option<FailurePolicy>,
+    #[schemars(with = "Option<String>")]
     condition: Option<TargetConditionConfig>,
+    after: Option<String>,
     #[serde(default)]
     tls: Option<TlsConfig>,
+    #[serde(default)]
+    http2: bool,
+    faults: Option<FaultsConfig>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    added_latency: Duration,
+    #[schemars(with = "Option<String>")]
+    bandwidth_limit: Option<Bandwidth>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    connect_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    read_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    total_timeout: Option<Duration>,
+    #[serde(default)]
+    timeout_status: TimeoutStatusConfig,
+    #[serde(default)]
+    compression: TargetCompressionConfig,
+    #[serde(default)]
+    ip_preference: IpPreference,
+    #[serde(
+        with = "humantime_serde",
+        default = "TargetConfig::default_happy_eyeballs_delay"
+    )]
+    #[schemars(with = "Option<String>")]
+    happy_eyeballs_delay: Duration,
+    bind_address: Option<BindAddress>,
+    interface: Option<String>,
+    #[serde(default)]
+    dns: TargetDnsConfig,
+    discovery: Option<DiscoveryConfig>,
+    #[serde(default)]
+    labels: Labels,
+    #[serde(default = "TargetConfig::default_weight")]
+    weight: u32,
+    #[serde(default)]
+    priority: TargetPriority,
+    #[serde(default)]
+    startup_check: bool,
+    content_types: Option<Vec<String>>,
+    max_body_size: Option<usize>,
+    max_response_size: Option<usize>,
+    #[serde(default)]
+    on_oversized_response: OversizedResponseAction,
+    sampling: Option<SamplingConfig>,
+    canary: Option<CanaryConfig>,
+    slo: Option<SloConfig>,
+    retry_queue: Option<RetryQueueConfig>,
+    #[serde(default)]
+    deprecated: bool,
+    #[schemars(with = "Option<String>")]
+    sunset: Option<Sunset>,
+    #[serde(default)]
+    deprecation_headers: bool,
 }
 
 impl TargetConfig {
@@ -62,10 +172,28 @@ impl TargetConfig {
         Duration::from_secs(DEFAULT_TARGET_TIMEOUT_SEC)
     }
 
+    fn default_happy_eyeballs_delay() -> Duration {
+        Duration::from_millis(DEFAULT_HAPPY_EYEBALLS_DELAY_MS)
+    }
+
+    fn default_weight() -> u32 {
+        DEFAULT_TARGET_WEIGHT
+    }
+
+    /// Parses URL as is, or without context placeholders if they make it invalid,
+    /// like `https://host${CTX_REQUEST_PATH}`
     fn uri(&self) -> Result<Uri, ConfigError> {
-        self.url.parse().map_err(|e| ConfigError::ValidateConfig {
-            cause: format!("invalid url `{}`: {e}", self.url),
-        })
+        static PLACEHOLDER: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"\$\{[^}]*\}").expect("invalid placeholder regex"));
+
+        self.url
+            .parse()
+            .or_else(|_| PLACEHOLDER.replace_all(&self.url, "").parse())
+            // Scheme and host may be placeholders too, like ones of the proxied request
+            .or_else(|_| PLACEHOLDER.replace_all(&self.url, PLACEHOLDER_HOST).parse())
+            .map_err(|e| ConfigError::ValidateConfig {
+                cause: format!("invalid url `{}`: {e}", self.url),
+            })
     }
 
     pub fn id(&self) -> String {
@@ -78,7 +206,10 @@ impl TargetConfig {
 
     pub fn host(&self) -> String {
         if let Ok(uri) = self.uri() {
-            uri.host().unwrap_or("").to_lowercase()
+            uri.host()
+                .filter(|host| *host != PLACEHOLDER_HOST)
+                .unwrap_or("")
+                .to_lowercase()
         } else {
             String::new()
         }
@@ -100,6 +231,29 @@ impl TargetConfig {
         &self.timeout
     }
 
+    /// Returns time to establish connection to the target, default is the target's `timeout`
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout.unwrap_or(self.timeout)
+    }
+
+    /// Returns maximum time between chunks of the response body, default is the target's `timeout`
+    pub fn read_timeout(&self) -> Duration {
+        self.read_timeout.unwrap_or(self.timeout)
+    }
+
+    /// Returns time to complete the whole request including receiving of the response body
+    pub fn total_timeout(&self) -> Option<Duration> {
+        self.total_timeout
+    }
+
+    pub fn timeout_status(&self) -> &TimeoutStatusConfig {
+        &self.timeout_status
+    }
+
+    pub fn compression(&self) -> &TargetCompressionConfig {
+        &self.compression
+    }
+
     pub fn on_error(&self) -> &TargetOnErrorAction {
         &self.on_error
     }
@@ -112,24 +266,254 @@ impl TargetConfig {
         &self.condition
     }
 
-    /// Returns http client with configured (or default) tls config and timeout
-    pub fn https_client(&'static self, default_tls_config: &'static TlsConfig) -> HttpsClient {
-        Self::get_https_client(
-            self.timeout(),
-            self.tls.as_ref().unwrap_or(default_tls_config),
-        )
+    /// Returns ID of the target which should respond before this one is requested,
+    /// condition of such target is evaluated with that response
+    pub fn after(&self) -> Option<&str> {
+        self.after.as_deref()
+    }
+
+    /// Returns `true` if target should be requested using HTTP/2 only
+    pub fn http2(&self) -> bool {
+        self.http2
+    }
+
+    /// Returns faults to inject into requests before forwarding them to this target
+    pub fn faults(&self) -> Option<&FaultsConfig> {
+        self.faults.as_ref()
+    }
+
+    /// Returns discovery config if target's instances are discovered dynamically
+    pub fn discovery(&self) -> Option<&DiscoveryConfig> {
+        self.discovery.as_ref()
+    }
+
+    /// Returns static labels which are attached to the log entries of the target's requests
+    pub fn labels(&self) -> &Labels {
+        &self.labels
     }
 
-    /// Check if client with specified timeout and tls config is present in the cache
+    /// Returns transformations of the query parameters of requests to the target
+    pub fn query(&self) -> Option<&QueryTransformsList> {
+        self.query.as_ref()
+    }
+
+    /// Returns cookie level transformations of the target's requests and responses
+    pub fn cookies(&self) -> Option<&CookiesConfig> {
+        self.cookies.as_ref()
+    }
+
+    /// Returns source of the `Host` header value for requests to the target
+    pub fn host_header(&self) -> &HostHeader {
+        &self.host_header
+    }
+
+    /// Returns `true` if path and query of the original request replace ones of the target's URL
+    pub fn forward_path(&self) -> bool {
+        self.forward_path
+    }
+
+    /// Returns policy to classify target's responses for the response strategies instead of the listener's one
+    pub fn failure_policy(&self) -> Option<&FailurePolicy> {
+        self.failure_policy.as_ref()
+    }
+
+    /// Returns rule to build path and query of the target's request from the original request path
+    pub fn path_rewrite(&self) -> Option<&ReplaceRule> {
+        self.path_rewrite.as_ref()
+    }
+
+    /// Returns method of the target's request instead of the original one
+    pub fn method_override(&self) -> Option<HttpMethod> {
+        self.method_override
+    }
+
+    /// Returns limits of the headers forwarded to the target
+    pub fn header_limits(&self) -> Option<&HeaderLimitsConfig> {
+        self.header_limits.as_ref()
+    }
+
+    /// Returns relative weight of the target for `weighted_random` target selection
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    /// Returns `true` if target should be probed on startup
+    pub fn startup_check(&self) -> bool {
+        self.startup_check
+    }
+
+    /// Returns content types of the request body which are sent to the target, any if it isn't defined
+    pub fn content_types(&self) -> Option<&[String]> {
+        self.content_types.as_deref()
+    }
+
+    /// Returns max size of the request body in bytes which is sent to the target
+    pub fn max_body_size(&self) -> Option<usize> {
+        self.max_body_size
+    }
+
+    /// Returns max size of the response body in bytes which is buffered from the target
+    pub fn max_response_size(&self) -> Option<usize> {
+        self.max_response_size
+    }
+
+    /// Returns how the response larger than `max_response_size` is handled
+    pub fn on_oversized_response(&self) -> OversizedResponseAction {
+        self.on_oversized_response
+    }
+
+    /// Verifies if request with the body should be sent to the target: body isn't too large
+    /// and its content type is allowed, requests without body pass content type check
+    pub fn accepts_body(&self, headers: &HeaderMap, body_size: usize) -> bool {
+        if self.max_body_size.is_some_and(|max| body_size > max) {
+            return false;
+        }
+        match &self.content_types {
+            Some(content_types) if body_size > 0 => headers
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| compression::is_content_type_matched(content_types, value)),
+            _ => true,
+        }
+    }
+
+    /// Establishes connection to the target, including TLS handshake, without sending any request
+    pub async fn probe(&self, default_tls_config: &TlsConfig) -> Result<(), String> {
+        let uri = self.uri().map_err(|e| e.to_string())?;
+        let mut connector = Self::create_https_connector(&ClientOptions {
+            connect_timeout: self.connect_timeout(),
+            tls: self.tls.as_ref().unwrap_or(default_tls_config).clone(),
+            http2: self.http2,
+            ip_preference: self.ip_preference,
+            happy_eyeballs_delay: self.happy_eyeballs_delay,
+            bind_address: self.bind_address.clone(),
+            interface: self.interface.clone(),
+            dns_negative_ttl: self.dns.negative_ttl(),
+        })
+        .map_err(|e| e.to_string())?;
+
+        // Connect timeout doesn't cover TLS handshake
+        match tokio::time::timeout(self.connect_timeout(), connector.call(uri)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("timeout".into()),
+        }
+    }
+
+    /// Returns priority of the target's requests under listener's concurrency limit
+    pub fn priority(&self) -> TargetPriority {
+        self.priority
+    }
+
+    /// Returns share of the requests which are sent to the target, all requests are sent if it isn't defined
+    pub fn sampling(&self) -> Option<&SamplingConfig> {
+        self.sampling.as_ref()
+    }
+
+    /// Returns gradual rollout of the target for `weighted_random` target selection, if any
+    pub fn canary(&self) -> Option<&CanaryConfig> {
+        self.canary.as_ref()
+    }
+
+    /// Returns service level objectives of the target, if any
+    pub fn slo(&self) -> Option<&SloConfig> {
+        self.slo.as_ref()
+    }
+
+    /// Returns config of the on-disk queue to retry failed requests, if any
+    pub fn retry_queue(&self) -> Option<&RetryQueueConfig> {
+        self.retry_queue.as_ref()
+    }
+
+    /// Returns `true` if the target is going to be retired, it's implied by the sunset date
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated || self.sunset.is_some()
+    }
+
+    /// Returns date after which the deprecated target is going to be removed, if any
+    pub fn sunset(&self) -> Option<Sunset> {
+        self.sunset
+    }
+
+    /// Returns `true` if `Deprecation` and `Sunset` headers should be added to the target's responses
+    pub fn deprecation_headers(&self) -> bool {
+        self.deprecation_headers
+    }
+
+    /// Returns latency which is added to each request to this target
+    pub fn added_latency(&self) -> Duration {
+        self.added_latency
+    }
+
+    /// Returns bandwidth limit of the requests and responses body transfer
+    pub fn bandwidth_limit(&self) -> Option<Bandwidth> {
+        self.bandwidth_limit
+    }
+
+    /// Returns preferred IP family to connect to the target
+    pub fn ip_preference(&self) -> IpPreference {
+        self.ip_preference
+    }
+
+    /// Returns delay before connection attempt to the next address (of another IP family),
+    /// zero means addresses are tried sequentially
+    pub fn happy_eyeballs_delay(&self) -> Duration {
+        self.happy_eyeballs_delay
+    }
+
+    /// Returns source address of the connections to the target, if it's bound
+    pub fn bind_address(&self) -> Option<&BindAddress> {
+        self.bind_address.as_ref()
+    }
+
+    /// Returns network interface which connections to the target are bound to, if any
+    pub fn interface(&self) -> Option<&str> {
+        self.interface.as_deref()
+    }
+
+    /// Returns handling of the host name resolution failures
+    pub fn dns(&self) -> &TargetDnsConfig {
+        &self.dns
+    }
+
+    /// Returns http client with configured (or default) tls config, connect timeout, protocol,
+    /// dual-stack connection and DNS parameters
+    pub fn https_client(&self, default_tls_config: &TlsConfig) -> HttpsClient {
+        Self::get_https_client(&ClientOptions {
+            connect_timeout: self.connect_timeout(),
+            tls: self.tls.as_ref().unwrap_or(default_tls_config).clone(),
+            http2: self.http2,
+            ip_preference: self.ip_preference,
+            happy_eyeballs_delay: self.happy_eyeballs_delay,
+            bind_address: self.bind_address.clone(),
+            interface: self.interface.clone(),
+            dns_negative_ttl: self.dns.negative_ttl(),
+        })
+    }
+
+    /// Returns http client with the specified tls config and connect timeout, default values of other options
+    pub(crate) fn tls_client(tls_config: &TlsConfig, connect_timeout: Duration) -> HttpsClient {
+        Self::get_https_client(&ClientOptions {
+            connect_timeout,
+            tls: tls_config.clone(),
+            http2: false,
+            ip_preference: IpPreference::default(),
+            happy_eyeballs_delay: Self::default_happy_eyeballs_delay(),
+            bind_address: None,
+            interface: None,
+            dns_negative_ttl: None,
+        })
+    }
+
+    /// Check if client with specified options is present in the cache
     /// and either:
     /// - returns clone of the cached one
     /// - or creates new one, store ith to the cache and returns it
-    fn get_https_client(timeout: &'static Duration, tls_config: &'static TlsConfig) -> HttpsClient {
-        type HashKey = (&'static Duration, &'static TlsConfig);
-        static CACHE: LazyLock<RwLock<HashMap<HashKey, HttpsClient>>> =
+    fn get_https_client(options: &ClientOptions) -> HttpsClient {
+        static CACHE: LazyLock<RwLock<HashMap<ClientOptions, HttpsClient>>> =
             LazyLock::new(|| RwLock::new(HashMap::new()));
 
-        let key = (timeout, tls_config);
+        let key = options.clone();
 
         debug!(key = ?key, "get https client");
         let client = if CACHE
@@ -151,24 +535,62 @@ impl TargetConfig {
                 let mut cache = CACHE
                     .write()
                     .expect("unable to lock cache, looks like a BUG");
-                let client = Self::create_https_client(timeout, tls_config)
+                let client = Self::create_https_client(options)
                     .expect("unable to create https client, looks like a BUG");
-                cache.insert(key, client);
+                cache.insert(key.clone(), client);
                 debug!(key = ?key, "get https client: put into the cache");
             }
-            Self::get_https_client(timeout, tls_config)
+            Self::get_https_client(options)
         };
 
         client
     }
 
-    /// Creates http client with specified timeout and tls config
-    fn create_https_client(
-        timeout: &Duration,
-        tls_config: &TlsConfig,
-    ) -> Result<HttpsClient, anyhow::Error> {
-        let mut http_connector = HttpConnector::new();
-        http_connector.set_connect_timeout(Some(*timeout));
+    /// Creates http client with specified options,
+    /// HTTP/2 client uses prior knowledge for plain connections and ALPN for TLS ones.
+    fn create_https_client(options: &ClientOptions) -> Result<HttpsClient, anyhow::Error> {
+        let https_client = Client::builder(TokioExecutor::default())
+            .http2_only(options.http2)
+            .build(Self::create_https_connector(options)?);
+        Ok(https_client)
+    }
+
+    /// Creates connector with specified options.
+    /// If host has addresses of both IP families, connections are raced as per RFC 8305 (Happy Eyeballs):
+    /// attempt to the next family starts if the previous one isn't established during the delay.
+    fn create_https_connector(options: &ClientOptions) -> Result<HttpsConnector, anyhow::Error> {
+        let ClientOptions {
+            connect_timeout,
+            tls: tls_config,
+            http2,
+            ip_preference,
+            happy_eyeballs_delay,
+            bind_address,
+            interface,
+            dns_negative_ttl,
+        } = options;
+        let http2 = *http2;
+
+        let resolver = PreferredFamilyResolver::new(*ip_preference, *dns_negative_ttl)
+            .with_bind_address(bind_address.as_ref());
+        let mut http_connector = HttpConnector::new_with_resolver(resolver);
+        match bind_address.as_ref().map(BindAddress::addresses) {
+            Some((Some(ipv4), Some(ipv6))) => http_connector.set_local_addresses(ipv4, ipv6),
+            Some((ipv4, ipv6)) => {
+                http_connector.set_local_address(ipv4.map(IpAddr::from).or(ipv6.map(IpAddr::from)))
+            }
+            None => {}
+        }
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        if let Some(interface) = interface {
+            http_connector.set_interface(interface);
+        }
+        #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+        let _ = interface;
+        http_connector.set_connect_timeout(Some(*connect_timeout));
+        http_connector.set_happy_eyeballs_timeout(
+            Some(*happy_eyeballs_delay).filter(|delay| !delay.is_zero()),
+        );
         http_connector.enforce_http(false);
 
         let https_connector = match tls_config.verify {
@@ -178,7 +600,19 @@ impl TargetConfig {
             }
             TlsVerifyConfig::Yes => {
                 debug!("TLS verification enabled");
-                if let Some(ca) = tls_config.ca.as_ref() {
+                if let Some(ca_dir) = tls_config.ca_dir.as_ref() {
+                    debug!(ca_dir = %ca_dir, "use reloadable Root CA directory");
+                    let verifier = ReloadableCaVerifier::new(
+                        tls_config.ca.as_deref(),
+                        ca_dir,
+                        tls_config.ca_reload_interval(),
+                    )?;
+                    let config = ClientConfig::builder()
+                        .dangerous()
+                        .with_custom_certificate_verifier(Arc::new(verifier))
+                        .with_no_client_auth();
+                    HttpsConnectorBuilder::default().with_tls_config(config)
+                } else if let Some(ca) = tls_config.ca.as_ref() {
                     debug!(pem = %ca, "use custom Root CA bundle");
                     HttpsConnectorBuilder::default()
                         .with_tls_config(Self::get_custom_ca_tls_config(ca)?)
@@ -192,13 +626,18 @@ impl TargetConfig {
             }
         };
 
-        let https_connector = https_connector
-            .https_or_http()
-            .enable_http1()
-            .wrap_connector(http_connector);
+        let https_connector = https_connector.https_or_http();
+        let https_connector = if http2 {
+            https_connector
+                .enable_http2()
+                .wrap_connector(http_connector)
+        } else {
+            https_connector
+                .enable_http1()
+                .wrap_connector(http_connector)
+        };
 
-        let https_client = Client::builder(TokioExecutor::default()).build(https_connector);
-        Ok(https_client)
+        Ok(https_connector)
     }
 
     fn get_dangerous_tls_config() -> Result<ClientConfig, hyper::Error> {
@@ -291,7 +730,271 @@ impl ServerCertVerifier for NoCertificateVerification {
     }
 }
 
-#[derive(Deserialize, Debug, Default)]
+/// Parameters of the http client, targets with the same parameters share the client
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ClientOptions {
+    connect_timeout: Duration,
+    tls: TlsConfig,
+    http2: bool,
+    ip_preference: IpPreference,
+    happy_eyeballs_delay: Duration,
+    bind_address: Option<BindAddress>,
+    interface: Option<String>,
+    dns_negative_ttl: Option<Duration>,
+}
+
+/// Value of the `Host` header of requests to the target, if it isn't set by headers transformations
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum HostHeader {
+    /// Host of the original request
+    Preserve,
+    /// Host of the target's URL
+    #[default]
+    Target,
+    /// Any other value, context placeholders are expanded
+    Custom(String),
+}
+
+impl<'de> Deserialize<'de> for HostHeader {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct HostHeaderVisitor;
+        impl Visitor<'_> for HostHeaderVisitor {
+            type Value = HostHeader;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("`preserve`, `target` or custom host header value")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                match v {
+                    "preserve" => Ok(HostHeader::Preserve),
+                    "target" => Ok(HostHeader::Target),
+                    _ if v.is_empty() || HeaderValue::from_str(v).is_err() => {
+                        Err(E::custom(format!("invalid host header value `{v}`")))
+                    }
+                    _ => Ok(HostHeader::Custom(v.into())),
+                }
+            }
+        }
+
+        deserializer.deserialize_string(HostHeaderVisitor)
+    }
+}
+
+/// IP family to try first if target's host has both IPv4 and IPv6 addresses
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IpPreference {
+    /// Order of addresses returned by the system resolver (usually IPv6 first)
+    #[default]
+    Auto,
+    V4,
+    V6,
+}
+
+/// Source address of the connections to the target: single IP or one address of each IP family,
+/// target's addresses of the families without source address aren't connected
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Hash, JsonSchema)]
+#[serde(untagged)]
+pub enum BindAddress {
+    Single(IpAddr),
+    DualStack(Vec<IpAddr>),
+}
+
+impl BindAddress {
+    /// Returns source addresses of IPv4 and IPv6 connections, the first one of each family is used
+    pub fn addresses(&self) -> (Option<Ipv4Addr>, Option<Ipv6Addr>) {
+        let addrs = match self {
+            BindAddress::Single(addr) => std::slice::from_ref(addr),
+            BindAddress::DualStack(addrs) => addrs.as_slice(),
+        };
+        let ipv4 = addrs.iter().find_map(|addr| match addr {
+            IpAddr::V4(addr) => Some(*addr),
+            IpAddr::V6(_) => None,
+        });
+        let ipv6 = addrs.iter().find_map(|addr| match addr {
+            IpAddr::V6(addr) => Some(*addr),
+            IpAddr::V4(_) => None,
+        });
+
+        (ipv4, ipv6)
+    }
+}
+
+impl ConfigValidator for BindAddress {
+    fn validate(&self) -> Result<(), ConfigError> {
+        let addrs = match self {
+            BindAddress::Single(addr) => std::slice::from_ref(addr),
+            BindAddress::DualStack(addrs) => addrs.as_slice(),
+        };
+        let ipv4 = addrs.iter().filter(|addr| addr.is_ipv4()).count();
+        if addrs.is_empty() || ipv4 > 1 || addrs.len() - ipv4 > 1 {
+            return Err(ConfigError::ValidateConfig {
+                cause:
+                    "`bind_address` should be single IP address or one address of each IP family"
+                        .into(),
+            });
+        }
+        if let Some(addr) = addrs
+            .iter()
+            .find(|addr| addr.is_multicast() || addr.is_unspecified())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("`bind_address` `{addr}` isn't a unicast address"),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Order of the target's requests waiting for a free slot of the listener's concurrency limit,
+/// low priority requests wait until there are no high priority ones and may be shed
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Display, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TargetPriority {
+    #[default]
+    High,
+    Low,
+}
+
+/// Phase of the target request which has been timed out
+#[derive(Debug, Clone, Copy, PartialEq, Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum TimeoutPhase {
+    /// Connection establishment, see `connect_timeout`
+    Connect,
+    /// Waiting for the response headers, see `timeout`
+    FirstByte,
+    /// Waiting for the next chunk of the response body, see `read_timeout`
+    Read,
+    /// Whole request including response body, see `total_timeout`
+    Total,
+}
+
+/// Statuses of the error response for each timeout phase, `504` is used if status isn't defined,
+/// and optional body and `Retry-After` header of that response
+#[derive(Deserialize, Debug, Default, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TimeoutStatusConfig {
+    connect: Option<ResponseStatus>,
+    first_byte: Option<ResponseStatus>,
+    read: Option<ResponseStatus>,
+    total: Option<ResponseStatus>,
+    body: Option<String>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    retry_after: Option<Duration>,
+}
+
+impl TimeoutStatusConfig {
+    pub fn status(&self, phase: TimeoutPhase) -> Option<ResponseStatus> {
+        match phase {
+            TimeoutPhase::Connect => self.connect,
+            TimeoutPhase::FirstByte => self.first_byte,
+            TimeoutPhase::Read => self.read,
+            TimeoutPhase::Total => self.total,
+        }
+    }
+
+    /// Adds body and `Retry-After` header to the timeout response, body placeholders are substituted from context
+    pub fn update_response(&self, resp: &mut Response<Full<Bytes>>, ctx: &Context) {
+        if let Some(body) = &self.body {
+            let body: String = env_with_context_no_errors(body, |v| ctx.lookup(v)).into();
+            *resp.body_mut() = Full::from(body);
+        }
+        if let Some(retry_after) = self.retry_after {
+            // Header value is whole seconds, so fractional interval is rounded up
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            resp.headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.connect.is_none()
+            && self.first_byte.is_none()
+            && self.read.is_none()
+            && self.total.is_none()
+            && self.body.is_none()
+            && self.retry_after.is_none()
+    }
+}
+
+/// Data transfer rate in bytes per second,
+/// parsed from strings like `512Bps`, `100KBps` or `1.5MBps` (binary multiples)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bandwidth(u64);
+
+impl Bandwidth {
+    fn from_str(value: &str) -> Result<Self, ConfigError> {
+        static BANDWIDTH: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^\s*([0-9]+(?:\.[0-9]+)?)\s*([kKMG]?)B(?:ps|/s)\s*$")
+                .expect("invalid bandwidth regex")
+        });
+
+        let invalid = || ConfigError::ValidateConfig {
+            cause: format!(
+                "invalid bandwidth `{value}`, expected something like `100KBps` or `1MBps`"
+            ),
+        };
+        let captures = BANDWIDTH.captures(value).ok_or_else(invalid)?;
+        let number: f64 = captures[1].parse().map_err(|_| invalid())?;
+        let multiplier: f64 = match &captures[2] {
+            "k" | "K" => 1024.0,
+            "M" => 1024.0 * 1024.0,
+            "G" => 1024.0 * 1024.0 * 1024.0,
+            _ => 1.0,
+        };
+        let rate = (number * multiplier) as u64;
+        if rate == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("bandwidth `{value}` should be at least 1 byte per second"),
+            });
+        }
+
+        Ok(Self(rate))
+    }
+
+    /// Returns time to transfer specified number of bytes
+    pub fn transfer_time(&self, bytes: u64) -> Duration {
+        Duration::from_secs_f64(bytes as f64 / self.0 as f64)
+    }
+}
+
+impl<'de> Deserialize<'de> for Bandwidth {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct BandwidthVisitor;
+        impl Visitor<'_> for BandwidthVisitor {
+            type Value = Bandwidth;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("bandwidth in bytes per second, like `100KBps` or `1MBps`")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                Bandwidth::from_str(v).map_err(|e| E::custom(e))
+            }
+        }
+
+        deserializer.deserialize_string(BandwidthVisitor)
+    }
+}
+
+#[derive(Deserialize, Debug, Default, JsonSchema)]
 #[serde(deny_unknown_fields, rename_all = "snake_case")]
 pub enum TargetOnErrorAction {
     #[default]
@@ -300,6 +1003,20 @@ pub enum TargetOnErrorAction {
     Drop,
 }
 
+/// Handling of the target's response which body is larger than `max_response_size`
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizedResponseAction {
+    /// Response is replaced with `502 Bad Gateway`, so it's a failed one
+    #[default]
+    Fail,
+    /// Body is cut to the limit, the rest of it isn't received
+    Truncate,
+    /// Body is cut to the limit for the strategy evaluation, full body is streamed to the requester
+    /// if the target's response is passed to the requester as is, otherwise the rest of it is received and discarded
+    StreamThrough,
+}
+
 #[derive(Debug)]
 pub enum TargetConditionConfig {
     Default,
@@ -350,8 +1067,27 @@ impl<'de> Deserialize<'de> for TargetConditionConfig {
     }
 }
 
+/// Definitions which are available in all JQ-like filters in addition to the core syntax
+const FILTER_PRELUDE: &str = r#"
+def true: 0 == 0;
+def false: 0 != 0;
+def not: if . then false else true end;
+def between(from; to): . >= from and . < to;
+"#;
+
+/// Returns parsing context of the JQ-like filters with prelude definitions
+pub fn filter_parse_ctx() -> ParseCtx {
+    let (prelude, errs) = jaq_parse::parse(FILTER_PRELUDE, jaq_parse::defs());
+    debug_assert!(errs.is_empty(), "invalid filter prelude: {errs:?}");
+
+    let mut defs = ParseCtx::new(Vec::new());
+    defs.insert_defs(prelude.expect("invalid filter prelude, looks like a BUG"));
+    defs
+}
+
 #[derive(Debug)]
 pub struct ConditionFilter {
+    expression: String,
     filter: Filter,
 }
 
@@ -361,10 +1097,13 @@ impl From<&str> for ConditionFilter {
     }
 }
 impl ConditionFilter {
-    fn run(&self, input: JsonValue) -> bool {
-        debug!("input=`{:#?}`", input);
+    pub fn run(&self, input: &ConditionInput) -> bool {
+        debug!(
+            "input=`{}`",
+            redaction::mask_secrets(&format!("{:#?}", input.0))
+        );
         let inputs = RcIter::new(core::iter::empty());
-        let out = self.filter.run((Ctx::new([], &inputs), Val::from(input)));
+        let out = self.filter.run((Ctx::new([], &inputs), input.0.clone()));
 
         let out: Vec<String> = out
             .map(|v| format!("{}", v.unwrap_or(Val::Bool(false))))
@@ -376,9 +1115,17 @@ impl ConditionFilter {
         result
     }
 
+    /// Returns `true` if expression doesn't depend on the request and isn't `true`,
+    /// it's detected by the absence of any path or variable in the expression
+    fn is_never_true(&self) -> bool {
+        !self.expression.contains(['.', '$'])
+            && !self.expression.contains("input")
+            && !self.run(&ConditionInput(Val::Null))
+    }
+
     fn from_str(value: &str) -> Result<Self, ConfigError> {
         debug!("filter=`{value}`");
-        let mut defs = ParseCtx::new(Vec::new());
+        let mut defs = filter_parse_ctx();
         let (f, errs) = jaq_parse::parse(value, jaq_parse::main());
         if !errs.is_empty() {
             errs.iter()
@@ -389,7 +1136,15 @@ impl ConditionFilter {
         }
         if let Some(f) = f {
             let filter = defs.compile(f);
-            Ok(ConditionFilter { filter })
+            if let Some((e, _)) = defs.errs.first() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("unable to compile conditional expression `{value}`: {e}"),
+                });
+            }
+            Ok(ConditionFilter {
+                expression: value.into(),
+                filter,
+            })
         } else {
             Err(ConfigError::ValidateConfig {
                 cause: "invalid conditional expression".into(),
@@ -403,6 +1158,101 @@ impl ConfigValidator for TargetConfig {
         // Validate URIs
         self.uri()?;
 
+        if let Some(tls) = &self.tls {
+            tls.validate()?;
+        }
+        if let Some(faults) = self.faults() {
+            faults.validate()?;
+        }
+        if let Some(failure_policy) = self.failure_policy() {
+            failure_policy.validate()?;
+        }
+        if let Some(discovery) = self.discovery() {
+            discovery.validate()?;
+            if self.startup_check {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`startup_check` can't be used with `discovery`, target `{}`",
+                        self.id()
+                    ),
+                });
+            }
+        }
+        self.labels.validate()?;
+        if self.weight == 0 {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`weight` should be greater than zero, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+        if self.content_types.as_ref().is_some_and(Vec::is_empty) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`content_types` should contain at least one content type, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+        if self.max_response_size == Some(0) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`max_response_size` should be greater than zero, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+        if let Some(header_limits) = self.header_limits() {
+            header_limits.validate()?;
+        }
+        if let Some(cookies) = self.cookies() {
+            cookies.validate()?;
+        }
+        self.dns.validate()?;
+        if let Some(bind_address) = self.bind_address() {
+            bind_address.validate()?;
+        }
+        if let Some(interface) = self.interface() {
+            if !cfg!(any(
+                target_os = "android",
+                target_os = "fuchsia",
+                target_os = "linux"
+            )) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!(
+                        "`interface` isn't supported on this platform, target `{}`",
+                        self.id()
+                    ),
+                });
+            }
+            if interface.is_empty() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("`interface` should not be empty, target `{}`", self.id()),
+                });
+            }
+        }
+        if let Some(sampling) = self.sampling() {
+            sampling.validate()?;
+        }
+        if let Some(canary) = self.canary() {
+            canary.validate()?;
+        }
+        if let Some(slo) = self.slo() {
+            slo.validate()?;
+        }
+        if let Some(retry_queue) = self.retry_queue() {
+            retry_queue.validate()?;
+        }
+        if self.deprecation_headers && !self.is_deprecated() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`deprecation_headers` can be set for deprecated target only, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+
         // Validate target's error response override
         match self.on_error() {
             TargetOnErrorAction::Propagate | TargetOnErrorAction::Drop => {
@@ -427,8 +1277,62 @@ impl ConfigValidator for TargetConfig {
             }
         }
 
+        // Timeout statuses are used to propagate errors only
+        if !self.timeout_status.is_empty()
+            && !matches!(self.on_error(), TargetOnErrorAction::Propagate)
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`timeout_status` can be set if `on_error` is `propagate` only, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+
+        if self
+            .timeout_status
+            .retry_after
+            .is_some_and(|retry_after| retry_after.is_zero())
+        {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "`timeout_status.retry_after` should be greater than zero, target `{}`",
+                    self.id()
+                ),
+            });
+        }
+
         Ok(())
     }
+
+    fn lint(&self) -> Vec<ConfigWarning> {
+        let mut warnings = vec![];
+        if let Some(TargetConditionConfig::Filter(filter)) = self.condition() {
+            if filter.is_never_true() {
+                warnings.push(ConfigWarning::new(format!(
+                    "`condition` `{}` can never be true, so the target is never requested",
+                    filter.expression
+                )));
+            }
+        }
+        if let Some(total_timeout) = self.total_timeout() {
+            if self.timeout >= total_timeout {
+                warnings.push(ConfigWarning::new(format!(
+                    "`timeout` {} isn't less than `total_timeout` {}, so it never fires",
+                    humantime::format_duration(self.timeout),
+                    humantime::format_duration(total_timeout)
+                )));
+            }
+        }
+        if let Some(sunset) = self.sunset().filter(Sunset::is_passed) {
+            warnings.push(ConfigWarning::new(format!(
+                "sunset date {} of the deprecated target has passed",
+                sunset.http_date()
+            )));
+        }
+
+        warnings
+    }
 }
 
 impl ConfigValidator for [TargetConfig] {
@@ -461,12 +1365,24 @@ impl ConfigValidator for [TargetConfig] {
     }
 }
 
-pub trait TargetBehavior {
-    fn check_condition(&self, ctx: &Context, req: &Parts, body: &Bytes) -> bool;
+/// Input of the target conditions, it's built once per request and shared by all targets,
+/// so the body is parsed once and cloning of the input is cheap
+pub struct ConditionInput(Val);
+
+impl From<Value> for ConditionInput {
+    fn from(input: Value) -> Self {
+        Self(Val::from(input))
+    }
 }
 
-impl TargetBehavior for TargetConfig {
-    fn check_condition(&self, ctx: &Context, req: &Parts, body: &Bytes) -> bool {
+impl ConditionInput {
+    pub fn new(ctx: &Context, req: &Parts, body: Value) -> Self {
+        Self::from(Self::snapshot(ctx, req, body))
+    }
+
+    /// Returns content of the input, unlike the input itself it may be kept across await points;
+    /// body is expected to be decoded already by the listener's codec
+    pub fn snapshot(ctx: &Context, req: &Parts, body: Value) -> Value {
         // Input content
         // .body
         // .env{}
@@ -475,14 +1391,15 @@ impl TargetBehavior for TargetConfig {
         // .request.uri.host
         // .request.uri.path
         // .request.uri.query
-        let body: Value = serde_json::from_slice(body).unwrap_or(json!({}));
+        // .maintenance
+        // .now{timestamp, rfc3339, year, month, day, hour, minute, second, weekday}
         let headers: HashMap<String, String> = req
             .headers
             .iter()
             .map(|(k, v)| (k.to_string(), v.to_str().unwrap().to_string()))
             .collect();
         let env = ctx.iter().collect::<HashMap<&String, &String>>();
-        let input = json!({
+        json!({
             "body": body,
             "env": env,
             "request": {
@@ -493,9 +1410,78 @@ impl TargetBehavior for TargetConfig {
                     "path": req.uri.path(),
                     "query": req.uri.query()
                 }
-            }
+            },
+            "maintenance": maintenance::is_enabled(),
+            "now": time_input(SystemTime::now())
+        })
+    }
+
+    /// Adds response of the preceding target to the input as `.response{status, headers{}, body}`,
+    /// it's `null` if the preceding target has no response (dropped on error)
+    pub fn with_response(&self, resp: Option<&Response<Full<Bytes>>>) -> Self {
+        let response = resp.map(|resp| {
+            let headers: HashMap<String, String> = resp
+                .headers()
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_string()))
+                .collect();
+            let body = full_bytes(resp.body());
+            let body: Value = serde_json::from_slice(&body).unwrap_or(json!({}));
+            json!({
+                "status": resp.status().as_u16(),
+                "headers": headers,
+                "body": body
+            })
         });
 
+        let mut input = Value::from(self.0.clone());
+        input["response"] = response.unwrap_or(Value::Null);
+        Self(Val::from(input))
+    }
+}
+
+/// Current UTC time in the form which is convenient for conditions:
+/// timestamps to check start/end of the window, and calendar fields for cron-like schedules
+fn time_input(now: SystemTime) -> Value {
+    let timestamp = now
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    let (hour, minute, second) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+
+    // Civil date from days since epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    json!({
+        "timestamp": timestamp,
+        "rfc3339": format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"),
+        "year": year,
+        "month": month,
+        "day": day,
+        "hour": hour,
+        "minute": minute,
+        "second": second,
+        // 0 is Sunday, 1970-01-01 was Thursday
+        "weekday": (days + 4).rem_euclid(7)
+    })
+}
+
+pub trait TargetBehavior {
+    fn check_condition(&self, input: &ConditionInput) -> bool;
+}
+
+impl TargetBehavior for TargetConfig {
+    fn check_condition(&self, input: &ConditionInput) -> bool {
         if let TargetConditionConfig::Filter(filter) = self.condition().as_ref().unwrap() {
             filter.run(input)
         } else {
@@ -513,12 +1499,189 @@ pub mod test_target {
             id: Some("TEST-TARGET-ID".into()),
             url: "https://www.google.com/test-path?query=some-query".into(),
             headers: None,
+            header_limits: None,
+            query: None,
+            cookies: None,
+            host_header: Default::default(),
+            forward_path: false,
+            path_rewrite: None,
+            method_override: None,
             body: None,
             timeout: Duration::from_secs(DEFAULT_TARGET_TIMEOUT_SEC),
             on_error: TargetOnErrorAction::Propagate,
             error_status: None,
+            failure_policy: None,
             condition: Some(TargetConditionConfig::Default),
+            after: None,
             tls: Default::default(),
+            http2: false,
+            faults: None,
+            added_latency: Duration::ZERO,
+            bandwidth_limit: None,
+            connect_timeout: None,
+            read_timeout: None,
+            total_timeout: None,
+            timeout_status: Default::default(),
+            compression: Default::default(),
+            ip_preference: Default::default(),
+            happy_eyeballs_delay: Duration::from_millis(DEFAULT_HAPPY_EYEBALLS_DELAY_MS),
+            bind_address: None,
+            interface: None,
+            dns: Default::default(),
+            discovery: None,
+            labels: Default::default(),
+            weight: DEFAULT_TARGET_WEIGHT,
+            priority: Default::default(),
+            startup_check: false,
+            content_types: None,
+            max_body_size: None,
+            max_response_size: None,
+            on_oversized_response: Default::default(),
+            sampling: None,
+            canary: None,
+            slo: None,
+            retry_queue: None,
+            deprecated: false,
+            sunset: None,
+            deprecation_headers: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::RootOsEnvironment;
+    use hyper::Request;
+
+    #[test]
+    fn shared_condition_input() {
+        let ctx = Context::root(RootOsEnvironment::new("^PATH$"));
+        let (req, _) = Request::post("http://localhost:8080/some/path?q=1")
+            .header("x-test", "value")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let input = ConditionInput::new(&ctx, &req, json!({"a": 1}));
+
+        let filters = [
+            (".body.a == 1", true),
+            (".body.a == 2", false),
+            (".request.uri.path == \"/some/path\"", true),
+            (".request.headers[\"x-test\"] == \"value\"", true),
+            (".env.CTX_APP_NAME == \"http-dragonfly\"", true),
+        ];
+        for (filter, expected) in filters {
+            assert_eq!(
+                ConditionFilter::from(filter).run(&input),
+                expected,
+                "{filter}"
+            );
         }
     }
+
+    #[test]
+    fn time_condition_input() {
+        // 2024-02-29T13:45:30Z, Thursday
+        let now = UNIX_EPOCH + Duration::from_secs(1709214330);
+        let input = time_input(now);
+
+        assert_eq!(input["rfc3339"], "2024-02-29T13:45:30Z");
+        assert_eq!(input["year"], 2024);
+        assert_eq!(input["month"], 2);
+        assert_eq!(input["day"], 29);
+        assert_eq!(input["hour"], 13);
+        assert_eq!(input["minute"], 45);
+        assert_eq!(input["weekday"], 4);
+
+        let ctx = Context::root(RootOsEnvironment::new("^PATH$"));
+        let (req, _) = Request::get("http://localhost:8080/")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let input = ConditionInput::new(&ctx, &req, json!({}));
+        let filters = [
+            (".maintenance", false),
+            (".maintenance | not", true),
+            (".now.hour | between(0; 24)", true),
+            (
+                ".now.rfc3339 | between(\"2024-01-01T00:00:00Z\"; \"2024-02-01T00:00:00Z\")",
+                false,
+            ),
+            (".now.timestamp > 1709214330", true),
+            (".now.hour >= 0 and .now.hour < 24", true),
+        ];
+        for (filter, expected) in filters {
+            assert_eq!(
+                ConditionFilter::from(filter).run(&input),
+                expected,
+                "{filter}"
+            );
+        }
+    }
+
+    #[test]
+    fn uri_with_placeholders() {
+        let target = |url: &str| TargetConfig {
+            url: url.into(),
+            ..test_target::get_test_target()
+        };
+
+        // gRPC targets get the original path right after the authority
+        let uri = target("http://grpc.example.com:50051${CTX_REQUEST_PATH}")
+            .uri()
+            .unwrap();
+        assert_eq!(uri.authority().unwrap(), "grpc.example.com:50051");
+        assert_eq!(
+            target("https://backend${CTX_REQUEST_PATH:-\"/\"}?q=${CTX_REQUEST_QUERY}")
+                .uri()
+                .unwrap()
+                .host(),
+            Some("backend")
+        );
+        // Host of the proxied request
+        assert!(target("http://${CTX_REQUEST_AUTHORITY}").uri().is_ok());
+        assert!(target("http://bad host${CTX_REQUEST_PATH}").uri().is_err());
+    }
+
+    #[tokio::test]
+    async fn probe() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let target: TargetConfig = serde_json::from_str(&format!(
+            r#"{{"url": "http://127.0.0.1:{port}/path", "startup_check": true, "connect_timeout": "1s"}}"#
+        ))
+        .unwrap();
+        let tls = TlsConfig::default();
+
+        assert_eq!(target.probe(&tls).await, Ok(()));
+        drop(listener);
+        assert!(target.probe(&tls).await.is_err());
+    }
+
+    #[test]
+    fn bind_address() {
+        let bind = |value: &str| serde_json::from_str::<BindAddress>(value).unwrap();
+
+        let single = bind(r#""10.0.0.1""#);
+        assert!(single.validate().is_ok());
+        assert_eq!(
+            single.addresses(),
+            (Some("10.0.0.1".parse().unwrap()), None)
+        );
+        let dual = bind(r#"["2001:db8::1", "10.0.0.1"]"#);
+        assert!(dual.validate().is_ok());
+        assert_eq!(
+            dual.addresses(),
+            (
+                Some("10.0.0.1".parse().unwrap()),
+                Some("2001:db8::1".parse().unwrap())
+            )
+        );
+
+        assert!(bind("[]").validate().is_err());
+        assert!(bind(r#"["10.0.0.1", "10.0.0.2"]"#).validate().is_err());
+        assert!(bind(r#""0.0.0.0""#).validate().is_err());
+        assert!(bind(r#""ff02::1""#).validate().is_err());
+    }
 }
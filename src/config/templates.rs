@@ -0,0 +1,143 @@
+use super::{
+    body_transform::ReplaceRule,
+    canary::CanaryConfig,
+    compression::TargetCompressionConfig,
+    cookies::CookiesConfig,
+    defaults::DefaultsConfig,
+    deprecation::Sunset,
+    discovery::DiscoveryConfig,
+    dns::TargetDnsConfig,
+    failure::FailurePolicy,
+    faults::FaultsConfig,
+    header_limits::HeaderLimitsConfig,
+    headers::HeaderTransform,
+    labels::Labels,
+    listener::{HttpMethod, TlsConfig},
+    query::QueryTransformsList,
+    response::ResponseStatus,
+    retry_queue::RetryQueueConfig,
+    sampling::SamplingConfig,
+    slo::SloConfig,
+    target::{
+        Bandwidth, BindAddress, HostHeader, IpPreference, OversizedResponseAction,
+        TargetConditionConfig, TargetOnErrorAction, TargetPriority, TimeoutStatusConfig,
+    },
+    ConfigError,
+};
+use figment::value::{Dict, Value};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{collections::HashMap, time::Duration};
+
+/// Key of the target parameter which refers to the template
+pub const TEMPLATE_KEY: &str = "template";
+
+pub type TargetTemplates = HashMap<String, TargetTemplate>;
+
+/// Reusable part of the target config, targets refer to it by name and may override any parameter.
+/// Used to validate `target_templates` section only, templates are materialized in the raw config before deserialization.
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+pub struct TargetTemplate {
+    url: Option<String>,
+    headers: Option<Vec<HeaderTransform>>,
+    header_limits: Option<HeaderLimitsConfig>,
+    query: Option<QueryTransformsList>,
+    cookies: Option<CookiesConfig>,
+    #[schemars(with = "Option<String>")]
+    host_header: Option<HostHeader>,
+    forward_path: Option<bool>,
+    path_rewrite: Option<ReplaceRule>,
+    method_override: Option<HttpMethod>,
+    body: Option<String>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    timeout: Option<Duration>,
+    on_error: Option<TargetOnErrorAction>,
+    error_status: Option<ResponseStatus>,
+    failure_policy: Option<FailurePolicy>,
+    #[schemars(with = "Option<String>")]
+    condition: Option<TargetConditionConfig>,
+    after: Option<String>,
+    tls: Option<TlsConfig>,
+    http2: Option<bool>,
+    faults: Option<FaultsConfig>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    added_latency: Option<Duration>,
+    #[schemars(with = "Option<String>")]
+    bandwidth_limit: Option<Bandwidth>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    connect_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    read_timeout: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    total_timeout: Option<Duration>,
+    timeout_status: Option<TimeoutStatusConfig>,
+    compression: Option<TargetCompressionConfig>,
+    ip_preference: Option<IpPreference>,
+    #[serde(default, with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    happy_eyeballs_delay: Option<Duration>,
+    bind_address: Option<BindAddress>,
+    interface: Option<String>,
+    dns: Option<TargetDnsConfig>,
+    discovery: Option<DiscoveryConfig>,
+    labels: Option<Labels>,
+    weight: Option<u32>,
+    priority: Option<TargetPriority>,
+    startup_check: Option<bool>,
+    content_types: Option<Vec<String>>,
+    max_body_size: Option<usize>,
+    max_response_size: Option<usize>,
+    on_oversized_response: Option<OversizedResponseAction>,
+    sampling: Option<SamplingConfig>,
+    canary: Option<CanaryConfig>,
+    slo: Option<SloConfig>,
+    retry_queue: Option<RetryQueueConfig>,
+    deprecated: Option<bool>,
+    #[schemars(with = "Option<String>")]
+    sunset: Option<Sunset>,
+    deprecation_headers: Option<bool>,
+}
+
+impl TargetTemplate {
+    /// Replaces template reference of each raw target by the template content,
+    /// parameters of the target itself take precedence over the template ones
+    pub fn apply(templates: &Dict, listeners: &mut [Value]) -> Result<(), ConfigError> {
+        for listener in listeners {
+            let Value::Dict(_, listener) = listener else {
+                continue;
+            };
+            let Some(Value::Array(_, targets)) = listener.get_mut("targets") else {
+                continue;
+            };
+            for target in targets {
+                let Value::Dict(_, dict) = target else {
+                    continue;
+                };
+                let Some(name) = dict.remove(TEMPLATE_KEY) else {
+                    continue;
+                };
+
+                let name = name
+                    .into_string()
+                    .ok_or_else(|| ConfigError::ValidateConfig {
+                        cause: "target template name should be a string".into(),
+                    })?;
+                let template = templates
+                    .get(&name)
+                    .ok_or_else(|| ConfigError::ValidateConfig {
+                        cause: format!("target template `{name}` isn't defined"),
+                    })?;
+                DefaultsConfig::merge_target(target, template);
+            }
+        }
+
+        Ok(())
+    }
+}
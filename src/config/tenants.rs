@@ -0,0 +1,150 @@
+use super::{target::TargetConfig, ConfigError, ConfigValidator};
+use hyper::{header::HeaderName, HeaderMap};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Multi-tenant routing: value of the header selects the group of targets which request is sent to,
+/// requests of unknown tenants are sent to the default group
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TenantsConfig {
+    header: String,
+    groups: BTreeMap<String, TenantGroupConfig>,
+    default: Option<TenantGroupConfig>,
+}
+
+/// Targets of the single tenant and its own response settings
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TenantGroupConfig {
+    targets: Vec<String>,
+    target_selector: Option<String>,
+}
+
+impl TenantsConfig {
+    /// Returns name of the header with tenant ID
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// Returns group of the request's tenant or default one,
+    /// there is no group if tenant is unknown and default group isn't defined
+    pub fn group(&self, headers: &HeaderMap) -> Option<&TenantGroupConfig> {
+        headers
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|tenant| self.groups.get(tenant))
+            .or(self.default.as_ref())
+    }
+
+    /// Returns `true` if any group overrides `target_selector` of the response
+    pub fn has_target_selector(&self) -> bool {
+        self.all_groups()
+            .any(|group| group.target_selector.is_some())
+    }
+
+    /// Verifies that all referred targets exist
+    pub fn validate_targets(&self, targets: &[TargetConfig]) -> Result<(), ConfigError> {
+        for target_id in self.all_groups().flat_map(|group| group.targets.iter()) {
+            if !targets.iter().any(|t| &t.id() == target_id) {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("`tenants` group points to unknown target_id `{target_id}`"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn all_groups(&self) -> impl Iterator<Item = &TenantGroupConfig> {
+        self.groups.values().chain(self.default.iter())
+    }
+}
+
+impl TenantGroupConfig {
+    /// Returns `true` if target belongs to this group
+    pub fn contains(&self, target_id: &str) -> bool {
+        self.targets.iter().any(|id| id == target_id)
+    }
+
+    /// Returns target to respond from in case of `*_target_id` strategies instead of the response's one
+    pub fn target_selector(&self) -> Option<&String> {
+        self.target_selector.as_ref()
+    }
+}
+
+impl ConfigValidator for TenantsConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if HeaderName::try_from(&self.header).is_err() {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!("`tenants` header `{}` isn't valid", self.header),
+            });
+        }
+        if self.groups.is_empty() {
+            return Err(ConfigError::ValidateConfig {
+                cause: "`tenants` should have at least one group".into(),
+            });
+        }
+        for (tenant, group) in self
+            .groups
+            .iter()
+            .map(|(tenant, group)| (tenant.as_str(), group))
+            .chain(self.default.iter().map(|group| ("default", group)))
+        {
+            if group.targets.is_empty() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("`tenants` group `{tenant}` should have at least one target"),
+                });
+            }
+            if let Some(target_id) = group.target_selector() {
+                if !group.contains(target_id) {
+                    return Err(ConfigError::ValidateConfig {
+                        cause: format!(
+                            "`target_selector` of `tenants` group `{tenant}` points to `{target_id}` which isn't in the group"
+                        ),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenant_group() {
+        let tenants: TenantsConfig = serde_json::from_str(
+            r#"{
+                "header": "X-Tenant-Id",
+                "groups": {
+                    "acme": {"targets": ["a1", "a2"], "target_selector": "a2"},
+                    "globex": {"targets": ["g1"]}
+                },
+                "default": {"targets": ["shared"]}
+            }"#,
+        )
+        .unwrap();
+        assert!(tenants.validate().is_ok());
+        assert!(tenants.has_target_selector());
+
+        let mut headers = HeaderMap::new();
+        assert!(tenants.group(&headers).unwrap().contains("shared"));
+        headers.insert("x-tenant-id", "acme".parse().unwrap());
+        let group = tenants.group(&headers).unwrap();
+        assert!(group.contains("a1") && !group.contains("g1"));
+        assert_eq!(group.target_selector(), Some(&"a2".to_string()));
+        headers.insert("x-tenant-id", "unknown".parse().unwrap());
+        assert!(tenants.group(&headers).unwrap().contains("shared"));
+
+        let tenants: TenantsConfig = serde_json::from_str(
+            r#"{"header": "x-tenant-id", "groups": {"acme": {"targets": ["a1"]}}}"#,
+        )
+        .unwrap();
+        assert!(tenants.group(&headers).is_none());
+    }
+}
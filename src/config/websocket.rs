@@ -0,0 +1,56 @@
+use super::{target::TargetConfig, ConfigError, ConfigValidator};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// WebSocket upgrade handling: session is proxied to the primary target,
+/// client's frames are optionally mirrored (one-way) to other targets
+#[derive(Deserialize, Debug, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WebSocketConfig {
+    primary: String,
+    #[serde(default)]
+    mirror: Vec<String>,
+}
+
+impl WebSocketConfig {
+    pub fn primary(&self) -> &str {
+        &self.primary
+    }
+
+    pub fn mirror(&self) -> &[String] {
+        &self.mirror
+    }
+
+    /// Verifies that all referred targets exist
+    pub fn validate_targets(&self, targets: &[TargetConfig]) -> Result<(), ConfigError> {
+        for target_id in std::iter::once(&self.primary).chain(self.mirror.iter()) {
+            let Some(target) = targets.iter().find(|t| &t.id() == target_id) else {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("`websocket` points to unknown target_id `{target_id}`"),
+                });
+            };
+            if target.discovery().is_some() {
+                return Err(ConfigError::ValidateConfig {
+                    cause: format!("`websocket` target `{target_id}` can't use discovery"),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConfigValidator for WebSocketConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.mirror.contains(&self.primary) {
+            return Err(ConfigError::ValidateConfig {
+                cause: format!(
+                    "websocket primary target `{}` can't be mirror target at the same time",
+                    self.primary
+                ),
+            });
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{
+    config::{connections::ConnectionsConfig, listener::ListenerConfig},
+    metrics::{self, MetricsSource},
+};
+use socket2::{SockRef, TcpKeepalive};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use strum_macros::Display;
+use tokio::{net::TcpStream, sync::Notify};
+use tracing::warn;
+
+/// Max interval between checks of the connection's limits
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Open client connections of the listener, all its workers share the same tracker
+#[derive(Debug)]
+pub struct ConnectionTracker {
+    listener_id: String,
+    keep_alive: bool,
+    idle_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+    max_lifetime: Option<Duration>,
+    tcp_keepalive: Option<TcpKeepalive>,
+    tcp_nodelay: bool,
+    idle_shedding_threshold: Option<usize>,
+    open: AtomicUsize,
+}
+
+/// State of the single client connection
+#[derive(Debug)]
+pub struct Connection {
+    tracker: Arc<ConnectionTracker>,
+    opened: Instant,
+    requests: AtomicUsize,
+    in_flight: AtomicUsize,
+    last_active: Mutex<Instant>,
+    limit_reached: Notify,
+    shed: AtomicBool,
+}
+
+/// Request in progress, connection is idle when all guards are dropped
+pub struct RequestGuard(Arc<Connection>);
+
+/// Why connection is closed by the listener
+#[derive(Debug, Display, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "snake_case")]
+pub enum CloseReason {
+    MaxRequests,
+    MaxLifetime,
+    IdleTimeout,
+    Shedding,
+}
+
+impl ConnectionTracker {
+    pub fn new(cfg: &ListenerConfig) -> Arc<Self> {
+        let connections = cfg.connections();
+        let tracker = Arc::new(Self {
+            listener_id: cfg.id(),
+            keep_alive: connections.is_none_or(ConnectionsConfig::keep_alive),
+            idle_timeout: connections.and_then(ConnectionsConfig::idle_timeout),
+            max_requests: connections.and_then(ConnectionsConfig::max_requests),
+            max_lifetime: connections.and_then(ConnectionsConfig::max_lifetime),
+            tcp_keepalive: connections
+                .and_then(ConnectionsConfig::tcp_keepalive)
+                .map(|cfg| {
+                    let keepalive = TcpKeepalive::new().with_time(cfg.time());
+                    #[cfg(any(
+                        target_os = "linux",
+                        target_os = "macos",
+                        target_os = "freebsd",
+                        target_os = "windows"
+                    ))]
+                    let keepalive = match cfg.interval() {
+                        Some(interval) => keepalive.with_interval(interval),
+                        None => keepalive,
+                    };
+                    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+                    let keepalive = match cfg.retries() {
+                        Some(retries) => keepalive.with_retries(retries),
+                        None => keepalive,
+                    };
+                    keepalive
+                }),
+            tcp_nodelay: cfg.socket_options().tcp_nodelay(),
+            idle_shedding_threshold: connections
+                .and_then(ConnectionsConfig::idle_shedding_threshold),
+            open: AtomicUsize::new(0),
+        });
+        metrics::register(tracker.clone());
+
+        tracker
+    }
+
+    /// Returns `false` if HTTP/1 connection should be closed after each response
+    pub fn keep_alive(&self) -> bool {
+        self.keep_alive
+    }
+
+    /// Registers accepted connection and sets its socket options
+    pub fn open(self: &Arc<Self>, stream: &TcpStream) -> Arc<Connection> {
+        if let Some(keepalive) = &self.tcp_keepalive {
+            if let Err(e) = SockRef::from(stream).set_tcp_keepalive(keepalive) {
+                warn!(listener = %self.listener_id, error = %e, "unable to set TCP keepalive");
+            }
+        }
+        if self.tcp_nodelay {
+            if let Err(e) = stream.set_nodelay(true) {
+                warn!(listener = %self.listener_id, error = %e, "unable to set TCP nodelay");
+            }
+        }
+        self.open.fetch_add(1, Ordering::Relaxed);
+
+        Arc::new(Connection {
+            tracker: self.clone(),
+            opened: Instant::now(),
+            requests: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            last_active: Mutex::new(Instant::now()),
+            limit_reached: Notify::new(),
+            shed: AtomicBool::new(false),
+        })
+    }
+
+    /// Interval between checks of the time limits, `None` if there are no such limits
+    fn check_interval(&self) -> Option<Duration> {
+        let shedding = self.idle_shedding_threshold.map(|_| MAX_CHECK_INTERVAL);
+        [self.idle_timeout, self.max_lifetime, shedding]
+            .into_iter()
+            .flatten()
+            .map(|limit| limit.min(MAX_CHECK_INTERVAL))
+            .min()
+    }
+
+    /// Claims shedding of one connection while there are more than `max` open ones: connection is unregistered
+    /// right away, so idle connections checked at the same time close the excess ones only
+    fn claim_shedding(&self, max: usize) -> bool {
+        self.open
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |open| {
+                (open > max).then(|| open - 1)
+            })
+            .is_ok()
+    }
+}
+
+impl MetricsSource for ConnectionTracker {
+    fn write_metrics(&self, out: &mut String) {
+        metrics::gauge(
+            out,
+            "open_connections",
+            [("listener", self.listener_id.as_str())],
+            self.open.load(Ordering::Relaxed) as f64,
+        );
+    }
+}
+
+impl Connection {
+    /// Marks start of the request, connection should be closed after it if the requests limit is reached
+    pub fn request(self: &Arc<Self>) -> RequestGuard {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        let requests = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.tracker.max_requests.is_some_and(|max| requests >= max) {
+            self.limit_reached.notify_one();
+        }
+
+        RequestGuard(self.clone())
+    }
+
+    /// Waits until connection should be closed by one of the limits
+    pub async fn wait_limit(&self) -> CloseReason {
+        let Some(interval) = self.tracker.check_interval() else {
+            self.limit_reached.notified().await;
+            return CloseReason::MaxRequests;
+        };
+
+        let mut ticks = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = self.limit_reached.notified() => return CloseReason::MaxRequests,
+                _ = ticks.tick() => {
+                    if let Some(reason) = self.check_time_limits() {
+                        return reason;
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_time_limits(&self) -> Option<CloseReason> {
+        let tracker = &self.tracker;
+        if tracker
+            .max_lifetime
+            .is_some_and(|max| self.opened.elapsed() >= max)
+        {
+            return Some(CloseReason::MaxLifetime);
+        }
+        if self.in_flight.load(Ordering::Relaxed) > 0 {
+            return None;
+        }
+        let idle = self
+            .last_active
+            .lock()
+            .expect("unable to lock connection state, looks like a BUG")
+            .elapsed();
+        // Just accepted connection isn't idle yet, its first request may be on the way
+        let served = self.requests.load(Ordering::Relaxed) > 0;
+        if tracker.idle_timeout.is_some_and(|max| idle >= max) {
+            Some(CloseReason::IdleTimeout)
+        } else if served
+            && tracker
+                .idle_shedding_threshold
+                .is_some_and(|max| tracker.claim_shedding(max))
+        {
+            self.shed.store(true, Ordering::Relaxed);
+            Some(CloseReason::Shedding)
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        // Shed connection is unregistered when it's claimed
+        if !self.shed.load(Ordering::Relaxed) {
+            self.tracker.open.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for RequestGuard {
+    fn drop(&mut self) {
+        *self
+            .0
+            .last_active
+            .lock()
+            .expect("unable to lock connection state, looks like a BUG") = Instant::now();
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn shed_excess_idle_connections() {
+        let cfg: ListenerConfig = serde_json::from_str(
+            r#"{"connections": {"idle_shedding_threshold": 3}, "targets": [{"url": "http://localhost/"}]}"#,
+        )
+        .unwrap();
+        let tracker = ConnectionTracker::new(&cfg);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut connections = vec![];
+        for index in 0..5 {
+            let stream = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let connection = tracker.open(&stream);
+            // The first connection is just accepted, the rest have served a request
+            if index > 0 {
+                drop(connection.request());
+            }
+            connections.push(connection);
+        }
+
+        // All idle connections are checked before any of them is closed
+        let closed = connections
+            .iter()
+            .map(|connection| connection.check_time_limits())
+            .collect::<Vec<_>>();
+        assert_eq!(closed[0], None);
+        assert_eq!(
+            closed
+                .iter()
+                .filter(|reason| **reason == Some(CloseReason::Shedding))
+                .count(),
+            2
+        );
+        assert_eq!(tracker.open.load(Ordering::Relaxed), 3);
+
+        drop(connections);
+        assert_eq!(tracker.open.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn tcp_nodelay_of_accepted_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        for nodelay in [false, true] {
+            let cfg: ListenerConfig = serde_json::from_str(&format!(
+                r#"{{"socket": {{"tcp_nodelay": {nodelay}}}, "targets": [{{"url": "http://localhost/"}}]}}"#
+            ))
+            .unwrap();
+            let tracker = ConnectionTracker::new(&cfg);
+            let _client = TcpStream::connect(listener.local_addr().unwrap())
+                .await
+                .unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+            let _connection = tracker.open(&stream);
+            assert_eq!(stream.nodelay().unwrap(), nodelay);
+        }
+    }
+}
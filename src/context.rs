@@ -1,10 +1,20 @@
-use crate::config::target::TargetConfig;
+pub mod secrets;
+
+use crate::{config::target::TargetConfig, redaction};
 use http_body_util::Full;
 use hyper::{body::Bytes, http::request::Parts, Response};
 use regex::Regex;
+use secrets::{SecretError, SecretProvider};
 use serde::Serialize;
-use std::{collections::HashMap, env, net::SocketAddr, sync::OnceLock};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    env,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tracing::{debug, info};
+use uuid::Uuid;
 
 const CTX_APP_NAME: &str = env!("CARGO_PKG_NAME");
 const CTX_APP_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -18,31 +28,46 @@ pub struct Context<'a> {
 }
 
 impl<'a> Context<'a> {
-    pub fn root(root_env: impl RootEnvironment) -> &'static Context<'a> {
-        static ROOT_CONTEXT: OnceLock<Context> = OnceLock::new();
+    pub fn root(root_env: impl RootEnvironment) -> Context<'a> {
+        let mut ctx = ContextMap::new();
+        let root_env = root_env.get_environment();
+        let root_env_size = root_env.len();
 
-        let ctx = ROOT_CONTEXT.get_or_init(|| {
-            let mut ctx = ContextMap::new();
-            let root_env = root_env.get_environment();
-            let root_env_size = root_env.len();
+        ctx.insert("CTX_APP_NAME".into(), CTX_APP_NAME.into());
+        ctx.insert("CTX_APP_VERSION".into(), CTX_APP_VERSION.into());
 
-            ctx.insert("CTX_APP_NAME".into(), CTX_APP_NAME.into());
-            ctx.insert("CTX_APP_VERSION".into(), CTX_APP_VERSION.into());
+        debug!("Accepted environment variables: {:?}", root_env);
+        ctx.extend(root_env);
 
-            debug!("Accepted environment variables: {:?}", root_env);
-            ctx.extend(root_env);
+        info!("Created root context, CTX_APP_NAME: {CTX_APP_NAME}, CTX_APP_VERSION: {CTX_APP_VERSION}, and {root_env_size} environment variables.");
+        Context {
+            own: ctx,
+            parent: None,
+        }
+    }
 
-            info!("Created root context, CTX_APP_NAME: {CTX_APP_NAME}, CTX_APP_VERSION: {CTX_APP_VERSION}, and {root_env_size} environment variables.");
-            Context {
-                own: ctx,
-                parent: None,
-            }
-        });
+    /// Adds secrets from the provider to the context, values are never logged
+    pub fn add_secrets(&mut self, provider: &dyn SecretProvider) -> Result<(), SecretError> {
+        let secrets = provider.get_secrets()?;
+        let mut names: Vec<&String> = secrets.keys().collect();
+        names.sort();
+        info!(
+            "Loaded {} secret(s) from `{}` provider: {names:?}",
+            names.len(),
+            provider.name()
+        );
+
+        redaction::add_secrets(secrets.values());
+        self.own.extend(secrets);
+        Ok(())
+    }
 
-        ctx
+    /// Adds variables to the own context, like ones of the request enrichment
+    pub fn extend(&mut self, vars: ContextMap) {
+        self.own.extend(vars);
     }
 
-    pub fn with(&self, own: ContextMap) -> Context {
+    pub fn with(&self, own: ContextMap) -> Context<'_> {
         Context {
             own,
             parent: Some(self),
@@ -67,10 +92,51 @@ impl<'a> Context<'a> {
         }
     }
 
+    /// Returns value of the variable like `get`, but computed variables (time and random values)
+    /// are evaluated on each call, so it's used to expand values at request time
+    pub fn lookup(&self, var: &str) -> Option<Cow<'_, str>> {
+        let computed = match var {
+            "CTX_TIMESTAMP_RFC3339" => {
+                Some(humantime::format_rfc3339_millis(SystemTime::now()).to_string())
+            }
+            "CTX_EPOCH_MS" => Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis()
+                    .to_string(),
+            ),
+            "CTX_RANDOM_UUID" => Some(Uuid::new_v4().to_string()),
+            _ => match var
+                .strip_prefix("CTX_RANDOM_INT(")
+                .and_then(|args| args.strip_suffix(')'))
+            {
+                // Both bounds are inclusive
+                Some(args) => {
+                    let (min, max) = args.split_once(',')?;
+                    let min: i64 = min.trim().parse().ok()?;
+                    let max: i64 = max.trim().parse().ok()?;
+                    if min > max {
+                        return None;
+                    }
+                    Some(fastrand::i64(min..=max).to_string())
+                }
+                None => None,
+            },
+        };
+
+        match computed {
+            Some(value) => Some(Cow::Owned(value)),
+            None => self
+                .get(&var.into())
+                .map(|value| Cow::Borrowed(value.as_str())),
+        }
+    }
+
     pub fn with_request(
         &'a self,
-        addr: &'a SocketAddr,
-        req: &'a Parts,
+        addr: &SocketAddr,
+        req: &Parts,
         listener_name: String,
     ) -> Context<'a> {
         let mut own = ContextMap::new();
@@ -81,6 +147,8 @@ impl<'a> Context<'a> {
         // CTX_REQUEST_HOST
         // CTX_REQUEST_PATH
         // CTX_REQUEST_QUERY
+        // CTX_REQUEST_SCHEME
+        // CTX_REQUEST_AUTHORITY
         own.insert("CTX_LISTENER_NAME".into(), listener_name);
         own.insert("CTX_REQUEST_SOURCE_IP".into(), addr.ip().to_string());
         own.insert("CTX_REQUEST_METHOD".into(), req.method.to_string());
@@ -91,6 +159,16 @@ impl<'a> Context<'a> {
         if let Some(query) = req.uri.query() {
             own.insert("CTX_REQUEST_QUERY".into(), query.to_lowercase());
         }
+        // Request in absolute form, like the proxied one, has scheme and authority
+        if let Some(scheme) = req.uri.scheme_str() {
+            own.insert("CTX_REQUEST_SCHEME".into(), scheme.to_lowercase());
+        }
+        if let Some(authority) = req.uri.authority() {
+            own.insert(
+                "CTX_REQUEST_AUTHORITY".into(),
+                authority.as_str().to_lowercase(),
+            );
+        }
 
         // CTX_REQUEST_HEADERS_<UPPERCASE_HEADER_NAME>
         for (n, v) in &req.headers {
@@ -128,7 +206,7 @@ impl<'a> Context<'a> {
         self.with(own)
     }
 
-    pub fn iter(&self) -> ContextIterator {
+    pub fn iter(&self) -> ContextIterator<'_> {
         let iter = Box::new(self.own.iter());
         ContextIterator {
             ctx: self,
@@ -150,17 +228,14 @@ impl<'a> Iterator for ContextIterator<'a> {
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(next) = self.iter.next() {
             Some(next)
-        } else if self.ctx.parent.is_none() {
-            None
-        } else if !self.finished {
-            self.finished = true;
-            self.iter = Box::new(
-                self.ctx
-                    .parent
-                    .expect("unable to get parent context, looks like a BUG")
-                    .iter(),
-            );
-            self.iter.next()
+        } else if let Some(parent) = self.ctx.parent {
+            if !self.finished {
+                self.finished = true;
+                self.iter = Box::new(parent.iter());
+                self.iter.next()
+            } else {
+                None
+            }
         } else {
             None
         }
@@ -210,7 +285,7 @@ pub mod test_context {
     use crate::config::target::test_target::get_test_target;
     use hyper::Request;
     use insta::assert_ron_snapshot;
-    use std::net::Ipv4Addr;
+    use std::{net::Ipv4Addr, sync::LazyLock};
 
     const TEST_ENV_KEY: &str = "TEST_ENV_KEY";
     const TEST_ENV_VALUE: &str = "TEST_ENV_VALUE";
@@ -239,10 +314,10 @@ pub mod test_context {
         }
     }
 
-    pub fn get_test_ctx<'a>() -> &'static Context<'a> {
-        let env_single: TestEnvironment = TestEnvironment::test_env();
-        let ctx = Context::root(env_single);
-        ctx
+    pub fn get_test_ctx() -> &'static Context<'static> {
+        static TEST_CTX: LazyLock<Context> =
+            LazyLock::new(|| Context::root(TestEnvironment::test_env()));
+        &TEST_CTX
     }
 
     fn get_test_source_addr() -> SocketAddr {
@@ -303,6 +378,62 @@ pub mod test_context {
         assert_ron_snapshot!(ctx_with, {".own" => insta::sorted_redaction(), ".parent.own" => insta::sorted_redaction()});
     }
 
+    struct TestSecretProvider;
+
+    impl SecretProvider for TestSecretProvider {
+        fn name(&self) -> &str {
+            "test"
+        }
+
+        fn get_secrets(&self) -> Result<ContextMap, SecretError> {
+            Ok(ContextMap::from([(
+                "CTX_SECRET_TEST".into(),
+                "TEST_SECRET_VALUE".into(),
+            )]))
+        }
+    }
+
+    #[test]
+    fn context_with_secrets() {
+        let mut ctx = Context::root(TestEnvironment::test_env());
+        ctx.add_secrets(&TestSecretProvider).unwrap();
+
+        assert_eq!(
+            ctx.get(&String::from("CTX_SECRET_TEST")),
+            Some(&String::from("TEST_SECRET_VALUE"))
+        );
+        assert_eq!(
+            ctx.get(&TEST_ENV_KEY.to_string()),
+            Some(&TEST_ENV_VALUE.to_string())
+        );
+    }
+
+    #[test]
+    fn computed_variables() {
+        let ctx = get_test_ctx();
+
+        let timestamp = ctx.lookup("CTX_TIMESTAMP_RFC3339").unwrap();
+        assert!(humantime::parse_rfc3339(&timestamp).is_ok(), "{timestamp}");
+        let epoch_ms: u128 = ctx.lookup("CTX_EPOCH_MS").unwrap().parse().unwrap();
+        assert!(epoch_ms > 0);
+        let uuid = ctx.lookup("CTX_RANDOM_UUID").unwrap();
+        assert!(Uuid::parse_str(&uuid).is_ok());
+        assert_ne!(uuid, ctx.lookup("CTX_RANDOM_UUID").unwrap());
+
+        for _ in 0..100 {
+            let value: i64 = ctx
+                .lookup("CTX_RANDOM_INT(-1, 1)")
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((-1..=1).contains(&value));
+        }
+        assert_eq!(ctx.lookup("CTX_RANDOM_INT(5,5)").unwrap(), "5");
+        assert_eq!(ctx.lookup("CTX_RANDOM_INT(1,0)"), None);
+        assert_eq!(ctx.lookup("CTX_RANDOM_INT(a,1)"), None);
+        assert_eq!(ctx.lookup(TEST_ENV_KEY).unwrap(), TEST_ENV_VALUE);
+    }
+
     #[test]
     fn request_context() {
         let parent = get_test_ctx();
@@ -0,0 +1,154 @@
+use super::ContextMap;
+use std::{
+    env, fs, io,
+    path::PathBuf,
+    sync::{Arc, LazyLock, RwLock},
+};
+use tracing::debug;
+
+/// Prefix of OS environment variables which point to files with secret values
+pub const SECRET_FILE_ENV_PREFIX: &str = "CTX_SECRET_FILE_";
+/// Prefix of context variables with values loaded from secret files
+pub const SECRET_CTX_PREFIX: &str = "CTX_SECRET_";
+
+/// Providers registered by the library user, they're queried after the file one in order of registration
+static PROVIDERS: LazyLock<RwLock<Vec<Arc<dyn SecretProvider + Send + Sync>>>> =
+    LazyLock::new(|| RwLock::new(vec![]));
+
+#[derive(thiserror::Error, Debug)]
+pub enum SecretError {
+    #[error("unable to read secret `{name}` from `{path}`: {cause}")]
+    ReadFile {
+        name: String,
+        path: PathBuf,
+        cause: io::Error,
+    },
+    #[error("secret provider `{provider}` failed: {cause}")]
+    Provider { provider: String, cause: String },
+}
+
+/// Source of secret values which are added to the root context,
+/// implement it to fetch credentials from external stores like Vault or KMS
+pub trait SecretProvider {
+    /// Provider name to use in logs and error messages
+    fn name(&self) -> &str;
+    /// Returns context variables with secret values
+    fn get_secrets(&self) -> Result<ContextMap, SecretError>;
+}
+
+/// Registers provider which secrets are added to the root context, it should be done before config is loaded,
+/// since secrets are substituted into the config; secrets of the later providers override earlier ones
+pub fn register(provider: impl SecretProvider + Send + Sync + 'static) {
+    PROVIDERS
+        .write()
+        .expect("unable to lock secret providers registry, looks like a BUG")
+        .push(Arc::new(provider));
+}
+
+/// Returns registered providers in order of registration
+pub(crate) fn registered() -> Vec<Arc<dyn SecretProvider + Send + Sync>> {
+    PROVIDERS
+        .read()
+        .expect("unable to lock secret providers registry, looks like a BUG")
+        .clone()
+}
+
+/// Loads secrets from files (like mounted Kubernetes/Docker secrets):
+/// content of the file pointed by `CTX_SECRET_FILE_<NAME>` variable
+/// becomes value of `CTX_SECRET_<NAME>` context variable
+#[derive(Debug, Default)]
+pub struct FileSecretProvider {
+    files: Vec<(String, PathBuf)>,
+}
+
+impl FileSecretProvider {
+    /// Collects secret files from the OS environment
+    pub fn from_env() -> Self {
+        Self::new(env::vars())
+    }
+
+    /// Collects secret files from the list of variables, all variables without prefix are ignored
+    pub fn new(vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        let mut files: Vec<(String, PathBuf)> = vars
+            .into_iter()
+            .filter_map(|(k, v)| {
+                k.strip_prefix(SECRET_FILE_ENV_PREFIX)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| (format!("{SECRET_CTX_PREFIX}{name}"), PathBuf::from(v)))
+            })
+            .collect();
+        files.sort();
+
+        Self { files }
+    }
+}
+
+impl SecretProvider for FileSecretProvider {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn get_secrets(&self) -> Result<ContextMap, SecretError> {
+        let mut secrets = ContextMap::new();
+        for (name, path) in &self.files {
+            debug!("Loading secret {name} from {}", path.display());
+            let value = fs::read_to_string(path).map_err(|cause| SecretError::ReadFile {
+                name: name.clone(),
+                path: path.clone(),
+                cause,
+            })?;
+            // Files are usually created with trailing newline which isn't a part of the secret
+            let value = value.trim_end_matches(['\r', '\n']).to_string();
+            secrets.insert(name.clone(), value);
+        }
+
+        Ok(secrets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env::temp_dir;
+
+    #[test]
+    fn file_secrets() {
+        let dir = temp_dir().join(format!("http-dragonfly-secrets-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("token"), "secret-token\n").unwrap();
+        fs::write(dir.join("password"), "p@ss\r\n").unwrap();
+
+        let provider = FileSecretProvider::new([
+            (
+                "CTX_SECRET_FILE_API_TOKEN".to_string(),
+                dir.join("token").to_string_lossy().to_string(),
+            ),
+            (
+                "CTX_SECRET_FILE_PASSWORD".to_string(),
+                dir.join("password").to_string_lossy().to_string(),
+            ),
+            ("CTX_SECRET_FILE_".to_string(), "ignored".to_string()),
+            ("HTTP_ENV_TOKEN".to_string(), "ignored".to_string()),
+        ]);
+        let secrets = provider.get_secrets().unwrap();
+
+        assert_eq!(secrets.len(), 2);
+        assert_eq!(secrets["CTX_SECRET_API_TOKEN"], "secret-token");
+        assert_eq!(secrets["CTX_SECRET_PASSWORD"], "p@ss");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_secret_file() {
+        let provider = FileSecretProvider::new([(
+            "CTX_SECRET_FILE_MISSING".to_string(),
+            "/non/existent/secret/file".to_string(),
+        )]);
+
+        let err = provider.get_secrets().unwrap_err();
+        assert!(
+            matches!(err, SecretError::ReadFile { ref name, .. } if name == "CTX_SECRET_MISSING")
+        );
+    }
+}
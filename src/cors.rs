@@ -0,0 +1,109 @@
+use crate::config::cors::CorsConfig;
+use http_body_util::Full;
+use hyper::{
+    body::Bytes,
+    header::{
+        HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+        ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+        ORIGIN, VARY,
+    },
+    HeaderMap, Method, Request, Response, StatusCode,
+};
+
+/// Returns origin of the cross-origin request
+pub fn origin<B>(req: &Request<B>) -> Option<HeaderValue> {
+    req.headers().get(ORIGIN).cloned()
+}
+
+/// Returns `true` if request is CORS preflight one
+pub fn is_preflight<B>(req: &Request<B>) -> bool {
+    req.method() == Method::OPTIONS
+        && req.headers().contains_key(ORIGIN)
+        && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+/// Answers preflight request: `204` with allowed methods and headers if origin and method are allowed,
+/// `403` without CORS headers otherwise
+pub fn preflight_response<B>(req: &Request<B>, cfg: &CorsConfig) -> Response<Full<Bytes>> {
+    let allowed = req
+        .headers()
+        .get(ACCESS_CONTROL_REQUEST_METHOD)
+        .and_then(|method| method.to_str().ok())
+        .is_some_and(|method| cfg.is_method_allowed(method));
+    let origin = origin(req).filter(|_| allowed);
+
+    let mut resp = Response::new(Full::default());
+    if !add_origin_headers(resp.headers_mut(), origin, cfg) {
+        *resp.status_mut() = StatusCode::FORBIDDEN;
+        return resp;
+    }
+
+    *resp.status_mut() = StatusCode::NO_CONTENT;
+    let headers = resp.headers_mut();
+    if let Ok(methods) = HeaderValue::from_str(&cfg.allowed_methods().join(", ")) {
+        headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods);
+    }
+    // Any header is allowed by mirroring requested ones, since `*` doesn't work with credentials
+    let allowed_headers = if cfg.is_any_header() {
+        req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS).cloned()
+    } else if !cfg.allowed_headers().is_empty() {
+        HeaderValue::from_str(&cfg.allowed_headers().join(", ")).ok()
+    } else {
+        None
+    };
+    if let Some(allowed_headers) = allowed_headers {
+        headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+        if cfg.is_any_header() {
+            headers.append(
+                VARY,
+                HeaderValue::from_static("access-control-request-headers"),
+            );
+        }
+    }
+    if let Some(max_age) = cfg.max_age() {
+        headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+    }
+
+    resp
+}
+
+/// Adds CORS headers to the response of the allowed origin
+pub fn add_headers<B>(resp: &mut Response<B>, origin: Option<HeaderValue>, cfg: &CorsConfig) {
+    let headers = resp.headers_mut();
+    if add_origin_headers(headers, origin, cfg) && !cfg.expose_headers().is_empty() {
+        if let Ok(expose) = HeaderValue::from_str(&cfg.expose_headers().join(", ")) {
+            headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, expose);
+        }
+    }
+}
+
+/// Adds `Access-Control-Allow-Origin` and related headers if origin is allowed, returns `true` in this case
+fn add_origin_headers(
+    headers: &mut HeaderMap,
+    origin: Option<HeaderValue>,
+    cfg: &CorsConfig,
+) -> bool {
+    let Some(origin) = origin.filter(|origin| {
+        origin
+            .to_str()
+            .is_ok_and(|origin| cfg.is_origin_allowed(origin))
+    }) else {
+        return false;
+    };
+
+    if cfg.is_any_origin() {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    } else {
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.append(VARY, HeaderValue::from_static("origin"));
+    }
+    if cfg.allow_credentials() {
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_CREDENTIALS,
+            HeaderValue::from_static("true"),
+        );
+    }
+
+    true
+}
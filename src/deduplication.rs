@@ -0,0 +1,197 @@
+use crate::{cache::CachedResponse, config::deduplication::DeduplicationConfig};
+use http_body_util::Full;
+use hyper::{body::Bytes, header::HeaderName, Request, Response};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+/// Header which is added to the responses of the duplicated requests
+pub const DEDUPLICATION_HEADER: &str = "x-splitter-deduplicated";
+
+/// Tracks idempotency keys of requests and their responses during the window
+#[derive(Debug)]
+pub struct Deduplicator {
+    header: HeaderName,
+    window: Duration,
+    max_entries: usize,
+    state: Mutex<DeduplicationState>,
+}
+
+/// Known idempotency keys ordered by insertion (or completion) time
+#[derive(Debug, Default)]
+struct DeduplicationState {
+    entries: HashMap<String, DeduplicationEntry>,
+    order: VecDeque<String>,
+}
+
+#[derive(Debug)]
+enum DeduplicationEntry {
+    /// Request is being processed, response will be sent to the channel
+    InFlight(watch::Receiver<Option<CachedResponse>>),
+    Completed {
+        response: CachedResponse,
+        expires_at: Instant,
+    },
+}
+
+pub enum Deduplication {
+    /// Request has no idempotency key, or previous request with the same key hasn't been completed
+    Skipped,
+    /// The first request with the key, its response should be passed to the guard
+    First(DeduplicationGuard),
+    /// Response of the previous request with the same key
+    Duplicate(Response<Full<Bytes>>),
+}
+
+/// Keeps idempotency key of the request which is being processed,
+/// key is released if guard is dropped without response
+pub struct DeduplicationGuard {
+    deduplicator: Arc<Deduplicator>,
+    key: String,
+    sender: watch::Sender<Option<CachedResponse>>,
+    completed: bool,
+}
+
+impl Deduplicator {
+    pub fn new(cfg: &DeduplicationConfig) -> Self {
+        Self {
+            header: cfg.header(),
+            window: cfg.window(),
+            max_entries: cfg.max_entries(),
+            state: Mutex::new(DeduplicationState::default()),
+        }
+    }
+
+    /// Verifies if request with the same idempotency key has been already received,
+    /// waits for the response if previous request is still in flight
+    pub async fn check<B>(self: &Arc<Self>, req: &Request<B>) -> Deduplication {
+        let Some(key) = req
+            .headers()
+            .get(&self.header)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+        else {
+            return Deduplication::Skipped;
+        };
+
+        let mut receiver = {
+            let mut state = self
+                .state
+                .lock()
+                .expect("unable to lock deduplication state, looks like a BUG");
+            match state.entries.get(&key) {
+                Some(DeduplicationEntry::Completed {
+                    response,
+                    expires_at,
+                }) if *expires_at > Instant::now() => {
+                    return Deduplication::Duplicate(
+                        response.clone().into_response(DEDUPLICATION_HEADER),
+                    );
+                }
+                Some(DeduplicationEntry::InFlight(receiver)) => receiver.clone(),
+                _ => {
+                    let (sender, receiver) = watch::channel(None);
+                    state.insert(key.clone(), DeduplicationEntry::InFlight(receiver));
+                    state.evict(self.max_entries);
+                    return Deduplication::First(DeduplicationGuard {
+                        deduplicator: self.clone(),
+                        key,
+                        sender,
+                        completed: false,
+                    });
+                }
+            }
+        };
+
+        let response = match receiver.wait_for(Option::is_some).await {
+            Ok(response) => response.clone(),
+            // Previous request has been completed without response to reuse
+            Err(_) => None,
+        };
+        match response {
+            Some(response) => {
+                Deduplication::Duplicate(response.into_response(DEDUPLICATION_HEADER))
+            }
+            None => Deduplication::Skipped,
+        }
+    }
+}
+
+impl DeduplicationGuard {
+    /// Stores response to return it to the following requests with the same key during the window
+    pub async fn complete(mut self, resp: &Response<Full<Bytes>>) {
+        let response = CachedResponse::from_response(resp).await;
+        {
+            let mut state = self
+                .deduplicator
+                .state
+                .lock()
+                .expect("unable to lock deduplication state, looks like a BUG");
+            state.insert(
+                self.key.clone(),
+                DeduplicationEntry::Completed {
+                    response: response.clone(),
+                    expires_at: Instant::now() + self.deduplicator.window,
+                },
+            );
+            state.evict(self.deduplicator.max_entries);
+        }
+        let _ = self.sender.send(Some(response));
+        self.completed = true;
+    }
+}
+
+impl Drop for DeduplicationGuard {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let mut state = self
+            .deduplicator
+            .state
+            .lock()
+            .expect("unable to lock deduplication state, looks like a BUG");
+        if matches!(
+            state.entries.get(&self.key),
+            Some(DeduplicationEntry::InFlight(_))
+        ) {
+            state.remove(&self.key);
+        }
+    }
+}
+
+impl DeduplicationState {
+    fn insert(&mut self, key: String, entry: DeduplicationEntry) {
+        if self.entries.insert(key.clone(), entry).is_some() {
+            self.order.retain(|k| k != &key);
+        }
+        self.order.push_back(key);
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.order.retain(|k| k != key);
+        }
+    }
+
+    /// Removes expired entries and the oldest ones above the limit
+    fn evict(&mut self, max_entries: usize) {
+        let now = Instant::now();
+        while let Some(key) = self.order.front() {
+            let expired = match self.entries.get(key) {
+                Some(DeduplicationEntry::Completed { expires_at, .. }) => *expires_at <= now,
+                Some(DeduplicationEntry::InFlight(_)) => false,
+                None => true,
+            };
+            if !expired && self.order.len() <= max_entries {
+                break;
+            }
+            if let Some(key) = self.order.pop_front() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
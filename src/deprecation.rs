@@ -0,0 +1,176 @@
+use crate::{
+    config::{deprecation::Sunset, target::TargetConfig},
+    metrics::{self, MetricsSource},
+};
+use hyper::{header::HeaderValue, HeaderMap};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Requests to the deprecated target are reported not more often than once per interval
+const DEPRECATION_WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Headers which mark responses of the deprecated targets
+pub const DEPRECATION_HEADER: &str = "deprecation";
+pub const SUNSET_HEADER: &str = "sunset";
+
+/// Counts requests to the deprecated target and reminds about it with rate-limited warnings
+#[derive(Debug)]
+pub struct DeprecationTracker {
+    listener_id: String,
+    target_id: String,
+    sunset: Option<Sunset>,
+    headers: bool,
+    state: Mutex<DeprecationState>,
+}
+
+#[derive(Debug, Default)]
+struct DeprecationState {
+    total: u64,
+    /// Requests since the last warning
+    unreported: u64,
+    last_warning: Option<Instant>,
+}
+
+impl DeprecationTracker {
+    /// Creates tracker of the target, or `None` if the target isn't deprecated
+    pub fn new(target: &TargetConfig, listener_id: String) -> Option<Self> {
+        if !target.is_deprecated() {
+            return None;
+        }
+        let tracker = Self {
+            listener_id,
+            target_id: target.id(),
+            sunset: target.sunset(),
+            headers: target.deprecation_headers(),
+            state: Mutex::new(DeprecationState::default()),
+        };
+        warn!(
+            listener = tracker.listener_id,
+            target = tracker.target_id,
+            sunset = tracker.sunset.map(|sunset| sunset.http_date()),
+            "target is deprecated"
+        );
+
+        Some(tracker)
+    }
+
+    /// Records request to the target and warns if the previous warning is old enough
+    pub fn record(&self) {
+        let now = Instant::now();
+        let mut state = self
+            .state
+            .lock()
+            .expect("unable to lock deprecation state, looks like a BUG");
+        state.total += 1;
+        state.unreported += 1;
+
+        if state
+            .last_warning
+            .is_none_or(|last| now.duration_since(last) >= DEPRECATION_WARNING_INTERVAL)
+        {
+            self.warn(state.unreported);
+            state.unreported = 0;
+            state.last_warning = Some(now);
+        }
+    }
+
+    /// Adds `Deprecation` and `Sunset` headers to the target's response if it's configured
+    pub fn add_headers(&self, headers: &mut HeaderMap) {
+        if !self.headers {
+            return;
+        }
+        headers.insert(DEPRECATION_HEADER, HeaderValue::from_static("true"));
+        if let Some(sunset) = self.sunset {
+            if let Ok(value) = HeaderValue::from_str(&sunset.http_date()) {
+                headers.insert(SUNSET_HEADER, value);
+            }
+        }
+    }
+
+    fn warn(&self, requests: u64) {
+        let sunset = self.sunset.map(|sunset| sunset.http_date());
+        if self.sunset.is_some_and(|sunset| sunset.is_passed()) {
+            warn!(
+                listener = self.listener_id,
+                target = self.target_id,
+                sunset,
+                requests,
+                "deprecated target is still in use after its sunset date"
+            );
+        } else {
+            warn!(
+                listener = self.listener_id,
+                target = self.target_id,
+                sunset,
+                requests,
+                "deprecated target is in use"
+            );
+        }
+    }
+}
+
+impl MetricsSource for DeprecationTracker {
+    fn write_metrics(&self, out: &mut String) {
+        let total = self
+            .state
+            .lock()
+            .expect("unable to lock deprecation state, looks like a BUG")
+            .total;
+        let sunset = self
+            .sunset
+            .map(|sunset| sunset.http_date())
+            .unwrap_or_default();
+        metrics::gauge(
+            out,
+            "deprecated_target_requests_total",
+            [
+                ("listener", self.listener_id.as_str()),
+                ("target", self.target_id.as_str()),
+                ("sunset", sunset.as_str()),
+            ],
+            total as f64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(cfg: &str) -> Option<DeprecationTracker> {
+        let target: TargetConfig = serde_json::from_str(cfg).unwrap();
+        DeprecationTracker::new(&target, "listener".into())
+    }
+
+    #[test]
+    fn deprecated_targets() {
+        assert!(tracker(r#"{"id": "current", "url": "http://localhost/"}"#).is_none());
+
+        let tracker = tracker(
+            r#"{"id": "old", "url": "http://localhost/", "sunset": "2025-12-31", "deprecation_headers": true}"#,
+        )
+        .unwrap();
+        for _ in 0..3 {
+            tracker.record();
+        }
+        {
+            let state = tracker.state.lock().unwrap();
+            assert_eq!((state.total, state.unreported), (3, 2));
+        }
+
+        let mut headers = HeaderMap::new();
+        tracker.add_headers(&mut headers);
+        assert_eq!(headers[DEPRECATION_HEADER], "true");
+        assert_eq!(headers[SUNSET_HEADER], "Wed, 31 Dec 2025 00:00:00 GMT");
+
+        let mut out = String::new();
+        tracker.write_metrics(&mut out);
+        assert_eq!(
+            out,
+            "http_dragonfly_deprecated_target_requests_total{listener=\"listener\",target=\"old\",sunset=\"Wed, 31 Dec 2025 00:00:00 GMT\"} 3\n"
+        );
+    }
+}
@@ -0,0 +1,673 @@
+use crate::{
+    config::{
+        discovery::{
+            BalancingPolicy, ConsulDiscoveryConfig, DiscoveryConfig, DiscoverySource,
+            KubernetesDiscoveryConfig,
+        },
+        listener::{TlsConfig, TlsVerifyConfig},
+        target::TargetConfig,
+    },
+    streaming::RequestBody,
+};
+use figment::{
+    providers::{Format, Yaml},
+    Figment,
+};
+use hickory_resolver::{error::ResolveError, TokioAsyncResolver};
+use http_body_util::BodyExt;
+use hyper::{
+    header::AUTHORIZATION,
+    http::{uri::Authority, HeaderValue},
+    Request, StatusCode, Uri,
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env, io,
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        LazyLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+const SERVICE_ACCOUNT_PATH: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+const KUBERNETES_TIMEOUT: Duration = Duration::from_secs(10);
+const CONSUL_TIMEOUT: Duration = Duration::from_secs(10);
+const CONSUL_TOKEN_HEADER: &str = "x-consul-token";
+
+#[derive(thiserror::Error, Debug)]
+pub enum DiscoveryError {
+    #[error("unable to resolve DNS SRV record: {0}")]
+    Dns(#[from] ResolveError),
+    #[error("unable to query Kubernetes API: {0}")]
+    Kubernetes(String),
+    #[error("unable to query Consul API: {0}")]
+    Consul(String),
+    #[error("unable to load instances from file: {0}")]
+    File(String),
+    #[error("unable to read service account: {0}")]
+    ServiceAccount(#[from] io::Error),
+    #[error("no live instances discovered")]
+    NoInstances,
+    #[error("invalid address of the instance `{0}`")]
+    InvalidAddress(String),
+}
+
+/// Instances of the single target, the list is refreshed on demand when it becomes outdated
+#[derive(Debug)]
+pub struct TargetDiscovery {
+    target_id: String,
+    cfg: DiscoveryConfig,
+    state: RwLock<DiscoveryState>,
+    /// Serializes refreshes, so only one request discovers instances
+    refreshing: Mutex<()>,
+    next: AtomicUsize,
+    /// Load and health of the instances by address, it's shared by all requests to the target
+    load: std::sync::Mutex<HashMap<String, InstanceLoad>>,
+}
+
+#[derive(Debug, Default)]
+struct DiscoveryState {
+    /// Addresses (`host:port`) of the instances
+    instances: Vec<String>,
+    refreshed_at: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct InstanceLoad {
+    /// Number of requests waiting for response headers
+    in_flight: usize,
+    failed_at: Option<Instant>,
+}
+
+/// Selected instance of the request, it's released when the lease is dropped
+pub struct InstanceLease<'a> {
+    discovery: &'a TargetDiscovery,
+    instance: String,
+}
+
+impl TargetDiscovery {
+    pub fn new(target: &TargetConfig, cfg: &DiscoveryConfig) -> Self {
+        Self {
+            target_id: target.id(),
+            cfg: cfg.clone(),
+            state: RwLock::new(DiscoveryState::default()),
+            refreshing: Mutex::new(()),
+            next: AtomicUsize::new(0),
+            load: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces host and port of the request's URI with the address of the selected instance,
+    /// outcome of the request should be reported to the returned lease
+    pub async fn route<B>(
+        &self,
+        mut req: Request<B>,
+    ) -> Result<(Request<B>, InstanceLease<'_>), DiscoveryError> {
+        let instance = self.select().await?;
+        debug!(target = self.target_id, instance, "route request");
+        *req.uri_mut() = with_authority(req.uri(), &instance)?;
+        self.lock_load()
+            .entry(instance.clone())
+            .or_default()
+            .in_flight += 1;
+        Ok((
+            req,
+            InstanceLease {
+                discovery: self,
+                instance,
+            },
+        ))
+    }
+
+    /// Selects the next instance according to the balancing policy,
+    /// instances in failure cooldown are skipped unless all instances are failed
+    async fn select(&self) -> Result<String, DiscoveryError> {
+        if self.is_outdated() {
+            self.refresh().await;
+        }
+
+        let state = self
+            .state
+            .read()
+            .expect("unable to lock discovery state, looks like a BUG");
+        if state.instances.is_empty() {
+            return Err(DiscoveryError::NoInstances);
+        }
+        let load = self.lock_load();
+        let in_cooldown =
+            |instance: &String| match (self.cfg.failure_cooldown(), load.get(instance)) {
+                (
+                    Some(cooldown),
+                    Some(InstanceLoad {
+                        failed_at: Some(at),
+                        ..
+                    }),
+                ) => at.elapsed() < cooldown,
+                _ => false,
+            };
+        let mut candidates: Vec<&String> =
+            state.instances.iter().filter(|i| !in_cooldown(i)).collect();
+        if candidates.is_empty() {
+            candidates = state.instances.iter().collect();
+        }
+        let count = candidates.len();
+        let index = match self.cfg.policy() {
+            BalancingPolicy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % count,
+            BalancingPolicy::Random => fastrand::usize(..count),
+            BalancingPolicy::LeastConnections => {
+                let in_flight = |instance: &String| load.get(instance).map_or(0, |l| l.in_flight);
+                let least = candidates
+                    .iter()
+                    .map(|i| in_flight(i))
+                    .min()
+                    .unwrap_or_default();
+                candidates.retain(|i| in_flight(i) == least);
+                self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()
+            }
+        };
+        Ok(candidates[index].clone())
+    }
+
+    fn lock_load(&self) -> std::sync::MutexGuard<'_, HashMap<String, InstanceLoad>> {
+        self.load
+            .lock()
+            .expect("unable to lock instances load, looks like a BUG")
+    }
+
+    fn is_outdated(&self) -> bool {
+        let state = self
+            .state
+            .read()
+            .expect("unable to lock discovery state, looks like a BUG");
+        state
+            .refreshed_at
+            .is_none_or(|at| at.elapsed() >= self.cfg.refresh_interval())
+    }
+
+    /// Discovers instances, previous list is kept if discovery fails
+    async fn refresh(&self) {
+        let _guard = self.refreshing.lock().await;
+        // Other request could refresh it while we were waiting for the lock
+        if !self.is_outdated() {
+            return;
+        }
+
+        let discovered = match self.cfg.source() {
+            DiscoverySource::DnsSrv(name) => resolve_srv(name).await,
+            DiscoverySource::Kubernetes(cfg) => kubernetes_endpoints(cfg).await,
+            DiscoverySource::Consul(cfg) => consul_instances(cfg).await,
+            DiscoverySource::File(path) => file_instances(path).await,
+            DiscoverySource::Static(instances) => Ok(instances.to_vec()),
+        };
+        let mut state = self
+            .state
+            .write()
+            .expect("unable to lock discovery state, looks like a BUG");
+        match discovered {
+            Ok(instances) => {
+                for instance in instances.iter().filter(|i| !state.instances.contains(i)) {
+                    info!(target = self.target_id, instance, "instance added");
+                }
+                for instance in state.instances.iter().filter(|i| !instances.contains(i)) {
+                    info!(target = self.target_id, instance, "instance removed");
+                }
+                self.lock_load()
+                    .retain(|instance, load| load.in_flight > 0 || instances.contains(instance));
+                state.instances = instances;
+            }
+            Err(e) => warn!(target = self.target_id, error = %e, "unable to discover instances"),
+        }
+        state.refreshed_at = Some(Instant::now());
+    }
+}
+
+impl InstanceLease<'_> {
+    /// Reports outcome of the request, failed instance isn't selected during the failure cooldown
+    pub fn report(self, failed: bool) {
+        if failed && self.discovery.cfg.failure_cooldown().is_some() {
+            warn!(
+                target = self.discovery.target_id,
+                instance = self.instance,
+                "instance failed"
+            );
+            self.discovery
+                .lock_load()
+                .entry(self.instance.clone())
+                .or_default()
+                .failed_at = Some(Instant::now());
+        }
+    }
+}
+
+impl Drop for InstanceLease<'_> {
+    fn drop(&mut self) {
+        if let Some(load) = self.discovery.lock_load().get_mut(&self.instance) {
+            load.in_flight = load.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+/// Returns the same URI with another host and port
+fn with_authority(uri: &Uri, authority: &str) -> Result<Uri, DiscoveryError> {
+    let mut parts = uri.clone().into_parts();
+    let invalid = |_| DiscoveryError::InvalidAddress(authority.into());
+    parts.authority = Some(authority.parse::<Authority>().map_err(invalid)?);
+    Uri::from_parts(parts).map_err(|e| DiscoveryError::InvalidAddress(e.to_string()))
+}
+
+/// Formats address of the instance, IPv6 addresses are enclosed in brackets
+fn instance_address(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => SocketAddr::new(ip, port).to_string(),
+        Err(_) => format!("{host}:{port}"),
+    }
+}
+
+/// Resolves instances from SRV records with the highest priority (the lowest value)
+async fn resolve_srv(name: &str) -> Result<Vec<String>, DiscoveryError> {
+    static RESOLVER: LazyLock<Result<TokioAsyncResolver, ResolveError>> =
+        LazyLock::new(TokioAsyncResolver::tokio_from_system_conf);
+
+    let resolver = RESOLVER.as_ref().map_err(Clone::clone)?;
+    let lookup = resolver.srv_lookup(name).await?;
+    let priority = lookup.iter().map(|srv| srv.priority()).min();
+    let instances = lookup
+        .iter()
+        .filter(|srv| Some(srv.priority()) == priority)
+        .map(|srv| {
+            let host = srv.target().to_utf8();
+            instance_address(host.trim_end_matches('.'), srv.port())
+        })
+        .collect();
+
+    Ok(instances)
+}
+
+#[derive(Deserialize, Debug)]
+struct Endpoints {
+    #[serde(default)]
+    subsets: Vec<EndpointSubset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EndpointSubset {
+    #[serde(default)]
+    addresses: Vec<EndpointAddress>,
+    #[serde(default)]
+    ports: Vec<EndpointPort>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EndpointAddress {
+    ip: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct EndpointPort {
+    name: Option<String>,
+    port: u16,
+}
+
+impl Endpoints {
+    /// Returns addresses of the ready endpoints with the specified (or the first) port
+    fn instances(&self, port_name: Option<&str>) -> Vec<String> {
+        self.subsets
+            .iter()
+            .flat_map(|subset| {
+                let port = match port_name {
+                    Some(name) => subset
+                        .ports
+                        .iter()
+                        .find(|p| p.name.as_deref() == Some(name)),
+                    None => subset.ports.first(),
+                };
+                port.into_iter().flat_map(|port| {
+                    subset
+                        .addresses
+                        .iter()
+                        .map(|address| instance_address(&address.ip, port.port))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Queries Endpoints of the service using in-cluster service account
+async fn kubernetes_endpoints(
+    cfg: &KubernetesDiscoveryConfig,
+) -> Result<Vec<String>, DiscoveryError> {
+    let host = env::var("KUBERNETES_SERVICE_HOST")
+        .map_err(|_| DiscoveryError::Kubernetes("not running in the cluster".into()))?;
+    let port = env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".into());
+    let namespace = match cfg.namespace() {
+        Some(namespace) => namespace.to_string(),
+        None => tokio::fs::read_to_string(format!("{SERVICE_ACCOUNT_PATH}/namespace"))
+            .await?
+            .trim()
+            .to_string(),
+    };
+    // Token is read each time since it's rotated
+    let token = tokio::fs::read_to_string(format!("{SERVICE_ACCOUNT_PATH}/token")).await?;
+
+    let host = instance_address(&host, port.parse().unwrap_or(443));
+    let uri = format!(
+        "https://{host}/api/v1/namespaces/{namespace}/endpoints/{}",
+        cfg.service()
+    );
+    let token = HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+        .map_err(|e| DiscoveryError::Kubernetes(e.to_string()))?;
+    let req = Request::get(uri)
+        .header(AUTHORIZATION, token)
+        .body(RequestBody::default())
+        .map_err(|e| DiscoveryError::Kubernetes(e.to_string()))?;
+
+    let tls_config = TlsConfig {
+        verify: TlsVerifyConfig::Yes,
+        ca: Some(format!("{SERVICE_ACCOUNT_PATH}/ca.crt")),
+        ..Default::default()
+    };
+    let client = TargetConfig::tls_client(&tls_config, KUBERNETES_TIMEOUT);
+    let resp = tokio::time::timeout(KUBERNETES_TIMEOUT, client.request(req))
+        .await
+        .map_err(|_| DiscoveryError::Kubernetes("request timed out".into()))?
+        .map_err(|e| DiscoveryError::Kubernetes(e.to_string()))?;
+    let status = resp.status();
+    let body = resp
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| DiscoveryError::Kubernetes(e.to_string()))?
+        .to_bytes();
+    if status != StatusCode::OK {
+        return Err(DiscoveryError::Kubernetes(format!(
+            "unexpected status {status}: {}",
+            String::from_utf8_lossy(&body)
+        )));
+    }
+
+    let endpoints: Endpoints =
+        serde_json::from_slice(&body).map_err(|e| DiscoveryError::Kubernetes(e.to_string()))?;
+    Ok(endpoints.instances(cfg.port()))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulServiceEntry {
+    node: ConsulNode,
+    service: ConsulService,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulNode {
+    address: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulService {
+    #[serde(default)]
+    address: String,
+    port: u16,
+}
+
+impl ConsulServiceEntry {
+    /// Returns address of the service instance, node's address is used if service's one is empty
+    fn instance(&self) -> String {
+        let host = if self.service.address.is_empty() {
+            &self.node.address
+        } else {
+            &self.service.address
+        };
+        instance_address(host, self.service.port)
+    }
+}
+
+/// Queries healthy instances of the service from the Consul agent
+async fn consul_instances(cfg: &ConsulDiscoveryConfig) -> Result<Vec<String>, DiscoveryError> {
+    let mut uri = format!(
+        "{}/v1/health/service/{}?passing=true",
+        cfg.address(),
+        cfg.service()
+    );
+    if let Some(tag) = cfg.tag() {
+        uri.push_str(&format!("&tag={tag}"));
+    }
+    if let Some(datacenter) = cfg.datacenter() {
+        uri.push_str(&format!("&dc={datacenter}"));
+    }
+    let mut req = Request::get(uri);
+    if let Some(token) = cfg.token() {
+        req = req.header(CONSUL_TOKEN_HEADER, token);
+    }
+    let req = req
+        .body(RequestBody::default())
+        .map_err(|e| DiscoveryError::Consul(e.to_string()))?;
+
+    let client = TargetConfig::tls_client(&TlsConfig::default(), CONSUL_TIMEOUT);
+    let resp = tokio::time::timeout(CONSUL_TIMEOUT, client.request(req))
+        .await
+        .map_err(|_| DiscoveryError::Consul("request timed out".into()))?
+        .map_err(|e| DiscoveryError::Consul(e.to_string()))?;
+    let status = resp.status();
+    let body = resp
+        .into_body()
+        .collect()
+        .await
+        .map_err(|e| DiscoveryError::Consul(e.to_string()))?
+        .to_bytes();
+    if status != StatusCode::OK {
+        return Err(DiscoveryError::Consul(format!(
+            "unexpected status {status}: {}",
+            String::from_utf8_lossy(&body)
+        )));
+    }
+
+    let entries: Vec<ConsulServiceEntry> =
+        serde_json::from_slice(&body).map_err(|e| DiscoveryError::Consul(e.to_string()))?;
+    Ok(entries.iter().map(ConsulServiceEntry::instance).collect())
+}
+
+/// Content of the file with instances, like `instances: ["10.0.0.1:8080", "backend-2:8080"]`
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct InstancesFile {
+    instances: Vec<String>,
+}
+
+/// Loads instances from JSON or YAML file, it's re-read on each refresh to pick up changes
+async fn file_instances(path: &str) -> Result<Vec<String>, DiscoveryError> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| DiscoveryError::File(format!("{path}: {e}")))?;
+    parse_instances_file(&content).map_err(|e| DiscoveryError::File(format!("{path}: {e}")))
+}
+
+fn parse_instances_file(content: &str) -> Result<Vec<String>, DiscoveryError> {
+    let file: InstancesFile = Figment::from(Yaml::string(content))
+        .extract()
+        .map_err(|e| DiscoveryError::File(e.to_string()))?;
+    for instance in &file.instances {
+        instance
+            .parse::<Authority>()
+            .map_err(|_| DiscoveryError::InvalidAddress(instance.clone()))?;
+    }
+
+    Ok(file.instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINTS: &str = r#"{
+        "kind": "Endpoints",
+        "subsets": [
+            {
+                "addresses": [{"ip": "10.0.0.1"}, {"ip": "10.0.0.2"}],
+                "notReadyAddresses": [{"ip": "10.0.0.3"}],
+                "ports": [{"name": "grpc", "port": 9090}, {"name": "http", "port": 8080}]
+            },
+            {
+                "addresses": [{"ip": "fd00::1"}],
+                "ports": [{"name": "http", "port": 8081}]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn endpoints_instances() {
+        let endpoints: Endpoints = serde_json::from_str(ENDPOINTS).unwrap();
+        assert_eq!(
+            endpoints.instances(Some("http")),
+            ["10.0.0.1:8080", "10.0.0.2:8080", "[fd00::1]:8081"]
+        );
+        assert_eq!(
+            endpoints.instances(None),
+            ["10.0.0.1:9090", "10.0.0.2:9090", "[fd00::1]:8081"]
+        );
+        assert!(endpoints.instances(Some("unknown")).is_empty());
+
+        let endpoints: Endpoints = serde_json::from_str(r#"{"kind": "Endpoints"}"#).unwrap();
+        assert!(endpoints.instances(None).is_empty());
+    }
+
+    #[test]
+    fn replace_authority() {
+        let uri: Uri = "https://backend.svc/some/path?query=1".parse().unwrap();
+        assert_eq!(
+            with_authority(&uri, "10.0.0.1:8080").unwrap().to_string(),
+            "https://10.0.0.1:8080/some/path?query=1"
+        );
+        assert_eq!(
+            with_authority(&uri, "[fd00::1]:8080").unwrap().to_string(),
+            "https://[fd00::1]:8080/some/path?query=1"
+        );
+        assert_eq!(
+            instance_address("backend-1.example.com", 8080),
+            "backend-1.example.com:8080"
+        );
+    }
+
+    fn test_discovery(options: &str, instances: &[&str]) -> TargetDiscovery {
+        let cfg: DiscoveryConfig = serde_json::from_str(&format!(
+            r#"{{"dns_srv": "_http._tcp.example.com", {options}, "refresh_interval": "1h"}}"#
+        ))
+        .unwrap();
+        let discovery = TargetDiscovery {
+            target_id: "test".into(),
+            cfg,
+            state: RwLock::default(),
+            refreshing: Mutex::new(()),
+            next: AtomicUsize::new(0),
+            load: std::sync::Mutex::new(HashMap::new()),
+        };
+        {
+            let mut state = discovery.state.write().unwrap();
+            state.instances = instances.iter().map(ToString::to_string).collect();
+            state.refreshed_at = Some(Instant::now());
+        }
+        discovery
+    }
+
+    #[tokio::test]
+    async fn round_robin_selection() {
+        let discovery = test_discovery(r#""policy": "round_robin""#, &["a:1", "b:2", "c:3"]);
+        let mut selected = vec![];
+        for _ in 0..6 {
+            selected.push(discovery.select().await.unwrap());
+        }
+        assert_eq!(selected, ["a:1", "b:2", "c:3", "a:1", "b:2", "c:3"]);
+    }
+
+    #[tokio::test]
+    async fn random_selection() {
+        let discovery = test_discovery(r#""policy": "random""#, &["a:1", "b:2"]);
+        for _ in 0..10 {
+            let selected = discovery.select().await.unwrap();
+            assert!(selected == "a:1" || selected == "b:2");
+        }
+
+        let discovery = test_discovery(r#""policy": "random""#, &[]);
+        assert!(matches!(
+            discovery.select().await,
+            Err(DiscoveryError::NoInstances)
+        ));
+    }
+
+    fn test_request() -> Request<()> {
+        Request::get("http://backend/").body(()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn least_connections_selection() {
+        let discovery = test_discovery(r#""policy": "least_connections""#, &["a:1", "b:2"]);
+        let (_, first) = discovery.route(test_request()).await.unwrap();
+        let (_, second) = discovery.route(test_request()).await.unwrap();
+        assert_ne!(first.instance, second.instance);
+
+        // Instance with completed request has the least connections
+        let released = second.instance.clone();
+        second.report(false);
+        let (req, _) = discovery.route(test_request()).await.unwrap();
+        assert_eq!(req.uri().authority().unwrap().as_str(), released);
+    }
+
+    #[tokio::test]
+    async fn failure_cooldown() {
+        let discovery = test_discovery(
+            r#""policy": "round_robin", "failure_cooldown": "1h""#,
+            &["a:1", "b:2"],
+        );
+        let (_, lease) = discovery.route(test_request()).await.unwrap();
+        assert_eq!(lease.instance, "a:1");
+        lease.report(true);
+        for _ in 0..3 {
+            assert_eq!(discovery.select().await.unwrap(), "b:2");
+        }
+
+        // All instances are used if all of them are failed
+        let (_, lease) = discovery.route(test_request()).await.unwrap();
+        lease.report(true);
+        let selected: Vec<String> = [discovery.select().await, discovery.select().await]
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert!(selected.contains(&"a:1".into()) && selected.contains(&"b:2".into()));
+    }
+
+    #[test]
+    fn consul_service_instances() {
+        let entries: Vec<ConsulServiceEntry> = serde_json::from_str(
+            r#"[
+                {"Node": {"Node": "node-1", "Address": "10.0.0.1"}, "Service": {"Address": "", "Port": 8080}},
+                {"Node": {"Node": "node-2", "Address": "10.0.0.2"}, "Service": {"Address": "10.1.0.2", "Port": 8081}}
+            ]"#,
+        )
+        .unwrap();
+        let instances: Vec<String> = entries.iter().map(ConsulServiceEntry::instance).collect();
+        assert_eq!(instances, ["10.0.0.1:8080", "10.1.0.2:8081"]);
+    }
+
+    #[test]
+    fn instances_file() {
+        assert_eq!(
+            parse_instances_file("instances:\n  - 10.0.0.1:8080\n  - backend-2:8080\n").unwrap(),
+            ["10.0.0.1:8080", "backend-2:8080"]
+        );
+        assert_eq!(
+            parse_instances_file(r#"{"instances": ["[fd00::1]:8080"]}"#).unwrap(),
+            ["[fd00::1]:8080"]
+        );
+        assert!(matches!(
+            parse_instances_file("instances: [\"bad address\"]"),
+            Err(DiscoveryError::InvalidAddress(_))
+        ));
+        assert!(parse_instances_file("hosts: []").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use crate::config::target::{BindAddress, IpPreference};
+use hyper_util::client::legacy::connect::dns::{GaiResolver, Name};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+    vec,
+};
+use tower_service::Service;
+use tracing::debug;
+
+/// System resolver which puts addresses of the preferred IP family first,
+/// so connector tries them first and falls back to another family after the Happy Eyeballs delay;
+/// failed resolutions are remembered for negative TTL if it's defined;
+/// if source address is bound, addresses of the families without bound address are dropped
+#[derive(Clone, Debug)]
+pub struct PreferredFamilyResolver {
+    inner: GaiResolver,
+    preference: IpPreference,
+    negative_cache: Option<Arc<NegativeCache>>,
+    /// Whether IPv4 and IPv6 addresses are allowed
+    families: Option<(bool, bool)>,
+}
+
+/// Host names which have failed to resolve, with expiration time of each failure
+#[derive(Debug)]
+struct NegativeCache {
+    ttl: Duration,
+    failures: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+/// Failure of the host name resolution, it's distinguished from other connection errors by [`is_dns_failure`]
+#[derive(Debug)]
+pub struct DnsFailure {
+    host: String,
+    cause: String,
+}
+
+impl PreferredFamilyResolver {
+    pub fn new(preference: IpPreference, negative_ttl: Option<Duration>) -> Self {
+        Self {
+            inner: GaiResolver::new(),
+            preference,
+            negative_cache: negative_ttl.map(|ttl| {
+                Arc::new(NegativeCache {
+                    ttl,
+                    failures: Mutex::new(HashMap::new()),
+                })
+            }),
+            families: None,
+        }
+    }
+
+    /// Restricts addresses to the families of the bound source address,
+    /// so connections never egress from the system's default address
+    pub fn with_bind_address(mut self, bind_address: Option<&BindAddress>) -> Self {
+        self.families = bind_address.map(|bind_address| {
+            let (ipv4, ipv6) = bind_address.addresses();
+            (ipv4.is_some(), ipv6.is_some())
+        });
+        self
+    }
+}
+
+impl NegativeCache {
+    /// Returns cause of the remembered failure if it hasn't expired yet
+    fn get(&self, host: &str) -> Option<String> {
+        let failures = self.lock();
+        failures
+            .get(host)
+            .filter(|(expires, _)| *expires > Instant::now())
+            .map(|(_, cause)| cause.clone())
+    }
+
+    fn insert(&self, host: &str, cause: &str) {
+        let now = Instant::now();
+        let mut failures = self.lock();
+        failures.retain(|_, (expires, _)| *expires > now);
+        failures.insert(host.into(), (now + self.ttl, cause.into()));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, (Instant, String)>> {
+        self.failures
+            .lock()
+            .expect("unable to lock DNS negative cache, looks like a BUG")
+    }
+}
+
+impl DnsFailure {
+    fn error(host: &str, kind: io::ErrorKind, cause: String) -> io::Error {
+        io::Error::new(
+            kind,
+            Self {
+                host: host.into(),
+                cause,
+            },
+        )
+    }
+}
+
+impl Display for DnsFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unable to resolve `{}`: {}", self.host, self.cause)
+    }
+}
+
+impl Error for DnsFailure {}
+
+/// Returns `true` if error is caused by failed resolution of the host name
+pub fn is_dns_failure(e: &(dyn Error + 'static)) -> bool {
+    let mut source = Some(e);
+    while let Some(err) = source {
+        if err
+            .downcast_ref::<io::Error>()
+            .and_then(io::Error::get_ref)
+            .is_some_and(|inner| inner.is::<DnsFailure>())
+        {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+impl Service<Name> for PreferredFamilyResolver {
+    type Response = vec::IntoIter<SocketAddr>;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let preference = self.preference;
+        let families = self.families;
+        let negative_cache = self.negative_cache.clone();
+        let host = name.as_str().to_string();
+        if let Some(cause) = negative_cache.as_ref().and_then(|cache| cache.get(&host)) {
+            debug!(host, "failed resolution is cached");
+            return Box::pin(
+                async move { Err(DnsFailure::error(&host, io::ErrorKind::Other, cause)) },
+            );
+        }
+        let resolving = self.inner.call(name);
+        Box::pin(async move {
+            let mut addrs: Vec<SocketAddr> = match resolving.await {
+                Ok(addrs) => addrs.collect(),
+                Err(e) => {
+                    let cause = e.to_string();
+                    if let Some(cache) = &negative_cache {
+                        cache.insert(&host, &cause);
+                    }
+                    return Err(DnsFailure::error(&host, e.kind(), cause));
+                }
+            };
+            sort_addrs(&mut addrs, preference);
+            if let Some((ipv4, ipv6)) = families {
+                addrs.retain(|addr| if addr.is_ipv4() { ipv4 } else { ipv6 });
+                if addrs.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrNotAvailable,
+                        format!("`{host}` has no addresses of the bound source address family"),
+                    ));
+                }
+            }
+            Ok(addrs.into_iter())
+        })
+    }
+}
+
+/// Moves addresses of the preferred family to the head, order within each family is kept
+fn sort_addrs(addrs: &mut [SocketAddr], preference: IpPreference) {
+    match preference {
+        IpPreference::Auto => {}
+        IpPreference::V4 => addrs.sort_by_key(|addr| !addr.is_ipv4()),
+        IpPreference::V6 => addrs.sort_by_key(|addr| !addr.is_ipv6()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs() -> Vec<SocketAddr> {
+        ["[::1]:80", "127.0.0.1:80", "[::2]:80", "127.0.0.2:80"]
+            .into_iter()
+            .map(|addr| addr.parse().unwrap())
+            .collect()
+    }
+
+    fn sorted(preference: IpPreference) -> Vec<String> {
+        let mut addrs = addrs();
+        sort_addrs(&mut addrs, preference);
+        addrs.iter().map(ToString::to_string).collect()
+    }
+
+    #[tokio::test]
+    async fn negative_cache() {
+        let mut resolver =
+            PreferredFamilyResolver::new(IpPreference::Auto, Some(Duration::from_secs(60)));
+        let name: Name = "nonexistent-host.invalid".parse().unwrap();
+
+        let e = resolver.call(name.clone()).await.unwrap_err();
+        assert!(is_dns_failure(&e));
+        let cache = resolver.negative_cache.as_ref().unwrap();
+        assert!(cache.get("nonexistent-host.invalid").is_some());
+
+        // Cached failure is returned without resolving
+        let e = resolver.call(name).await.unwrap_err();
+        assert!(is_dns_failure(&e));
+        assert!(e
+            .to_string()
+            .starts_with("unable to resolve `nonexistent-host.invalid`"));
+        assert!(!is_dns_failure(&io::Error::other("connection refused")));
+    }
+
+    #[test]
+    fn sort_by_preference() {
+        assert_eq!(
+            sorted(IpPreference::Auto),
+            ["[::1]:80", "127.0.0.1:80", "[::2]:80", "127.0.0.2:80"]
+        );
+        assert_eq!(
+            sorted(IpPreference::V4),
+            ["127.0.0.1:80", "127.0.0.2:80", "[::1]:80", "[::2]:80"]
+        );
+        assert_eq!(
+            sorted(IpPreference::V6),
+            ["[::1]:80", "[::2]:80", "127.0.0.1:80", "127.0.0.2:80"]
+        );
+    }
+}
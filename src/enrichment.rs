@@ -0,0 +1,471 @@
+use crate::{
+    buffer_pool::{self, Collected},
+    config::{
+        enrichment::{EnrichmentConfig, EnrichmentSource, RedisServer, ENRICHMENT_KEY_PLACEHOLDER},
+        listener::TlsConfig,
+        target::{HttpsClient, TargetConfig},
+    },
+    context::ContextMap,
+    streaming::RequestBody,
+};
+use hyper::{Request, StatusCode};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::watch,
+};
+use tracing::debug;
+
+/// Variable with the looked up key of the request
+const CTX_ENRICHMENT_KEY: &str = "CTX_ENRICHMENT_KEY";
+/// Variable with the raw looked up value, fields of JSON object value are exposed as separate variables too
+const CTX_ENRICHMENT_VALUE: &str = "CTX_ENRICHMENT_VALUE";
+const CTX_ENRICHMENT_PREFIX: &str = "CTX_ENRICHMENT_";
+/// Max number of idle Redis connections which are kept for the next lookups
+const MAX_IDLE_REDIS_CONNECTIONS: usize = 16;
+
+type RedisConnection = BufReader<TcpStream>;
+/// Result of the lookup in flight which is shared with concurrent lookups of the same key
+type SharedLookup = Option<Result<Option<ContextMap>, String>>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum EnrichmentError {
+    #[error("unable to query lookup endpoint: {0}")]
+    Http(String),
+    #[error("unexpected lookup response status {0}")]
+    Status(StatusCode),
+    #[error("unable to query Redis: {0}")]
+    Redis(#[from] io::Error),
+    #[error("Redis error: {0}")]
+    RedisReply(String),
+    #[error("lookup timed out")]
+    Timeout,
+    #[error("concurrent lookup of the same key failed: {0}")]
+    Shared(String),
+    #[error("looked up value is larger than {0} bytes")]
+    Oversized(usize),
+}
+
+/// Looks up keys of the requests against external source, values are cached for TTL,
+/// state is shared by all workers of the listener
+#[derive(Debug)]
+pub struct Enricher {
+    lookup: Lookup,
+    timeout: Duration,
+    ttl: Duration,
+    max_entries: usize,
+    /// Max size of the lookup response body or Redis reply
+    max_value_size: usize,
+    state: Mutex<EnrichmentCache>,
+}
+
+#[derive(Debug)]
+enum Lookup {
+    Http {
+        url: String,
+        client: Box<HttpsClient>,
+    },
+    Redis {
+        server: RedisServer,
+        key: String,
+        /// Connections are reused by the next lookups, broken ones are dropped
+        idle: Mutex<Vec<RedisConnection>>,
+    },
+}
+
+/// Looked up values by key, missing value is cached as `None`;
+/// keys are ordered by insertion time, so the first one is the oldest one since all entries have the same TTL
+#[derive(Debug, Default)]
+struct EnrichmentCache {
+    entries: HashMap<String, (Instant, Option<ContextMap>)>,
+    order: VecDeque<String>,
+    /// Keys which are being looked up, concurrent lookups of the same key wait for the result
+    in_flight: HashMap<String, watch::Receiver<SharedLookup>>,
+}
+
+/// Releases key of the lookup in flight when it's completed or dropped
+struct InFlightGuard<'a> {
+    enricher: &'a Enricher,
+    key: &'a str,
+}
+
+impl Enricher {
+    pub fn new(cfg: &EnrichmentConfig, tls: &TlsConfig) -> Self {
+        let lookup = match cfg.source() {
+            EnrichmentSource::Http(url) => Lookup::Http {
+                url: url.into(),
+                client: Box::new(TargetConfig::tls_client(tls, cfg.timeout())),
+            },
+            EnrichmentSource::Redis(redis) => Lookup::Redis {
+                server: redis.server().expect("invalid Redis URL, looks like a BUG"),
+                key: redis.key().into(),
+                idle: Mutex::default(),
+            },
+        };
+
+        Self {
+            lookup,
+            timeout: cfg.timeout(),
+            ttl: cfg.cache_ttl(),
+            max_entries: cfg.max_cache_entries(),
+            max_value_size: cfg.max_value_size(),
+            state: Mutex::new(EnrichmentCache::default()),
+        }
+    }
+
+    /// Returns context variables of the key, only the key itself is returned if there is no value
+    pub async fn lookup(&self, key: &str) -> Result<ContextMap, EnrichmentError> {
+        let cached =
+            self.lock().entries.get(key).and_then(|(expires_at, vars)| {
+                (*expires_at > Instant::now()).then(|| vars.clone())
+            });
+        let vars = match cached {
+            Some(vars) => {
+                debug!("enrichment of `{key}` is taken from cache");
+                vars
+            }
+            None => self.fetch_once(key).await?,
+        };
+
+        let mut vars = vars.unwrap_or_default();
+        vars.insert(CTX_ENRICHMENT_KEY.into(), key.into());
+        Ok(vars)
+    }
+
+    /// Fetches and caches variables of the key, concurrent lookups of the same key are coalesced:
+    /// the first one fetches the value, others wait for its result
+    async fn fetch_once(&self, key: &str) -> Result<Option<ContextMap>, EnrichmentError> {
+        loop {
+            let leader = {
+                let mut state = self.lock();
+                match state.in_flight.get(key) {
+                    Some(receiver) => Err(receiver.clone()),
+                    None => {
+                        let (sender, receiver) = watch::channel(None);
+                        state.in_flight.insert(key.into(), receiver);
+                        Ok(sender)
+                    }
+                }
+            };
+
+            match leader {
+                Ok(sender) => {
+                    let _guard = InFlightGuard {
+                        enricher: self,
+                        key,
+                    };
+                    let vars = tokio::time::timeout(self.timeout, self.fetch(key))
+                        .await
+                        .map_err(|_| EnrichmentError::Timeout)
+                        .and_then(|value| value)
+                        .map(|value| value.as_deref().map(variables));
+                    // Value is cached before the key is released, so it's taken either from cache or from the leader
+                    if let Ok(vars) = &vars {
+                        self.insert(key, vars.clone());
+                    }
+                    let shared = vars.as_ref().map(Clone::clone).map_err(ToString::to_string);
+                    let _ = sender.send(Some(shared));
+                    return vars;
+                }
+                Err(mut receiver) => {
+                    if let Ok(shared) = receiver.wait_for(Option::is_some).await {
+                        debug!("enrichment of `{key}` is shared by concurrent lookup");
+                        return shared
+                            .clone()
+                            .expect("shared lookup is empty, looks like a BUG")
+                            .map_err(EnrichmentError::Shared);
+                    }
+                    // Leading lookup has been cancelled without result, so this one leads the next attempt
+                }
+            }
+        }
+    }
+
+    async fn fetch(&self, key: &str) -> Result<Option<String>, EnrichmentError> {
+        match &self.lookup {
+            Lookup::Http { url, client } => {
+                let encoded: String = form_urlencoded::byte_serialize(key.as_bytes()).collect();
+                let req = Request::get(url.replace(ENRICHMENT_KEY_PLACEHOLDER, &encoded))
+                    .body(RequestBody::default())
+                    .map_err(|e| EnrichmentError::Http(e.to_string()))?;
+                let resp = client
+                    .request(req)
+                    .await
+                    .map_err(|e| EnrichmentError::Http(e.to_string()))?;
+                let status = resp.status();
+                if status == StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                if !status.is_success() {
+                    return Err(EnrichmentError::Status(status));
+                }
+                let body =
+                    buffer_pool::collect_limited(None, resp.into_body(), self.max_value_size)
+                        .await
+                        .map_err(|e| EnrichmentError::Http(e.to_string()))?;
+                let Collected::Complete(body, _) = body else {
+                    return Err(EnrichmentError::Oversized(self.max_value_size));
+                };
+                Ok(Some(String::from_utf8_lossy(&body).trim().to_string()))
+            }
+            Lookup::Redis {
+                server,
+                key: template,
+                idle,
+            } => {
+                let key = template.replace(ENRICHMENT_KEY_PLACEHOLDER, key);
+                let pooled = lock_idle(idle).pop();
+                if let Some(mut conn) = pooled {
+                    match redis_command(&mut conn, &["GET", &key], self.max_value_size).await {
+                        Ok(value) => {
+                            release_idle(idle, conn);
+                            return Ok(value);
+                        }
+                        // Idle connection may be closed by the server, so the command is repeated on the new one
+                        Err(EnrichmentError::Redis(e)) => {
+                            debug!("idle Redis connection is broken: {e}")
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                let mut conn = redis_connect(server, self.max_value_size).await?;
+                let value = redis_command(&mut conn, &["GET", &key], self.max_value_size).await?;
+                release_idle(idle, conn);
+                Ok(value)
+            }
+        }
+    }
+
+    fn insert(&self, key: &str, vars: Option<ContextMap>) {
+        let now = Instant::now();
+        let mut state = self.lock();
+        // Expired entries are dropped first, then the oldest ones if cache is still full
+        while let Some(oldest) = state.order.front() {
+            let expired = state
+                .entries
+                .get(oldest)
+                .is_none_or(|(expires_at, _)| *expires_at <= now);
+            if !expired && state.entries.len() < self.max_entries {
+                break;
+            }
+            let oldest = state.order.pop_front().expect("cache order is empty");
+            state.entries.remove(&oldest);
+        }
+        // Refreshed key is moved to the end, so order of the keys is still the order of their expiration
+        if state
+            .entries
+            .insert(key.into(), (now + self.ttl, vars))
+            .is_some()
+        {
+            state.order.retain(|existing| existing != key);
+        }
+        state.order.push_back(key.into());
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, EnrichmentCache> {
+        self.state
+            .lock()
+            .expect("unable to lock enrichment cache, looks like a BUG")
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.enricher.lock().in_flight.remove(self.key);
+    }
+}
+
+fn lock_idle(
+    idle: &Mutex<Vec<RedisConnection>>,
+) -> std::sync::MutexGuard<'_, Vec<RedisConnection>> {
+    idle.lock()
+        .expect("unable to lock Redis connections, looks like a BUG")
+}
+
+fn release_idle(idle: &Mutex<Vec<RedisConnection>>, conn: RedisConnection) {
+    let mut idle = lock_idle(idle);
+    if idle.len() < MAX_IDLE_REDIS_CONNECTIONS {
+        idle.push(conn);
+    }
+}
+
+/// Opens new connection, authenticates it and selects database
+async fn redis_connect(
+    server: &RedisServer,
+    max_reply_size: usize,
+) -> Result<RedisConnection, EnrichmentError> {
+    let mut conn = BufReader::new(TcpStream::connect(&server.address).await?);
+    match (&server.user, &server.password) {
+        (Some(user), Some(password)) => {
+            redis_command(&mut conn, &["AUTH", user, password], max_reply_size).await?;
+        }
+        (None, Some(password)) => {
+            redis_command(&mut conn, &["AUTH", password], max_reply_size).await?;
+        }
+        (_, None) => {}
+    }
+    if server.db != 0 {
+        let db = server.db.to_string();
+        redis_command(&mut conn, &["SELECT", &db], max_reply_size).await?;
+    }
+
+    Ok(conn)
+}
+
+/// Raw value is exposed as is, fields of JSON object are exposed by their uppercase names
+fn variables(value: &str) -> ContextMap {
+    let mut vars = ContextMap::new();
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::from_str(value) {
+        for (name, field) in fields {
+            let name = name.to_uppercase().replace('-', "_");
+            let field = match field {
+                serde_json::Value::String(field) => field,
+                field => field.to_string(),
+            };
+            vars.insert(format!("{CTX_ENRICHMENT_PREFIX}{name}"), field);
+        }
+    }
+    vars.insert(CTX_ENRICHMENT_VALUE.into(), value.into());
+
+    vars
+}
+
+/// Sends command in RESP format and returns its bulk string reply, `None` if it's nil;
+/// status replies are returned as strings too, replies larger than `max_reply_size` are failures
+async fn redis_command<S>(
+    stream: &mut S,
+    args: &[&str],
+    max_reply_size: usize,
+) -> Result<Option<String>, EnrichmentError>
+where
+    S: AsyncBufRead + AsyncWriteExt + Unpin,
+{
+    let mut command = format!("*{}\r\n", args.len());
+    for arg in args {
+        command.push_str(&format!("${}\r\n{arg}\r\n", arg.len()));
+    }
+    stream.write_all(command.as_bytes()).await?;
+    stream.flush().await?;
+
+    let mut line = String::new();
+    // Reply line is read up to the limit and its CRLF, so unterminated line is either EOF or too long
+    let limit = u64::try_from(max_reply_size + 2).unwrap_or(u64::MAX);
+    match (&mut *stream).take(limit).read_line(&mut line).await? {
+        0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        _ if !line.ends_with('\n') => return Err(EnrichmentError::Oversized(max_reply_size)),
+        _ => {}
+    }
+    let line = line.trim_end();
+    match line.split_at_checked(1) {
+        Some(("+", status)) => Ok(Some(status.into())),
+        Some(("-", error)) => Err(EnrichmentError::RedisReply(error.into())),
+        Some(("$", "-1")) => Ok(None),
+        Some(("$", len)) => {
+            let len: usize = len
+                .parse()
+                .map_err(|_| EnrichmentError::RedisReply(format!("invalid reply `{line}`")))?;
+            if len > max_reply_size {
+                return Err(EnrichmentError::Oversized(max_reply_size));
+            }
+            // Value is followed by CRLF
+            let mut value = vec![0; len + 2];
+            stream.read_exact(&mut value).await?;
+            value.truncate(len);
+            Ok(Some(String::from_utf8_lossy(&value).into()))
+        }
+        _ => Err(EnrichmentError::RedisReply(format!(
+            "unexpected reply `{line}`"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_variables() {
+        let vars = variables(r#"{"region": "eu-west", "shard-id": 3}"#);
+        assert_eq!(vars["CTX_ENRICHMENT_REGION"], "eu-west");
+        assert_eq!(vars["CTX_ENRICHMENT_SHARD_ID"], "3");
+        assert_eq!(
+            vars["CTX_ENRICHMENT_VALUE"],
+            r#"{"region": "eu-west", "shard-id": 3}"#
+        );
+
+        let vars = variables("eu-west");
+        assert_eq!(vars.len(), 1);
+        assert_eq!(vars["CTX_ENRICHMENT_VALUE"], "eu-west");
+    }
+
+    #[test]
+    fn refreshed_entry_is_evicted_last() {
+        let enricher = Enricher {
+            lookup: Lookup::Redis {
+                server: RedisServer {
+                    address: "127.0.0.1:1".into(),
+                    user: None,
+                    password: None,
+                    db: 0,
+                },
+                key: ENRICHMENT_KEY_PLACEHOLDER.into(),
+                idle: Mutex::default(),
+            },
+            timeout: Duration::from_secs(1),
+            ttl: Duration::from_secs(60),
+            max_entries: 2,
+            max_value_size: 1024,
+            state: Mutex::default(),
+        };
+
+        enricher.insert("a", None);
+        enricher.insert("b", None);
+        enricher.insert("a", None);
+        enricher.insert("c", None);
+
+        let state = enricher.lock();
+        assert_eq!(state.order, ["a", "c"]);
+        assert!(state.entries.contains_key("a"));
+        assert!(!state.entries.contains_key("b"));
+    }
+
+    #[tokio::test]
+    async fn redis_replies() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        server
+            .write_all(b"+OK\r\n$7\r\neu-west\r\n$-1\r\n-ERR unknown\r\n")
+            .await
+            .unwrap();
+        let mut client = BufReader::new(client);
+
+        let reply = redis_command(&mut client, &["AUTH", "secret"], 16).await;
+        assert_eq!(reply.unwrap(), Some("OK".into()));
+        let reply = redis_command(&mut client, &["GET", "tenant:acme"], 16).await;
+        assert_eq!(reply.unwrap(), Some("eu-west".into()));
+        let reply = redis_command(&mut client, &["GET", "tenant:none"], 16).await;
+        assert_eq!(reply.unwrap(), None);
+        let reply = redis_command(&mut client, &["GET", "tenant:error"], 16).await;
+        assert!(matches!(reply, Err(EnrichmentError::RedisReply(_))));
+
+        // Replies above the limit aren't read
+        server
+            .write_all(b"$1000000000\r\n+0123456789abcdefXYZ\r\n")
+            .await
+            .unwrap();
+        let reply = redis_command(&mut client, &["GET", "tenant:huge"], 16).await;
+        assert!(matches!(reply, Err(EnrichmentError::Oversized(16))));
+        let reply = redis_command(&mut client, &["GET", "tenant:long"], 16).await;
+        assert!(matches!(reply, Err(EnrichmentError::Oversized(16))));
+
+        let mut sent = vec![0; 37];
+        server.read_exact(&mut sent).await.unwrap();
+        assert_eq!(sent, b"*2\r\n$4\r\nAUTH\r\n$6\r\nsecret\r\n*2\r\n$3\r\nGET");
+    }
+}
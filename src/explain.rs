@@ -0,0 +1,192 @@
+use crate::config::{listener::ResponseStrategy, response::TargetSelection};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Routing decision of the single request: which conditions matched,
+/// what happened with each target and why the response was selected
+#[derive(Serialize, Debug)]
+pub struct Explanation {
+    strategy: String,
+    conditions: Vec<ExplainedCondition>,
+    targets: Vec<ExplainedTarget>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selected: Option<String>,
+    reason: String,
+}
+
+#[derive(Serialize, Debug)]
+struct ExplainedCondition {
+    target: String,
+    result: ConditionResult,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum ConditionResult {
+    True,
+    False,
+    Default,
+}
+
+#[derive(Serialize, Debug)]
+struct ExplainedTarget {
+    id: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+}
+
+impl Explanation {
+    pub fn new(strategy: &ResponseStrategy) -> Self {
+        Self {
+            strategy: strategy.to_string(),
+            conditions: vec![],
+            targets: vec![],
+            selected: None,
+            reason: String::new(),
+        }
+    }
+
+    /// Adds result of the target's condition
+    pub fn add_condition(&mut self, target_id: &str, result: bool) {
+        self.conditions.push(ExplainedCondition {
+            target: target_id.into(),
+            result: if result {
+                ConditionResult::True
+            } else {
+                ConditionResult::False
+            },
+        });
+    }
+
+    /// Adds target with default condition, it's used if no other target matches
+    pub fn add_default_condition(&mut self, target_id: &str) {
+        self.conditions.push(ExplainedCondition {
+            target: target_id.into(),
+            result: ConditionResult::Default,
+        });
+    }
+
+    /// Adds outcome of the target's request, status is response status or why target wasn't requested/awaited
+    pub fn add_target(&mut self, id: &str, status: &str, duration: Option<Duration>) {
+        self.targets.push(ExplainedTarget {
+            id: id.into(),
+            status: status.into(),
+            duration_ms: duration.map(|d| d.as_millis() as u64),
+        });
+    }
+
+    /// Explains which response is selected by strategy with these candidates
+    pub fn select(
+        &mut self,
+        strategy: &ResponseStrategy,
+        ok_target_id: &Option<String>,
+        failed_target_id: &Option<String>,
+        selected_target_id: &Option<String>,
+        selection: Option<TargetSelection>,
+    ) {
+        let selected = match selection {
+            Some(selection) => format!("target selected by `{selection}`"),
+            None => "target selected by `target_selector`".into(),
+        };
+        let (target_id, reason) = match strategy {
+            ResponseStrategy::AlwaysOverride => (&None, "response is always overridden".into()),
+            ResponseStrategy::OkThenOverride => match ok_target_id {
+                Some(_) => (ok_target_id, "first OK response".into()),
+                None => (&None, "no OK responses, response is overridden".into()),
+            },
+            ResponseStrategy::FailedThenOverride => match failed_target_id {
+                Some(_) => (failed_target_id, "first failed response".into()),
+                None => (&None, "no failed responses, response is overridden".into()),
+            },
+            ResponseStrategy::OkThenTargetId => match ok_target_id {
+                Some(_) => (ok_target_id, "first OK response".into()),
+                None => (selected_target_id, format!("no OK responses, {selected}")),
+            },
+            ResponseStrategy::FailedThenTargetId => match failed_target_id {
+                Some(_) => (failed_target_id, "first failed response".into()),
+                None => (
+                    selected_target_id,
+                    format!("no failed responses, {selected}"),
+                ),
+            },
+            ResponseStrategy::OkThenFailed => match (ok_target_id, failed_target_id) {
+                (Some(_), _) => (ok_target_id, "first OK response".into()),
+                (None, Some(_)) => (
+                    failed_target_id,
+                    "no OK responses, first failed response".into(),
+                ),
+                (None, None) => (&None, "no responses".into()),
+            },
+            ResponseStrategy::FailedThenOk => match (failed_target_id, ok_target_id) {
+                (Some(_), _) => (failed_target_id, "first failed response".into()),
+                (None, Some(_)) => (
+                    ok_target_id,
+                    "no failed responses, first OK response".into(),
+                ),
+                (None, None) => (&None, "no responses".into()),
+            },
+            ResponseStrategy::AlwaysTargetId => (selected_target_id, selected),
+            ResponseStrategy::ConditionalRouting => match selected_target_id {
+                Some(_) => (selected_target_id, "target routed by condition".into()),
+                None => (&None, "no targets satisfy conditions".into()),
+            },
+            ResponseStrategy::Script => (&None, "response selected by script".into()),
+            ResponseStrategy::Custom => (&None, "response selected by custom strategy".into()),
+            ResponseStrategy::PreferredThenFallback => match ok_target_id {
+                Some(_) => (ok_target_id, "first OK response in preference order".into()),
+                None => (
+                    selected_target_id,
+                    "no OK responses, preferred target".into(),
+                ),
+            },
+        };
+        self.selected = target_id.clone();
+        self.reason = reason;
+    }
+
+    /// Returns explanation as single-line JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("unable to serialize explanation, looks like a BUG")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn explanation() {
+        let mut explanation = Explanation::new(&ResponseStrategy::OkThenTargetId);
+        explanation.add_condition("first", false);
+        explanation.add_default_condition("second");
+        explanation.add_target("second", "503", Some(Duration::from_millis(7)));
+        explanation.add_target("third", "skipped", None);
+        explanation.select(
+            &ResponseStrategy::OkThenTargetId,
+            &None,
+            &Some("second".into()),
+            &Some("third".into()),
+            Some(TargetSelection::RoundRobin),
+        );
+
+        let json: Value = serde_json::from_str(&explanation.to_json()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "strategy": "ok_then_target_id",
+                "conditions": [
+                    {"target": "first", "result": "false"},
+                    {"target": "second", "result": "default"}
+                ],
+                "targets": [
+                    {"id": "second", "status": "503", "duration_ms": 7},
+                    {"id": "third", "status": "skipped"}
+                ],
+                "selected": "third",
+                "reason": "no OK responses, target selected by `round_robin`"
+            })
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    config::{forward_proxy::ForwardProxyConfig, listener::ListenerConfig},
+    handler::RequestHandler,
+    streaming::{buffered_response, ResponseBody},
+    tls::TLS_HANDSHAKE_RECORD,
+};
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderName, PROXY_AUTHORIZATION},
+    http::{
+        self,
+        uri::{Authority, PathAndQuery, Scheme},
+    },
+    service::service_fn,
+    HeaderMap, Method, Request, Response, StatusCode, Uri,
+};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+};
+use rustls::ServerConfig;
+use std::{io::Cursor, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite},
+    sync::watch,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info, warn};
+
+/// Header which is addressed to the proxy itself, like `Proxy-Authorization`, so it isn't forwarded to targets
+const PROXY_CONNECTION: HeaderName = HeaderName::from_static("proxy-connection");
+
+/// Forward proxy mode of the listener, TLS config of the tunnels is loaded once with the listener's config
+#[derive(Debug)]
+pub struct ForwardProxy {
+    tunnel_tls: Option<Arc<ServerConfig>>,
+}
+
+impl ForwardProxy {
+    pub fn new(cfg: &ForwardProxyConfig) -> Result<Self, anyhow::Error> {
+        let tunnel_tls = cfg
+            .tunnel_tls()
+            .map(|tls| tls.server_config())
+            .transpose()?;
+        Ok(Self { tunnel_tls })
+    }
+}
+
+/// Drops headers which are addressed to the proxy
+pub fn strip_proxy_headers(headers: &mut HeaderMap) {
+    headers.remove(PROXY_AUTHORIZATION);
+    headers.remove(PROXY_CONNECTION);
+}
+
+/// Returns `true` if request opens tunnel which is served by the listener's forward proxy
+pub fn is_tunnel(cfg: &ListenerConfig, req: &Request<Incoming>) -> bool {
+    req.method() == Method::CONNECT && cfg.forward_proxy().is_some_and(|proxy| proxy.connect())
+}
+
+/// Accepts `CONNECT` request if its destination is allowed, and serves requests from the tunnel
+/// by the current listener's handler as if they were sent to the destination in absolute form
+pub async fn tunnel(
+    handler: watch::Receiver<RequestHandler>,
+    addr: SocketAddr,
+    req: Request<Incoming>,
+) -> Result<Response<ResponseBody>, http::Error> {
+    let current = handler.borrow().clone();
+    let listener_cfg = current.listener_cfg();
+    let Some(authority) = req.uri().authority().cloned() else {
+        warn!("rejected tunnel from {addr}: there is no destination");
+        return empty_response(StatusCode::BAD_REQUEST);
+    };
+    if !listener_cfg
+        .forward_proxy()
+        .is_some_and(|proxy| proxy.is_host_allowed(authority.host()))
+    {
+        warn!("rejected tunnel from {addr} to `{authority}`: destination isn't allowed");
+        return empty_response(StatusCode::FORBIDDEN);
+    }
+
+    info!("tunnel from {addr} to `{authority}` accepted");
+    let acceptor = current
+        .forward_proxy()
+        .and_then(|proxy| proxy.tunnel_tls.clone())
+        .map(TlsAcceptor::from);
+    let timeout = listener_cfg.timeout();
+    tokio::spawn(async move {
+        match hyper::upgrade::on(req).await {
+            Ok(upgraded) => {
+                serve_tunnel(
+                    handler,
+                    addr,
+                    authority,
+                    TokioIo::new(upgraded),
+                    acceptor,
+                    timeout,
+                )
+                .await
+            }
+            Err(e) => warn!("unable to establish tunnel from {addr}: {e}"),
+        }
+    });
+
+    empty_response(StatusCode::OK)
+}
+
+/// Detects protocol inside the tunnel by its first byte, TLS is terminated if the tunnel's certificate is configured
+async fn serve_tunnel(
+    handler: watch::Receiver<RequestHandler>,
+    addr: SocketAddr,
+    authority: Authority,
+    mut stream: impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    acceptor: Option<TlsAcceptor>,
+    timeout: Duration,
+) {
+    let mut first = [0u8; 1];
+    match tokio::time::timeout(timeout, stream.read(&mut first)).await {
+        Ok(Ok(1)) => {}
+        Ok(Ok(_)) => return,
+        Ok(Err(e)) => {
+            debug!("unable to read tunnel from {addr} to `{authority}`: {e}");
+            return;
+        }
+        Err(_) => {
+            debug!("tunnel from {addr} to `{authority}` timed out");
+            return;
+        }
+    }
+    // The first byte is put back in front of the rest of the stream
+    let (rd, wr) = tokio::io::split(stream);
+    let stream = tokio::io::join(Cursor::new(first).chain(rd), wr);
+
+    if first[0] != TLS_HANDSHAKE_RECORD {
+        return serve_requests(handler, addr, Scheme::HTTP, authority, stream, timeout).await;
+    }
+    let Some(acceptor) = acceptor else {
+        warn!("TLS in tunnel from {addr} to `{authority}` isn't terminated without `tunnel_tls`");
+        return;
+    };
+    match tokio::time::timeout(timeout, acceptor.accept(stream)).await {
+        Ok(Ok(stream)) => {
+            serve_requests(handler, addr, Scheme::HTTPS, authority, stream, timeout).await
+        }
+        Ok(Err(e)) => debug!("TLS handshake in tunnel from {addr} to `{authority}` failed: {e}"),
+        Err(_) => debug!("TLS handshake in tunnel from {addr} to `{authority}` timed out"),
+    }
+}
+
+async fn serve_requests(
+    handler: watch::Receiver<RequestHandler>,
+    addr: SocketAddr,
+    scheme: Scheme,
+    authority: Authority,
+    stream: impl AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    timeout: Duration,
+) {
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::default())
+        .header_read_timeout(timeout);
+    let conn = builder.serve_connection_with_upgrades(
+        TokioIo::new(stream),
+        service_fn(|mut req| {
+            *req.uri_mut() = absolute_uri(&scheme, &authority, req.uri());
+            // Each request is served by the current handler, even if it's reloaded
+            handler.borrow().clone().handle(addr, req)
+        }),
+    );
+
+    if let Err(e) = conn.await {
+        debug!("error serving tunnel from {addr} to `{authority}`: {e}");
+    }
+}
+
+/// Requests inside the tunnel are in origin form, so destination of the tunnel is added to them
+fn absolute_uri(scheme: &Scheme, authority: &Authority, uri: &Uri) -> Uri {
+    if uri.authority().is_some() {
+        return uri.clone();
+    }
+    let mut parts = uri.clone().into_parts();
+    parts.scheme = Some(scheme.clone());
+    parts.authority = Some(authority.clone());
+    parts
+        .path_and_query
+        .get_or_insert(PathAndQuery::from_static("/"));
+
+    Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+}
+
+fn empty_response(status: StatusCode) -> Result<Response<ResponseBody>, http::Error> {
+    let resp = Response::builder()
+        .status(status)
+        .body(Full::from(Bytes::new()))?;
+    Ok(buffered_response(resp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tunnel_request_uri() {
+        let authority = Authority::from_static("api.example.com:8443");
+        let uri = absolute_uri(
+            &Scheme::HTTPS,
+            &authority,
+            &Uri::from_static("/users?page=2"),
+        );
+        assert_eq!(uri, "https://api.example.com:8443/users?page=2");
+
+        let uri = absolute_uri(
+            &Scheme::HTTP,
+            &authority,
+            &Uri::from_static("http://other.host/"),
+        );
+        assert_eq!(uri, "http://other.host/");
+    }
+}
@@ -1,150 +1,791 @@
 use crate::{
+    audit::{AuditLog, AuditRecord},
+    body_logging::BodyLogger,
+    buffer_pool::{self, BufferPool, Collected},
+    cache::ResponseCache,
+    canary::CanaryController,
+    coalescing::{Coalescer, Coalescing},
+    comparison::ComparisonReport,
+    compression::{self, Decompressed},
+    concurrency::ConcurrencyLimiter,
     config::{
+        compression::{RequestBodyForward, ResponseDecompression},
+        dns::DnsFailureAction,
+        enrichment::EnrichmentErrorAction,
+        failure::{Elapsed, TargetFailureClassifier},
+        faults::{FaultsConfig, FAULT_HEADER},
         headers::HeadersTransformator,
         listener::{ListenerConfig, ResponseStrategy},
-        response::{ResponseBehavior, ResponseKind},
-        target::{TargetBehavior, TargetConditionConfig, TargetConfig, TargetOnErrorAction},
+        pipeline::PipelineStage,
+        query::{QueryTransformator, QueryTransformsList},
+        ranges::MirrorRanges,
+        response::{ExplainMode, ResponseBehavior, ResponseConfig, ResponseKind, EXPLAIN_HEADER},
+        target::{
+            ConditionInput, HostHeader, OversizedResponseAction, TargetBehavior,
+            TargetConditionConfig, TargetConfig, TargetOnErrorAction, TimeoutPhase,
+        },
+        websocket::WebSocketConfig,
+        AppConfig,
     },
-    context::Context,
+    context::{Context, ContextMap},
+    cors,
+    deduplication::{Deduplication, Deduplicator},
+    deprecation::DeprecationTracker,
+    discovery::TargetDiscovery,
+    dns,
+    enrichment::Enricher,
+    explain::Explanation,
+    forward_proxy::{self, ForwardProxy},
+    metrics,
+    plugins::Plugins,
+    ranges::{self, FullContent},
+    redaction,
+    retry_queue::{QueuedRequest, RetryQueue},
+    sampling::TargetSampler,
+    selection::TargetSelector,
+    shaping::ThrottledBody,
+    slo::{SloOutcome, SloTracker},
+    streaming::{
+        buffered_response, streamed_response, IdleTimeoutBody, RequestBody, ResponseBody,
+        StreamError, Trailers,
+    },
+    websocket,
 };
-use futures_util::future::join_all;
+use futures_util::{stream::FuturesUnordered, StreamExt};
 use http::HeaderValue;
 use http_body_util::{BodyExt, Full};
 use hyper::{
-    body::{Bytes, Incoming},
-    header::HOST,
-    http, Request, Response, StatusCode, Uri,
+    body::{Body, Bytes, Incoming},
+    header::{HeaderName, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, EXPECT, HOST, VIA},
+    http::{self, request::Parts},
+    HeaderMap, Method, Request, Response, StatusCode, Uri,
 };
+use sha2::{Digest, Sha256};
 use shellexpand::env_with_context_no_errors;
-use std::{collections::HashMap, net::SocketAddr};
-use tracing::{debug, error, info, warn};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    future::Future,
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::task::{AbortHandle, JoinError, JoinHandle};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 use uuid::Uuid;
 
+/// Headers with checksum and size of the request body forwarded to targets
+const BODY_SHA256_HEADER: &str = "x-body-sha256";
+const BODY_LENGTH_HEADER: &str = "x-body-length";
+
 pub type ResponsesMap<'a> = HashMap<String, (Option<Response<Full<Bytes>>>, &'a Context<'a>)>;
 pub type HyperError = hyper_util::client::legacy::Error;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct RequestHandler {
-    pub listener_cfg: &'static ListenerConfig,
-    pub root_ctx: &'static Context<'static>,
+    app_config: Arc<AppConfig>,
+    listener_index: usize,
+    root_ctx: Arc<Context<'static>>,
+    cache: Option<Arc<ResponseCache>>,
+    deduplicator: Option<Arc<Deduplicator>>,
+    coalescer: Option<Arc<Coalescer>>,
+    body_logger: Option<Arc<BodyLogger>>,
+    comparison: Option<Arc<ComparisonReport>>,
+    audit: Option<Arc<AuditLog>>,
+    plugins: Option<Arc<Plugins>>,
+    /// Discovered instances of the targets by target ID
+    discovery: Arc<HashMap<String, Arc<TargetDiscovery>>>,
+    /// SLO trackers of the targets by target ID
+    slo: Arc<HashMap<String, Arc<SloTracker>>>,
+    /// Trackers of the deprecated targets by target ID
+    deprecations: Arc<HashMap<String, Arc<DeprecationTracker>>>,
+    /// Samplers of the targets which get part of the requests only, by target ID
+    samplers: Arc<HashMap<String, Arc<TargetSampler>>>,
+    /// Queues of the failed requests to retry by target ID
+    retry_queues: Arc<HashMap<String, Arc<RetryQueue>>>,
+    /// Selector of the target for `*_target_id` strategies, if it isn't static
+    selector: Option<Arc<TargetSelector>>,
+    /// Limiter of the target requests in progress
+    concurrency: Option<Arc<ConcurrencyLimiter>>,
+    /// Reusable buffers to read bodies of the requests and targets' responses
+    buffer_pool: Option<Arc<BufferPool>>,
+    forward_proxy: Option<Arc<ForwardProxy>>,
+    /// Lookup of the request's key against external source, values are exposed as context variables
+    enricher: Option<Arc<Enricher>>,
 }
 
 impl RequestHandler {
-    pub fn new(cfg: &'static ListenerConfig, ctx: &'static Context) -> Self {
+    pub fn new(
+        app_config: Arc<AppConfig>,
+        listener_index: usize,
+        root_ctx: Arc<Context<'static>>,
+    ) -> Result<Self, anyhow::Error> {
+        let cfg = &app_config.listeners()[listener_index];
         info!("Creating listener: {}, on: {}", cfg.id(), cfg.on());
-        Self {
-            listener_cfg: cfg,
-            root_ctx: ctx,
+        let cache = cfg.cache().map(|cfg| Arc::new(ResponseCache::new(cfg)));
+        let deduplicator = cfg
+            .deduplication()
+            .map(|cfg| Arc::new(Deduplicator::new(cfg)));
+        let coalescer = cfg.coalescing().map(|cfg| Arc::new(Coalescer::new(cfg)));
+        let body_logger = cfg.body_logging().map(|cfg| Arc::new(BodyLogger::new(cfg)));
+        let comparison = cfg
+            .comparison()
+            .map(|comparison| ComparisonReport::new(comparison, cfg.id()));
+        let buffer_pool = cfg
+            .buffer_pool()
+            .map(|buffer_pool| BufferPool::new(buffer_pool, cfg.id()));
+        let audit = cfg
+            .audit()
+            .map(|audit| AuditLog::new(audit, cfg.tls()))
+            .transpose()?
+            .map(Arc::new);
+        let plugins = Plugins::new(cfg.plugins())?.map(Arc::new);
+        let discovery: HashMap<String, Arc<TargetDiscovery>> = cfg
+            .targets()
+            .iter()
+            .filter_map(|target| {
+                let discovery = target.discovery()?;
+                Some((
+                    target.id(),
+                    Arc::new(TargetDiscovery::new(target, discovery)),
+                ))
+            })
+            .collect();
+        let slo = cfg
+            .targets()
+            .iter()
+            .filter_map(|target| {
+                let tracker = Arc::new(SloTracker::new(
+                    target.slo()?,
+                    cfg.id(),
+                    cfg.labels(),
+                    target.id(),
+                    target.labels(),
+                ));
+                metrics::register(tracker.clone());
+                Some((target.id(), tracker))
+            })
+            .collect();
+        let deprecations = cfg
+            .targets()
+            .iter()
+            .filter_map(|target| {
+                let tracker = Arc::new(DeprecationTracker::new(target, cfg.id())?);
+                metrics::register(tracker.clone());
+                Some((target.id(), tracker))
+            })
+            .collect();
+        let samplers = cfg
+            .targets()
+            .iter()
+            .filter_map(|target| {
+                let sampler = Arc::new(TargetSampler::new(
+                    target.sampling()?,
+                    cfg.id(),
+                    target.id(),
+                ));
+                metrics::register(sampler.clone());
+                Some((target.id(), sampler))
+            })
+            .collect();
+        let mut retry_queues = HashMap::new();
+        for target in cfg.targets().iter().filter(|t| t.retry_queue().is_some()) {
+            let discovery = discovery.get(&target.id()).cloned();
+            let classifier =
+                TargetFailureClassifier::new(app_config.clone(), listener_index, target.id());
+            let queue = RetryQueue::new(cfg.id(), target, cfg.tls(), discovery, classifier)?;
+            retry_queues.insert(target.id(), queue);
         }
+        let selector = cfg.response().target_selection().map(|policy| {
+            let canary = cfg.targets().iter().find_map(|target| {
+                Some(CanaryController::new(
+                    target.canary()?,
+                    cfg.id(),
+                    target.id(),
+                ))
+            });
+            Arc::new(TargetSelector::new(policy, canary))
+        });
+        let concurrency = cfg
+            .concurrency()
+            .map(|concurrency| ConcurrencyLimiter::new(concurrency, cfg.id()));
+        let forward_proxy = cfg
+            .forward_proxy()
+            .map(ForwardProxy::new)
+            .transpose()?
+            .map(Arc::new);
+        let enricher = cfg
+            .enrichment()
+            .map(|enrichment| Arc::new(Enricher::new(enrichment, cfg.tls())));
+        Ok(Self {
+            app_config,
+            listener_index,
+            root_ctx,
+            cache,
+            deduplicator,
+            coalescer,
+            body_logger,
+            comparison,
+            audit,
+            plugins,
+            discovery: Arc::new(discovery),
+            slo: Arc::new(slo),
+            deprecations: Arc::new(deprecations),
+            samplers: Arc::new(samplers),
+            retry_queues: Arc::new(retry_queues),
+            selector,
+            concurrency,
+            buffer_pool,
+            forward_proxy,
+            enricher,
+        })
+    }
+
+    /// Returns config of the listener served by this handler
+    pub fn listener_cfg(&self) -> &ListenerConfig {
+        &self.app_config.listeners()[self.listener_index]
+    }
+
+    /// Returns forward proxy of the listener, if it's enabled
+    pub fn forward_proxy(&self) -> Option<&ForwardProxy> {
+        self.forward_proxy.as_deref()
     }
 
     pub async fn handle(
         self,
         addr: SocketAddr,
         req: Request<Incoming>,
-    ) -> Result<Response<Full<Bytes>>, http::Error> {
+    ) -> Result<Response<ResponseBody>, http::Error> {
+        let listener_cfg = self.listener_cfg();
         let req_id = Uuid::new_v4();
+        let mut audit = self
+            .audit
+            .as_ref()
+            .map(|audit| audit.start(req_id, listener_cfg.id(), &addr, &req));
+        // Request's ID, listener and its labels are attached to all log entries of the request,
+        // including ones of the detached tasks: mirrors, streaming and websocket sessions
+        let span = match listener_cfg.labels() {
+            labels if labels.is_empty() => {
+                info_span!("request", id = %req_id, listener = %listener_cfg.id())
+            }
+            labels => {
+                info_span!("request", id = %req_id, listener = %listener_cfg.id(), labels = %labels)
+            }
+        };
+        let mut resp = match listener_cfg.cors() {
+            // Preflight is answered by listener itself instead of forwarding it to targets
+            Some(cors_cfg) if cors::is_preflight(&req) => {
+                debug!(
+                    "CORS preflight from: {addr}, origin: {:?}",
+                    cors::origin(&req)
+                );
+                buffered_response(cors::preflight_response(&req, cors_cfg))
+            }
+            Some(cors_cfg) => {
+                let origin = cors::origin(&req);
+                let mut resp = self
+                    .clone()
+                    .handle_request(req_id, addr, req, &mut audit)
+                    .instrument(span.clone())
+                    .await?;
+                cors::add_headers(&mut resp, origin, cors_cfg);
+                resp
+            }
+            None => {
+                self.clone()
+                    .handle_request(req_id, addr, req, &mut audit)
+                    .instrument(span.clone())
+                    .await?
+            }
+        };
+        if let Some(security_headers) = listener_cfg.security_headers() {
+            security_headers.apply(resp.headers_mut());
+        }
+        if let (Some(log), Some(record)) = (&self.audit, audit) {
+            log.write(record, &resp);
+        }
+
+        Ok(resp)
+    }
+
+    async fn handle_request(
+        self,
+        req_id: Uuid,
+        addr: SocketAddr,
+        mut req: Request<Incoming>,
+        audit: &mut Option<AuditRecord>,
+    ) -> Result<Response<ResponseBody>, http::Error> {
+        let listener_cfg = self.listener_cfg();
         info!(
             "{req_id}: accepted from: {}, to: {}, method: {}",
             addr,
-            self.listener_cfg.id(),
+            listener_cfg.id(),
             req.method()
         );
 
-        let response_cfg = self.listener_cfg.response();
+        let response_cfg = listener_cfg.response();
+
+        // Normalized request is used for routing, conditions and forwarding, WebSocket handshake is kept as is
+        if let Some(normalization) = listener_cfg.normalization() {
+            let keep_upgrade =
+                listener_cfg.websocket().is_some() && websocket::is_upgrade_request(&req);
+            normalization.apply(&mut req, keep_upgrade);
+        }
+
+        // Proxied request is accepted if its destination is allowed, headers addressed to the proxy aren't forwarded
+        if let Some(forward_proxy) = listener_cfg.forward_proxy() {
+            // Tunnels are served before the handler, so this one isn't allowed
+            if req.method() == Method::CONNECT {
+                error!(
+                    "{req_id}: rejected, tunnels aren't allowed, listener: {}",
+                    listener_cfg.id()
+                );
+                let resp = response_cfg.empty_response(StatusCode::METHOD_NOT_ALLOWED.into())?;
+                return Ok(buffered_response(resp));
+            }
+            if let Some(host) = req.uri().host() {
+                if !forward_proxy.is_host_allowed(host) {
+                    error!(
+                        "{req_id}: rejected, proxying to `{host}` isn't allowed, listener: {}",
+                        listener_cfg.id()
+                    );
+                    let resp = response_cfg.empty_response(StatusCode::FORBIDDEN.into())?;
+                    return Ok(buffered_response(resp));
+                }
+            }
+            forward_proxy::strip_proxy_headers(req.headers_mut());
+        }
+
+        // Static routes are answered by listener itself, so such requests don't reach targets
+        if let Some(route) = listener_cfg
+            .static_routes()
+            .iter()
+            .find(|route| route.is_match(req.method(), req.uri().path()))
+        {
+            debug!("{req_id}: static route: {}", req.uri().path());
+            let resp = route.response()?;
+            info!("{req_id}: completed, status={}", resp.status().as_u16());
+            return Ok(buffered_response(resp));
+        }
 
         // Verify is method allowed in the config
-        if !self.listener_cfg.is_method_allowed(req.method().as_ref()) {
+        if !listener_cfg.is_method_allowed(req.method().as_ref()) {
             error!(
                 "{req_id}: rejected, not allowed method: {}, listener: {}",
                 req.method(),
-                self.listener_cfg.id()
+                listener_cfg.id()
+            );
+            let resp = response_cfg.empty_response(StatusCode::METHOD_NOT_ALLOWED.into())?;
+            return Ok(buffered_response(resp));
+        }
+
+        // Verify headers limits to protect buffered fan-out from oversized requests
+        if let Some(Err(e)) = listener_cfg.header_limits().map(|l| l.check(req.headers())) {
+            error!(
+                "{req_id}: rejected, too large headers: {e}, listener: {}",
+                listener_cfg.id()
+            );
+            let resp =
+                response_cfg.empty_response(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE.into())?;
+            return Ok(buffered_response(resp));
+        }
+
+        // Request which has passed this splitter already is rejected to break forwarding loop
+        if listener_cfg
+            .proxy_headers()
+            .is_some_and(|proxy_headers| proxy_headers.is_loop(req.headers()))
+        {
+            error!(
+                "{req_id}: rejected, forwarding loop detected, via: {:?}, listener: {}",
+                req.headers().get(VIA),
+                listener_cfg.id()
             );
-            return response_cfg.empty_response(StatusCode::METHOD_NOT_ALLOWED.into());
+            let resp = response_cfg.empty_response(StatusCode::LOOP_DETECTED.into())?;
+            return Ok(buffered_response(resp));
+        }
+
+        // `100 Continue` is sent when the body is read first time, so all checks which don't need body
+        // should be done before it, to reject request without transferring its body
+        if let Some(expect) = req.headers().get(EXPECT) {
+            if !expect.as_bytes().eq_ignore_ascii_case(b"100-continue") {
+                error!(
+                    "{req_id}: rejected, unsupported expectation: {expect:?}, listener: {}",
+                    listener_cfg.id()
+                );
+                let resp = response_cfg.empty_response(StatusCode::EXPECTATION_FAILED.into())?;
+                return Ok(buffered_response(resp));
+            }
+        }
+        let openapi_operation = match listener_cfg.openapi_spec().map(|s| s.validate_head(&req)) {
+            Some(Err(details)) => return self.openapi_rejection(req_id, details),
+            Some(Ok(operation)) => Some(operation),
+            None => None,
+        };
+
+        // WebSocket session is proxied as is, without buffering
+        if let Some(ws_cfg) = listener_cfg.websocket() {
+            if websocket::is_upgrade_request(&req) {
+                let resp = self.handle_websocket(req_id, addr, req, ws_cfg).await?;
+                return Ok(buffered_response(resp));
+            }
+        }
+
+        // Return cached response if it's present
+        let cache_key = self.cache.as_ref().and_then(|cache| cache.key(&req));
+        if let (Some(cache), Some(key)) = (&self.cache, &cache_key) {
+            if let Some(resp) = cache.get(key).await {
+                info!(
+                    "{req_id}: completed from cache, status={}",
+                    resp.status().as_u16()
+                );
+                let resp = self.compress_response(resp, req.headers()).await;
+                return Ok(buffered_response(resp));
+            }
+        }
+
+        // Return response of the previous request with the same idempotency key
+        let deduplication_guard = match &self.deduplicator {
+            Some(deduplicator) => match deduplicator.check(&req).await {
+                Deduplication::Duplicate(resp) => {
+                    info!(
+                        "{req_id}: completed as duplicate, status={}",
+                        resp.status().as_u16()
+                    );
+                    let resp = self.compress_response(resp, req.headers()).await;
+                    return Ok(buffered_response(resp));
+                }
+                Deduplication::First(guard) => Some(guard),
+                Deduplication::Skipped => None,
+            },
+            None => None,
+        };
+
+        // Identical GET requests in flight share response of the first one
+        let coalescing_guard = match &self.coalescer {
+            Some(coalescer) => match coalescer.check(&req).await {
+                Coalescing::Coalesced(resp) => {
+                    info!(
+                        "{req_id}: completed as coalesced, status={}",
+                        resp.status().as_u16()
+                    );
+                    let resp = self.compress_response(resp, req.headers()).await;
+                    return Ok(buffered_response(resp));
+                }
+                Coalescing::Leader(guard) => Some(guard),
+                Coalescing::Skipped => None,
+            },
+            None => None,
+        };
+
+        // Inject listener's faults before forwarding request to any target
+        if let Some(faults) = listener_cfg.faults() {
+            if let Some(delay) = faults.delay() {
+                info!("{req_id}: fault injected, delay={delay:?}");
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(status) = faults.abort() {
+                info!("{req_id}: fault injected, abort status={}", status.as_u16());
+                let resp = Self::fault_response(response_cfg, status)?;
+                return Ok(buffered_response(resp));
+            }
         }
 
         // Prepare owned body
-        let (req_parts, req_body) = req.into_parts();
-        let body_bytes = req_body
-            .collect()
-            .await
-            .expect("Looks like a BUG!")
-            .to_bytes();
+        let (mut req_parts, req_body) = req.into_parts();
+        let (mut body_bytes, trailers) =
+            buffer_pool::collect(self.buffer_pool.as_deref(), req_body)
+                .await
+                .expect("Looks like a BUG!");
+        // Trailers of chunked uploads are dropped unless they're forwarded to targets
+        let trailers = listener_cfg
+            .forwarding()
+            .filter(|forwarding| forwarding.trailers())
+            .and(trailers);
+        if let Some(record) = audit {
+            record.set_body(&body_bytes);
+        }
+        // Compressed body is inspected decompressed, targets get the original one unless it's configured otherwise
+        let decompression = listener_cfg.request_decompression();
+        let mut compressed_body = None;
+        if let Some(cfg) = decompression {
+            match compression::decompress_request(&req_parts.headers, &body_bytes, cfg.max_size()) {
+                Ok(Some((decoded, encoding))) => {
+                    debug!("{req_id}: request body is decompressed from `{encoding}`");
+                    if cfg.forward() == RequestBodyForward::Decompressed {
+                        req_parts.headers.remove(CONTENT_ENCODING);
+                        req_parts.headers.remove(CONTENT_LENGTH);
+                    }
+                    let original = std::mem::replace(&mut body_bytes, decoded);
+                    compressed_body = Some((original, body_bytes.clone(), encoding));
+                }
+                Ok(None) => {}
+                Err((status, e)) => {
+                    error!(
+                        "{req_id}: rejected, unable to decompress request body: {e}, listener: {}",
+                        listener_cfg.id()
+                    );
+                    let resp = response_cfg.empty_response(status.into())?;
+                    return Ok(buffered_response(resp));
+                }
+            }
+        }
+        if let Some(logger) = &self.body_logger {
+            // Decompressed body is logged as plain one
+            let mut headers = Cow::Borrowed(&req_parts.headers);
+            if compressed_body.is_some() {
+                headers.to_mut().remove(CONTENT_ENCODING);
+            }
+            if let Some(body) = logger.request_body(&headers, &body_bytes) {
+                info!("{req_id}: request body: {body}");
+            }
+        }
+        // Requests which don't conform to the contract are rejected before any target is called
+        if let (Some(spec), Some(operation)) = (listener_cfg.openapi_spec(), &openapi_operation) {
+            if let Err(details) = spec.validate_body(operation, &req_parts.headers, &body_bytes) {
+                return self.openapi_rejection(req_id, details);
+            }
+        }
+        // Variables of the enrichment are added to the request's context, so all stages see them
+        let mut enrichment = ContextMap::new();
+        if let (Some(enricher), Some(cfg)) = (&self.enricher, listener_cfg.enrichment()) {
+            if let Some(key) = cfg.key(&req_parts.headers, &body_bytes) {
+                match enricher.lookup(&key).await {
+                    Ok(vars) => enrichment = vars,
+                    Err(e) if cfg.on_error() == EnrichmentErrorAction::Reject => {
+                        error!(
+                            "{req_id}: rejected, unable to enrich request: {e}, listener: {}",
+                            listener_cfg.id()
+                        );
+                        let resp = response_cfg.empty_response(StatusCode::BAD_GATEWAY.as_u16())?;
+                        return Ok(buffered_response(resp));
+                    }
+                    Err(e) => warn!("{req_id}: unable to enrich request: {e}"),
+                }
+            }
+        }
+        // Stages which precede plugins in the listener's pipeline see request as it's received,
+        // so input of conditions is taken before plugins change the request
+        let pipeline = listener_cfg.pipeline();
+        let body_codec = listener_cfg.body_codec();
+        let mut headers_applied = false;
+        let mut condition_snapshot = None;
+        for stage in pipeline.before(PipelineStage::Plugins) {
+            match stage {
+                PipelineStage::Headers => {
+                    self.transform_request_headers(&addr, &mut req_parts, &enrichment);
+                    headers_applied = true;
+                }
+                PipelineStage::Conditions if listener_cfg.has_filter_conditions() => {
+                    let ctx = self.request_ctx(&addr, &req_parts, &enrichment);
+                    condition_snapshot = Some(ConditionInput::snapshot(
+                        &ctx,
+                        &req_parts,
+                        body_codec.decode(&req_parts.headers, &body_bytes),
+                    ));
+                }
+                _ => {}
+            }
+        }
+        // Plugins may change request or respond instead of targets
+        if let Some(plugins) = &self.plugins {
+            if let Some(resp) = plugins.on_request(&mut req_parts, &mut body_bytes) {
+                info!(
+                    "{req_id}: completed by plugin, status={}",
+                    resp.status().as_u16()
+                );
+                return Ok(buffered_response(resp));
+            }
+        }
+        // Body changed by plugins is compressed back to send it in the original encoding
+        let target_body = match (compressed_body, decompression.map(|cfg| cfg.forward())) {
+            (Some((original, decoded, encoding)), Some(RequestBodyForward::Original)) => {
+                if decoded == body_bytes {
+                    original
+                } else {
+                    match compression::encode(encoding, &body_bytes) {
+                        Ok(encoded) => {
+                            req_parts.headers.remove(CONTENT_LENGTH);
+                            encoded
+                        }
+                        Err(e) => {
+                            warn!("{req_id}: unable to compress request body back, send it decompressed: {e}");
+                            req_parts.headers.remove(CONTENT_ENCODING);
+                            req_parts.headers.remove(CONTENT_LENGTH);
+                            body_bytes.clone()
+                        }
+                    }
+                }
+            }
+            _ => body_bytes.clone(),
+        };
+        let forwarded_body = RequestBody::with_trailers(target_body.clone(), trailers);
+        // Listener's headers are applied to the request itself if conditions should see them
+        if !headers_applied && pipeline.is_before(PipelineStage::Headers, PipelineStage::Conditions)
+        {
+            self.transform_request_headers(&addr, &mut req_parts, &enrichment);
+            headers_applied = true;
+        }
         // Add own context - listener + request
-        let ctx = self
-            .root_ctx
-            .with_request(&addr, &req_parts, self.listener_cfg.id());
+        let ctx = self.request_ctx(&addr, &req_parts, &enrichment);
 
         // Prepare new headers
         let mut headers = req_parts.headers.clone();
         headers.remove(HOST);
-        if let Some(transforms) = self.listener_cfg.headers() {
+        // Body is buffered already, so the expectation has been met by the listener itself
+        headers.remove(EXPECT);
+        let mut explanation = response_cfg
+            .explain()
+            .is_requested(&req_parts.headers)
+            .then(|| Explanation::new(listener_cfg.strategy()));
+        if response_cfg.explain() != ExplainMode::Never {
+            headers.remove(EXPLAIN_HEADER);
+        }
+        // Body is the same for all targets, so it's hashed once; client's values are replaced
+        if listener_cfg.body_annotations() {
+            let sha256 = format!("{:x}", Sha256::digest(&target_body));
+            headers.insert(BODY_SHA256_HEADER, HeaderValue::from_str(&sha256)?);
+            headers.insert(BODY_LENGTH_HEADER, HeaderValue::from(target_body.len()));
+        }
+        if let Some(transforms) = listener_cfg.headers().filter(|_| !headers_applied) {
             transforms.transform(&mut headers, &ctx)
         }
-        debug!("request headers: {:?}", headers);
+        debug!("request headers: {:?}", redaction::headers(&headers));
 
         // Process targets
         debug!(
             "Listener={}, strategy={}",
-            self.listener_cfg.id(),
-            self.listener_cfg.strategy()
+            listener_cfg.id(),
+            listener_cfg.strategy()
         );
 
-        let mut target_requests = vec![];
-        let mut target_ctx = vec![];
-        let mut target_ids = vec![];
-
         let mut targets: Vec<&TargetConfig> = vec![];
+        let mut chained: Vec<&TargetConfig> = vec![];
         let mut conditional_target_id: Option<String> = None;
+        // Tenant's group limits targets of the request, unknown tenant without default group has no targets
+        let tenant_group = listener_cfg
+            .tenants()
+            .map(|tenants| tenants.group(&req_parts.headers));
 
-        // Verify conditions
-        for target in self.listener_cfg.targets() {
-            match &self.listener_cfg.strategy() {
-                // Special flow in case of conditional routing
-                ResponseStrategy::ConditionalRouting => {
-                    match target.condition().as_ref().unwrap() {
-                        // Always insert default into empty targets list
-                        TargetConditionConfig::Default => {
-                            if targets.is_empty() {
-                                targets.push(target)
-                            }
+        // Verify conditions, input of conditions is built on demand and shared by all targets;
+        // it isn't `Send`, so it's dropped before any await point
+        {
+            let mut condition_input = condition_snapshot.map(ConditionInput::from);
+            for target in listener_cfg.targets() {
+                if let Some(group) = tenant_group {
+                    if !group.is_some_and(|group| group.contains(&target.id())) {
+                        debug!("{req_id}: target `{}` isn't in tenant's group", target.id());
+                        if let Some(explanation) = &mut explanation {
+                            explanation.add_target(&target.id(), "other_tenant", None);
                         }
-                        TargetConditionConfig::Filter(_) => {
-                            if target.check_condition(&ctx, &req_parts, &body_bytes) {
+                        continue;
+                    }
+                }
+                // Target which doesn't accept the body is skipped as if its condition is false
+                if !target.accepts_body(&headers, target_body.len()) {
+                    debug!(
+                        "{req_id}: target `{}` skipped by body filters, size={}",
+                        target.id(),
+                        target_body.len()
+                    );
+                    if let Some(explanation) = &mut explanation {
+                        explanation.add_target(&target.id(), "filtered", None);
+                    }
+                    continue;
+                }
+                if self
+                    .samplers
+                    .get(&target.id())
+                    .is_some_and(|sampler| !sampler.sample())
+                {
+                    debug!("{req_id}: target `{}` isn't sampled", target.id());
+                    if let Some(explanation) = &mut explanation {
+                        explanation.add_target(&target.id(), "not_sampled", None);
+                    }
+                    continue;
+                }
+                match &listener_cfg.strategy() {
+                    // Special flow in case of conditional routing
+                    ResponseStrategy::ConditionalRouting => {
+                        match target.condition().as_ref().unwrap() {
+                            // Always insert default into empty targets list
+                            TargetConditionConfig::Default => {
+                                if let Some(explanation) = &mut explanation {
+                                    explanation.add_default_condition(&target.id());
+                                }
                                 if targets.is_empty() {
                                     targets.push(target)
-                                } else if matches!(
-                                    targets[0].condition().as_ref().unwrap(),
-                                    TargetConditionConfig::Default
-                                ) {
-                                    // Replace default by this target
-                                    targets.pop();
-                                    targets.push(target);
-                                } else {
-                                    // Error - more than one target has true condition
-                                    error!("{req_id}: not routed: more than one targets satisfy condition, listener: {}, targets: `{}` and `{}`", self.listener_cfg.id(), targets[0].id(), target.id());
-                                    return response_cfg.no_target_response(&ctx);
                                 }
                             }
+                            TargetConditionConfig::Filter(_) => {
+                                let allowed = target.check_condition(
+                                    condition_input.get_or_insert_with(|| {
+                                        ConditionInput::new(
+                                            &ctx,
+                                            &req_parts,
+                                            body_codec.decode(&req_parts.headers, &body_bytes),
+                                        )
+                                    }),
+                                );
+                                if let Some(explanation) = &mut explanation {
+                                    explanation.add_condition(&target.id(), allowed);
+                                }
+                                if allowed {
+                                    if targets.is_empty() {
+                                        targets.push(target)
+                                    } else if matches!(
+                                        targets[0].condition().as_ref().unwrap(),
+                                        TargetConditionConfig::Default
+                                    ) {
+                                        // Replace default by this target
+                                        targets.pop();
+                                        targets.push(target);
+                                    } else {
+                                        // Error - more than one target has true condition
+                                        error!("{req_id}: not routed: more than one targets satisfy condition, listener: {}, targets: `{}` and `{}`", listener_cfg.id(), targets[0].id(), target.id());
+                                        let resp = response_cfg.no_target_response(&ctx)?;
+                                        return Ok(buffered_response(resp));
+                                    }
+                                }
+                            }
+                        };
+                        if !targets.is_empty() {
+                            conditional_target_id = Some(targets[0].id())
                         }
-                    };
-                    if !targets.is_empty() {
-                        conditional_target_id = Some(targets[0].id())
                     }
-                }
-                // Any other strategy
-                _ => {
-                    if let Some(condition) = target.condition().as_ref() {
-                        match condition {
-                            TargetConditionConfig::Default => targets.push(target),
-                            TargetConditionConfig::Filter(_) => {
-                                if target.check_condition(&ctx, &req_parts, &body_bytes) {
+                    // Chained target is requested after its preceding target has responded
+                    _ if target.after().is_some() => chained.push(target),
+                    // Any other strategy
+                    _ => {
+                        if let Some(condition) = target.condition().as_ref() {
+                            match condition {
+                                TargetConditionConfig::Default => {
+                                    if let Some(explanation) = &mut explanation {
+                                        explanation.add_default_condition(&target.id());
+                                    }
                                     targets.push(target)
                                 }
+                                TargetConditionConfig::Filter(_) => {
+                                    let allowed = target.check_condition(
+                                        condition_input.get_or_insert_with(|| {
+                                            ConditionInput::new(
+                                                &ctx,
+                                                &req_parts,
+                                                body_codec.decode(&req_parts.headers, &body_bytes),
+                                            )
+                                        }),
+                                    );
+                                    if let Some(explanation) = &mut explanation {
+                                        explanation.add_condition(&target.id(), allowed);
+                                    }
+                                    if allowed {
+                                        targets.push(target)
+                                    }
+                                }
                             }
+                        } else {
+                            targets.push(target);
                         }
-                    } else {
-                        targets.push(target);
                     }
                 }
             }
@@ -153,98 +794,336 @@ impl RequestHandler {
         if targets.is_empty() {
             warn!(
                 "{req_id}: no targets satisfy conditions, listener: {}",
-                self.listener_cfg.id()
+                listener_cfg.id()
             );
         }
 
-        for target in targets.iter() {
-            let ctx = ctx.with_target(target);
-            let target_request_builder = Request::builder();
-            // Set method
-            let target_request_builder = target_request_builder.method(&req_parts.method);
-            // Set uri
-            let url = env_with_context_no_errors(target.url(), |v| ctx.get(&v.into()));
-            let uri: Uri = url.parse()?;
-            let mut target_request_builder = target_request_builder.uri(uri);
-            // Prepare headers
-            let mut headers = headers.clone();
-            if let Some(transforms) = &target.headers() {
-                transforms.transform(&mut headers, &ctx);
-            }
-            // Add Host header if empty
-            if !headers.contains_key(HOST) {
-                let uri: Uri = url.parse()?;
-                let host = uri
-                    .host()
-                    .unwrap_or_else(|| panic!("there is no `host` part in the URI: {uri:?}"));
-
-                debug!("add host header: {host}");
-                headers.insert(HOST, HeaderValue::from_str(host)?);
+        // Target which response is selected by strategy regardless of its status
+        let selected_target_id = match listener_cfg.strategy() {
+            ResponseStrategy::ConditionalRouting => conditional_target_id.clone(),
+            ResponseStrategy::PreferredThenFallback => {
+                response_cfg.preferred_target_id().map(String::from)
             }
-            // Insert all headers into request
-            for (k, v) in &headers {
-                target_request_builder = target_request_builder.header(k, v);
+            _ => match &self.selector {
+                Some(selector) => selector.select(&targets),
+                None => tenant_group
+                    .flatten()
+                    .and_then(|group| group.target_selector())
+                    .or(response_cfg.target_selector().as_ref())
+                    .cloned(),
+            },
+        };
+
+        // Range is forwarded to the primary target only, mirrors are requested for the full body or skipped
+        let range_primary = listener_cfg
+            .ranges()
+            .filter(|_| ranges::is_ranged(&headers))
+            .and_then(|cfg| {
+                cfg.primary()
+                    .map(String::from)
+                    .or_else(|| selected_target_id.clone())
+            });
+        let mut full_headers = None;
+        if let (Some(primary), Some(cfg)) = (&range_primary, listener_cfg.ranges()) {
+            match cfg.mirrors() {
+                MirrorRanges::Full => {
+                    let mut headers = headers.clone();
+                    ranges::strip_range_headers(&mut headers);
+                    full_headers = Some(headers);
+                }
+                MirrorRanges::Skip => {
+                    for target in targets.iter().chain(&chained) {
+                        if target.id() != *primary {
+                            debug!("{req_id}: target `{}` skipped by range", target.id());
+                            if let Some(explanation) = &mut explanation {
+                                explanation.add_target(&target.id(), "range_skipped", None);
+                            }
+                        }
+                    }
+                    targets.retain(|target| target.id() == *primary);
+                    chained.retain(|target| target.id() == *primary);
+                }
             }
-            // Finalize request with body
-            let target_request: Request<Full<Bytes>> = if let Some(body) = &target.body() {
-                let body = env_with_context_no_errors(body, |v| ctx.get(&v.into()));
-                target_request_builder.body(Full::from(body))?
-            } else {
-                target_request_builder.body(Full::from(body_bytes.clone()))?
-            };
+        } else if listener_cfg.ranges().is_some() && ranges::is_ranged(&headers) {
+            debug!("{req_id}: there is no primary target, range is forwarded to all targets");
+        }
+        let is_full_content =
+            |target_id: &str| full_headers.is_some() && range_primary.as_deref() != Some(target_id);
+        let target_headers = |target_id: &str| match &full_headers {
+            Some(full_headers) if is_full_content(target_id) => full_headers,
+            _ => &headers,
+        };
 
-            // Put request to queue
-            debug!(
-                "add to queue: target `{}` request: {:?}",
-                target.id(),
-                target_request
-            );
+        // Chained targets are placed after the ones to request immediately,
+        // contexts of all targets are prepared in advance since responses refer to them
+        let first_wave = targets.len();
+        targets.extend(chained);
+        let target_ctx: Vec<Context> = targets.iter().map(|t| ctx.with_target(t)).collect();
+        let target_ids: Vec<String> = targets.iter().map(|t| t.id()).collect();
+        let mut target_requests = vec![];
+        for (target, ctx) in targets.iter().zip(&target_ctx).take(first_wave) {
+            target_requests.push(self.spawn_target_request(
+                req_id,
+                target,
+                ctx,
+                &req_parts,
+                target_headers(&target.id()),
+                &forwarded_body,
+            )?);
+        }
 
-            // Prepare target request
-            let http_client = target.https_client(self.listener_cfg.tls());
-            let http_request = http_client.request(target_request);
-            let http_request = tokio::time::timeout(*target.timeout(), http_request);
+        let cancel_unneeded_targets = response_cfg.cancel_unneeded_targets();
+        let streaming = listener_cfg.streaming();
+        let streamed_target_id = selected_target_id
+            .as_ref()
+            .filter(|_| listener_cfg.is_selected_response_passed());
+        let mut abort_handles: Vec<AbortHandle> =
+            target_requests.iter().map(|r| r.abort_handle()).collect();
+        let mut pending: FuturesUnordered<_> = target_requests
+            .into_iter()
+            .enumerate()
+            .map(|(pos, r)| join_target(pos, r))
+            .collect();
 
-            target_requests.push(tokio::spawn(http_request));
-            target_ctx.push(ctx);
-            target_ids.push(target.id());
-        }
-
-        // Get results
-        let raw_results = join_all(target_requests).await;
-        let mut results: Vec<ResponseResult> = vec![];
-        for r in raw_results.into_iter().map(|r| r.unwrap()) {
-            let r = match r {
-                Err(_ee) => ResponseResult::Timeout,
-                Ok(r) => match r {
-                    Ok(r) => {
-                        // Prepare owned body
-                        let (parts, body) = r.into_parts();
-                        let body = body.collect().await.expect("Looks like a BUG!").to_bytes();
-                        let r: Response<Full<Bytes>> =
-                            Response::from_parts(parts, Full::from(body));
-                        ResponseResult::Ok(r)
+        // Get and pre-process results as soon as each of them is completed
+        let mut responses: ResponsesMap = ResponsesMap::new();
+        let mut streamed_body: Option<IdleTimeoutBody> = None;
+        let mut outcomes: Vec<TargetOutcome> = target_ids
+            .iter()
+            .enumerate()
+            .map(|(pos, id)| {
+                if pos < first_wave {
+                    TargetOutcome::cancelled(id)
+                } else {
+                    TargetOutcome::skipped(id)
+                }
+            })
+            .collect();
+        // Response is decided with the targets which have responded before the deadline
+        let max_wait_deadline = response_cfg
+            .max_wait()
+            .map(|max_wait| tokio::time::Instant::now() + max_wait);
+        let mut max_wait_exceeded = false;
+        loop {
+            let next = match max_wait_deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline, pending.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        if cancel_unneeded_targets {
+                            info!(
+                                "{req_id}: max wait is exceeded, {} pending target(s) cancelled",
+                                pending.len()
+                            );
+                            abort_handles.iter().for_each(AbortHandle::abort);
+                        } else {
+                            info!(
+                                "{req_id}: max wait is exceeded, {} pending target(s) are completed in background",
+                                pending.len()
+                            );
+                            max_wait_exceeded = true;
+                        }
+                        break;
                     }
-                    Err(he) => ResponseResult::HyperError(he),
                 },
+                None => pending.next().await,
+            };
+            let Some((pos, r)) = next else {
+                break;
+            };
+            let (r, duration) = r.unwrap();
+            // Shed request and skipped unresolved target aren't the target's failures,
+            // so they don't affect the target's health
+            let neutral_status = match r {
+                TargetResponse::Shed => Some("shed"),
+                TargetResponse::Unresolved => Some("unresolved"),
+                _ => None,
+            };
+            let mut res = match r {
+                TargetResponse::Timeout(phase) => ResponseResult::Timeout(phase),
+                TargetResponse::Unavailable | TargetResponse::Unresolved => {
+                    ResponseResult::Unavailable
+                }
+                TargetResponse::Aborted(status) => {
+                    ResponseResult::Ok(Self::fault_response(response_cfg, status)?)
+                }
+                TargetResponse::Rejected(status) => {
+                    ResponseResult::Ok(response_cfg.empty_response(status.as_u16())?)
+                }
+                TargetResponse::Shed => ResponseResult::Ok(
+                    response_cfg.empty_response(StatusCode::SERVICE_UNAVAILABLE.as_u16())?,
+                ),
+                TargetResponse::Received(Ok(r))
+                    if streaming && selected_target_id.as_ref() == Some(&target_ids[pos]) =>
+                {
+                    // Body of the selected target is passed to the requester as is
+                    let (mut parts, body) = r.into_parts();
+                    if let Some(cookies) = targets[pos].cookies() {
+                        cookies.transform_response(&mut parts.headers);
+                    }
+                    let body = ThrottledBody::new(body, targets[pos].bandwidth_limit());
+                    streamed_body = Some(IdleTimeoutBody::new(body, targets[pos].read_timeout()));
+                    ResponseResult::Ok(Response::from_parts(parts, Full::default()))
+                }
+                TargetResponse::Received(Ok(r)) => {
+                    // Prepare owned body, the rest of the total timeout is applied to it
+                    let target = targets[pos];
+                    let (mut parts, body) = r.into_parts();
+                    if let Some(cookies) = target.cookies() {
+                        cookies.transform_response(&mut parts.headers);
+                    }
+                    let body = IdleTimeoutBody::new(
+                        ThrottledBody::new(body, target.bandwidth_limit()),
+                        target.read_timeout(),
+                    );
+                    let pool = self.buffer_pool.as_deref();
+                    let limit = target.max_response_size().unwrap_or(usize::MAX);
+                    let collect = buffer_pool::collect_limited(pool, body, limit);
+                    let body = match target.total_timeout() {
+                        Some(total_timeout) => {
+                            tokio::time::timeout(total_timeout.saturating_sub(duration), collect)
+                                .await
+                                .unwrap_or(Err(StreamError::TotalTimeout(total_timeout)))
+                        }
+                        None => collect.await,
+                    };
+                    // Oversized body is cut to the limit, the rest of it is streamed to the requester
+                    // if the target's response is selected and it's allowed by the target's action
+                    let mut streamed_through = false;
+                    let body = match body {
+                        Ok(Collected::Complete(body, trailers)) => Ok((body, trailers)),
+                        Ok(Collected::Oversized(data, rest)) => {
+                            let action = target.on_oversized_response();
+                            warn!(
+                                "{req_id}: target `{}` response body is larger than {limit} bytes, action: {action:?}",
+                                target.id()
+                            );
+                            let truncated = data.slice(..limit);
+                            match action {
+                                OversizedResponseAction::Fail => Err(StreamError::Oversized(limit)),
+                                OversizedResponseAction::StreamThrough
+                                    if streamed_target_id == Some(&target_ids[pos]) =>
+                                {
+                                    streamed_through = true;
+                                    streamed_body = Some(rest.with_prefix(data));
+                                    Ok((truncated, None))
+                                }
+                                OversizedResponseAction::StreamThrough => {
+                                    parts.headers.remove(CONTENT_LENGTH);
+                                    tokio::spawn(drain_body(rest).in_current_span());
+                                    Ok((truncated, None))
+                                }
+                                OversizedResponseAction::Truncate => {
+                                    parts.headers.remove(CONTENT_LENGTH);
+                                    Ok((truncated, None))
+                                }
+                            }
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match body {
+                        Ok((mut body, trailers)) => {
+                            if let Some(trailers) = trailers {
+                                parts.extensions.insert(Trailers(trailers));
+                            }
+                            // Decompress body to pass it uncompressed or to compress it back later,
+                            // streamed body is passed as is
+                            let decompression = target.compression().response();
+                            if decompression != ResponseDecompression::Pass && !streamed_through {
+                                let encoding;
+                                (body, encoding) =
+                                    compression::decompress(&mut parts.headers, body);
+                                if let (Some(encoding), ResponseDecompression::Recompress) =
+                                    (encoding, decompression)
+                                {
+                                    parts.extensions.insert(Decompressed(encoding));
+                                }
+                            }
+                            if let Some(logged) = self
+                                .body_logger
+                                .as_ref()
+                                .and_then(|logger| logger.response_body(&parts.headers, &body))
+                            {
+                                info!("{req_id}: target `{}` response body: {logged}", target.id());
+                            }
+                            let resp = Response::from_parts(parts, Full::from(body));
+                            ResponseResult::Ok(match &self.plugins {
+                                Some(plugins) => plugins.on_target_response(&target_ids[pos], resp),
+                                None => resp,
+                            })
+                        }
+                        Err(StreamError::IdleTimeout(_)) => {
+                            ResponseResult::Timeout(TimeoutPhase::Read)
+                        }
+                        Err(StreamError::TotalTimeout(_)) => {
+                            ResponseResult::Timeout(TimeoutPhase::Total)
+                        }
+                        Err(StreamError::Oversized(_)) => ResponseResult::Ok(
+                            response_cfg.empty_response(StatusCode::BAD_GATEWAY.as_u16())?,
+                        ),
+                        Err(e @ StreamError::Target(_)) => {
+                            warn!(
+                                "{req_id}: target `{}` response body failed: {e}",
+                                target.id()
+                            );
+                            ResponseResult::Ok(
+                                response_cfg.empty_response(StatusCode::BAD_GATEWAY.as_u16())?,
+                            )
+                        }
+                    }
+                }
+                TargetResponse::Received(Err(he)) if is_connect_timeout(&he) => {
+                    ResponseResult::Timeout(TimeoutPhase::Connect)
+                }
+                TargetResponse::Received(Err(he)) => ResponseResult::HyperError(he),
             };
-            results.push(r);
-        }
 
-        // Pre-process results
-        let mut responses: ResponsesMap = ResponsesMap::new();
-        for (pos, res) in results.into_iter().enumerate() {
+            if let ResponseResult::Ok(resp) = &mut res {
+                resp.extensions_mut().insert(Elapsed(duration));
+                if is_full_content(&target_ids[pos]) {
+                    resp.extensions_mut().insert(FullContent);
+                }
+                if let Some(deprecation) = self.deprecations.get(&target_ids[pos]) {
+                    deprecation.add_headers(resp.headers_mut());
+                }
+            }
+            outcomes[pos].complete(&res, duration);
+            if let Some(status) = neutral_status {
+                outcomes[pos].status = status.into();
+            } else {
+                self.record_target_result(targets[pos], &res, duration);
+            }
             match res {
                 ResponseResult::Ok(resp) => {
-                    debug!("OK response: {:#?}", resp);
+                    debug!(
+                        "OK response: status={}, headers: {:#?}",
+                        resp.status(),
+                        redaction::headers(resp.headers())
+                    );
                     responses.insert(target_ids[pos].clone(), (Some(resp), &target_ctx[pos]));
                 }
-                ResponseResult::HyperError(_) | ResponseResult::Timeout => {
+                ResponseResult::HyperError(_)
+                | ResponseResult::Timeout(_)
+                | ResponseResult::Unavailable => {
                     debug!("ERR response: {:#?}", res);
                     let target = targets[pos];
                     let resp = match target.on_error() {
+                        // Skipped target's response is dropped regardless of its `on_error`
+                        _ if neutral_status.is_some() => None,
                         TargetOnErrorAction::Propagate => {
-                            Some(response_cfg.error_response(res, &None))
+                            let timed_out = matches!(res, ResponseResult::Timeout(_));
+                            let status = match &res {
+                                ResponseResult::Timeout(phase) => {
+                                    target.timeout_status().status(*phase)
+                                }
+                                _ => None,
+                            };
+                            let mut resp = response_cfg.error_response(res, &status);
+                            if timed_out {
+                                target
+                                    .timeout_status()
+                                    .update_response(&mut resp, &target_ctx[pos]);
+                            }
+                            Some(resp)
                         }
                         TargetOnErrorAction::Status => {
                             Some(response_cfg.error_response(res, &target.error_status()))
@@ -254,14 +1133,139 @@ impl RequestHandler {
                     responses.insert(target_ids[pos].clone(), (resp, &target_ctx[pos]));
                 }
             }
+
+            // Request chained targets which wait for this one, their conditions are evaluated with its response
+            for next in (first_wave..targets.len())
+                .filter(|next| targets[*next].after() == Some(target_ids[pos].as_str()))
+            {
+                let target = targets[next];
+                let allowed = match target.condition() {
+                    Some(TargetConditionConfig::Filter(_)) => {
+                        let resp = responses
+                            .get(&target_ids[pos])
+                            .and_then(|(resp, _)| resp.as_ref());
+                        target.check_condition(
+                            &ConditionInput::new(
+                                &ctx,
+                                &req_parts,
+                                body_codec.decode(&req_parts.headers, &body_bytes),
+                            )
+                            .with_response(resp),
+                        )
+                    }
+                    _ => true,
+                };
+                if let (Some(explanation), Some(TargetConditionConfig::Filter(_))) =
+                    (&mut explanation, target.condition())
+                {
+                    explanation.add_condition(&target.id(), allowed);
+                }
+                if !allowed {
+                    debug!("{req_id}: chained target `{}` skipped", target.id());
+                    continue;
+                }
+                debug!(
+                    "{req_id}: chained target `{}` requested after `{}`",
+                    target.id(),
+                    target_ids[pos]
+                );
+                let request = self.spawn_target_request(
+                    req_id,
+                    target,
+                    &target_ctx[next],
+                    &req_parts,
+                    target_headers(&target_ids[next]),
+                    &forwarded_body,
+                )?;
+                abort_handles.push(request.abort_handle());
+                outcomes[next] = TargetOutcome::cancelled(&target_ids[next]);
+                pending.push(join_target(next, request));
+            }
+
+            if (cancel_unneeded_targets || streaming)
+                && !pending.is_empty()
+                && response_cfg.is_response_determined(
+                    listener_cfg.strategy(),
+                    &responses,
+                    listener_cfg.targets(),
+                    &selected_target_id,
+                )
+            {
+                if cancel_unneeded_targets {
+                    info!(
+                        "{req_id}: response is determined, {} pending target(s) cancelled",
+                        pending.len()
+                    );
+                    abort_handles.iter().for_each(AbortHandle::abort);
+                } else {
+                    // Streamed response shouldn't wait for other targets
+                    debug!(
+                        "{req_id}: response is determined, {} pending target(s) aren't awaited",
+                        pending.len()
+                    );
+                }
+                break;
+            }
+        }
+
+        // Targets which haven't responded before the deadline aren't dropped, so their responses are read to the end
+        if max_wait_exceeded && !pending.is_empty() {
+            tokio::spawn(
+                self.clone()
+                    .complete_pending(req_id, pending, target_ids.clone())
+                    .in_current_span(),
+            );
+        }
+
+        if let Some(record) = audit {
+            for outcome in &outcomes {
+                record.add_target(&outcome.id, &outcome.status, outcome.duration);
+            }
+        }
+        if let Some(explanation) = &mut explanation {
+            for outcome in &outcomes {
+                explanation.add_target(&outcome.id, &outcome.status, outcome.duration);
+            }
+        }
+
+        if let Some(comparison) = &self.comparison {
+            comparison.compare(
+                &req_id,
+                &req_parts.method,
+                &req_parts.uri,
+                &responses,
+                listener_cfg.targets(),
+            );
         }
 
         // Select/create response according to strategy
-        let ok_target_id = response_cfg.find_first_response(&responses, ResponseKind::Ok);
-        let failed_target_id = response_cfg.find_first_response(&responses, ResponseKind::Failed);
-        let selector_target_id = response_cfg.target_selector().clone();
-        let resp =
-            match &self.listener_cfg.strategy() {
+        let ok_target_id = match listener_cfg.strategy() {
+            ResponseStrategy::PreferredThenFallback => {
+                response_cfg.find_preferred_response(&responses, listener_cfg.targets())
+            }
+            _ => response_cfg.find_first_response(
+                &responses,
+                listener_cfg.targets(),
+                ResponseKind::Ok,
+            ),
+        };
+        let failed_target_id = response_cfg.find_first_response(
+            &responses,
+            listener_cfg.targets(),
+            ResponseKind::Failed,
+        );
+        let selector_target_id = selected_target_id.clone();
+        if let Some(explanation) = &mut explanation {
+            explanation.select(
+                listener_cfg.strategy(),
+                &ok_target_id,
+                &failed_target_id,
+                &selected_target_id,
+                response_cfg.target_selection(),
+            );
+        }
+        let mut resp =
+            match &listener_cfg.strategy() {
                 ResponseStrategy::AlwaysOverride => {
                     response_cfg.override_empty_response(StatusCode::OK.into(), &ctx)?
                 }
@@ -301,18 +1305,835 @@ impl RequestHandler {
                 ),
                 ResponseStrategy::ConditionalRouting => response_cfg
                     .select_target_or_error_response(conditional_target_id, &mut responses, &ctx),
+                ResponseStrategy::Script => {
+                    response_cfg.select_by_script_response(&mut responses, &ctx)
+                }
+                ResponseStrategy::Custom => response_cfg.select_by_custom_strategy_response(
+                    &mut responses,
+                    listener_cfg.targets(),
+                    &ctx,
+                ),
+                ResponseStrategy::PreferredThenFallback => response_cfg
+                    .select_from_two_targets_response(
+                        ok_target_id,
+                        selector_target_id,
+                        &mut responses,
+                        &ctx,
+                    ),
             };
 
+        response_cfg.copy_headers(&responses, &mut resp);
+        if response_cfg.debug_headers() {
+            TargetOutcome::add_headers(&outcomes, &mut resp);
+        }
+        if let Some(explanation) = explanation {
+            let explanation = explanation.to_json();
+            info!("{req_id}: explanation: {explanation}");
+            match HeaderValue::from_str(&explanation) {
+                Ok(value) => {
+                    resp.headers_mut().insert(EXPLAIN_HEADER, value);
+                }
+                Err(_) => warn!("{req_id}: explanation isn't a valid header value"),
+            }
+        }
+
         // Final response
-        debug!("Final response: {:?}", resp);
+        debug!(
+            "Final response: status={}, headers: {:?}",
+            resp.status(),
+            redaction::headers(resp.headers())
+        );
         info!("{req_id}: completed, status={}", resp.status().as_u16());
+        if let Some(body) = streamed_body {
+            // Selected target has responded, so the final response is its one
+            Ok(streamed_response(resp, body))
+        } else {
+            let resp = match &self.plugins {
+                Some(plugins) => plugins.on_response(resp),
+                None => resp,
+            };
+            let resp = compression::recompress_response(resp, &req_parts.headers).await;
+            if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                cache.put(key, &resp).await;
+            }
+            if let Some(guard) = deduplication_guard {
+                guard.complete(&resp).await;
+            }
+            if let Some(guard) = coalescing_guard {
+                guard.complete(&resp).await;
+            }
+            let resp = self.compress_response(resp, &req_parts.headers).await;
+            Ok(buffered_response(resp))
+        }
+    }
+
+    /// Applies listener's headers transformations to the request itself, so the following stages see them
+    fn transform_request_headers(
+        &self,
+        addr: &SocketAddr,
+        req_parts: &mut Parts,
+        enrichment: &ContextMap,
+    ) {
+        if let Some(transforms) = self.listener_cfg().headers() {
+            let ctx = self.request_ctx(addr, req_parts, enrichment);
+            transforms.transform(&mut req_parts.headers, &ctx);
+        }
+    }
+
+    /// Context of the request with variables of its enrichment
+    fn request_ctx(
+        &self,
+        addr: &SocketAddr,
+        req_parts: &Parts,
+        enrichment: &ContextMap,
+    ) -> Context<'_> {
+        let mut ctx = self
+            .root_ctx
+            .with_request(addr, req_parts, self.listener_cfg().id());
+        ctx.extend(enrichment.clone());
+        ctx
+    }
+
+    /// Rejects request which doesn't match listener's OpenAPI spec, details are returned in the JSON body
+    fn openapi_rejection(
+        &self,
+        req_id: Uuid,
+        details: Vec<String>,
+    ) -> Result<Response<ResponseBody>, http::Error> {
+        error!(
+            "{req_id}: rejected, request doesn't match OpenAPI spec: {}, listener: {}",
+            details.join("; "),
+            self.listener_cfg().id()
+        );
+        let body = serde_json::json!({
+            "error": "request doesn't match OpenAPI spec",
+            "details": details,
+        });
+        let resp = Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Full::from(body.to_string()))?;
+
+        Ok(buffered_response(resp))
+    }
+
+    /// Compresses the final response if listener's compression is configured,
+    /// cached and deduplicated responses are kept uncompressed
+    async fn compress_response(
+        &self,
+        resp: Response<Full<Bytes>>,
+        req_headers: &HeaderMap,
+    ) -> Response<Full<Bytes>> {
+        match self.listener_cfg().compression() {
+            Some(cfg) => compression::compress_response(resp, req_headers, cfg).await,
+            None => resp,
+        }
+    }
+
+    /// Establishes WebSocket session with the primary target and mirror targets,
+    /// handshake response of the primary target is returned to the client
+    async fn handle_websocket(
+        &self,
+        req_id: Uuid,
+        addr: SocketAddr,
+        mut req: Request<Incoming>,
+        ws_cfg: &WebSocketConfig,
+    ) -> Result<Response<Full<Bytes>>, http::Error> {
+        let listener_cfg = self.listener_cfg();
+        let response_cfg = listener_cfg.response();
+        let client_upgrade = hyper::upgrade::on(&mut req);
+        let (req_parts, _) = req.into_parts();
+        let ctx = self
+            .root_ctx
+            .with_request(&addr, &req_parts, listener_cfg.id());
+
+        // Prepare new headers, handshake headers are required regardless of transformations
+        let mut headers = req_parts.headers.clone();
+        headers.remove(HOST);
+        if let Some(transforms) = listener_cfg.headers() {
+            transforms.transform(&mut headers, &ctx)
+        }
+        let find_target = |id: &str| {
+            listener_cfg
+                .targets()
+                .iter()
+                .find(|t| t.id() == id)
+                .expect("websocket target should be validated")
+        };
+        let target_request = |target: &TargetConfig, ctx: &Context| {
+            let mut request = Self::target_request(
+                target,
+                ctx,
+                &req_parts,
+                listener_cfg.query(),
+                &headers,
+                &Bytes::new(),
+                None,
+            )?;
+            websocket::restore_upgrade_headers(&req_parts.headers, request.headers_mut());
+            if let Some(proxy_headers) = listener_cfg.proxy_headers() {
+                proxy_headers.apply(request.headers_mut(), req_parts.version);
+            }
+            Ok::<_, http::Error>(request)
+        };
+
+        // Mirrors' handshakes are running in parallel with the primary one
+        let mut mirrors = vec![];
+        for target_id in ws_cfg.mirror() {
+            let target = find_target(target_id);
+            let request = target_request(target, &ctx.with_target(target))?;
+            let http_request = target.https_client(listener_cfg.tls()).request(request);
+            let http_request = tokio::time::timeout(*target.timeout(), http_request);
+            mirrors.push((target.id(), tokio::spawn(http_request.in_current_span())));
+        }
+
+        let primary = find_target(ws_cfg.primary());
+        let request = target_request(primary, &ctx.with_target(primary))?;
+        let http_request = primary.https_client(listener_cfg.tls()).request(request);
+        let error_status = match primary.on_error() {
+            TargetOnErrorAction::Status => primary.error_status(),
+            _ => None,
+        };
+        let mut resp = match tokio::time::timeout(*primary.timeout(), http_request).await {
+            Ok(Ok(resp)) => resp,
+            Ok(Err(he)) => {
+                error!("{req_id}: websocket target `{}` failed: {he}", primary.id());
+                mirrors.iter().for_each(|(_, m)| m.abort());
+                return Ok(
+                    response_cfg.error_response(ResponseResult::HyperError(he), &error_status)
+                );
+            }
+            Err(_) => {
+                error!("{req_id}: websocket target `{}` timed out", primary.id());
+                mirrors.iter().for_each(|(_, m)| m.abort());
+                return Ok(response_cfg.error_response(
+                    ResponseResult::Timeout(TimeoutPhase::FirstByte),
+                    &error_status,
+                ));
+            }
+        };
+
+        // Handshake was rejected by the primary target, so its response is a final one
+        if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+            warn!(
+                "{req_id}: websocket upgrade rejected by target `{}`, status={}",
+                primary.id(),
+                resp.status().as_u16()
+            );
+            mirrors.iter().for_each(|(_, m)| m.abort());
+            let (parts, body) = resp.into_parts();
+            return match body.collect().await {
+                Ok(body) => Ok(Response::from_parts(parts, Full::from(body.to_bytes()))),
+                Err(e) => {
+                    error!(
+                        "{req_id}: websocket target `{}` response body failed: {e}",
+                        primary.id()
+                    );
+                    response_cfg.empty_response(StatusCode::BAD_GATEWAY.as_u16())
+                }
+            };
+        }
+
+        let primary_upgrade = hyper::upgrade::on(&mut resp);
+        tokio::spawn(
+            websocket::proxy_session(req_id, client_upgrade, primary_upgrade, mirrors)
+                .in_current_span(),
+        );
+
+        info!(
+            "{req_id}: websocket session established, target: {}, mirrors: {}",
+            primary.id(),
+            ws_cfg.mirror().len()
+        );
+        let (parts, _) = resp.into_parts();
+        Ok(Response::from_parts(parts, Full::default()))
+    }
+
+    /// Records classified result of the target request to the target's SLO, sampler and selector
+    fn record_target_result(
+        &self,
+        target: &TargetConfig,
+        res: &ResponseResult,
+        duration: Duration,
+    ) {
+        let failed = res.is_failed(self.listener_cfg().response(), target);
+        let target_id = target.id();
+        if let Some(slo) = self.slo.get(&target_id) {
+            slo.record(res.slo_outcome(failed, duration));
+        }
+        if let Some(sampler) = self.samplers.get(&target_id) {
+            sampler.record(failed, duration);
+        }
+        if let Some(selector) = &self.selector {
+            selector.record(&target_id, failed);
+        }
+    }
+
+    /// Completes requests of the targets which aren't awaited because of `max_wait` after the response is sent:
+    /// bodies are read so targets complete their responses, outcomes are logged and recorded to the targets' health;
+    /// targets chained after them aren't requested since the response is already decided
+    async fn complete_pending<F>(
+        self,
+        req_id: Uuid,
+        mut pending: FuturesUnordered<F>,
+        target_ids: Vec<String>,
+    ) where
+        F: Future<Output = (usize, Result<(TargetResponse, Duration), JoinError>)>,
+    {
+        let status_response = |status: StatusCode| {
+            let mut resp = Response::default();
+            *resp.status_mut() = status;
+            ResponseResult::Ok(resp)
+        };
+
+        while let Some((pos, r)) = pending.next().await {
+            let Ok((r, duration)) = r else {
+                continue;
+            };
+            let Some(target) = self
+                .listener_cfg()
+                .targets()
+                .iter()
+                .find(|target| target.id() == target_ids[pos])
+            else {
+                continue;
+            };
+            let neutral_status = match r {
+                TargetResponse::Shed => Some("shed"),
+                TargetResponse::Unresolved => Some("unresolved"),
+                _ => None,
+            };
+            let res = match r {
+                TargetResponse::Timeout(phase) => ResponseResult::Timeout(phase),
+                TargetResponse::Unavailable | TargetResponse::Unresolved => {
+                    ResponseResult::Unavailable
+                }
+                TargetResponse::Aborted(status) | TargetResponse::Rejected(status) => {
+                    status_response(status)
+                }
+                TargetResponse::Shed => status_response(StatusCode::SERVICE_UNAVAILABLE),
+                TargetResponse::Received(Ok(r)) => {
+                    let (parts, body) = r.into_parts();
+                    let mut body = IdleTimeoutBody::new(
+                        ThrottledBody::new(body, target.bandwidth_limit()),
+                        target.read_timeout(),
+                    );
+                    let read = async move {
+                        while let Some(frame) = body.frame().await {
+                            frame?;
+                        }
+                        Ok::<_, StreamError>(())
+                    };
+                    let read = match target.total_timeout() {
+                        Some(total_timeout) => {
+                            tokio::time::timeout(total_timeout.saturating_sub(duration), read)
+                                .await
+                                .unwrap_or(Err(StreamError::TotalTimeout(total_timeout)))
+                        }
+                        None => read.await,
+                    };
+                    match read {
+                        Ok(()) => ResponseResult::Ok(Response::from_parts(parts, Full::default())),
+                        Err(StreamError::IdleTimeout(_)) => {
+                            ResponseResult::Timeout(TimeoutPhase::Read)
+                        }
+                        Err(StreamError::TotalTimeout(_)) => {
+                            ResponseResult::Timeout(TimeoutPhase::Total)
+                        }
+                        Err(_) => status_response(StatusCode::BAD_GATEWAY),
+                    }
+                }
+                TargetResponse::Received(Err(he)) if is_connect_timeout(&he) => {
+                    ResponseResult::Timeout(TimeoutPhase::Connect)
+                }
+                TargetResponse::Received(Err(he)) => ResponseResult::HyperError(he),
+            };
+
+            let mut outcome = TargetOutcome::cancelled(&target_ids[pos]);
+            outcome.complete(&res, duration);
+            if let Some(status) = neutral_status {
+                outcome.status = status.into();
+            } else {
+                self.record_target_result(target, &res, duration);
+            }
+            info!(
+                "{req_id}: target `{}` has completed after the response, status={}, duration={duration:?}",
+                outcome.id, outcome.status
+            );
+        }
+    }
+
+    /// Creates empty response of the request aborted by the injected fault
+    fn fault_response(
+        response_cfg: &ResponseConfig,
+        status: StatusCode,
+    ) -> Result<Response<Full<Bytes>>, http::Error> {
+        let mut resp = response_cfg.empty_response(status.as_u16())?;
+        resp.headers_mut()
+            .insert(FAULT_HEADER, HeaderValue::from_static("abort"));
         Ok(resp)
     }
+
+    /// Prepares request to the target and spawns it, injected faults, added latency and limits are applied
+    fn spawn_target_request(
+        &self,
+        req_id: Uuid,
+        target: &TargetConfig,
+        ctx: &Context,
+        req_parts: &Parts,
+        headers: &HeaderMap,
+        body: &RequestBody,
+    ) -> Result<JoinHandle<(TargetResponse, Duration)>, http::Error> {
+        let mut target_request = Self::target_request(
+            target,
+            ctx,
+            req_parts,
+            self.listener_cfg().query(),
+            headers,
+            &body.data(),
+            body.trailers(),
+        )?;
+        if let Some(forwarding) = self.listener_cfg().forwarding() {
+            let trailers = target_request.body().trailers().cloned();
+            forwarding.apply(target_request.headers_mut(), trailers.as_ref());
+        }
+        if let Some(proxy_headers) = self.listener_cfg().proxy_headers() {
+            proxy_headers.apply(target_request.headers_mut(), req_parts.version);
+        }
+        let mut target_request = match &self.plugins {
+            Some(plugins) => plugins.on_target_request(&target.id(), target_request),
+            None => target_request,
+        };
+        // Redelivered request has the same key as the original one, so target can drop duplicates
+        let retry_queue = self.retry_queues.get(&target.id());
+        if let Some(deprecation) = self.deprecations.get(&target.id()) {
+            deprecation.record();
+        }
+        if let Some(name) = retry_queue.and_then(|queue| queue.dedup_header()) {
+            let key = HeaderValue::from_str(&req_id.to_string())
+                .expect("invalid request ID header value, looks like a BUG");
+            target_request.headers_mut().insert(name.clone(), key);
+        }
+
+        // Put request to queue
+        debug!(
+            "add to queue: target `{}` request: {} {}, headers: {:?}",
+            target.id(),
+            target_request.method(),
+            target_request.uri(),
+            redaction::headers(target_request.headers())
+        );
+
+        // Added latency, time to send body with limited bandwidth and injected delay
+        // are parts of the target's response time, so they're limited by the timeout
+        let request_size = target_request.body().size_hint().lower();
+        let mut delay = target.added_latency()
+            + target
+                .bandwidth_limit()
+                .map(|bandwidth| bandwidth.transfer_time(request_size))
+                .unwrap_or_default();
+        if let Some(fault_delay) = target.faults().and_then(FaultsConfig::delay) {
+            info!(
+                "{req_id}: fault injected, target `{}`, delay={fault_delay:?}",
+                target.id()
+            );
+            delay += fault_delay;
+        }
+        // Request with headers above the target's limits isn't forwarded
+        let rejected = match target
+            .header_limits()
+            .map(|l| l.check(target_request.headers()))
+        {
+            Some(Err(e)) => {
+                warn!(
+                    "{req_id}: not forwarded, too large headers: {e}, target `{}`",
+                    target.id()
+                );
+                Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+            }
+            _ => None,
+        };
+        let abort = target.faults().and_then(FaultsConfig::abort);
+        if let Some(status) = abort {
+            info!(
+                "{req_id}: fault injected, target `{}`, abort status={}",
+                target.id(),
+                status.as_u16()
+            );
+        }
+
+        // Prepare target request
+        let http_client = target.https_client(self.listener_cfg().tls());
+        let discovery = self.discovery.get(&target.id()).cloned();
+        let concurrency = self.concurrency.clone();
+        let (target_id, priority) = (target.id(), target.priority());
+        let skip_unresolved = target.dns().on_failure() == DnsFailureAction::Skip;
+        // Copy of the request is kept to queue it if request fails
+        let retry = retry_queue.map(|queue| {
+            let queued = QueuedRequest::from_request(req_id, &target_request);
+            (queue.clone(), queued)
+        });
+        let classifier =
+            TargetFailureClassifier::new(self.app_config.clone(), self.listener_index, target.id());
+        let lease_classifier = classifier.clone();
+        let started = Instant::now();
+        let http_request = async move {
+            if let Some(status) = rejected {
+                return TargetResponse::Rejected(status);
+            }
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(status) = abort {
+                return TargetResponse::Aborted(status);
+            }
+            // Slot is held until response headers are received, it's released before the body is read
+            let _permit = match &concurrency {
+                Some(concurrency) => match concurrency.acquire(priority).await {
+                    Some(permit) => Some(permit),
+                    None => {
+                        warn!("{req_id}: target `{target_id}` request is shed, concurrency limit is reached");
+                        return TargetResponse::Shed;
+                    }
+                },
+                None => None,
+            };
+            // Request is sent to one of the discovered instances
+            let (target_request, lease) = match &discovery {
+                Some(discovery) => match discovery.route(target_request).await {
+                    Ok((target_request, lease)) => (target_request, Some(lease)),
+                    Err(e) => {
+                        error!("{req_id}: target instance isn't available: {e}");
+                        return TargetResponse::Unavailable;
+                    }
+                },
+                None => (target_request, None),
+            };
+            let response = http_client.request(target_request).await;
+            if let Some(lease) = lease {
+                lease.report(response.as_ref().map_or(true, |resp| {
+                    lease_classifier.is_failed_head(resp, started.elapsed())
+                }));
+            }
+            match response {
+                Err(e) if skip_unresolved && dns::is_dns_failure(&e) => {
+                    debug!("{req_id}: target `{target_id}` is skipped: {e}");
+                    TargetResponse::Unresolved
+                }
+                response => TargetResponse::Received(response),
+            }
+        };
+        let http_request = tokio::time::timeout(*target.timeout(), http_request);
+        let total_timeout = target.total_timeout();
+
+        // Spawned request inherits listener's span, target's labels are attached if any
+        let span = match target.labels() {
+            labels if labels.is_empty() => Span::current(),
+            labels => info_span!("target", id = %target.id(), labels = %labels),
+        };
+
+        Ok(tokio::spawn(
+            async move {
+                // Journaled request is queued if it isn't acknowledged, even if the task is cancelled
+                let journal = match &retry {
+                    Some((queue, queued)) if queue.write_ahead() => queue.journal(queued).await,
+                    _ => None,
+                };
+                let result = match total_timeout {
+                    Some(total_timeout) => tokio::time::timeout(total_timeout, http_request)
+                        .await
+                        .unwrap_or(Ok(TargetResponse::Timeout(TimeoutPhase::Total))),
+                    None => http_request.await,
+                };
+                let result = result.unwrap_or(TargetResponse::Timeout(TimeoutPhase::FirstByte));
+                let failed = || result.is_failed(&classifier, started.elapsed());
+                match (journal, retry) {
+                    (Some(entry), _) if failed() => entry.requeue().await,
+                    (Some(entry), _) => entry.acknowledge().await,
+                    (None, Some((queue, queued))) if failed() => queue.push(queued).await,
+                    _ => {}
+                }
+                (result, started.elapsed())
+            }
+            .instrument(span),
+        ))
+    }
+
+    /// Builds request to the target: expands URL and body, applies target's headers transformations
+    fn target_request(
+        target: &TargetConfig,
+        ctx: &Context,
+        req_parts: &Parts,
+        query: Option<&QueryTransformsList>,
+        headers: &HeaderMap,
+        body_bytes: &Bytes,
+        trailers: Option<&HeaderMap>,
+    ) -> Result<Request<RequestBody>, http::Error> {
+        let target_request_builder = Request::builder();
+        // Set method
+        let target_request_builder = match target.method_override() {
+            Some(method) => target_request_builder.method(Method::from(method)),
+            None => target_request_builder.method(&req_parts.method),
+        };
+        // Set uri
+        let url = env_with_context_no_errors(target.url(), |v| ctx.lookup(v));
+        let mut uri: Uri = url.parse()?;
+        if target.forward_path() {
+            let mut parts = uri.into_parts();
+            parts.path_and_query = req_parts.uri.path_and_query().cloned();
+            uri = Uri::from_parts(parts)?;
+        }
+        // Rewritten path replaces path and query of the URL, unmatched path leaves it as is
+        if let Some(rewritten) = target
+            .path_rewrite()
+            .and_then(|rule| rule.rewrite(req_parts.uri.path()))
+        {
+            let mut parts = uri.into_parts();
+            parts.path_and_query = Some(rewritten.parse()?);
+            uri = Uri::from_parts(parts)?;
+        }
+        // Listener's query transformations are applied before target's ones
+        if query.is_some() || target.query().is_some() {
+            let query = query
+                .into_iter()
+                .chain(target.query())
+                .fold(uri.query().map(String::from), |query, transforms| {
+                    transforms.transform_query(query.as_deref(), ctx)
+                });
+            let path = uri.path();
+            let path_and_query = match query {
+                Some(query) => format!("{path}?{query}"),
+                None => path.to_string(),
+            };
+            let mut parts = uri.into_parts();
+            parts.path_and_query = Some(path_and_query.parse()?);
+            uri = Uri::from_parts(parts)?;
+        }
+        // Prepare headers
+        let mut headers = headers.clone();
+        if let Some(transforms) = &target.headers() {
+            transforms.transform(&mut headers, ctx);
+        }
+        if let Some(cookies) = target.cookies() {
+            cookies.transform_request(&mut headers);
+        }
+        // Add Host header if empty
+        if !headers.contains_key(HOST) {
+            let target_host = || {
+                let host = uri
+                    .host()
+                    .unwrap_or_else(|| panic!("there is no `host` part in the URI: {uri:?}"));
+                HeaderValue::from_str(host)
+            };
+            let host = match target.host_header() {
+                HostHeader::Target => target_host()?,
+                // HTTP/2 request has authority in the URI instead of the header
+                HostHeader::Preserve => match req_parts.headers.get(HOST) {
+                    Some(host) => host.clone(),
+                    None => match req_parts.uri.authority() {
+                        Some(authority) => HeaderValue::from_str(authority.as_str())?,
+                        None => target_host()?,
+                    },
+                },
+                HostHeader::Custom(host) => {
+                    HeaderValue::from_str(&env_with_context_no_errors(host, |v| ctx.lookup(v)))?
+                }
+            };
+
+            debug!("add host header: {host:?}");
+            headers.insert(HOST, host);
+        }
+        let target_request_builder = target_request_builder.uri(uri);
+        // Prepare body, requester's trailers don't belong to the target's own body
+        let (body, trailers) = if let Some(body) = &target.body() {
+            let body = env_with_context_no_errors(body, |v| ctx.lookup(v));
+            (Bytes::from(body.into_owned()), None)
+        } else {
+            // Reference counted, so all targets share the same request body without copying
+            (body_bytes.clone(), trailers.cloned())
+        };
+        // Compress body if target accepts it and it isn't compressed yet
+        let body = match target.compression().request() {
+            Some(encoding) if !body.is_empty() && !headers.contains_key(CONTENT_ENCODING) => {
+                match compression::encode(encoding, &body) {
+                    Ok(encoded) => {
+                        headers.insert(CONTENT_ENCODING, encoding.header_value());
+                        headers.remove(CONTENT_LENGTH);
+                        encoded
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "unable to compress request body to target `{}`", target.id());
+                        body
+                    }
+                }
+            }
+            _ => body,
+        };
+        // Finalize request with body, prepared headers are moved into it as is
+        let mut target_request =
+            target_request_builder.body(RequestBody::with_trailers(body, trailers))?;
+        *target_request.headers_mut() = headers;
+
+        Ok(target_request)
+    }
+}
+
+/// Result of the target request before its body is received
+enum TargetResponse {
+    Received(Result<Response<Incoming>, HyperError>),
+    Timeout(TimeoutPhase),
+    /// Request wasn't forwarded because of the injected fault
+    Aborted(StatusCode),
+    /// Request wasn't forwarded because it exceeds target's limits
+    Rejected(StatusCode),
+    /// There are no discovered instances of the target
+    Unavailable,
+    /// Low priority request wasn't forwarded because listener's concurrency limit is reached
+    Shed,
+    /// Target's host name can't be resolved and target should be skipped
+    Unresolved,
+}
+
+impl TargetResponse {
+    /// Request hasn't been delivered: target is unreachable, timed out or its response is classified as failed
+    /// by its head, requests which weren't forwarded intentionally aren't failed
+    fn is_failed(&self, classifier: &TargetFailureClassifier, elapsed: Duration) -> bool {
+        match self {
+            TargetResponse::Received(Ok(resp)) => classifier.is_failed_head(resp, elapsed),
+            TargetResponse::Received(Err(_))
+            | TargetResponse::Timeout(_)
+            | TargetResponse::Unavailable => true,
+            TargetResponse::Aborted(_)
+            | TargetResponse::Rejected(_)
+            | TargetResponse::Shed
+            | TargetResponse::Unresolved => false,
+        }
+    }
+}
+
+/// Receives the rest of the target's body without keeping it, so the target completes its response
+async fn drain_body(mut body: IdleTimeoutBody) {
+    while let Some(frame) = body.frame().await {
+        if frame.is_err() {
+            break;
+        }
+    }
+}
+
+/// Waits for the spawned target request, position of the target is kept to match the result
+async fn join_target(
+    pos: usize,
+    request: JoinHandle<(TargetResponse, Duration)>,
+) -> (usize, Result<(TargetResponse, Duration), JoinError>) {
+    (pos, request.await)
+}
+
+/// Result of the single target request, to report in debug headers
+#[derive(Debug)]
+struct TargetOutcome {
+    id: String,
+    status: String,
+    duration: Option<Duration>,
+}
+
+impl TargetOutcome {
+    fn cancelled(id: &str) -> Self {
+        Self {
+            id: id.into(),
+            status: "cancelled".into(),
+            duration: None,
+        }
+    }
+
+    /// Chained target which hasn't been requested
+    fn skipped(id: &str) -> Self {
+        Self {
+            id: id.into(),
+            status: "skipped".into(),
+            duration: None,
+        }
+    }
+
+    fn complete(&mut self, res: &ResponseResult, duration: Duration) {
+        self.status = match res {
+            ResponseResult::Ok(resp) => resp.status().as_u16().to_string(),
+            ResponseResult::HyperError(_) => "error".into(),
+            ResponseResult::Unavailable => "unavailable".into(),
+            ResponseResult::Timeout(TimeoutPhase::FirstByte) => "timeout".into(),
+            ResponseResult::Timeout(phase) => format!("{phase}_timeout"),
+        };
+        self.duration = Some(duration);
+    }
+
+    /// Adds `X-Splitter-Target-<id>-Status` and `X-Splitter-Target-<id>-Duration-Ms` headers for each target,
+    /// all characters of target ID which aren't allowed in header name are replaced with `-`
+    fn add_headers(outcomes: &[TargetOutcome], resp: &mut Response<Full<Bytes>>) {
+        for outcome in outcomes {
+            let id: String = outcome
+                .id
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            let headers = resp.headers_mut();
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(format!("x-splitter-target-{id}-status").as_bytes()),
+                HeaderValue::from_str(&outcome.status),
+            ) {
+                headers.insert(name, value);
+            }
+            if let (Ok(name), Some(duration)) = (
+                HeaderName::from_bytes(format!("x-splitter-target-{id}-duration-ms").as_bytes()),
+                outcome.duration,
+            ) {
+                headers.insert(name, HeaderValue::from(duration.as_millis() as u64));
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum ResponseResult {
     Ok(Response<Full<Bytes>>),
     HyperError(HyperError),
-    Timeout,
+    Timeout(TimeoutPhase),
+    Unavailable,
+}
+
+impl ResponseResult {
+    /// Request hasn't been delivered or target's response is classified as failed,
+    /// like it's done by the response strategies
+    fn is_failed(&self, response_cfg: &ResponseConfig, target: &TargetConfig) -> bool {
+        match self {
+            ResponseResult::Ok(resp) => response_cfg.is_failed(resp, Some(target)),
+            ResponseResult::HyperError(_)
+            | ResponseResult::Timeout(_)
+            | ResponseResult::Unavailable => true,
+        }
+    }
+
+    /// Converts result to the outcome of the request to evaluate target's SLOs,
+    /// `failed` is the classification of the result
+    fn slo_outcome(&self, failed: bool, duration: Duration) -> SloOutcome {
+        match self {
+            ResponseResult::Ok(_) => SloOutcome::Response { failed, duration },
+            ResponseResult::Timeout(_) => SloOutcome::Timeout,
+            ResponseResult::HyperError(_) | ResponseResult::Unavailable => SloOutcome::Error,
+        }
+    }
+}
+
+/// Returns `true` if connection to the target hasn't been established during connect timeout
+fn is_connect_timeout(e: &HyperError) -> bool {
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if err
+            .downcast_ref::<io::Error>()
+            .is_some_and(|e| e.kind() == io::ErrorKind::TimedOut)
+        {
+            return true;
+        }
+        source = err.source();
+    }
+
+    false
 }
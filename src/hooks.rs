@@ -0,0 +1,116 @@
+use crate::{
+    config::{
+        hooks::{HookAction, HookConfig},
+        listener::TlsConfig,
+        target::TargetConfig,
+        AppConfig,
+    },
+    streaming::RequestBody,
+};
+use hyper::{header::CONTENT_TYPE, Method, Request};
+use serde::Serialize;
+use std::sync::Arc;
+use strum_macros::Display;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Variable with the name of the event, it's passed to the command hooks
+const EVENT_ENV_VAR: &str = "HTTP_DRAGONFLY_EVENT";
+/// Variable with comma-separated `id=listen_on` pairs of all listeners, it's passed to the command hooks
+const LISTENERS_ENV_VAR: &str = "HTTP_DRAGONFLY_LISTENERS";
+
+/// Lifecycle event of the process which hooks are called on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum LifecycleEvent {
+    /// All listeners are bound and serve requests
+    Startup,
+    /// Shutdown signal is received, listeners complete requests in progress
+    Shutdown,
+}
+
+/// Payload which is posted to the HTTP hooks
+#[derive(Serialize)]
+struct HookEvent {
+    event: LifecycleEvent,
+    listeners: Vec<HookListener>,
+}
+
+#[derive(Serialize)]
+struct HookListener {
+    id: String,
+    listen_on: String,
+}
+
+/// Calls hooks of the event one by one, failed hook is reported and doesn't affect the process or other hooks
+pub async fn run(app_config: Arc<AppConfig>, event: LifecycleEvent) {
+    let hooks = match event {
+        LifecycleEvent::Startup => app_config.hooks().startup(),
+        LifecycleEvent::Shutdown => app_config.hooks().shutdown(),
+    };
+    if hooks.is_empty() {
+        return;
+    }
+
+    let payload = HookEvent {
+        event,
+        listeners: app_config
+            .listeners()
+            .iter()
+            .map(|cfg| HookListener {
+                id: cfg.id(),
+                listen_on: cfg.socket().to_string(),
+            })
+            .collect(),
+    };
+    for (index, hook) in hooks.iter().enumerate() {
+        match call(hook, &payload).await {
+            Ok(()) => info!(%event, hook = index, "lifecycle hook completed"),
+            Err(e) => warn!(%event, hook = index, error = e, "lifecycle hook failed"),
+        }
+    }
+}
+
+async fn call(hook: &HookConfig, payload: &HookEvent) -> Result<(), String> {
+    match hook.action() {
+        HookAction::Http(url) => {
+            let client = TargetConfig::tls_client(&TlsConfig::default(), hook.timeout());
+            let body = serde_json::to_string(payload)
+                .expect("unable to serialize hook event, looks like a BUG");
+            let req = Request::builder()
+                .method(Method::POST)
+                .uri(url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(RequestBody::from(body))
+                .map_err(|e| e.to_string())?;
+            match tokio::time::timeout(hook.timeout(), client.request(req)).await {
+                Ok(Ok(resp)) if resp.status().is_success() => Ok(()),
+                Ok(Ok(resp)) => Err(format!("response status {}", resp.status())),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("timeout".into()),
+            }
+        }
+        HookAction::Command(command) => {
+            let listeners = payload
+                .listeners
+                .iter()
+                .map(|l| format!("{}={}", l.id, l.listen_on))
+                .collect::<Vec<_>>()
+                .join(",");
+            // Command is killed if it isn't completed in time
+            let status = Command::new(&command[0])
+                .args(&command[1..])
+                .env(EVENT_ENV_VAR, payload.event.to_string())
+                .env(LISTENERS_ENV_VAR, listeners)
+                .kill_on_drop(true)
+                .status();
+            match tokio::time::timeout(hook.timeout(), status).await {
+                Ok(Ok(status)) if status.success() => Ok(()),
+                Ok(Ok(status)) => Err(format!("command exited with {status}")),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("timeout".into()),
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{config::listener::ListenerConfig, handler::RequestHandler, signal::SignalHandler};
+use bytes::{Buf, Bytes};
+use h3::{error::StreamError, server::RequestResolver};
+use http_body_util::BodyExt;
+use hyper::{
+    body::{Body, Frame},
+    client::conn::http1,
+    header::{self, HeaderValue},
+    service::service_fn,
+    Request, Response, StatusCode, Uri, Version,
+};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto::Builder,
+};
+use quinn::{crypto::rustls::QuicServerConfig, Endpoint, Incoming, ServerConfig};
+use std::{
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tokio::{select, sync::watch, task::JoinSet};
+use tracing::{debug, warn};
+
+/// Size of the in-memory connection between HTTP/3 request and the listener's handler
+const BRIDGE_BUFFER_SIZE: usize = 64 * 1024;
+/// How long clients may use HTTP/3 endpoint from `Alt-Svc` header without re-checking it, in seconds
+const ALT_SVC_MAX_AGE_SEC: u64 = 86400;
+/// Connection-specific headers aren't allowed in HTTP/3 responses
+const CONNECTION_HEADERS: [&str; 5] = [
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "transfer-encoding",
+    "upgrade",
+];
+
+type QuicConnection = h3_quinn::Connection;
+
+/// Binds UDP socket of the listener's HTTP/3 endpoint, it's the same address as the listener's TCP socket
+pub fn bind(cfg: &ListenerConfig) -> Result<Endpoint, anyhow::Error> {
+    let tls = cfg
+        .server_tls()
+        .expect("HTTP/3 listener has no TLS termination, looks like a BUG")
+        .quic_server_config()?;
+    let config = ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls)?));
+
+    Ok(Endpoint::server(config, cfg.socket())?)
+}
+
+/// Returns `Alt-Svc` header value which advertises HTTP/3 endpoint of the listener to HTTP/1.1 clients
+pub fn alt_svc(cfg: &ListenerConfig) -> HeaderValue {
+    HeaderValue::from_str(&format!(
+        "h3=\":{}\"; ma={ALT_SVC_MAX_AGE_SEC}",
+        cfg.socket().port()
+    ))
+    .expect("invalid Alt-Svc header value, looks like a BUG")
+}
+
+/// Serves HTTP/3 connections of the listener until shutdown, connections are closed gracefully:
+/// new requests are refused, requests in progress are completed
+pub async fn service_loop(
+    endpoint: Endpoint,
+    handler: watch::Receiver<RequestHandler>,
+) -> Result<(), anyhow::Error> {
+    let mut join_set = JoinSet::new();
+    let (shutdown_sender, shutdown) = watch::channel(false);
+
+    let name = handler.borrow().listener_cfg().id();
+    let mut signal_handler = SignalHandler::new(format!("{name}-h3"));
+
+    loop {
+        select! {
+            biased;
+            _ = signal_handler.wait() => {
+                endpoint.set_server_config(None);
+                let _ = shutdown_sender.send(true);
+                while (join_set.join_next().await).is_some() {};
+                break
+            },
+            incoming = endpoint.accept() => {
+                let Some(incoming) = incoming else {
+                    break;
+                };
+                join_set.spawn(serve_connection(incoming, handler.clone(), shutdown.clone()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn serve_connection(
+    incoming: Incoming,
+    handler: watch::Receiver<RequestHandler>,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let addr = incoming.remote_address();
+    let conn = match incoming.await {
+        Ok(conn) => conn,
+        Err(e) => {
+            debug!("QUIC handshake with {addr} failed: {e}");
+            return;
+        }
+    };
+    let mut conn = match h3::server::Connection::<_, Bytes>::new(QuicConnection::new(conn)).await {
+        Ok(conn) => conn,
+        Err(e) => {
+            debug!("HTTP/3 connection from {addr} failed: {e}");
+            return;
+        }
+    };
+
+    let mut requests = JoinSet::new();
+    loop {
+        select! {
+            _ = shutdown.changed() => {
+                if let Err(e) = conn.shutdown(0).await {
+                    debug!("unable to close HTTP/3 connection from {addr}: {e}");
+                }
+                break
+            },
+            accepted = conn.accept() => match accepted {
+                Ok(Some(resolver)) => {
+                    requests.spawn(serve_request(resolver, handler.clone(), addr));
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    if !e.is_h3_no_error() {
+                        debug!("HTTP/3 connection from {addr} failed: {e}");
+                    }
+                    break
+                }
+            }
+        }
+    }
+
+    // Connection is closed when it's dropped, so requests in progress are completed first
+    while (requests.join_next().await).is_some() {}
+}
+
+/// Passes request to the listener's handler over in-memory HTTP/1.1 connection,
+/// so it's processed exactly like requests over TCP, and streams response back
+async fn serve_request(
+    resolver: RequestResolver<QuicConnection, Bytes>,
+    handler: watch::Receiver<RequestHandler>,
+    addr: SocketAddr,
+) {
+    let (req, stream) = match resolver.resolve_request().await {
+        Ok(req) => req,
+        Err(e) => {
+            debug!("unable to read HTTP/3 request from {addr}: {e}");
+            return;
+        }
+    };
+    let (mut send, recv) = stream.split();
+    let req = bridged_request(
+        req,
+        RequestBody {
+            recv,
+            data_done: false,
+        },
+    );
+
+    let (client, server) = tokio::io::duplex(BRIDGE_BUFFER_SIZE);
+    tokio::spawn(async move {
+        let conn = Builder::new(TokioExecutor::new())
+            .http1_only()
+            .serve_connection(
+                TokioIo::new(server),
+                service_fn(|req| {
+                    // Each request is served by the current handler, even if it's reloaded
+                    handler.borrow().clone().handle(addr, req)
+                }),
+            )
+            .await;
+        if let Err(e) = conn {
+            debug!("error serving HTTP/3 request from {addr}: {e}");
+        }
+    });
+    let resp = match http1::handshake(TokioIo::new(client)).await {
+        Ok((mut sender, conn)) => {
+            tokio::spawn(conn);
+            sender.send_request(req).await
+        }
+        Err(e) => Err(e),
+    };
+
+    let (parts, mut body) = match resp {
+        Ok(resp) => resp.into_parts(),
+        Err(e) => {
+            warn!("unable to serve HTTP/3 request from {addr}: {e}");
+            let resp = Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .body(())
+                .expect("invalid empty response, looks like a BUG");
+            if send.send_response(resp).await.is_ok() {
+                let _ = send.finish().await;
+            }
+            return;
+        }
+    };
+    let mut resp = Response::from_parts(parts, ());
+    *resp.version_mut() = Version::HTTP_3;
+    for name in CONNECTION_HEADERS {
+        resp.headers_mut().remove(name);
+    }
+    if let Err(e) = send.send_response(resp).await {
+        debug!("unable to send HTTP/3 response to {addr}: {e}");
+        return;
+    }
+    while let Some(frame) = body.frame().await {
+        let sent = match frame.map(Frame::into_data) {
+            Ok(Ok(data)) => send.send_data(data).await,
+            Ok(Err(frame)) => match frame.into_trailers() {
+                Ok(trailers) => send.send_trailers(trailers).await,
+                Err(_) => Ok(()),
+            },
+            Err(e) => {
+                warn!("unable to stream HTTP/3 response to {addr}: {e}");
+                return;
+            }
+        };
+        if let Err(e) = sent {
+            debug!("unable to send HTTP/3 response to {addr}: {e}");
+            return;
+        }
+    }
+    if let Err(e) = send.finish().await {
+        debug!("unable to send HTTP/3 response to {addr}: {e}");
+    }
+}
+
+/// HTTP/1.1 request has origin form URI and `Host` header instead of `:authority`
+fn bridged_request<B>(req: Request<()>, body: B) -> Request<B> {
+    let (mut parts, ()) = req.into_parts();
+    if let Some(authority) = parts.uri.authority() {
+        if !parts.headers.contains_key(header::HOST) {
+            if let Ok(host) = HeaderValue::from_str(authority.as_str()) {
+                parts.headers.insert(header::HOST, host);
+            }
+        }
+    }
+    // Path of authority-only URI is empty, but it's displayed as `/`
+    parts.uri = parts
+        .uri
+        .path_and_query()
+        .and_then(|path| Uri::try_from(path.as_str()).ok())
+        .unwrap_or_else(|| Uri::from_static("/"));
+    parts.version = Version::HTTP_11;
+
+    Request::from_parts(parts, body)
+}
+
+/// Body of HTTP/3 request which is read from its QUIC stream
+struct RequestBody {
+    recv: h3::server::RequestStream<h3_quinn::RecvStream, Bytes>,
+    data_done: bool,
+}
+
+impl Body for RequestBody {
+    type Data = Bytes;
+    type Error = StreamError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if !self.data_done {
+            match ready!(self.recv.poll_recv_data(cx)) {
+                Ok(Some(mut data)) => {
+                    return Poll::Ready(Some(Ok(Frame::data(data.copy_to_bytes(data.remaining())))))
+                }
+                Ok(None) => self.data_done = true,
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+
+        match ready!(self.recv.poll_recv_trailers(cx)) {
+            Ok(Some(trailers)) => Poll::Ready(Some(Ok(Frame::trailers(trailers)))),
+            Ok(None) => Poll::Ready(None),
+            Err(e) => Poll::Ready(Some(Err(e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bridged_request_uri() {
+        let req = Request::builder()
+            .uri("https://api.example.com:8443/users?id=1")
+            .version(Version::HTTP_3)
+            .body(())
+            .unwrap();
+        let req = bridged_request(req, ());
+        assert_eq!(req.uri(), "/users?id=1");
+        assert_eq!(req.headers()[header::HOST], "api.example.com:8443");
+        assert_eq!(req.version(), Version::HTTP_11);
+
+        // Explicit `Host` header is kept
+        let req = Request::builder()
+            .uri("https://api.example.com")
+            .header(header::HOST, "other.example.com")
+            .body(())
+            .unwrap();
+        let req = bridged_request(req, ());
+        assert_eq!(req.uri(), "/");
+        assert_eq!(req.headers()[header::HOST], "other.example.com");
+    }
+}
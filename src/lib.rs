@@ -1,48 +1,245 @@
 pub mod cli;
 pub mod config;
 pub mod context;
+#[cfg(windows)]
+pub mod service;
 pub mod signal;
+pub mod strategy;
+#[cfg(feature = "test-support")]
+pub mod testing;
 
+mod acme;
+mod admin;
+mod audit;
+mod body_logging;
+mod buffer_pool;
+mod ca_bundle;
+mod cache;
+mod canary;
+mod coalescing;
+mod comparison;
+mod compression;
+mod concurrency;
+mod connections;
+mod cors;
+mod deduplication;
+mod deprecation;
+mod discovery;
+mod dns;
+mod enrichment;
+mod explain;
+mod forward_proxy;
 mod handler;
 mod health_check;
+mod hooks;
+mod http3;
+mod log_governor;
+mod maintenance;
+mod metrics;
+mod plugins;
+mod ranges;
+mod redaction;
+mod reload;
+mod retry_queue;
+mod sampling;
+mod selection;
+mod shaping;
+mod slo;
+mod startup_check;
+mod streaming;
+mod systemd;
+mod tls;
+mod websocket;
 
-use cli::CliConfig;
-use config::{listener::ListenerConfig, AppConfig};
-use context::{Context, RootEnvironment};
-use futures_util::future::join_all;
+use cli::{CliCommand, CliConfig};
+use config::{listener::ListenerConfig, server_tls::ServerProtocol, AppConfig, ConfigValidator};
+use connections::ConnectionTracker;
+use context::{
+    secrets::{self, FileSecretProvider},
+    Context, RootEnvironment,
+};
+use futures_util::{
+    future::{join_all, Either},
+    FutureExt,
+};
 use handler::RequestHandler;
-use hyper::service::service_fn;
+use hooks::LifecycleEvent;
+use hyper::{
+    header::{HeaderValue, ALT_SVC},
+    service::service_fn,
+};
 use hyper_util::{
     rt::{TokioExecutor, TokioIo, TokioTimer},
     server::conn::auto::Builder,
 };
 use signal::SignalHandler;
-use std::sync::Arc;
+#[cfg(target_os = "linux")]
+use socket2::SockRef;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use systemd::InheritedSockets;
+use tls::{ServerStream, TlsTermination};
 use tokio::{
-    net::TcpListener,
+    net::{TcpListener, TcpSocket},
+    runtime::{Handle, Runtime},
     select,
+    sync::watch,
     task::{JoinHandle, JoinSet},
 };
-use tracing::{error, warn};
+use tracing::{debug, error, info, warn};
 
 pub type HyperTaskJoinHandle = JoinHandle<Result<(), anyhow::Error>>;
 
+/// Creates root context with environment and all secrets
+fn root_context<'a>(env_provider: impl RootEnvironment) -> Result<Context<'a>, anyhow::Error> {
+    let mut root_ctx = Context::root(env_provider);
+    root_ctx.add_secrets(&FileSecretProvider::from_env())?;
+    for provider in secrets::registered() {
+        root_ctx.add_secrets(provider.as_ref())?;
+    }
+
+    Ok(root_ctx)
+}
+
+/// Loads and validates config, reports result and exits without serving anything
+pub fn validate(
+    cli_config: CliConfig,
+    env_provider: impl RootEnvironment,
+) -> Result<(), anyhow::Error> {
+    let root_ctx = root_context(env_provider)?;
+    let config_source = cli_config.config_source();
+    let app_config = AppConfig::new(config_source.clone(), &root_ctx, cli_config.strict_env())?;
+    let warnings = app_config.lint();
+    for warning in &warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    if let Some(CliCommand::Validate {
+        dump_config: true, ..
+    }) = cli_config.command()
+    {
+        println!("{}", app_config.dump());
+        return Ok(());
+    }
+    println!(
+        "Config `{config_source}` is valid, {} listener(s) configured, {} warning(s)",
+        app_config.listeners().len(),
+        warnings.len()
+    );
+    Ok(())
+}
+
 pub async fn run(
     cli_config: CliConfig,
     env_provider: impl RootEnvironment,
 ) -> Result<(), anyhow::Error> {
-    let root_ctx = Arc::new(Context::root(env_provider));
-    let app_config = AppConfig::new(cli_config.config_path(), *root_ctx)?;
+    let root_ctx = Arc::new(root_context(env_provider)?);
+    let app_config = Arc::new(AppConfig::new(
+        cli_config.config_source(),
+        &root_ctx,
+        cli_config.strict_env(),
+    )?);
+    for warning in app_config.lint() {
+        warn!("config: {warning}");
+    }
+    log_governor::configure(app_config.logging());
+    log_governor::start_summaries();
+
+    serve(app_config, root_ctx, &cli_config).await
+}
+
+/// Binds and serves all listeners of the loaded config until shutdown
+async fn serve(
+    app_config: Arc<AppConfig>,
+    root_ctx: Arc<Context<'static>>,
+    cli_config: &CliConfig,
+) -> Result<(), anyhow::Error> {
     let mut servers: Vec<HyperTaskJoinHandle> = vec![];
+    let mut bound = vec![];
+    let mut runtimes: Vec<ListenerRuntime> = vec![];
+    let mut inherited = InheritedSockets::from_env();
+    let mut handlers = HashMap::new();
 
-    for cfg in app_config.listeners().iter().map(Arc::new) {
-        let listener = TcpListener::bind(&cfg.socket()).await?;
+    // Listeners which share the socket are distinguished by SNI host name,
+    // socket is bound and served by the first of them
+    let mut sockets: Vec<(usize, Handle, Vec<Endpoint>)> = vec![];
+    for (index, cfg) in app_config.listeners().iter().enumerate() {
+        let handler = RequestHandler::new(app_config.clone(), index, root_ctx.clone())?;
+        // Handler is replaced when listener's config is reloaded by admin API
+        let (handler_sender, handler) = watch::channel(handler);
+        handlers.insert(cfg.id(), handler_sender);
 
-        let ctx = root_ctx.clone();
-        let cfg = cfg.clone();
+        let shared = sockets
+            .iter()
+            .position(|(first, _, _)| cfg.is_sni_shared_with(&app_config.listeners()[*first]));
+        let handle = match shared {
+            Some(pos) => sockets[pos].1.clone(),
+            None => {
+                // Listener with dedicated runtime is served by its own threads, so it isn't affected by others
+                let runtime = cfg
+                    .runtime()
+                    .map(|runtime| runtime.runtime(&format!("listener-{index}")))
+                    .transpose()?
+                    .map(ListenerRuntime::from);
+                let handle = runtime
+                    .as_ref()
+                    .map(ListenerRuntime::handle)
+                    .unwrap_or_else(Handle::current);
+                runtimes.extend(runtime);
+                handle
+            }
+        };
 
-        let server = service_loop(listener, &ctx, &cfg);
-        servers.push(tokio::spawn(server));
+        // Sockets are registered in the runtime which serves them
+        let _guard = handle.enter();
+        // All workers of the listener share the same handler, so cache and other state are common
+        let endpoint = Endpoint {
+            handler,
+            connections: ConnectionTracker::new(cfg),
+            tls: cfg.server_tls().map(TlsTermination::new).transpose()?,
+            alt_svc: cfg
+                .server_tls()
+                .is_some_and(|tls| tls.protocol() == ServerProtocol::H3)
+                .then(|| http3::alt_svc(cfg)),
+        };
+        match shared {
+            Some(pos) => sockets[pos].2.push(endpoint),
+            None => sockets.push((index, handle, vec![endpoint])),
+        }
+    }
+    for (index, handle, endpoints) in sockets {
+        let _guard = handle.enter();
+        let cfg = &app_config.listeners()[index];
+        // HTTP/3 is served on UDP socket with the same address, it isn't supported by shared sockets
+        if endpoints[0].alt_svc.is_some() {
+            let server = http3::service_loop(http3::bind(cfg)?, endpoints[0].handler.clone());
+            bound.push((handle.clone(), server.boxed()));
+        }
+        let endpoints = Arc::new(endpoints);
+        for listener in bind(cfg, &mut inherited)? {
+            let server = service_loop(listener, endpoints.clone());
+            bound.push((handle.clone(), server.boxed()));
+        }
+    }
+
+    inherited.warn_unused();
+    reload::register(
+        app_config.clone(),
+        root_ctx.clone(),
+        cli_config.strict_env(),
+        handlers,
+    );
+
+    // Misconfigured targets are reported before any request is served,
+    // sockets are bound already, so clients wait in the accept queue
+    let unreachable = startup_check::probe_targets(&app_config).await;
+    if !unreachable.is_empty() && cli_config.fail_fast_on_unreachable_targets() {
+        return Err(anyhow::anyhow!(
+            "startup check failed, unreachable targets: {}",
+            unreachable.join(", ")
+        ));
+    }
+    for (handle, server) in bound {
+        servers.push(handle.spawn(server));
     }
 
     // Setup health check responder
@@ -50,20 +247,115 @@ pub async fn run(
         servers.push(health_check::new(port, 5).await);
     }
 
-    let _results = join_all(servers).await;
+    // Setup admin API
+    if let Some(port) = cli_config.admin_port {
+        servers.push(admin::new(port, 5).await);
+    }
+
+    // Hooks don't delay serving, failed hook is reported only
+    tokio::spawn(hooks::run(app_config.clone(), LifecycleEvent::Startup));
+
+    // Each server handles signals itself, so shutdown hooks are called while servers complete requests in progress
+    let mut signal_handler = SignalHandler::new("hooks");
+    let servers = join_all(servers);
+    tokio::pin!(servers);
+    select! {
+        _results = &mut servers => {},
+        _ = signal_handler.wait() => {
+            let (_results, _) = tokio::join!(servers, hooks::run(app_config, LifecycleEvent::Shutdown));
+        }
+    }
 
     Ok(())
 }
 
+/// Dedicated runtime of the listener,
+/// it's shut down without waiting for tasks since it's dropped in the async context
+struct ListenerRuntime(Option<Runtime>);
+
+impl From<Runtime> for ListenerRuntime {
+    fn from(runtime: Runtime) -> Self {
+        Self(Some(runtime))
+    }
+}
+
+impl ListenerRuntime {
+    fn handle(&self) -> Handle {
+        self.0
+            .as_ref()
+            .expect("runtime has been shut down, looks like a BUG")
+            .handle()
+            .clone()
+    }
+}
+
+impl Drop for ListenerRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Binds listener's socket, or several sockets with `SO_REUSEPORT` option
+/// to spread accepting of connections across runtime workers.
+/// Sockets inherited from systemd with the listener's name are used as is instead of binding,
+/// so listener's socket options aren't applied to them, except options of the accepted connections.
+fn bind(
+    cfg: &ListenerConfig,
+    inherited: &mut InheritedSockets,
+) -> Result<Vec<TcpListener>, anyhow::Error> {
+    let listeners = inherited.take(&cfg.id())?;
+    if !listeners.is_empty() {
+        info!(
+            listener = %cfg.id(),
+            sockets = listeners.len(),
+            "use sockets inherited from systemd"
+        );
+        return Ok(listeners);
+    }
+
+    (0..cfg.workers())
+        .map(|_| {
+            let socket = match cfg.socket() {
+                SocketAddr::V4(_) => TcpSocket::new_v4()?,
+                SocketAddr::V6(_) => TcpSocket::new_v6()?,
+            };
+            #[cfg(unix)]
+            {
+                socket.set_reuseaddr(true)?;
+                socket.set_reuseport(cfg.reuse_port())?;
+            }
+            let options = cfg.socket_options();
+            if let Some(size) = options.recv_buffer_size() {
+                socket.set_recv_buffer_size(size)?;
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(device) = options.bind_device() {
+                SockRef::from(&socket).bind_device(Some(device.as_bytes()))?;
+            }
+            socket.bind(cfg.socket())?;
+            Ok(socket.listen(options.backlog())?)
+        })
+        .collect()
+}
+
+/// Listener which serves connections accepted on the socket
+struct Endpoint {
+    handler: watch::Receiver<RequestHandler>,
+    connections: Arc<ConnectionTracker>,
+    tls: Option<TlsTermination>,
+    /// Advertisement of HTTP/3 endpoint of the listener, if it's served
+    alt_svc: Option<HeaderValue>,
+}
+
 async fn service_loop(
     listener: TcpListener,
-    ctx: &'static Context<'static>,
-    cfg: &'static ListenerConfig,
+    endpoints: Arc<Vec<Endpoint>>,
 ) -> Result<(), anyhow::Error> {
     let mut join_set = JoinSet::new();
 
-    let name = cfg.id();
-    let handler = RequestHandler::new(cfg, ctx);
+    let name = endpoints[0].handler.borrow().listener_cfg().id();
     let mut signal_handler = SignalHandler::new(name);
 
     loop {
@@ -82,16 +374,87 @@ async fn service_loop(
                     }
                 };
 
+                let endpoints = endpoints.clone();
                 let serve_connection = async move {
-                    let result = Builder::new(TokioExecutor::new())
+                    let timeout = endpoints[0].handler.borrow().listener_cfg().timeout();
+                    // Handshake is limited by the same timeout as request headers
+                    let accepted = if endpoints.len() > 1 {
+                        let terminations = endpoints
+                            .iter()
+                            .map(|endpoint| {
+                                endpoint.tls.as_ref().expect(
+                                    "listener which shares socket has no TLS termination, looks like a BUG",
+                                )
+                            })
+                            .collect::<Vec<_>>();
+                        tokio::time::timeout(
+                            timeout,
+                            TlsTermination::accept_by_server_name(&terminations, stream),
+                        )
+                        .await
+                    } else {
+                        match &endpoints[0].tls {
+                            Some(tls) => tokio::time::timeout(timeout, tls.accept(stream))
+                                .await
+                                .map(|accepted| accepted.map(|stream| (0, stream))),
+                            None => Ok(Ok((0, ServerStream::Plain(stream)))),
+                        }
+                    };
+                    let (endpoint, stream) = match accepted {
+                        Ok(Ok((pos, stream))) => (&endpoints[pos], stream),
+                        Ok(Err(e)) => {
+                            debug!("TLS handshake with {addr} failed: {e}");
+                            return;
+                        }
+                        Err(_) => {
+                            debug!("TLS handshake with {addr} timed out");
+                            return;
+                        }
+                    };
+
+                    let handler = endpoint.handler.clone();
+                    let timeout = handler.borrow().listener_cfg().timeout();
+                    let keep_alive = endpoint.connections.keep_alive();
+                    let connection = endpoint.connections.open(stream.tcp());
+                    let mut builder = Builder::new(TokioExecutor::new());
+                    builder
                         .http1()
                         .timer(TokioTimer::default())
-                        .header_read_timeout(cfg.timeout())
-                        .serve_connection(
-                            TokioIo::new(stream),
-                            service_fn(move |req| handler.handle(addr, req)),
-                        )
-                        .await;
+                        .header_read_timeout(timeout)
+                        .keep_alive(keep_alive);
+                    let conn = builder.serve_connection_with_upgrades(
+                        TokioIo::new(stream),
+                        service_fn(|req| {
+                            let request = connection.request();
+                            // Each request is served by the current handler, even if it's reloaded
+                            let current = handler.borrow().clone();
+                            let resp = if forward_proxy::is_tunnel(current.listener_cfg(), &req) {
+                                Either::Left(forward_proxy::tunnel(handler.clone(), addr, req))
+                            } else {
+                                Either::Right(current.handle(addr, req))
+                            };
+                            let alt_svc = endpoint.alt_svc.clone();
+                            async move {
+                                let mut resp = resp.await;
+                                drop(request);
+                                if let (Ok(resp), Some(alt_svc)) = (&mut resp, alt_svc) {
+                                    resp.headers_mut().insert(ALT_SVC, alt_svc);
+                                }
+                                resp
+                            }
+                        }),
+                    );
+                    tokio::pin!(conn);
+
+                    // Connection is closed gracefully, after the response to the request in progress
+                    let result = select! {
+                        result = conn.as_mut() => result,
+                        reason = connection.wait_limit() => {
+                            debug!("closing connection from {addr}: {reason}");
+                            conn.as_mut().graceful_shutdown();
+                            conn.await
+                        }
+                    };
 
                     if let Err(e) = result {
                         error!(error = %e, "error serving request from {addr}");
@@ -105,3 +468,37 @@ async fn service_loop(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listener_cfg(listen_on: &str, socket: &str) -> ListenerConfig {
+        serde_json::from_str(&format!(
+            r#"{{"listen_on": "{listen_on}", "socket": {socket}, "targets": [{{"url": "http://localhost/"}}]}}"#
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn bind_with_socket_options() {
+        let cfg = listener_cfg(
+            "127.0.0.1:9940",
+            r#"{"backlog": 16, "recv_buffer_size": 65536}"#,
+        );
+        let listeners = bind(&cfg, &mut InheritedSockets::default()).unwrap();
+        assert_eq!(listeners.len(), 1);
+        // OS may round the buffer size up, but not below the requested one
+        let size = socket2::SockRef::from(&listeners[0])
+            .recv_buffer_size()
+            .unwrap();
+        assert!(size >= 65536, "{size}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn bind_to_unknown_device() {
+        let cfg = listener_cfg("127.0.0.1:9941", r#"{"bind_device": "no-such-device"}"#);
+        assert!(bind(&cfg, &mut InheritedSockets::default()).is_err());
+    }
+}
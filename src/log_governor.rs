@@ -0,0 +1,293 @@
+use crate::config::logging::LoggingConfig;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, Mutex, Once},
+    time::{Duration, Instant},
+};
+use tracing::{error, field::Field, warn, Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Filter};
+
+/// Target of the summaries, they aren't governed
+const GOVERNOR_TARGET: &str = "log_governor";
+/// How often expired windows are checked for suppressed messages
+const SUMMARY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Messages above this number aren't tracked, so they're logged as is
+const MAX_GOVERNED_MESSAGES: usize = 10000;
+
+/// Request IDs differ in otherwise identical messages
+static REQUEST_ID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new("[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}")
+        .expect("invalid request ID regex, looks like a BUG")
+});
+
+/// Active governor, it's set by config loading; messages aren't governed if it's `None`
+static GOVERNOR: LazyLock<Mutex<Option<Governor>>> = LazyLock::new(|| Mutex::new(None));
+static SUMMARIES: Once = Once::new();
+
+/// Filter of the log events which suppresses repeated warnings and errors
+pub struct GovernorFilter;
+
+struct Governor {
+    interval: Duration,
+    limit: usize,
+    classes: Vec<(Regex, Duration, usize)>,
+    messages: HashMap<(Level, String), Window>,
+    /// Summaries of the windows which were restarted by the new message before they were reported
+    pending: Vec<Summary>,
+}
+
+/// Counters of the single message during its interval
+struct Window {
+    started_at: Instant,
+    interval: Duration,
+    logged: usize,
+    suppressed: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    level: Level,
+    message: String,
+    suppressed: usize,
+    interval: Duration,
+}
+
+/// Replaces governor by the configured one, counters of the previous one are dropped
+pub fn configure(cfg: &LoggingConfig) {
+    let governor = cfg.governor().map(|cfg| Governor {
+        interval: cfg.interval(),
+        limit: cfg.limit(),
+        classes: cfg
+            .classes()
+            .iter()
+            .map(|class| {
+                (
+                    class.pattern(),
+                    class.interval().unwrap_or(cfg.interval()),
+                    class.limit().unwrap_or(cfg.limit()),
+                )
+            })
+            .collect(),
+        messages: HashMap::new(),
+        pending: vec![],
+    });
+    *lock() = governor;
+}
+
+/// Starts periodic reporting of the suppressed messages, it's started once per process
+pub fn start_summaries() {
+    SUMMARIES.call_once(|| {
+        tokio::spawn(async {
+            let mut interval = tokio::time::interval(SUMMARY_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                // Lock is released before logging, since summaries pass the filter too
+                let summaries = lock()
+                    .as_mut()
+                    .map(|governor| governor.flush(Instant::now()))
+                    .unwrap_or_default();
+                for summary in summaries {
+                    summary.log();
+                }
+            }
+        });
+    });
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Governor>> {
+    GOVERNOR
+        .lock()
+        .expect("unable to lock log governor, looks like a BUG")
+}
+
+impl Governor {
+    /// Returns `true` if the message should be logged
+    fn check(&mut self, level: Level, message: &str, now: Instant) -> bool {
+        let message = REQUEST_ID.replace_all(message, "*");
+        let key = (level, message.into_owned());
+        if !self.messages.contains_key(&key) && self.messages.len() >= MAX_GOVERNED_MESSAGES {
+            return true;
+        }
+        let (interval, limit) = self
+            .classes
+            .iter()
+            .find(|(pattern, _, _)| pattern.is_match(&key.1))
+            .map_or((self.interval, self.limit), |(_, interval, limit)| {
+                (*interval, *limit)
+            });
+        let window = self.messages.entry(key.clone()).or_insert(Window {
+            started_at: now,
+            interval,
+            logged: 0,
+            suppressed: 0,
+        });
+        if now.duration_since(window.started_at) >= window.interval {
+            if window.suppressed > 0 {
+                self.pending.push(Summary {
+                    level: key.0,
+                    message: key.1,
+                    suppressed: window.suppressed,
+                    interval: window.interval,
+                });
+            }
+            *window = Window {
+                started_at: now,
+                interval,
+                logged: 0,
+                suppressed: 0,
+            };
+        }
+        if window.logged < limit {
+            window.logged += 1;
+            true
+        } else {
+            window.suppressed += 1;
+            false
+        }
+    }
+
+    /// Returns summaries of the expired windows with suppressed messages, expired windows are dropped
+    fn flush(&mut self, now: Instant) -> Vec<Summary> {
+        let mut summaries = std::mem::take(&mut self.pending);
+        self.messages.retain(|(level, message), window| {
+            if now.duration_since(window.started_at) < window.interval {
+                return true;
+            }
+            if window.suppressed > 0 {
+                summaries.push(Summary {
+                    level: *level,
+                    message: message.clone(),
+                    suppressed: window.suppressed,
+                    interval: window.interval,
+                });
+            }
+            false
+        });
+
+        summaries
+    }
+}
+
+impl Summary {
+    fn log(&self) {
+        let interval = humantime::format_duration(self.interval);
+        if self.level == Level::ERROR {
+            error!(target: GOVERNOR_TARGET, "{}: suppressed {} more time(s) in last {interval}", self.message, self.suppressed);
+        } else {
+            warn!(target: GOVERNOR_TARGET, "{}: suppressed {} more time(s) in last {interval}", self.message, self.suppressed);
+        }
+    }
+}
+
+impl<S: Subscriber> Filter<S> for GovernorFilter {
+    fn enabled(&self, _meta: &Metadata<'_>, _cx: &Context<'_, S>) -> bool {
+        true
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, _cx: &Context<'_, S>) -> bool {
+        let meta = event.metadata();
+        if *meta.level() > Level::WARN || meta.target() == GOVERNOR_TARGET {
+            return true;
+        }
+        let mut governor = lock();
+        let Some(governor) = governor.as_mut() else {
+            return true;
+        };
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+
+        governor.check(*meta.level(), &message.0, Instant::now())
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{value:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_messages_are_summarized() {
+        let mut governor = Governor {
+            interval: Duration::from_secs(60),
+            limit: 2,
+            classes: vec![(
+                Regex::new("target `dead`").unwrap(),
+                Duration::from_secs(10),
+                0,
+            )],
+            messages: HashMap::new(),
+            pending: vec![],
+        };
+        let start = Instant::now();
+        let failed = |id: &str| format!("{id}: target `main` failed: connection refused");
+
+        // Messages which differ by request ID only are identical
+        assert!(governor.check(
+            Level::ERROR,
+            &failed("7a1e1c70-7d32-4a4c-9d3e-1b7e0d8b6f01"),
+            start
+        ));
+        assert!(governor.check(
+            Level::ERROR,
+            &failed("0f4b5a1e-2c3d-4e5f-8a9b-0c1d2e3f4a5b"),
+            start
+        ));
+        for _ in 0..3 {
+            assert!(!governor.check(
+                Level::ERROR,
+                &failed("0f4b5a1e-2c3d-4e5f-8a9b-0c1d2e3f4a5b"),
+                start
+            ));
+        }
+        // Other level or message isn't affected
+        assert!(governor.check(
+            Level::WARN,
+            &failed("0f4b5a1e-2c3d-4e5f-8a9b-0c1d2e3f4a5b"),
+            start
+        ));
+        assert!(governor.check(Level::ERROR, "other", start));
+
+        // Class has its own rate
+        assert!(!governor.check(Level::WARN, "target `dead` is unreachable", start));
+        assert!(governor.flush(start + Duration::from_secs(1)).is_empty());
+        assert_eq!(
+            governor.flush(start + Duration::from_secs(10)),
+            vec![Summary {
+                level: Level::WARN,
+                message: "target `dead` is unreachable".into(),
+                suppressed: 1,
+                interval: Duration::from_secs(10),
+            }]
+        );
+
+        // New message after the interval restarts the window, suppressed ones are reported anyway
+        let later = start + Duration::from_secs(60);
+        assert!(governor.check(
+            Level::ERROR,
+            &failed("7a1e1c70-7d32-4a4c-9d3e-1b7e0d8b6f01"),
+            later
+        ));
+        assert_eq!(
+            governor.flush(later),
+            vec![Summary {
+                level: Level::ERROR,
+                message: failed("*"),
+                suppressed: 3,
+                interval: Duration::from_secs(60),
+            }]
+        );
+        assert_eq!(governor.messages.len(), 1);
+    }
+}
@@ -1,10 +1,25 @@
-use http_dragonfly::{cli::CliConfig, context::RootOsEnvironment};
+use http_dragonfly::{
+    cli::{CliCommand, CliConfig},
+    config::AppConfig,
+    context::RootOsEnvironment,
+};
 
-#[tokio::main]
-async fn main() -> Result<(), anyhow::Error> {
+fn main() -> Result<(), anyhow::Error> {
     let cli_config = CliConfig::new();
     let env_mask = cli_config.env_mask().to_string();
     let env_provider = RootOsEnvironment::new(&env_mask);
 
-    http_dragonfly::run(cli_config, env_provider).await
+    match cli_config.command() {
+        Some(CliCommand::Validate { .. }) => http_dragonfly::validate(cli_config, env_provider),
+        Some(CliCommand::Schema) => {
+            println!("{}", AppConfig::json_schema());
+            Ok(())
+        }
+        #[cfg(windows)]
+        None if cli_config.service() => http_dragonfly::service::run(cli_config),
+        None => cli_config
+            .runtime_config()
+            .runtime("http-dragonfly")?
+            .block_on(http_dragonfly::run(cli_config, env_provider)),
+    }
 }
@@ -0,0 +1,15 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Global maintenance mode switch, it's controlled by the admin API
+/// and available to the target conditions as `.maintenance`
+static MAINTENANCE: AtomicBool = AtomicBool::new(false);
+
+/// Returns `true` if maintenance mode is on
+pub fn is_enabled() -> bool {
+    MAINTENANCE.load(Ordering::Relaxed)
+}
+
+/// Switches maintenance mode on or off
+pub fn set_enabled(enabled: bool) {
+    MAINTENANCE.store(enabled, Ordering::Relaxed)
+}
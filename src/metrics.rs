@@ -0,0 +1,100 @@
+use std::{
+    fmt::Write,
+    sync::{Arc, LazyLock, Mutex, Weak},
+};
+
+/// Content type of the metrics in Prometheus text format
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Prefix of all metric names
+const METRICS_PREFIX: &str = "http_dragonfly";
+
+static REGISTRY: LazyLock<Mutex<Vec<Weak<dyn MetricsSource>>>> =
+    LazyLock::new(|| Mutex::new(vec![]));
+
+/// Component which reports its own metrics
+pub trait MetricsSource: Send + Sync {
+    /// Writes current values of the metrics using `gauge` or similar helpers
+    fn write_metrics(&self, out: &mut String);
+}
+
+/// Registers source of metrics, it's unregistered automatically when the source is dropped
+pub fn register(source: Arc<dyn MetricsSource>) {
+    REGISTRY
+        .lock()
+        .expect("unable to lock metrics registry, looks like a BUG")
+        .push(Arc::downgrade(&source));
+}
+
+/// Renders metrics of all registered sources in Prometheus text format
+pub fn render() -> String {
+    let sources: Vec<Arc<dyn MetricsSource>> = {
+        let mut registry = REGISTRY
+            .lock()
+            .expect("unable to lock metrics registry, looks like a BUG");
+        registry.retain(|source| source.strong_count() > 0);
+        registry.iter().filter_map(Weak::upgrade).collect()
+    };
+
+    let mut out = String::new();
+    for source in sources {
+        source.write_metrics(&mut out);
+    }
+
+    out
+}
+
+/// Writes single gauge value with labels
+pub fn gauge<'a>(
+    out: &mut String,
+    name: &str,
+    labels: impl IntoIterator<Item = (&'a str, &'a str)>,
+    value: f64,
+) {
+    let labels: Vec<String> = labels
+        .into_iter()
+        .map(|(name, value)| format!("{name}=\"{}\"", escape(value)))
+        .collect();
+    let _ = writeln!(
+        out,
+        "{METRICS_PREFIX}_{name}{{{}}} {value}",
+        labels.join(",")
+    );
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSource;
+
+    impl MetricsSource for TestSource {
+        fn write_metrics(&self, out: &mut String) {
+            gauge(
+                out,
+                "test_gauge",
+                [("target", "a\"b"), ("env", "test")],
+                0.5,
+            );
+        }
+    }
+
+    #[test]
+    fn render_registered_sources() {
+        let source: Arc<dyn MetricsSource> = Arc::new(TestSource);
+        register(source.clone());
+        assert!(
+            render().contains("http_dragonfly_test_gauge{target=\"a\\\"b\",env=\"test\"} 0.5\n")
+        );
+
+        drop(source);
+        assert!(!render().contains("http_dragonfly_test_gauge"));
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    config::plugins::PluginConfig,
+    streaming::{self, RequestBody},
+};
+use http_body_util::Full;
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue, CONTENT_LENGTH},
+    http::request::Parts,
+    HeaderMap, Request, Response, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use strum_macros::Display;
+use tracing::{debug, error};
+
+#[cfg(feature = "wasm-plugins")]
+mod wasm;
+
+#[cfg(not(feature = "wasm-plugins"))]
+use disabled::Plugin;
+#[cfg(feature = "wasm-plugins")]
+use wasm::WasmPlugin as Plugin;
+
+/// Stage of the request processing which plugins are called on
+#[derive(Clone, Copy, Debug, Display)]
+pub enum Hook {
+    /// Original request, before any listener's processing
+    #[strum(serialize = "on_request")]
+    Request,
+    /// Request to the target, after all target's transformations
+    #[strum(serialize = "on_target_request")]
+    TargetRequest,
+    /// Buffered response of the target
+    #[strum(serialize = "on_target_response")]
+    TargetResponse,
+    /// Final buffered response to the requester
+    #[strum(serialize = "on_response")]
+    Response,
+}
+
+/// WASM plugins of the listener, they're called one by one in order of declaration
+#[derive(Debug)]
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+}
+
+/// Part of the request or response which is passed to the hook and may be changed by it
+struct HookMessage {
+    status: Option<StatusCode>,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Changes requested by the hook, all parameters are optional
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct HookOutput {
+    status: Option<u16>,
+    /// Headers to set, `null` value removes header
+    #[serde(default)]
+    headers: HashMap<String, Option<String>>,
+    body: Option<String>,
+}
+
+impl Plugins {
+    /// Loads and compiles all plugins of the listener, returns `None` if there are no plugins
+    pub fn new(cfgs: &[PluginConfig]) -> Result<Option<Self>, anyhow::Error> {
+        if cfgs.is_empty() {
+            return Ok(None);
+        }
+
+        let plugins = cfgs.iter().map(Plugin::new).collect::<Result<_, _>>()?;
+        Ok(Some(Self { plugins }))
+    }
+
+    /// Calls `on_request` hook with the original request,
+    /// returns response if any plugin has set status to respond immediately instead of forwarding.
+    /// Failed plugin stops processing with `500` response, so requests aren't forwarded without check
+    pub fn on_request(
+        &self,
+        req_parts: &mut Parts,
+        body: &mut Bytes,
+    ) -> Option<Response<Full<Bytes>>> {
+        let attributes = json!({
+            "method": req_parts.method.as_str(),
+            "uri": req_parts.uri.to_string(),
+        });
+        let mut msg = HookMessage {
+            status: None,
+            headers: std::mem::take(&mut req_parts.headers),
+            body: std::mem::take(body),
+        };
+        let result = self.run(Hook::Request, &attributes, &mut msg);
+        req_parts.headers = msg.headers;
+        *body = msg.body;
+
+        match result {
+            Ok(false) => None,
+            Ok(true) => {
+                let mut resp = Response::new(Full::new(body.clone()));
+                *resp.status_mut() = msg.status.unwrap_or_default();
+                *resp.headers_mut() = req_parts.headers.clone();
+                resp.headers_mut().remove(CONTENT_LENGTH);
+                Some(resp)
+            }
+            Err(()) => {
+                let mut resp = Response::new(Full::default());
+                *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                Some(resp)
+            }
+        }
+    }
+
+    /// Calls `on_target_request` hook with the request to the target
+    pub fn on_target_request(
+        &self,
+        target_id: &str,
+        req: Request<RequestBody>,
+    ) -> Request<RequestBody> {
+        let attributes = json!({
+            "target": target_id,
+            "method": req.method().as_str(),
+            "uri": req.uri().to_string(),
+        });
+        let (mut parts, body) = req.into_parts();
+        let mut msg = HookMessage {
+            status: None,
+            headers: std::mem::take(&mut parts.headers),
+            body: body.data(),
+        };
+        let _ = self.run(Hook::TargetRequest, &attributes, &mut msg);
+        parts.headers = msg.headers;
+
+        Request::from_parts(
+            parts,
+            RequestBody::with_trailers(msg.body, body.trailers().cloned()),
+        )
+    }
+
+    /// Calls `on_target_response` hook with the buffered response of the target
+    pub fn on_target_response(
+        &self,
+        target_id: &str,
+        resp: Response<Full<Bytes>>,
+    ) -> Response<Full<Bytes>> {
+        self.run_on_response(Hook::TargetResponse, json!({"target": target_id}), resp)
+    }
+
+    /// Calls `on_response` hook with the final response
+    pub fn on_response(&self, resp: Response<Full<Bytes>>) -> Response<Full<Bytes>> {
+        self.run_on_response(Hook::Response, json!({}), resp)
+    }
+
+    fn run_on_response(
+        &self,
+        hook: Hook,
+        attributes: Value,
+        resp: Response<Full<Bytes>>,
+    ) -> Response<Full<Bytes>> {
+        let (mut parts, body) = resp.into_parts();
+        let mut msg = HookMessage {
+            status: Some(parts.status),
+            headers: std::mem::take(&mut parts.headers),
+            body: streaming::full_bytes(&body),
+        };
+        let _ = self.run(hook, &attributes, &mut msg);
+        parts.headers = msg.headers;
+        parts.status = msg.status.unwrap_or(parts.status);
+
+        Response::from_parts(parts, Full::new(msg.body))
+    }
+
+    /// Calls hook of each plugin with the message changed by the previous one,
+    /// returns `true` if any plugin has set status, and error if any plugin has failed
+    fn run(&self, hook: Hook, attributes: &Value, msg: &mut HookMessage) -> Result<bool, ()> {
+        let mut status_changed = false;
+        for plugin in &self.plugins {
+            let input = msg.input(hook, attributes, plugin.config());
+            let output = match plugin.call(hook, &input) {
+                Ok(Some(output)) => output,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("plugin `{}` failed on `{hook}`: {e:#}", plugin.name());
+                    return Err(());
+                }
+            };
+            match msg.apply(&output) {
+                Ok(changed) => status_changed |= changed,
+                Err(e) => {
+                    error!(
+                        "plugin `{}` returned invalid output on `{hook}`: {e}",
+                        plugin.name()
+                    );
+                    return Err(());
+                }
+            }
+        }
+
+        Ok(status_changed)
+    }
+}
+
+impl HookMessage {
+    /// Serializes message to JSON input of the hook, body is passed if it's a valid UTF-8 only
+    fn input(&self, hook: Hook, attributes: &Value, config: &Value) -> Vec<u8> {
+        let headers: HashMap<&str, &str> = self
+            .headers
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str(), v.to_str().ok()?)))
+            .collect();
+        let mut input = json!({
+            "hook": hook.to_string(),
+            "config": config,
+            "headers": headers,
+            "body": std::str::from_utf8(&self.body).ok(),
+        });
+        if let Some(status) = self.status {
+            input["status"] = json!(status.as_u16());
+        }
+        if let (Some(input), Some(attributes)) = (input.as_object_mut(), attributes.as_object()) {
+            input.extend(attributes.clone());
+        }
+
+        serde_json::to_vec(&input).expect("unable to serialize hook input, looks like a BUG")
+    }
+
+    /// Applies output of the hook, returns `true` if status has been set
+    fn apply(&mut self, output: &[u8]) -> Result<bool, String> {
+        let output: HookOutput = serde_json::from_slice(output).map_err(|e| e.to_string())?;
+        debug!("hook output: {output:?}");
+
+        for (name, value) in output.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| format!("invalid header name `{name}`: {e}"))?;
+            match value {
+                Some(value) => {
+                    let value = HeaderValue::from_str(&value)
+                        .map_err(|e| format!("invalid value of header `{name}`: {e}"))?;
+                    self.headers.insert(name, value);
+                }
+                None => {
+                    self.headers.remove(name);
+                }
+            }
+        }
+        if let Some(body) = output.body {
+            self.body = Bytes::from(body);
+            self.headers.remove(CONTENT_LENGTH);
+        }
+        match output.status {
+            Some(status) => {
+                self.status =
+                    Some(StatusCode::from_u16(status).map_err(|e| format!("invalid status: {e}"))?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Plugins can't be loaded without `wasm-plugins` feature, it's verified during config validation
+#[cfg(not(feature = "wasm-plugins"))]
+mod disabled {
+    use super::Hook;
+    use crate::config::plugins::PluginConfig;
+    use serde_json::Value;
+
+    #[derive(Debug)]
+    pub enum Plugin {}
+
+    impl Plugin {
+        pub fn new(_cfg: &PluginConfig) -> Result<Self, anyhow::Error> {
+            anyhow::bail!(
+                "plugins aren't supported, application is built without `wasm-plugins` feature"
+            )
+        }
+
+        pub fn name(&self) -> &str {
+            match *self {}
+        }
+
+        pub fn config(&self) -> &Value {
+            match *self {}
+        }
+
+        pub fn call(&self, _hook: Hook, _input: &[u8]) -> Result<Option<Vec<u8>>, anyhow::Error> {
+            match *self {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_hook_output() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-remove", HeaderValue::from_static("1"));
+        headers.insert(CONTENT_LENGTH, HeaderValue::from_static("4"));
+        let mut msg = HookMessage {
+            status: None,
+            headers,
+            body: Bytes::from("body"),
+        };
+
+        let input: Value = serde_json::from_slice(&msg.input(
+            Hook::Request,
+            &json!({"method": "GET"}),
+            &json!({"key": 1}),
+        ))
+        .unwrap();
+        assert_eq!(input["hook"], "on_request");
+        assert_eq!(input["method"], "GET");
+        assert_eq!(input["config"]["key"], 1);
+        assert_eq!(input["headers"]["x-remove"], "1");
+        assert_eq!(input["body"], "body");
+
+        let changed = msg
+            .apply(br#"{"headers": {"x-remove": null, "x-added": "2"}, "body": "new"}"#)
+            .unwrap();
+        assert!(!changed);
+        assert_eq!(msg.headers.get("x-remove"), None);
+        assert_eq!(msg.headers.get(CONTENT_LENGTH), None);
+        assert_eq!(msg.headers["x-added"], "2");
+        assert_eq!(msg.body, "new");
+
+        assert!(msg.apply(br#"{"status": 401}"#).unwrap());
+        assert_eq!(msg.status, Some(StatusCode::UNAUTHORIZED));
+        assert!(msg.apply(br#"{"status": 1000}"#).is_err());
+        assert!(msg.apply(br#"{"unknown": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use super::Hook;
+use crate::config::plugins::PluginConfig;
+use serde_json::Value;
+use std::collections::HashSet;
+use tracing::info;
+use wasmtime::{
+    bail, error::Context as _, format_err, Caller, Config, Engine, InstancePre, Linker, Memory,
+    Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+/// Loaded and linked WASM module, it's instantiated in the isolated store on each hook call.
+///
+/// Module should export `memory` and `alloc(len: i32) -> i32` to receive hook's input,
+/// each hook is an optional export `<hook>(ptr: i32, len: i32) -> i64` which returns
+/// `0` to keep message as is or `(ptr << 32) | len` of the JSON output in the module's memory.
+pub struct WasmPlugin {
+    name: String,
+    config: Value,
+    fuel: u64,
+    max_memory: usize,
+    engine: Engine,
+    instance: InstancePre<StoreState>,
+    hooks: HashSet<String>,
+}
+
+struct StoreState {
+    limits: StoreLimits,
+    name: String,
+}
+
+impl WasmPlugin {
+    pub fn new(cfg: &PluginConfig) -> Result<Self, wasmtime::Error> {
+        let name = cfg.path().display().to_string();
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+
+        let module = Module::from_file(&engine, cfg.path())
+            .with_context(|| format!("unable to load plugin `{name}`"))?;
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("env", "log", log)?;
+        let instance = linker
+            .instantiate_pre(&module)
+            .with_context(|| format!("unable to link plugin `{name}`"))?;
+
+        let hooks: HashSet<String> = [
+            Hook::Request,
+            Hook::TargetRequest,
+            Hook::TargetResponse,
+            Hook::Response,
+        ]
+        .iter()
+        .map(Hook::to_string)
+        .filter(|hook| module.get_export(hook).is_some())
+        .collect();
+        info!("plugin `{name}` loaded, hooks: {hooks:?}");
+
+        Ok(Self {
+            name,
+            config: cfg.config().clone(),
+            fuel: cfg.fuel(),
+            max_memory: cfg.max_memory(),
+            engine,
+            instance,
+            hooks,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn config(&self) -> &Value {
+        &self.config
+    }
+
+    /// Calls hook with JSON input, returns JSON output or `None` if plugin doesn't change message
+    pub fn call(&self, hook: Hook, input: &[u8]) -> Result<Option<Vec<u8>>, wasmtime::Error> {
+        let hook = hook.to_string();
+        if !self.hooks.contains(&hook) {
+            return Ok(None);
+        }
+
+        let state = StoreState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.max_memory)
+                .build(),
+            name: self.name.clone(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.fuel)?;
+
+        let instance = self.instance.instantiate(&mut store)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| format_err!("plugin doesn't export `memory`"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc")?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&mut store, &hook)?;
+
+        let len = i32::try_from(input.len()).context("hook input is too large")?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, input)?;
+
+        let result = func.call(&mut store, (ptr, len))?;
+        if result == 0 {
+            return Ok(None);
+        }
+        let ptr = (result as u64 >> 32) as usize;
+        let len = (result as u64 & 0xffff_ffff) as usize;
+
+        Ok(Some(read(&memory, &store, ptr, len)?.to_vec()))
+    }
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("name", &self.name)
+            .field("hooks", &self.hooks)
+            .finish()
+    }
+}
+
+/// Host function `env.log(ptr, len)` to write UTF-8 message to the application's log
+fn log(mut caller: Caller<'_, StoreState>, ptr: i32, len: i32) -> Result<(), wasmtime::Error> {
+    let Some(memory) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        bail!("plugin doesn't export `memory`");
+    };
+    let msg = read(&memory, &caller, ptr as u32 as usize, len as u32 as usize)?;
+    info!(
+        "plugin `{}`: {}",
+        caller.data().name,
+        String::from_utf8_lossy(msg)
+    );
+
+    Ok(())
+}
+
+fn read<'a>(
+    memory: &Memory,
+    store: &'a impl wasmtime::AsContext<Data = StoreState>,
+    ptr: usize,
+    len: usize,
+) -> Result<&'a [u8], wasmtime::Error> {
+    ptr.checked_add(len)
+        .and_then(|end| memory.data(store).get(ptr..end))
+        .ok_or_else(|| format_err!("plugin returned data out of memory bounds"))
+}
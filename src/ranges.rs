@@ -0,0 +1,71 @@
+use hyper::{
+    body::Bytes,
+    header::{CONTENT_RANGE, IF_RANGE, RANGE},
+    HeaderMap, Response, StatusCode,
+};
+
+/// Marks response of the target which was requested for the full body instead of the requested range,
+/// so it isn't selected as response to the requester
+#[derive(Clone, Copy, Debug)]
+pub struct FullContent;
+
+/// Returns `true` if request asks for part of the resource
+pub fn is_ranged(headers: &HeaderMap) -> bool {
+    headers.contains_key(RANGE)
+}
+
+/// Drops headers which make request partial
+pub fn strip_range_headers(headers: &mut HeaderMap) {
+    headers.remove(RANGE);
+    headers.remove(IF_RANGE);
+}
+
+/// Returns `true` if response is to the full body request instead of the requested range
+pub fn is_full_content<B>(resp: &Response<B>) -> bool {
+    resp.extensions().get::<FullContent>().is_some()
+}
+
+/// Returns first and last byte positions of the partial response with single range
+pub fn content_range<B>(resp: &Response<B>) -> Option<(usize, usize)> {
+    if resp.status() != StatusCode::PARTIAL_CONTENT {
+        return None;
+    }
+    let value = resp.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, _) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let (first, last) = (first.trim().parse().ok()?, last.trim().parse().ok()?);
+
+    (first <= last).then_some((first, last))
+}
+
+/// Returns part of the full body which corresponds to the range of the partial response
+pub fn slice(body: &Bytes, (first, last): (usize, usize)) -> Bytes {
+    let end = last.saturating_add(1).min(body.len());
+    body.slice(first.min(end)..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_response_range() {
+        let resp = |status: u16, range: &str| {
+            Response::builder()
+                .status(status)
+                .header(CONTENT_RANGE, range)
+                .body(())
+                .unwrap()
+        };
+        assert_eq!(content_range(&resp(206, "bytes 0-99/1000")), Some((0, 99)));
+        assert_eq!(content_range(&resp(206, "bytes 10-19/*")), Some((10, 19)));
+        assert_eq!(content_range(&resp(200, "bytes 0-99/1000")), None);
+        assert_eq!(content_range(&resp(206, "bytes */1000")), None);
+        assert_eq!(content_range(&resp(206, "bytes 20-10/1000")), None);
+
+        let body = Bytes::from_static(b"0123456789");
+        assert_eq!(slice(&body, (2, 4)), "234");
+        assert_eq!(slice(&body, (8, 20)), "89");
+        assert_eq!(slice(&body, (12, 20)), "");
+    }
+}
@@ -0,0 +1,144 @@
+use hyper::HeaderMap;
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{LazyLock, RwLock},
+};
+
+/// Replacement of the sensitive header values
+pub const MASK: &str = "***";
+/// Headers which values are masked if `sensitive_headers` isn't configured
+pub const DEFAULT_SENSITIVE_HEADERS: [&str; 3] = ["authorization", "cookie", "set-cookie"];
+
+/// Lowercase names of the headers which values are masked in logs and config dump,
+/// it's set by config loading
+static SENSITIVE_HEADERS: LazyLock<RwLock<HashSet<String>>> = LazyLock::new(|| {
+    RwLock::new(
+        DEFAULT_SENSITIVE_HEADERS
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+    )
+});
+
+/// Values of the secrets which are masked in logs and config dump, longest ones first
+static SECRETS: LazyLock<RwLock<Vec<String>>> = LazyLock::new(|| RwLock::new(vec![]));
+
+/// Replaces list of the sensitive headers
+pub fn set_sensitive_headers(names: &[String]) {
+    *SENSITIVE_HEADERS
+        .write()
+        .expect("unable to lock sensitive headers, looks like a BUG") =
+        names.iter().map(|name| name.to_ascii_lowercase()).collect();
+}
+
+/// Returns `true` if value of the header shouldn't be logged
+pub fn is_sensitive(name: &str) -> bool {
+    SENSITIVE_HEADERS
+        .read()
+        .expect("unable to lock sensitive headers, looks like a BUG")
+        .contains(&name.to_ascii_lowercase())
+}
+
+/// Returns value of the header as is or masked if header is sensitive
+pub fn value<'a>(name: &str, value: &'a str) -> &'a str {
+    if is_sensitive(name) {
+        MASK
+    } else {
+        value
+    }
+}
+
+/// Adds values of the secrets which are masked by `mask_secrets`
+pub fn add_secrets<'a>(values: impl IntoIterator<Item = &'a String>) {
+    let mut secrets = SECRETS
+        .write()
+        .expect("unable to lock secrets, looks like a BUG");
+    for value in values.into_iter().filter(|value| !value.is_empty()) {
+        if !secrets.contains(value) {
+            secrets.push(value.clone());
+        }
+    }
+    // Longer secrets may contain shorter ones, so they're replaced first
+    secrets.sort_by_key(|value| std::cmp::Reverse(value.len()));
+}
+
+/// Returns text with all secret values masked, `Debug` escaped values are masked too
+pub fn mask_secrets(text: &str) -> String {
+    let secrets = SECRETS
+        .read()
+        .expect("unable to lock secrets, looks like a BUG");
+    let mut text = text.to_string();
+    for secret in secrets.iter() {
+        text = text.replace(secret.as_str(), MASK);
+        let escaped = format!("{secret:?}");
+        let escaped = &escaped[1..escaped.len() - 1];
+        if escaped != secret {
+            text = text.replace(escaped, MASK);
+        }
+    }
+
+    text
+}
+
+/// Headers which are formatted by `Debug` with masked values of the sensitive ones
+pub struct Headers<'a>(&'a HeaderMap);
+
+/// Wraps headers to log them
+pub fn headers(headers: &HeaderMap) -> Headers<'_> {
+    Headers(headers)
+}
+
+impl fmt::Debug for Headers<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(name, value)| {
+                let value: &dyn fmt::Debug = if is_sensitive(name.as_str()) {
+                    &MASK
+                } else {
+                    value
+                };
+                (name, value)
+            }))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer secret".parse().unwrap());
+        headers.insert("x-request-id", "42".parse().unwrap());
+
+        assert_eq!(
+            format!("{:?}", self::headers(&headers)),
+            r#"{"authorization": "***", "x-request-id": "42"}"#
+        );
+        assert_eq!(value("Cookie", "id=1"), MASK);
+        assert_eq!(value("x-request-id", "42"), "42");
+    }
+
+    #[test]
+    fn masked_secrets() {
+        add_secrets(&[
+            "s3cr3t".to_string(),
+            "s3cr3t-token".to_string(),
+            "multi\nline".to_string(),
+            String::new(),
+        ]);
+
+        assert_eq!(
+            mask_secrets("token=s3cr3t-token, password=s3cr3t, user=admin"),
+            "token=***, password=***, user=admin"
+        );
+        assert_eq!(
+            mask_secrets(&format!("{:?}", "key: multi\nline")),
+            r#""key: ***""#
+        );
+        assert_eq!(mask_secrets("nothing"), "nothing");
+    }
+}
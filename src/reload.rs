@@ -0,0 +1,192 @@
+use crate::{
+    config::{diff::ConfigDiff, AppConfig, ConfigError, ConfigFormat, ConfigValidator},
+    context::Context,
+    handler::RequestHandler,
+    log_governor, redaction,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+static RUNNING: LazyLock<Mutex<Option<RunningConfig>>> = LazyLock::new(|| Mutex::new(None));
+
+/// Config which is served now, with handlers of the listeners which can be replaced on the fly
+struct RunningConfig {
+    app_config: Arc<AppConfig>,
+    root_ctx: Arc<Context<'static>>,
+    strict_env: bool,
+    handlers: HashMap<String, watch::Sender<RequestHandler>>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ReloadError {
+    #[error("config isn't loaded yet")]
+    NotRunning,
+    #[error("{0}")]
+    InvalidConfig(#[from] ConfigError),
+    #[error("listeners can't be changed without restart")]
+    RestartRequired(ConfigDiff),
+    #[error("unable to create listener `{0}`: {1}")]
+    Handler(String, anyhow::Error),
+}
+
+/// Registers served config and senders of the listeners' handlers, so new config can be applied
+pub fn register(
+    app_config: Arc<AppConfig>,
+    root_ctx: Arc<Context<'static>>,
+    strict_env: bool,
+    handlers: HashMap<String, watch::Sender<RequestHandler>>,
+) {
+    *lock() = Some(RunningConfig {
+        app_config,
+        root_ctx,
+        strict_env,
+        handlers,
+    });
+}
+
+/// Validates candidate config and compares it with the running one without applying it
+pub fn diff(content: &str, format: ConfigFormat) -> Result<ConfigDiff, ReloadError> {
+    let running = lock();
+    let running = running.as_ref().ok_or(ReloadError::NotRunning)?;
+    let (_, diff) = running.candidate(content, format)?;
+
+    Ok(diff)
+}
+
+/// Returns running config with source of each value
+pub fn dump() -> Result<String, ReloadError> {
+    let running = lock();
+    let running = running.as_ref().ok_or(ReloadError::NotRunning)?;
+
+    Ok(running.app_config.dump())
+}
+
+/// Applies candidate config if it changes existing listeners only, returns applied changes;
+/// changed listeners get new handlers, requests in progress are completed by the previous ones
+pub fn apply(content: &str, format: ConfigFormat) -> Result<ConfigDiff, ReloadError> {
+    let mut running = lock();
+    let running = running.as_mut().ok_or(ReloadError::NotRunning)?;
+    let (candidate, diff) = running.candidate(content, format)?;
+    if diff.is_restart_required() {
+        return Err(ReloadError::RestartRequired(diff));
+    }
+
+    let candidate = Arc::new(candidate);
+    let mut handlers = vec![];
+    for id in diff.changed() {
+        let index = candidate
+            .listeners()
+            .iter()
+            .position(|l| l.id() == id)
+            .expect("changed listener isn't found, looks like a BUG");
+        let handler = RequestHandler::new(candidate.clone(), index, running.root_ctx.clone())
+            .map_err(|e| ReloadError::Handler(id.into(), e))?;
+        handlers.push((id, handler));
+    }
+    // All handlers are created before any of them is replaced, so config is applied entirely or not at all
+    for (id, handler) in handlers {
+        info!(listener = id, "listener config is reloaded");
+        if let Some(sender) = running.handlers.get(id) {
+            sender.send_replace(handler);
+        }
+    }
+    redaction::set_sensitive_headers(candidate.sensitive_headers());
+    log_governor::configure(candidate.logging());
+    running.app_config = candidate;
+
+    Ok(diff)
+}
+
+impl RunningConfig {
+    fn candidate(
+        &self,
+        content: &str,
+        format: ConfigFormat,
+    ) -> Result<(AppConfig, ConfigDiff), ReloadError> {
+        let candidate = AppConfig::from_content(content, format, &self.root_ctx, self.strict_env)?;
+        for warning in candidate.lint() {
+            warn!("candidate config: {warning}");
+        }
+        let diff = self.app_config.diff(&candidate);
+
+        Ok((candidate, diff))
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<RunningConfig>> {
+    RUNNING
+        .lock()
+        .expect("unable to lock running config, looks like a BUG")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::test_context::TestEnvironment;
+
+    const RUNNING_CONFIG: &str = r#"
+        listeners:
+          - id: first
+            listen_on: "*:8001"
+            targets: [{url: "https://example.com/"}]
+          - id: second
+            listen_on: "*:8002"
+            targets: [{url: "https://example.com/"}]
+        "#;
+
+    #[tokio::test]
+    async fn apply_config() {
+        let root_ctx = Arc::new(Context::root(TestEnvironment::test_env()));
+        let app_config = Arc::new(
+            AppConfig::from_content(RUNNING_CONFIG, ConfigFormat::Yaml, &root_ctx, false).unwrap(),
+        );
+        let mut senders = HashMap::new();
+        let mut receivers = vec![];
+        for (index, cfg) in app_config.listeners().iter().enumerate() {
+            let handler = RequestHandler::new(app_config.clone(), index, root_ctx.clone()).unwrap();
+            let (sender, receiver) = watch::channel(handler);
+            senders.insert(cfg.id(), sender);
+            receivers.push(receiver);
+        }
+        register(app_config, root_ctx, false, senders);
+
+        // Added listener requires restart, so nothing is applied
+        let with_added = format!(
+            "{RUNNING_CONFIG}
+          - id: third
+            listen_on: \"*:8003\"
+            targets: [{{url: \"https://example.com/\"}}]"
+        );
+        assert!(diff(&with_added, ConfigFormat::Yaml)
+            .unwrap()
+            .is_restart_required());
+        assert!(matches!(
+            apply(&with_added, ConfigFormat::Yaml),
+            Err(ReloadError::RestartRequired(_))
+        ));
+        assert!(matches!(
+            apply("listeners: [{targets: []}]", ConfigFormat::Yaml),
+            Err(ReloadError::InvalidConfig(_))
+        ));
+        assert!(!receivers.iter().any(|r| r.has_changed().unwrap()));
+
+        // Only changed listener gets new handler
+        let changed = RUNNING_CONFIG.replace(
+            "listen_on: \"*:8002\"",
+            "listen_on: \"*:8002\"\n            timeout: 3s",
+        );
+        let applied = apply(&changed, ConfigFormat::Yaml).unwrap();
+        assert_eq!(applied.changed().collect::<Vec<_>>(), ["second"]);
+        assert!(!receivers[0].has_changed().unwrap());
+        assert!(receivers[1].has_changed().unwrap());
+        assert_eq!(
+            receivers[1].borrow_and_update().listener_cfg().timeout(),
+            std::time::Duration::from_secs(3)
+        );
+        assert!(diff(&changed, ConfigFormat::Yaml).unwrap() == ConfigDiff::default());
+    }
+}
@@ -0,0 +1,702 @@
+use crate::{
+    config::{
+        failure::TargetFailureClassifier,
+        listener::TlsConfig,
+        retry_queue::DeliveryMode,
+        target::{HttpsClient, TargetConfig},
+    },
+    discovery::TargetDiscovery,
+    metrics::{self, MetricsSource},
+    streaming::RequestBody,
+};
+use hyper::{body::Bytes, header::HeaderName, Request};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex, Weak,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{fs, sync::Notify};
+use tracing::{debug, info, info_span, warn, Instrument};
+use uuid::Uuid;
+
+/// Extension of the queued request files, other files in the queue directory are ignored
+const ENTRY_EXTENSION: &str = "req";
+/// Extension of the journaled requests in progress, they're moved to the queue if delivery isn't acknowledged
+const JOURNAL_EXTENSION: &str = "wal";
+
+/// Queues by their directory: queue of the reloaded listener supersedes the previous one of the same directory,
+/// so requests of the directory are retried by the single worker
+static QUEUES: LazyLock<Mutex<HashMap<PathBuf, QueueDir>>> = LazyLock::new(Default::default);
+
+/// State of the queue directory which is shared by the queues of the listener's handlers
+#[derive(Debug)]
+struct QueueDir {
+    /// The newest queue of the directory, only its worker retries requests
+    current: Weak<RetryQueue>,
+    depth: Arc<AtomicUsize>,
+}
+
+impl QueueDir {
+    /// Creates directory if needed and moves journal entries left by the previous run to the queue
+    fn open(path: &Path, target_id: &str) -> Result<Self, io::Error> {
+        std::fs::create_dir_all(path)?;
+        let recovered = recover_journal(path)?;
+        if recovered > 0 {
+            info!(
+                "retry queue of target `{target_id}` has {recovered} unacknowledged request(s) to redeliver"
+            );
+        }
+        let depth = std::fs::read_dir(path)?
+            .filter_map(Result::ok)
+            .filter(|entry| is_entry(&entry.path()))
+            .count();
+        if depth > 0 {
+            info!("retry queue of target `{target_id}` has {depth} request(s) to retry");
+        }
+
+        Ok(Self {
+            current: Weak::new(),
+            depth: Arc::new(AtomicUsize::new(depth)),
+        })
+    }
+}
+
+/// On-disk queue of the target's failed requests, they're retried in background with backoff.
+///
+/// Each request is stored in its own file: JSON line with method, URI and headers, followed by the raw body.
+/// File name starts with the enqueue time, so lexicographic order of the names is the queue order.
+/// In `write_ahead` delivery mode request is journaled in the same directory before it's sent,
+/// and journal entries left after crash are moved to the queue when directory is opened first time by the process.
+#[derive(Debug)]
+pub struct RetryQueue {
+    listener_id: String,
+    target_id: String,
+    dir: PathBuf,
+    max_entries: usize,
+    max_age: Duration,
+    backoff: (Duration, Duration),
+    write_ahead: bool,
+    dedup_header: Option<HeaderName>,
+    client: HttpsClient,
+    timeout: Duration,
+    discovery: Option<Arc<TargetDiscovery>>,
+    /// Responses are classified as the target's ones by the response strategies
+    classifier: TargetFailureClassifier,
+    depth: Arc<AtomicUsize>,
+    /// Wakes up worker when new request is queued or queue is dropped or superseded
+    queued: Arc<Notify>,
+}
+
+/// Journaled request in progress: it's removed if delivery is acknowledged, otherwise it's moved to the queue,
+/// including the case when request is cancelled and entry is dropped without explicit decision
+#[derive(Debug)]
+pub struct JournalEntry {
+    queue: Arc<RetryQueue>,
+    path: Option<PathBuf>,
+}
+
+/// Request to the target which can be stored in the queue
+#[derive(Debug)]
+pub struct QueuedRequest {
+    head: RequestHead,
+    body: Bytes,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RequestHead {
+    /// ID of the originating request, it's absent in entries queued by older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    method: String,
+    uri: String,
+    headers: Vec<(String, String)>,
+}
+
+impl RetryQueue {
+    /// Creates queue directory if needed and starts worker which retries queued requests
+    pub fn new(
+        listener_id: String,
+        target: &TargetConfig,
+        tls: &TlsConfig,
+        discovery: Option<Arc<TargetDiscovery>>,
+        classifier: TargetFailureClassifier,
+    ) -> Result<Arc<Self>, io::Error> {
+        let cfg = target
+            .retry_queue()
+            .expect("retry queue isn't configured, looks like a BUG");
+        let mut queues = QUEUES
+            .lock()
+            .expect("unable to lock retry queues, looks like a BUG");
+        // Journal of the reloaded listener's directory belongs to the requests in progress, it isn't recovered
+        let shared = match queues.entry(cfg.path().into()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(QueueDir::open(cfg.path(), &target.id())?),
+        };
+        let queue = Arc::new(Self {
+            listener_id,
+            target_id: target.id(),
+            dir: cfg.path().into(),
+            max_entries: cfg.max_entries(),
+            max_age: cfg.max_age(),
+            backoff: cfg.backoff(),
+            write_ahead: cfg.delivery() == DeliveryMode::WriteAhead,
+            dedup_header: cfg.dedup_header(),
+            client: target.https_client(tls),
+            timeout: *target.timeout(),
+            discovery,
+            classifier,
+            depth: shared.depth.clone(),
+            queued: Arc::new(Notify::new()),
+        });
+        let previous = std::mem::replace(&mut shared.current, Arc::downgrade(&queue));
+        drop(queues);
+        if let Some(previous) = previous.upgrade() {
+            previous.queued.notify_one();
+        }
+        metrics::register(queue.clone());
+        tokio::spawn(Self::retry_loop(
+            Arc::downgrade(&queue),
+            queue.queued.clone(),
+        ));
+
+        Ok(queue)
+    }
+
+    /// Returns `true` if requests should be journaled before they're sent
+    pub fn write_ahead(&self) -> bool {
+        self.write_ahead
+    }
+
+    /// Returns name of the header with the originating request ID, which is kept in all deliveries of the request
+    pub fn dedup_header(&self) -> Option<&HeaderName> {
+        self.dedup_header.as_ref()
+    }
+
+    /// Stores failed request to retry it later, the oldest requests are dropped if queue is full
+    pub async fn push(&self, req: QueuedRequest) {
+        let name = entry_name(ENTRY_EXTENSION);
+        if let Err(e) = self.write_entry(&name, &req).await {
+            warn!(
+                "unable to queue failed request to target `{}`: {e}",
+                self.target_id
+            );
+            return;
+        }
+        debug!("request to target `{}` is queued: {name}", self.target_id);
+        self.enqueued().await;
+    }
+
+    /// Stores request before it's sent, `None` is returned if request can't be journaled,
+    /// so it's queued only if it's failed
+    pub async fn journal(self: &Arc<Self>, req: &QueuedRequest) -> Option<JournalEntry> {
+        let name = entry_name(JOURNAL_EXTENSION);
+        if let Err(e) = self.write_entry(&name, req).await {
+            warn!(
+                "unable to journal request to target `{}`: {e}",
+                self.target_id
+            );
+            return None;
+        }
+        debug!(
+            "request to target `{}` is journaled: {name}",
+            self.target_id
+        );
+
+        Some(JournalEntry {
+            queue: self.clone(),
+            path: Some(self.dir.join(name)),
+        })
+    }
+
+    /// Accounts entry added to the queue, the oldest requests are dropped if queue is full
+    async fn enqueued(&self) {
+        self.depth.fetch_add(1, Ordering::Relaxed);
+
+        if self.depth.load(Ordering::Relaxed) > self.max_entries {
+            match self.entries().await {
+                Ok(entries) => {
+                    let excess = entries.len().saturating_sub(self.max_entries);
+                    for path in entries.iter().take(excess) {
+                        warn!(
+                            "retry queue of target `{}` is full, request is dropped: {}",
+                            self.target_id,
+                            path.display()
+                        );
+                        self.remove_entry(path).await;
+                    }
+                }
+                Err(e) => warn!(
+                    "unable to read retry queue of target `{}`: {e}",
+                    self.target_id
+                ),
+            }
+        }
+        self.wake_worker();
+    }
+
+    /// Wakes up worker of the directory's current queue, it may belong to the handler of the reloaded listener
+    fn wake_worker(&self) {
+        let current = QUEUES
+            .lock()
+            .expect("unable to lock retry queues, looks like a BUG")
+            .get(&self.dir)
+            .and_then(|shared| shared.current.upgrade());
+        if let Some(current) = current {
+            current.queued.notify_one();
+        }
+    }
+
+    /// Returns `false` if queue is superseded by the queue of the reloaded listener
+    fn is_current(&self) -> bool {
+        QUEUES
+            .lock()
+            .expect("unable to lock retry queues, looks like a BUG")
+            .get(&self.dir)
+            .is_some_and(|shared| std::ptr::eq(shared.current.as_ptr(), self))
+    }
+
+    /// Retries queued requests while target responds, backoff is increased after each failed attempt;
+    /// worker doesn't keep the queue while it waits, and it exits when queue is dropped or superseded
+    async fn retry_loop(queue: Weak<Self>, queued: Arc<Notify>) {
+        let mut backoff = None;
+        while let Some(current) = queue.upgrade().filter(|queue| queue.is_current()) {
+            let (min_backoff, max_backoff) = current.backoff;
+            let wait = match current.retry_all().await {
+                Ok(()) => None,
+                Err(e) => {
+                    let wait = backoff.map_or(min_backoff, |backoff: Duration| {
+                        (backoff * 2).min(max_backoff)
+                    });
+                    debug!(
+                        "retry of target `{}` failed, next attempt in {wait:?}: {e}",
+                        current.target_id
+                    );
+                    Some(wait)
+                }
+            };
+            drop(current);
+
+            backoff = wait;
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => queued.notified().await,
+            }
+        }
+    }
+
+    /// Sends all queued requests in order, stops on the first failed one
+    async fn retry_all(&self) -> Result<(), String> {
+        let entries = self.entries().await.map_err(|e| e.to_string())?;
+        for path in entries {
+            // The rest of the requests is retried by the worker of the queue which has superseded this one
+            if !self.is_current() {
+                break;
+            }
+            if is_expired(&path, self.max_age) {
+                warn!(
+                    "request to target `{}` is expired in retry queue: {}",
+                    self.target_id,
+                    path.display()
+                );
+                self.remove_entry(&path).await;
+                continue;
+            }
+            let req = match read_entry(&path).await {
+                Ok(req) => req,
+                Err(e) => {
+                    warn!("invalid request in retry queue is dropped: {e}");
+                    self.remove_entry(&path).await;
+                    continue;
+                }
+            };
+
+            // Delivery is logged in the span of the originating request to tie it with the request's log entries
+            let req_id = req.head.request_id.clone().unwrap_or_default();
+            let span = info_span!("request", id = %req_id, listener = %self.listener_id);
+            self.send(req).instrument(span.clone()).await?;
+            span.in_scope(|| {
+                info!(
+                    "{req_id}: queued request to target `{}` is delivered: {}",
+                    self.target_id,
+                    path.display()
+                )
+            });
+            self.remove_entry(&path).await;
+        }
+
+        Ok(())
+    }
+
+    /// Sends request to the target, failed response means that target isn't recovered yet
+    async fn send(&self, req: QueuedRequest) -> Result<(), String> {
+        let req = req.into_request()?;
+        let (req, lease) = match &self.discovery {
+            Some(discovery) => {
+                let (req, lease) = discovery.route(req).await.map_err(|e| e.to_string())?;
+                (req, Some(lease))
+            }
+            None => (req, None),
+        };
+        let started = Instant::now();
+        let result = tokio::time::timeout(self.timeout, self.client.request(req)).await;
+        let elapsed = started.elapsed();
+        let failed = |resp| self.classifier.is_failed_head(resp, elapsed);
+        if let (Some(lease), Ok(response)) = (lease, &result) {
+            lease.report(response.as_ref().map_or(true, failed));
+        }
+        match result {
+            Ok(Ok(resp)) if failed(&resp) => Err(format!("response status {}", resp.status())),
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("timeout".into()),
+        }
+    }
+
+    /// Returns paths of the queued requests, the oldest one is the first
+    async fn entries(&self) -> Result<Vec<PathBuf>, io::Error> {
+        let mut entries = vec![];
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            if is_entry(&entry.path()) {
+                entries.push(entry.path());
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    /// Entry is written to temporary file and renamed, so partially written entries are never read
+    async fn write_entry(&self, name: &str, req: &QueuedRequest) -> Result<(), io::Error> {
+        let mut content = serde_json::to_vec(&req.head)?;
+        content.push(b'\n');
+        content.extend_from_slice(&req.body);
+
+        let path = self.dir.join(name);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, content).await?;
+        fs::rename(&tmp, &path).await
+    }
+
+    async fn remove_entry(&self, path: &Path) {
+        match fs::remove_file(path).await {
+            Ok(()) => {
+                self.depth.fetch_sub(1, Ordering::Relaxed);
+            }
+            Err(e) => warn!("unable to remove {}: {e}", path.display()),
+        }
+    }
+}
+
+impl JournalEntry {
+    /// Target has received the request, so entry isn't needed anymore
+    pub async fn acknowledge(mut self) {
+        if let Some(path) = self.path.take() {
+            if let Err(e) = fs::remove_file(&path).await {
+                warn!("unable to remove {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// Request isn't delivered, entry is moved to the queue to retry it
+    pub async fn requeue(mut self) {
+        if let Some(path) = self.path.take() {
+            let queued = path.with_extension(ENTRY_EXTENSION);
+            match fs::rename(&path, &queued).await {
+                Ok(()) => {
+                    debug!(
+                        "request to target `{}` is queued: {}",
+                        self.queue.target_id,
+                        queued.display()
+                    );
+                    self.queue.enqueued().await;
+                }
+                Err(e) => warn!(
+                    "unable to queue failed request to target `{}`: {e}",
+                    self.queue.target_id
+                ),
+            }
+        }
+    }
+}
+
+impl Drop for JournalEntry {
+    /// Request is cancelled and its outcome is unknown, so it's queued to redeliver it
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            match std::fs::rename(&path, path.with_extension(ENTRY_EXTENSION)) {
+                Ok(()) => {
+                    self.queue.depth.fetch_add(1, Ordering::Relaxed);
+                    self.queue.wake_worker();
+                }
+                Err(e) => warn!(
+                    "unable to queue cancelled request to target `{}`: {e}",
+                    self.queue.target_id
+                ),
+            }
+        }
+    }
+}
+
+impl Drop for RetryQueue {
+    /// Worker exits when it's woken up and queue is gone
+    fn drop(&mut self) {
+        self.queued.notify_one();
+    }
+}
+
+impl MetricsSource for RetryQueue {
+    /// Superseded queue isn't reported, depth of the directory is reported by the current one
+    fn write_metrics(&self, out: &mut String) {
+        if !self.is_current() {
+            return;
+        }
+        metrics::gauge(
+            out,
+            "retry_queue_depth",
+            [
+                ("listener", self.listener_id.as_str()),
+                ("target", self.target_id.as_str()),
+            ],
+            self.depth.load(Ordering::Relaxed) as f64,
+        );
+    }
+}
+
+impl QueuedRequest {
+    /// Copies request to the target, body of the request is always buffered
+    pub fn from_request(req_id: Uuid, req: &Request<RequestBody>) -> Self {
+        let headers = req
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        // Trailers aren't queued, retried request is sent without them
+        let body = req.body().data();
+
+        Self {
+            head: RequestHead {
+                request_id: Some(req_id.to_string()),
+                method: req.method().to_string(),
+                uri: req.uri().to_string(),
+                headers,
+            },
+            body,
+        }
+    }
+
+    fn into_request(self) -> Result<Request<RequestBody>, String> {
+        let mut req = Request::builder()
+            .method(self.head.method.as_str())
+            .uri(self.head.uri.as_str());
+        for (name, value) in &self.head.headers {
+            req = req.header(name, value);
+        }
+        req.body(RequestBody::from(self.body))
+            .map_err(|e| e.to_string())
+    }
+}
+
+async fn read_entry(path: &Path) -> Result<QueuedRequest, String> {
+    let content = fs::read(path)
+        .await
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let split = content
+        .iter()
+        .position(|b| *b == b'\n')
+        .ok_or_else(|| format!("{}: no request head", path.display()))?;
+    let head = serde_json::from_slice(&content[..split])
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let body = Bytes::from(content).slice(split + 1..);
+
+    Ok(QueuedRequest { head, body })
+}
+
+/// Moves journal entries left by the previous run to the queue, their delivery status is unknown
+fn recover_journal(dir: &Path) -> Result<usize, io::Error> {
+    let mut recovered = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == JOURNAL_EXTENSION) {
+            std::fs::rename(&path, path.with_extension(ENTRY_EXTENSION))?;
+            recovered += 1;
+        }
+    }
+
+    Ok(recovered)
+}
+
+/// File name starts with the current time, so entries are ordered by the time of storing
+fn entry_name(extension: &str) -> String {
+    format!(
+        "{:013}-{}.{extension}",
+        unix_millis(SystemTime::now()),
+        Uuid::new_v4()
+    )
+}
+
+fn is_entry(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == ENTRY_EXTENSION)
+}
+
+/// Checks enqueue time of the entry which is the prefix of its name
+fn is_expired(path: &Path, max_age: Duration) -> bool {
+    let queued_at = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.split('-').next())
+        .and_then(|millis| millis.parse::<u64>().ok())
+        .unwrap_or_default();
+    unix_millis(SystemTime::now()).saturating_sub(queued_at) > max_age.as_millis() as u64
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{AppConfig, ConfigFormat},
+        context::test_context::get_test_ctx,
+    };
+
+    /// Creates queue of the single listener's target, like handler of the listener does
+    fn queue(dir: &Path) -> Arc<RetryQueue> {
+        let config = format!(
+            r#"
+            listeners:
+              - id: retry-queue
+                listen_on: "*:8001"
+                targets:
+                  - url: http://127.0.0.1:1/
+                    retry_queue:
+                      path: {}
+            "#,
+            dir.display()
+        );
+        let app_config = Arc::new(
+            AppConfig::from_content(&config, ConfigFormat::Yaml, get_test_ctx(), false).unwrap(),
+        );
+        let cfg = &app_config.listeners()[0];
+        let target = &cfg.targets()[0];
+        let classifier = TargetFailureClassifier::new(app_config.clone(), 0, target.id());
+
+        RetryQueue::new(cfg.id(), target, cfg.tls(), None, classifier).unwrap()
+    }
+
+    #[tokio::test]
+    async fn queued_request_round_trip() {
+        let req = Request::builder()
+            .method("POST")
+            .uri("http://localhost:1/path?q=1")
+            .header("x-header", "value")
+            .body(RequestBody::from("line 1\nline 2"))
+            .unwrap();
+        let req_id = Uuid::new_v4();
+        let queued = QueuedRequest::from_request(req_id, &req);
+
+        let dir = std::env::temp_dir().join(format!("retry-queue-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("0000000000001-test.{ENTRY_EXTENSION}"));
+        let mut content = serde_json::to_vec(&queued.head).unwrap();
+        content.push(b'\n');
+        content.extend_from_slice(&queued.body);
+        std::fs::write(&path, content).unwrap();
+
+        let queued = read_entry(&path).await.unwrap();
+        assert_eq!(queued.head.request_id, Some(req_id.to_string()));
+        let req = queued.into_request().unwrap();
+        assert_eq!(req.method(), "POST");
+        assert_eq!(req.uri(), "http://localhost:1/path?q=1");
+        assert_eq!(req.headers()["x-header"], "value");
+        assert_eq!(req.body().data(), "line 1\nline 2");
+        assert!(is_entry(&path));
+        assert!(is_expired(&path, Duration::from_secs(3600)));
+        // Entries of older versions have no request ID
+        let head: RequestHead =
+            serde_json::from_str(r#"{"method": "GET", "uri": "/", "headers": []}"#).unwrap();
+        assert_eq!(head.request_id, None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn journal_is_recovered() {
+        let dir = std::env::temp_dir().join(format!("retry-queue-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journaled = dir.join(entry_name(JOURNAL_EXTENSION));
+        std::fs::write(&journaled, "{}\n").unwrap();
+        std::fs::write(dir.join(entry_name(ENTRY_EXTENSION)), "{}\n").unwrap();
+        std::fs::write(dir.join("other.tmp"), "").unwrap();
+
+        assert_eq!(recover_journal(&dir).unwrap(), 1);
+        assert!(!journaled.exists());
+        assert!(is_entry(&journaled.with_extension(ENTRY_EXTENSION)));
+        assert!(journaled.with_extension(ENTRY_EXTENSION).exists());
+        assert_eq!(recover_journal(&dir).unwrap(), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn queue_is_superseded() {
+        let dir = std::env::temp_dir().join(format!("retry-queue-{}", Uuid::new_v4()));
+        let previous = queue(&dir);
+        assert!(previous.is_current());
+
+        // Listener is reloaded, the previous handler is still alive
+        let current = queue(&dir);
+        assert!(!previous.is_current());
+        assert!(current.is_current());
+        // Worker has exited, only metrics registry refers to the queue
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while Arc::weak_count(&previous) > 1 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("worker of the superseded queue isn't stopped");
+
+        let series = metrics::render()
+            .lines()
+            .filter(|line| line.contains("retry_queue_depth{listener=\"retry-queue\""))
+            .count();
+        assert_eq!(series, 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn journal_in_progress_is_kept_on_reload() {
+        let dir = std::env::temp_dir().join(format!("retry-queue-{}", Uuid::new_v4()));
+        let previous = queue(&dir);
+        let req = Request::builder()
+            .uri("http://127.0.0.1:1/")
+            .body(RequestBody::from("body"))
+            .unwrap();
+        let entry = previous
+            .journal(&QueuedRequest::from_request(Uuid::new_v4(), &req))
+            .await
+            .unwrap();
+        let journaled = entry.path.clone().unwrap();
+
+        // Listener is reloaded while request is in progress on the previous handler
+        let _current = queue(&dir);
+        assert!(journaled.exists());
+        entry.acknowledge().await;
+
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        assert_eq!(previous.depth.load(Ordering::Relaxed), 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    config::sampling::SamplingConfig,
+    metrics::{self, MetricsSource},
+};
+use std::{sync::Mutex, time::Duration};
+use tracing::{info, warn};
+
+/// Number of the recent requests of the target which its health is assessed by
+const ADAPTIVE_SAMPLING_WINDOW: usize = 20;
+/// Share of the configured rate which is added after each healthy window
+const ADAPTIVE_SAMPLING_RAMP_UP: f64 = 0.1;
+
+/// Decides which requests are sent to the target and adapts sampling rate to the target's health,
+/// all workers of the listener share its state
+#[derive(Debug)]
+pub struct TargetSampler {
+    listener_id: String,
+    target_id: String,
+    rate: f64,
+    min_rate: f64,
+    max_latency: Option<Duration>,
+    max_error_rate: Option<f64>,
+    state: Mutex<SamplerState>,
+}
+
+#[derive(Debug)]
+struct SamplerState {
+    rate: f64,
+    total: usize,
+    failed: usize,
+    latency: Duration,
+}
+
+impl TargetSampler {
+    pub fn new(cfg: &SamplingConfig, listener_id: String, target_id: String) -> Self {
+        let adaptive = cfg.adaptive();
+        Self {
+            listener_id,
+            target_id,
+            rate: cfg.rate(),
+            min_rate: adaptive.map_or(cfg.rate(), |a| a.min_rate()),
+            max_latency: adaptive.and_then(|a| a.max_latency()),
+            max_error_rate: adaptive.and_then(|a| a.max_error_rate()),
+            state: Mutex::new(SamplerState {
+                rate: cfg.rate(),
+                total: 0,
+                failed: 0,
+                latency: Duration::ZERO,
+            }),
+        }
+    }
+
+    /// Returns `true` if request should be sent to the target
+    pub fn sample(&self) -> bool {
+        fastrand::f64() < self.lock().rate
+    }
+
+    /// Records outcome of the target's request, sampling rate is adapted after each window of requests
+    pub fn record(&self, failed: bool, duration: Duration) {
+        if self.max_latency.is_none() && self.max_error_rate.is_none() {
+            return;
+        }
+        let mut state = self.lock();
+        state.total += 1;
+        state.failed += usize::from(failed);
+        state.latency += duration;
+        if state.total < ADAPTIVE_SAMPLING_WINDOW {
+            return;
+        }
+
+        let error_rate = state.failed as f64 / state.total as f64;
+        let latency = state.latency / state.total as u32;
+        let unhealthy = self.max_error_rate.is_some_and(|max| error_rate > max)
+            || self.max_latency.is_some_and(|max| latency > max);
+        let rate = if unhealthy {
+            (state.rate / 2.0).max(self.min_rate)
+        } else {
+            (state.rate + self.rate * ADAPTIVE_SAMPLING_RAMP_UP).min(self.rate)
+        };
+        if rate < state.rate {
+            warn!(
+                listener = self.listener_id,
+                target = self.target_id,
+                error_rate,
+                latency_ms = latency.as_millis() as u64,
+                "target is unhealthy, sampling rate is reduced to {rate}"
+            );
+        } else if rate > state.rate {
+            info!(
+                listener = self.listener_id,
+                target = self.target_id,
+                "target is healthy, sampling rate is increased to {rate}"
+            );
+        }
+        *state = SamplerState {
+            rate,
+            total: 0,
+            failed: 0,
+            latency: Duration::ZERO,
+        };
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SamplerState> {
+        self.state
+            .lock()
+            .expect("unable to lock sampler state, looks like a BUG")
+    }
+}
+
+impl MetricsSource for TargetSampler {
+    fn write_metrics(&self, out: &mut String) {
+        metrics::gauge(
+            out,
+            "target_sample_rate",
+            [
+                ("listener", self.listener_id.as_str()),
+                ("target", self.target_id.as_str()),
+            ],
+            self.lock().rate,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sampler(cfg: &str) -> TargetSampler {
+        let cfg: SamplingConfig = serde_json::from_str(cfg).unwrap();
+        TargetSampler::new(&cfg, "listener".into(), "target".into())
+    }
+
+    fn record_window(sampler: &TargetSampler, failed: usize, duration: Duration) {
+        for pos in 0..ADAPTIVE_SAMPLING_WINDOW {
+            sampler.record(pos < failed, duration);
+        }
+    }
+
+    #[test]
+    fn static_rate() {
+        let sampler = sampler(r#"{"rate": 0.25}"#);
+        let sampled = (0..4000).filter(|_| sampler.sample()).count();
+        assert!((800..1200).contains(&sampled), "{sampled}");
+
+        record_window(&sampler, ADAPTIVE_SAMPLING_WINDOW, Duration::from_secs(1));
+        assert_eq!(sampler.lock().rate, 0.25);
+    }
+
+    #[test]
+    fn adaptive_rate() {
+        let sampler = sampler(
+            r#"{"rate": 0.8, "adaptive": {"max_latency": "100ms", "max_error_rate": 0.1, "min_rate": 0.3}}"#,
+        );
+        let fast = Duration::from_millis(10);
+
+        // Too many errors
+        record_window(&sampler, 5, fast);
+        assert_eq!(sampler.lock().rate, 0.4);
+        // Too slow, limited by min rate
+        record_window(&sampler, 0, Duration::from_millis(200));
+        assert_eq!(sampler.lock().rate, 0.3);
+        // Healthy, ramps up to configured rate
+        record_window(&sampler, 1, fast);
+        assert!((sampler.lock().rate - 0.38).abs() < 1e-9);
+        for _ in 0..10 {
+            record_window(&sampler, 0, fast);
+        }
+        assert_eq!(sampler.lock().rate, 0.8);
+    }
+}
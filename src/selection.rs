@@ -0,0 +1,204 @@
+use crate::{
+    canary::CanaryController,
+    config::{response::TargetSelection, target::TargetConfig},
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Number of the recent requests of each target to calculate its failures rate
+const LEAST_ERRORS_WINDOW: usize = 100;
+
+/// Selects target for `*_target_id` strategies on each request, all workers of the listener share its state
+#[derive(Debug)]
+pub struct TargetSelector {
+    policy: TargetSelection,
+    /// Position of the next target in turn, it's used to break ties of `least_errors` as well
+    next: AtomicUsize,
+    /// Recent outcomes of the targets by target ID, `true` means failed request
+    outcomes: Mutex<HashMap<String, VecDeque<bool>>>,
+    /// Rollout of the canary target, it's used by `weighted_random` only
+    canary: Option<Arc<CanaryController>>,
+}
+
+impl TargetSelector {
+    pub fn new(policy: TargetSelection, canary: Option<Arc<CanaryController>>) -> Self {
+        Self {
+            policy,
+            next: AtomicUsize::new(0),
+            outcomes: Mutex::new(HashMap::new()),
+            canary,
+        }
+    }
+
+    /// Returns ID of the target which response should be selected, `None` if there are no targets
+    pub fn select(&self, targets: &[&TargetConfig]) -> Option<String> {
+        if targets.is_empty() {
+            return None;
+        }
+        let pos = match self.policy {
+            TargetSelection::WeightedRandom if self.canary.is_some() => {
+                return self.select_with_canary(targets)
+            }
+            TargetSelection::WeightedRandom => {
+                let total: u64 = targets.iter().map(|t| u64::from(t.weight())).sum();
+                let mut point = fastrand::u64(0..total);
+                targets
+                    .iter()
+                    .position(|t| match point.checked_sub(u64::from(t.weight())) {
+                        Some(rest) => {
+                            point = rest;
+                            false
+                        }
+                        None => true,
+                    })
+                    .expect("weighted random point is out of targets, looks like a BUG")
+            }
+            TargetSelection::RoundRobin => {
+                self.next.fetch_add(1, Ordering::Relaxed) % targets.len()
+            }
+            TargetSelection::LeastErrors => {
+                let rates: Vec<f64> = {
+                    let outcomes = self.lock();
+                    targets
+                        .iter()
+                        .map(|t| outcomes.get(&t.id()).map_or(0.0, failures_rate))
+                        .collect()
+                };
+                let least = rates.iter().copied().fold(f64::INFINITY, f64::min);
+                let candidates: Vec<usize> = (0..targets.len())
+                    .filter(|pos| rates[*pos] == least)
+                    .collect();
+                candidates[self.next.fetch_add(1, Ordering::Relaxed) % candidates.len()]
+            }
+        };
+
+        Some(targets[pos].id())
+    }
+
+    /// Selects canary target with its current percent, other targets share the rest by their weights
+    fn select_with_canary(&self, targets: &[&TargetConfig]) -> Option<String> {
+        let canary = self.canary.as_ref()?;
+        let (canary_targets, others): (Vec<&TargetConfig>, Vec<&TargetConfig>) =
+            targets.iter().partition(|t| t.id() == canary.target_id());
+        if others.is_empty()
+            || (!canary_targets.is_empty() && fastrand::f64() * 100.0 < canary.percent())
+        {
+            return canary_targets.first().map(|t| t.id());
+        }
+        let selector = Self::new(TargetSelection::WeightedRandom, None);
+        selector.select(&others)
+    }
+
+    /// Records outcome of the target's request, it's used by `least_errors` and canary rollout only
+    pub fn record(&self, target_id: &str, failed: bool) {
+        if let Some(canary) = &self.canary {
+            canary.record(target_id, failed);
+        }
+        if self.policy != TargetSelection::LeastErrors {
+            return;
+        }
+        let mut outcomes = self.lock();
+        let recent = outcomes.entry(target_id.into()).or_default();
+        if recent.len() == LEAST_ERRORS_WINDOW {
+            recent.pop_front();
+        }
+        recent.push_back(failed);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, VecDeque<bool>>> {
+        self.outcomes
+            .lock()
+            .expect("unable to lock target selector state, looks like a BUG")
+    }
+}
+
+fn failures_rate(recent: &VecDeque<bool>) -> f64 {
+    if recent.is_empty() {
+        0.0
+    } else {
+        recent.iter().filter(|failed| **failed).count() as f64 / recent.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canary::CanaryAction;
+
+    fn targets(weights: &[u32]) -> Vec<TargetConfig> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(pos, weight)| {
+                serde_json::from_str(&format!(
+                    r#"{{"id": "T{pos}", "url": "http://localhost/", "weight": {weight}}}"#
+                ))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_robin() {
+        let targets = targets(&[1, 1, 1]);
+        let targets: Vec<&TargetConfig> = targets.iter().collect();
+        let selector = TargetSelector::new(TargetSelection::RoundRobin, None);
+
+        let selected: Vec<String> = (0..4).filter_map(|_| selector.select(&targets)).collect();
+        assert_eq!(selected, ["T0", "T1", "T2", "T0"]);
+        assert_eq!(selector.select(&[]), None);
+    }
+
+    #[test]
+    fn weighted_random() {
+        let targets = targets(&[1, 3]);
+        let targets: Vec<&TargetConfig> = targets.iter().collect();
+        let selector = TargetSelector::new(TargetSelection::WeightedRandom, None);
+
+        let heavy = (0..4000)
+            .filter(|_| selector.select(&targets).as_deref() == Some("T1"))
+            .count();
+        assert!((2700..3300).contains(&heavy), "{heavy}");
+    }
+
+    #[test]
+    fn canary() {
+        let targets = targets(&[1, 1]);
+        let targets: Vec<&TargetConfig> = targets.iter().collect();
+        let cfg = serde_json::from_str(r#"{"steps": [100], "bake_time": "1h"}"#).unwrap();
+        let canary = CanaryController::new(&cfg, "selection".into(), "T1".into());
+        let selector = TargetSelector::new(TargetSelection::WeightedRandom, Some(canary.clone()));
+
+        assert!((0..100).all(|_| selector.select(&targets).as_deref() == Some("T1")));
+        // Canary isn't selected after rollback, unless it's the only allowed target
+        canary.apply(CanaryAction::Rollback);
+        assert!((0..100).all(|_| selector.select(&targets).as_deref() == Some("T0")));
+        assert_eq!(selector.select(&targets[1..]).as_deref(), Some("T1"));
+    }
+
+    #[test]
+    fn least_errors() {
+        let targets = targets(&[1, 1, 1]);
+        let targets: Vec<&TargetConfig> = targets.iter().collect();
+        let selector = TargetSelector::new(TargetSelection::LeastErrors, None);
+
+        selector.record("T0", true);
+        selector.record("T0", false);
+        selector.record("T1", false);
+        selector.record("T2", true);
+        assert_eq!(selector.select(&targets).as_deref(), Some("T1"));
+
+        // Failures are forgotten out of the window
+        for _ in 0..LEAST_ERRORS_WINDOW {
+            selector.record("T0", false);
+        }
+        let selected: Vec<String> = (0..2).filter_map(|_| selector.select(&targets)).collect();
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&"T0".into()) && selected.contains(&"T1".into()));
+    }
+}
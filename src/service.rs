@@ -0,0 +1,98 @@
+use crate::{cli::CliConfig, context::RootOsEnvironment, signal};
+use std::{
+    ffi::OsString,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+use tracing::error;
+use windows_service::{
+    define_windows_service,
+    service::{
+        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
+        ServiceType,
+    },
+    service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle},
+    service_dispatcher,
+};
+
+/// Name of the service, it's ignored by the service control manager for own-process services
+const SERVICE_NAME: &str = "http-dragonfly";
+
+/// CLI config is passed to the service main function which is called by the service control manager
+static CLI_CONFIG: OnceLock<Mutex<Option<CliConfig>>> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Connects to the service control manager and serves listeners until the service is stopped
+pub fn run(cli_config: CliConfig) -> Result<(), anyhow::Error> {
+    CLI_CONFIG
+        .set(Mutex::new(Some(cli_config)))
+        .map_err(|_| anyhow::anyhow!("service is started already"))?;
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!(error = %e, "service has been failed");
+    }
+}
+
+fn run_service() -> Result<(), anyhow::Error> {
+    let cli_config = CLI_CONFIG
+        .get()
+        .and_then(|config| config.lock().ok()?.take())
+        .ok_or_else(|| anyhow::anyhow!("service is started twice, looks like a BUG"))?;
+
+    // Stop is handled as a shutdown signal, so requests in progress are completed
+    let status_handle = service_control_handler::register(SERVICE_NAME, |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            signal::request_service_stop();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+    set_status(
+        &status_handle,
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+        0,
+    )?;
+
+    let env_mask = cli_config.env_mask().to_string();
+    let env_provider = RootOsEnvironment::new(&env_mask);
+    let result = cli_config
+        .runtime_config()
+        .runtime("http-dragonfly")
+        .map_err(anyhow::Error::from)
+        .and_then(|runtime| runtime.block_on(crate::run(cli_config, env_provider)));
+
+    let exit_code = if result.is_ok() { 0 } else { 1 };
+    set_status(
+        &status_handle,
+        ServiceState::Stopped,
+        ServiceControlAccept::empty(),
+        exit_code,
+    )?;
+
+    result
+}
+
+fn set_status(
+    handle: &ServiceStatusHandle,
+    state: ServiceState,
+    controls_accepted: ServiceControlAccept,
+    exit_code: u32,
+) -> Result<(), windows_service::Error> {
+    handle.set_service_status(ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted,
+        exit_code: ServiceExitCode::Win32(exit_code),
+        checkpoint: 0,
+        wait_hint: Duration::default(),
+        process_id: None,
+    })
+}
@@ -0,0 +1,61 @@
+use crate::config::target::Bandwidth;
+use hyper::body::{Body, Bytes, Frame, Incoming, SizeHint};
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::time::Sleep;
+
+/// Target's response body which is received not faster than bandwidth limit allows:
+/// each chunk is followed by pause which is required to transfer it with the limited rate
+#[derive(Debug)]
+pub struct ThrottledBody {
+    inner: Incoming,
+    bandwidth: Option<Bandwidth>,
+    pause: Option<Pin<Box<Sleep>>>,
+}
+
+impl ThrottledBody {
+    pub fn new(inner: Incoming, bandwidth: Option<Bandwidth>) -> Self {
+        Self {
+            inner,
+            bandwidth,
+            pause: None,
+        }
+    }
+}
+
+impl Body for ThrottledBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        if let Some(pause) = this.pause.as_mut() {
+            ready!(pause.as_mut().poll(cx));
+            this.pause = None;
+        }
+
+        let frame = ready!(Pin::new(&mut this.inner).poll_frame(cx));
+        if let (Some(bandwidth), Some(Ok(frame))) = (this.bandwidth, &frame) {
+            if let Some(data) = frame.data_ref() {
+                let pause = bandwidth.transfer_time(data.len() as u64);
+                this.pause = Some(Box::pin(tokio::time::sleep(pause)));
+            }
+        }
+
+        Poll::Ready(frame)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pause.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
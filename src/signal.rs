@@ -1,18 +1,48 @@
+#[cfg(windows)]
+use std::sync::LazyLock;
+use tokio::select;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+#[cfg(windows)]
 use tokio::{
-    select,
-    signal::unix::{signal, Signal, SignalKind},
+    signal::windows::{
+        ctrl_break, ctrl_c, ctrl_close, ctrl_shutdown, CtrlBreak, CtrlC, CtrlClose, CtrlShutdown,
+    },
+    sync::watch,
 };
 use tracing::info;
 
+/// Stop request of the Windows service, it's sent by the service control handler
+#[cfg(windows)]
+static SERVICE_STOP: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+
+/// Waits for the shutdown request:
+/// TERM, INT, QUIT or HUP signals on Unix-like platforms,
+/// Ctrl+C, Ctrl+Break, console close, system shutdown or service stop on Windows
 pub struct SignalHandler {
+    #[cfg(unix)]
     terminate: Signal,
+    #[cfg(unix)]
     interrupt: Signal,
+    #[cfg(unix)]
     quit: Signal,
+    #[cfg(unix)]
     hangup: Signal,
+    #[cfg(windows)]
+    ctrl_c: CtrlC,
+    #[cfg(windows)]
+    ctrl_break: CtrlBreak,
+    #[cfg(windows)]
+    ctrl_close: CtrlClose,
+    #[cfg(windows)]
+    ctrl_shutdown: CtrlShutdown,
+    #[cfg(windows)]
+    service_stop: watch::Receiver<bool>,
     handler_id: String,
 }
 
 impl SignalHandler {
+    #[cfg(unix)]
     pub fn new(handler_id: impl Into<String>) -> Self {
         Self {
             handler_id: handler_id.into(),
@@ -27,6 +57,19 @@ impl SignalHandler {
         }
     }
 
+    #[cfg(windows)]
+    pub fn new(handler_id: impl Into<String>) -> Self {
+        Self {
+            handler_id: handler_id.into(),
+            ctrl_c: ctrl_c().expect("unable to install Ctrl+C handler"),
+            ctrl_break: ctrl_break().expect("unable to install Ctrl+Break handler"),
+            ctrl_close: ctrl_close().expect("unable to install console close handler"),
+            ctrl_shutdown: ctrl_shutdown().expect("unable to install system shutdown handler"),
+            service_stop: SERVICE_STOP.subscribe(),
+        }
+    }
+
+    #[cfg(unix)]
     pub async fn wait(&mut self) {
         let sig = select! {
             _ = self.terminate.recv() => "TERM",
@@ -39,4 +82,25 @@ impl SignalHandler {
             "signal has been received, shutting down",
         );
     }
+
+    #[cfg(windows)]
+    pub async fn wait(&mut self) {
+        let sig = select! {
+            _ = self.ctrl_c.recv() => "CTRL_C",
+            _ = self.ctrl_break.recv() => "CTRL_BREAK",
+            _ = self.ctrl_close.recv() => "CTRL_CLOSE",
+            _ = self.ctrl_shutdown.recv() => "CTRL_SHUTDOWN",
+            _ = self.service_stop.wait_for(|stop| *stop) => "SERVICE_STOP",
+        };
+
+        info!(handler_id = %self.handler_id, signal = %sig,
+            "signal has been received, shutting down",
+        );
+    }
+}
+
+/// Requests shutdown of all listeners as if the signal has been received
+#[cfg(windows)]
+pub(crate) fn request_service_stop() {
+    SERVICE_STOP.send_replace(true);
 }
@@ -0,0 +1,325 @@
+use crate::{
+    config::{labels::Labels, slo::SloConfig},
+    metrics::{self, MetricsSource},
+};
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Number of buckets the rolling window is divided into
+const SLO_WINDOW_BUCKETS: u32 = 60;
+/// Burn rate isn't reported until the window has at least this number of requests
+const SLO_MIN_REQUESTS: u64 = 10;
+
+/// Tracks outcomes of the target's requests over the rolling window to evaluate its SLOs
+#[derive(Debug)]
+pub struct SloTracker {
+    listener_id: String,
+    target_id: String,
+    /// Static labels of the listener and target, target's labels take precedence
+    labels: Vec<(String, String)>,
+    availability: Option<f64>,
+    latency: Option<(Duration, f64)>,
+    warn_burn_rate: f64,
+    bucket_width: Duration,
+    started: Instant,
+    state: Mutex<SloState>,
+}
+
+#[derive(Debug, Default)]
+struct SloState {
+    buckets: VecDeque<SloBucket>,
+    last_warning: Option<Instant>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SloBucket {
+    index: u64,
+    total: u64,
+    failed: u64,
+    timed: u64,
+    slow: u64,
+}
+
+/// Outcome of the single request to the target
+#[derive(Debug, Clone, Copy)]
+pub enum SloOutcome {
+    /// Response has been received, it's failed if status is `5xx`
+    Response { failed: bool, duration: Duration },
+    /// Request has been timed out, it's failed and slow
+    Timeout,
+    /// Connection or protocol error, or target isn't available
+    Error,
+}
+
+/// Current state of the single SLI
+#[derive(Debug, PartialEq)]
+struct SliState {
+    success_ratio: f64,
+    burn_rate: f64,
+}
+
+impl SloTracker {
+    pub fn new(
+        cfg: &SloConfig,
+        listener_id: String,
+        listener_labels: &Labels,
+        target_id: String,
+        target_labels: &Labels,
+    ) -> Self {
+        let mut labels: Vec<(String, String)> = vec![];
+        for (name, value) in listener_labels.iter().chain(target_labels.iter()) {
+            labels.retain(|(n, _)| n != name);
+            labels.push((name.clone(), value.clone()));
+        }
+
+        Self {
+            listener_id,
+            target_id,
+            labels,
+            availability: cfg.availability(),
+            latency: cfg.latency(),
+            warn_burn_rate: cfg.warn_burn_rate(),
+            bucket_width: cfg.window() / SLO_WINDOW_BUCKETS,
+            started: Instant::now(),
+            state: Mutex::new(SloState::default()),
+        }
+    }
+
+    /// Records outcome of the request and warns if error budget is burning too fast
+    pub fn record(&self, outcome: SloOutcome) {
+        let now = Instant::now();
+        let mut state = self
+            .state
+            .lock()
+            .expect("unable to lock SLO state, looks like a BUG");
+
+        let bucket = state.bucket(self.bucket_index(now));
+        bucket.total += 1;
+        match outcome {
+            SloOutcome::Response { failed, duration } => {
+                bucket.failed += u64::from(failed);
+                bucket.timed += 1;
+                if let Some((threshold, _)) = self.latency {
+                    bucket.slow += u64::from(duration > threshold);
+                }
+            }
+            SloOutcome::Timeout => {
+                bucket.failed += 1;
+                bucket.timed += 1;
+                bucket.slow += 1;
+            }
+            SloOutcome::Error => bucket.failed += 1,
+        }
+
+        let warning_due = state
+            .last_warning
+            .is_none_or(|last| now.duration_since(last) >= self.bucket_width);
+        if !warning_due {
+            return;
+        }
+        let totals = state.totals(self.oldest_index(now));
+        for (sli, _, sli_state) in self.sli_states(&totals) {
+            if totals.total >= SLO_MIN_REQUESTS && sli_state.burn_rate >= self.warn_burn_rate {
+                warn!(
+                    listener = self.listener_id,
+                    target = self.target_id,
+                    sli,
+                    success_ratio = sli_state.success_ratio,
+                    burn_rate = sli_state.burn_rate,
+                    "error budget is burning fast"
+                );
+                state.last_warning = Some(now);
+            }
+        }
+    }
+
+    fn bucket_index(&self, now: Instant) -> u64 {
+        (now.duration_since(self.started).as_nanos() / self.bucket_width.as_nanos()) as u64
+    }
+
+    /// Returns index of the oldest bucket in the window
+    fn oldest_index(&self, now: Instant) -> u64 {
+        (self.bucket_index(now) + 1).saturating_sub(SLO_WINDOW_BUCKETS as u64)
+    }
+
+    /// Returns name, objective and current state of each configured SLI
+    fn sli_states(&self, totals: &SloBucket) -> Vec<(&'static str, f64, SliState)> {
+        let mut states = vec![];
+        if let Some(objective) = self.availability {
+            states.push((
+                "availability",
+                objective,
+                SliState::new(totals.total, totals.failed, objective),
+            ));
+        }
+        if let Some((_, objective)) = self.latency {
+            states.push((
+                "latency",
+                objective,
+                SliState::new(totals.timed, totals.slow, objective),
+            ));
+        }
+
+        states
+    }
+}
+
+impl SloState {
+    /// Returns bucket with the index, creates it if needed
+    fn bucket(&mut self, index: u64) -> &mut SloBucket {
+        if self.buckets.back().is_none_or(|b| b.index != index) {
+            self.buckets.push_back(SloBucket {
+                index,
+                ..Default::default()
+            });
+            while self.buckets.len() > SLO_WINDOW_BUCKETS as usize {
+                self.buckets.pop_front();
+            }
+        }
+        self.buckets
+            .back_mut()
+            .expect("no SLO bucket, looks like a BUG")
+    }
+
+    /// Sums all buckets within the window
+    fn totals(&self, oldest_index: u64) -> SloBucket {
+        self.buckets
+            .iter()
+            .filter(|b| b.index >= oldest_index)
+            .fold(SloBucket::default(), |acc, b| SloBucket {
+                index: acc.index,
+                total: acc.total + b.total,
+                failed: acc.failed + b.failed,
+                timed: acc.timed + b.timed,
+                slow: acc.slow + b.slow,
+            })
+    }
+}
+
+impl SliState {
+    /// Burn rate is a ratio of the actual errors rate to the allowed one,
+    /// `1.0` means that error budget is consumed exactly during the SLO period
+    fn new(total: u64, bad: u64, objective: f64) -> Self {
+        if total == 0 {
+            return Self {
+                success_ratio: 1.0,
+                burn_rate: 0.0,
+            };
+        }
+        let success_ratio = (total - bad) as f64 / total as f64;
+        Self {
+            success_ratio,
+            burn_rate: (1.0 - success_ratio) / (1.0 - objective),
+        }
+    }
+}
+
+impl MetricsSource for SloTracker {
+    fn write_metrics(&self, out: &mut String) {
+        let totals = self
+            .state
+            .lock()
+            .expect("unable to lock SLO state, looks like a BUG")
+            .totals(self.oldest_index(Instant::now()));
+        for (sli, objective, state) in self.sli_states(&totals) {
+            let labels = || {
+                [
+                    ("listener", self.listener_id.as_str()),
+                    ("target", self.target_id.as_str()),
+                    ("sli", sli),
+                ]
+                .into_iter()
+                .chain(self.labels.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+            };
+            metrics::gauge(out, "slo_objective", labels(), objective);
+            metrics::gauge(out, "slo_success_ratio", labels(), state.success_ratio);
+            metrics::gauge(out, "slo_burn_rate", labels(), state.burn_rate);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(cfg: &str) -> SloTracker {
+        let cfg: SloConfig = serde_json::from_str(cfg).unwrap();
+        let labels: Labels = serde_json::from_str(r#"{"team": "payments"}"#).unwrap();
+        SloTracker::new(
+            &cfg,
+            "listener".into(),
+            &labels,
+            "target".into(),
+            &Labels::default(),
+        )
+    }
+
+    #[test]
+    fn success_ratio_and_burn_rate() {
+        let tracker = tracker(
+            r#"{"availability": 0.9, "latency": {"threshold": "100ms", "objective": 0.5}}"#,
+        );
+        for _ in 0..6 {
+            tracker.record(SloOutcome::Response {
+                failed: false,
+                duration: Duration::from_millis(10),
+            });
+        }
+        tracker.record(SloOutcome::Response {
+            failed: true,
+            duration: Duration::from_millis(200),
+        });
+        tracker.record(SloOutcome::Timeout);
+
+        let totals = tracker
+            .state
+            .lock()
+            .unwrap()
+            .totals(tracker.oldest_index(Instant::now()));
+        let states = tracker.sli_states(&totals);
+        assert_eq!(states[0].0, "availability");
+        assert_eq!(states[0].2.success_ratio, 0.75);
+        assert!((states[0].2.burn_rate - 2.5).abs() < 1e-9);
+        assert_eq!(states[1].0, "latency");
+        assert_eq!(states[1].2.success_ratio, 0.75);
+        assert!((states[1].2.burn_rate - 0.5).abs() < 1e-9);
+
+        let mut out = String::new();
+        tracker.write_metrics(&mut out);
+        assert!(out.contains(
+            "http_dragonfly_slo_success_ratio{listener=\"listener\",target=\"target\",sli=\"availability\",team=\"payments\"} 0.75\n"
+        ));
+        assert!(out.contains(
+            "http_dragonfly_slo_objective{listener=\"listener\",target=\"target\",sli=\"latency\",team=\"payments\"} 0.5\n"
+        ));
+    }
+
+    #[test]
+    fn errors_are_excluded_from_latency() {
+        let tracker = tracker(r#"{"latency": {"threshold": "100ms", "objective": 0.9}}"#);
+        tracker.record(SloOutcome::Error);
+        tracker.record(SloOutcome::Response {
+            failed: false,
+            duration: Duration::from_millis(10),
+        });
+
+        let totals = tracker
+            .state
+            .lock()
+            .unwrap()
+            .totals(tracker.oldest_index(Instant::now()));
+        let states = tracker.sli_states(&totals);
+        assert_eq!(states.len(), 1);
+        assert_eq!(
+            states[0].2,
+            SliState {
+                success_ratio: 1.0,
+                burn_rate: 0.0
+            }
+        );
+    }
+}
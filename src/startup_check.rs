@@ -0,0 +1,30 @@
+use crate::config::AppConfig;
+use futures_util::future::join_all;
+use tracing::{info, warn};
+
+/// Probes all targets with `startup_check` concurrently,
+/// returns description of each unreachable one like `listener/target: error`
+pub async fn probe_targets(app_config: &AppConfig) -> Vec<String> {
+    let probes = app_config.listeners().iter().flat_map(|listener| {
+        listener
+            .targets()
+            .iter()
+            .filter(|target| target.startup_check())
+            .map(move |target| async move {
+                let result = target.probe(listener.tls()).await;
+                let name = format!("{}/{}", listener.id(), target.id());
+                match result {
+                    Ok(()) => {
+                        info!("startup check of target `{name}` passed");
+                        None
+                    }
+                    Err(e) => {
+                        warn!("startup check of target `{name}` failed: {e}");
+                        Some(format!("{name}: {e}"))
+                    }
+                }
+            })
+    });
+
+    join_all(probes).await.into_iter().flatten().collect()
+}
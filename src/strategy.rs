@@ -0,0 +1,59 @@
+use crate::config::labels::Labels;
+use http_body_util::Full;
+use hyper::{body::Bytes, Response};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+static REGISTRY: LazyLock<RwLock<HashMap<String, Arc<dyn CustomStrategy>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Target of the request as it's seen by the custom strategy
+pub struct StrategyTarget<'a> {
+    /// ID of the target
+    pub id: &'a str,
+    /// Static labels of the target, e.g. region or cost
+    pub labels: &'a Labels,
+    /// Response of the target, `None` if the target has failed, has been skipped or cancelled
+    pub response: Option<&'a Response<Full<Bytes>>>,
+    /// `true` if the response is classified as failed by the listener's and target's failure settings
+    pub failed: bool,
+}
+
+/// Final response which is chosen by the custom strategy
+pub enum StrategyDecision {
+    /// Response of the target with the ID, listener's response override is applied to it
+    Target(String),
+    /// Overridden response with the status
+    Status(u16),
+    /// Response is built by the strategy itself, it's sent as is
+    Response(Response<Full<Bytes>>),
+    /// There is no suitable response, `no_targets_status` is responded
+    NoTarget,
+}
+
+/// Response strategy which is implemented by the library user,
+/// listener refers to it by name with `strategy: custom` and `response.custom_strategy`
+pub trait CustomStrategy: Send + Sync {
+    /// Chooses final response when targets are completed or `max_wait` is expired, targets are in order of the config
+    fn select(&self, targets: &[StrategyTarget]) -> StrategyDecision;
+}
+
+/// Registers strategy with the name, it should be done before config is loaded,
+/// since config which refers to unknown strategy is invalid; strategy with the same name is replaced
+pub fn register(name: impl Into<String>, strategy: impl CustomStrategy + 'static) {
+    REGISTRY
+        .write()
+        .expect("unable to lock strategies registry, looks like a BUG")
+        .insert(name.into(), Arc::new(strategy));
+}
+
+/// Returns registered strategy by name
+pub(crate) fn get(name: &str) -> Option<Arc<dyn CustomStrategy>> {
+    REGISTRY
+        .read()
+        .expect("unable to lock strategies registry, looks like a BUG")
+        .get(name)
+        .cloned()
+}
@@ -0,0 +1,201 @@
+use crate::shaping::ThrottledBody;
+use futures_util::FutureExt;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full};
+use hyper::{
+    body::{Body, Bytes, Frame, SizeHint},
+    HeaderMap, Response,
+};
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{Instant, Sleep};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+/// Body of the response returned to the requester: buffered or streamed from the target
+pub type ResponseBody = UnsyncBoxBody<Bytes, BoxError>;
+
+/// Trailers of the buffered target's response (like `grpc-status`), kept in the response extensions
+#[derive(Clone, Debug)]
+pub struct Trailers(pub HeaderMap);
+
+/// Returns content of the buffered body, it's always ready, so it's collected synchronously
+pub fn full_bytes(body: &Full<Bytes>) -> Bytes {
+    body.clone()
+        .collect()
+        .now_or_never()
+        .and_then(Result::ok)
+        .map(|body| body.to_bytes())
+        .unwrap_or_default()
+}
+
+/// Converts buffered response into the final one, trailers are sent after the body if they're present
+pub fn buffered_response(resp: Response<Full<Bytes>>) -> Response<ResponseBody> {
+    let trailers = resp.extensions().get::<Trailers>().cloned();
+    resp.map(|body| {
+        let body = body.map_err(|e: Infallible| match e {});
+        if let Some(Trailers(trailers)) = trailers {
+            body.with_trailers(async move { Some(Ok(trailers)) })
+                .boxed_unsync()
+        } else {
+            body.boxed_unsync()
+        }
+    })
+}
+
+/// Replaces body of the final response with the target's stream
+pub fn streamed_response(
+    resp: Response<Full<Bytes>>,
+    body: IdleTimeoutBody,
+) -> Response<ResponseBody> {
+    resp.map(|_| body.map_err(BoxError::from).boxed_unsync())
+}
+
+/// Buffered body of the requests to targets and other upstreams, trailers of the requester
+/// are sent after the data if they're preserved; size is unknown in this case, so HTTP/1 request is chunked
+#[derive(Debug, Default, Clone)]
+pub struct RequestBody {
+    data: Option<Bytes>,
+    trailers: Option<HeaderMap>,
+}
+
+impl RequestBody {
+    pub fn with_trailers(data: impl Into<Bytes>, trailers: Option<HeaderMap>) -> Self {
+        Self {
+            data: Some(data.into()),
+            trailers,
+        }
+    }
+
+    /// Returns buffered data, it's reference counted, so it's cheap
+    pub fn data(&self) -> Bytes {
+        self.data.clone().unwrap_or_default()
+    }
+
+    pub fn trailers(&self) -> Option<&HeaderMap> {
+        self.trailers.as_ref()
+    }
+}
+
+impl<T: Into<Bytes>> From<T> for RequestBody {
+    fn from(data: T) -> Self {
+        Self::with_trailers(data, None)
+    }
+}
+
+impl Body for RequestBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(data) = self.data.take().filter(|data| !data.is_empty()) {
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+        Poll::Ready(
+            self.trailers
+                .take()
+                .map(|trailers| Ok(Frame::trailers(trailers))),
+        )
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.as_ref().is_none_or(Bytes::is_empty) && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let len = self.data.as_ref().map_or(0, Bytes::len) as u64;
+        if self.trailers.is_some() {
+            let mut hint = SizeHint::new();
+            hint.set_lower(len);
+            hint
+        } else {
+            SizeHint::with_exact(len)
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum StreamError {
+    #[error("target stream failed: {0}")]
+    Target(#[from] hyper::Error),
+    #[error("target stream has been idle for {0:?}")]
+    IdleTimeout(Duration),
+    #[error("target request hasn't been completed in {0:?}")]
+    TotalTimeout(Duration),
+    #[error("target response body is larger than {0} bytes")]
+    Oversized(usize),
+}
+
+/// Target's response body which is passed to the requester as is, chunk by chunk,
+/// stream is interrupted if there is no new data during idle timeout
+#[derive(Debug)]
+pub struct IdleTimeoutBody {
+    inner: ThrottledBody,
+    timeout: Duration,
+    idle: Pin<Box<Sleep>>,
+    prefix: Option<Bytes>,
+}
+
+impl IdleTimeoutBody {
+    pub fn new(inner: ThrottledBody, timeout: Duration) -> Self {
+        Self {
+            inner,
+            timeout,
+            idle: Box::pin(tokio::time::sleep(timeout)),
+            prefix: None,
+        }
+    }
+
+    /// Returns body which starts with the data which has been read from the stream already
+    pub fn with_prefix(mut self, prefix: Bytes) -> Self {
+        self.prefix = Some(prefix).filter(|prefix| !prefix.is_empty());
+        self
+    }
+}
+
+impl Body for IdleTimeoutBody {
+    type Data = Bytes;
+    type Error = StreamError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        if let Some(prefix) = this.prefix.take() {
+            return Poll::Ready(Some(Ok(Frame::data(prefix))));
+        }
+        match Pin::new(&mut this.inner).poll_frame(cx) {
+            Poll::Ready(frame) => {
+                let deadline = Instant::now() + this.timeout;
+                this.idle.as_mut().reset(deadline);
+                Poll::Ready(frame.map(|frame| frame.map_err(StreamError::Target)))
+            }
+            Poll::Pending => match this.idle.as_mut().poll(cx) {
+                Poll::Ready(()) => Poll::Ready(Some(Err(StreamError::IdleTimeout(this.timeout)))),
+                Poll::Pending => Poll::Pending,
+            },
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.prefix.is_none() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let prefix = self.prefix.as_ref().map_or(0, Bytes::len) as u64;
+        let inner = self.inner.size_hint();
+        let mut hint = SizeHint::new();
+        hint.set_lower(inner.lower() + prefix);
+        if let Some(upper) = inner.upper() {
+            hint.set_upper(upper + prefix);
+        }
+        hint
+    }
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, env, io};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// The first file descriptor passed by systemd, see `sd_listen_fds(3)`
+const LISTEN_FDS_START: i32 = 3;
+/// Name of the socket if `FileDescriptorName=` isn't set in the socket unit
+const DEFAULT_FD_NAME: &str = "unknown";
+
+/// Listening sockets inherited from systemd (socket activation), grouped by their names
+#[derive(Debug, Default)]
+pub struct InheritedSockets(HashMap<String, Vec<i32>>);
+
+impl InheritedSockets {
+    /// Collects sockets from `LISTEN_PID`, `LISTEN_FDS` and `LISTEN_FDNAMES` variables,
+    /// sockets are ignored if they're passed to another process
+    pub fn from_env() -> Self {
+        let sockets = Self::parse(
+            env::var("LISTEN_PID").ok().as_deref(),
+            env::var("LISTEN_FDS").ok().as_deref(),
+            env::var("LISTEN_FDNAMES").ok().as_deref(),
+            std::process::id(),
+        );
+        if !sockets.0.is_empty() {
+            info!(names = ?sockets.0.keys(), "inherited sockets from systemd");
+        }
+
+        sockets
+    }
+
+    fn parse(pid: Option<&str>, fds: Option<&str>, names: Option<&str>, own_pid: u32) -> Self {
+        let mut sockets = HashMap::new();
+        if pid.and_then(|pid| pid.trim().parse::<u32>().ok()) != Some(own_pid) {
+            return Self(sockets);
+        }
+        let Some(fds) = fds.and_then(|fds| fds.trim().parse::<i32>().ok()) else {
+            return Self(sockets);
+        };
+
+        let mut names = names.unwrap_or_default().split(':');
+        for fd in LISTEN_FDS_START..LISTEN_FDS_START + fds {
+            let name = match names.next() {
+                Some(name) if !name.is_empty() => name,
+                _ => DEFAULT_FD_NAME,
+            };
+            sockets
+                .entry(name.to_string())
+                .or_insert_with(Vec::new)
+                .push(fd);
+        }
+
+        Self(sockets)
+    }
+
+    /// Takes all sockets with the specified name,
+    /// each socket can be taken once only, so it's owned by a single listener
+    pub fn take(&mut self, name: &str) -> Result<Vec<TcpListener>, io::Error> {
+        let Some(fds) = self.0.remove(name) else {
+            return Ok(vec![]);
+        };
+
+        fds.into_iter().map(Self::listener).collect()
+    }
+
+    #[cfg(unix)]
+    fn listener(fd: i32) -> Result<TcpListener, io::Error> {
+        use std::os::fd::FromRawFd;
+
+        // SAFETY: descriptor is passed by systemd to this process and it's taken once only
+        let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+        listener.set_nonblocking(true)?;
+        TcpListener::from_std(listener)
+    }
+
+    #[cfg(not(unix))]
+    fn listener(fd: i32) -> Result<TcpListener, io::Error> {
+        warn!(
+            fd,
+            "socket activation is supported on Unix-like platforms only"
+        );
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+
+    /// Reports sockets which aren't matched to any listener
+    pub fn warn_unused(&self) {
+        for name in self.0.keys() {
+            warn!(name, "inherited socket doesn't match any listener");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fds(sockets: &InheritedSockets, name: &str) -> Option<Vec<i32>> {
+        sockets.0.get(name).cloned()
+    }
+
+    #[test]
+    fn parse_named_sockets() {
+        let sockets = InheritedSockets::parse(Some("42"), Some("3"), Some("api:api:admin"), 42);
+        assert_eq!(fds(&sockets, "api"), Some(vec![3, 4]));
+        assert_eq!(fds(&sockets, "admin"), Some(vec![5]));
+        assert_eq!(sockets.0.len(), 2);
+    }
+
+    #[test]
+    fn parse_unnamed_sockets() {
+        let sockets = InheritedSockets::parse(Some("42"), Some("2"), None, 42);
+        assert_eq!(fds(&sockets, DEFAULT_FD_NAME), Some(vec![3, 4]));
+
+        let sockets = InheritedSockets::parse(Some("42"), Some("2"), Some("api"), 42);
+        assert_eq!(fds(&sockets, "api"), Some(vec![3]));
+        assert_eq!(fds(&sockets, DEFAULT_FD_NAME), Some(vec![4]));
+    }
+
+    #[test]
+    fn ignore_foreign_sockets() {
+        assert!(
+            InheritedSockets::parse(Some("43"), Some("2"), Some("api"), 42)
+                .0
+                .is_empty()
+        );
+        assert!(InheritedSockets::parse(None, Some("2"), Some("api"), 42)
+            .0
+            .is_empty());
+        assert!(InheritedSockets::parse(Some("42"), None, None, 42)
+            .0
+            .is_empty());
+        assert!(InheritedSockets::parse(Some("42"), Some("x"), None, 42)
+            .0
+            .is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{
+    cli::CliConfig,
+    config::{AppConfig, ConfigFormat},
+    context::{secrets, Context, RootOsEnvironment},
+};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    service::service_fn,
+    HeaderMap, Method, Request, Response, StatusCode, Uri,
+};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo},
+    server::conn::auto::Builder,
+};
+use std::{
+    convert::Infallible,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// Environment variables which are available to the config, as in the default CLI mode
+const ENV_MASK: &str = "^HTTP_ENV_[a-zA-Z0-9_]+$";
+/// Max time to wait for all listeners to accept connections
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Listeners of the config which are served in-process, to test routing and response strategies end-to-end;
+/// listeners and mock targets are served by the current runtime, so they live until the end of the test's runtime
+pub struct TestSplitter {
+    app_config: Arc<AppConfig>,
+    server: JoinHandle<Result<(), anyhow::Error>>,
+}
+
+impl TestSplitter {
+    /// Starts all listeners of the config and waits until they accept connections
+    pub async fn start(app_config: AppConfig) -> Result<Self, anyhow::Error> {
+        let app_config = Arc::new(app_config);
+        let root_ctx = Arc::new(root_context()?);
+        let server = tokio::spawn({
+            let app_config = app_config.clone();
+            async move { crate::serve(app_config, root_ctx, &CliConfig::default()).await }
+        });
+        let splitter = Self { app_config, server };
+
+        for cfg in splitter.app_config.listeners() {
+            splitter.wait_for(local_addr(cfg.socket())).await?;
+        }
+
+        Ok(splitter)
+    }
+
+    /// Parses config from the content and starts it, `${VAR}` references are resolved from `HTTP_ENV_*` variables
+    pub async fn from_content(content: &str, format: ConfigFormat) -> Result<Self, anyhow::Error> {
+        let app_config = AppConfig::from_content(content, format, &root_context()?, false)?;
+
+        Self::start(app_config).await
+    }
+
+    /// Returns base URL of the listener, panics if listener isn't defined
+    pub fn url(&self, listener_id: &str) -> String {
+        let cfg = self
+            .app_config
+            .listeners()
+            .iter()
+            .find(|cfg| cfg.id() == listener_id)
+            .unwrap_or_else(|| panic!("listener `{listener_id}` isn't defined in the config"));
+
+        format!("http://{}", local_addr(cfg.socket()))
+    }
+
+    async fn wait_for(&self, addr: SocketAddr) -> Result<(), anyhow::Error> {
+        let started = tokio::time::Instant::now();
+        loop {
+            if self.server.is_finished() {
+                return Err(anyhow::anyhow!(
+                    "listeners have been stopped during startup"
+                ));
+            }
+            if TcpStream::connect(addr).await.is_ok() {
+                return Ok(());
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(anyhow::anyhow!("listener on `{addr}` isn't ready in time"));
+            }
+            tokio::time::sleep(STARTUP_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Response of the mock target
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: StatusCode,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+    delay: Option<Duration>,
+}
+
+impl Default for MockResponse {
+    fn default() -> Self {
+        Self::new(StatusCode::OK.as_u16())
+    }
+}
+
+impl MockResponse {
+    /// Response with the status and empty body
+    pub fn new(status: u16) -> Self {
+        Self {
+            status: StatusCode::from_u16(status).expect("invalid mock response status"),
+            headers: vec![],
+            body: Bytes::new(),
+            delay: None,
+        }
+    }
+
+    /// Adds header to the response
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets body of the response
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Delays response headers, to test timeouts and latency-based strategies
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// Request which is received by the mock target
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// Trailers sent after the chunked body, if any
+    pub trailers: Option<HeaderMap>,
+}
+
+#[derive(Default)]
+struct MockState {
+    response: MockResponse,
+    received: Vec<ReceivedRequest>,
+}
+
+/// In-process HTTP target which records received requests and responds with the programmed response
+pub struct MockTarget {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    server: JoinHandle<()>,
+}
+
+impl MockTarget {
+    /// Starts mock target on the random local port, it responds with `200` and empty body by default
+    pub async fn start() -> Result<Self, anyhow::Error> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState::default()));
+
+        let server = tokio::spawn({
+            let state = state.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        let service = service_fn(move |req| Self::handle(state.clone(), req));
+                        let _ = Builder::new(TokioExecutor::new())
+                            .serve_connection(TokioIo::new(stream), service)
+                            .await;
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            state,
+            server,
+        })
+    }
+
+    /// Returns base URL of the target to use in the config
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Sets response to all subsequent requests
+    pub fn respond_with(&self, response: MockResponse) {
+        self.lock().response = response;
+    }
+
+    /// Returns all received requests in order of arrival
+    pub fn received(&self) -> Vec<ReceivedRequest> {
+        self.lock().received.clone()
+    }
+
+    /// Forgets received requests
+    pub fn reset(&self) {
+        self.lock().received.clear();
+    }
+
+    /// Asserts number of the received requests, waits for them up to `timeout`,
+    /// since targets may be called after the response is sent to the client
+    pub async fn assert_received(&self, expected: usize, timeout: Duration) {
+        let started = tokio::time::Instant::now();
+        while self.lock().received.len() < expected && started.elapsed() < timeout {
+            tokio::time::sleep(STARTUP_POLL_INTERVAL).await;
+        }
+        let received = self.lock().received.len();
+        assert_eq!(
+            received, expected,
+            "mock target {}: {expected} request(s) expected, {received} received",
+            self.addr
+        );
+    }
+
+    async fn handle(
+        state: Arc<Mutex<MockState>>,
+        req: Request<Incoming>,
+    ) -> Result<Response<Full<Bytes>>, Infallible> {
+        let (parts, body) = req.into_parts();
+        let (body, trailers) = body
+            .collect()
+            .await
+            .map(|body| {
+                let trailers = body.trailers().cloned();
+                (body.to_bytes(), trailers)
+            })
+            .unwrap_or_default();
+        let response = {
+            let mut state = state.lock().expect("unable to lock mock target state");
+            state.received.push(ReceivedRequest {
+                method: parts.method,
+                uri: parts.uri,
+                headers: parts.headers,
+                body,
+                trailers,
+            });
+            state.response.clone()
+        };
+
+        if let Some(delay) = response.delay {
+            tokio::time::sleep(delay).await;
+        }
+        let mut resp = Response::builder().status(response.status);
+        for (name, value) in &response.headers {
+            resp = resp.header(name, value);
+        }
+
+        Ok(resp
+            .body(Full::new(response.body))
+            .expect("invalid mock response"))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().expect("unable to lock mock target state")
+    }
+}
+
+impl Drop for MockTarget {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Root context with secrets of the registered providers, file secrets aren't loaded
+fn root_context() -> Result<Context<'static>, anyhow::Error> {
+    let mut root_ctx = Context::root(RootOsEnvironment::new(ENV_MASK));
+    for provider in secrets::registered() {
+        root_ctx.add_secrets(provider.as_ref())?;
+    }
+
+    Ok(root_ctx)
+}
+
+/// Unspecified address of the listener is replaced with loopback one to connect to
+fn local_addr(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(v4) if v4.ip().is_unspecified() => (Ipv4Addr::LOCALHOST, v4.port()).into(),
+        SocketAddr::V6(v6) if v6.ip().is_unspecified() => (Ipv6Addr::LOCALHOST, v6.port()).into(),
+        addr => addr,
+    }
+}
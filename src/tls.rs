@@ -0,0 +1,358 @@
+use crate::{
+    acme::ACME_TLS_ALPN_NAME,
+    config::server_tls::{self, ServerTlsConfig},
+};
+use rustls::{
+    server::{Acceptor, ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+    ServerConfig,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::{server::TlsStream, LazyConfigAcceptor, TlsAcceptor};
+use tracing::{info, warn};
+
+/// Content type of the TLS handshake record, it's the first byte sent by TLS client
+pub const TLS_HANDSHAKE_RECORD: u8 = 0x16;
+
+/// Terminates TLS of the listener's connections, and detects protocol of the connection if plaintext is accepted
+#[derive(Clone)]
+pub struct TlsTermination {
+    config: Arc<ServerConfig>,
+    accept_plaintext: bool,
+    server_names: Vec<String>,
+}
+
+/// Accepted connection of the listener, either plaintext or TLS one
+pub enum ServerStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl TlsTermination {
+    pub fn new(cfg: &ServerTlsConfig) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            config: cfg.server_config()?,
+            accept_plaintext: cfg.accept_plaintext(),
+            server_names: cfg.server_names().to_vec(),
+        })
+    }
+
+    /// Performs TLS handshake, the first byte of the connection is peeked to decide if it's TLS
+    /// when plaintext is accepted as well
+    pub async fn accept(&self, stream: TcpStream) -> Result<ServerStream, io::Error> {
+        if self.accept_plaintext {
+            let mut first = [0u8; 1];
+            if stream.peek(&mut first).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if first[0] != TLS_HANDSHAKE_RECORD {
+                return Ok(ServerStream::Plain(stream));
+            }
+        }
+
+        let stream = TlsAcceptor::from(self.config.clone())
+            .accept(stream)
+            .await?;
+        tls_stream(stream)
+    }
+
+    /// Performs TLS handshake of the connection to the socket which is shared by several listeners:
+    /// termination is selected by SNI host name of the client, exact names take precedence over wildcards,
+    /// and termination without server names is used if no one matches.
+    /// Returns position of the selected termination.
+    pub async fn accept_by_server_name(
+        terminations: &[&TlsTermination],
+        stream: TcpStream,
+    ) -> Result<(usize, ServerStream), io::Error> {
+        let start = LazyConfigAcceptor::new(Acceptor::default(), stream).await?;
+        let server_name = start.client_hello().server_name().map(str::to_lowercase);
+        let position = server_name
+            .as_deref()
+            .and_then(|name| {
+                let position = |wildcard| {
+                    terminations.iter().position(|tls| {
+                        tls.server_names
+                            .iter()
+                            .any(|pattern| matches_server_name(pattern, name, wildcard))
+                    })
+                };
+                position(false).or_else(|| position(true))
+            })
+            .or_else(|| {
+                terminations
+                    .iter()
+                    .position(|tls| tls.server_names.is_empty())
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown server name {server_name:?}"),
+                )
+            })?;
+
+        let stream = start
+            .into_stream(terminations[position].config.clone())
+            .await?;
+        Ok((position, tls_stream(stream)?))
+    }
+}
+
+/// Connection of TLS-ALPN-01 challenge is closed after the handshake, it isn't used for requests
+fn tls_stream(stream: TlsStream<TcpStream>) -> Result<ServerStream, io::Error> {
+    if stream.get_ref().1.alpn_protocol() == Some(ACME_TLS_ALPN_NAME) {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionAborted,
+            "ACME challenge handshake is completed",
+        ));
+    }
+
+    Ok(ServerStream::Tls(Box::new(stream)))
+}
+
+/// Returns `true` if lowercase host name matches the pattern: either exact name, or `*.example.com` wildcard
+/// which matches any subdomain of `example.com`
+fn matches_server_name(pattern: &str, name: &str, wildcard: bool) -> bool {
+    match pattern.strip_prefix('*') {
+        Some(suffix) => {
+            wildcard && name.len() > suffix.len() && name.ends_with(&suffix.to_lowercase())
+        }
+        None => !wildcard && pattern.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Resolves listener's certificate which is re-read from its files on new handshake, but not more often than once
+/// per reload interval, so renewed certificates are picked up without restart, established connections keep
+/// the certificate they were accepted with. Previous certificate is kept if files can't be loaded.
+#[derive(Debug)]
+pub struct ReloadableCertResolver {
+    cert: PathBuf,
+    key: PathBuf,
+    reload_interval: Duration,
+    state: RwLock<CertState>,
+}
+
+#[derive(Debug)]
+struct CertState {
+    /// Content of the certificate and key files, they're parsed again only if they're changed
+    content: (Vec<u8>, Vec<u8>),
+    certified_key: Arc<CertifiedKey>,
+    loaded_at: Instant,
+}
+
+impl ReloadableCertResolver {
+    pub fn new(cert: &Path, key: &Path, reload_interval: Duration) -> Result<Self, anyhow::Error> {
+        let content = (fs::read(cert)?, fs::read(key)?);
+        let certified_key = server_tls::certified_key(&content.0, cert, &content.1, key)?;
+        let state = CertState {
+            content,
+            certified_key: Arc::new(certified_key),
+            loaded_at: Instant::now(),
+        };
+
+        Ok(Self {
+            cert: cert.into(),
+            key: key.into(),
+            reload_interval,
+            state: RwLock::new(state),
+        })
+    }
+
+    /// Returns current certificate, reloads it if it's old enough
+    fn certified_key(&self) -> Arc<CertifiedKey> {
+        {
+            let state = self
+                .state
+                .read()
+                .expect("unable to lock server certificate, looks like a BUG");
+            if state.loaded_at.elapsed() < self.reload_interval {
+                return state.certified_key.clone();
+            }
+        }
+
+        let mut state = self
+            .state
+            .write()
+            .expect("unable to lock server certificate, looks like a BUG");
+        // Other handshake could reload it while we were waiting for the lock
+        if state.loaded_at.elapsed() >= self.reload_interval {
+            self.reload(&mut state);
+        }
+        state.certified_key.clone()
+    }
+
+    fn reload(&self, state: &mut CertState) {
+        state.loaded_at = Instant::now();
+        let content = match (fs::read(&self.cert), fs::read(&self.key)) {
+            (Ok(cert), Ok(key)) if (&cert, &key) == (&state.content.0, &state.content.1) => return,
+            (Ok(cert), Ok(key)) => (cert, key),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!(cert = %self.cert.display(), error = %e, "unable to reload server certificate, keep previous one");
+                return;
+            }
+        };
+        match server_tls::certified_key(&content.0, &self.cert, &content.1, &self.key) {
+            Ok(certified_key) => {
+                info!(cert = %self.cert.display(), "server certificate reloaded");
+                state.content = content;
+                state.certified_key = Arc::new(certified_key);
+            }
+            Err(e) => {
+                warn!(cert = %self.cert.display(), error = %e, "unable to reload server certificate, keep previous one")
+            }
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadableCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key())
+    }
+}
+
+impl ServerStream {
+    /// Returns underlying TCP connection
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            ServerStream::Plain(stream) => stream,
+            ServerStream::Tls(stream) => stream.get_ref().0,
+        }
+    }
+}
+
+impl AsyncRead for ServerStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            ServerStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ServerStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            ServerStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            ServerStream::Tls(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            ServerStream::Plain(stream) => stream.is_write_vectored(),
+            ServerStream::Tls(stream) => stream.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            ServerStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ServerStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            ServerStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERTS_DIR: &str = env!("OUT_DIR");
+
+    #[test]
+    fn reloadable_certificate() {
+        let dir = std::env::temp_dir().join(format!("http-dragonfly-cert-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("server.pem"), dir.join("server.key"));
+        fs::copy(format!("{CERTS_DIR}/test-server.pem"), &cert).unwrap();
+        fs::copy(format!("{CERTS_DIR}/test-server.key"), &key).unwrap();
+
+        // Certificates are reloaded on each handshake with zero interval
+        let resolver = ReloadableCertResolver::new(&cert, &key, Duration::ZERO).unwrap();
+        assert_eq!(resolver.certified_key().cert.len(), 2);
+
+        // Renewed certificate without intermediate CA
+        fs::copy(format!("{CERTS_DIR}/end.crt"), &cert).unwrap();
+        assert_eq!(resolver.certified_key().cert.len(), 1);
+
+        // Broken certificate isn't loaded, previous one is kept
+        fs::write(&cert, "broken").unwrap();
+        assert_eq!(resolver.certified_key().cert.len(), 1);
+        fs::remove_file(&key).unwrap();
+        assert_eq!(resolver.certified_key().cert.len(), 1);
+
+        // Nothing is reloaded during the interval
+        let resolver = ReloadableCertResolver::new(
+            Path::new(&format!("{CERTS_DIR}/test-server.pem")),
+            Path::new(&format!("{CERTS_DIR}/test-server.key")),
+            Duration::from_secs(3600),
+        )
+        .unwrap();
+        let loaded = resolver.certified_key();
+        assert!(Arc::ptr_eq(&loaded, &resolver.certified_key()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn server_name_patterns() {
+        assert!(matches_server_name(
+            "API.example.com",
+            "api.example.com",
+            false
+        ));
+        assert!(!matches_server_name(
+            "api.example.com",
+            "api.example.com",
+            true
+        ));
+        assert!(matches_server_name(
+            "*.Example.com",
+            "api.example.com",
+            true
+        ));
+        assert!(!matches_server_name(
+            "*.example.com",
+            "api.example.com",
+            false
+        ));
+        assert!(!matches_server_name("*.example.com", ".example.com", true));
+        assert!(!matches_server_name("*.example.com", "example.com", true));
+        assert!(!matches_server_name("*.example.com", "api.other.com", true));
+    }
+}
@@ -0,0 +1,203 @@
+use crate::handler::HyperError;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{CONNECTION, UPGRADE},
+    upgrade::OnUpgrade,
+    HeaderMap, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use tokio::{
+    io::{self, AsyncReadExt, AsyncWriteExt},
+    sync::mpsc,
+    task::JoinHandle,
+    time::error::Elapsed,
+};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Size of the buffer to read client's data
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+/// How many chunks of client's data can be queued to each mirror,
+/// mirror is disconnected if it's slower than the client
+const MIRROR_QUEUE_SIZE: usize = 64;
+
+pub type MirrorHandshake = JoinHandle<Result<Result<Response<Incoming>, HyperError>, Elapsed>>;
+
+/// Verifies if request asks for WebSocket protocol upgrade
+pub fn is_upgrade_request<B>(req: &Request<B>) -> bool {
+    let has_token = |name, token: &str| {
+        req.headers().get_all(name).iter().any(|value| {
+            value
+                .to_str()
+                .unwrap_or_default()
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case(token))
+        })
+    };
+
+    has_token(CONNECTION, "upgrade") && has_token(UPGRADE, "websocket")
+}
+
+/// Puts back hop-by-hop and WebSocket handshake headers which may be dropped by headers transformations
+pub fn restore_upgrade_headers(original: &HeaderMap, headers: &mut HeaderMap) {
+    for (name, value) in original {
+        let is_handshake_header =
+            name == CONNECTION || name == UPGRADE || name.as_str().starts_with("sec-websocket-");
+        if is_handshake_header && !headers.contains_key(name) {
+            headers.insert(name, value.clone());
+        }
+    }
+}
+
+/// Pumps data between client and primary target until any of them closes connection,
+/// all data from the client is copied to the mirrors as well, all data from mirrors is discarded
+pub async fn proxy_session(
+    req_id: Uuid,
+    client: OnUpgrade,
+    primary: OnUpgrade,
+    mirrors: Vec<(String, MirrorHandshake)>,
+) {
+    let (client, primary) = match tokio::try_join!(client, primary) {
+        Ok((client, primary)) => (TokioIo::new(client), TokioIo::new(primary)),
+        Err(e) => {
+            warn!("{req_id}: unable to upgrade websocket connection: {e}");
+            return;
+        }
+    };
+
+    let mut mirror_queues = vec![];
+    for (id, handshake) in mirrors {
+        if let Some(queue) = connect_mirror(req_id, &id, handshake).await {
+            mirror_queues.push((id, queue));
+        }
+    }
+
+    let (mut client_rd, mut client_wr) = io::split(client);
+    let (mut primary_rd, mut primary_wr) = io::split(primary);
+
+    let upstream = async {
+        let mut buf = vec![0u8; READ_BUFFER_SIZE];
+        loop {
+            let size = client_rd.read(&mut buf).await?;
+            if size == 0 {
+                break;
+            }
+            let chunk = Bytes::copy_from_slice(&buf[..size]);
+            mirror_queues.retain(|(id, queue)| {
+                let sent = queue.try_send(chunk.clone()).is_ok();
+                if !sent {
+                    warn!("{req_id}: websocket mirror `{id}` is disconnected, it's too slow or closed");
+                }
+                sent
+            });
+            primary_wr.write_all(&chunk).await?;
+        }
+        primary_wr.shutdown().await
+    };
+    let downstream = async {
+        io::copy(&mut primary_rd, &mut client_wr).await?;
+        client_wr.shutdown().await
+    };
+
+    match tokio::try_join!(upstream, downstream) {
+        Ok(_) => info!("{req_id}: websocket session completed"),
+        Err(e) => debug!("{req_id}: websocket session closed: {e}"),
+    }
+}
+
+/// Completes mirror's handshake and spawns task to feed it with client's data
+async fn connect_mirror(
+    req_id: Uuid,
+    id: &str,
+    handshake: MirrorHandshake,
+) -> Option<mpsc::Sender<Bytes>> {
+    let resp = match handshake.await {
+        Ok(Ok(Ok(resp))) => resp,
+        Ok(Ok(Err(e))) => {
+            warn!("{req_id}: websocket mirror `{id}` failed: {e}");
+            return None;
+        }
+        _ => {
+            warn!("{req_id}: websocket mirror `{id}` timed out");
+            return None;
+        }
+    };
+    if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+        warn!(
+            "{req_id}: websocket mirror `{id}` rejected upgrade with status {}",
+            resp.status()
+        );
+        return None;
+    }
+    let upgraded = match hyper::upgrade::on(resp).await {
+        Ok(upgraded) => upgraded,
+        Err(e) => {
+            warn!("{req_id}: unable to upgrade websocket mirror `{id}`: {e}");
+            return None;
+        }
+    };
+
+    let (mut mirror_rd, mut mirror_wr) = io::split(TokioIo::new(upgraded));
+    let (queue, mut receiver) = mpsc::channel::<Bytes>(MIRROR_QUEUE_SIZE);
+    tokio::spawn(async move {
+        // Mirror's responses aren't needed, but they have to be read to keep connection alive
+        let _ = io::copy(&mut mirror_rd, &mut io::sink()).await;
+    });
+    tokio::spawn(async move {
+        while let Some(chunk) = receiver.recv().await {
+            if mirror_wr.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+        let _ = mirror_wr.shutdown().await;
+    });
+
+    Some(queue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::{HeaderValue, SEC_WEBSOCKET_KEY};
+
+    #[test]
+    fn upgrade_request() {
+        let req = Request::builder()
+            .header(CONNECTION, "keep-alive, Upgrade")
+            .header(UPGRADE, "websocket")
+            .body(())
+            .unwrap();
+        assert!(is_upgrade_request(&req));
+
+        let req = Request::builder()
+            .header(UPGRADE, "websocket")
+            .body(())
+            .unwrap();
+        assert!(!is_upgrade_request(&req));
+
+        let req = Request::builder()
+            .header(CONNECTION, "upgrade")
+            .header(UPGRADE, "h2c")
+            .body(())
+            .unwrap();
+        assert!(!is_upgrade_request(&req));
+    }
+
+    #[test]
+    fn restore_headers() {
+        let mut original = HeaderMap::new();
+        original.insert(CONNECTION, HeaderValue::from_static("Upgrade"));
+        original.insert(UPGRADE, HeaderValue::from_static("websocket"));
+        original.insert(SEC_WEBSOCKET_KEY, HeaderValue::from_static("key"));
+        original.insert("x-other", HeaderValue::from_static("other"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(UPGRADE, HeaderValue::from_static("websocket-transformed"));
+        restore_upgrade_headers(&original, &mut headers);
+
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers[UPGRADE], "websocket-transformed");
+        assert_eq!(headers[CONNECTION], "Upgrade");
+        assert_eq!(headers[SEC_WEBSOCKET_KEY], "key");
+    }
+}
@@ -0,0 +1,368 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use hyper::{body::Incoming, service::service_fn, Request, Response};
+use hyper_util::rt::TokioIo;
+use rcgen::{
+    BasicConstraints, CertificateParams, CertificateSigningRequestParams, IsCa, Issuer, KeyPair,
+};
+use reqwest::{Certificate, Client};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+    DigitallySignedStruct, SignatureScheme,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+use x509_parser::{oid_registry::Oid, parse_x509_certificate};
+
+const CERTS_DIR: &str = env!("OUT_DIR");
+const ACME_SERVER: &str = "https://localhost:9984";
+const LISTENER: &str = "127.0.0.1:9985";
+const DOMAIN: &str = "test-server.com";
+const TOKEN: &str = "challenge-token";
+/// `id-pe-acmeIdentifier` extension of TLS-ALPN-01 challenge certificate
+const ACME_IDENTIFIER_OID: &[u64] = &[1, 3, 6, 1, 5, 5, 7, 1, 31];
+
+/// Minimal ACME server which validates TLS-ALPN-01 challenge on the listener and signs CSR by its own CA
+struct MockAcmeServer {
+    issuer: Issuer<'static, KeyPair>,
+    ca: String,
+    state: Mutex<OrderState>,
+}
+
+#[derive(Default)]
+struct OrderState {
+    nonce: u64,
+    thumbprint: String,
+    challenge_valid: Option<bool>,
+    certificate: Option<String>,
+}
+
+impl MockAcmeServer {
+    async fn start() -> Arc<Self> {
+        let mut params = CertificateParams::new(vec![]).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let key = KeyPair::generate().unwrap();
+        let ca = params.self_signed(&key).unwrap().pem();
+        let server = Arc::new(Self {
+            issuer: Issuer::new(params, key),
+            ca,
+            state: Mutex::default(),
+        });
+
+        let tls = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(
+                CertificateDer::pem_file_iter(format!("{CERTS_DIR}/test-server.pem"))
+                    .unwrap()
+                    .map(|cert| cert.unwrap())
+                    .collect(),
+                PrivateKeyDer::from_pem_file(format!("{CERTS_DIR}/test-server.key")).unwrap(),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(tls));
+        let listener = TcpListener::bind("127.0.0.1:9984").await.unwrap();
+        let mock = server.clone();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (acceptor, mock) = (acceptor.clone(), mock.clone());
+                tokio::spawn(async move {
+                    let Ok(stream) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let service = service_fn(|req| {
+                        let mock = mock.clone();
+                        async move { Ok::<_, hyper::Error>(mock.handle(req).await) }
+                    });
+                    let _ = hyper::server::conn::http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        server
+    }
+
+    async fn handle(&self, req: Request<Incoming>) -> Response<Full<Bytes>> {
+        let path = req.uri().path().to_string();
+        let body = req.into_body().collect().await.unwrap().to_bytes();
+        let (protected, payload) = match serde_json::from_slice::<Value>(&body) {
+            Ok(jws) => (decode(&jws["protected"]), decode(&jws["payload"])),
+            Err(_) => (Value::Null, Value::Null),
+        };
+
+        let (status, location, body) = match path.as_str() {
+            "/directory" => (
+                200,
+                None,
+                json!({
+                    "newNonce": format!("{ACME_SERVER}/nonce"),
+                    "newAccount": format!("{ACME_SERVER}/account"),
+                    "newOrder": format!("{ACME_SERVER}/order"),
+                }),
+            ),
+            "/nonce" => (200, None, Value::Null),
+            "/account" => {
+                self.state.lock().unwrap().thumbprint = thumbprint(&protected["jwk"]);
+                (201, Some("/account/1"), json!({"status": "valid"}))
+            }
+            "/order" => {
+                assert_eq!(
+                    payload["identifiers"],
+                    json!([{"type": "dns", "value": DOMAIN}])
+                );
+                (201, Some("/order/1"), self.order())
+            }
+            "/order/1" => (200, None, self.order()),
+            "/authz/1" => (
+                200,
+                None,
+                json!({
+                    "identifier": {"type": "dns", "value": DOMAIN},
+                    "status": "pending",
+                    "challenges": [self.challenge()],
+                }),
+            ),
+            "/challenge/1" => {
+                let valid = self.validate_challenge().await;
+                self.state.lock().unwrap().challenge_valid = Some(valid);
+                (200, None, self.challenge())
+            }
+            "/finalize/1" => {
+                let csr = URL_SAFE_NO_PAD
+                    .decode(payload["csr"].as_str().unwrap())
+                    .unwrap();
+                let csr = CertificateSigningRequestParams::from_der(&csr.into()).unwrap();
+                let cert = csr.signed_by(&self.issuer).unwrap();
+                self.state.lock().unwrap().certificate = Some(cert.pem() + self.ca.as_str());
+                (200, None, self.order())
+            }
+            "/certificate/1" => {
+                let chain = self.state.lock().unwrap().certificate.clone().unwrap();
+                return self.response(200, None, Bytes::from(chain));
+            }
+            _ => (404, None, Value::Null),
+        };
+
+        self.response(status, location, Bytes::from(body.to_string()))
+    }
+
+    fn response(&self, status: u16, location: Option<&str>, body: Bytes) -> Response<Full<Bytes>> {
+        let nonce = {
+            let mut state = self.state.lock().unwrap();
+            state.nonce += 1;
+            state.nonce
+        };
+        let mut resp = Response::builder()
+            .status(status)
+            .header("replay-nonce", format!("nonce-{nonce}"));
+        if let Some(location) = location {
+            resp = resp.header("location", format!("{ACME_SERVER}{location}"));
+        }
+
+        resp.body(Full::new(body)).unwrap()
+    }
+
+    fn order(&self) -> Value {
+        let state = self.state.lock().unwrap();
+        let status = match (state.challenge_valid, &state.certificate) {
+            (_, Some(_)) => "valid",
+            (Some(true), None) => "ready",
+            (Some(false), None) => "invalid",
+            (None, None) => "pending",
+        };
+
+        json!({
+            "status": status,
+            "authorizations": [format!("{ACME_SERVER}/authz/1")],
+            "finalize": format!("{ACME_SERVER}/finalize/1"),
+            "certificate": state.certificate.as_ref().map(|_| format!("{ACME_SERVER}/certificate/1")),
+        })
+    }
+
+    fn challenge(&self) -> Value {
+        let status = match self.state.lock().unwrap().challenge_valid {
+            Some(true) => "valid",
+            Some(false) => "invalid",
+            None => "pending",
+        };
+
+        json!({
+            "type": "tls-alpn-01",
+            "url": format!("{ACME_SERVER}/challenge/1"),
+            "token": TOKEN,
+            "status": status,
+        })
+    }
+
+    /// Challenge certificate should be served to the client with `acme-tls/1` protocol, its `acmeIdentifier`
+    /// extension is a digest of the key authorization
+    async fn validate_challenge(&self) -> bool {
+        let key_authorization = format!("{TOKEN}.{}", self.state.lock().unwrap().thumbprint);
+        let mut expected = vec![0x04, 0x20];
+        expected.extend(Sha256::digest(key_authorization));
+
+        let mut tls = rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AnyCertificate))
+            .with_no_client_auth();
+        tls.alpn_protocols = vec![b"acme-tls/1".to_vec()];
+        // Order may be placed before the listener is bound
+        let mut stream = TcpStream::connect(LISTENER).await;
+        for _ in 0..20 {
+            if stream.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stream = TcpStream::connect(LISTENER).await;
+        }
+        let stream = stream.unwrap();
+        let Ok(stream) = TlsConnector::from(Arc::new(tls))
+            .connect(ServerName::try_from(DOMAIN).unwrap(), stream)
+            .await
+        else {
+            return false;
+        };
+
+        let (_, conn) = stream.get_ref();
+        let cert = &conn.peer_certificates().unwrap()[0];
+        let (_, cert) = parse_x509_certificate(cert).unwrap();
+        let oid = Oid::from(ACME_IDENTIFIER_OID).unwrap();
+        conn.alpn_protocol() == Some(b"acme-tls/1")
+            && cert
+                .get_extension_unique(&oid)
+                .unwrap()
+                .is_some_and(|ext| ext.critical && ext.value == expected)
+    }
+}
+
+fn decode(value: &Value) -> Value {
+    value
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(|value| serde_json::from_slice(&URL_SAFE_NO_PAD.decode(value).unwrap()).unwrap())
+        .unwrap_or_default()
+}
+
+/// JWK thumbprint of EC account key, RFC 7638
+fn thumbprint(jwk: &Value) -> String {
+    let jwk = format!(
+        r#"{{"crv":{},"kty":{},"x":{},"y":{}}}"#,
+        jwk["crv"], jwk["kty"], jwk["x"], jwk["y"]
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(jwk))
+}
+
+/// Challenge certificate is self-signed, so it's checked by its content only
+#[derive(Debug)]
+struct AnyCertificate;
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &CertificateDer<'_>,
+        _dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        Ok(HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[tokio::test]
+async fn certificate_is_obtained_from_acme_server() {
+    let acme = MockAcmeServer::start().await;
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body("target"));
+
+    let cache_dir =
+        std::env::temp_dir().join(format!("http-dragonfly-acme-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+    let config = format!(
+        r#"
+        listeners:
+          - id: acme
+            listen_on: "{LISTENER}"
+            strategy: always_target_id
+            server_tls:
+              acme:
+                domains: ["{DOMAIN}"]
+                email: admin@test-server.com
+                cache_dir: "{cache_dir}"
+                directory: "{ACME_SERVER}/directory"
+                directory_ca: "{CERTS_DIR}/ca.crt"
+            targets:
+              - {{id: target, url: "{target}"}}
+            response:
+              target_selector: target
+        "#,
+        cache_dir = cache_dir.display(),
+        target = target.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    // Certificate is issued by CA of the ACME server after the challenge
+    let client = Client::builder()
+        .add_root_certificate(Certificate::from_pem(acme.ca.as_bytes()).unwrap())
+        .resolve(DOMAIN, LISTENER.parse().unwrap())
+        .build()
+        .unwrap();
+    let mut resp = None;
+    for _ in 0..50 {
+        if let Ok(ok) = client.get(format!("https://{DOMAIN}:9985/")).send().await {
+            resp = Some(ok);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(acme.state.lock().unwrap().challenge_valid, Some(true));
+    let resp = resp.expect("certificate wasn't obtained");
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.text().await.unwrap(), "target");
+
+    // Account and certificate are cached for restarts
+    for file in ["account.json", "cert.pem", "key.pem"] {
+        assert!(cache_dir.join(file).exists(), "{file} isn't cached");
+    }
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{path::Path, time::Duration};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/audit.yaml";
+const TEST_PORT: u16 = 3025;
+const AUDIT_PATH: &str = "/tmp/http-dragonfly-audit-9912.jsonl";
+
+fn audit_records() -> Vec<String> {
+    std::fs::read_to_string(AUDIT_PATH)
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+#[tokio::test]
+async fn audit() {
+    init_logging();
+    if Path::new(AUDIT_PATH).exists() {
+        std::fs::remove_file(AUDIT_PATH).unwrap();
+    }
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request to single target is audited",
+                port: 9912,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request to two targets is audited",
+                port: 9912,
+                include_wrong_port: true,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        while audit_records().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert_eq!(result, Ok(()));
+
+    let records = audit_records();
+    let mut prev_hash = "0".repeat(64);
+    for (seq, line) in records.iter().enumerate() {
+        let record: Value = serde_json::from_str(line).unwrap();
+        assert_eq!(record["seq"], seq as u64 + 1);
+        assert_eq!(record["listener"], "audit-9912");
+        assert_eq!(record["identity"], "yes");
+        assert_eq!(record["status"], 200);
+        assert_eq!(record["targets"][0]["id"], "GOOD");
+        assert_eq!(record["targets"][0]["status"], "200");
+        assert_eq!(record["prev_hash"], prev_hash.as_str());
+
+        let (content, _) = line.rsplit_once(",\"hash\":").unwrap();
+        prev_hash = format!("{:x}", Sha256::digest(format!("{content}}}").as_bytes()));
+        assert_eq!(record["hash"], prev_hash.as_str());
+    }
+    let record: Value = serde_json::from_str(&records[1]).unwrap();
+    assert_eq!(record["targets"][1]["id"], "WRONG");
+    assert_eq!(record["targets"][1]["status"], "error");
+}
@@ -3,6 +3,7 @@ mod common;
 use crate::common::run_test_with_config;
 use common::{init_logging, test_one_case, TestConfig};
 use futures_util::future::join_all;
+use std::time::Duration;
 
 const TEST_CONFIG_PATH: &str = "tests/configs/integration/basic.yaml";
 const TEST_PORT: u16 = 3000;
@@ -230,6 +231,31 @@ fn prepare_test_cases() -> Vec<TestConfig> {
             expected_x_target_id_header: Some("wrong_port_with_dropped_status"),
             ..TestConfig::default()
         },
+        TestConfig {
+            description: "cancel_unneeded_targets",
+            port: 8011,
+            include_timeout_target: true,
+            expected_max_duration: Some(Duration::from_secs(3)),
+            ..TestConfig::default()
+        },
+        TestConfig {
+            description: "cancel_unneeded_targets",
+            port: 8011,
+            include_good_target: false,
+            include_timeout_target: true,
+            expected_x_target_id_header: Some("SLOW"),
+            ..TestConfig::default()
+        },
+        TestConfig {
+            description: "copy_headers",
+            port: 8012,
+            expected_x_target_id_header: Some("PRIMARY-COPY"),
+            expected_headers: &[
+                ("x-splitter-target-primary-status", "200"),
+                ("x-splitter-target-secondary-status", "200"),
+            ],
+            ..TestConfig::default()
+        },
     ]
 }
 
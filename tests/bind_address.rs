@@ -0,0 +1,49 @@
+use http_dragonfly::{config::ConfigFormat, testing::TestSplitter};
+use reqwest::Client;
+use std::net::IpAddr;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+#[tokio::test]
+async fn bind_address() {
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_addr = target.local_addr().unwrap();
+
+    let config = format!(
+        r#"
+        listeners:
+          - listen_on: "127.0.0.1:9978"
+            targets:
+              - id: mirror
+                url: "http://{target_addr}"
+                bind_address: [127.0.0.2, "::1"]
+        "#
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    let accepted = tokio::spawn(async move {
+        let (mut stream, peer) = target.accept().await.unwrap();
+        let _ = stream.read(&mut [0; 1024]).await.unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+            .await
+            .unwrap();
+        peer.ip()
+    });
+
+    let resp = Client::new()
+        .get("http://127.0.0.1:9978/")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    // Connection to IPv4 target is made from the bound IPv4 address
+    assert_eq!(
+        accepted.await.unwrap(),
+        "127.0.0.2".parse::<IpAddr>().unwrap()
+    );
+}
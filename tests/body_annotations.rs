@@ -0,0 +1,55 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/body-annotations.yaml";
+const TEST_PORT: u16 = 3043;
+
+#[tokio::test]
+async fn body_annotations() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "empty body",
+                port: 9932,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    (
+                        "x-body-sha256",
+                        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                    ),
+                    ("x-body-length", "0"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Values sent by client are replaced
+        let resp = client
+            .post("http://localhost:9932/")
+            .header("x-body-sha256", "fake")
+            .header("x-body-length", "100")
+            .body("hello")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "request with body");
+        assert_eq!(
+            resp.headers()["x-body-sha256"],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(resp.headers()["x-body-length"], "5");
+        assert_eq!(resp.text().await.unwrap(), "hello");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
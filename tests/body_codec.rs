@@ -0,0 +1,94 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn body_codecs() {
+    let urgent = MockTarget::start().await.unwrap();
+    let normal = MockTarget::start().await.unwrap();
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: auto
+            listen_on: "127.0.0.1:9976"
+            strategy: conditional_routing
+            body_codec:
+              protobuf:
+                schema: tests/protobuf/order.proto
+                message: Order
+            targets:
+              - id: urgent
+                url: "{urgent}"
+                condition: .body.priority == "URGENT"
+              - id: normal
+                url: "{normal}"
+                condition: default
+          - id: forced
+            listen_on: "127.0.0.1:9977"
+            strategy: conditional_routing
+            body_codec:
+              codec: form_urlencoded
+            targets:
+              - id: urgent
+                url: "{urgent}"
+                condition: .body.priority == "URGENT"
+              - id: normal
+                url: "{normal}"
+                condition: default
+        "#,
+        urgent = urgent.url(),
+        normal = normal.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let send = |port: u16, content_type: &str, body: Vec<u8>| {
+        client
+            .post(format!("http://127.0.0.1:{port}/orders"))
+            .header("content-type", content_type)
+            .body(body)
+            .send()
+    };
+
+    // {"priority": "URGENT"}
+    let msgpack = [&[0x81, 0xa8][..], b"priority", &[0xa6], b"URGENT"].concat();
+    send(9976, "application/msgpack", msgpack).await.unwrap();
+    send(
+        9976,
+        "application/x-www-form-urlencoded",
+        b"priority=URGENT".to_vec(),
+    )
+    .await
+    .unwrap();
+    // Order {id: "o-1", priority: URGENT} in gRPC frame
+    let order = [&[0x0a, 0x03][..], b"o-1", &[0x10, 0x01]].concat();
+    let grpc = [&[0, 0, 0, 0, order.len() as u8][..], &order].concat();
+    send(9976, "application/grpc", grpc).await.unwrap();
+    send(
+        9976,
+        "application/json",
+        br#"{"priority": "URGENT"}"#.to_vec(),
+    )
+    .await
+    .unwrap();
+    urgent.assert_received(4, Duration::from_secs(1)).await;
+
+    // Codec is forced regardless of content type
+    send(9977, "application/json", b"priority=URGENT".to_vec())
+        .await
+        .unwrap();
+    urgent.assert_received(5, Duration::from_secs(1)).await;
+    send(
+        9977,
+        "application/json",
+        br#"{"priority": "URGENT"}"#.to_vec(),
+    )
+    .await
+    .unwrap();
+    normal.assert_received(1, Duration::from_secs(1)).await;
+}
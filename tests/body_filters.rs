@@ -0,0 +1,74 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/body-filters.yaml";
+const TEST_PORT: u16 = 3036;
+
+/// Returns outcome of the `JSON` target from the explanation of the request
+async fn json_target_status(client: &Client, content_type: &str, body: String) -> String {
+    let resp = client
+        .post("http://localhost:9924/")
+        .header("content-type", content_type)
+        .header("x-splitter-explain", "true")
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let explanation: Value =
+        serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+    explanation["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|target| target["id"] == "JSON")
+        .map(|target| target["status"].as_str().unwrap().to_string())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn body_filters() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request without body",
+                port: 9924,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        assert_eq!(
+            json_target_status(&client, "application/json; charset=utf-8", "{}".into()).await,
+            "200",
+            "small JSON body"
+        );
+        assert_eq!(
+            json_target_status(&client, "text/plain", "{}".into()).await,
+            "filtered",
+            "not allowed content type"
+        );
+        assert_eq!(
+            json_target_status(
+                &client,
+                "application/json",
+                format!("\"{}\"", "x".repeat(64))
+            )
+            .await,
+            "filtered",
+            "too large body"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::Duration;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/cache.yaml";
+const TEST_PORT: u16 = 3005;
+
+/// Returns echoed `x-request-id` and `x-splitter-cache` headers of the response
+async fn cached_request(
+    client: &Client,
+    port: u16,
+    request_id: &str,
+    variant: &str,
+) -> (String, Option<String>) {
+    let resp = client
+        .get(format!("http://localhost:{port}/path?query=1"))
+        .header("x-request-id", request_id)
+        .header("x-variant", variant)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200, "request to port {port}");
+
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+    (header("x-request-id").unwrap(), header("x-splitter-cache"))
+}
+
+#[tokio::test]
+async fn response_cache() {
+    init_logging();
+    let _ = std::fs::remove_dir_all("target/tmp/test-cache-8401");
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        // Wait for the listeners
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "cache warm-up",
+                port: 8400,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        for port in [8400, 8401] {
+            let resp = cached_request(&client, port, "1", "a").await;
+            assert_eq!(resp, ("1".into(), None), "port {port}");
+            // Cached response of the first request
+            let resp = cached_request(&client, port, "2", "a").await;
+            assert_eq!(resp, ("1".into(), Some("hit".into())), "port {port}");
+            // Different value of the vary header
+            if port == 8400 {
+                let resp = cached_request(&client, port, "3", "b").await;
+                assert_eq!(resp, ("3".into(), None), "port {port}");
+            }
+        }
+
+        // Expired responses
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        for port in [8400, 8401] {
+            let resp = cached_request(&client, port, "4", "a").await;
+            assert_eq!(resp, ("4".into(), None), "port {port}");
+        }
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
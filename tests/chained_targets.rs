@@ -0,0 +1,48 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/chained-targets.yaml";
+const TEST_PORT: u16 = 3020;
+
+#[tokio::test]
+async fn chained_targets() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "primary is OK",
+                port: 9900,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    ("x-splitter-target-primary-status", "200"),
+                    ("x-splitter-target-fallback-status", "skipped"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client
+            .get("http://localhost:9900/")
+            .header("x-delay", "2")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "primary is timed out");
+        assert_eq!(
+            resp.headers()["x-splitter-target-primary-status"],
+            "timeout"
+        );
+        assert_eq!(resp.headers()["x-splitter-target-fallback-status"], "200");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
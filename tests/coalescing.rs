@@ -0,0 +1,92 @@
+use futures_util::future::join_all;
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn coalescing() {
+    let primary = MockTarget::start().await.unwrap();
+    primary.respond_with(
+        MockResponse::new(200)
+            .body("primary")
+            .delay(Duration::from_millis(300)),
+    );
+    let mirror = MockTarget::start().await.unwrap();
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: coalescing
+            listen_on: "127.0.0.1:9964"
+            strategy: always_target_id
+            targets:
+              - {{id: primary, url: "{primary}"}}
+              - {{id: mirror, url: "{mirror}"}}
+            response:
+              target_selector: primary
+            coalescing:
+              vary: [Accept]
+              paths: [/api/]
+        "#,
+        primary = primary.url(),
+        mirror = mirror.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let get = |path: &'static str, accept: &'static str| {
+        let url = format!("{}{path}", splitter.url("coalescing"));
+        let client = client.clone();
+        async move {
+            let resp = client
+                .get(url)
+                .header("accept", accept)
+                .send()
+                .await
+                .unwrap();
+            let coalesced = resp.headers().contains_key("x-splitter-coalesced");
+            (
+                resp.status().as_u16(),
+                coalesced,
+                resp.text().await.unwrap(),
+            )
+        }
+    };
+
+    // Identical requests share a single fan-out
+    let responses = join_all((0..5).map(|_| get("/api/users", "*/*"))).await;
+    assert!(responses
+        .iter()
+        .all(|(status, _, body)| *status == 200 && body == "primary"));
+    assert_eq!(
+        responses
+            .iter()
+            .filter(|(_, coalesced, _)| *coalesced)
+            .count(),
+        4
+    );
+    primary.assert_received(1, Duration::from_secs(1)).await;
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+
+    // Requests with different vary headers, or to other paths, aren't coalesced
+    primary.reset();
+    mirror.reset();
+    let responses = tokio::join!(
+        get("/api/users", "*/*"),
+        get("/api/users", "text/html"),
+        get("/health", "*/*"),
+        get("/health", "*/*")
+    );
+    assert!(!responses.0 .1 && !responses.1 .1 && !responses.2 .1 && !responses.3 .1);
+    primary.assert_received(4, Duration::from_secs(1)).await;
+
+    // Completed request doesn't share its response with the following ones
+    primary.reset();
+    let (_, coalesced, _) = get("/api/users", "*/*").await;
+    assert!(!coalesced);
+    primary.assert_received(1, Duration::from_secs(1)).await;
+}
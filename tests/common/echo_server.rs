@@ -1,8 +1,10 @@
-use http_body_util::{BodyExt, Full};
+use futures_util::stream;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
 use http_dragonfly::signal::SignalHandler;
-use hyper::body::{Bytes, Incoming};
+use hyper::body::{Bytes, Frame, Incoming};
+use hyper::header::{HeaderValue, TE, UPGRADE};
 use hyper::service::service_fn;
-use hyper::{Request, Response};
+use hyper::{HeaderMap, Request, Response, StatusCode};
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use hyper_util::server::conn::auto::Builder;
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
@@ -48,7 +50,7 @@ pub async fn echo_server(port: u16) -> Result<(), anyhow::Error> {
 
                     let serve_connection = async move {
                         let result = Builder::new(TokioExecutor::new())
-                            .serve_connection(TokioIo::new(stream), service_fn(handle_request))
+                            .serve_connection_with_upgrades(TokioIo::new(stream), service_fn(handle_request))
                             .await;
 
                         if let Err(e) = result {
@@ -127,7 +129,7 @@ pub async fn tls_echo_server(
                             }
                         };
                         if let Err(err) = Builder::new(TokioExecutor::new())
-                            .serve_connection(TokioIo::new(tls_stream), service)
+                            .serve_connection_with_upgrades(TokioIo::new(tls_stream), service)
                             .await
                         {
                             error!(error = ?err, address = %addr, "failed to serve tls connection");
@@ -144,8 +146,13 @@ pub async fn tls_echo_server(
     tokio::task::spawn(server).await?
 }
 
-async fn handle_request(req: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
-    let mut response = Response::new(Full::from(""));
+/// Interval between chunks of the streamed response
+const STREAM_CHUNK_INTERVAL: Duration = Duration::from_millis(300);
+
+async fn handle_request(
+    mut req: Request<Incoming>,
+) -> Result<Response<UnsyncBoxBody<Bytes, Infallible>>, Infallible> {
+    let mut response = Response::new(Full::from("").boxed_unsync());
     let echo_headers = response.headers_mut();
     let headers = req.headers();
     let path = req.uri().path();
@@ -155,6 +162,49 @@ async fn handle_request(req: Request<Incoming>) -> Result<Response<Full<Bytes>>,
     headers.iter().for_each(|(name, value)| {
         echo_headers.insert(name, value.clone());
     });
+    // Echo method and URI as seen by the target
+    echo_headers.insert(
+        "x-echo-method",
+        HeaderValue::from_str(req.method().as_str()).unwrap(),
+    );
+    if let Some(path_and_query) = req.uri().path_and_query() {
+        echo_headers.insert(
+            "x-echo-uri",
+            HeaderValue::from_str(path_and_query.as_str()).unwrap(),
+        );
+    }
+
+    // Switch protocol and echo raw data of upgraded connection
+    if headers.contains_key(UPGRADE) {
+        let upgrade = hyper::upgrade::on(&mut req);
+        tokio::spawn(async move {
+            match upgrade.await {
+                Ok(upgraded) => {
+                    let (mut reader, mut writer) = tokio::io::split(TokioIo::new(upgraded));
+                    let _ = tokio::io::copy(&mut reader, &mut writer).await;
+                }
+                Err(e) => error!(error = %e, "upgrade failed"),
+            }
+        });
+        *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+        return Ok(response);
+    }
+
+    // Stream N chunks if path is `/stream/N`, first one immediately and others with interval
+    if let Some(Ok(chunks)) = path.strip_prefix("/stream/").map(str::parse::<usize>) {
+        let body = stream::unfold(0, move |chunk| async move {
+            if chunk >= chunks {
+                return None;
+            }
+            if chunk > 0 {
+                tokio::time::sleep(STREAM_CHUNK_INTERVAL).await;
+            }
+            let frame = Frame::data(Bytes::from(format!("chunk-{chunk}\n")));
+            Some((Ok(frame), chunk + 1))
+        });
+        *response.body_mut() = StreamBody::new(body).boxed_unsync();
+        return Ok(response);
+    }
 
     // Delay response if path can be interpreted as number of seconds
     if path.len() > 1 {
@@ -165,13 +215,26 @@ async fn handle_request(req: Request<Incoming>) -> Result<Response<Full<Bytes>>,
     }
 
     // Echo whole body
+    let grpc_status = path
+        .strip_prefix("/grpc/")
+        .filter(|_| headers.get(TE).is_some_and(|te| te == "trailers"))
+        .map(str::to_string);
     let (_req_parts, req_body) = req.into_parts();
     let body_bytes = req_body
         .collect()
         .await
         .expect("Looks like a BUG!")
         .to_bytes();
-    *response.body_mut() = Full::from(body_bytes);
+    // Send `grpc-status` trailer if path is `/grpc/N` and client accepts trailers
+    if let Some(grpc_status) = grpc_status {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_str(&grpc_status).unwrap());
+        *response.body_mut() = Full::from(body_bytes)
+            .with_trailers(async move { Some(Ok(trailers)) })
+            .boxed_unsync();
+        return Ok(response);
+    }
+    *response.body_mut() = Full::from(body_bytes).boxed_unsync();
 
     Ok(response)
 }
@@ -199,5 +262,5 @@ fn load_private_key(filename: &str) -> io::Result<PrivateKeyDer<'static>> {
 }
 
 fn error(err: String) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, err)
+    io::Error::other(err)
 }
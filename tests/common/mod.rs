@@ -5,7 +5,11 @@ use futures_util::Future;
 use http_dragonfly::{cli::CliConfig, context::RootOsEnvironment};
 use hyper::header::HeaderValue;
 use reqwest::Client;
-use std::{env, sync::LazyLock, time::Duration};
+use std::{
+    env,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 const SERVER_CERT_BUNDLE: &str = "/end.crt";
 const SERVER_PRIVATE_KEY: &str = "/test-server.key";
@@ -18,6 +22,8 @@ pub struct TestConfig {
     pub include_good_target: bool,
     pub expected_status: u16,
     pub expected_x_target_id_header: Option<&'static str>,
+    pub expected_max_duration: Option<Duration>,
+    pub expected_headers: &'static [(&'static str, &'static str)],
 }
 
 impl Default for TestConfig {
@@ -30,6 +36,8 @@ impl Default for TestConfig {
             include_good_target: true,
             expected_status: 200,
             expected_x_target_id_header: Some("GOOD"),
+            expected_max_duration: None,
+            expected_headers: &[],
         }
     }
 }
@@ -82,6 +90,7 @@ async fn run_test_with_config_and_server(
     let timer = tokio::time::sleep(Duration::from_secs(timeout_sec));
 
     tokio::select! {
+        biased;
         _ = server => Err("http-dragonfly server has been crashed".into()),
         _ = echo_server => Err("echo server has been crashed".into()),
         _ = timer => Err("test has been timed out".into()),
@@ -101,7 +110,18 @@ pub async fn test_one_case(client: &Client, test_config: TestConfig) {
         req = req.header("x-include-good", "yes")
     }
 
+    let started = Instant::now();
     let resp = req.send().await.unwrap();
+    if let Some(max_duration) = test_config.expected_max_duration {
+        assert!(
+            started.elapsed() < max_duration,
+            "{}: request to port {}, expected to be completed in {:?}, but took {:?}",
+            test_config.description,
+            test_config.port,
+            max_duration,
+            started.elapsed()
+        );
+    }
     assert_eq!(
         resp.status().as_u16(),
         test_config.expected_status,
@@ -126,4 +146,15 @@ pub async fn test_one_case(client: &Client, test_config: TestConfig) {
         test_config.port,
         test_config.expected_x_target_id_header
     );
+
+    for (name, value) in test_config.expected_headers {
+        assert_eq!(
+            resp.headers().get(*name),
+            Some(&HeaderValue::from_static(value)),
+            "{}: request to port {}, expected `{}` header",
+            test_config.description,
+            test_config.port,
+            name
+        );
+    }
 }
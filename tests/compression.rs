@@ -0,0 +1,106 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/compression.yaml";
+const TEST_PORT: u16 = 3010;
+
+/// Returns `Content-Encoding` header and body of the response
+async fn post(
+    client: &Client,
+    port: u16,
+    body: &str,
+    accept_encoding: Option<&str>,
+) -> (Option<String>, Vec<u8>) {
+    post_typed(client, port, body, accept_encoding, "text/plain").await
+}
+
+/// Returns `Content-Encoding` header and body of the response to request with specified content type
+async fn post_typed(
+    client: &Client,
+    port: u16,
+    body: &str,
+    accept_encoding: Option<&str>,
+    content_type: &str,
+) -> (Option<String>, Vec<u8>) {
+    let mut req = client
+        .post(format!("http://localhost:{port}/"))
+        .header("content-type", content_type)
+        .body(body.to_string());
+    if let Some(accept_encoding) = accept_encoding {
+        req = req.header("accept-encoding", accept_encoding);
+    }
+    let resp = req.send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+
+    let encoding = resp
+        .headers()
+        .get("content-encoding")
+        .map(|v| v.to_str().unwrap().to_string());
+    (encoding, resp.bytes().await.unwrap().to_vec())
+}
+
+#[tokio::test]
+async fn compression() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        // Requests without body aren't compressed
+        for port in [8900, 8901, 8902] {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "request without body",
+                    port,
+                    expected_x_target_id_header: None,
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+
+        let body = "some text to compress ".repeat(100);
+
+        // Echoed compressed body is passed as is
+        let (encoding, resp_body) = post(&client, 8900, &body, None).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(resp_body.len() < body.len());
+
+        // Echoed compressed body is decompressed regardless of the requester's preferences
+        let (encoding, resp_body) = post(&client, 8901, &body, Some("deflate")).await;
+        assert_eq!(encoding, None);
+        assert_eq!(resp_body, body.as_bytes());
+
+        // Echoed compressed body is compressed back for the requester which accepts it only
+        let (encoding, resp_body) = post(&client, 8902, &body, None).await;
+        assert_eq!(encoding, None);
+        assert_eq!(resp_body, body.as_bytes());
+        let (encoding, resp_body) = post(&client, 8902, &body, Some("br, gzip")).await;
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(resp_body.len() < body.len());
+
+        // Final response is compressed with the first configured encoding accepted by the requester
+        let json = format!("[{}]", vec!["{\"key\": \"value\"}"; 100].join(","));
+        let (encoding, resp_body) =
+            post_typed(&client, 8903, &json, Some("gzip, br"), "application/json").await;
+        assert_eq!(encoding.as_deref(), Some("br"));
+        assert!(resp_body.len() < json.len());
+        let (encoding, _) = post_typed(&client, 8903, &json, Some("*"), "application/json").await;
+        assert_eq!(encoding.as_deref(), Some("zstd"));
+        let (encoding, resp_body) =
+            post_typed(&client, 8903, &json, None, "application/json").await;
+        assert_eq!(encoding, None);
+        assert_eq!(resp_body, json.as_bytes());
+        // Too small or not compressible responses are sent as is
+        let (encoding, _) = post_typed(&client, 8903, "[]", Some("gzip"), "application/json").await;
+        assert_eq!(encoding, None);
+        let (encoding, _) = post_typed(&client, 8903, &json, Some("gzip"), "image/png").await;
+        assert_eq!(encoding, None);
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/computed-context.yaml";
+const TEST_PORT: u16 = 3034;
+
+#[tokio::test]
+async fn computed_context() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "random int in the range",
+                port: 9921,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-random-int", "7")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Echo server reflects target's request headers
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let first = client.get("http://localhost:9921/").send().await.unwrap();
+        let second = client.get("http://localhost:9921/").send().await.unwrap();
+        assert_eq!(first.status(), 200);
+
+        let epoch_ms: u128 = first.headers()["x-epoch-ms"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(epoch_ms >= started.as_millis(), "{epoch_ms}");
+        let timestamp = first.headers()["x-timestamp"].to_str().unwrap();
+        assert!(
+            timestamp.len() == 24 && timestamp.ends_with('Z'),
+            "{timestamp}"
+        );
+        assert_eq!(first.headers()["x-random-uuid"].len(), 36);
+        assert_ne!(
+            first.headers()["x-random-uuid"],
+            second.headers()["x-random-uuid"]
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/concurrency.yaml";
+const TEST_PORT: u16 = 3040;
+
+#[tokio::test]
+async fn concurrency() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "response of the primary target",
+                port: 9928,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client
+            .get("http://localhost:9928/")
+            .header("x-splitter-explain", "true")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let explanation: Value =
+            serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+        let statuses: Vec<(&str, &str)> = explanation["targets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|target| {
+                (
+                    target["id"].as_str().unwrap(),
+                    target["status"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(statuses, [("GOOD", "200"), ("MIRROR", "shed")]);
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
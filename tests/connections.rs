@@ -0,0 +1,99 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use futures_util::future::join_all;
+use reqwest::Client;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/connections.yaml";
+const TEST_PORT: u16 = 3028;
+
+/// Sends request and reads its response head, the echoed body is empty
+async fn request(stream: &mut TcpStream) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte).await.unwrap();
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap().to_lowercase()
+}
+
+/// Returns `true` if connection is closed by the listener during the timeout
+async fn is_closed(stream: &mut TcpStream, timeout: Duration) -> bool {
+    let mut buf = [0u8; 16];
+    matches!(
+        tokio::time::timeout(timeout, stream.read(&mut buf)).await,
+        Ok(Ok(0)) | Ok(Err(_))
+    )
+}
+
+#[tokio::test]
+async fn connections() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        test_one_case(
+            &Client::new(),
+            TestConfig {
+                description: "request is forwarded",
+                port: 9915,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let mut stream = TcpStream::connect("localhost:9915").await.unwrap();
+        let head = request(&mut stream).await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(!head.contains("connection: close"), "{head}");
+        let head = request(&mut stream).await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(head.contains("connection: close"), "{head}");
+        assert!(
+            is_closed(&mut stream, Duration::from_secs(1)).await,
+            "connection is closed after max requests"
+        );
+
+        let mut stream = TcpStream::connect("localhost:9916").await.unwrap();
+        let head = request(&mut stream).await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(
+            is_closed(&mut stream, Duration::from_secs(2)).await,
+            "idle connection is closed"
+        );
+
+        // Each of the idle connections sees the same excess, but only the excess ones are closed
+        let mut streams = vec![];
+        for _ in 0..4 {
+            let mut stream = TcpStream::connect("localhost:9986").await.unwrap();
+            let head = request(&mut stream).await;
+            assert!(head.starts_with("http/1.1 200"), "{head}");
+            streams.push(stream);
+        }
+        let closed = join_all(
+            streams
+                .iter_mut()
+                .map(|stream| is_closed(stream, Duration::from_secs(3))),
+        )
+        .await;
+        assert_eq!(
+            closed.iter().filter(|closed| **closed).count(),
+            2,
+            "excess idle connections are shed"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
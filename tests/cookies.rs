@@ -0,0 +1,43 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/cookies.yaml";
+const TEST_PORT: u16 = 3018;
+
+#[tokio::test]
+async fn cookies() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request without cookies",
+                port: 9700,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Echo server returns request headers as response ones
+        let resp = client
+            .get("http://localhost:9700/")
+            .header("cookie", "session=prod; theme=dark; staging_session=stage")
+            .header("set-cookie", "session=stage")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "cookies transformations");
+        assert_eq!(resp.headers()["cookie"], "theme=dark; session=stage");
+        assert!(!resp.headers().contains_key("set-cookie"));
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, Method, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/cors.yaml";
+const TEST_PORT: u16 = 3014;
+const URL: &str = "http://localhost:9300/";
+const ORIGIN: &str = "https://app.example.com";
+
+#[tokio::test]
+async fn cors() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "not cross-origin request",
+                port: 9300,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Preflight is answered by listener even if OPTIONS isn't allowed
+        let resp = client
+            .request(Method::OPTIONS, URL)
+            .header("origin", ORIGIN)
+            .header("access-control-request-method", "PUT")
+            .header("access-control-request-headers", "x-custom, content-type")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT, "preflight");
+        let headers = resp.headers();
+        assert_eq!(headers["access-control-allow-origin"], ORIGIN);
+        assert_eq!(headers["access-control-allow-methods"], "GET, POST, PUT");
+        assert_eq!(
+            headers["access-control-allow-headers"],
+            "x-custom, content-type"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-max-age"], "600");
+
+        let resp = client
+            .request(Method::OPTIONS, URL)
+            .header("origin", "https://evil.example.com")
+            .header("access-control-request-method", "GET")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::FORBIDDEN,
+            "preflight, wrong origin"
+        );
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+
+        let resp = client
+            .request(Method::OPTIONS, URL)
+            .header("origin", ORIGIN)
+            .header("access-control-request-method", "DELETE")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::FORBIDDEN,
+            "preflight, wrong method"
+        );
+
+        // Actual request is forwarded to targets, CORS headers are added to the response
+        let resp = client
+            .get(URL)
+            .header("origin", ORIGIN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "cross-origin request");
+        let headers = resp.headers();
+        assert_eq!(headers["access-control-allow-origin"], ORIGIN);
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-expose-headers"], "x-request-id");
+
+        let resp = client
+            .get(URL)
+            .header("origin", "https://evil.example.com")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::OK,
+            "cross-origin request, wrong origin"
+        );
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
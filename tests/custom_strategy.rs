@@ -0,0 +1,66 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    strategy::{self, CustomStrategy, StrategyDecision, StrategyTarget},
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+
+/// Responds with the cheapest successful target, or 503 if all targets are failed
+struct PreferCheapestRegion;
+
+impl CustomStrategy for PreferCheapestRegion {
+    fn select(&self, targets: &[StrategyTarget]) -> StrategyDecision {
+        targets
+            .iter()
+            .filter(|target| !target.failed)
+            .min_by_key(|target| target.labels.get("cost"))
+            .map(|target| StrategyDecision::Target(target.id.to_string()))
+            .unwrap_or(StrategyDecision::Status(503))
+    }
+}
+
+#[tokio::test]
+async fn prefer_cheapest_region() {
+    strategy::register("prefer-cheapest-region", PreferCheapestRegion);
+
+    let expensive = MockTarget::start().await.unwrap();
+    let cheap = MockTarget::start().await.unwrap();
+    expensive.respond_with(MockResponse::new(200).body("eu-west"));
+    cheap.respond_with(MockResponse::new(200).body("us-east"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: custom
+            listen_on: "127.0.0.1:9939"
+            strategy: custom
+            targets:
+              - {{id: eu-west, url: "{expensive}", labels: {{cost: "2"}}}}
+              - {{id: us-east, url: "{cheap}", labels: {{cost: "1"}}}}
+            response:
+              custom_strategy: prefer-cheapest-region
+        "#,
+        expensive = expensive.url(),
+        cheap = cheap.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // The cheapest region wins while it's healthy
+    let resp = client.get(splitter.url("custom")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.text().await.unwrap(), "us-east");
+
+    // Failed targets are skipped
+    cheap.respond_with(MockResponse::new(500).body("us-east"));
+    let resp = client.get(splitter.url("custom")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.text().await.unwrap(), "eu-west");
+
+    // Status decision if nothing is suitable
+    expensive.respond_with(MockResponse::new(502));
+    let resp = client.get(splitter.url("custom")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 503);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::Duration;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/deduplication.yaml";
+const TEST_PORT: u16 = 3006;
+const TEST_LISTENER: u16 = 8500;
+
+/// Returns echoed `x-request-id` and `x-splitter-deduplicated` headers of the response
+async fn keyed_request(
+    client: &Client,
+    path: &str,
+    key: &str,
+    request_id: &str,
+) -> (String, Option<String>) {
+    let resp = client
+        .post(format!("http://localhost:{TEST_LISTENER}{path}"))
+        .header("idempotency-key", key)
+        .header("x-request-id", request_id)
+        .body(request_id.to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+
+    let header = |name| {
+        resp.headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+    };
+    (
+        header("x-request-id").unwrap(),
+        header("x-splitter-deduplicated"),
+    )
+}
+
+#[tokio::test]
+async fn deduplication() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        // Requests without key aren't deduplicated
+        for _ in 0..2 {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "request without idempotency key",
+                    port: TEST_LISTENER,
+                    expected_x_target_id_header: None,
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+
+        let resp = keyed_request(&client, "/", "key-1", "1").await;
+        assert_eq!(resp, ("1".into(), None));
+        let resp = keyed_request(&client, "/", "key-1", "2").await;
+        assert_eq!(resp, ("1".into(), Some("hit".into())));
+        let resp = keyed_request(&client, "/", "key-2", "3").await;
+        assert_eq!(resp, ("3".into(), None));
+
+        // Concurrent requests with the same key get the same response
+        let (first, second) = tokio::join!(
+            keyed_request(&client, "/1", "key-3", "4"),
+            keyed_request(&client, "/1", "key-3", "5")
+        );
+        assert_eq!(first.0, second.0);
+        assert!(first.1.is_some() ^ second.1.is_some());
+
+        // Window is over
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let resp = keyed_request(&client, "/", "key-1", "6").await;
+        assert_eq!(resp, ("6".into(), None));
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,53 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn deprecated_targets() {
+    let current = MockTarget::start().await.unwrap();
+    let legacy = MockTarget::start().await.unwrap();
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: legacy
+            listen_on: "127.0.0.1:9965"
+            strategy: always_target_id
+            targets:
+              - {{id: legacy, url: "{legacy}", sunset: 2030-01-15, deprecation_headers: true}}
+              - {{id: current, url: "{current}"}}
+            response:
+              target_selector: legacy
+          - id: current
+            listen_on: "127.0.0.1:9966"
+            strategy: always_target_id
+            targets:
+              - {{id: legacy, url: "{legacy}", deprecated: true}}
+              - {{id: current, url: "{current}"}}
+            response:
+              target_selector: current
+        "#,
+        current = current.url(),
+        legacy = legacy.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Deprecated target is still requested, its response is marked
+    let resp = client.get(splitter.url("legacy")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.headers()["deprecation"], "true");
+    assert_eq!(resp.headers()["sunset"], "Tue, 15 Jan 2030 00:00:00 GMT");
+
+    let resp = client.get(splitter.url("current")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert!(!resp.headers().contains_key("deprecation"));
+
+    legacy.assert_received(2, Duration::from_secs(1)).await;
+    current.assert_received(2, Duration::from_secs(1)).await;
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/discovery.yaml";
+const TEST_PORT: u16 = 3013;
+
+#[tokio::test]
+async fn discovery() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "instances from file",
+                port: 9200,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-splitter-target-good-status", "200")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "no instances",
+                port: 9201,
+                expected_status: 503,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-splitter-target-good-status", "unavailable")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // The first instance fails, so it's skipped by the next requests
+        client.get("http://localhost:9202/").send().await.unwrap();
+        for _ in 0..3 {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "static instances",
+                    port: 9202,
+                    expected_x_target_id_header: None,
+                    expected_headers: &[("x-splitter-target-good-status", "200")],
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/dns.yaml";
+const TEST_PORT: u16 = 3041;
+
+#[tokio::test]
+async fn dns() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        // Second request gets cached failure
+        for _ in 0..2 {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "unresolved target is failed",
+                    port: 9929,
+                    expected_status: 502,
+                    expected_x_target_id_header: Some("UNRESOLVED"),
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "unresolved target is skipped",
+                port: 9930,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client
+            .get("http://localhost:9930/")
+            .header("x-splitter-explain", "true")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let explanation: Value =
+            serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+        assert!(explanation["targets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|target| target["id"] == "UNRESOLVED" && target["status"] == "unresolved"));
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
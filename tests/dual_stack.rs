@@ -0,0 +1,42 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/dual_stack.yaml";
+const TEST_PORT: u16 = 3012;
+
+#[tokio::test]
+async fn dual_stack() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "IPv4 is preferred",
+                port: 9100,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "IPv6 is preferred, fallback to IPv4",
+                port: 9101,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,222 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// Redis stub which replies to `GET tenant:<name>` with region of the tenant, other keys are missing;
+/// commands are accepted after `AUTH` of the ACL user only; accepted connections are counted
+async fn start_redis() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                let mut authenticated = false;
+                while let Some(args) = read_command(&mut stream).await {
+                    let reply = match args[0].as_str() {
+                        "AUTH" if args[1..] == ["app@eu", "p@ss:w0rd"] => {
+                            authenticated = true;
+                            "+OK\r\n"
+                        }
+                        "AUTH" => "-WRONGPASS invalid username-password pair\r\n",
+                        _ if !authenticated => "-NOAUTH Authentication required.\r\n",
+                        "GET" if args[1] == "tenant:acme" => "$2\r\neu\r\n",
+                        _ => "$-1\r\n",
+                    };
+                    stream.write_all(reply.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    });
+
+    (format!("redis://app%40eu:p%40ss:w0rd@{addr}"), connections)
+}
+
+/// Reads command in RESP format: array header, then length and value of each argument
+async fn read_command(stream: &mut BufReader<TcpStream>) -> Option<Vec<String>> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await.ok()?;
+    let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut args = vec![];
+    for _ in 0..count {
+        for _ in 0..2 {
+            line.clear();
+            stream.read_line(&mut line).await.ok()?;
+        }
+        args.push(line.trim_end().to_string());
+    }
+
+    Some(args)
+}
+
+#[tokio::test]
+async fn enrichment() {
+    let mapping = MockTarget::start().await.unwrap();
+    mapping.respond_with(MockResponse::new(200).body(r#"{"region": "eu", "tier": "gold"}"#));
+    let eu = MockTarget::start().await.unwrap();
+    let us = MockTarget::start().await.unwrap();
+    let (redis, redis_connections) = start_redis().await;
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: http
+            listen_on: "127.0.0.1:9974"
+            strategy: conditional_routing
+            enrichment:
+              key:
+                header: x-tenant
+              http: "{mapping}/tenants/{{key}}"
+              cache_ttl: 1m
+              max_value_size: 1024
+            headers:
+              - add: x-tier
+                value: ${{CTX_ENRICHMENT_TIER}}
+              - add: x-tenant-info
+                value: ${{CTX_ENRICHMENT_VALUE}}
+            targets:
+              - id: eu
+                url: "{eu}"
+                condition: .env.CTX_ENRICHMENT_REGION == "eu"
+              - id: us
+                url: "{us}"
+                condition: default
+          - id: redis
+            listen_on: "127.0.0.1:9975"
+            strategy: conditional_routing
+            enrichment:
+              key:
+                jq: .tenant
+              redis:
+                url: "{redis}"
+                key: "tenant:{{key}}"
+            targets:
+              - id: eu
+                url: "{eu}"
+                condition: .env.CTX_ENRICHMENT_VALUE == "eu"
+              - id: us
+                url: "{us}"
+                condition: default
+        "#,
+        mapping = mapping.url(),
+        eu = eu.url(),
+        us = us.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Looked up values are used by conditions and headers, and they are cached
+    for _ in 0..2 {
+        client
+            .get("http://127.0.0.1:9974/orders")
+            .header("x-tenant", "acme corp")
+            .send()
+            .await
+            .unwrap();
+    }
+    eu.assert_received(2, Duration::from_secs(1)).await;
+    let received = eu.received().pop().unwrap();
+    assert_eq!(received.headers["x-tier"], "gold");
+    assert_eq!(
+        received.headers["x-tenant-info"],
+        r#"{"region": "eu", "tier": "gold"}"#
+    );
+    let lookups = mapping.received();
+    assert_eq!(lookups.len(), 1);
+    assert_eq!(lookups[0].uri, "/tenants/acme+corp");
+
+    // Request without key isn't enriched
+    client
+        .get("http://127.0.0.1:9974/orders")
+        .send()
+        .await
+        .unwrap();
+    us.assert_received(1, Duration::from_secs(1)).await;
+    assert_eq!(mapping.received().len(), 1);
+
+    // Concurrent lookups of the same key are coalesced
+    mapping.respond_with(
+        MockResponse::new(200)
+            .body(r#"{"region": "us"}"#)
+            .delay(Duration::from_millis(200)),
+    );
+    let request = || {
+        client
+            .get("http://127.0.0.1:9974/orders")
+            .header("x-tenant", "globex")
+            .send()
+    };
+    let (first, second, third) = tokio::join!(request(), request(), request());
+    for resp in [first, second, third] {
+        assert_eq!(resp.unwrap().status().as_u16(), 200);
+    }
+    us.assert_received(4, Duration::from_secs(1)).await;
+    assert_eq!(mapping.received().len(), 2);
+
+    // Looked up values which aren't valid header values are skipped by header transforms
+    mapping.respond_with(
+        MockResponse::new(200).body("{\n  \"region\": \"eu\",\n  \"tier\": \"gold\\nplatinum\"\n}"),
+    );
+    let resp = client
+        .get("http://127.0.0.1:9974/orders")
+        .header("x-tenant", "initech")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    eu.assert_received(3, Duration::from_secs(1)).await;
+    let received = eu.received().pop().unwrap();
+    assert!(!received.headers.contains_key("x-tier"));
+    assert!(!received.headers.contains_key("x-tenant-info"));
+
+    // Lookup response above the limit is a failure, so request isn't enriched
+    mapping.respond_with(MockResponse::new(200).body(format!(
+        r#"{{"region": "eu", "padding": "{}"}}"#,
+        "x".repeat(2048)
+    )));
+    client
+        .get("http://127.0.0.1:9974/orders")
+        .header("x-tenant", "hooli")
+        .send()
+        .await
+        .unwrap();
+    us.assert_received(5, Duration::from_secs(1)).await;
+
+    // Key is taken from the body and looked up in Redis
+    client
+        .post("http://127.0.0.1:9975/orders")
+        .body(r#"{"tenant": "acme"}"#)
+        .send()
+        .await
+        .unwrap();
+    eu.assert_received(4, Duration::from_secs(1)).await;
+    client
+        .post("http://127.0.0.1:9975/orders")
+        .body(r#"{"tenant": "other"}"#)
+        .send()
+        .await
+        .unwrap();
+    us.assert_received(6, Duration::from_secs(1)).await;
+
+    // Redis connection is reused by the next lookups
+    assert_eq!(redis_connections.load(Ordering::SeqCst), 1);
+}
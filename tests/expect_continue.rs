@@ -0,0 +1,73 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/expect-continue.yaml";
+const TEST_PORT: u16 = 3027;
+const LISTENER_ADDR: &str = "localhost:9914";
+
+/// Reads response head or interim response, up to the empty line
+async fn read_head(stream: &mut TcpStream) -> String {
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte).await.unwrap();
+        head.push(byte[0]);
+    }
+    String::from_utf8(head).unwrap().to_lowercase()
+}
+
+async fn send_head(method: &str, expect: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(LISTENER_ADDR).await.unwrap();
+    let head = format!(
+        "{method} / HTTP/1.1\r\nhost: {LISTENER_ADDR}\r\ncontent-length: 4\r\nexpect: {expect}\r\n\r\n"
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn expect_continue() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        test_one_case(
+            &Client::new(),
+            TestConfig {
+                description: "not allowed method is rejected",
+                port: 9914,
+                expected_status: 405,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Rejected request gets final response instead of `100 Continue`
+        let mut stream = send_head("GET", "100-continue").await;
+        let head = read_head(&mut stream).await;
+        assert!(head.starts_with("http/1.1 405"), "{head}");
+
+        let mut stream = send_head("POST", "something-else").await;
+        let head = read_head(&mut stream).await;
+        assert!(head.starts_with("http/1.1 417"), "{head}");
+
+        // Accepted request gets `100 Continue` and expectation isn't forwarded to the target
+        let mut stream = send_head("POST", "100-continue").await;
+        let head = read_head(&mut stream).await;
+        assert!(head.starts_with("http/1.1 100"), "{head}");
+        stream.write_all(b"BODY").await.unwrap();
+        let head = read_head(&mut stream).await;
+        assert!(head.starts_with("http/1.1 200"), "{head}");
+        assert!(!head.contains("\r\nexpect:"), "{head}");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
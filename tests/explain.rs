@@ -0,0 +1,55 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::{json, Value};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/explain.yaml";
+const TEST_PORT: u16 = 3031;
+
+#[tokio::test]
+async fn explain() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request isn't explained without header",
+                port: 9919,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        let resp = client.get("http://localhost:9919/").send().await.unwrap();
+        assert!(resp.headers().get("x-splitter-explain").is_none());
+
+        let resp = client
+            .get("http://localhost:9919/")
+            .header("x-splitter-explain", "true")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let explanation: Value =
+            serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+        assert_eq!(explanation["strategy"], "conditional_routing");
+        assert_eq!(
+            explanation["conditions"],
+            json!([
+                {"target": "ADMIN", "result": "false"},
+                {"target": "GOOD", "result": "default"}
+            ])
+        );
+        assert_eq!(explanation["targets"][0]["id"], "GOOD");
+        assert_eq!(explanation["targets"][0]["status"], "200");
+        assert_eq!(explanation["selected"], "GOOD");
+        assert_eq!(explanation["reason"], "target routed by condition");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/failure-policy.yaml";
+const TEST_PORT: u16 = 3044;
+
+#[tokio::test]
+async fn failure_policy() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "slow response is failed",
+                port: 9933,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "listed status is failed",
+                port: 9934,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use http_dragonfly::config::faults::FaultsConfig;
+use reqwest::Client;
+use std::time::{Duration, Instant};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/faults.yaml";
+const TEST_PORT: u16 = 3007;
+
+#[tokio::test]
+async fn faults() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "listener's abort fault",
+                port: 8600,
+                expected_status: 503,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-splitter-fault", "abort")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let started = Instant::now();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "listener's delay fault",
+                port: 8601,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(1000)),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        assert!(started.elapsed() >= Duration::from_millis(500));
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "target's abort fault",
+                port: 8602,
+                expected_status: 502,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-splitter-fault", "abort")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "target's delay fault longer than timeout",
+                port: 8603,
+                expected_status: 504,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(1500)),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Faults are switched off in runtime
+        FaultsConfig::set_enabled(false);
+        for port in [8600, 8601, 8602, 8603] {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "disabled faults",
+                    port,
+                    expected_x_target_id_header: None,
+                    expected_max_duration: Some(Duration::from_millis(500)),
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+        FaultsConfig::set_enabled(true);
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
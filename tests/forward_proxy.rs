@@ -0,0 +1,113 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::{Certificate, Client, Proxy};
+use std::time::Duration;
+
+const CERTS_DIR: &str = env!("OUT_DIR");
+
+#[tokio::test]
+async fn forward_proxy() {
+    let primary = MockTarget::start().await.unwrap();
+    primary.respond_with(MockResponse::new(200).body("primary"));
+    let mirror = MockTarget::start().await.unwrap();
+    let origin = MockTarget::start().await.unwrap();
+    origin.respond_with(MockResponse::new(200).body("origin"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: proxy
+            listen_on: "127.0.0.1:9969"
+            strategy: always_target_id
+            forward_proxy:
+              hosts: [localhost]
+              tunnel_tls:
+                cert: "{CERTS_DIR}/test-server.pem"
+                key: "{CERTS_DIR}/test-server.key"
+            targets:
+              - id: primary
+                url: "{primary}"
+                forward_path: true
+                headers:
+                  - add: x-origin
+                    value: ${{CTX_REQUEST_SCHEME}}://${{CTX_REQUEST_AUTHORITY}}
+              - id: mirror
+                url: "{mirror}"
+                forward_path: true
+            response:
+              target_selector: primary
+          - id: origin
+            listen_on: "127.0.0.1:9970"
+            strategy: always_target_id
+            forward_proxy:
+              hosts: ["127.0.0.1"]
+              connect: false
+            targets:
+              - id: origin
+                url: "http://${{CTX_REQUEST_AUTHORITY}}"
+                forward_path: true
+            response:
+              target_selector: origin
+        "#,
+        primary = primary.url(),
+        mirror = mirror.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let ca = std::fs::read(format!("{CERTS_DIR}/ca.crt")).unwrap();
+    let client = Client::builder()
+        .proxy(
+            Proxy::all("http://127.0.0.1:9969")
+                .unwrap()
+                .basic_auth("user", "secret"),
+        )
+        .add_root_certificate(Certificate::from_pem(&ca).unwrap())
+        .build()
+        .unwrap();
+
+    // Request in absolute form is split to all targets, credentials of the proxy aren't forwarded
+    let resp = client
+        .get("http://localhost:12345/plain?page=1")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "primary");
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+    let received = primary.received().pop().unwrap();
+    assert_eq!(received.uri, "/plain?page=1");
+    assert_eq!(received.headers["x-origin"], "http://localhost:12345");
+    assert!(!received.headers.contains_key("proxy-authorization"));
+
+    // TLS inside the tunnel is terminated, so requests are split as plain ones
+    let resp = client
+        .get("https://localhost:12345/secure")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "primary");
+    mirror.assert_received(2, Duration::from_secs(1)).await;
+    let received = primary.received().pop().unwrap();
+    assert_eq!(received.uri, "/secure");
+    assert_eq!(received.headers["x-origin"], "https://localhost:12345");
+
+    // Destinations which aren't allowed are rejected
+    let resp = client.get("http://127.0.0.1:12345/").send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 403);
+    assert!(client.get("https://127.0.0.1:12345/").send().await.is_err());
+
+    // Target's URL may point to the original destination of the request
+    let client = Client::builder()
+        .proxy(Proxy::all("http://127.0.0.1:9970").unwrap())
+        .build()
+        .unwrap();
+    let resp = client
+        .get(format!("{}/api", origin.url().trim_end_matches('/')))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "origin");
+    assert_eq!(origin.received().pop().unwrap().uri, "/api");
+}
@@ -0,0 +1,93 @@
+use futures_util::stream;
+use http_body_util::{BodyExt, StreamBody};
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use hyper::{
+    body::{Bytes, Frame},
+    HeaderMap, Request,
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::TokioExecutor,
+};
+use std::{convert::Infallible, time::Duration};
+
+/// Sends chunked upload with the checksum trailer
+async fn upload(url: String) -> u16 {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("x-checksum", "abc123".parse().unwrap());
+    let frames = vec![
+        Ok::<_, Infallible>(Frame::data(Bytes::from("chunked "))),
+        Ok(Frame::data(Bytes::from("upload"))),
+        Ok(Frame::trailers(trailers)),
+    ];
+    let req = Request::post(url)
+        .header("trailer", "x-checksum")
+        .header("connection", "x-hop")
+        .header("x-hop", "yes")
+        .header("keep-alive", "timeout=5")
+        .body(StreamBody::new(stream::iter(frames)).boxed())
+        .unwrap();
+
+    let client = Client::builder(TokioExecutor::new()).build(HttpConnector::new());
+    client.request(req).await.unwrap().status().as_u16()
+}
+
+#[tokio::test]
+async fn trailers_and_hop_by_hop_headers() {
+    let primary = MockTarget::start().await.unwrap();
+    let mirror = MockTarget::start().await.unwrap();
+    primary.respond_with(MockResponse::new(200));
+    mirror.respond_with(MockResponse::new(202));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: compliant
+            listen_on: "127.0.0.1:9951"
+            strategy: always_target_id
+            forwarding:
+              trailers: true
+            targets:
+              - {{id: primary, url: "{primary}"}}
+              - {{id: mirror, url: "{mirror}"}}
+            response:
+              target_selector: primary
+          - id: as-is
+            listen_on: "127.0.0.1:9952"
+            strategy: always_target_id
+            targets:
+              - {{id: primary, url: "{primary}"}}
+            response:
+              target_selector: primary
+        "#,
+        primary = primary.url(),
+        mirror = mirror.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    // Trailers are forwarded to all targets, hop-by-hop headers aren't
+    assert_eq!(upload(splitter.url("compliant")).await, 200);
+    primary.assert_received(1, Duration::from_secs(1)).await;
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+    for target in [&primary, &mirror] {
+        let received = target.received().pop().unwrap();
+        assert_eq!(received.body, "chunked upload");
+        assert_eq!(received.trailers.unwrap()["x-checksum"], "abc123");
+        assert_eq!(received.headers["trailer"], "x-checksum");
+        assert_eq!(received.headers.get("x-hop"), None);
+        assert_eq!(received.headers.get("keep-alive"), None);
+    }
+
+    // Without forwarding policy trailers are dropped
+    primary.reset();
+    assert_eq!(upload(splitter.url("as-is")).await, 200);
+    primary.assert_received(1, Duration::from_secs(1)).await;
+    let received = primary.received().pop().unwrap();
+    assert_eq!(received.body, "chunked upload");
+    assert_eq!(received.trailers, None);
+}
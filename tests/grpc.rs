@@ -0,0 +1,72 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Bytes, header::TE, Request};
+use hyper_util::{client::legacy::Client, rt::TokioExecutor};
+use std::time::Duration;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/grpc.yaml";
+const TEST_PORT: u16 = 3004;
+
+/// Sends HTTP/2 request and returns target ID, `grpc-status` trailer and body
+async fn grpc_request(port: u16) -> (String, String, Bytes) {
+    let client = Client::builder(TokioExecutor::new())
+        .http2_only(true)
+        .build_http();
+    let resp = loop {
+        let req = Request::post(format!("http://localhost:{port}/test.Service/Method"))
+            .header(TE, "trailers")
+            .header("content-type", "application/grpc")
+            .body(Full::<Bytes>::from("grpc-message"))
+            .unwrap();
+        match client.request(req).await {
+            Ok(resp) => break resp,
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    };
+    assert_eq!(resp.status().as_u16(), 200, "request to port {port}");
+
+    let target_id = resp.headers()["x-target-id"].to_str().unwrap().to_string();
+    let body = resp.into_body().collect().await.unwrap();
+    let grpc_status = body
+        .trailers()
+        .and_then(|trailers| trailers.get("grpc-status"))
+        .map(|status| status.to_str().unwrap().to_string())
+        .unwrap_or_default();
+
+    (target_id, grpc_status, body.to_bytes())
+}
+
+#[tokio::test]
+async fn grpc_trailers() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let (target_id, grpc_status, body) = grpc_request(8300).await;
+        assert_eq!(target_id, "FAILED");
+        assert_eq!(grpc_status, "13");
+        assert_eq!(body, "grpc-message");
+
+        let (target_id, grpc_status, body) = grpc_request(8301).await;
+        assert_eq!(target_id, "OK");
+        assert_eq!(grpc_status, "0");
+        assert_eq!(body, "grpc-message");
+
+        // HTTP/1 requests are handled as usual
+        test_one_case(
+            &reqwest::Client::new(),
+            TestConfig {
+                description: "http/1 request to http/2 targets",
+                port: 8301,
+                expected_x_target_id_header: Some("OK"),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,71 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn mock_targets() {
+    let primary = MockTarget::start().await.unwrap();
+    let mirror = MockTarget::start().await.unwrap();
+    primary.respond_with(MockResponse::new(503).body("primary"));
+    mirror.respond_with(
+        MockResponse::new(200)
+            .header("x-mirror", "yes")
+            .body("mirror")
+            .delay(Duration::from_millis(100)),
+    );
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: selected
+            listen_on: "127.0.0.1:9937"
+            strategy: always_target_id
+            targets:
+              - {{id: primary, url: "{primary}"}}
+              - {{id: mirror, url: "{mirror}"}}
+            response:
+              target_selector: primary
+          - id: first-ok
+            listen_on: "127.0.0.1:9938"
+            strategy: ok_then_failed
+            targets:
+              - {{id: primary, url: "{primary}"}}
+              - {{id: mirror, url: "{mirror}"}}
+        "#,
+        primary = primary.url(),
+        mirror = mirror.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Selected target responds, but request is sent to all targets
+    let resp = client
+        .post(splitter.url("selected"))
+        .body("payload")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 503);
+    assert_eq!(resp.text().await.unwrap(), "primary");
+    primary.assert_received(1, Duration::from_secs(1)).await;
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+    let received = mirror.received().pop().unwrap();
+    assert_eq!(received.method, "POST");
+    assert_eq!(received.uri.path(), "/");
+    assert_eq!(received.body, "payload");
+
+    // The first successful response wins even if it's slower
+    primary.reset();
+    mirror.reset();
+    let resp = client.get(splitter.url("first-ok")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.headers()["x-mirror"], "yes");
+    assert_eq!(resp.text().await.unwrap(), "mirror");
+    primary.assert_received(1, Duration::from_secs(1)).await;
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+}
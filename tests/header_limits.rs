@@ -0,0 +1,58 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/header-limits.yaml";
+const TEST_PORT: u16 = 3016;
+
+#[tokio::test]
+async fn header_limits() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        for port in [9500, 9501] {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "headers within limits",
+                    port,
+                    expected_x_target_id_header: None,
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+
+        let mut req = client.get("http://localhost:9500/");
+        for i in 0..5 {
+            req = req.header(format!("x-extra-{i}"), "value");
+        }
+        let resp = req.send().await.unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            "too many request headers"
+        );
+
+        // Response of the rejected target request is processed by strategy as usual
+        let resp = client
+            .get("http://localhost:9501/")
+            .header("x-include-good", "yes")
+            .header("x-large", "x".repeat(128))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            "too large headers to forward"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::Duration;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/hooks.yaml";
+const TEST_PORT: u16 = 3046;
+const HOOK_OUTPUT_PATH: &str = "target/hooks-startup.env";
+
+#[tokio::test]
+async fn startup_hook() {
+    init_logging();
+    let _ = std::fs::remove_file(HOOK_OUTPUT_PATH);
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "listener with startup hook",
+                port: 9936,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Hook is called in background, so wait for its output
+        let mut output = String::new();
+        for _ in 0..50 {
+            output = std::fs::read_to_string(HOOK_OUTPUT_PATH).unwrap_or_default();
+            if output.contains("HTTP_DRAGONFLY_LISTENERS=") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(output.contains("HTTP_DRAGONFLY_EVENT=startup"));
+        assert!(output.contains("HTTP_DRAGONFLY_LISTENERS=hooks-9936=0.0.0.0:9936"));
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/host-header.yaml";
+const TEST_PORT: u16 = 3017;
+
+#[tokio::test]
+async fn host_header() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "custom host header",
+                port: 9601,
+                expected_x_target_id_header: None,
+                expected_headers: &[("host", "GOOD.example.com")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Echo server streams chunks if path is `/stream/N`
+        let resp = client
+            .get("http://localhost:9600/stream/2?q=1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "original path and host");
+        assert_eq!(resp.headers()["host"], "localhost:9600");
+        assert_eq!(resp.text().await.unwrap(), "chunk-0\nchunk-1\n");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
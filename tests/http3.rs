@@ -0,0 +1,92 @@
+use bytes::{BufMut, Bytes};
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use hyper::Request;
+use quinn::{crypto::rustls::QuicClientConfig, ClientConfig, Endpoint};
+use reqwest::{Certificate, Client};
+use rustls::RootCertStore;
+use std::sync::Arc;
+
+const CERTS_DIR: &str = env!("OUT_DIR");
+
+#[tokio::test]
+async fn http3_requests() {
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body("target"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: h3
+            listen_on: "127.0.0.1:9983"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+              protocol: h3
+            targets:
+              - {{id: target, url: "{target}"}}
+            response:
+              target_selector: target
+        "#,
+        target = target.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let ca = std::fs::read(format!("{CERTS_DIR}/ca.crt")).unwrap();
+
+    // HTTP/1.1 responses advertise HTTP/3 endpoint
+    let client = Client::builder()
+        .add_root_certificate(Certificate::from_pem(&ca).unwrap())
+        .build()
+        .unwrap();
+    let resp = client.get("https://localhost:9983/").send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.headers()["alt-svc"], "h3=\":9983\"; ma=86400");
+
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(rustls_pemfile::certs(&mut &ca[..]).map(|cert| cert.unwrap()));
+    let mut tls = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    tls.alpn_protocols = vec![b"h3".to_vec()];
+    let mut endpoint = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+    endpoint.set_default_client_config(ClientConfig::new(Arc::new(
+        QuicClientConfig::try_from(tls).unwrap(),
+    )));
+    let conn = endpoint
+        .connect("127.0.0.1:9983".parse().unwrap(), "localhost")
+        .unwrap()
+        .await
+        .unwrap();
+    let (mut driver, mut send_request) = h3::client::new(h3_quinn::Connection::new(conn))
+        .await
+        .unwrap();
+    tokio::spawn(async move { std::future::poll_fn(|cx| driver.poll_close(cx)).await });
+
+    let req = Request::post("https://localhost:9983/items")
+        .header("x-request", "h3")
+        .body(())
+        .unwrap();
+    let mut stream = send_request.send_request(req).await.unwrap();
+    stream.send_data(Bytes::from("payload")).await.unwrap();
+    stream.finish().await.unwrap();
+
+    let resp = stream.recv_response().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert!(!resp.headers().contains_key("alt-svc"));
+    let mut body = vec![];
+    while let Some(chunk) = stream.recv_data().await.unwrap() {
+        body.put(chunk);
+    }
+    assert_eq!(body, b"target");
+
+    // Request is passed to the target like one over HTTP/1.1
+    let received = target.received().pop().unwrap();
+    assert_eq!(received.method, "POST");
+    assert_eq!(received.headers["x-request"], "h3");
+    assert_eq!(received.body, "payload");
+}
@@ -0,0 +1,87 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn write_ahead_delivery() {
+    let primary = MockTarget::start().await.unwrap();
+    let sink = MockTarget::start().await.unwrap();
+    primary.respond_with(MockResponse::new(200));
+    sink.respond_with(MockResponse::new(200));
+
+    // Journal entry left by the crashed process, it's redelivered on start
+    let dir = std::env::temp_dir().join(format!("journaled-delivery-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    std::fs::write(
+        dir.join(format!("{:013}-crashed.wal", now.as_millis())),
+        format!(
+            "{{\"method\": \"POST\", \"uri\": \"{}/audit\", \"headers\": [[\"idempotency-key\", \"crashed\"]]}}\nevent",
+            sink.url()
+        ),
+    )
+    .unwrap();
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: audit
+            listen_on: "127.0.0.1:9954"
+            strategy: always_target_id
+            targets:
+              - {{id: primary, url: "{primary}"}}
+              - id: sink
+                url: "{sink}/audit"
+                retry_queue:
+                  path: "{dir}"
+                  delivery: write_ahead
+                  dedup_header: Idempotency-Key
+                  min_backoff: 500ms
+                  max_backoff: 1s
+            response:
+              target_selector: primary
+        "#,
+        primary = primary.url(),
+        sink = sink.url(),
+        dir = dir.display()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    sink.assert_received(1, Duration::from_secs(2)).await;
+    let received = sink.received().pop().unwrap();
+    assert_eq!(received.headers["idempotency-key"], "crashed");
+    assert_eq!(received.body, "event");
+
+    // Failed delivery is retried with the same dedup key
+    sink.reset();
+    sink.respond_with(MockResponse::new(503));
+    let client = Client::new();
+    let resp = client
+        .post(splitter.url("audit"))
+        .body("event")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    // The first retry is immediate, the next one is after backoff
+    sink.assert_received(2, Duration::from_secs(1)).await;
+    sink.respond_with(MockResponse::new(200));
+    sink.assert_received(3, Duration::from_secs(2)).await;
+    let received = sink.received();
+    let key = &received[0].headers["idempotency-key"];
+    assert_ne!(key, "crashed");
+    for attempt in &received {
+        assert_eq!(&attempt.headers["idempotency-key"], key);
+        assert_eq!(attempt.body, "event");
+    }
+
+    // Acknowledged requests aren't kept
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::{Duration, Instant};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/max-wait.yaml";
+const TEST_PORT: u16 = 3038;
+
+#[tokio::test]
+async fn max_wait() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "all targets respond in time",
+                port: 9926,
+                expected_max_duration: Some(Duration::from_millis(500)),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "slow target isn't awaited",
+                port: 9926,
+                include_timeout_target: true,
+                expected_max_duration: Some(Duration::from_secs(2)),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
+
+#[tokio::test]
+async fn slow_target_completes_in_background() {
+    let fast = MockTarget::start().await.unwrap();
+    let slow = MockTarget::start().await.unwrap();
+    slow.respond_with(
+        MockResponse::new(500)
+            .body("slow failure")
+            .delay(Duration::from_secs(1)),
+    );
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: background
+            listen_on: "127.0.0.1:9987"
+            strategy: always_target_id
+            targets:
+              - id: FAST
+                url: "{fast}"
+              - id: SLOW
+                url: "{slow}"
+            response:
+              target_selection: least_errors
+              max_wait: 300ms
+              override:
+                headers:
+                  - add: x-target-id
+                    value: ${{CTX_TARGET_ID}}
+        "#,
+        fast = fast.url(),
+        slow = slow.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Both targets have no failures yet, so the first one is selected and the client doesn't wait for the slow one
+    let started = Instant::now();
+    let resp = client.get(splitter.url("background")).send().await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(1));
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-target-id"], "FAST");
+    slow.assert_received(1, Duration::from_secs(1)).await;
+
+    // Failure of the slow target is recorded once it completes after the response,
+    // otherwise the tie would be resolved in turn in favor of the slow target
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let resp = client.get(splitter.url("background")).send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-target-id"], "FAST");
+}
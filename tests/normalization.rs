@@ -0,0 +1,64 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn normalized_routing() {
+    let api = MockTarget::start().await.unwrap();
+    let fallback = MockTarget::start().await.unwrap();
+    api.respond_with(MockResponse::new(200).body("api"));
+    fallback.respond_with(MockResponse::new(200).body("fallback"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: normalized
+            listen_on: "127.0.0.1:9950"
+            strategy: conditional_routing
+            normalization:
+              lowercase_host: true
+            targets:
+              - id: api
+                url: "{api}${{CTX_REQUEST_PATH}}"
+                condition: .request.uri.path == "/api/users/" and .request.headers.host == "127.0.0.1:9950"
+              - id: fallback
+                url: "{fallback}"
+                condition: default
+        "#,
+        api = api.url(),
+        fallback = fallback.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Raw path with duplicate slashes matches the same rule as the clean one
+    let resp = client
+        .get(format!(
+            "{}//api///users//?page=1",
+            splitter.url("normalized")
+        ))
+        .header("connection", "x-hop")
+        .header("x-hop", "yes")
+        .header("x-end-to-end", "yes")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "api");
+    api.assert_received(1, Duration::from_secs(1)).await;
+    let received = api.received().pop().unwrap();
+    assert_eq!(received.uri.path(), "/api/users/");
+    assert_eq!(received.headers.get("x-hop"), None);
+    assert_eq!(received.headers["x-end-to-end"], "yes");
+
+    let resp = client
+        .get(format!("{}/other", splitter.url("normalized")))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "fallback");
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/openapi.yaml";
+const TEST_PORT: u16 = 3026;
+
+#[tokio::test]
+async fn openapi() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "undefined path is rejected",
+                port: 9913,
+                expected_status: 400,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client
+            .post("http://localhost:9913/v1/pets")
+            .header("content-type", "application/json")
+            .body(r#"{"name": "Rex"}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200, "valid request is forwarded");
+
+        let resp = client
+            .post("http://localhost:9913/v1/pets")
+            .header("content-type", "application/json")
+            .body(r#"{"name": 1}"#)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 400, "invalid body is rejected");
+        let body: Value = serde_json::from_str(&resp.text().await.unwrap()).unwrap();
+        assert_eq!(
+            body["details"][0],
+            "body `$.name`: expected string, got integer"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/override-cases.yaml";
+const TEST_PORT: u16 = 3019;
+
+#[tokio::test]
+async fn override_cases() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "no failed responses, default override",
+                port: 9800,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-override", "default")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "connection error, 5xx case",
+                port: 9800,
+                include_wrong_port: true,
+                include_good_target: false,
+                expected_status: 503,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    ("x-override", "server-error"),
+                    ("content-type", "application/json"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "timeout, timeout case",
+                port: 9800,
+                include_timeout_target: true,
+                include_good_target: false,
+                expected_status: 504,
+                expected_x_target_id_header: None,
+                expected_headers: &[("x-override", "timeout")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
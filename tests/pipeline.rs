@@ -0,0 +1,71 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn headers_before_conditions() {
+    let gold = MockTarget::start().await.unwrap();
+    let regular = MockTarget::start().await.unwrap();
+    gold.respond_with(MockResponse::new(200).body("gold"));
+    regular.respond_with(MockResponse::new(200).body("regular"));
+
+    let listener = |id: &str, port: u16, pipeline: &str| {
+        format!(
+            r#"
+          - id: {id}
+            listen_on: "127.0.0.1:{port}"
+            strategy: conditional_routing
+            {pipeline}
+            headers:
+              - add: x-tier
+                value: gold
+            targets:
+              - id: gold
+                url: "{gold}"
+                condition: .request.headers["x-tier"] == "gold"
+              - id: regular
+                url: "{regular}"
+                condition: default
+            "#,
+            gold = gold.url(),
+            regular = regular.url()
+        )
+    };
+    let config = format!(
+        "listeners:{}{}",
+        listener("default-order", 9967, ""),
+        listener(
+            "headers-first",
+            9968,
+            "pipeline: [normalization, headers, plugins, conditions]"
+        )
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let get = |listener: &str| client.get(splitter.url(listener)).send();
+
+    // Conditions see request without listener's headers by default
+    let resp = get("default-order").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "regular");
+    let received = regular.received().pop().unwrap();
+    assert_eq!(received.headers["x-tier"], "gold");
+
+    // Injected header is visible to conditions and forwarded once
+    let resp = get("headers-first").await.unwrap();
+    assert_eq!(resp.text().await.unwrap(), "gold");
+    gold.assert_received(1, Duration::from_secs(1)).await;
+    let received = gold.received().pop().unwrap();
+    assert_eq!(
+        received
+            .headers
+            .get_all("x-tier")
+            .iter()
+            .collect::<Vec<_>>(),
+        ["gold"]
+    );
+}
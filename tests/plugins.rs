@@ -0,0 +1,41 @@
+#![cfg(feature = "wasm-plugins")]
+
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/plugins.yaml";
+const TEST_PORT: u16 = 3021;
+
+#[tokio::test]
+async fn plugins() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request and response headers",
+                port: 9901,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    ("x-plugin-request", "on-request"),
+                    ("x-plugin-response", "on-response"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client.get("http://localhost:9902/").send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN, "rejected by plugin");
+        assert_eq!(resp.text().await.unwrap(), "denied by plugin");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
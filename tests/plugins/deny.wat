@@ -0,0 +1,16 @@
+;; Test plugin: responds to all requests instead of forwarding them to targets
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "{\"status\":403,\"body\":\"denied by plugin\"}")
+  ;; Input buffers are allocated after the output, memory is never released since each call has own instance
+  (global $next (mut i32) (i32.const 64))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+      (then (drop (memory.grow (i32.add (i32.shr_u (local.get $len) (i32.const 16)) (i32.const 1))))))
+    (local.get $ptr))
+  ;; Output is at 0, 40 bytes
+  (func (export "on_request") (param $ptr i32) (param $len i32) (result i64)
+    (i64.const 40)))
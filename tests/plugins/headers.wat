@@ -0,0 +1,24 @@
+;; Test plugin: adds header to the original request and to the final response
+(module
+  (import "env" "log" (func $log (param i32 i32)))
+  (memory (export "memory") 1)
+  ;; Hooks' outputs
+  (data (i32.const 0) "{\"headers\":{\"x-plugin-request\":\"on-request\"}}")
+  (data (i32.const 64) "{\"headers\":{\"x-plugin-response\":\"on-response\"}}")
+  (data (i32.const 128) "request is processed by plugin")
+  ;; Input buffers are allocated after the outputs, memory is never released since each call has own instance
+  (global $next (mut i32) (i32.const 256))
+  (func (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $len)))
+    (if (i32.gt_u (global.get $next) (i32.mul (memory.size) (i32.const 65536)))
+      (then (drop (memory.grow (i32.add (i32.shr_u (local.get $len) (i32.const 16)) (i32.const 1))))))
+    (local.get $ptr))
+  ;; Output is at 0, 45 bytes
+  (func (export "on_request") (param $ptr i32) (param $len i32) (result i64)
+    (call $log (i32.const 128) (i32.const 30))
+    (i64.const 45))
+  ;; Output is at 64, 47 bytes
+  (func (export "on_response") (param $ptr i32) (param $len i32) (result i64)
+    (i64.const 274877906991)))
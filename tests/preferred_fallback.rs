@@ -0,0 +1,65 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn preferred_then_fallback() {
+    let local = MockTarget::start().await.unwrap();
+    let zone = MockTarget::start().await.unwrap();
+    let remote = MockTarget::start().await.unwrap();
+    local.respond_with(MockResponse::new(200).body("local"));
+    zone.respond_with(MockResponse::new(200).body("zone"));
+    remote.respond_with(MockResponse::new(200).body("remote"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: fallback
+            listen_on: "127.0.0.1:9953"
+            strategy: preferred_then_fallback
+            targets:
+              - {{id: remote, url: "{remote}"}}
+              - {{id: zone, url: "{zone}"}}
+              - {{id: local, url: "{local}"}}
+            response:
+              preferred_target_id: local
+              fallback_order: [zone, remote]
+        "#,
+        local = local.url(),
+        zone = zone.url(),
+        remote = remote.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let get = || async {
+        let resp = client.get(splitter.url("fallback")).send().await.unwrap();
+        (resp.status().as_u16(), resp.text().await.unwrap())
+    };
+
+    // Preferred target wins while it's healthy, regardless of the targets' order
+    assert_eq!(get().await, (200, "local".into()));
+
+    // Fallback targets are tried in order
+    local.respond_with(MockResponse::new(503).body("local"));
+    assert_eq!(get().await, (200, "zone".into()));
+    zone.respond_with(MockResponse::new(500).body("zone"));
+    assert_eq!(get().await, (200, "remote".into()));
+
+    // Slow preferred target is awaited even if fallback ones respond earlier
+    local.respond_with(
+        MockResponse::new(200)
+            .body("local")
+            .delay(Duration::from_millis(200)),
+    );
+    assert_eq!(get().await, (200, "local".into()));
+
+    // All targets are failed, so response of the preferred one is returned
+    local.respond_with(MockResponse::new(503).body("local"));
+    remote.respond_with(MockResponse::new(502).body("remote"));
+    assert_eq!(get().await, (503, "local".into()));
+}
@@ -0,0 +1,62 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::{Certificate, Client};
+
+const CERTS_DIR: &str = env!("OUT_DIR");
+
+#[tokio::test]
+async fn tls_and_plaintext_on_the_same_port() {
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body("target"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: migration
+            listen_on: "127.0.0.1:9955"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+              accept_plaintext: true
+            targets:
+              - {{id: target, url: "{target}"}}
+            response:
+              target_selector: target
+          - id: tls-only
+            listen_on: "127.0.0.1:9956"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+              reload_interval: 1m
+            targets:
+              - {{id: target, url: "{target}"}}
+            response:
+              target_selector: target
+        "#,
+        target = target.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let ca = std::fs::read(format!("{CERTS_DIR}/ca.crt")).unwrap();
+    let client = Client::builder()
+        .add_root_certificate(Certificate::from_pem(&ca).unwrap())
+        .build()
+        .unwrap();
+
+    // Both protocols are served on the same port
+    for url in ["https://localhost:9955/", "http://localhost:9955/"] {
+        let resp = client.get(url).send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 200, "{url}");
+        assert_eq!(resp.text().await.unwrap(), "target", "{url}");
+    }
+
+    // Plaintext isn't accepted unless it's enabled
+    let resp = client.get("https://localhost:9956/").send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert!(client.get("http://localhost:9956/").send().await.is_err());
+}
@@ -0,0 +1,69 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn proxy_headers_and_loop_detection() {
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: edge
+            listen_on: "127.0.0.1:9957"
+            strategy: failed_then_ok
+            proxy_headers:
+              user_agent: replace
+              pseudonym: edge
+            targets:
+              - {{id: target, url: "{target}"}}
+          - id: looped
+            listen_on: "127.0.0.1:9958"
+            strategy: failed_then_ok
+            proxy_headers: {{}}
+            targets:
+              - {{id: self, url: "http://127.0.0.1:9958"}}
+        "#,
+        target = target.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Splitter's identity is added to Via and replaces requester's User-Agent
+    let resp = client
+        .get(splitter.url("edge"))
+        .header("user-agent", "test-client/1.0")
+        .header("via", "1.1 upstream")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    target.assert_received(1, Duration::from_millis(100)).await;
+    let received = &target.received()[0];
+    assert_eq!(received.headers["via"], "1.1 upstream, 1.1 edge");
+    assert_eq!(
+        received.headers["user-agent"],
+        concat!("http-dragonfly/", env!("CARGO_PKG_VERSION"))
+    );
+
+    // Request which has passed the splitter already isn't forwarded
+    target.reset();
+    let resp = client
+        .get(splitter.url("edge"))
+        .header("via", "1.0 proxy, 1.1 Edge")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 508);
+    target.assert_received(0, Duration::from_millis(100)).await;
+
+    // Listener which forwards requests to itself breaks the loop on the second pass
+    let resp = client.get(splitter.url("looped")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 508);
+}
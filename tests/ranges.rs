@@ -0,0 +1,113 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn ranged_requests() {
+    let primary = MockTarget::start().await.unwrap();
+    primary.respond_with(
+        MockResponse::new(206)
+            .header("content-range", "bytes 2-4/10")
+            .body("234"),
+    );
+    let mirror = MockTarget::start().await.unwrap();
+    mirror.respond_with(MockResponse::new(200).body("0123456789"));
+    let failed = MockTarget::start().await.unwrap();
+    failed.respond_with(MockResponse::new(416).header("content-range", "bytes */10"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: full
+            listen_on: "127.0.0.1:9971"
+            strategy: always_target_id
+            ranges:
+              mirrors: full
+            targets:
+              - id: primary
+                url: "{primary}"
+              - id: mirror
+                url: "{mirror}"
+            response:
+              target_selector: primary
+          - id: skip
+            listen_on: "127.0.0.1:9972"
+            strategy: ok_then_failed
+            ranges:
+              primary: primary
+              mirrors: skip
+            targets:
+              - id: primary
+                url: "{primary}"
+              - id: mirror
+                url: "{mirror}"
+          - id: failed
+            listen_on: "127.0.0.1:9973"
+            strategy: ok_then_failed
+            ranges:
+              primary: failed
+            targets:
+              - id: failed
+                url: "{failed}"
+              - id: mirror
+                url: "{mirror}"
+        "#,
+        primary = primary.url(),
+        mirror = mirror.url(),
+        failed = failed.url()
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+
+    // Range is forwarded to the selected target only, mirror is requested for the full body
+    let resp = client
+        .get("http://127.0.0.1:9971/file")
+        .header("range", "bytes=2-4")
+        .header("if-range", "\"v1\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 206);
+    assert_eq!(resp.text().await.unwrap(), "234");
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+    let received = mirror.received().pop().unwrap();
+    assert!(!received.headers.contains_key("range"));
+    assert!(!received.headers.contains_key("if-range"));
+    let received = primary.received().pop().unwrap();
+    assert_eq!(received.headers["range"], "bytes=2-4");
+    assert_eq!(received.headers["if-range"], "\"v1\"");
+
+    // Mirror isn't requested with range, but it's requested without it
+    mirror.reset();
+    let resp = client
+        .get("http://127.0.0.1:9972/file")
+        .header("range", "bytes=2-4")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.text().await.unwrap(), "234");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(mirror.received().is_empty());
+    client
+        .get("http://127.0.0.1:9972/file")
+        .send()
+        .await
+        .unwrap();
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+
+    // Full body of the mirror isn't a response to the range, even if the primary one has failed
+    mirror.reset();
+    let resp = client
+        .get("http://127.0.0.1:9973/file")
+        .header("range", "bytes=20-30")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 416);
+    mirror.assert_received(1, Duration::from_secs(1)).await;
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use flate2::{write::GzEncoder, Compression};
+use reqwest::{Client, StatusCode};
+use std::io::Write;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/request-decompression.yaml";
+const TEST_PORT: u16 = 3035;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[tokio::test]
+async fn request_decompression() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "uncompressed request is routed to default target",
+                port: 9922,
+                expected_x_target_id_header: Some("DEFAULT"),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Condition sees decompressed body, echo server returns the original one
+        let compressed = gzip(br#"{"kind": "good"}"#);
+        let resp = client
+            .post("http://localhost:9922/")
+            .header("content-encoding", "gzip")
+            .body(compressed.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "routed by compressed body");
+        assert_eq!(resp.headers()["x-target-id"], "GOOD");
+        assert_eq!(resp.headers()["content-encoding"], "gzip");
+        assert_eq!(resp.bytes().await.unwrap(), compressed);
+
+        let resp = client
+            .post("http://localhost:9923/")
+            .header("content-encoding", "gzip")
+            .body(gzip(b"plain body"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "forwarded decompressed");
+        assert!(resp.headers().get("content-encoding").is_none());
+        assert_eq!(resp.text().await.unwrap(), "plain body");
+
+        let resp = client
+            .post("http://localhost:9923/")
+            .header("content-encoding", "gzip")
+            .body(gzip(&[b'x'; 33]))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.status(),
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "too large body"
+        );
+
+        let resp = client
+            .post("http://localhost:9923/")
+            .header("content-encoding", "gzip")
+            .body("not a gzip")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "broken body");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,66 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+
+const BODY: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+#[tokio::test]
+async fn oversized_responses() {
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body(BODY));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: fail
+            listen_on: "127.0.0.1:9959"
+            strategy: always_target_id
+            targets:
+              - {{id: target, url: "{target}", max_response_size: 10}}
+            response:
+              target_selector: target
+          - id: truncate
+            listen_on: "127.0.0.1:9960"
+            strategy: always_target_id
+            targets:
+              - {{id: target, url: "{target}", max_response_size: 10, on_oversized_response: truncate}}
+            response:
+              target_selector: target
+          - id: stream
+            listen_on: "127.0.0.1:9961"
+            strategy: always_target_id
+            targets:
+              - {{id: target, url: "{target}", max_response_size: 10, on_oversized_response: stream_through}}
+              - {{id: shadow, url: "{target}", max_response_size: 10, on_oversized_response: stream_through}}
+            response:
+              target_selector: target
+          - id: unlimited
+            listen_on: "127.0.0.1:9962"
+            strategy: always_target_id
+            targets:
+              - {{id: target, url: "{target}", max_response_size: 36}}
+            response:
+              target_selector: target
+        "#,
+        target = target.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let get = |listener: &'static str| {
+        let url = splitter.url(listener);
+        let client = client.clone();
+        async move {
+            let resp = client.get(url).send().await.unwrap();
+            (resp.status().as_u16(), resp.text().await.unwrap())
+        }
+    };
+
+    assert_eq!(get("fail").await, (502, String::new()));
+    assert_eq!(get("truncate").await, (200, BODY[..10].into()));
+    assert_eq!(get("stream").await, (200, BODY.into()));
+    assert_eq!(get("unlimited").await, (200, BODY.into()));
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use crate::common::{echo_server::echo_server, run_test_with_config};
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::{path::Path, time::Duration};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/retry-queue.yaml";
+const TEST_PORT: u16 = 3023;
+const QUEUED_TARGET_PORT: u16 = 3024;
+const QUEUE_PATH: &str = "/tmp/http-dragonfly-retry-queue-9911";
+
+fn queue_depth() -> usize {
+    std::fs::read_dir(QUEUE_PATH)
+        .unwrap()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "req"))
+        .count()
+}
+
+async fn wait_for_depth(depth: usize) {
+    while queue_depth() != depth {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+async fn retry_queue() {
+    init_logging();
+    if Path::new(QUEUE_PATH).exists() {
+        std::fs::remove_dir_all(QUEUE_PATH).unwrap();
+    }
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "failed request is queued",
+                port: 9911,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        wait_for_depth(1).await;
+
+        tokio::spawn(echo_server(QUEUED_TARGET_PORT));
+        wait_for_depth(0).await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
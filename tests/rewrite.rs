@@ -0,0 +1,45 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/rewrite.yaml";
+const TEST_PORT: u16 = 3042;
+
+#[tokio::test]
+async fn method_and_path_rewrite() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        let resp = client
+            .get("http://localhost:9931/v1/users/123?q=1")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "matched path");
+        assert_eq!(resp.headers()["x-echo-method"], "POST");
+        assert_eq!(resp.headers()["x-echo-uri"], "/ingest?src=users");
+
+        // Unmatched path leaves target's URL as is
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "unmatched path",
+                port: 9931,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    ("x-echo-method", "POST"),
+                    ("x-echo-uri", "/default?src=none"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
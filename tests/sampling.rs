@@ -0,0 +1,50 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/sampling.yaml";
+const TEST_PORT: u16 = 3039;
+
+#[tokio::test]
+async fn sampling() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "response of the primary target",
+                port: 9927,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let mut not_sampled = 0;
+        for _ in 0..20 {
+            let resp = client
+                .get("http://localhost:9927/")
+                .header("x-splitter-explain", "true")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), 200);
+            let explanation: Value =
+                serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+            not_sampled += explanation["targets"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter(|target| target["id"] == "MIRROR" && target["status"] == "not_sampled")
+                .count();
+        }
+        assert!(not_sampled > 0, "mirror target is always sampled");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/scripting.yaml";
+const TEST_PORT: u16 = 3022;
+
+#[tokio::test]
+async fn scripting() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "good target is selected by script",
+                port: 9910,
+                include_wrong_port: true,
+                expected_headers: &[("x-scripted", "scripting-9910")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "status is returned by script",
+                port: 9910,
+                include_wrong_port: true,
+                include_good_target: false,
+                expected_status: 503,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,66 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    context::{
+        secrets::{self, SecretError, SecretProvider},
+        ContextMap,
+    },
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+
+/// Provider which mimics external store of the credentials
+struct VaultProvider;
+
+impl SecretProvider for VaultProvider {
+    fn name(&self) -> &str {
+        "vault"
+    }
+
+    fn get_secrets(&self) -> Result<ContextMap, SecretError> {
+        Ok(ContextMap::from([
+            ("CTX_SECRET_API_TOKEN".into(), "vault-token".into()),
+            ("CTX_SECRET_TENANT".into(), "acme".into()),
+        ]))
+    }
+}
+
+#[tokio::test]
+async fn registered_provider_secrets() {
+    secrets::register(VaultProvider);
+
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body("ok"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: vault
+            listen_on: "127.0.0.1:9979"
+            strategy: always_target_id
+            targets:
+              - id: api
+                url: "{target}/${{CTX_SECRET_TENANT}}"
+                headers:
+                  - add: authorization
+                    value: Bearer ${{CTX_SECRET_API_TOKEN}}
+            response:
+              target_selector: api
+        "#,
+        target = target.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    let resp = Client::new()
+        .get(splitter.url("vault"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+
+    // Secrets are substituted into the config and request transformations
+    let received = target.received().pop().unwrap();
+    assert_eq!(received.uri, "/acme");
+    assert_eq!(received.headers["authorization"], "Bearer vault-token");
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::{Client, StatusCode};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/security-headers.yaml";
+const TEST_PORT: u16 = 3015;
+
+#[tokio::test]
+async fn security_headers() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "default security headers",
+                port: 9400,
+                expected_x_target_id_header: None,
+                expected_headers: &[
+                    (
+                        "strict-transport-security",
+                        "max-age=31536000; includeSubDomains",
+                    ),
+                    ("x-content-type-options", "nosniff"),
+                    ("x-frame-options", "DENY"),
+                    ("referrer-policy", "strict-origin-when-cross-origin"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Headers from the response config have precedence over the preset
+        let resp = client.get("http://localhost:9401/").send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK, "overridden security headers");
+        let headers = resp.headers();
+        assert!(!headers.contains_key("strict-transport-security"));
+        assert_eq!(headers["x-content-type-options"], "nosniff");
+        assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+        assert_eq!(headers["referrer-policy"], "no-referrer");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
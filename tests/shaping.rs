@@ -0,0 +1,51 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::{Duration, Instant};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/shaping.yaml";
+const TEST_PORT: u16 = 3008;
+
+#[tokio::test]
+async fn shaping() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        let started = Instant::now();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "added latency",
+                port: 8700,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(1000)),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        assert!(started.elapsed() >= Duration::from_millis(500));
+
+        // 5KB are sent and received back with 10KBps limit
+        let body = "x".repeat(5 * 1024);
+        let started = Instant::now();
+        let resp = client
+            .post("http://localhost:8701/")
+            .body(body.clone())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        assert_eq!(resp.text().await.unwrap(), body);
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(900) && elapsed < Duration::from_millis(2000),
+            "unexpected duration of the throttled request: {elapsed:?}"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
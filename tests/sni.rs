@@ -0,0 +1,85 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::{Certificate, Client};
+
+const CERTS_DIR: &str = env!("OUT_DIR");
+
+#[tokio::test]
+async fn listeners_share_socket_by_server_name() {
+    let targets = [
+        MockTarget::start().await.unwrap(),
+        MockTarget::start().await.unwrap(),
+        MockTarget::start().await.unwrap(),
+    ];
+    for (target, body) in targets.iter().zip(["exact", "wildcard", "default"]) {
+        target.respond_with(MockResponse::new(200).body(body));
+    }
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: exact
+            listen_on: "127.0.0.1:9982"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+              server_names: [test-server.com]
+            targets:
+              - {{id: target, url: "{exact}"}}
+            response:
+              target_selector: target
+          - id: wildcard
+            listen_on: "127.0.0.1:9982"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+              server_names: ["*.test-server.com"]
+            targets:
+              - {{id: target, url: "{wildcard}"}}
+            response:
+              target_selector: target
+          - id: default
+            listen_on: "127.0.0.1:9982"
+            strategy: always_target_id
+            server_tls:
+              cert: "{CERTS_DIR}/test-server.pem"
+              key: "{CERTS_DIR}/test-server.key"
+            targets:
+              - {{id: target, url: "{default}"}}
+            response:
+              target_selector: target
+        "#,
+        exact = targets[0].url(),
+        wildcard = targets[1].url(),
+        default = targets[2].url(),
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let ca = std::fs::read(format!("{CERTS_DIR}/ca.crt")).unwrap();
+    let socket = "127.0.0.1:9982".parse().unwrap();
+    let client = Client::builder()
+        .add_root_certificate(Certificate::from_pem(&ca).unwrap())
+        .resolve("test-server.com", socket)
+        .resolve("second.test-server.com", socket)
+        .build()
+        .unwrap();
+
+    // Each connection is served by the listener of its SNI host name, unknown name is served by the default one
+    for (url, expected) in [
+        ("https://test-server.com:9982/", "exact"),
+        ("https://second.test-server.com:9982/", "wildcard"),
+        ("https://localhost:9982/", "default"),
+    ] {
+        let resp = client.get(url).send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 200, "{url}");
+        assert_eq!(resp.text().await.unwrap(), expected, "{url}");
+    }
+    for target in &targets {
+        assert_eq!(target.received().len(), 1);
+    }
+}
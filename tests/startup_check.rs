@@ -0,0 +1,55 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use clap::Parser;
+use common::{init_logging, test_one_case, TestConfig};
+use http_dragonfly::{cli::CliConfig, context::RootOsEnvironment};
+use reqwest::Client;
+use tokio::net::TcpStream;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/startup-check.yaml";
+const FAIL_FAST_CONFIG_PATH: &str = "tests/configs/integration/startup-check-fail-fast.yaml";
+const TEST_PORT: u16 = 3032;
+
+#[tokio::test]
+async fn startup_check() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        test_one_case(
+            &Client::new(),
+            TestConfig {
+                description: "listener serves requests despite unreachable target",
+                port: 9920,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        // Echo server is running, so only the unreachable target is reported
+        let cli_config = CliConfig::try_parse_from([
+            "http-dragonfly",
+            "--config",
+            FAIL_FAST_CONFIG_PATH,
+            "--fail-fast-on-unreachable-targets",
+        ])
+        .unwrap();
+        let env_provider = RootOsEnvironment::new("^TEST_HTTP_ENV_[A-Z0-9]+$");
+        let error = http_dragonfly::run(cli_config, env_provider)
+            .await
+            .expect_err("startup is aborted");
+        let error = error.to_string();
+        assert!(
+            error.starts_with("startup check failed, unreachable targets: fail-fast-9942/DOWN: "),
+            "{error}"
+        );
+        assert!(!error.contains("GOOD"), "{error}");
+        assert!(
+            TcpStream::connect("localhost:9942").await.is_err(),
+            "listener doesn't accept connections"
+        );
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
@@ -0,0 +1,66 @@
+use http_dragonfly::{
+    config::ConfigFormat,
+    testing::{MockResponse, MockTarget, TestSplitter},
+};
+use reqwest::Client;
+use std::time::Duration;
+
+#[tokio::test]
+async fn static_routes() {
+    let target = MockTarget::start().await.unwrap();
+    target.respond_with(MockResponse::new(200).body("target"));
+
+    let config = format!(
+        r#"
+        listeners:
+          - id: static
+            listen_on: "127.0.0.1:9963"
+            methods: [POST]
+            static_routes:
+              - path: /favicon.ico
+                status: 204
+              - path: /maintenance.html
+                file: tests/static/maintenance.html
+                status: 503
+              - path: /ready
+                methods: [GET]
+                headers:
+                  content-type: text/plain
+                body: ready
+            targets:
+              - {{id: target, url: "{target}"}}
+        "#,
+        target = target.url()
+    );
+    let splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+    let client = Client::new();
+    let url = |path: &str| format!("{}{path}", splitter.url("static"));
+
+    let resp = client.get(url("/favicon.ico")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 204);
+
+    let resp = client.get(url("/maintenance.html")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 503);
+    assert_eq!(resp.headers()["content-type"], "text/html; charset=utf-8");
+    assert!(resp.text().await.unwrap().contains("under maintenance"));
+
+    let resp = client.head(url("/maintenance.html")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 503);
+    assert_eq!(resp.text().await.unwrap(), "");
+
+    let resp = client.get(url("/ready?probe=1")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    assert_eq!(resp.text().await.unwrap(), "ready");
+
+    // Static routes don't reach targets
+    target.assert_received(0, Duration::from_millis(100)).await;
+
+    // Other methods of the static paths are handled as usual
+    let resp = client.head(url("/ready")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 405);
+    let resp = client.post(url("/ready")).send().await.unwrap();
+    assert_eq!(resp.status().as_u16(), 200);
+    target.assert_received(1, Duration::from_millis(100)).await;
+}
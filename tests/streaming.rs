@@ -0,0 +1,112 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use http_dragonfly::{config::ConfigFormat, testing::TestSplitter};
+use std::time::{Duration, Instant};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/streaming.yaml";
+const TEST_PORT: u16 = 3003;
+
+/// Returns received chunks with elapsed time, and the error which interrupted the body, if any
+async fn read_chunks(port: u16) -> (Vec<(String, Duration)>, Option<reqwest::Error>) {
+    let started = Instant::now();
+    let mut resp = loop {
+        match reqwest::get(format!("http://localhost:{port}/")).await {
+            Ok(resp) => break resp,
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    };
+    assert_eq!(resp.status().as_u16(), 200, "request to port {port}");
+
+    let mut chunks = vec![];
+    loop {
+        match resp.chunk().await {
+            Ok(Some(chunk)) => chunks.push((
+                String::from_utf8(chunk.to_vec()).unwrap(),
+                started.elapsed(),
+            )),
+            Ok(None) => return (chunks, None),
+            Err(e) => return (chunks, Some(e)),
+        }
+    }
+}
+
+#[tokio::test]
+async fn streaming_responses() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        // Whole stream is longer than target timeout, first chunk is passed without waiting for the rest
+        let (chunks, error) = read_chunks(8200).await;
+        assert!(error.is_none(), "unexpected error: {error:?}");
+        let body: String = chunks.iter().map(|(chunk, _)| chunk.as_str()).collect();
+        assert_eq!(body, "chunk-0\nchunk-1\nchunk-2\nchunk-3\nchunk-4\n");
+        assert!(chunks[0].1 < Duration::from_millis(1000));
+        assert!(chunks.last().unwrap().1 > Duration::from_millis(1000));
+
+        // Gap between chunks is longer than target timeout
+        let (chunks, error) = read_chunks(8201).await;
+        assert!(error.is_some());
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].0, "chunk-0\n");
+
+        // Buffered response is returned at once
+        let (chunks, error) = read_chunks(8202).await;
+        assert!(error.is_none(), "unexpected error: {error:?}");
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].1 > Duration::from_millis(1000));
+        test_one_case(
+            &reqwest::Client::new(),
+            TestConfig {
+                description: "buffered response",
+                port: 8202,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
+
+#[tokio::test]
+async fn interrupted_target_body() {
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_addr = target.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = target.accept().await {
+            let _ = stream.read(&mut [0; 1024]).await.unwrap();
+            // Connection is closed before the whole body is sent
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\npartial")
+                .await
+                .unwrap();
+        }
+    });
+
+    let config = format!(
+        r#"
+        listeners:
+          - listen_on: "127.0.0.1:9980"
+            strategy: always_target_id
+            targets:
+              - id: broken
+                url: "http://{target_addr}"
+            response:
+              target_selector: broken
+        "#
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    let resp = reqwest::get("http://127.0.0.1:9980/").await.unwrap();
+    assert_eq!(resp.status().as_u16(), 502);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/target-selection.yaml";
+const TEST_PORT: u16 = 3029;
+
+#[tokio::test]
+async fn target_selection() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+
+        for expected in ["FIRST", "SECOND", "FIRST", "SECOND"] {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "targets are selected in turn",
+                    port: 9917,
+                    expected_x_target_id_header: Some(expected),
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+
+        // Both targets have no errors yet, so the first one is selected
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "failed target is selected before its failure",
+                port: 9918,
+                expected_status: 502,
+                expected_x_target_id_header: Some("BAD"),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+        for _ in 0..3 {
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "target with the least errors is selected",
+                    port: 9918,
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        }
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
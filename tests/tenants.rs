@@ -0,0 +1,77 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use serde_json::Value;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/tenants.yaml";
+const TEST_PORT: u16 = 3037;
+
+/// Returns ID of the responded target and outcomes of all targets from the explanation
+async fn tenant_request(client: &Client, tenant: &str) -> (String, Vec<(String, String)>) {
+    let resp = client
+        .get("http://localhost:9925/")
+        .header("x-tenant-id", tenant)
+        .header("x-splitter-explain", "true")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let explanation: Value =
+        serde_json::from_slice(resp.headers()["x-splitter-explain"].as_bytes()).unwrap();
+    let outcomes = explanation["targets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|target| {
+            (
+                target["id"].as_str().unwrap().to_string(),
+                target["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+
+    (
+        resp.headers()["x-target-id"].to_str().unwrap().to_string(),
+        outcomes,
+    )
+}
+
+#[tokio::test]
+async fn tenants() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "request without tenant",
+                port: 9925,
+                expected_x_target_id_header: Some("SHARED"),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let (target_id, outcomes) = tenant_request(&client, "acme").await;
+        assert_eq!(target_id, "ACME-2", "tenant's target selector");
+        for (id, status) in outcomes {
+            let expected = if id.starts_with("ACME") {
+                "200"
+            } else {
+                "other_tenant"
+            };
+            assert_eq!(status, expected, "outcome of target `{id}`");
+        }
+
+        let (target_id, _) = tenant_request(&client, "globex").await;
+        assert_eq!(target_id, "GLOBEX", "single target group");
+        let (target_id, _) = tenant_request(&client, "unknown").await;
+        assert_eq!(target_id, "SHARED", "default group");
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
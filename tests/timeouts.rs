@@ -0,0 +1,72 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use reqwest::Client;
+use std::time::Duration;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/timeouts.yaml";
+const TEST_PORT: u16 = 3009;
+
+#[tokio::test]
+async fn timeouts() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "first byte timeout",
+                port: 8800,
+                expected_status: 521,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(1500)),
+                expected_headers: &[
+                    ("x-splitter-target-good-status", "timeout"),
+                    ("retry-after", "2"),
+                ],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        let resp = client.get("http://localhost:8800/").send().await.unwrap();
+        assert_eq!(resp.status(), 521);
+        assert_eq!(
+            resp.text().await.unwrap(),
+            r#"{"error": "timeout", "target": "GOOD"}"#
+        );
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "read timeout",
+                port: 8801,
+                expected_status: 522,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(500)),
+                expected_headers: &[("x-splitter-target-good-status", "read_timeout")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "total timeout",
+                port: 8802,
+                expected_status: 523,
+                expected_x_target_id_header: None,
+                expected_max_duration: Some(Duration::from_millis(700)),
+                expected_headers: &[("x-splitter-target-good-status", "total_timeout")],
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
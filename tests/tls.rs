@@ -42,6 +42,11 @@ fn prepare_test_cases() -> Vec<TestConfig> {
             port: 9005,
             ..TestConfig::default()
         },
+        TestConfig {
+            description: "valid target CA directory with absent listener cert",
+            port: 9006,
+            ..TestConfig::default()
+        },
     ]
 }
 
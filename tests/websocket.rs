@@ -0,0 +1,124 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use http_dragonfly::{config::ConfigFormat, testing::TestSplitter};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/websocket.yaml";
+const TEST_PORT: u16 = 3002;
+
+const HANDSHAKE_REQUEST: &str = "GET / HTTP/1.1\r\n\
+    Host: localhost\r\n\
+    Connection: Upgrade\r\n\
+    Upgrade: websocket\r\n\
+    Sec-WebSocket-Version: 13\r\n\
+    Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+
+/// Sends handshake request and returns connection with response head
+async fn websocket_handshake(port: u16) -> (TcpStream, String) {
+    let mut stream = loop {
+        match TcpStream::connect(("localhost", port)).await {
+            Ok(stream) => break stream,
+            Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+        }
+    };
+    stream
+        .write_all(HANDSHAKE_REQUEST.as_bytes())
+        .await
+        .unwrap();
+
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8; 1];
+        if stream.read(&mut byte).await.unwrap() == 0 {
+            break;
+        }
+        head.push(byte[0]);
+    }
+
+    (stream, String::from_utf8(head).unwrap().to_lowercase())
+}
+
+#[tokio::test]
+async fn websocket_sessions() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        let (mut stream, head) = websocket_handshake(8100).await;
+        assert!(
+            head.starts_with("http/1.1 101"),
+            "unexpected response: {head}"
+        );
+        assert!(head.contains("sec-websocket-key: dghlihnhbxbszsbub25jzq=="));
+
+        for message in ["hello", "world"] {
+            stream.write_all(message.as_bytes()).await.unwrap();
+            let mut buf = vec![0u8; message.len()];
+            stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, message.as_bytes());
+        }
+
+        let (_, head) = websocket_handshake(8101).await;
+        assert!(
+            head.starts_with("http/1.1 502"),
+            "unexpected response: {head}"
+        );
+
+        // Regular requests are handled as usual
+        test_one_case(
+            &reqwest::Client::new(),
+            TestConfig {
+                description: "websocket listener without upgrade",
+                port: 8100,
+                expected_x_target_id_header: Some("PRIMARY"),
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}
+
+#[tokio::test]
+async fn rejected_handshake_with_broken_body() {
+    let target = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let target_addr = target.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = target.accept().await {
+            let _ = stream.read(&mut [0; 1024]).await.unwrap();
+            // Handshake is rejected and connection is closed before the whole body is sent
+            stream
+                .write_all(b"HTTP/1.1 403 Forbidden\r\ncontent-length: 100\r\n\r\ndenied")
+                .await
+                .unwrap();
+        }
+    });
+
+    let config = format!(
+        r#"
+        listeners:
+          - listen_on: "127.0.0.1:9981"
+            targets:
+              - id: primary
+                url: "http://{target_addr}"
+            websocket:
+              primary: primary
+        "#
+    );
+    let _splitter = TestSplitter::from_content(&config, ConfigFormat::Yaml)
+        .await
+        .unwrap();
+
+    let (_, head) = websocket_handshake(9981).await;
+    assert!(
+        head.starts_with("http/1.1 502"),
+        "unexpected response: {head}"
+    );
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use crate::common::run_test_with_config;
+use common::{init_logging, test_one_case, TestConfig};
+use futures_util::future::join_all;
+use reqwest::Client;
+
+const TEST_CONFIG_PATH: &str = "tests/configs/integration/workers.yaml";
+const TEST_PORT: u16 = 3011;
+
+#[tokio::test]
+async fn workers() {
+    init_logging();
+
+    let result = run_test_with_config(TEST_CONFIG_PATH, TEST_PORT, 30, false, async {
+        // Each request uses its own connection, so they're spread across all workers
+        let requests = (0..32).map(|_| async {
+            let client = Client::builder().pool_max_idle_per_host(0).build().unwrap();
+            test_one_case(
+                &client,
+                TestConfig {
+                    description: "several workers",
+                    port: 9000,
+                    expected_x_target_id_header: None,
+                    ..TestConfig::default()
+                },
+            )
+            .await;
+        });
+        join_all(requests).await;
+
+        let client = Client::new();
+        test_one_case(
+            &client,
+            TestConfig {
+                description: "dedicated runtime",
+                port: 9001,
+                expected_x_target_id_header: None,
+                ..TestConfig::default()
+            },
+        )
+        .await;
+    })
+    .await;
+
+    assert_eq!(result, Ok(()))
+}